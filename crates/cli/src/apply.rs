@@ -0,0 +1,169 @@
+use crate::utils::ErrorContext as EC;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+use std::ops::Range;
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ApplyArg {
+  /// The JSON report generated by `run --json` or `scan --json` with rewrites.
+  ///
+  /// All JSON styles are accepted. Matches without replacement are ignored.
+  /// A fix is skipped if the matched code has changed since the report was generated.
+  #[clap(long, value_name = "REPORT")]
+  from: PathBuf,
+}
+
+/// A match in the JSON report. Only fields needed to apply the fix are read.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportEntry {
+  file: PathBuf,
+  text: String,
+  range: ReportRange,
+  replacement: Option<String>,
+  replacement_offsets: Option<Range<usize>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReportRange {
+  byte_offset: Range<usize>,
+}
+
+/// A recorded fix to apply, with the matched text to check the file is not stale.
+struct Fix {
+  matched: Range<usize>,
+  text: String,
+  replaced: Range<usize>,
+  replacement: String,
+}
+
+/// Apply fixes recorded in a JSON report to the working tree.
+pub fn run_apply(arg: ApplyArg) -> Result<()> {
+  let report = read_to_string(&arg.from).with_context(|| EC::ReadReport(arg.from.clone()))?;
+  let entries = parse_report(&report).with_context(|| EC::ParseReport(arg.from.clone()))?;
+  let mut fixes: BTreeMap<PathBuf, Vec<Fix>> = BTreeMap::new();
+  for entry in entries {
+    let (Some(replacement), Some(replaced)) = (entry.replacement, entry.replacement_offsets) else {
+      continue;
+    };
+    fixes.entry(entry.file).or_default().push(Fix {
+      matched: entry.range.byte_offset,
+      text: entry.text,
+      replaced,
+      replacement,
+    });
+  }
+  let (mut applied, mut skipped, mut file_count) = (0, 0, 0);
+  for (path, fixes) in fixes {
+    let total = fixes.len();
+    let Ok(old_content) = read_to_string(&path) else {
+      eprintln!(
+        "Warning: skipped {total} fix(es) in {}, file cannot be read.",
+        path.display()
+      );
+      skipped += total;
+      continue;
+    };
+    let (new_content, count) = apply_fixes(&old_content, fixes);
+    if count < total {
+      let stale = total - count;
+      eprintln!(
+        "Warning: skipped {stale} stale fix(es) in {}.",
+        path.display()
+      );
+      skipped += stale;
+    }
+    if count > 0 {
+      write(&path, new_content).with_context(|| EC::WriteFile(path.clone()))?;
+      applied += count;
+      file_count += 1;
+    }
+  }
+  println!("Applied {applied} fix(es) to {file_count} file(s), skipped {skipped}.");
+  Ok(())
+}
+
+/// Parse a report printed in any JSON style: a pretty or compact array, or one object per line.
+fn parse_report(report: &str) -> Result<Vec<ReportEntry>> {
+  if report.trim_start().starts_with('[') {
+    return Ok(serde_json::from_str(report)?);
+  }
+  let stream = serde_json::Deserializer::from_str(report).into_iter::<ReportEntry>();
+  Ok(stream.collect::<Result<_, _>>()?)
+}
+
+/// Returns the fixed content and the number of applied fixes.
+/// A fix is skipped if its matched text differs from the file, or it overlaps an earlier fix.
+fn apply_fixes(old_content: &str, mut fixes: Vec<Fix>) -> (String, usize) {
+  fixes.sort_by_key(|f| (f.replaced.start, f.replaced.end));
+  let mut new_content = String::new();
+  let mut start = 0;
+  let mut count = 0;
+  for fix in fixes {
+    let is_fresh = old_content.get(fix.matched.clone()) == Some(fix.text.as_str());
+    let is_valid = fix.replaced.start >= start && old_content.get(fix.replaced.clone()).is_some();
+    if !is_fresh || !is_valid {
+      continue;
+    }
+    new_content.push_str(&old_content[start..fix.replaced.start]);
+    new_content.push_str(&fix.replacement);
+    start = fix.replaced.end;
+    count += 1;
+  }
+  new_content.push_str(&old_content[start..]);
+  (new_content, count)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn fix(old: &str, text: &str, replacement: &str) -> Fix {
+    let start = old.find(text).expect("should find text");
+    let range = start..start + text.len();
+    Fix {
+      matched: range.clone(),
+      text: text.into(),
+      replaced: range,
+      replacement: replacement.into(),
+    }
+  }
+
+  #[test]
+  fn test_apply_fixes() {
+    let old = "let a = 123; let b = 456;";
+    let fixes = vec![fix(old, "456", "4"), fix(old, "123", "1")];
+    assert_eq!(apply_fixes(old, fixes), ("let a = 1; let b = 4;".into(), 2));
+    let mut stale = fix(old, "123", "1");
+    stale.text = "789".into();
+    let fixes = vec![stale, fix(old, "456", "4")];
+    assert_eq!(
+      apply_fixes(old, fixes),
+      ("let a = 123; let b = 4;".into(), 1)
+    );
+    let fixes = vec![fix(old, "a = 123", "a"), fix(old, "123", "1")];
+    assert_eq!(apply_fixes(old, fixes), ("let a; let b = 456;".into(), 1));
+  }
+
+  #[test]
+  fn test_parse_report() -> Result<()> {
+    let entry = r#"{"file":"a.ts","text":"1","range":{"byteOffset":{"start":0,"end":1}},"replacement":"2","replacementOffsets":{"start":0,"end":1}}"#;
+    let no_fix = r#"{"file":"b.ts","text":"1","range":{"byteOffset":{"start":0,"end":1}}}"#;
+    let array = parse_report(&format!("[{entry},{no_fix}]"))?;
+    assert_eq!(array.len(), 2);
+    assert_eq!(array[0].replacement.as_deref(), Some("2"));
+    assert!(array[1].replacement.is_none());
+    let stream = parse_report(&format!("{entry}\n{no_fix}\n"))?;
+    assert_eq!(stream.len(), 2);
+    assert!(parse_report("").expect("should parse").is_empty());
+    assert!(parse_report("{").is_err());
+    Ok(())
+  }
+}
@@ -1,13 +1,20 @@
 use crate::lang::{CustomLang, LanguageGlobs, SerializableInjection, SgLang};
-use crate::utils::{ErrorContext as EC, RuleOverwrite, RuleTrace};
+use crate::utils::{
+  parse_file_size, register_project_ignores, ErrorContext as EC, FileLimits, RuleOverwrite,
+  RuleTrace,
+};
 
 use anyhow::{Context, Result};
 use ast_grep_config::{
-  from_str, from_yaml_string, DeserializeEnv, GlobalRules, RuleCollection, RuleConfig,
+  from_str, from_yaml_string, from_yaml_string_with_rewriters, DeserializeEnv, GlobalRules,
+  RuleCollection, RuleConfig, SerializableGlobalRule, SerializableRewriter, Severity,
+  SharedRewriters,
 };
+use ast_grep_core::MatchStrictness;
 use ast_grep_language::config_file_type;
 use ignore::WalkBuilder;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml::with::singleton_map_recursive;
 
 use std::collections::HashMap;
 use std::fs::read_to_string;
@@ -35,6 +42,7 @@ impl From<PathBuf> for TestConfig {
 #[serde(rename_all = "camelCase")]
 pub struct AstGrepConfig {
   /// YAML rule directories
+  #[serde(default)]
   pub rule_dirs: Vec<PathBuf>,
   /// test configurations
   #[serde(skip_serializing_if = "Option::is_none")]
@@ -42,6 +50,9 @@ pub struct AstGrepConfig {
   /// util rules directories
   #[serde(skip_serializing_if = "Option::is_none")]
   pub util_dirs: Option<Vec<PathBuf>>,
+  /// shared rewriter directories, rewriters in them can be used by any rule
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub rewriter_dirs: Option<Vec<PathBuf>>,
   /// configuration for custom languages
   #[serde(skip_serializing_if = "Option::is_none")]
   pub custom_languages: Option<HashMap<String, CustomLang>>,
@@ -51,6 +62,44 @@ pub struct AstGrepConfig {
   /// injection config for embedded languages
   #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub language_injections: Vec<SerializableInjection>,
+  /// other config files or names of rule packages installed by `sg install` to include
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub extends: Vec<String>,
+  /// false for a nested config in a subdirectory, which applies only to files under it
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub root: Option<bool>,
+  /// severity overrides of rules from outer configs, used in nested configs
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub severities: HashMap<String, Severity>,
+  /// skip files larger than this size, in bytes or with K/M/G suffix like `10M`
+  #[serde(
+    default,
+    deserialize_with = "deserialize_file_size",
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub max_file_size: Option<usize>,
+  /// skip files with more lines than this count
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub max_line_count: Option<usize>,
+  /// gitignore-style patterns of files to skip, relative to the project directory
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub ignores: Vec<String>,
+}
+
+fn deserialize_file_size<'de, D: Deserializer<'de>>(de: D) -> Result<Option<usize>, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum FileSize {
+    Bytes(usize),
+    Text(String),
+  }
+  match Option::<FileSize>::deserialize(de)? {
+    Some(FileSize::Bytes(size)) => Ok(Some(size)),
+    Some(FileSize::Text(text)) => parse_file_size(&text)
+      .map(Some)
+      .map_err(serde::de::Error::custom),
+    None => Ok(None),
+  }
 }
 
 #[derive(Clone)]
@@ -62,6 +111,10 @@ pub struct ProjectConfig {
   pub test_configs: Option<Vec<TestConfig>>,
   /// util rules directories
   pub util_dirs: Option<Vec<PathBuf>>,
+  /// shared rewriter directories
+  pub rewriter_dirs: Option<Vec<PathBuf>>,
+  /// limits to skip large files
+  pub file_limits: FileLimits,
 }
 
 impl ProjectConfig {
@@ -72,8 +125,7 @@ impl ProjectConfig {
     let Some(config_path) = config_path else {
       return Ok(None);
     };
-    let config_str = read_to_string(&config_path).context(EC::ReadConfiguration)?;
-    let sg_config: AstGrepConfig = from_str(&config_str).context(EC::ParseConfiguration)?;
+    let sg_config = read_config(&config_path, &mut vec![])?;
     let project_dir = config_path
       .parent()
       .expect("config file must have parent directory")
@@ -85,8 +137,9 @@ impl ProjectConfig {
     &self,
     rule_overwrite: RuleOverwrite,
   ) -> Result<(RuleCollection<SgLang>, RuleTrace)> {
-    let global_rules = find_util_rules(self)?;
-    read_directory_yaml(self, global_rules, rule_overwrite)
+    let global_rules = find_util_rules(self, rule_overwrite.strictness())?;
+    let rewriters = find_shared_rewriters(self)?;
+    read_directory_yaml(self, global_rules, &rewriters, rule_overwrite)
   }
   /// returns a Result of Result.
   /// The inner Result is for configuration not found, or ProjectNotExist
@@ -100,6 +153,12 @@ impl ProjectConfig {
       rule_dirs: sg_config.rule_dirs.drain(..).collect(),
       test_configs: sg_config.test_configs.take(),
       util_dirs: sg_config.util_dirs.take(),
+      rewriter_dirs: sg_config.rewriter_dirs.take(),
+      file_limits: FileLimits {
+        max_size: sg_config.max_file_size,
+        max_lines: sg_config.max_line_count,
+        ..Default::default()
+      },
     };
     // sg_config will not use rule dirs and test configs anymore
     register_custom_language(&config.project_dir, sg_config)?;
@@ -107,6 +166,85 @@ impl ProjectConfig {
   }
 }
 
+impl AstGrepConfig {
+  fn is_nested(&self) -> bool {
+    self.root == Some(false)
+  }
+
+  /// Merge a config listed in `extends`, whose paths are relative to `base_dir`.
+  /// Rule dirs, util dirs, rewriter dirs and injections are appended, globs are combined per language,
+  /// and custom languages defined in this config take precedence over the base's.
+  /// Test configs and ignores are not inherited.
+  fn merge_base(&mut self, base: AstGrepConfig, base_dir: &Path) {
+    let rebase = |dirs: Vec<PathBuf>| dirs.into_iter().map(|d| base_dir.join(d));
+    self.rule_dirs.extend(rebase(base.rule_dirs));
+    if let Some(util_dirs) = base.util_dirs {
+      let own = self.util_dirs.get_or_insert_with(Vec::new);
+      own.extend(rebase(util_dirs));
+    }
+    if let Some(rewriter_dirs) = base.rewriter_dirs {
+      let own = self.rewriter_dirs.get_or_insert_with(Vec::new);
+      own.extend(rebase(rewriter_dirs));
+    }
+    if let Some(langs) = base.custom_languages {
+      let own = self.custom_languages.get_or_insert_with(HashMap::new);
+      for (name, mut lang) in langs {
+        lang.library_path = base_dir.join(&lang.library_path);
+        own.entry(name).or_insert(lang);
+      }
+    }
+    if let Some(globs) = base.language_globs {
+      let own = self.language_globs.get_or_insert_with(HashMap::new);
+      for (lang, globs) in globs {
+        let own_globs = own.entry(lang).or_default();
+        for glob in globs {
+          if !own_globs.contains(&glob) {
+            own_globs.push(glob);
+          }
+        }
+      }
+    }
+    self.language_injections.extend(base.language_injections);
+    self.max_file_size = self.max_file_size.or(base.max_file_size);
+    self.max_line_count = self.max_line_count.or(base.max_line_count);
+  }
+}
+
+/// Read the config file and merge all configs it extends, recursively.
+/// Paths in the returned config are relative to the directory of `config_path`.
+fn read_config(config_path: &Path, extending: &mut Vec<PathBuf>) -> Result<AstGrepConfig> {
+  let canonical = config_path
+    .canonicalize()
+    .unwrap_or_else(|_| config_path.to_path_buf());
+  if extending.contains(&canonical) {
+    return Err(anyhow::anyhow!(EC::CyclicExtends(canonical)));
+  }
+  let config_str = read_to_string(config_path).context(EC::ReadConfiguration)?;
+  let mut sg_config: AstGrepConfig = from_str(&config_str).context(EC::ParseConfiguration)?;
+  let dir = config_path.parent().unwrap_or(Path::new(""));
+  extending.push(canonical);
+  for entry in std::mem::take(&mut sg_config.extends) {
+    let base_path = extends_path(&entry);
+    if !dir.join(&base_path).is_file() {
+      return Err(anyhow::anyhow!(EC::ExtendsNotFound(entry)));
+    }
+    let base = read_config(&dir.join(&base_path), extending)?;
+    let base_dir = base_path.parent().unwrap_or(Path::new(""));
+    sg_config.merge_base(base, base_dir);
+  }
+  extending.pop();
+  Ok(sg_config)
+}
+
+/// An `extends` entry is either a path to a YAML config or the name of an installed package.
+fn extends_path(entry: &str) -> PathBuf {
+  if entry.ends_with(".yml") || entry.ends_with(".yaml") {
+    PathBuf::from(entry)
+  } else {
+    Path::new(PACKAGE_DIR).join(entry).join(CONFIG_FILE)
+  }
+}
+
 fn register_custom_language(project_dir: &Path, sg_config: AstGrepConfig) -> Result<()> {
   if let Some(custom_langs) = sg_config.custom_languages {
     SgLang::register_custom_language(project_dir, custom_langs)?;
@@ -115,6 +253,7 @@ fn register_custom_language(project_dir: &Path, sg_config: AstGrepConfig) -> Res
     SgLang::register_globs(globs)?;
   }
   SgLang::register_injections(sg_config.language_injections)?;
+  register_project_ignores(project_dir, &sg_config.ignores)?;
   Ok(())
 }
 
@@ -128,7 +267,10 @@ fn build_util_walker(base_dir: &Path, util_dirs: &Option<Vec<PathBuf>>) -> Optio
   Some(walker)
 }
 
-fn find_util_rules(config: &ProjectConfig) -> Result<GlobalRules<SgLang>> {
+fn find_util_rules(
+  config: &ProjectConfig,
+  strictness: Option<&MatchStrictness>,
+) -> Result<GlobalRules<SgLang>> {
   let ProjectConfig {
     project_dir,
     util_dirs,
@@ -150,28 +292,108 @@ fn find_util_rules(config: &ProjectConfig) -> Result<GlobalRules<SgLang>> {
       continue;
     }
     let path = config_file.path();
-    let file = read_to_string(path)?;
-    let new_configs = from_str(&file)?;
-    utils.push(new_configs);
+    let file = read_to_string(path).with_context(|| EC::ReadRule(path.to_path_buf()))?;
+    // a util file can define multiple utils separated by `---`
+    for yaml in serde_yaml::Deserializer::from_str(&file) {
+      let mut util: SerializableGlobalRule<SgLang> = singleton_map_recursive::deserialize(yaml)
+        .with_context(|| EC::ParseRule(path.to_path_buf()))?;
+      if let Some(strictness) = strictness {
+        util.core.set_strictness(strictness.clone());
+      }
+      utils.push(util);
+    }
   }
 
   let ret = DeserializeEnv::parse_global_utils(utils).context(EC::InvalidGlobalUtils)?;
   Ok(ret)
 }
 
+/// Read rewriters in rewriter dirs, a file can define multiple rewriters separated by `---`.
+fn find_shared_rewriters(config: &ProjectConfig) -> Result<SharedRewriters> {
+  let ProjectConfig {
+    project_dir,
+    rewriter_dirs,
+    ..
+  } = config;
+  let Some(mut walker) = build_util_walker(project_dir, rewriter_dirs) else {
+    return Ok(SharedRewriters::default());
+  };
+  let mut rewriters = vec![];
+  let walker = walker.types(config_file_type()).build();
+  for dir in walker {
+    let config_file = dir.with_context(|| EC::WalkRuleDir(PathBuf::new()))?;
+    // file_type is None only if it is stdin, safe to panic here
+    if !config_file
+      .file_type()
+      .expect("file type should be available for non-stdin")
+      .is_file()
+    {
+      continue;
+    }
+    let path = config_file.path();
+    let file = read_to_string(path).with_context(|| EC::ReadRule(path.to_path_buf()))?;
+    for yaml in serde_yaml::Deserializer::from_str(&file) {
+      let rewriter: SerializableRewriter = singleton_map_recursive::deserialize(yaml)
+        .with_context(|| EC::ParseRule(path.to_path_buf()))?;
+      rewriters.push(rewriter);
+    }
+  }
+  let ret = SharedRewriters::try_new(rewriters).context(EC::InvalidSharedRewriters)?;
+  Ok(ret)
+}
+
 fn read_directory_yaml(
   config: &ProjectConfig,
   global_rules: GlobalRules<SgLang>,
+  rewriters: &SharedRewriters,
   rule_overwrite: RuleOverwrite,
 ) -> Result<(RuleCollection<SgLang>, RuleTrace)> {
-  let mut configs = vec![];
   let ProjectConfig {
     project_dir,
     rule_dirs,
     ..
   } = config;
+  let configs = read_rule_dirs(project_dir, rule_dirs, &global_rules, rewriters)?;
+  let mut total_rule_count = configs.len();
+
+  let configs = rule_overwrite.process_configs(configs)?;
+  let configs = rule_overwrite.override_strictness(configs, &global_rules)?;
+  let mut collection = RuleCollection::try_new(configs).context(EC::GlobPattern)?;
+  let nested_configs = find_nested_configs(project_dir)?;
+  for nested in &nested_configs {
+    // scanned paths can be relative or absolute, the collection compares them canonically
+    let dir = project_dir
+      .join(&nested.dir)
+      .canonicalize()
+      .context(EC::ReadConfiguration)?;
+    let configs = read_nested_rules(config, nested, &nested_configs, &global_rules, rewriters)?;
+    total_rule_count += configs.len();
+    let configs = rule_overwrite.overwrite_configs(configs);
+    let configs = rule_overwrite.override_strictness(configs, &global_rules)?;
+    collection
+      .add_scope(dir, configs)
+      .context(EC::GlobPattern)?;
+  }
+  let effective_rule_count = collection.total_rule_count();
+  let trace = RuleTrace {
+    file_trace: Default::default(),
+    effective_rule_count,
+    skipped_rule_count: total_rule_count - effective_rule_count,
+    rule_timings: Default::default(),
+    profile_rules: false,
+  };
+  Ok((collection, trace))
+}
+
+fn read_rule_dirs(
+  base_dir: &Path,
+  rule_dirs: &[PathBuf],
+  global_rules: &GlobalRules<SgLang>,
+  rewriters: &SharedRewriters,
+) -> Result<Vec<RuleConfig<SgLang>>> {
+  let mut configs = vec![];
   for dir in rule_dirs {
-    let dir_path = project_dir.join(dir);
+    let dir_path = base_dir.join(dir);
     let walker = WalkBuilder::new(&dir_path)
       .types(config_file_type())
       .build();
@@ -186,21 +408,99 @@ fn read_directory_yaml(
         continue;
       }
       let path = config_file.path();
-      let new_configs = read_rule_file(path, Some(&global_rules))?;
+      let new_configs = read_rule_file_with_rewriters(path, global_rules, rewriters)?;
       configs.extend(new_configs);
     }
   }
-  let total_rule_count = configs.len();
+  Ok(configs)
+}
 
-  let configs = rule_overwrite.process_configs(configs)?;
-  let collection = RuleCollection::try_new(configs).context(EC::GlobPattern)?;
-  let effective_rule_count = collection.total_rule_count();
-  let trace = RuleTrace {
-    file_trace: Default::default(),
-    effective_rule_count,
-    skipped_rule_count: total_rule_count - effective_rule_count,
-  };
-  Ok((collection, trace))
+/// A config with `root: false` in a subdirectory of the project.
+/// Only its rule dirs and severities are used, utils and languages come from the project.
+struct NestedConfig {
+  /// directory of the config, relative to the project directory
+  dir: PathBuf,
+  config: AstGrepConfig,
+}
+
+/// Find nested configs in the project, sorted from the shallowest to the deepest.
+/// Only directories and config files are visited, other files are pruned from the walk.
+fn find_nested_configs(project_dir: &Path) -> Result<Vec<NestedConfig>> {
+  let mut nested_configs = vec![];
+  let walker = WalkBuilder::new(project_dir)
+    .filter_entry(|entry| {
+      let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+      is_dir || entry.file_name() == CONFIG_FILE
+    })
+    .build();
+  for entry in walker.flatten() {
+    // depth 1 is the project config itself
+    if entry.depth() <= 1 || entry.file_name() != CONFIG_FILE {
+      continue;
+    }
+    let config = read_config(entry.path(), &mut vec![])?;
+    if !config.is_nested() {
+      continue;
+    }
+    let dir = entry
+      .path()
+      .parent()
+      .and_then(|p| p.strip_prefix(project_dir).ok())
+      .expect("nested config must be under project dir")
+      .to_path_buf();
+    nested_configs.push(NestedConfig { dir, config });
+  }
+  nested_configs.sort_by_key(|n| n.dir.components().count());
+  Ok(nested_configs)
+}
+
+/// Read rules of the nested config, and rules of outer configs whose severities it overrides.
+fn read_nested_rules(
+  project: &ProjectConfig,
+  nested: &NestedConfig,
+  all_nested: &[NestedConfig],
+  global_rules: &GlobalRules<SgLang>,
+  rewriters: &SharedRewriters,
+) -> Result<Vec<RuleConfig<SgLang>>> {
+  let project_dir = &project.project_dir;
+  let nested_dir = project_dir.join(&nested.dir);
+  let mut configs = read_rule_dirs(
+    &nested_dir,
+    &nested.config.rule_dirs,
+    global_rules,
+    rewriters,
+  )?;
+  let severities = &nested.config.severities;
+  if severities.is_empty() {
+    return Ok(configs);
+  }
+  let mut outer_dirs: Vec<_> = project
+    .rule_dirs
+    .iter()
+    .map(|d| project_dir.join(d))
+    .collect();
+  let outer_nested = all_nested
+    .iter()
+    .filter(|o| o.dir != nested.dir && nested.dir.starts_with(&o.dir));
+  for outer in outer_nested {
+    let outer_dir = project_dir.join(&outer.dir);
+    outer_dirs.extend(outer.config.rule_dirs.iter().map(|d| outer_dir.join(d)));
+  }
+  let mut outer_rules = read_rule_dirs(Path::new(""), &outer_dirs, global_rules, rewriters)?;
+  // deeper configs come later and take precedence
+  outer_rules.reverse();
+  for rule in outer_rules {
+    let is_overridden = severities.contains_key(&rule.id);
+    if is_overridden && !configs.iter().any(|c| c.id == rule.id) {
+      configs.push(rule);
+    }
+  }
+  for rule in &mut configs {
+    if let Some(severity) = severities.get(&rule.id) {
+      rule.severity = severity.clone();
+    }
+  }
+  Ok(configs)
 }
 
 pub fn with_rule_stats(
@@ -213,6 +513,8 @@ pub fn with_rule_stats(
     file_trace: Default::default(),
     effective_rule_count,
     skipped_rule_count: total_rule_count - effective_rule_count,
+    rule_timings: Default::default(),
+    profile_rules: false,
   };
   Ok((collection, trace))
 }
@@ -230,7 +532,21 @@ pub fn read_rule_file(
   parsed.with_context(|| EC::ParseRule(path.to_path_buf()))
 }
 
+fn read_rule_file_with_rewriters(
+  path: &Path,
+  global_rules: &GlobalRules<SgLang>,
+  rewriters: &SharedRewriters,
+) -> Result<Vec<RuleConfig<SgLang>>> {
+  let yaml = read_to_string(path).with_context(|| EC::ReadRule(path.to_path_buf()))?;
+  from_yaml_string_with_rewriters(&yaml, global_rules, rewriters)
+    .with_context(|| EC::ParseRule(path.to_path_buf()))
+}
+
 const CONFIG_FILE: &str = "sgconfig.yml";
+/// where `sg install` puts rule packages, relative to the project directory
+pub const PACKAGE_DIR: &str = ".ast-grep/packages";
+/// where `sg lang install` puts custom language grammars, relative to the project directory
+pub const LANGUAGE_DIR: &str = ".ast-grep/languages";
 
 /// return None if config file does not exist
 fn find_config_path_with_default(config_path: Option<PathBuf>) -> Result<Option<PathBuf>> {
@@ -240,7 +556,7 @@ fn find_config_path_with_default(config_path: Option<PathBuf>) -> Result<Option<
   let mut path = std::env::current_dir()?;
   loop {
     let maybe_config = path.join(CONFIG_FILE);
-    if maybe_config.exists() {
+    if maybe_config.exists() && !is_nested_config(&maybe_config) {
       break Ok(Some(maybe_config));
     }
     if let Some(parent) = path.parent() {
@@ -250,3 +566,11 @@ fn find_config_path_with_default(config_path: Option<PathBuf>) -> Result<Option<
     }
   }
 }
+
+/// Nested configs are skipped when searching for the project config.
+fn is_nested_config(path: &Path) -> bool {
+  let Ok(content) = read_to_string(path) else {
+    return false;
+  };
+  from_str::<AstGrepConfig>(&content).is_ok_and(|c| c.is_nested())
+}
@@ -0,0 +1,47 @@
+use crate::lang::SgLang;
+use crate::print::ColorArg;
+use crate::utils::DebugFormat;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+pub struct DumpArg {
+  /// The file to print its syntax tree.
+  #[clap(value_name = "FILE")]
+  file: PathBuf,
+
+  /// The language of the file. Inferred from the file path if not specified.
+  #[clap(short, long)]
+  lang: Option<SgLang>,
+
+  /// The format of the printed tree.
+  ///
+  /// `ast` and `cst` print node kinds, fields and ranges like the playground.
+  /// `pattern` parses the whole file as a pattern.
+  #[clap(long, default_value = "ast", value_name = "FORMAT")]
+  format: DebugFormat,
+
+  /// Controls output color.
+  #[clap(long, default_value = "auto", value_name = "WHEN")]
+  color: ColorArg,
+}
+
+/// Print the syntax tree of a file to help write rules without the playground.
+pub fn run_dump(arg: DumpArg) -> Result<()> {
+  let lang = match arg.lang {
+    Some(lang) => lang,
+    None => SgLang::from_file(&arg.file).with_context(|| {
+      let file = arg.file.display();
+      format!("Cannot infer the language of {file}, please use `--lang` to specify it")
+    })?,
+  };
+  let src = read_to_string(&arg.file)
+    .with_context(|| format!("Cannot read file {}", arg.file.display()))?;
+  let dumped = arg.format.dump(&src, lang, arg.color.should_use_color())?;
+  println!("{}", dumped.trim_end());
+  Ok(())
+}
@@ -0,0 +1,652 @@
+use crate::config::{ProjectConfig, LANGUAGE_DIR, PACKAGE_DIR};
+use crate::lang::SgLang;
+use crate::utils::ErrorContext as EC;
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use serde_yaml::Value;
+
+use std::ffi::OsString;
+use std::fs::{read_to_string, write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+#[derive(Args)]
+pub struct InstallArg {
+  /// The rule package to install.
+  ///
+  /// It can be a git URL, a local git repository or a GitHub repository like `owner/repo`.
+  /// The package must have a sgconfig.yml at its root.
+  #[clap(value_name = "PACKAGE")]
+  source: String,
+
+  /// Name of the installed package. Default to the repository name.
+  /// It is used as a directory name, so only letters, digits, `_`, `-` and `.` are allowed.
+  #[clap(long)]
+  name: Option<String>,
+}
+
+/// Clone the package into the project and add it to `extends` in sgconfig.yml.
+pub fn run_install(arg: InstallArg, project: Result<ProjectConfig>) -> Result<()> {
+  let project = project?;
+  let url = resolve_url(&arg.source).context(EC::InstallPackage(arg.source.clone()))?;
+  let name = match arg.name {
+    Some(name) => name,
+    None => package_name(&url).context(EC::InstallPackage(arg.source.clone()))?,
+  };
+  check_name(&name).context(EC::InstallPackage(arg.source.clone()))?;
+  let dest = project.project_dir.join(PACKAGE_DIR).join(&name);
+  if dest.exists() {
+    return Err(anyhow::anyhow!(EC::FileAlreadyExist(dest)));
+  }
+  let status = Command::new("git")
+    .args(["clone", "--depth", "1", "--quiet", &url])
+    .arg(&dest)
+    .status()
+    .context(EC::InstallPackage(arg.source.clone()))?;
+  if !status.success() {
+    return Err(anyhow::anyhow!(EC::InstallPackage(arg.source)));
+  }
+  if !dest.join("sgconfig.yml").exists() {
+    std::fs::remove_dir_all(&dest)?;
+    return Err(anyhow::anyhow!(EC::PackageNotFound(name)));
+  }
+  add_to_extends(&project.project_dir.join("sgconfig.yml"), &name)?;
+  println!("Installed package {name} into {}", dest.display());
+  Ok(())
+}
+
+#[derive(Args)]
+pub struct LangArg {
+  #[clap(subcommand)]
+  command: LangCommands,
+}
+
+#[derive(Subcommand)]
+enum LangCommands {
+  /// Fetch and build a tree-sitter grammar as a custom language of the project.
+  ///
+  /// The grammar is put in `.ast-grep/languages/<NAME>` and its `src/parser.c`,
+  /// together with an optional external scanner, is compiled by the C compiler in `CC`
+  /// (default to `cl` on MSVC toolchains and `cc` elsewhere).
+  /// The built library is then added to `customLanguages` in sgconfig.yml.
+  Install(LangInstallArg),
+  /// List builtin and custom languages of the project.
+  List(LangListArg),
+}
+
+#[derive(Args)]
+struct LangListArg {
+  /// Also print the file patterns of each language.
+  ///
+  /// Patterns from `languageGlobs` in sgconfig.yml are marked because they take precedence over default ones.
+  #[clap(long)]
+  globs: bool,
+}
+
+#[derive(Args)]
+struct LangInstallArg {
+  /// The tree-sitter grammar to install.
+  ///
+  /// It can be a git URL, a local git repository, a GitHub repository like `owner/repo`
+  /// or an npm package prefixed by `npm:`, e.g. `npm:tree-sitter-toml`.
+  #[clap(value_name = "GRAMMAR")]
+  source: String,
+
+  /// Name of the custom language. Default to the grammar name without `tree-sitter-` prefix.
+  #[clap(long)]
+  name: Option<String>,
+
+  /// File extensions of the language, e.g. `--extensions toml`.
+  /// Default to the `file-types` declared in the grammar's tree-sitter.json or package.json.
+  #[clap(long, value_delimiter = ',')]
+  extensions: Vec<String>,
+
+  /// Directory of the grammar in a repository with multiple grammars, e.g. `tsx` in tree-sitter-typescript.
+  /// Default to the repository root.
+  #[clap(long, value_name = "DIR")]
+  grammar_dir: Option<PathBuf>,
+}
+
+pub fn run_lang(arg: LangArg, project: Result<ProjectConfig>) -> Result<()> {
+  match arg.command {
+    LangCommands::Install(arg) => run_lang_install(arg, project),
+    // languages are registered in project setup, listing works without a project
+    LangCommands::List(arg) => {
+      println!("{}", list_langs(arg.globs));
+      Ok(())
+    }
+  }
+}
+
+fn list_langs(with_globs: bool) -> String {
+  let mut lines = vec![];
+  for lang in SgLang::all_langs() {
+    if !with_globs {
+      lines.push(lang.to_string());
+      continue;
+    }
+    let configured = lang
+      .configured_globs()
+      .into_iter()
+      .map(|g| format!("{g} (languageGlobs)"));
+    let globs: Vec<_> = configured.chain(lang.default_globs()).collect();
+    lines.push(format!("{lang}: {}", globs.join(", ")));
+  }
+  lines.join("\n")
+}
+
+/// Fetch and compile the grammar, then register it in `customLanguages` of sgconfig.yml.
+fn run_lang_install(arg: LangInstallArg, project: Result<ProjectConfig>) -> Result<()> {
+  let project = project?;
+  let source = arg.source.as_str();
+  let err = || EC::InstallLanguage(source.to_string());
+  let npm_package = source.strip_prefix("npm:");
+  let grammar = match npm_package {
+    Some(package) => package.to_string(),
+    None => package_name(&resolve_url(source).context(err())?).context(err())?,
+  };
+  // a grammar in a subdirectory is named after the directory, e.g. tsx in tree-sitter-typescript
+  let grammar_dir = arg.grammar_dir.unwrap_or_default();
+  if !is_relative_subdir(&grammar_dir) {
+    let msg = format!(
+      "grammar dir {} must be inside the grammar",
+      grammar_dir.display()
+    );
+    return Err(anyhow::anyhow!(msg).context(err()));
+  }
+  let default_name = match grammar_dir.file_name() {
+    Some(dir) => language_name(&dir.to_string_lossy()),
+    None => language_name(&grammar),
+  };
+  let name = arg.name.unwrap_or_else(|| default_name.clone());
+  check_name(&name).context(err())?;
+  // the grammar exports `tree_sitter_<default_name>`, which ast-grep cannot guess from a custom name
+  let symbol = (name != default_name).then(|| format!("tree_sitter_{default_name}"));
+  let dir = Path::new(LANGUAGE_DIR).join(&name);
+  let dest = project.project_dir.join(&dir);
+  if dest.exists() {
+    return Err(anyhow::anyhow!(EC::FileAlreadyExist(dest)));
+  }
+  let fetched = match npm_package {
+    Some(package) => fetch_npm(package, &dest),
+    None => fetch_git(&resolve_url(source)?, &dest),
+  };
+  let installed = fetched
+    .and_then(|_| find_extensions(&dest, &grammar_dir, arg.extensions))
+    .and_then(|extensions| {
+      let lib = dir.join(library_file(&name));
+      compile_grammar(&dest.join(&grammar_dir), &project.project_dir.join(&lib))?;
+      Ok((lib, extensions))
+    });
+  let (lib, extensions) = match installed {
+    Ok(installed) => installed,
+    Err(e) => {
+      // do not leave a half installed grammar behind
+      let _ = std::fs::remove_dir_all(&dest);
+      return Err(e.context(err()));
+    }
+  };
+  let config_path = project.project_dir.join("sgconfig.yml");
+  add_custom_language(&config_path, &name, &lib, symbol, &extensions)?;
+  println!("Installed language {name} into {}", dest.display());
+  Ok(())
+}
+
+fn fetch_git(url: &str, dest: &Path) -> Result<()> {
+  let status = Command::new("git")
+    .args(["clone", "--depth", "1", "--quiet", url])
+    .arg(dest)
+    .status()?;
+  if !status.success() {
+    return Err(anyhow::anyhow!("git clone {url} failed"));
+  }
+  Ok(())
+}
+
+fn fetch_npm(package: &str, dest: &Path) -> Result<()> {
+  std::fs::create_dir_all(dest)?;
+  let output = Command::new("npm")
+    .args(["pack", "--silent", package, "--pack-destination"])
+    .arg(dest)
+    .output()?;
+  if !output.status.success() {
+    return Err(anyhow::anyhow!("npm pack {package} failed"));
+  }
+  let tarball = String::from_utf8_lossy(&output.stdout);
+  let tarball = dest.join(tarball.trim());
+  let status = Command::new("tar")
+    .arg("-xzf")
+    .arg(&tarball)
+    .args(["--strip-components", "1", "-C"])
+    .arg(dest)
+    .status()?;
+  if !status.success() {
+    return Err(anyhow::anyhow!("cannot extract {}", tarball.display()));
+  }
+  std::fs::remove_file(tarball)?;
+  Ok(())
+}
+
+/// `tree-sitter-toml` and `@scope/tree-sitter-toml@0.5` are both named `toml`
+fn language_name(grammar: &str) -> String {
+  let name = grammar.rsplit('/').next().unwrap_or(grammar);
+  let name = name.split('@').next().unwrap_or(name);
+  let name = name.strip_prefix("tree-sitter-").unwrap_or(name);
+  name.replace('-', "_")
+}
+
+fn library_file(name: &str) -> String {
+  let ext = if cfg!(target_os = "macos") {
+    "dylib"
+  } else if cfg!(windows) {
+    "dll"
+  } else {
+    "so"
+  };
+  format!("{name}.{ext}")
+}
+
+/// Names are used as directory names and config keys, so they must be a plain path component.
+fn check_name(name: &str) -> Result<()> {
+  let is_valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+  if name.is_empty() || name.contains("..") || !name.chars().all(is_valid_char) {
+    return Err(anyhow::anyhow!(
+      "invalid name `{name}`, only letters, digits, `_`, `-` and `.` are allowed"
+    ));
+  }
+  Ok(())
+}
+
+fn is_relative_subdir(dir: &Path) -> bool {
+  dir
+    .components()
+    .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+fn normal_components(path: &Path) -> Vec<Component<'_>> {
+  let not_cur_dir = |c: &Component| *c != Component::CurDir;
+  path.components().filter(not_cur_dir).collect()
+}
+
+fn find_extensions(
+  repo: &Path,
+  grammar_dir: &Path,
+  extensions: Vec<String>,
+) -> Result<Vec<String>> {
+  if !extensions.is_empty() {
+    return Ok(extensions);
+  }
+  // tree-sitter.json is the new grammar metadata, package.json is the legacy one.
+  // Both list grammars in the repository with their paths, default to the root.
+  let declared = [
+    ("tree-sitter.json", "grammars"),
+    ("package.json", "tree-sitter"),
+  ];
+  for (file, key) in declared {
+    let Ok(content) = read_to_string(repo.join(file)) else {
+      continue;
+    };
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) else {
+      continue;
+    };
+    let Some(grammars) = json.get(key).and_then(|g| g.as_array()) else {
+      continue;
+    };
+    let grammar = grammars.iter().find(|g| {
+      let path = g.get("path").and_then(|p| p.as_str()).unwrap_or(".");
+      normal_components(Path::new(path)) == normal_components(grammar_dir)
+    });
+    let Some(types) = grammar.and_then(|g| g.get("file-types")?.as_array()) else {
+      continue;
+    };
+    let extensions: Vec<_> = types
+      .iter()
+      .filter_map(|t| Some(t.as_str()?.to_string()))
+      .collect();
+    if !extensions.is_empty() {
+      return Ok(extensions);
+    }
+  }
+  Err(anyhow::anyhow!(
+    "cannot infer file extensions of the grammar, please specify `--extensions`"
+  ))
+}
+
+fn compile_grammar(grammar_dir: &Path, output: &Path) -> Result<()> {
+  let src = grammar_dir.join("src");
+  let parser = src.join("parser.c");
+  if !parser.exists() {
+    return Err(anyhow::anyhow!("cannot find {}", parser.display()));
+  }
+  let mut sources = vec![parser];
+  let mut is_cpp = false;
+  for scanner in ["scanner.c", "scanner.cc"] {
+    let path = src.join(scanner);
+    if path.exists() {
+      is_cpp |= scanner.ends_with(".cc");
+      sources.push(path);
+    }
+  }
+  let cc = std::env::var("CC").ok();
+  // MSVC toolchain does not ship cc, use cl unless CC is set
+  let is_msvc = match &cc {
+    Some(cc) => cc.ends_with("cl") || cc.ends_with("cl.exe"),
+    None => cfg!(target_env = "msvc"),
+  };
+  let compiler = cc.unwrap_or_else(|| if is_msvc { "cl" } else { "cc" }.into());
+  let mut cmd = Command::new(&compiler);
+  if is_msvc {
+    let mut out = OsString::from("/Fe");
+    out.push(output);
+    // put intermediate obj files next to the sources
+    let mut obj = OsString::from("/Fo");
+    obj.push(src.join(""));
+    cmd
+      .args(["/nologo", "/LD", "/O2", "/utf-8", "/I"])
+      .arg(&src);
+    cmd.args(&sources).arg(out).arg(obj);
+  } else {
+    cmd.args(["-shared", "-fPIC", "-O2", "-I"]).arg(&src);
+    cmd.args(&sources).arg("-o").arg(output);
+    if is_cpp {
+      cmd.arg("-lstdc++");
+    }
+  }
+  let status = cmd.status()?;
+  if !status.success() {
+    return Err(anyhow::anyhow!("{compiler} failed to compile the grammar"));
+  }
+  Ok(())
+}
+
+fn add_custom_language(
+  config_path: &Path,
+  name: &str,
+  lib: &Path,
+  symbol: Option<String>,
+  extensions: &[String],
+) -> Result<()> {
+  let content = read_to_string(config_path).context(EC::ReadConfiguration)?;
+  let config: Value = serde_yaml::from_str(&content).context(EC::ParseConfiguration)?;
+  if !config["customLanguages"][name].is_null() {
+    return Err(anyhow::anyhow!("custom language {name} already exists"));
+  }
+  // use forward slash so the config is portable
+  let lib = lib.to_string_lossy().replace('\\', "/");
+  let mut entry = vec![format!("{}:", yaml_scalar(name)?)];
+  entry.push(format!("  libraryPath: {}", yaml_scalar(&lib)?));
+  if let Some(symbol) = symbol {
+    entry.push(format!("  languageSymbol: {}", yaml_scalar(&symbol)?));
+  }
+  let extensions: Result<Vec<_>> = extensions.iter().map(|e| yaml_scalar(e)).collect();
+  entry.push(format!("  extensions: [{}]", extensions?.join(", ")));
+  let content = insert_entry(&content, "customLanguages", &entry)?;
+  let config: Value = serde_yaml::from_str(&content)?;
+  if config["customLanguages"][name].is_null() {
+    return Err(anyhow::anyhow!(EC::ParseConfiguration));
+  }
+  write(config_path, content)?;
+  Ok(())
+}
+
+fn yaml_scalar(s: &str) -> Result<String> {
+  Ok(serde_yaml::to_string(s)?.trim_end().to_string())
+}
+
+/// Add an entry under the top level `key` of sgconfig.yml by editing its text,
+/// so comments and formatting of the rest of the file are kept.
+/// Each line of the entry is indented like the existing entries under the key.
+fn insert_entry(content: &str, key: &str, entry: &[String]) -> Result<String> {
+  let newline = if content.contains("\r\n") {
+    "\r\n"
+  } else {
+    "\n"
+  };
+  let mut lines: Vec<_> = content.lines().map(str::to_string).collect();
+  let is_comment_or_blank = |line: &str| {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#')
+  };
+  let key_line = lines.iter().position(|line| {
+    let Some(value) = line.strip_prefix(key).and_then(|l| l.strip_prefix(':')) else {
+      return false;
+    };
+    let value = value.trim();
+    value.is_empty() || value.starts_with('#') || value == "[]" || value == "{}"
+  });
+  let Some(key_line) = key_line else {
+    let key_prefix = format!("{key}:");
+    if lines.iter().any(|line| line.starts_with(&key_prefix)) {
+      return Err(anyhow::anyhow!(
+        "cannot edit `{key}` in flow style, please update sgconfig.yml manually"
+      ));
+    }
+    let mut block = vec![format!("{key}:")];
+    block.extend(entry.iter().map(|l| format!("  {l}")));
+    lines.extend(block);
+    return Ok(lines.join(newline) + newline);
+  };
+  // an empty flow collection becomes a block one
+  lines[key_line] = format!("{key}:");
+  let mut end = key_line + 1;
+  let mut indent = None;
+  for (i, line) in lines.iter().enumerate().skip(key_line + 1) {
+    if is_comment_or_blank(line) {
+      continue;
+    }
+    // a block sequence can be at the same indentation as its key
+    if !line.starts_with([' ', '\t']) && !line.starts_with("- ") && line != "-" {
+      break;
+    }
+    let content_start = line.len() - line.trim_start().len();
+    indent.get_or_insert_with(|| line[..content_start].to_string());
+    end = i + 1;
+  }
+  let indent = indent.unwrap_or_else(|| "  ".into());
+  let block = entry.iter().map(|l| format!("{indent}{l}"));
+  lines.splice(end..end, block);
+  Ok(lines.join(newline) + newline)
+}
+
+fn resolve_url(source: &str) -> Result<String> {
+  let is_url = source.contains("://") || source.starts_with("git@") || source.ends_with(".git");
+  if is_url || Path::new(source).is_dir() {
+    return Ok(source.to_string());
+  }
+  // GitHub shorthand owner/repo
+  let mut parts = source.split('/');
+  match (parts.next(), parts.next(), parts.next()) {
+    (Some(owner), Some(repo), None) if !owner.is_empty() && !repo.is_empty() => {
+      Ok(format!("https://github.com/{owner}/{repo}.git"))
+    }
+    _ => Err(anyhow::anyhow!("unrecognized package {source}")),
+  }
+}
+
+fn package_name(url: &str) -> Result<String> {
+  let last = url
+    .trim_end_matches('/')
+    .rsplit(['/', ':', '\\'])
+    .next()
+    .unwrap_or_default();
+  let name = last.strip_suffix(".git").unwrap_or(last);
+  if name.is_empty() {
+    return Err(anyhow::anyhow!("cannot infer package name from {url}"));
+  }
+  Ok(name.to_string())
+}
+
+fn add_to_extends(config_path: &Path, name: &str) -> Result<()> {
+  let content = read_to_string(config_path).context(EC::ReadConfiguration)?;
+  let config: Value = serde_yaml::from_str(&content).context(EC::ParseConfiguration)?;
+  let extends = |config: &Value| match &config["extends"] {
+    Value::Sequence(extends) => extends.contains(&Value::from(name)),
+    _ => false,
+  };
+  if extends(&config) {
+    return Ok(());
+  }
+  let entry = [format!("- {}", yaml_scalar(name)?)];
+  let content = insert_entry(&content, "extends", &entry)?;
+  let config: Value = serde_yaml::from_str(&content)?;
+  if !extends(&config) {
+    return Err(anyhow::anyhow!(EC::ParseConfiguration));
+  }
+  write(config_path, content)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_resolve_url() {
+    let url = resolve_url("ast-grep/rules").expect("should resolve");
+    assert_eq!(url, "https://github.com/ast-grep/rules.git");
+    let url = "https://example.com/team/rules.git";
+    assert_eq!(resolve_url(url).expect("should resolve"), url);
+    let url = "git@github.com:team/rules.git";
+    assert_eq!(resolve_url(url).expect("should resolve"), url);
+    assert!(resolve_url("rules").is_err());
+    assert!(resolve_url("a/b/c").is_err());
+  }
+
+  #[test]
+  fn test_package_name() {
+    let name = |url| package_name(url).expect("should infer name");
+    assert_eq!(name("https://github.com/ast-grep/rules.git"), "rules");
+    assert_eq!(name("git@github.com:team/shared-rules.git"), "shared-rules");
+    assert_eq!(name("../local/pack/"), "pack");
+    assert!(package_name("https://").is_err());
+  }
+
+  #[test]
+  fn test_add_to_extends() -> Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("sgconfig.yml");
+    write(&path, "# my project\nruleDirs:\n- rules\n")?;
+    add_to_extends(&path, "shared")?;
+    add_to_extends(&path, "shared")?;
+    add_to_extends(&path, "other")?;
+    let content = read_to_string(&path)?;
+    let expected = "# my project\nruleDirs:\n- rules\nextends:\n  - shared\n  - other\n";
+    assert_eq!(content, expected);
+    let config: Value = serde_yaml::from_str(&content)?;
+    assert_eq!(
+      config["extends"],
+      serde_yaml::from_str::<Value>("[shared, other]")?
+    );
+    assert_eq!(config["ruleDirs"][0], Value::from("rules"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_list_langs() {
+    let names = list_langs(false);
+    assert!(names.lines().any(|l| l == "Rust"));
+    let globs = list_langs(true);
+    assert!(globs.lines().any(|l| l == "Rust: *.rs"));
+  }
+
+  #[test]
+  fn test_language_name() {
+    assert_eq!(language_name("tree-sitter-toml"), "toml");
+    assert_eq!(language_name("@scope/tree-sitter-c-sharp"), "c_sharp");
+    assert_eq!(language_name("tree-sitter-toml@0.5.1"), "toml");
+    assert_eq!(language_name("mylang"), "mylang");
+  }
+
+  #[test]
+  fn test_find_extensions() -> Result<()> {
+    let dir = TempDir::new()?;
+    let root = Path::new("");
+    assert!(find_extensions(dir.path(), root, vec![]).is_err());
+    let exts = find_extensions(dir.path(), root, vec!["a".into()])?;
+    assert_eq!(exts, ["a"]);
+    let package = r#"{"tree-sitter": [{"scope": "source.toml", "file-types": ["toml"]}]}"#;
+    write(dir.path().join("package.json"), package)?;
+    assert_eq!(find_extensions(dir.path(), root, vec![])?, ["toml"]);
+    let metadata = r#"{"grammars": [{"name": "toml", "file-types": ["toml", "tml"]}]}"#;
+    write(dir.path().join("tree-sitter.json"), metadata)?;
+    assert_eq!(find_extensions(dir.path(), root, vec![])?, ["toml", "tml"]);
+    Ok(())
+  }
+
+  #[test]
+  fn test_find_extensions_in_grammar_dir() -> Result<()> {
+    let dir = TempDir::new()?;
+    let metadata = r#"{"grammars": [
+      {"name": "typescript", "path": "typescript", "file-types": ["ts"]},
+      {"name": "tsx", "path": "tsx", "file-types": ["tsx"]}
+    ]}"#;
+    write(dir.path().join("tree-sitter.json"), metadata)?;
+    assert_eq!(
+      find_extensions(dir.path(), Path::new("tsx"), vec![])?,
+      ["tsx"]
+    );
+    assert!(find_extensions(dir.path(), Path::new(""), vec![]).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_name() {
+    assert!(check_name("toml").is_ok());
+    assert!(check_name("c_sharp-2.0").is_ok());
+    assert!(check_name("").is_err());
+    assert!(check_name("..").is_err());
+    assert!(check_name("a/b").is_err());
+    assert!(check_name("a\\b").is_err());
+    assert!(is_relative_subdir(Path::new("grammars/ocaml")));
+    assert!(!is_relative_subdir(Path::new("../ocaml")));
+    assert!(!is_relative_subdir(Path::new("/ocaml")));
+  }
+
+  #[test]
+  fn test_insert_entry() -> Result<()> {
+    let entry = ["- b".to_string()];
+    let config = "# project\nextends:\n- a # first\n\n# rules\nruleDirs: [rules]\n";
+    let expected = "# project\nextends:\n- a # first\n- b\n\n# rules\nruleDirs: [rules]\n";
+    assert_eq!(insert_entry(config, "extends", &entry)?, expected);
+    let config = "extends:\n  - a\n";
+    assert_eq!(
+      insert_entry(config, "extends", &entry)?,
+      "extends:\n  - a\n  - b\n"
+    );
+    let config = "extends: []\nruleDirs: [rules]";
+    let expected = "extends:\n  - b\nruleDirs: [rules]\n";
+    assert_eq!(insert_entry(config, "extends", &entry)?, expected);
+    let config = "ruleDirs: [rules]\r\n";
+    let expected = "ruleDirs: [rules]\r\nextends:\r\n  - b\r\n";
+    assert_eq!(insert_entry(config, "extends", &entry)?, expected);
+    assert!(insert_entry("extends: [a]", "extends", &entry).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_add_custom_language() -> Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("sgconfig.yml");
+    write(&path, "# my project\nruleDirs:\n- rules\n")?;
+    let lib = Path::new(LANGUAGE_DIR).join("toml").join("toml.so");
+    add_custom_language(&path, "toml", &lib, None, &["toml".into()])?;
+    let config: Value = serde_yaml::from_str(&read_to_string(&path)?)?;
+    let expected = "libraryPath: .ast-grep/languages/toml/toml.so\nextensions: [toml]";
+    assert_eq!(
+      config["customLanguages"]["toml"],
+      serde_yaml::from_str::<Value>(expected)?
+    );
+    assert_eq!(config["ruleDirs"][0], Value::from("rules"));
+    let symbol = Some("tree_sitter_toml".to_string());
+    add_custom_language(&path, "my_toml", &lib, symbol, &["toml".into()])?;
+    let content = read_to_string(&path)?;
+    let config: Value = serde_yaml::from_str(&content)?;
+    let lang = &config["customLanguages"]["my_toml"];
+    assert_eq!(lang["languageSymbol"], Value::from("tree_sitter_toml"));
+    assert!(content.starts_with("# my project\nruleDirs:\n- rules\n"));
+    assert!(add_custom_language(&path, "toml", &lib, None, &[]).is_err());
+    Ok(())
+  }
+}
@@ -156,20 +156,12 @@ fn extract_custom_inject(
       else {
         continue;
       };
-      let range = node_to_range(region);
+      let range = region.ts_range();
       ret.entry(lang).or_default().push(range);
     }
   }
 }
 
-fn node_to_range<D: Doc>(node: &Node<D>) -> TSRange {
-  let r = node.range();
-  let start = node.start_pos();
-  let sp = start.ts_point();
-  let end = node.end_pos();
-  let ep = end.ts_point();
-  TSRange::new(r.start as u32, r.end as u32, &sp, &ep)
-}
 
 #[cfg(test)]
 mod test {
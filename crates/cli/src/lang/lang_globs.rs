@@ -5,7 +5,7 @@ use std::path::Path;
 use std::ptr::{addr_of, addr_of_mut};
 use std::str::FromStr;
 
-use crate::utils::ErrorContext as EC;
+use crate::utils::{print_warning, ErrorContext as EC};
 use anyhow::{Context, Result};
 
 // both use vec since lang will be small
@@ -18,6 +18,11 @@ pub unsafe fn register(regs: LanguageGlobs) -> Result<()> {
     (*addr_of!(LANG_GLOBS)).is_empty()
   };
   let lang_globs = register_impl(regs)?;
+  let conflicts = find_conflicts(&lang_globs);
+  if !conflicts.is_empty() {
+    // files are still parsed as the winning language, so do not fail the command
+    print_warning(EC::LanguageGlobsConflict(conflicts));
+  }
   _ = std::mem::replace(&mut *addr_of_mut!(LANG_GLOBS), lang_globs);
   Ok(())
 }
@@ -32,9 +37,77 @@ fn register_impl(regs: LanguageGlobs) -> Result<Vec<(SgLang, Types)>> {
     let types = build_types(&lang_name, globs)?;
     lang_globs.push((lang, types));
   }
+  // sort to make `from_path` and reported conflicts stable regardless of HashMap
+  lang_globs.sort_by_key(|(lang, _)| lang.to_string());
   Ok(lang_globs)
 }
 
+/// A pattern conflicts if a file it matches is also matched by the globs of another language,
+/// e.g. `*.vue` in html and `**/*.vue` in vue. The first language in `lang_globs` wins.
+fn find_conflicts(lang_globs: &[(SgLang, Types)]) -> Vec<String> {
+  let mut conflicts = vec![];
+  for (i, (lang, types)) in lang_globs.iter().enumerate() {
+    for glob in types.definitions().iter().flat_map(|d| d.globs()) {
+      let samples = glob_samples(glob);
+      for (j, (other, other_types)) in lang_globs.iter().enumerate() {
+        let overlap = samples
+          .iter()
+          .any(|s| other_types.matched(s, false).is_whitelist());
+        if i == j || !overlap {
+          continue;
+        }
+        let winner = if i < j { lang } else { other };
+        conflicts.push(format!(
+          "`{glob}` of {lang} is also matched by {other}, {winner} is used"
+        ));
+      }
+    }
+  }
+  conflicts
+}
+
+/// File names matched by the glob, with braces expanded and wildcards filled in.
+/// The glob itself is included so a literal `*.vue` matched by `*.vue` still counts.
+fn glob_samples(glob: &str) -> Vec<String> {
+  let mut samples = vec![glob.to_string()];
+  for expanded in expand_braces(glob) {
+    let mut sample = String::new();
+    let mut chars = expanded.chars().peekable();
+    while let Some(c) = chars.next() {
+      match c {
+        '*' | '?' => sample.push('a'),
+        '[' => {
+          // pick the first char of the class, or any char if it is negated
+          let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+          match class.chars().next() {
+            Some('!' | '^') | None => sample.push('_'),
+            Some(first) => sample.push(first),
+          }
+        }
+        '\\' => sample.extend(chars.next()),
+        c => sample.push(c),
+      }
+    }
+    samples.push(sample);
+  }
+  samples
+}
+
+/// Expand the first `{a,b}` group of the glob recursively.
+fn expand_braces(glob: &str) -> Vec<String> {
+  let (Some(open), Some(close)) = (glob.find('{'), glob.find('}')) else {
+    return vec![glob.to_string()];
+  };
+  if close < open {
+    return vec![glob.to_string()];
+  }
+  let (prefix, suffix) = (&glob[..open], &glob[close + 1..]);
+  glob[open + 1..close]
+    .split(',')
+    .flat_map(|alt| expand_braces(&format!("{prefix}{alt}{suffix}")))
+    .collect()
+}
+
 fn build_types(lang: &str, globs: Vec<String>) -> Result<Types> {
   let mut builder = TypesBuilder::new();
   for glob in globs {
@@ -56,6 +129,15 @@ fn add_types(builder: &mut TypesBuilder, types: &Types) {
   }
 }
 
+/// patterns configured in languageGlobs for the language
+pub fn get_globs(lang: &SgLang) -> Vec<String> {
+  let Some(types) = get_types(lang) else {
+    return vec![];
+  };
+  let globs = types.definitions().iter().flat_map(|d| d.globs());
+  globs.cloned().collect()
+}
+
 fn get_types(lang: &SgLang) -> Option<&Types> {
   for (l, types) in unsafe { &*addr_of!(LANG_GLOBS) } {
     if l == lang {
@@ -91,7 +173,11 @@ pub fn merge_globs(lang: &SgLang, type1: Types) -> Types {
 }
 
 pub fn from_path(p: &Path) -> Option<SgLang> {
-  for (lang, types) in unsafe { &*addr_of!(LANG_GLOBS) } {
+  from_path_impl(unsafe { &*addr_of!(LANG_GLOBS) }, p)
+}
+
+fn from_path_impl(lang_globs: &[(SgLang, Types)], p: &Path) -> Option<SgLang> {
+  for (lang, types) in lang_globs {
     if types.matched(p, false).is_whitelist() {
       return Some(*lang);
     }
@@ -139,6 +225,51 @@ html: ['*.vue', '*.svelte']";
     ));
   }
 
+  #[test]
+  fn test_glob_conflict() -> Result<()> {
+    let mut globs = get_globs();
+    globs.insert("ts".into(), vec!["**/*.vue".into()]);
+    let lang_globs = register_impl(globs)?;
+    assert_eq!(
+      find_conflicts(&lang_globs),
+      [
+        "`*.vue` of Html is also matched by TypeScript, Html is used",
+        "`**/*.vue` of TypeScript is also matched by Html, Html is used",
+      ]
+    );
+    let lang = from_path_impl(&lang_globs, Path::new("src/a.vue"));
+    assert_eq!(lang, Some(SupportLang::Html.into()));
+    Ok(())
+  }
+
+  #[test]
+  fn test_glob_conflict_with_wildcards() -> Result<()> {
+    let mut globs = get_globs();
+    globs.insert(
+      "ts".into(),
+      vec!["*.{ts,svelte}".into(), "?.eslintrc".into()],
+    );
+    let lang_globs = register_impl(globs)?;
+    assert_eq!(
+      find_conflicts(&lang_globs),
+      [
+        "`*.svelte` of Html is also matched by TypeScript, Html is used",
+        "`*.{ts,svelte}` of TypeScript is also matched by Html, Html is used",
+      ]
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_glob_samples() {
+    assert_eq!(glob_samples("*.vue"), ["*.vue", "a.vue"]);
+    assert_eq!(
+      glob_samples("[!x]?.{js,ts}"),
+      ["[!x]?.{js,ts}", "_a.js", "_a.ts"]
+    );
+    assert_eq!(glob_samples("[ab]\\*"), ["[ab]\\*", "a*"]);
+  }
+
   #[test]
   fn test_merge_types() {
     let lang: SgLang = SupportLang::Rust.into();
@@ -16,6 +16,8 @@ use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 use std::str::FromStr;
 
@@ -58,6 +60,21 @@ impl SgLang {
     unsafe { injection::register_injetables(injections) }
   }
 
+  /// default file patterns of the language, without languageGlobs
+  pub fn default_globs(&self) -> Vec<String> {
+    let types = match self {
+      Builtin(b) => b.file_types(),
+      Custom(c) => c.file_types(),
+    };
+    let globs = types.definitions().iter().flat_map(|d| d.globs());
+    globs.cloned().collect()
+  }
+
+  /// file patterns configured in languageGlobs, which take precedence over default ones
+  pub fn configured_globs(&self) -> Vec<String> {
+    lang_globs::get_globs(self)
+  }
+
   pub fn all_langs() -> Vec<Self> {
     let builtin = SupportLang::all_langs().iter().copied().map(Self::Builtin);
     let customs = DynamicLang::all_langs().into_iter().map(Self::Custom);
@@ -90,6 +107,24 @@ impl SgLang {
     let types = langs.map(|lang| lang.augmented_file_type());
     lang_globs::merge_types(types)
   }
+
+  /// Infer the language of a file by its path, or by the shebang line of a script without extension.
+  pub fn from_file(path: &Path) -> Option<Self> {
+    Self::from_path(path).or_else(|| Self::from_script(path))
+  }
+
+  /// Read the interpreter in the shebang line of a script without extension.
+  pub fn from_script(path: &Path) -> Option<Self> {
+    if path.extension().is_some() {
+      return None;
+    }
+    let file = File::open(path).ok()?;
+    // shebang is short, do not read the whole line of a binary file
+    let mut reader = BufReader::new(file.take(256));
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    SupportLang::from_shebang(&line).map(Builtin)
+  }
 }
 
 impl Display for SgLang {
@@ -191,6 +226,14 @@ impl Language for SgLang {
     }
   }
 
+  /// Comment delimiters are unknown for custom languages.
+  fn comment_delimiters(&self) -> Option<(&'static str, &'static str)> {
+    match self {
+      Builtin(b) => b.comment_delimiters(),
+      Custom(_) => None,
+    }
+  }
+
   fn injectable_languages(&self) -> Option<&'static [&'static str]> {
     injection::injectable_languages(*self)
   }
@@ -1,11 +1,15 @@
+mod apply;
 mod completions;
 mod config;
+mod dump;
+mod install;
 mod lang;
 mod lsp;
 mod new;
 mod print;
 mod run;
 mod scan;
+mod suppress;
 mod utils;
 mod verify;
 
@@ -13,12 +17,16 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use apply::{run_apply, ApplyArg};
 use completions::{run_shell_completion, CompletionsArg};
 use config::ProjectConfig;
+use dump::{run_dump, DumpArg};
+use install::{run_install, run_lang, InstallArg, LangArg};
 use lsp::{run_language_server, LspArg};
 use new::{run_create_new, NewArg};
 use run::{run_with_pattern, RunArg};
 use scan::{run_with_config, ScanArg};
+use suppress::{run_suppress, SuppressArg};
 use utils::exit_with_error;
 use verify::{run_test_rule, TestArg};
 
@@ -52,10 +60,20 @@ enum Commands {
   Run(RunArg),
   /// Scan and rewrite code by configuration.
   Scan(ScanArg),
+  /// Insert ast-grep-ignore comments above all current findings of project rules.
+  Suppress(SuppressArg),
+  /// Apply fixes recorded in a JSON report generated by run or scan.
+  Apply(ApplyArg),
   /// Test ast-grep rules.
   Test(TestArg),
   /// Create new ast-grep project or items like rules/tests.
   New(NewArg),
+  /// Install a rule package into the project.
+  Install(InstallArg),
+  /// Manage custom languages of the project.
+  Lang(LangArg),
+  /// Print the syntax tree of a file.
+  Dump(DumpArg),
   /// Start language server.
   Lsp(LspArg),
   /// Generate shell completion script.
@@ -134,8 +152,13 @@ pub fn main_with_args(args: impl Iterator<Item = String>) -> Result<()> {
   match app.command {
     Commands::Run(arg) => run_with_pattern(arg, project),
     Commands::Scan(arg) => run_with_config(arg, project),
+    Commands::Suppress(arg) => run_suppress(arg, project),
+    Commands::Apply(arg) => run_apply(arg),
     Commands::Test(arg) => run_test_rule(arg, project),
     Commands::New(arg) => run_create_new(arg, project),
+    Commands::Install(arg) => run_install(arg, project),
+    Commands::Lang(arg) => run_lang(arg, project),
+    Commands::Dump(arg) => run_dump(arg),
     Commands::Lsp(arg) => run_language_server(arg, project),
     Commands::Completions(arg) => run_shell_completion::<App>(arg),
     Commands::Docs => todo!("todo, generate rule docs based on current config"),
@@ -206,10 +229,16 @@ mod test_cli {
     ok("run -p test -i");
     ok("run -p test --interactive dir");
     ok("run -p test -r Test dir");
+    ok("run -p test --output out.txt");
+    ok("run -p test -A 2 dir");
+    ok("run -p test --before 2 --json");
+    ok("run -p test -C 1");
+    error("run -p test -A 1 -C 1"); // conflict
     ok("run -p test -l rs --debug-query");
     ok("run -p test -l rs --debug-query not");
     ok("run -p test -l rs --debug-query=ast");
     ok("run -p test -l rs --debug-query=cst");
+    ok("run -p test -l rs --debug-query=match");
     ok("run -p test -l rs --color always");
     ok("run -p test -l rs --heading always");
     ok("run -p test dir1 dir2 dir3"); // multiple paths
@@ -220,6 +249,7 @@ mod test_cli {
     ok("run -p test --json dir"); // arg after --json should not be parsed as JsonStyle
     ok("run -p test --strictness ast");
     ok("run -p test --strictness relaxed");
+    ok("run -p test --allow-error-nodes");
     ok("run -p test --selector identifier"); // pattern + selector
     ok("run -p test --selector identifier -l js");
     ok("run -p test --follow");
@@ -228,7 +258,10 @@ mod test_cli {
     ok("run -p test --globs '*.js' --globs '*.ts'");
     ok("run -p fubuki -j8");
     ok("run -p test --threads 12");
+    ok("run -p test -l rs --rewrite-config rewrite.yml");
     ok("run -p test -l rs -c config.yml"); // global config arg
+    ok("run -p test -r Test --format patch");
+    ok("run -p test -r Test --stats");
     error("run test");
     error("run --debug-query test"); // missing lang
     error("run -r Test dir");
@@ -239,6 +272,11 @@ mod test_cli {
     error("run -p test -l rs --debug-query=not");
     error("run -p test --selector");
     error("run -p test --threads");
+    error("run -p test --rewrite-config rewrite.yml");
+    error("run -p test -r Test --format patch --json");
+    error("run -p test -r Test --format github");
+    error("run -p test --stats"); // missing rewrite
+    error("run -p test -r Test --stats -U");
   }
 
   #[test]
@@ -252,6 +290,9 @@ mod test_cli {
     ok("scan dir1 dir2 dir3"); // multiple paths
     ok("scan -r test.yml --format github");
     ok("scan --format github");
+    ok("scan --format gitlab");
+    ok("scan --format patch");
+    ok("scan --stats");
     ok("scan --interactive");
     ok("scan --follow");
     ok("scan -r test.yml -c test.yml --json dir"); // allow registering custom lang
@@ -263,16 +304,59 @@ mod test_cli {
     ok("scan -A 12");
     ok("scan --after 12");
     ok("scan --context 1");
+    ok("scan --severity no-console=off");
+    ok("scan --filter no-console.*");
+    ok("scan --rule-filter no-console.*");
+    ok("scan --baseline baseline.json");
+    ok("scan --severity no-*=warning --severity *=error");
+    ok("scan --strictness signature");
+    ok("scan --dedup-fixes");
+    ok("scan --error-on warning");
+    ok("scan --max-findings 10");
+    ok("scan --sort path");
+    ok("scan --json --output report/scan.json");
+    error("scan -i --output report/scan.txt");
+    ok("scan --summary --summary-by-dir");
+    error("scan --summary-by-dir");
+    error("scan --summary --json");
+    ok("scan --sort severity --json");
+    error("scan --sort line");
+    error("scan --max-findings 10 --baseline baseline.json");
+    error("scan --error-on off");
+    ok("scan -r test.yml --inline-rules '{}'");
+    ok("scan --inline-rules -");
     error("scan -i --json dir"); // conflict
     error("scan --report-style rich --json dir"); // conflict
-    error("scan -r test.yml --inline-rules '{}'"); // conflict
-    error("scan --format gitlab");
     error("scan --format github -i");
     error("scan --format local");
+    error("scan --stats --json");
     error("scan --json=dir"); // wrong json flag
     error("scan --json= not-pretty"); // wrong json flag
     error("scan -j");
     error("scan --threads");
+    error("scan --severity no-console");
+    error("scan --baseline baseline.json --stdin");
+    error("scan --severity no-console=fatal");
+    error("scan --strictness loose");
+    error("scan -r test.yml --rule-filter no-console");
+  }
+
+  #[test]
+  fn test_suppress() {
+    ok("suppress");
+    ok("suppress dir1 dir2");
+    ok("suppress --rule-id no-console");
+    ok("suppress --rule-id no-console --rule-id no-debugger dir");
+    ok("suppress --globs '*.js'");
+    error("suppress --rule-id");
+    error("suppress --json");
+  }
+
+  #[test]
+  fn test_apply() {
+    ok("apply --from report.json");
+    error("apply");
+    error("apply report.json");
   }
 
   #[test]
@@ -283,6 +367,8 @@ mod test_cli {
     ok("test -U");
     ok("test --update-all");
     error("test --update-all --skip-snapshot-tests");
+    ok("test --output report/test.txt");
+    error("test --output report/test.txt --watch");
   }
   #[test]
   fn test_new() {
@@ -296,6 +382,25 @@ mod test_cli {
     error("new --base-dir");
   }
 
+  #[test]
+  fn test_lang() {
+    ok("lang install tree-sitter/tree-sitter-toml");
+    ok("lang install npm:tree-sitter-toml --name toml --extensions toml,tml");
+    error("lang install");
+    error("lang");
+    ok("lang list");
+    ok("lang list --globs");
+  }
+
+  #[test]
+  fn test_dump() {
+    ok("dump a.ts");
+    ok("dump a.ts --format cst -l ts");
+    ok("dump a.ts --format sexp --color never");
+    error("dump");
+    error("dump a.ts --format tree");
+  }
+
   #[test]
   fn test_shell() {
     ok("completions");
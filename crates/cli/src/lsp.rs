@@ -1,12 +1,34 @@
 use crate::config::ProjectConfig;
-use crate::utils::ErrorContext as EC;
+use crate::utils::{ErrorContext as EC, FileLimits, NoIgnore};
 use anyhow::{Context, Result};
-use ast_grep_lsp::{Backend, LspService, Server};
+use ast_grep_lsp::{Backend, LspService, Server, Walker, SEARCH};
 use clap::Args;
+use ignore::WalkBuilder;
+
+use std::path::Path;
 
 #[derive(Args)]
 pub struct LspArg {}
 
+/// Walk workspace files like `sg scan` does by default,
+/// respecting ignore files and skipping large or minified files.
+struct ProjectWalker {
+  limits: FileLimits,
+}
+
+impl Walker for ProjectWalker {
+  fn walk(&self, base: &Path) -> WalkBuilder {
+    NoIgnore::default().walk(&[base.to_path_buf()])
+  }
+  fn read(&self, path: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    if self.limits.skip_reason(&text).is_some() {
+      return None;
+    }
+    Some(text)
+  }
+}
+
 async fn run_language_server_impl(_arg: LspArg, project: Result<ProjectConfig>) -> Result<()> {
   // env_logger::init();
   // TODO: move this error to client
@@ -23,9 +45,25 @@ async fn run_language_server_impl(_arg: LspArg, project: Result<ProjectConfig>)
         .join(". ")
     })
     .map(|r| r.0);
+  let walker = ProjectWalker {
+    limits: FileLimits {
+      skip_minified: true,
+      ..project_config.file_limits
+    },
+  };
+  let rule_dirs = project_config
+    .rule_dirs
+    .iter()
+    .map(|dir| project_config.project_dir.join(dir))
+    .collect();
   let config_base = project_config.project_dir;
-  let (service, socket) =
-    LspService::build(|client| Backend::new(client, config_base, config_result_std)).finish();
+  let (service, socket) = LspService::build(|client| {
+    Backend::new(client, config_base, config_result_std)
+      .with_walker(walker)
+      .with_rule_dirs(rule_dirs)
+  })
+  .custom_method(SEARCH, Backend::search)
+  .finish();
   Server::new(stdin, stdout, socket).serve(service).await;
   Ok(())
 }
@@ -1,9 +1,13 @@
-use crate::config::{AstGrepConfig, ProjectConfig, TestConfig};
+use crate::config::{read_rule_file, AstGrepConfig, ProjectConfig, TestConfig};
 use crate::lang::SgLang;
 use crate::utils::ErrorContext as EC;
+use crate::verify::TestCase;
 
 use anyhow::Result;
+use ast_grep_config::RuleConfig;
+use ast_grep_core::Language;
 use clap::{Parser, Subcommand};
+use ignore::WalkBuilder;
 use inquire::validator::ValueRequiredValidator;
 
 use std::fmt::Display;
@@ -23,6 +27,13 @@ pub struct NewArg {
   /// This option is only available when creating rule and util.
   #[arg(short, long, global = true)]
   lang: Option<SgLang>,
+  /// The pattern of the rule to create.
+  ///
+  /// This option is only available when creating rule.
+  /// ast-grep will search the project with the new rule and
+  /// offer to seed the rule's test with the matched code.
+  #[arg(short, long, global = true)]
+  pattern: Option<String>,
   /// Accept all default options without interactive input during creation.
   ///
   /// You need to provide all required arguments via command line if this flag is true.
@@ -170,7 +181,7 @@ fn do_create_entity(entity: Entity, found: ProjectConfig, arg: NewArg) -> Result
   // ask user what destination to create if multiple dirs exist
   match entity {
     Entity::Rule => create_new_rule(found, arg),
-    Entity::Test => create_new_test(found.test_configs, arg.name),
+    Entity::Test => create_new_test(found.test_configs, arg.name, None),
     Entity::Util => create_new_util(found, arg),
     Entity::Project => Err(anyhow::anyhow!(EC::ProjectAlreadyExist)),
   }
@@ -213,9 +224,16 @@ fn create_new_project(arg: NewArg, project_dir: &Path) -> Result<()> {
     rule_dirs: vec![rule_dirs],
     test_configs: test_dirs.map(|t| vec![t]),
     util_dirs: utils.map(|u| vec![u]),
+    rewriter_dirs: None,
     custom_languages: None,      // advanced feature, skip now
     language_globs: None,        // advanced feature, skip now
     language_injections: vec![], // advanced feature
+    extends: vec![],
+    root: None,
+    severities: Default::default(),
+    max_file_size: None,
+    max_line_count: None,
+    ignores: vec![],
   };
   let config_path = project_dir.join("sgconfig.yml");
   let f = File::create(config_path)?;
@@ -224,7 +242,12 @@ fn create_new_project(arg: NewArg, project_dir: &Path) -> Result<()> {
   Ok(())
 }
 
-fn default_rule(id: &str, lang: SgLang) -> String {
+fn default_rule(id: &str, lang: SgLang, pattern: Option<&str>) -> String {
+  // JSON string is also a valid YAML scalar
+  let pattern = pattern.map_or_else(
+    || "Your Rule Pattern here...".to_string(),
+    |p| serde_json::to_string(p).expect("string must serialize"),
+  );
   format!(
     r#"# yaml-language-server: $schema=https://raw.githubusercontent.com/ast-grep/ast-grep/main/schemas/rule.json
 
@@ -233,7 +256,7 @@ message: Add your rule message here....
 severity: error # error, warning, info, hint
 language: {lang}
 rule:
-  pattern: Your Rule Pattern here...
+  pattern: {pattern}
 # utils: Extract repeated rule as local utility here.
 # note: Add detailed explanation for the rule."#
   )
@@ -260,15 +283,98 @@ fn create_new_rule(found: ProjectConfig, arg: NewArg) -> Result<()> {
     return Err(anyhow::anyhow!(EC::FileAlreadyExist(path)));
   }
   let lang = arg.choose_language()?;
-  fs::write(&path, default_rule(&name, lang))?;
+  fs::write(&path, default_rule(&name, lang, arg.pattern.as_deref()))?;
   println!("Created rules at {}", path.display());
   let need_test = arg.confirm("Do you also need to create a test for the rule?")?;
   if need_test {
-    create_new_test(test_configs, Some(name))?;
+    let seed = if arg.pattern.is_some() {
+      seed_test_case(&project_dir, &path, &arg)?
+    } else {
+      None
+    };
+    create_new_test(test_configs, Some(name), seed)?;
   }
   Ok(())
 }
 
+const MAX_SAMPLES: usize = 5;
+
+/// How a matched code sample is used in the seeded test.
+/// Matched code is always reported by the rule, so it can only be an invalid case.
+#[derive(Clone, Copy)]
+enum SampleUsage {
+  Invalid,
+  Skip,
+}
+
+impl Display for SampleUsage {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    use SampleUsage::*;
+    match self {
+      Invalid => f.write_str("Invalid: the rule should report it"),
+      Skip => f.write_str("Skip"),
+    }
+  }
+}
+
+/// Run the new rule against the project and let the user pick matched code as invalid test cases.
+/// With `--yes`, all samples are used.
+fn seed_test_case(project_dir: &Path, rule_path: &Path, arg: &NewArg) -> Result<Option<TestCase>> {
+  let rules = read_rule_file(rule_path, None)?;
+  let Some(rule) = rules.first() else {
+    return Ok(None);
+  };
+  let samples = find_samples(project_dir, rule);
+  if samples.is_empty() {
+    println!("No code in the project matches the new rule.");
+    return Ok(None);
+  }
+  let mut case = TestCase {
+    id: rule.id.clone(),
+    valid: vec![],
+    invalid: vec![],
+  };
+  for (path, line, code) in samples {
+    let usage = if arg.yes {
+      SampleUsage::Invalid
+    } else {
+      println!("\n{}:{}\n{code}\n", path.display(), line + 1);
+      let options = vec![SampleUsage::Invalid, SampleUsage::Skip];
+      inquire::Select::new("How do you want to use this match in the test?", options).prompt()?
+    };
+    match usage {
+      SampleUsage::Invalid => case.invalid.push(code),
+      SampleUsage::Skip => (),
+    }
+  }
+  Ok(Some(case))
+}
+
+/// Collect the first few matches of the rule in the project as (path, line, code).
+fn find_samples(project_dir: &Path, rule: &RuleConfig<SgLang>) -> Vec<(PathBuf, usize, String)> {
+  let walker = WalkBuilder::new(project_dir)
+    .types(rule.language.augmented_file_type())
+    .build();
+  let mut samples = vec![];
+  for entry in walker.flatten() {
+    if !entry.file_type().is_some_and(|t| t.is_file()) {
+      continue;
+    }
+    let Ok(source) = fs::read_to_string(entry.path()) else {
+      continue;
+    };
+    let grep = rule.language.ast_grep(source);
+    for m in grep.root().find_all(&rule.matcher) {
+      let line = m.start_pos().line();
+      samples.push((entry.path().to_path_buf(), line, m.text().to_string()));
+      if samples.len() >= MAX_SAMPLES {
+        return samples;
+      }
+    }
+  }
+  samples
+}
+
 fn default_test(id: &str) -> String {
   format!(
     r#"id: {id}
@@ -280,7 +386,11 @@ invalid:
   )
 }
 
-fn create_new_test(test_configs: Option<Vec<TestConfig>>, name: Option<String>) -> Result<()> {
+fn create_new_test(
+  test_configs: Option<Vec<TestConfig>>,
+  name: Option<String>,
+  seed: Option<TestCase>,
+) -> Result<()> {
   let Some(tests) = test_configs else {
     return Err(anyhow::anyhow!(EC::NoTestDirConfigured));
   };
@@ -305,7 +415,11 @@ fn create_new_test(test_configs: Option<Vec<TestConfig>>, name: Option<String>)
   if path.exists() {
     return Err(anyhow::anyhow!(EC::FileAlreadyExist(path)));
   }
-  fs::write(&path, default_test(&name))?;
+  let content = match seed {
+    Some(case) => serde_yaml::to_string(&case)?,
+    None => default_test(&name),
+  };
+  fs::write(&path, content)?;
   println!("Created test at {}", path.display());
   Ok(())
 }
@@ -363,6 +477,7 @@ mod test {
       entity: None,
       name: None,
       lang: None,
+      pattern: None,
       yes: true,
     };
     create_new_project(arg, tempdir)?;
@@ -376,6 +491,7 @@ mod test {
       entity: Some(Entity::Rule),
       name: Some("test-rule".into()),
       lang: Some(SupportLang::Rust.into()),
+      pattern: None,
       yes: true,
     };
     run_create_new(arg, project)?;
@@ -389,6 +505,7 @@ mod test {
       entity: Some(Entity::Util),
       name: Some("test-utils".into()),
       lang: Some(SupportLang::Rust.into()),
+      pattern: None,
       yes: true,
     };
     run_create_new(arg, project)?;
@@ -405,6 +522,35 @@ mod test {
     Ok(())
   }
 
+  #[test]
+  fn test_create_rule_with_samples() -> Result<()> {
+    let dir = TempDir::new()?;
+    let temp = dir.path();
+    create_project(temp)?;
+    fs::write(
+      temp.join("main.rs"),
+      "fn main() { foo(1); bar(2); foo(3); }",
+    )?;
+    let project = ProjectConfig::setup(Some(temp.join("sgconfig.yml")))?;
+    let arg = NewArg {
+      entity: Some(Entity::Rule),
+      name: Some("no-foo".into()),
+      lang: Some(SupportLang::Rust.into()),
+      pattern: Some("foo($A)".into()),
+      yes: true,
+    };
+    run_create_new(arg, project)?;
+    let rule = fs::read_to_string(temp.join("rules/no-foo.yml"))?;
+    assert!(rule.contains(r#"pattern: "foo($A)""#));
+    let test = fs::read_to_string(temp.join("rule-tests/no-foo-test.yml"))?;
+    let case: TestCase = serde_yaml::from_str(&test)?;
+    assert_eq!(case.id, "no-foo");
+    assert!(case.valid.is_empty());
+    assert_eq!(case.invalid, ["foo(1)", "foo(3)"]);
+    drop(dir); // drop at the end since temp dir clean up is done in Drop
+    Ok(())
+  }
+
   #[test]
   fn test_create_util() -> Result<()> {
     let dir = TempDir::new()?;
@@ -1,12 +1,14 @@
 use super::{Diff, Printer};
 use crate::lang::SgLang;
+use crate::utils::{fingerprint, fnv_hash, normalize_path};
 use ast_grep_config::{RuleConfig, Severity};
-use clap::ValueEnum;
 
 use anyhow::Result;
 use ast_grep_core::{NodeMatch as SgNodeMatch, StrDoc};
 use codespan_reporting::files::SimpleFile;
-use std::io::{Stdout, Write};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
 
 type NodeMatch<'a, L> = SgNodeMatch<'a, StrDoc<L>>;
 
@@ -21,25 +23,38 @@ macro_rules! Diffs {
   ($lt: lifetime) => { impl Iterator<Item = Diff<$lt>> };
 }
 
-#[derive(PartialEq, Eq, Clone, ValueEnum)]
-#[clap(rename_all = "lower")]
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub enum Platform {
+  /// GitHub Action workflow commands
   GitHub,
+  /// GitLab Code Quality report in JSON
+  GitLab,
+  /// Reviewdog Diagnostic Format in JSON
+  RdJson,
+  /// JUnit XML test report
+  JUnit,
 }
 
 pub struct CloudPrinter<W: Write> {
   writer: W,
+  platform: Platform,
+  // indicate if any issue has been written, JSON formats need it to separate items
+  matched: bool,
+  // occurrence count of each fingerprint seed, used to make GitLab fingerprints unique
+  seen: HashMap<u64, usize>,
+  // JUnit report needs total counts in the header, so test suites are written after scanning
+  suites: Vec<JUnitSuite>,
 }
 
 impl<W: Write> CloudPrinter<W> {
-  pub fn new(writer: W) -> Self {
-    Self { writer }
-  }
-}
-
-impl CloudPrinter<Stdout> {
-  pub fn stdout() -> Self {
-    Self::new(std::io::stdout())
+  pub fn new(writer: W, platform: Platform) -> Self {
+    Self {
+      writer,
+      platform,
+      matched: false,
+      seen: HashMap::new(),
+      suites: vec![],
+    }
   }
 }
 
@@ -47,7 +62,7 @@ impl<W: Write> Printer for CloudPrinter<W> {
   fn print_rule<'a>(
     &mut self,
     matches: Matches!('a),
-    file: SimpleFile<Cow<str>, &String>,
+    file: SimpleFile<Cow<str>, &str>,
     rule: &RuleConfig<SgLang>,
   ) -> Result<()> {
     let path = PathBuf::from(file.name().to_string());
@@ -68,8 +83,43 @@ impl<W: Write> Printer for CloudPrinter<W> {
     path: &Path,
   ) -> Result<()> {
     for (diff, rule) in diffs {
-      print_rule(self, std::iter::once(diff.node_match), path, rule)?;
+      if self.platform == Platform::RdJson {
+        let suggestion = RdSuggestion {
+          range: rd_range(diff.get_root_text(), &diff.range),
+          text: diff.replacement.to_string(),
+        };
+        let item = (diff.node_match, Some(suggestion));
+        print_rdjson(self, std::iter::once(item), path, rule)?;
+      } else {
+        print_rule(self, std::iter::once(diff.node_match), path, rule)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn before_print(&mut self) -> Result<()> {
+    match self.platform {
+      Platform::GitLab => write!(self.writer, "[")?,
+      Platform::RdJson => write!(
+        self.writer,
+        r#"{{"source":{{"name":"ast-grep","url":"https://ast-grep.github.io"}},"diagnostics":["#
+      )?,
+      Platform::GitHub | Platform::JUnit => (),
+    }
+    Ok(())
+  }
+
+  fn after_print(&mut self) -> Result<()> {
+    let end = match self.platform {
+      Platform::GitLab => "]",
+      Platform::RdJson => "]}",
+      Platform::JUnit => return write_junit(self),
+      Platform::GitHub => return Ok(()),
+    };
+    if self.matched {
+      writeln!(self.writer)?;
     }
+    writeln!(self.writer, "{end}")?;
     Ok(())
   }
 }
@@ -79,6 +129,20 @@ fn print_rule<'a, W: Write>(
   matches: Matches!('a),
   path: &Path,
   rule: &RuleConfig<SgLang>,
+) -> Result<()> {
+  match p.platform {
+    Platform::GitHub => print_github(p, matches, path, rule),
+    Platform::GitLab => print_gitlab(p, matches, path, rule),
+    Platform::RdJson => print_rdjson(p, matches.map(|m| (m, None)), path, rule),
+    Platform::JUnit => collect_junit(p, matches, path, rule),
+  }
+}
+
+fn print_github<'a, W: Write>(
+  p: &mut CloudPrinter<W>,
+  matches: Matches!('a),
+  path: &Path,
+  rule: &RuleConfig<SgLang>,
 ) -> Result<()> {
   let writer = &mut p.writer;
   let level = match rule.severity {
@@ -102,6 +166,279 @@ fn print_rule<'a, W: Write>(
   Ok(())
 }
 
+/// A single issue in GitLab's Code Quality report.
+/// See https://docs.gitlab.com/ee/ci/testing/code_quality.html#code-quality-report-format
+#[derive(Serialize)]
+struct CodeQualityIssue<'a> {
+  description: String,
+  check_name: &'a str,
+  fingerprint: String,
+  severity: &'static str,
+  location: Location,
+}
+
+#[derive(Serialize)]
+struct Location {
+  path: String,
+  lines: Lines,
+}
+
+/// One-based line range of the issue.
+#[derive(Serialize)]
+struct Lines {
+  begin: usize,
+  end: usize,
+}
+
+fn print_gitlab<'a, W: Write>(
+  p: &mut CloudPrinter<W>,
+  matches: Matches!('a),
+  path: &Path,
+  rule: &RuleConfig<SgLang>,
+) -> Result<()> {
+  let severity = match rule.severity {
+    Severity::Error => "major",
+    Severity::Warning => "minor",
+    Severity::Info => "info",
+    Severity::Hint => return Ok(()),
+    Severity::Off => unreachable!("turned-off rule should not have match."),
+  };
+  let name = normalize_path(path);
+  for m in matches {
+    let seed = fingerprint(&rule.id, &name, &m.text());
+    let occurrence = p.seen.entry(seed).or_default();
+    *occurrence += 1;
+    let fingerprint = fnv_hash(&[&seed.to_le_bytes(), &occurrence.to_le_bytes()]);
+    let issue = CodeQualityIssue {
+      description: rule.get_message(&m),
+      check_name: &rule.id,
+      fingerprint: format!("{fingerprint:016x}"),
+      severity,
+      location: Location {
+        path: name.clone(),
+        lines: Lines {
+          begin: m.start_pos().line() + 1,
+          end: m.end_pos().line() + 1,
+        },
+      },
+    };
+    let writer = &mut p.writer;
+    if p.matched {
+      write!(writer, ",")?;
+    }
+    p.matched = true;
+    writeln!(writer)?;
+    serde_json::to_writer(&mut *writer, &issue)?;
+  }
+  Ok(())
+}
+
+/// A diagnostic in reviewdog's rdjson format.
+/// See https://github.com/reviewdog/reviewdog/tree/master/proto/rdf
+#[derive(Serialize)]
+struct RdDiagnostic<'a> {
+  message: String,
+  location: RdLocation,
+  severity: &'static str,
+  code: RdCode<'a>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  suggestions: Vec<RdSuggestion>,
+}
+
+#[derive(Serialize)]
+struct RdLocation {
+  path: String,
+  range: RdRange,
+}
+
+#[derive(Serialize)]
+struct RdRange {
+  start: RdPosition,
+  end: RdPosition,
+}
+
+/// One-based line and column. Column counts UTF-8 bytes.
+#[derive(Serialize)]
+struct RdPosition {
+  line: usize,
+  column: usize,
+}
+
+#[derive(Serialize)]
+struct RdCode<'a> {
+  value: &'a str,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  url: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct RdSuggestion {
+  range: RdRange,
+  text: String,
+}
+
+fn rd_position(src: &str, offset: usize) -> RdPosition {
+  let before = &src[..offset];
+  let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+  RdPosition {
+    line: before.matches('\n').count() + 1,
+    column: offset - line_start + 1,
+  }
+}
+
+fn rd_range(src: &str, range: &std::ops::Range<usize>) -> RdRange {
+  RdRange {
+    start: rd_position(src, range.start),
+    end: rd_position(src, range.end),
+  }
+}
+
+fn print_rdjson<'a, W: Write>(
+  p: &mut CloudPrinter<W>,
+  matches: impl Iterator<Item = (NodeMatch<'a, SgLang>, Option<RdSuggestion>)>,
+  path: &Path,
+  rule: &RuleConfig<SgLang>,
+) -> Result<()> {
+  let severity = match rule.severity {
+    Severity::Error => "ERROR",
+    Severity::Warning => "WARNING",
+    Severity::Info => "INFO",
+    Severity::Hint => return Ok(()),
+    Severity::Off => unreachable!("turned-off rule should not have match."),
+  };
+  let name = normalize_path(path);
+  for (m, suggestion) in matches {
+    let diagnostic = RdDiagnostic {
+      message: rule.get_message(&m),
+      location: RdLocation {
+        path: name.clone(),
+        range: rd_range(m.root().get_text(), &m.range()),
+      },
+      severity,
+      code: RdCode {
+        value: &rule.id,
+        url: rule.url.as_deref(),
+      },
+      suggestions: suggestion.into_iter().collect(),
+    };
+    let writer = &mut p.writer;
+    if p.matched {
+      write!(writer, ",")?;
+    }
+    p.matched = true;
+    writeln!(writer)?;
+    serde_json::to_writer(&mut *writer, &diagnostic)?;
+  }
+  Ok(())
+}
+
+/// All test cases of one file, a test case is one rule with its findings as failures.
+struct JUnitSuite {
+  file: String,
+  cases: Vec<(String, Vec<JUnitFailure>)>,
+}
+
+struct JUnitFailure {
+  severity: &'static str,
+  message: String,
+  line: usize,
+  column: usize,
+}
+
+fn collect_junit<'a, W: Write>(
+  p: &mut CloudPrinter<W>,
+  matches: Matches!('a),
+  path: &Path,
+  rule: &RuleConfig<SgLang>,
+) -> Result<()> {
+  let severity = match rule.severity {
+    Severity::Error => "error",
+    Severity::Warning => "warning",
+    Severity::Info => "info",
+    Severity::Hint => return Ok(()),
+    Severity::Off => unreachable!("turned-off rule should not have match."),
+  };
+  let failures = matches.map(|m| {
+    let start = m.start_pos();
+    JUnitFailure {
+      severity,
+      message: rule.get_message(&m),
+      line: start.line() + 1,
+      column: start.column(&m) + 1,
+    }
+  });
+  let file = normalize_path(path);
+  if p.suites.last().map_or(true, |s| s.file != file) {
+    p.suites.push(JUnitSuite {
+      file,
+      cases: vec![],
+    });
+  }
+  let suite = p.suites.last_mut().expect("suite must exist");
+  // fixable and unfixable findings of the same rule are printed separately
+  if let Some((_, existing)) = suite.cases.iter_mut().find(|(id, _)| id == &rule.id) {
+    existing.extend(failures);
+  } else {
+    suite.cases.push((rule.id.clone(), failures.collect()));
+  }
+  Ok(())
+}
+
+fn escape_xml(text: &str) -> Cow<str> {
+  if !text.contains(['&', '<', '>', '"', '\'']) {
+    return Cow::Borrowed(text);
+  }
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      _ => escaped.push(c),
+    }
+  }
+  Cow::Owned(escaped)
+}
+
+fn write_junit<W: Write>(p: &mut CloudPrinter<W>) -> Result<()> {
+  let count_failures = |s: &JUnitSuite| s.cases.iter().map(|(_, f)| f.len()).sum::<usize>();
+  let tests: usize = p.suites.iter().map(|s| s.cases.len()).sum();
+  let failures: usize = p.suites.iter().map(count_failures).sum();
+  let w = &mut p.writer;
+  writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+  writeln!(
+    w,
+    r#"<testsuites name="ast-grep" tests="{tests}" failures="{failures}">"#
+  )?;
+  for suite in &p.suites {
+    let file = escape_xml(&suite.file);
+    let tests = suite.cases.len();
+    let failures = count_failures(suite);
+    writeln!(
+      w,
+      r#"  <testsuite name="{file}" tests="{tests}" failures="{failures}">"#
+    )?;
+    for (id, failures) in &suite.cases {
+      let id = escape_xml(id);
+      writeln!(w, r#"    <testcase name="{id}" classname="{file}">"#)?;
+      for f in failures {
+        let message = escape_xml(&f.message);
+        let (severity, line, column) = (f.severity, f.line, f.column);
+        writeln!(
+          w,
+          r#"      <failure message="{message}" type="{severity}">{file}:{line}:{column}: {message}</failure>"#
+        )?;
+      }
+      writeln!(w, "    </testcase>")?;
+    }
+    writeln!(w, "  </testsuite>")?;
+  }
+  writeln!(w, "</testsuites>")?;
+  Ok(())
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -110,7 +447,7 @@ mod test {
   use codespan_reporting::term::termcolor::Buffer;
 
   fn make_test_printer() -> CloudPrinter<Buffer> {
-    CloudPrinter::new(Buffer::no_color())
+    CloudPrinter::new(Buffer::no_color(), Platform::GitHub)
   }
   fn get_text(printer: &mut CloudPrinter<Buffer>) -> String {
     let buffer = &mut printer.writer;
@@ -121,12 +458,16 @@ mod test {
   }
 
   fn make_rule(rule: &str) -> RuleConfig<SgLang> {
+    make_rule_with_message(rule, "test rule")
+  }
+
+  fn make_rule_with_message(rule: &str, message: &str) -> RuleConfig<SgLang> {
     let globals = GlobalRules::default();
     from_yaml_string(
       &format!(
         r"
 id: test
-message: test rule
+message: {message}
 language: TypeScript
 {rule}"
       ),
@@ -138,13 +479,24 @@ language: TypeScript
   }
 
   fn test_output(src: &str, rule_str: &str, expect: &str) {
+    let printer = make_test_printer();
+    test_platform_output(printer, src, rule_str, expect);
+  }
+
+  fn test_platform_output(
+    mut printer: CloudPrinter<Buffer>,
+    src: &str,
+    rule_str: &str,
+    expect: &str,
+  ) {
     let src = src.to_owned();
-    let mut printer = make_test_printer();
     let grep = SgLang::from(SupportLang::Tsx).ast_grep(&src);
     let rule = make_rule(rule_str);
     let matches = grep.root().find_all(&rule.matcher);
-    let file = SimpleFile::new(Cow::Borrowed("test.tsx"), &src);
+    let file = SimpleFile::new(Cow::Borrowed("test.tsx"), src.as_str());
+    printer.before_print().unwrap();
     printer.print_rule(matches, file, &rule).unwrap();
+    printer.after_print().unwrap();
     let actual = get_text(&mut printer);
     assert_eq!(actual, expect);
   }
@@ -208,4 +560,184 @@ severity: error
       "::error file=test.tsx,line=1,endLine=1,title=test::test rule\n",
     );
   }
+
+  fn test_gitlab(src: &str, rule_str: &str, expect: &str) {
+    let printer = CloudPrinter::new(Buffer::no_color(), Platform::GitLab);
+    test_platform_output(printer, src, rule_str, expect);
+  }
+
+  #[test]
+  fn test_gitlab_no_match() {
+    test_gitlab("let a = 123", "rule: { pattern: console }", "[]\n");
+    test_gitlab(
+      "console.log(123)",
+      "
+rule: { pattern: console }
+severity: hint",
+      "[]\n",
+    );
+  }
+
+  #[test]
+  fn test_gitlab_output() {
+    let rule = "
+rule: { pattern: console }
+severity: error";
+    let mut printer = CloudPrinter::new(Buffer::no_color(), Platform::GitLab);
+    let src = "console.log(1)\nconsole.log(2)".to_string();
+    let grep = SgLang::from(SupportLang::Tsx).ast_grep(&src);
+    let rule = make_rule(rule);
+    let matches = grep.root().find_all(&rule.matcher);
+    let file = SimpleFile::new(Cow::Borrowed("test.tsx"), src.as_str());
+    printer.before_print().unwrap();
+    printer.print_rule(matches, file, &rule).unwrap();
+    printer.after_print().unwrap();
+    let actual = get_text(&mut printer);
+    let json: serde_json::Value = serde_json::from_str(&actual).expect("should be valid json");
+    let issues = json.as_array().expect("should be array");
+    assert_eq!(issues.len(), 2);
+    let issue = &issues[1];
+    assert_eq!(issue["description"], "test rule");
+    assert_eq!(issue["check_name"], "test");
+    assert_eq!(issue["severity"], "major");
+    assert_eq!(issue["location"]["path"], "test.tsx");
+    assert_eq!(issue["location"]["lines"]["begin"], 2);
+    assert_eq!(issue["location"]["lines"]["end"], 2);
+    // same text in the same file should still have unique fingerprints
+    assert_ne!(issues[0]["fingerprint"], issues[1]["fingerprint"]);
+  }
+
+  #[test]
+  fn test_gitlab_stable_fingerprint() {
+    let rule = "
+rule: { pattern: console }
+severity: warning";
+    let fingerprint = |src: &str| {
+      let mut printer = CloudPrinter::new(Buffer::no_color(), Platform::GitLab);
+      let src = src.to_string();
+      let grep = SgLang::from(SupportLang::Tsx).ast_grep(&src);
+      let rule = make_rule(rule);
+      let matches = grep.root().find_all(&rule.matcher);
+      let file = SimpleFile::new(Cow::Borrowed("test.tsx"), src.as_str());
+      printer.print_rule(matches, file, &rule).unwrap();
+      let text = get_text(&mut printer);
+      let json: serde_json::Value = serde_json::from_str(&text).expect("should be valid json");
+      json["fingerprint"].as_str().unwrap().to_string()
+    };
+    // fingerprint does not depend on line number
+    assert_eq!(
+      fingerprint("console.log(1)"),
+      fingerprint("\n\nconsole.log(1)")
+    );
+  }
+
+  #[test]
+  fn test_rdjson_output() {
+    let rule = "
+rule: { pattern: console }
+severity: warning
+url: https://example.com/rule";
+    let mut printer = CloudPrinter::new(Buffer::no_color(), Platform::RdJson);
+    let src = "let a = 1\n  console.log(2)".to_string();
+    let grep = SgLang::from(SupportLang::Tsx).ast_grep(&src);
+    let rule = make_rule(rule);
+    let matches = grep.root().find_all(&rule.matcher);
+    let file = SimpleFile::new(Cow::Borrowed("test.tsx"), src.as_str());
+    printer.before_print().unwrap();
+    printer.print_rule(matches, file, &rule).unwrap();
+    printer.after_print().unwrap();
+    let actual = get_text(&mut printer);
+    let json: serde_json::Value = serde_json::from_str(&actual).expect("should be valid json");
+    assert_eq!(json["source"]["name"], "ast-grep");
+    let diagnostics = json["diagnostics"].as_array().expect("should be array");
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic["message"], "test rule");
+    assert_eq!(diagnostic["severity"], "WARNING");
+    assert_eq!(diagnostic["code"]["value"], "test");
+    assert_eq!(diagnostic["code"]["url"], "https://example.com/rule");
+    assert_eq!(diagnostic["location"]["path"], "test.tsx");
+    let range = &diagnostic["location"]["range"];
+    assert_eq!(range["start"], serde_json::json!({"line": 2, "column": 3}));
+    assert_eq!(range["end"], serde_json::json!({"line": 2, "column": 10}));
+    assert!(diagnostic.get("suggestions").is_none());
+  }
+
+  #[test]
+  fn test_rdjson_suggestion() {
+    let rule = "
+rule: { pattern: console.log($A) }
+fix: alert($A)
+severity: error";
+    let mut printer = CloudPrinter::new(Buffer::no_color(), Platform::RdJson);
+    let src = "console.log(1)".to_string();
+    let rule = make_rule(rule);
+    let grep = rule.language.ast_grep(&src);
+    let diffs = grep
+      .root()
+      .find_all(&rule.matcher)
+      .map(|m| {
+        let fixer = rule.matcher.get_fixer(&m).expect("should have fix");
+        (Diff::generate(m, &rule.matcher, fixer), &rule)
+      })
+      .collect();
+    printer.before_print().unwrap();
+    printer
+      .print_rule_diffs(diffs, Path::new("test.tsx"))
+      .unwrap();
+    printer.after_print().unwrap();
+    let actual = get_text(&mut printer);
+    let json: serde_json::Value = serde_json::from_str(&actual).expect("should be valid json");
+    let diagnostic = &json["diagnostics"][0];
+    assert_eq!(diagnostic["severity"], "ERROR");
+    let suggestion = &diagnostic["suggestions"][0];
+    assert_eq!(suggestion["text"], "alert(1)");
+    assert_eq!(suggestion["range"]["start"]["column"], 1);
+    assert_eq!(suggestion["range"]["end"]["column"], 15);
+  }
+
+  #[test]
+  fn test_rdjson_no_match() {
+    let printer = CloudPrinter::new(Buffer::no_color(), Platform::RdJson);
+    let expect = "{\"source\":{\"name\":\"ast-grep\",\"url\":\"https://ast-grep.github.io\"},\"diagnostics\":[]}\n";
+    test_platform_output(printer, "let a = 123", "rule: { pattern: console }", expect);
+  }
+
+  #[test]
+  fn test_junit_output() {
+    let rule = "
+rule: { pattern: console.log($A) }
+severity: error";
+    let mut printer = CloudPrinter::new(Buffer::no_color(), Platform::JUnit);
+    let src = "console.log(1)\n  console.log(2)".to_string();
+    let rule = make_rule_with_message(rule, "no <console>");
+    let grep = rule.language.ast_grep(&src);
+    let matches = grep.root().find_all(&rule.matcher);
+    let file = SimpleFile::new(Cow::Borrowed("test.tsx"), src.as_str());
+    printer.before_print().unwrap();
+    printer.print_rule(matches, file, &rule).unwrap();
+    printer.after_print().unwrap();
+    let actual = get_text(&mut printer);
+    let expect = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="ast-grep" tests="1" failures="2">
+  <testsuite name="test.tsx" tests="1" failures="2">
+    <testcase name="test" classname="test.tsx">
+      <failure message="no &lt;console&gt;" type="error">test.tsx:1:1: no &lt;console&gt;</failure>
+      <failure message="no &lt;console&gt;" type="error">test.tsx:2:3: no &lt;console&gt;</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#;
+    assert_eq!(actual, expect);
+  }
+
+  #[test]
+  fn test_junit_no_match() {
+    let printer = CloudPrinter::new(Buffer::no_color(), Platform::JUnit);
+    let expect = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="ast-grep" tests="0" failures="0">
+</testsuites>
+"#;
+    test_platform_output(printer, "let a = 123", "rule: { pattern: console }", expect);
+  }
 }
@@ -1,6 +1,6 @@
 use super::{Diff, Printer};
 use crate::lang::SgLang;
-use ast_grep_config::{RuleConfig, Severity};
+use ast_grep_config::{LabelStyle, RuleConfig, Severity};
 use ast_grep_core::DisplayContext;
 
 use ansi_term::{Color, Style};
@@ -131,7 +131,7 @@ impl<W: WriteColor> Printer for ColoredPrinter<W> {
   fn print_rule<'a>(
     &mut self,
     matches: Matches!('a),
-    file: SimpleFile<Cow<str>, &String>,
+    file: SimpleFile<Cow<str>, &str>,
     rule: &RuleConfig<SgLang>,
   ) -> Result<()> {
     let config = &self.config;
@@ -146,7 +146,15 @@ impl<W: WriteColor> Printer for ColoredPrinter<W> {
     for m in matches {
       let range = m.range();
       let mut labels = vec![Label::primary((), range)];
-      if let Some(secondary_nodes) = m.get_env().get_labels("secondary") {
+      if rule.labels.is_some() {
+        labels.extend(rule.get_labels(&m).into_iter().map(|l| {
+          let label = match l.style {
+            LabelStyle::Primary => Label::primary((), l.range()),
+            LabelStyle::Secondary => Label::secondary((), l.range()),
+          };
+          label.with_message(l.message.unwrap_or_default())
+        }));
+      } else if let Some(secondary_nodes) = m.get_env().get_labels("secondary") {
         labels.extend(secondary_nodes.iter().map(|n| {
           let range = n.range();
           Label::secondary((), range)
@@ -204,6 +212,10 @@ impl<W: WriteColor> Printer for ColoredPrinter<W> {
         writeln!(writer, "{}", self.styles.rule.note.paint("Note:"))?;
         writeln!(writer, "{note}")?;
       }
+      if !diff.merged_rule_ids.is_empty() {
+        let title = self.styles.rule.note.paint("Same fix by:");
+        writeln!(writer, "{title} {}", diff.merged_rule_ids.join(", "))?;
+      }
     }
     Ok(())
   }
@@ -91,7 +91,7 @@ fn test_print_rules() {
       .style(ReportStyle::Short);
     let grep = SgLang::from(SupportLang::TypeScript).ast_grep(source);
     let source = source.to_string();
-    let file = SimpleFile::new(Cow::Borrowed("test.tsx"), &source);
+    let file = SimpleFile::new(Cow::Borrowed("test.tsx"), source.as_str());
     let rule = from_yaml_string(
       &format!(
         r"
@@ -238,7 +238,7 @@ fix: '{rewrite}'"
     .pop()
     .unwrap();
     let matcher = rule.get_matcher(&globals).expect("should parse");
-    let fixer = matcher.fixer.as_ref().expect("should have fixer");
+    let fixer = matcher.fixer.first().expect("should have fixer");
     let matches = grep.root().find_all(&matcher);
     let diffs = matches.map(|n| (Diff::generate(n, &pattern, fixer), &rule));
     printer
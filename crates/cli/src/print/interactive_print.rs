@@ -42,10 +42,16 @@ impl<P: Printer> InteractivePrinter<P> {
     }
   }
 
-  fn prompt_edit(&self) -> char {
+  fn prompt_edit(&self, can_switch: bool) -> char {
     if self.accept_all {
       return 'a';
     }
+    if can_switch {
+      const SWITCH_PROMPT: &str =
+        "Accept change? (Yes[y], No[n], Accept All[a], Quit[q], Edit[e], Switch Fix[s])";
+      return utils::prompt(SWITCH_PROMPT, "ynaqes", Some('n'))
+        .expect("Error happened during prompt");
+    }
     const EDIT_PROMPT: &str = "Accept change? (Yes[y], No[n], Accept All[a], Quit[q], Edit[e])";
     utils::prompt(EDIT_PROMPT, "ynaqe", Some('n')).expect("Error happened during prompt")
   }
@@ -76,7 +82,7 @@ impl<P: Printer> Printer for InteractivePrinter<P> {
   fn print_rule<'a>(
     &mut self,
     matches: Matches!('a),
-    file: SimpleFile<Cow<str>, &String>,
+    file: SimpleFile<Cow<str>, &str>,
     rule: &RuleConfig<SgLang>,
   ) -> Result<()> {
     utils::run_in_alternate_screen(|| {
@@ -153,13 +159,14 @@ fn print_diffs_interactive<'a>(
     if diff.range.start < end {
       continue;
     }
-    let confirm = all || {
-      let (accept_curr, accept_all) =
-        print_diff_and_prompt_action(interactive, path, (diff.clone(), rule))?;
+    let confirmed_diff = if all {
+      Some(diff)
+    } else {
+      let (accepted, accept_all) = print_diff_and_prompt_action(interactive, path, (diff, rule))?;
       all = accept_all;
-      accept_curr
+      accepted
     };
-    if confirm {
+    if let Some(diff) = confirmed_diff {
       end = diff.range.end;
       confirmed.push(diff);
       interactive.committed_cnt = interactive.committed_cnt.saturating_add(1);
@@ -167,32 +174,72 @@ fn print_diffs_interactive<'a>(
   }
   Ok((confirmed, all))
 }
-/// returns if accept_current and accept_all
-fn print_diff_and_prompt_action(
+
+/// generate diffs for every applicable fix of the rule with fix titles, the default fix comes first
+fn alternative_diffs<'a, 'r>(
+  diff: Diff<'a>,
+  rule: Option<&'r RuleConfig<SgLang>>,
+) -> Vec<(Diff<'a>, Option<&'r str>)> {
+  let Some(rule) = rule else {
+    return vec![(diff, None)];
+  };
+  let matcher = &rule.matcher;
+  let fixers: Vec<_> = matcher
+    .fixer
+    .iter()
+    .filter(|f| f.is_applicable(&diff.node_match))
+    .collect();
+  if fixers.len() <= 1 {
+    return vec![(diff, None)];
+  }
+  fixers
+    .into_iter()
+    .map(|f| {
+      (
+        Diff::generate(diff.node_match.clone(), matcher, f),
+        f.title(),
+      )
+    })
+    .collect()
+}
+
+/// returns the accepted diff, if any, and accept_all
+fn print_diff_and_prompt_action<'a>(
   interactive: &mut InteractivePrinter<impl Printer>,
   path: &Path,
-  (diff, rule): (Diff, Option<&RuleConfig<SgLang>>),
-) -> Result<(bool, bool)> {
-  utils::run_in_alternate_screen(|| {
-    let printer = &mut interactive.inner;
-    if let Some(rule) = rule {
-      printer.print_rule_diffs(vec![(diff.clone(), rule)], path)?;
-    } else {
-      printer.print_diffs(std::iter::once(diff.clone()), path)?;
-    }
-    match interactive.prompt_edit() {
-      'y' => Ok((true, false)),
-      'a' => Ok((true, true)),
+  (diff, rule): (Diff<'a>, Option<&RuleConfig<SgLang>>),
+) -> Result<(Option<Diff<'a>>, bool)> {
+  let mut alternatives = alternative_diffs(diff, rule);
+  let total = alternatives.len();
+  let mut index = 0;
+  loop {
+    let (diff, title) = &alternatives[index];
+    let resp = utils::run_in_alternate_screen(|| {
+      let printer = &mut interactive.inner;
+      if let Some(rule) = rule {
+        printer.print_rule_diffs(vec![(diff.clone(), rule)], path)?;
+      } else {
+        printer.print_diffs(std::iter::once(diff.clone()), path)?;
+      }
+      if total > 1 {
+        let title = title.unwrap_or_default();
+        println!("Fix {}/{total}: {title}", index + 1);
+      }
+      Ok(interactive.prompt_edit(total > 1))
+    })?;
+    match resp {
+      'y' => return Ok((Some(alternatives.swap_remove(index).0), false)),
+      'a' => return Ok((Some(alternatives.swap_remove(index).0), true)),
       'e' => {
         let pos = diff.node_match.start_pos().line();
         open_in_editor(path, pos)?;
-        Ok((false, false))
+        return Ok((None, false));
       }
-      'q' => Err(anyhow::anyhow!("Exit interactive editing")),
-      'n' => Ok((false, false)),
-      _ => Ok((false, false)),
+      'q' => return Err(anyhow::anyhow!("Exit interactive editing")),
+      's' => index = (index + 1) % total,
+      _ => return Ok((None, false)),
     }
-  })
+  }
 }
 
 fn print_matches_and_confirm_next<'a>(
@@ -305,7 +352,7 @@ rule:
 fix: ($B, lifecycle.update(['$A']))",
     );
     let mut matcher = config.matcher;
-    let fixer = matcher.fixer.take().unwrap();
+    let fixer = matcher.fixer.pop().unwrap();
     let diffs = make_diffs(&root, matcher, &fixer);
     let ret = apply_rewrite(diffs);
     assert_eq!(ret, "let a = () => (c++, lifecycle.update(['c']))");
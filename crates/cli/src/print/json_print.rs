@@ -1,5 +1,5 @@
 use crate::lang::SgLang;
-use ast_grep_config::{RuleConfig, Severity};
+use ast_grep_config::{LabelStyle, RuleConfig, Severity};
 use ast_grep_core::{meta_var::MetaVariable, Node as SgNode, NodeMatch as SgNodeMatch, StrDoc};
 
 type NodeMatch<'a, L> = SgNodeMatch<'a, StrDoc<L>>;
@@ -14,7 +14,7 @@ use codespan_reporting::files::SimpleFile;
 use serde::{Deserialize, Serialize};
 
 use std::borrow::Cow;
-use std::io::{Stdout, Write};
+use std::io::Write;
 use std::path::Path;
 
 // add this macro because neither trait_alias nor type_alias_impl is supported.
@@ -49,6 +49,10 @@ struct Range {
 struct LabelJSON<'a> {
   text: &'a str,
   range: Range,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  style: Option<LabelStyle>,
+  #[serde(skip_serializing_if = "Option::is_none", borrow)]
+  message: Option<&'a str>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -142,7 +146,7 @@ fn get_range(n: &Node<'_, SgLang>) -> Range {
   let start_pos = n.start_pos();
   let end_pos = n.end_pos();
   Range {
-    byte_offset: n.range(),
+    byte_offset: n.original_range(),
     start: Position {
       line: start_pos.line(),
       column: start_pos.column(n),
@@ -181,15 +185,34 @@ impl<'a> MatchJSON<'a> {
     ret
   }
 }
-fn get_labels<'a>(nm: &NodeMatch<'a, SgLang>) -> Option<Vec<MatchNode<'a>>> {
+fn get_labels<'a>(
+  nm: &NodeMatch<'a, SgLang>,
+  rule: &'a RuleConfig<SgLang>,
+) -> Option<Vec<LabelJSON<'a>>> {
+  if rule.labels.is_some() {
+    let labels = rule.get_labels(nm).into_iter().map(|l| {
+      let mut range = get_range(&l.start_node);
+      range.byte_offset = l.start_node.original_range().start..l.end_node.original_range().end;
+      range.end = get_range(&l.end_node).end;
+      LabelJSON {
+        text: &l.start_node.root().get_text()[l.range()],
+        range,
+        style: Some(l.style),
+        message: l.message,
+      }
+    });
+    return Some(labels.collect());
+  }
   let env = nm.get_env();
   let labels = env.get_labels("secondary")?;
   Some(
     labels
       .iter()
-      .map(|l| MatchNode {
-        text: l.text(),
+      .map(|l| LabelJSON {
+        text: &l.root().get_text()[l.range()],
         range: get_range(l),
+        style: None,
+        message: None,
       })
       .collect(),
   )
@@ -205,12 +228,15 @@ struct RuleMatchJSON<'a> {
   note: Option<String>,
   message: String,
   #[serde(skip_serializing_if = "Option::is_none")]
-  labels: Option<Vec<MatchNode<'a>>>,
+  labels: Option<Vec<LabelJSON<'a>>>,
+  /// ids of other rules making the same fix, see `scan --dedup-fixes`
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  merged_rule_ids: Vec<String>,
 }
 impl<'a> RuleMatchJSON<'a> {
   fn new(nm: NodeMatch<'a, SgLang>, path: &'a str, rule: &'a RuleConfig<SgLang>) -> Self {
     let message = rule.get_message(&nm);
-    let labels = get_labels(&nm);
+    let labels = get_labels(&nm, rule);
     let matched = MatchJSON::new(nm, path, (0, 0));
     Self {
       matched,
@@ -219,12 +245,14 @@ impl<'a> RuleMatchJSON<'a> {
       note: rule.note.clone(),
       message,
       labels,
+      merged_rule_ids: vec![],
     }
   }
-  fn diff(diff: Diff<'a>, path: &'a str, rule: &'a RuleConfig<SgLang>) -> Self {
+  fn diff(mut diff: Diff<'a>, path: &'a str, rule: &'a RuleConfig<SgLang>) -> Self {
     let nm = &diff.node_match;
     let message = rule.get_message(nm);
-    let labels = get_labels(nm);
+    let labels = get_labels(nm, rule);
+    let merged_rule_ids = std::mem::take(&mut diff.merged_rule_ids);
     let matched = MatchJSON::diff(diff, path, (0, 0));
     Self {
       matched,
@@ -233,6 +261,7 @@ impl<'a> RuleMatchJSON<'a> {
       note: rule.note.clone(),
       message,
       labels,
+      merged_rule_ids,
     }
   }
 }
@@ -259,11 +288,6 @@ pub struct JSONPrinter<W: Write> {
   // indicate if any matches happened
   matched: bool,
 }
-impl JSONPrinter<Stdout> {
-  pub fn stdout(style: JsonStyle) -> Self {
-    Self::new(std::io::stdout(), style)
-  }
-}
 
 impl<W: Write> JSONPrinter<W> {
   pub fn new(output: W, style: JsonStyle) -> Self {
@@ -328,7 +352,7 @@ impl<W: Write> Printer for JSONPrinter<W> {
   fn print_rule<'a>(
     &mut self,
     matches: Matches!('a),
-    file: SimpleFile<Cow<str>, &String>,
+    file: SimpleFile<Cow<str>, &str>,
     rule: &RuleConfig<SgLang>,
   ) -> Result<()> {
     let path = file.name();
@@ -514,7 +538,7 @@ rule:
       let rule = make_rule(pattern);
       let matches = grep.root().find_all(&rule.matcher);
       printer.before_print().unwrap();
-      let file = SimpleFile::new(Cow::Borrowed("test.ts"), &source);
+      let file = SimpleFile::new(Cow::Borrowed("test.ts"), source.as_str());
       printer.print_rule(matches, file, &rule).unwrap();
       printer.after_print().unwrap();
       let json_str = get_text(&printer);
@@ -604,4 +628,27 @@ transform:
     assert_eq!(metas.single["A"].text, "123");
     assert_eq!(metas.transformed["B"], "2");
   }
+
+  #[test]
+  fn test_rule_labels() {
+    let mut printer = make_test_printer(JsonStyle::Compact);
+    let rule = get_rule_config(
+      "pattern: console.log($A)\nlabels:\n  A: { style: secondary, message: the arg }",
+    );
+    let source = "console.log(123)".to_string();
+    let grep = SgLang::from(SupportLang::TypeScript).ast_grep(&source);
+    let matches = grep.root().find_all(&rule.matcher);
+    printer.before_print().unwrap();
+    let file = SimpleFile::new(Cow::Borrowed("test.ts"), source.as_str());
+    printer.print_rule(matches, file, &rule).unwrap();
+    printer.after_print().unwrap();
+    let json_str = get_text(&printer);
+    let json: Vec<RuleMatchJSON> = serde_json::from_str(&json_str).unwrap();
+    let labels = json[0].labels.as_ref().expect("should have labels");
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].text, "123");
+    assert_eq!(labels[0].range.byte_offset, 12..15);
+    assert_eq!(labels[0].style, Some(LabelStyle::Secondary));
+    assert_eq!(labels[0].message, Some("the arg"));
+  }
 }
@@ -2,6 +2,10 @@ mod cloud_print;
 mod colored_print;
 mod interactive_print;
 mod json_print;
+mod output_writer;
+mod patch_print;
+mod stats_print;
+mod summary_print;
 
 use crate::lang::SgLang;
 use ast_grep_config::{Fixer, RuleConfig};
@@ -19,6 +23,10 @@ pub use codespan_reporting::term::termcolor::ColorChoice;
 pub use colored_print::{print_diff, ColoredPrinter, Heading, PrintStyles, ReportStyle};
 pub use interactive_print::InteractivePrinter;
 pub use json_print::{JSONPrinter, JsonStyle};
+pub use output_writer::OutputWriter;
+pub use patch_print::{DiffFormat, PatchPrinter};
+pub use stats_print::StatsPrinter;
+pub use summary_print::SummaryPrinter;
 
 type NodeMatch<'a, L> = SgNodeMatch<'a, StrDoc<L>>;
 
@@ -34,7 +42,7 @@ pub trait Printer {
   fn print_rule<'a>(
     &mut self,
     matches: Matches!('a),
-    file: SimpleFile<Cow<str>, &String>,
+    file: SimpleFile<Cow<str>, &str>,
     rule: &RuleConfig<SgLang>,
   ) -> Result<()>;
   fn print_matches<'a>(&mut self, matches: Matches!('a), path: &Path) -> Result<()>;
@@ -63,6 +71,8 @@ pub struct Diff<'n> {
   /// string content for the replacement
   pub replacement: Cow<'n, str>,
   pub range: std::ops::Range<usize>,
+  /// ids of other rules making the same fix, merged into this one by `scan --dedup-fixes`
+  pub merged_rule_ids: Vec<String>,
 }
 
 impl<'n> Diff<'n> {
@@ -78,6 +88,7 @@ impl<'n> Diff<'n> {
       node_match,
       replacement,
       range: edit.position..edit.position + edit.deleted_length,
+      merged_rule_ids: vec![],
     }
   }
 
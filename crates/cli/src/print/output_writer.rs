@@ -0,0 +1,149 @@
+use crate::utils::ErrorContext as EC;
+
+use anyhow::{Context, Result};
+use codespan_reporting::term::termcolor::{ColorChoice, ColorSpec, StandardStream, WriteColor};
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// The destination of printers, either stdout or the file of `--output`.
+/// Colors are never written to the file.
+pub enum OutputWriter {
+  Stdout(StandardStream),
+  File(StripAnsi<BufWriter<File>>),
+}
+
+impl OutputWriter {
+  pub fn stdout(color: ColorChoice) -> Self {
+    Self::Stdout(StandardStream::stdout(color))
+  }
+
+  /// Create the file to write, including its parent directories.
+  pub fn file(path: &Path) -> Result<Self> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+      std::fs::create_dir_all(dir).with_context(|| EC::WriteFile(path.to_path_buf()))?;
+    }
+    let file = File::create(path).with_context(|| EC::WriteFile(path.to_path_buf()))?;
+    Ok(Self::File(StripAnsi::new(BufWriter::new(file))))
+  }
+}
+
+impl Write for OutputWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    match self {
+      Self::Stdout(s) => s.write(buf),
+      Self::File(f) => f.write(buf),
+    }
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    match self {
+      Self::Stdout(s) => s.flush(),
+      Self::File(f) => f.flush(),
+    }
+  }
+}
+
+impl WriteColor for OutputWriter {
+  fn supports_color(&self) -> bool {
+    match self {
+      Self::Stdout(s) => s.supports_color(),
+      Self::File(_) => false,
+    }
+  }
+
+  fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+    match self {
+      Self::Stdout(s) => s.set_color(spec),
+      Self::File(_) => Ok(()),
+    }
+  }
+
+  fn reset(&mut self) -> io::Result<()> {
+    match self {
+      Self::Stdout(s) => s.reset(),
+      Self::File(_) => Ok(()),
+    }
+  }
+}
+
+#[derive(Clone, Copy)]
+enum Escape {
+  None,
+  Start,
+  Csi,
+}
+
+/// Remove ANSI escape sequences from text styled without checking the color choice,
+/// like diffs and test reports.
+pub struct StripAnsi<W: Write> {
+  inner: W,
+  state: Escape,
+}
+
+impl<W: Write> StripAnsi<W> {
+  fn new(inner: W) -> Self {
+    Self {
+      inner,
+      state: Escape::None,
+    }
+  }
+}
+
+impl<W: Write> Write for StripAnsi<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let mut plain = Vec::with_capacity(buf.len());
+    for &b in buf {
+      // the state is kept across writes since a sequence can be split
+      self.state = match (self.state, b) {
+        (Escape::None, 0x1b) => Escape::Start,
+        (Escape::None, _) => {
+          plain.push(b);
+          Escape::None
+        }
+        (Escape::Start, b'[') => Escape::Csi,
+        (Escape::Start, _) => Escape::None,
+        (Escape::Csi, 0x40..=0x7e) => Escape::None,
+        (Escape::Csi, _) => Escape::Csi,
+      };
+    }
+    self.inner.write_all(&plain)?;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ansi_term::Color;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_strip_ansi() -> Result<()> {
+    let mut writer = StripAnsi::new(vec![]);
+    let painted = Color::Red.bold().paint("error").to_string();
+    let (head, tail) = painted.split_at(3);
+    write!(writer, "{head}")?;
+    write!(writer, "{tail}: [ok]")?;
+    assert_eq!(String::from_utf8(writer.inner)?, "error: [ok]");
+    Ok(())
+  }
+
+  #[test]
+  fn test_output_file() -> Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("reports/scan.txt");
+    let mut writer = OutputWriter::file(&path)?;
+    assert!(!writer.supports_color());
+    writer.set_color(ColorSpec::new().set_bold(true))?;
+    write!(writer, "{}", Color::Green.paint("pass"))?;
+    drop(writer);
+    assert_eq!(std::fs::read_to_string(path)?, "pass");
+    Ok(())
+  }
+}
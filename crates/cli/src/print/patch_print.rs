@@ -0,0 +1,171 @@
+use super::{Diff, Printer};
+use crate::lang::SgLang;
+use crate::utils::normalize_path;
+
+use anyhow::Result;
+use ast_grep_config::RuleConfig;
+use ast_grep_core::{NodeMatch as SgNodeMatch, StrDoc};
+use clap::ValueEnum;
+use codespan_reporting::files::SimpleFile;
+use similar::TextDiff;
+
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+
+type NodeMatch<'a, L> = SgNodeMatch<'a, StrDoc<L>>;
+
+// add this macro because neither trait_alias nor type_alias_impl is supported.
+macro_rules! Matches {
+  ($lt: lifetime) => { impl Iterator<Item = NodeMatch<$lt, SgLang>> };
+}
+macro_rules! Diffs {
+  ($lt: lifetime) => { impl Iterator<Item = Diff<$lt>> };
+}
+
+/// Output format of rewrites in `run`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+  /// Unified diff of rewrites, which can be applied by `git apply`.
+  Patch,
+}
+
+/// Print rewrites as a unified diff instead of modifying files.
+/// Matches without fix are not printed.
+pub struct PatchPrinter<W: Write> {
+  writer: W,
+}
+
+impl<W: Write> PatchPrinter<W> {
+  pub fn new(writer: W) -> Self {
+    Self { writer }
+  }
+
+  fn print_patch(&mut self, diffs: Vec<Diff<'_>>, path: &Path) -> Result<()> {
+    let Some(first) = diffs.first() else {
+      return Ok(());
+    };
+    let old = first.get_root_text();
+    let new = apply_diffs(old, diffs);
+    if old == new {
+      return Ok(());
+    }
+    let path = normalize_path(path.strip_prefix("./").unwrap_or(path));
+    let (old_name, new_name) = (format!("a/{path}"), format!("b/{path}"));
+    writeln!(self.writer, "diff --git {old_name} {new_name}")?;
+    let diff = TextDiff::from_lines(old, &new);
+    let unified = diff.unified_diff().header(&old_name, &new_name).to_string();
+    write!(self.writer, "{unified}")?;
+    Ok(())
+  }
+}
+
+/// Sort items by the position of their diffs and drop those overlapping with a previous one,
+/// the same as how fixes are applied to files.
+pub(super) fn non_overlapping<T>(mut items: Vec<T>, diff: impl Fn(&T) -> &Diff<'_>) -> Vec<T> {
+  items.sort_by_key(|item| diff(item).range.start);
+  let mut end = 0;
+  items.retain(|item| {
+    let range = &diff(item).range;
+    let keep = range.start >= end;
+    if keep {
+      end = range.end;
+    }
+    keep
+  });
+  items
+}
+
+/// Apply diffs in order of position. Diffs overlapping with a previous one are skipped.
+fn apply_diffs(old: &str, diffs: Vec<Diff<'_>>) -> String {
+  let mut new = String::new();
+  let mut start = 0;
+  for diff in non_overlapping(diffs, |d| d) {
+    new.push_str(&old[start..diff.range.start]);
+    new.push_str(&diff.replacement);
+    start = diff.range.end;
+  }
+  new.push_str(&old[start..]);
+  new
+}
+
+impl<W: Write> Printer for PatchPrinter<W> {
+  fn print_rule<'a>(
+    &mut self,
+    _matches: Matches!('a),
+    _file: SimpleFile<Cow<str>, &str>,
+    _rule: &RuleConfig<SgLang>,
+  ) -> Result<()> {
+    Ok(())
+  }
+
+  fn print_matches<'a>(&mut self, _matches: Matches!('a), _path: &Path) -> Result<()> {
+    Ok(())
+  }
+
+  fn print_diffs<'a>(&mut self, diffs: Diffs!('a), path: &Path) -> Result<()> {
+    self.print_patch(diffs.collect(), path)
+  }
+
+  fn print_rule_diffs(
+    &mut self,
+    diffs: Vec<(Diff<'_>, &RuleConfig<SgLang>)>,
+    path: &Path,
+  ) -> Result<()> {
+    let diffs = diffs.into_iter().map(|(diff, _)| diff).collect();
+    self.print_patch(diffs, path)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_config::Fixer;
+  use ast_grep_core::AstGrep;
+  use ast_grep_language::SupportLang;
+
+  fn get_patch(src: &str, pattern: &str, fix: &str) -> String {
+    let lang = SgLang::from(SupportLang::TypeScript);
+    let grep = AstGrep::new(src, lang);
+    let fixer = Fixer::from_str(fix, &lang).expect("should parse");
+    let pattern = ast_grep_core::Pattern::new(pattern, lang);
+    let diffs = grep
+      .root()
+      .find_all(&pattern)
+      .map(|nm| Diff::generate(nm, &pattern, &fixer));
+    let mut printer = PatchPrinter::new(vec![]);
+    printer
+      .print_diffs(diffs, Path::new("./src/a.ts"))
+      .expect("should print");
+    String::from_utf8(printer.writer).expect("should be utf8")
+  }
+
+  #[test]
+  fn test_print_patch() {
+    let src = "let a = 1\nfoo(a)\nlet b = 2\n";
+    let patch = get_patch(src, "foo($A)", "bar($A)");
+    let expected = "\
+diff --git a/src/a.ts b/src/a.ts
+--- a/src/a.ts
++++ b/src/a.ts
+@@ -1,3 +1,3 @@
+ let a = 1
+-foo(a)
++bar(a)
+ let b = 2
+";
+    assert_eq!(patch, expected);
+  }
+
+  #[test]
+  fn test_no_change() {
+    assert_eq!(get_patch("foo(a)", "foo($A)", "foo($A)"), "");
+    assert_eq!(get_patch("bar(a)", "foo($A)", "baz($A)"), "");
+  }
+
+  #[test]
+  fn test_missing_newline() {
+    let patch = get_patch("foo(a)", "foo($A)", "bar($A)");
+    assert!(patch.contains("-foo(a)\n\\ No newline at end of file\n+bar(a)"));
+  }
+}
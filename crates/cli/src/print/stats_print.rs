@@ -0,0 +1,186 @@
+use super::patch_print::non_overlapping;
+use super::{Diff, Printer};
+use crate::lang::SgLang;
+
+use anyhow::Result;
+use ast_grep_config::RuleConfig;
+use ast_grep_core::{NodeMatch as SgNodeMatch, StrDoc};
+use codespan_reporting::files::SimpleFile;
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+type NodeMatch<'a, L> = SgNodeMatch<'a, StrDoc<L>>;
+
+// add this macro because neither trait_alias nor type_alias_impl is supported.
+macro_rules! Matches {
+  ($lt: lifetime) => { impl Iterator<Item = NodeMatch<$lt, SgLang>> };
+}
+macro_rules! Diffs {
+  ($lt: lifetime) => { impl Iterator<Item = Diff<$lt>> };
+}
+
+#[derive(Default)]
+struct RuleStats {
+  edits: usize,
+  files: usize,
+}
+
+/// Print statistics of rewrites instead of modifying files, used by `--stats`.
+pub struct StatsPrinter<W: Write> {
+  writer: W,
+  files: usize,
+  edits: usize,
+  deleted_bytes: usize,
+  inserted_bytes: usize,
+  rules: BTreeMap<String, RuleStats>,
+}
+
+impl<W: Write> StatsPrinter<W> {
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      files: 0,
+      edits: 0,
+      deleted_bytes: 0,
+      inserted_bytes: 0,
+      rules: BTreeMap::new(),
+    }
+  }
+
+  /// Record edits in one file. Rule is None for rewrites of `run`.
+  fn record(&mut self, diffs: Vec<(Diff<'_>, Option<&RuleConfig<SgLang>>)>) {
+    let edits_before = self.edits;
+    let diffs = non_overlapping(diffs, |(diff, _)| diff);
+    let mut rules_in_file = vec![];
+    for (diff, rule) in diffs {
+      let old = &diff.get_root_text()[diff.range.clone()];
+      if old == diff.replacement {
+        continue;
+      }
+      self.edits += 1;
+      self.deleted_bytes += old.len();
+      self.inserted_bytes += diff.replacement.len();
+      let Some(rule) = rule else {
+        continue;
+      };
+      let stats = self.rules.entry(rule.id.clone()).or_default();
+      stats.edits += 1;
+      if !rules_in_file.contains(&&rule.id) {
+        stats.files += 1;
+        rules_in_file.push(&rule.id);
+      }
+    }
+    if edits_before != self.edits {
+      self.files += 1;
+    }
+  }
+}
+
+impl<W: Write> Printer for StatsPrinter<W> {
+  fn print_rule<'a>(
+    &mut self,
+    _matches: Matches!('a),
+    _file: SimpleFile<Cow<str>, &str>,
+    _rule: &RuleConfig<SgLang>,
+  ) -> Result<()> {
+    Ok(())
+  }
+
+  fn print_matches<'a>(&mut self, _matches: Matches!('a), _path: &Path) -> Result<()> {
+    Ok(())
+  }
+
+  fn print_diffs<'a>(&mut self, diffs: Diffs!('a), _path: &Path) -> Result<()> {
+    self.record(diffs.map(|diff| (diff, None)).collect());
+    Ok(())
+  }
+
+  fn print_rule_diffs(
+    &mut self,
+    diffs: Vec<(Diff<'_>, &RuleConfig<SgLang>)>,
+    _path: &Path,
+  ) -> Result<()> {
+    self.record(diffs.into_iter().map(|(d, r)| (d, Some(r))).collect());
+    Ok(())
+  }
+
+  fn after_print(&mut self) -> Result<()> {
+    let writer = &mut self.writer;
+    writeln!(
+      writer,
+      "{} file(s) would be modified by {} edit(s), {} byte(s) deleted and {} byte(s) inserted.",
+      self.files, self.edits, self.deleted_bytes, self.inserted_bytes
+    )?;
+    if self.rules.is_empty() {
+      return Ok(());
+    }
+    let width = self.rules.keys().map(|id| id.len()).max().unwrap_or(0);
+    let width = width.max("RULE".len());
+    writeln!(writer)?;
+    writeln!(writer, "{:width$}  EDITS  FILES", "RULE")?;
+    for (id, stats) in &self.rules {
+      writeln!(
+        writer,
+        "{id:width$}  {:>5}  {:>5}",
+        stats.edits, stats.files
+      )?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+  use ast_grep_core::AstGrep;
+  use ast_grep_language::SupportLang;
+
+  fn make_rule(id: &str, pattern: &str, fix: &str) -> RuleConfig<SgLang> {
+    let yaml =
+      format!("id: {id}\nlanguage: TypeScript\nrule: {{pattern: '{pattern}'}}\nfix: '{fix}'");
+    from_yaml_string(&yaml, &GlobalRules::default())
+      .expect("should parse")
+      .pop()
+      .expect("should have rule")
+  }
+
+  fn rule_diffs<'a>(
+    grep: &'a AstGrep<StrDoc<SgLang>>,
+    rule: &'a RuleConfig<SgLang>,
+  ) -> Vec<(Diff<'a>, &'a RuleConfig<SgLang>)> {
+    let fixer = rule.matcher.fixer.first().expect("should have fix");
+    grep
+      .root()
+      .find_all(&rule.matcher)
+      .map(|nm| (Diff::generate(nm, &rule.matcher, fixer), rule))
+      .collect()
+  }
+
+  #[test]
+  fn test_rewrite_stats() -> Result<()> {
+    let lang = SgLang::from(SupportLang::TypeScript);
+    let a = make_rule("to-bar", "foo($A)", "bar($A)");
+    let b = make_rule("same", "let a = 1", "let a = 1");
+    let mut printer = StatsPrinter::new(vec![]);
+    let grep = AstGrep::new("foo(1); foo(2); let a = 1", lang);
+    let mut diffs = rule_diffs(&grep, &a);
+    diffs.extend(rule_diffs(&grep, &b));
+    printer.print_rule_diffs(diffs, Path::new("a.ts"))?;
+    let grep = AstGrep::new("foo(3)", lang);
+    printer.print_rule_diffs(rule_diffs(&grep, &a), Path::new("b.ts"))?;
+    let grep = AstGrep::new("let a = 1", lang);
+    printer.print_rule_diffs(rule_diffs(&grep, &b), Path::new("c.ts"))?;
+    printer.after_print()?;
+    let output = String::from_utf8(printer.writer)?;
+    assert!(output.starts_with(
+      "2 file(s) would be modified by 3 edit(s), 18 byte(s) deleted and 18 byte(s) inserted."
+    ));
+    assert!(output.contains("RULE    EDITS  FILES\nto-bar      3      2\n"));
+    assert!(!output.contains("same"));
+    Ok(())
+  }
+}
@@ -0,0 +1,230 @@
+use super::{Diff, Printer};
+use crate::lang::SgLang;
+
+use anyhow::Result;
+use ast_grep_config::{RuleConfig, Severity};
+use ast_grep_core::{NodeMatch as SgNodeMatch, StrDoc};
+use codespan_reporting::files::SimpleFile;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+type NodeMatch<'a, L> = SgNodeMatch<'a, StrDoc<L>>;
+
+// add this macro because neither trait_alias nor type_alias_impl is supported.
+macro_rules! Matches {
+  ($lt: lifetime) => { impl Iterator<Item = NodeMatch<$lt, SgLang>> };
+}
+macro_rules! Diffs {
+  ($lt: lifetime) => { impl Iterator<Item = Diff<$lt>> };
+}
+
+#[derive(Default)]
+struct RuleSummary {
+  findings: usize,
+  files: HashSet<PathBuf>,
+}
+
+/// Print counts of findings instead of every finding, used by `--summary`.
+pub struct SummaryPrinter<W: Write> {
+  writer: W,
+  by_dir: bool,
+  files: HashSet<PathBuf>,
+  findings: usize,
+  rules: BTreeMap<String, RuleSummary>,
+  /// counts indexed by the order of SEVERITIES
+  severities: [usize; 4],
+  dirs: BTreeMap<PathBuf, usize>,
+}
+
+/// severities listed from the most severe, Off rules never report findings
+const SEVERITIES: [&str; 4] = ["error", "warning", "info", "hint"];
+
+fn severity_index(severity: &Severity) -> Option<usize> {
+  match severity {
+    Severity::Error => Some(0),
+    Severity::Warning => Some(1),
+    Severity::Info => Some(2),
+    Severity::Hint => Some(3),
+    Severity::Off => None,
+  }
+}
+
+impl<W: Write> SummaryPrinter<W> {
+  pub fn new(writer: W, by_dir: bool) -> Self {
+    Self {
+      writer,
+      by_dir,
+      files: HashSet::new(),
+      findings: 0,
+      rules: BTreeMap::new(),
+      severities: [0; 4],
+      dirs: BTreeMap::new(),
+    }
+  }
+
+  /// Record findings in one file. Rule is None for matches of `run`.
+  fn record(&mut self, count: usize, path: &Path, rule: Option<&RuleConfig<SgLang>>) {
+    if count == 0 {
+      return;
+    }
+    self.findings += count;
+    self.files.insert(path.to_path_buf());
+    if self.by_dir {
+      let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+      };
+      *self.dirs.entry(dir).or_default() += count;
+    }
+    let Some(rule) = rule else {
+      return;
+    };
+    if let Some(idx) = severity_index(&rule.severity) {
+      self.severities[idx] += count;
+    }
+    let summary = self.rules.entry(rule.id.clone()).or_default();
+    summary.findings += count;
+    summary.files.insert(path.to_path_buf());
+  }
+}
+
+impl<W: Write> Printer for SummaryPrinter<W> {
+  fn print_rule<'a>(
+    &mut self,
+    matches: Matches!('a),
+    file: SimpleFile<Cow<str>, &str>,
+    rule: &RuleConfig<SgLang>,
+  ) -> Result<()> {
+    let name: &str = file.name();
+    self.record(matches.count(), Path::new(name), Some(rule));
+    Ok(())
+  }
+
+  fn print_matches<'a>(&mut self, matches: Matches!('a), path: &Path) -> Result<()> {
+    self.record(matches.count(), path, None);
+    Ok(())
+  }
+
+  fn print_diffs<'a>(&mut self, diffs: Diffs!('a), path: &Path) -> Result<()> {
+    self.record(diffs.count(), path, None);
+    Ok(())
+  }
+
+  fn print_rule_diffs(
+    &mut self,
+    diffs: Vec<(Diff<'_>, &RuleConfig<SgLang>)>,
+    path: &Path,
+  ) -> Result<()> {
+    let mut counts: BTreeMap<&str, (usize, &RuleConfig<SgLang>)> = BTreeMap::new();
+    for (_, rule) in diffs {
+      counts.entry(rule.id.as_str()).or_insert((0, rule)).0 += 1;
+    }
+    for (count, rule) in counts.into_values() {
+      self.record(count, path, Some(rule));
+    }
+    Ok(())
+  }
+
+  fn after_print(&mut self) -> Result<()> {
+    let writer = &mut self.writer;
+    writeln!(
+      writer,
+      "{} finding(s) in {} file(s).",
+      self.findings,
+      self.files.len()
+    )?;
+    if !self.rules.is_empty() {
+      writeln!(writer)?;
+      writeln!(writer, "SEVERITY  COUNT")?;
+      for (name, count) in SEVERITIES.iter().zip(self.severities) {
+        if count > 0 {
+          writeln!(writer, "{name:8}  {count:>5}")?;
+        }
+      }
+      let width = self.rules.keys().map(|id| id.len()).max().unwrap_or(0);
+      let width = width.max("RULE".len());
+      writeln!(writer)?;
+      writeln!(writer, "{:width$}  COUNT  FILES", "RULE")?;
+      for (id, summary) in &self.rules {
+        writeln!(
+          writer,
+          "{id:width$}  {:>5}  {:>5}",
+          summary.findings,
+          summary.files.len()
+        )?;
+      }
+    }
+    if !self.dirs.is_empty() {
+      let dirs: Vec<_> = self
+        .dirs
+        .iter()
+        .map(|(dir, count)| (dir.display().to_string(), count))
+        .collect();
+      let width = dirs.iter().map(|(dir, _)| dir.len()).max().unwrap_or(0);
+      let width = width.max("DIRECTORY".len());
+      writeln!(writer)?;
+      writeln!(writer, "{:width$}  COUNT", "DIRECTORY")?;
+      for (dir, count) in dirs {
+        writeln!(writer, "{dir:width$}  {count:>5}")?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+  use ast_grep_core::AstGrep;
+  use ast_grep_language::SupportLang;
+
+  fn make_rule(id: &str, severity: &str) -> RuleConfig<SgLang> {
+    let yaml =
+      format!("id: {id}\nlanguage: TypeScript\nseverity: {severity}\nrule: {{pattern: 'foo($A)'}}");
+    from_yaml_string(&yaml, &GlobalRules::default())
+      .expect("should parse")
+      .pop()
+      .expect("should have rule")
+  }
+
+  fn print(
+    printer: &mut SummaryPrinter<Vec<u8>>,
+    rule: &RuleConfig<SgLang>,
+    path: &str,
+    src: &str,
+  ) {
+    let lang = SgLang::from(SupportLang::TypeScript);
+    let grep = AstGrep::new(src, lang);
+    let matches = grep.root().find_all(&rule.matcher);
+    let src = src.to_string();
+    let file = SimpleFile::new(Cow::Borrowed(path), src.as_str());
+    printer
+      .print_rule(matches, file, rule)
+      .expect("should print");
+  }
+
+  #[test]
+  fn test_summary() -> Result<()> {
+    let a = make_rule("no-foo", "error");
+    let b = make_rule("warn-foo", "warning");
+    let mut printer = SummaryPrinter::new(vec![], true);
+    print(&mut printer, &a, "src/a.ts", "foo(1); foo(2)");
+    print(&mut printer, &a, "src/b.ts", "foo(3)");
+    print(&mut printer, &b, "lib/c.ts", "foo(4)");
+    print(&mut printer, &b, "d.ts", "bar()");
+    printer.after_print()?;
+    let output = String::from_utf8(printer.writer)?;
+    assert!(output.starts_with("4 finding(s) in 3 file(s).\n"));
+    assert!(output.contains("SEVERITY  COUNT\nerror         3\nwarning       1\n"));
+    assert!(
+      output.contains("RULE      COUNT  FILES\nno-foo        3      2\nwarn-foo      1      1\n")
+    );
+    assert!(output.contains("DIRECTORY  COUNT\nlib            1\nsrc            3\n"));
+    assert!(!output.contains("hint"));
+    Ok(())
+  }
+}
@@ -1,18 +1,24 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use ast_grep_config::Fixer;
-use ast_grep_core::{MatchStrictness, Matcher, Pattern};
+use ast_grep_config::{Fixer, GlobalRules, RuleConfig, RuleCore, SerializableRuleConfig};
+use ast_grep_core::{AstGrep, Matcher, Pattern, StrDoc};
 use ast_grep_language::Language;
-use clap::{builder::PossibleValue, Parser, ValueEnum};
+use clap::{Parser, ValueEnum};
 use ignore::WalkParallel;
 
 use crate::config::ProjectConfig;
 use crate::lang::SgLang;
-use crate::print::{ColoredPrinter, Diff, Heading, InteractivePrinter, JSONPrinter, Printer};
+use crate::print::{
+  ColoredPrinter, Diff, DiffFormat, Heading, InteractivePrinter, JSONPrinter, PatchPrinter,
+  Printer, StatsPrinter,
+};
 use crate::utils::ErrorContext as EC;
-use crate::utils::{filter_file_pattern, ContextArgs, InputArgs, MatchUnit, OutputArgs};
-use crate::utils::{DebugFormat, FileTrace, RunTrace};
+use crate::utils::{explain_mismatch, DebugFormat, FileTrace, RunTrace};
+use crate::utils::{
+  filter_file_pattern, ContextArgs, FileLimits, InputArgs, MatchUnit, OutputArgs, Strictness,
+};
 use crate::utils::{Items, PathWorker, StdInWorker, Worker};
 
 fn lang_help() -> String {
@@ -24,33 +30,6 @@ fn lang_help() -> String {
 
 const LANG_HELP_LONG: &str = "The language of the pattern. For full language list, visit https://ast-grep.github.io/reference/languages.html";
 
-#[derive(Clone)]
-struct Strictness(MatchStrictness);
-impl ValueEnum for Strictness {
-  fn value_variants<'a>() -> &'a [Self] {
-    use MatchStrictness as M;
-    &[
-      Strictness(M::Cst),
-      Strictness(M::Smart),
-      Strictness(M::Ast),
-      Strictness(M::Relaxed),
-      Strictness(M::Signature),
-    ]
-  }
-  fn to_possible_value(&self) -> Option<PossibleValue> {
-    use MatchStrictness as M;
-    Some(match &self.0 {
-      M::Cst => PossibleValue::new("cst").help("Match exact all node"),
-      M::Smart => PossibleValue::new("smart").help("Match all node except source trivial nodes"),
-      M::Ast => PossibleValue::new("ast").help("Match only ast nodes"),
-      M::Relaxed => PossibleValue::new("relaxed").help("Match ast node except comments"),
-      M::Signature => {
-        PossibleValue::new("signature").help("Match ast node except comments, without text")
-      }
-    })
-  }
-}
-
 #[derive(Parser)]
 pub struct RunArg {
   // search pattern related options
@@ -69,6 +48,15 @@ pub struct RunArg {
   #[clap(short, long, value_name = "FIX", required_if_eq("update_all", "true"))]
   rewrite: Option<String>,
 
+  /// YAML config of `transform` and `rewriters` used by the rewrite.
+  ///
+  /// REWRITE_CONFIG can be a YAML string or a path to a YAML file. It allows one-off rewrites
+  /// to use transformations like `convert`, `substring` and `rewrite` without a project.
+  /// The config can also provide `fix`, which is overridden by `--rewrite`. Requires lang be set explicitly.
+  /// See https://ast-grep.github.io/reference/yaml/transformation.html.
+  #[clap(long, value_name = "REWRITE_CONFIG", requires = "lang")]
+  rewrite_config: Option<String>,
+
   /// The language of the pattern query.
   #[clap(short, long, help(lang_help()), long_help=LANG_HELP_LONG)]
   lang: Option<SgLang>,
@@ -88,6 +76,13 @@ pub struct RunArg {
   #[clap(long)]
   strictness: Option<Strictness>,
 
+  /// Skip ERROR and missing nodes in the searched code when matching.
+  ///
+  /// Use it when tree-sitter fails to parse part of the code, e.g. macros or partial code,
+  /// and the error recovery hides legitimate matches.
+  #[clap(long)]
+  allow_error_nodes: bool,
+
   /// input related options
   #[clap(flatten)]
   input: InputArgs,
@@ -108,6 +103,31 @@ pub struct RunArg {
   /// and to disable heading when piping to another program or redirected to files.
   #[clap(long, default_value = "auto", value_name = "WHEN")]
   heading: Heading,
+
+  /// Print rewrites in the FORMAT instead of modifying files.
+  ///
+  /// `patch` prints a unified diff that `git apply` accepts. Matches without rewrite are not printed.
+  #[clap(
+    long,
+    value_name = "FORMAT",
+    conflicts_with = "json",
+    conflicts_with = "interactive",
+    conflicts_with = "update_all"
+  )]
+  format: Option<DiffFormat>,
+
+  /// Print statistics of rewrites instead of modifying files.
+  ///
+  /// It reports how many files would be modified, the number of edits and bytes changed.
+  #[clap(
+    long,
+    requires = "rewrite",
+    conflicts_with = "json",
+    conflicts_with = "interactive",
+    conflicts_with = "update_all",
+    conflicts_with = "format"
+  )]
+  stats: bool,
 }
 
 impl RunArg {
@@ -117,7 +137,8 @@ impl RunArg {
     } else {
       Pattern::try_new(&self.pattern, lang)
     }
-    .context(EC::ParsePattern)?;
+    .context(EC::ParsePattern)?
+    .with_error_nodes_allowed(self.allow_error_nodes);
     if let Some(strictness) = &self.strictness {
       Ok(pattern.with_strictness(strictness.0.clone()))
     } else {
@@ -125,13 +146,53 @@ impl RunArg {
     }
   }
 
+  /// Build a rule from pattern and rewrite config so that transforms are applied in matching.
+  fn build_rewrite_rule(&self, lang: SgLang) -> Result<Option<RuleCore<SgLang>>> {
+    use serde_yaml::{Mapping, Value};
+    let Some(config) = &self.rewrite_config else {
+      return Ok(None);
+    };
+    let path = Path::new(config);
+    let (source, text) = if path.is_file() {
+      let text = std::fs::read_to_string(path).with_context(|| EC::ReadRule(path.into()))?;
+      (path.to_path_buf(), text)
+    } else {
+      (PathBuf::from("REWRITE_CONFIG"), config.clone())
+    };
+    let parse_error = || EC::ParseRule(source.clone());
+    let mut yaml: Mapping = serde_yaml::from_str(&text).with_context(parse_error)?;
+    let mut pattern = Mapping::new();
+    pattern.insert("context".into(), self.pattern.clone().into());
+    if let Some(selector) = &self.selector {
+      pattern.insert("selector".into(), selector.clone().into());
+    }
+    if let Some(strictness) = self.strictness.as_ref().and_then(|s| s.to_possible_value()) {
+      pattern.insert("strictness".into(), strictness.get_name().into());
+    }
+    if self.allow_error_nodes {
+      pattern.insert("allowErrorNodes".into(), true.into());
+    }
+    let mut rule = Mapping::new();
+    rule.insert("pattern".into(), Value::Mapping(pattern));
+    yaml.insert("id".into(), "rewrite-config".into());
+    yaml.insert("language".into(), serde_yaml::to_value(lang)?);
+    yaml.insert("rule".into(), Value::Mapping(rule));
+    if let Some(rewrite) = &self.rewrite {
+      yaml.insert("fix".into(), rewrite.clone().into());
+    }
+    let config: SerializableRuleConfig<SgLang> =
+      serde_yaml::from_value(Value::Mapping(yaml)).with_context(parse_error)?;
+    let rule = RuleConfig::try_from(config, &GlobalRules::default()).with_context(parse_error)?;
+    Ok(Some(rule.matcher))
+  }
+
   // do not unwrap pattern here, we should allow non-pattern to be debugged as tree
   fn debug_pattern_if_needed(&self, pattern_ret: &Result<Pattern<SgLang>>, lang: SgLang) {
     let Some(debug_query) = &self.debug_query else {
       return;
     };
     let colored = self.output.color.should_use_color();
-    if !matches!(debug_query, DebugFormat::Pattern) {
+    if !matches!(debug_query, DebugFormat::Pattern | DebugFormat::Match) {
       debug_query.debug_tree(&self.pattern, lang, colored);
     } else if let Ok(pattern) = pattern_ret {
       debug_query.debug_pattern(pattern, lang, colored);
@@ -144,38 +205,59 @@ impl RunArg {
 pub fn run_with_pattern(arg: RunArg, project: Result<ProjectConfig>) -> Result<()> {
   let proj = arg.output.inspect.project_trace();
   proj.print_project(&project)?;
+  let limits = arg.input.file_limits(project.as_ref().ok());
   let context = arg.context.get();
+  let writer = arg.output.writer()?;
+  if arg.stats {
+    return run_pattern_with_printer(arg, StatsPrinter::new(writer), limits);
+  }
+  if arg.format == Some(DiffFormat::Patch) {
+    return run_pattern_with_printer(arg, PatchPrinter::new(writer), limits);
+  }
   if let Some(json) = arg.output.json {
-    let printer = JSONPrinter::stdout(json).context(context);
-    return run_pattern_with_printer(arg, printer);
+    let printer = JSONPrinter::new(writer, json).context(context);
+    return run_pattern_with_printer(arg, printer, limits);
   }
-  let printer = ColoredPrinter::stdout(arg.output.color)
+  let printer = ColoredPrinter::new(writer)
+    .color(arg.output.color_choice())
     .heading(arg.heading)
     .context(context);
   let interactive = arg.output.needs_interactive();
   if interactive {
     let from_stdin = arg.input.stdin;
     let printer = InteractivePrinter::new(printer, arg.output.update_all, from_stdin)?;
-    run_pattern_with_printer(arg, printer)
+    run_pattern_with_printer(arg, printer, limits)
   } else {
-    run_pattern_with_printer(arg, printer)
+    run_pattern_with_printer(arg, printer, limits)
   }
 }
 
-fn run_pattern_with_printer(arg: RunArg, printer: impl Printer + 'static) -> Result<()> {
+fn run_pattern_with_printer(
+  mut arg: RunArg,
+  printer: impl Printer + 'static,
+  limits: FileLimits,
+) -> Result<()> {
   let trace = arg.output.inspect.run_trace();
   if arg.input.stdin {
-    RunWithSpecificLang::new(arg, trace)?.run_std_in(printer)
+    if arg.lang.is_none() {
+      arg.lang = arg
+        .input
+        .stdin_filepath
+        .as_deref()
+        .and_then(SgLang::from_path);
+    }
+    RunWithSpecificLang::new(arg, trace, limits)?.run_std_in(printer)
   } else if arg.lang.is_some() {
-    RunWithSpecificLang::new(arg, trace)?.run_path(printer)
+    RunWithSpecificLang::new(arg, trace, limits)?.run_path(printer)
   } else {
-    RunWithInferredLang { arg, trace }.run_path(printer)
+    RunWithInferredLang { arg, trace, limits }.run_path(printer)
   }
 }
 
 struct RunWithInferredLang {
   arg: RunArg,
   trace: RunTrace,
+  limits: FileLimits,
 }
 impl Worker for RunWithInferredLang {
   type Item = (MatchUnit<Pattern<SgLang>>, SgLang);
@@ -190,9 +272,9 @@ impl Worker for RunWithInferredLang {
         .map(|s| Fixer::from_str(s, &lang))
         .transpose();
       match rewrite {
-        Ok(r) => match_one_file(printer, &match_unit, &r)?,
+        Ok(r) => match_one_file(printer, &match_unit, r.as_ref())?,
         Err(e) => {
-          match_one_file(printer, &match_unit, &None)?;
+          match_one_file(printer, &match_unit, None)?;
           eprintln!("⚠️  Rewriting was skipped because pattern fails to parse. Error detail:");
           eprintln!("╰▻ {e}");
         }
@@ -205,15 +287,19 @@ impl Worker for RunWithInferredLang {
 }
 
 impl PathWorker for RunWithInferredLang {
+  type State<'w> = ();
   fn build_walk(&self) -> Result<WalkParallel> {
     self.arg.input.walk()
   }
+  fn get_threads(&self) -> usize {
+    self.arg.input.get_threads()
+  }
   fn get_trace(&self) -> &FileTrace {
     &self.trace.inner
   }
 
-  fn produce_item(&self, path: &Path) -> Option<Vec<Self::Item>> {
-    let lang = SgLang::from_path(path)?;
+  fn produce_item(&self, path: &Path, _: &mut ()) -> Option<Vec<Self::Item>> {
+    let lang = SgLang::from_file(path)?;
     self.trace.print_file(path, lang).ok()?;
     let matcher = self.arg.build_pattern(lang).ok()?;
     // match sub region
@@ -222,9 +308,24 @@ impl PathWorker for RunWithInferredLang {
         let pattern = self.arg.build_pattern(l).ok()?;
         Some((l, pattern))
       });
-      filter_file_pattern(path, lang, Some(matcher), matchers)
+      filter_file_pattern(
+        path,
+        lang,
+        Some(matcher),
+        matchers,
+        &self.limits,
+        &self.trace,
+      )
     } else {
-      filter_file_pattern(path, lang, Some(matcher), std::iter::empty())
+      let matchers = std::iter::empty();
+      filter_file_pattern(
+        path,
+        lang,
+        Some(matcher),
+        matchers,
+        &self.limits,
+        &self.trace,
+      )
     }
   }
 }
@@ -233,27 +334,58 @@ struct RunWithSpecificLang {
   arg: RunArg,
   pattern: Pattern<SgLang>,
   rewrite: Option<Fixer<SgLang>>,
+  /// rule built from --rewrite-config, used in place of pattern for matching and rewriting
+  rewrite_rule: Option<RuleCore<SgLang>>,
   stats: RunTrace,
+  limits: FileLimits,
+  /// files passed as paths, whose mismatch is explained for --debug-query=match
+  explained_files: HashSet<PathBuf>,
 }
 
 impl RunWithSpecificLang {
-  fn new(arg: RunArg, stats: RunTrace) -> Result<Self> {
+  fn new(arg: RunArg, stats: RunTrace, limits: FileLimits) -> Result<Self> {
     let lang = arg.lang.ok_or(anyhow::anyhow!(EC::LanguageNotSpecified))?;
     // do not unwrap result here
     let pattern_ret = arg.build_pattern(lang);
     arg.debug_pattern_if_needed(&pattern_ret, lang);
-    let rewrite = if let Some(s) = &arg.rewrite {
+    let rewrite_rule = arg.build_rewrite_rule(lang)?;
+    let rewrite = if rewrite_rule.is_some() {
+      None
+    } else if let Some(s) = &arg.rewrite {
       Some(Fixer::from_str(s, &lang).context(EC::ParsePattern)?)
     } else {
       None
     };
+    let mut explained_files = HashSet::new();
+    if matches!(arg.debug_query, Some(DebugFormat::Match)) {
+      // explaining every file in a directory walk would flood stderr
+      let files = arg.input.paths.iter().filter(|p| p.is_file());
+      explained_files = files
+        .map(|p| p.strip_prefix("./").unwrap_or(p).to_path_buf())
+        .collect();
+      if explained_files.is_empty() && !arg.input.stdin {
+        eprintln!("Note: --debug-query=match only explains files passed as paths.");
+      }
+    }
     Ok(Self {
       arg,
       pattern: pattern_ret?,
       rewrite,
+      rewrite_rule,
       stats,
+      limits,
+      explained_files,
     })
   }
+
+  // print why the pattern does not match the file for --debug-query=match
+  fn explain_if_needed(&self, path: &Path, grep: &AstGrep<StrDoc<SgLang>>) {
+    if !matches!(self.arg.debug_query, Some(DebugFormat::Match)) {
+      return;
+    }
+    let explained = explain_mismatch(&self.pattern, grep);
+    eprintln!("Debug Match {}:\n{explained}", path.display());
+  }
 }
 
 impl Worker for RunWithSpecificLang {
@@ -263,7 +395,16 @@ impl Worker for RunWithSpecificLang {
     printer.before_print()?;
     let mut has_matches = false;
     for match_unit in items {
-      match_one_file(&mut printer, &match_unit, &self.rewrite)?;
+      if let Some(rule) = &self.rewrite_rule {
+        let match_unit = MatchUnit {
+          path: match_unit.path,
+          grep: match_unit.grep,
+          matcher: rule,
+        };
+        match_one_file(&mut printer, &match_unit, rule.fixer.first())?;
+      } else {
+        match_one_file(&mut printer, &match_unit, self.rewrite.as_ref())?;
+      }
       has_matches = true;
     }
     printer.after_print()?;
@@ -277,23 +418,36 @@ impl Worker for RunWithSpecificLang {
 }
 
 impl PathWorker for RunWithSpecificLang {
+  type State<'w> = ();
   fn build_walk(&self) -> Result<WalkParallel> {
     let lang = self.arg.lang.expect("must present");
     Ok(self.arg.input.walk_lang(lang))
   }
+  fn get_threads(&self) -> usize {
+    self.arg.input.get_threads()
+  }
   fn get_trace(&self) -> &FileTrace {
     &self.stats.inner
   }
-  fn produce_item(&self, path: &Path) -> Option<Vec<Self::Item>> {
+  fn produce_item(&self, path: &Path, _: &mut ()) -> Option<Vec<Self::Item>> {
     let arg = &self.arg;
     let pattern = self.pattern.clone();
     let lang = arg.lang.expect("must present");
-    let path_lang = SgLang::from_path(path)?;
+    let path_lang = SgLang::from_file(path)?;
     self.stats.print_file(path, path_lang).ok()?;
+    let (limits, trace) = (&self.limits, &self.stats);
     let ret = if path_lang == lang {
-      filter_file_pattern(path, lang, Some(pattern), std::iter::empty())?
+      let ret = filter_file_pattern(path, lang, Some(pattern), std::iter::empty(), limits, trace)?;
+      // files skipped by limits are not explained
+      if ret.is_empty() && self.explained_files.contains(path) {
+        if let Ok(src) = std::fs::read_to_string(path) {
+          self.explain_if_needed(path, &lang.ast_grep(src));
+        }
+      }
+      ret
     } else {
-      filter_file_pattern(path, path_lang, None, std::iter::once((lang, pattern)))?
+      let matchers = std::iter::once((lang, pattern));
+      filter_file_pattern(path, path_lang, None, matchers, limits, trace)?
     };
     Some(ret.into_iter().map(|n| n.0).collect())
   }
@@ -304,8 +458,11 @@ impl StdInWorker for RunWithSpecificLang {
     let lang = self.arg.lang.expect("must present");
     let grep = lang.ast_grep(src);
     let has_match = grep.root().find(&self.pattern).is_some();
+    if !has_match {
+      self.explain_if_needed(&self.arg.input.stdin_path(), &grep);
+    }
     has_match.then(|| MatchUnit {
-      path: PathBuf::from("STDIN"),
+      path: self.arg.input.stdin_path(),
       matcher: self.pattern.clone(),
       grep,
     })
@@ -315,7 +472,7 @@ impl StdInWorker for RunWithSpecificLang {
 fn match_one_file(
   printer: &mut impl Printer,
   match_unit: &MatchUnit<impl Matcher<SgLang>>,
-  rewrite: &Option<Fixer<SgLang>>,
+  rewrite: Option<&Fixer<SgLang>>,
 ) -> Result<()> {
   let MatchUnit {
     path,
@@ -336,6 +493,7 @@ fn match_one_file(
 mod test {
   use super::*;
   use crate::print::ColorArg;
+  use ast_grep_core::MatchStrictness;
   use ast_grep_language::SupportLang;
 
   fn default_run_arg() -> RunArg {
@@ -343,17 +501,24 @@ mod test {
       pattern: String::new(),
       selector: None,
       rewrite: None,
+      rewrite_config: None,
       lang: None,
       heading: Heading::Never,
+      format: None,
+      stats: false,
       debug_query: None,
       strictness: None,
+      allow_error_nodes: false,
       input: InputArgs {
         no_ignore: vec![],
         stdin: false,
+        stdin_filepath: None,
         follow: false,
         paths: vec![PathBuf::from(".")],
         globs: vec![],
         threads: 0,
+        max_filesize: None,
+        no_skip_minified: false,
       },
       output: OutputArgs {
         color: ColorArg::Never,
@@ -361,6 +526,7 @@ mod test {
         json: None,
         update_all: false,
         inspect: Default::default(),
+        output_file: None,
       },
       context: ContextArgs {
         before: 0,
@@ -401,4 +567,40 @@ mod test {
     let proj = Err(anyhow::anyhow!("no project"));
     assert!(run_with_pattern(arg, proj).is_ok())
   }
+
+  #[test]
+  fn test_build_rewrite_rule() {
+    let arg = RunArg {
+      pattern: "let $A = $B".to_string(),
+      rewrite: Some("let $UPPER = $B".to_string()),
+      rewrite_config: Some("transform: {UPPER: {convert: {source: $A, toCase: upperCase}}}".into()),
+      lang: Some(SupportLang::TypeScript.into()),
+      ..default_run_arg()
+    };
+    let lang = SupportLang::TypeScript.into();
+    let rule = arg.build_rewrite_rule(lang).expect("should build");
+    let rule = rule.expect("should have rule");
+    let grep = lang.ast_grep("let abc = 123");
+    let node = grep.root().find(&rule).expect("should match");
+    let fixer = rule.fixer.first().expect("should have fixer");
+    let edit = node.make_edit(&rule, fixer);
+    assert_eq!(edit.inserted_text, b"let ABC = 123");
+  }
+
+  #[test]
+  fn test_build_rewrite_rule_error() {
+    let arg = RunArg {
+      pattern: "let $A = $B".to_string(),
+      rewrite_config: Some("transform: {UPPER: {convert: {source: $C}}}".into()),
+      ..default_run_arg()
+    };
+    let lang = SupportLang::TypeScript.into();
+    assert!(arg.build_rewrite_rule(lang).is_err());
+    let arg = RunArg {
+      pattern: "let $A = $B".to_string(),
+      rewrite_config: Some("[not a mapping]".into()),
+      ..default_run_arg()
+    };
+    assert!(arg.build_rewrite_rule(lang).is_err());
+  }
 }
@@ -2,29 +2,92 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use ast_grep_config::{
-  from_yaml_string, CombinedScan, PreScan, RuleCollection, RuleConfig, Severity,
+  from_yaml_string, CombinedScan, Fixer, PreScan, RequireIndex, RuleCollection, RuleConfig,
+  Severity,
 };
 use ast_grep_core::{NodeMatch, StrDoc};
 use ast_grep_language::SupportLang;
-use clap::Args;
-use ignore::WalkParallel;
+use clap::{Args, ValueEnum};
+use ignore::{DirEntry, WalkParallel, WalkState};
 
 use crate::config::{read_rule_file, with_rule_stats, ProjectConfig};
 use crate::lang::SgLang;
 use crate::print::{
-  CloudPrinter, ColoredPrinter, Diff, InteractivePrinter, JSONPrinter, Platform, Printer,
-  ReportStyle, SimpleFile,
+  CloudPrinter, ColoredPrinter, Diff, InteractivePrinter, JSONPrinter, PatchPrinter, Platform,
+  Printer, ReportStyle, SimpleFile, StatsPrinter, SummaryPrinter,
 };
 use crate::utils::ErrorContext as EC;
-use crate::utils::RuleOverwrite;
-use crate::utils::{filter_file_interactive, ContextArgs, InputArgs, OutputArgs, OverwriteArgs};
+use crate::utils::{
+  filter_file_interactive, CombinedScans, ContextArgs, FileLimits, InputArgs, OutputArgs,
+  OverwriteArgs,
+};
+use crate::utils::{Baseline, ChangedFiles, RuleOverwrite};
 use crate::utils::{FileTrace, ScanTrace};
 use crate::utils::{Items, PathWorker, StdInWorker, Worker};
 
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Mutex;
 
 type AstGrep = ast_grep_core::AstGrep<StrDoc<SgLang>>;
 
+/// The lowest severity of findings that makes scan exit with a non-zero code.
+#[derive(Clone, Copy, Default, ValueEnum, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorLevel {
+  /// Fail on findings of any severity.
+  Hint,
+  /// Fail on findings of info, warning or error severity.
+  Info,
+  /// Fail on findings of warning or error severity.
+  Warning,
+  /// Fail on findings of error severity.
+  #[default]
+  Error,
+}
+
+impl ErrorLevel {
+  fn includes(self, severity: &Severity) -> bool {
+    let level = match severity {
+      Severity::Hint => Self::Hint,
+      Severity::Info => Self::Info,
+      Severity::Warning => Self::Warning,
+      Severity::Error => Self::Error,
+      Severity::Off => return false,
+    };
+    level >= self
+  }
+}
+
+/// Output formats of `--format`.
+#[derive(Clone, Copy, ValueEnum, PartialEq, Eq)]
+#[clap(rename_all = "lower")]
+pub enum Format {
+  /// GitHub Action workflow commands
+  GitHub,
+  /// GitLab Code Quality report in JSON
+  GitLab,
+  /// Reviewdog Diagnostic Format in JSON
+  RdJson,
+  /// JUnit XML test report
+  JUnit,
+  /// Unified diff of fixes, which can be applied by `git apply`
+  Patch,
+}
+
+impl Format {
+  /// The CI platform printed by CloudPrinter, None for the patch printed by PatchPrinter.
+  fn platform(self) -> Option<Platform> {
+    match self {
+      Self::GitHub => Some(Platform::GitHub),
+      Self::GitLab => Some(Platform::GitLab),
+      Self::RdJson => Some(Platform::RdJson),
+      Self::JUnit => Some(Platform::JUnit),
+      Self::Patch => None,
+    }
+  }
+}
+
 #[derive(Args)]
 pub struct ScanArg {
   /// Scan the codebase with the single rule located at the path RULE_FILE.
@@ -37,19 +100,119 @@ pub struct ScanArg {
   ///
   /// Use this argument if you want to test a rule without creating a YAML file on disk.
   /// You can run multiple rules by separating them with `---` in the RULE_TEXT.
-  /// --inline-rules is incompatible with --rule.
-  #[clap(long, conflicts_with = "rule", value_name = "RULE_TEXT")]
+  /// Pass `-` to read RULE_TEXT from StdIn. It can be combined with --rule to run both.
+  #[clap(long, value_name = "RULE_TEXT")]
   inline_rules: Option<String>,
 
-  /// Output warning/error messages in GitHub Action format.
+  /// Output warning/error messages in CI platform format.
   ///
-  /// Currently, only GitHub is supported.
+  /// `github` prints GitHub Action workflow commands.
+  /// `gitlab` prints a GitLab Code Quality JSON report.
+  /// `rdjson` prints a Reviewdog Diagnostic Format JSON for reviewdog.
+  /// `junit` prints a JUnit XML report with one test case per rule per file.
+  /// `patch` prints fixes as a unified diff that `git apply` accepts, without modifying files.
   #[clap(long, conflicts_with = "json", conflicts_with = "interactive")]
-  format: Option<Platform>,
+  format: Option<Format>,
 
   #[clap(long, default_value = "rich", conflicts_with = "json")]
   report_style: ReportStyle,
 
+  /// Report only new findings that are not recorded in the BASELINE_FILE.
+  ///
+  /// If the file does not exist, all current findings will be recorded into it and suppressed.
+  /// Findings are matched by fingerprints of rule id, file path and matched code,
+  /// so they are not affected by line shifts or reformatting.
+  #[clap(long, value_name = "BASELINE_FILE", conflicts_with = "stdin")]
+  baseline: Option<PathBuf>,
+
+  /// Scan only files changed since the merge-base of the git REF and HEAD, including untracked files.
+  ///
+  /// REF defaults to HEAD, which means uncommitted changes.
+  /// Use a branch like `origin/main` to scan the changes of a pull request.
+  #[clap(
+    long,
+    value_name = "REF",
+    num_args = 0..=1,
+    default_missing_value = "HEAD",
+    conflicts_with = "stdin"
+  )]
+  changed: Option<String>,
+
+  /// Report only findings overlapping with changed lines. Requires --changed.
+  #[clap(long, requires = "changed")]
+  changed_lines: bool,
+
+  /// Report suppression comments that do not suppress any finding.
+  ///
+  /// Unused suppressions are reported as warnings even if only some rules are scanned.
+  /// Use it with --update-all to remove the stale comments.
+  #[clap(long, conflicts_with = "stdin")]
+  report_unused_suppressions: bool,
+
+  /// Time each rule across the scan and print a table of the slowest rules to stderr.
+  ///
+  /// The table lists files hit, total and average matching time per file of every rule.
+  /// It helps to find pathological rules like expensive regex or relational rules.
+  #[clap(long, conflicts_with = "stdin")]
+  profile_rules: bool,
+
+  /// Print statistics of fixes instead of modifying files.
+  ///
+  /// It reports how many files would be modified, the number of edits and bytes changed,
+  /// and the edits and files of every rule with fix.
+  #[clap(
+    long,
+    conflicts_with = "json",
+    conflicts_with = "interactive",
+    conflicts_with = "update_all",
+    conflicts_with = "format"
+  )]
+  stats: bool,
+
+  /// Print only the counts of findings per severity and per rule, without individual findings.
+  #[clap(
+    long,
+    conflicts_with = "json",
+    conflicts_with = "interactive",
+    conflicts_with = "update_all",
+    conflicts_with = "format",
+    conflicts_with = "stats"
+  )]
+  summary: bool,
+
+  /// Also print the counts of findings per directory in --summary.
+  #[clap(long, requires = "summary")]
+  summary_by_dir: bool,
+
+  /// Sort findings before printing to make the output stable across runs.
+  ///
+  /// Files are scanned in parallel so findings are printed in nondeterministic order by default.
+  /// Sorting keeps the files with findings in memory and scans them again once all files are scanned.
+  /// --max-findings still limits the printed findings but no longer stops the scan early.
+  #[clap(long, value_name = "ORDER")]
+  sort: Option<SortBy>,
+
+  /// Stop scanning once NUM findings are reported.
+  ///
+  /// It is useful for fast checks on huge repositories. A note is printed to stderr
+  /// if the results are truncated.
+  #[clap(long, value_name = "NUM", conflicts_with = "baseline")]
+  max_findings: Option<usize>,
+
+  /// Report only one finding if several rules make the same fix on the same code.
+  ///
+  /// The finding of the most severe rule is kept, ties go to the first rule by id.
+  /// Ids of the other rules are listed with the kept finding.
+  #[clap(long)]
+  dedup_fixes: bool,
+
+  /// Exit with code 1 if findings at or above the severity LEVEL exist.
+  ///
+  /// Findings below the LEVEL are still reported but do not fail the scan.
+  /// Errors of ast-grep itself, like invalid rules, exit with codes other than 1.
+  #[clap(long, value_name = "LEVEL", default_value = "error")]
+  error_on: ErrorLevel,
+
   /// severity related options
   #[clap(flatten)]
   overwrite: OverwriteArgs,
@@ -70,21 +233,64 @@ impl ScanArg {
   fn include_all_rules(&self) -> bool {
     self.overwrite.include_all_rules() && self.rule.is_none() && self.inline_rules.is_none()
   }
+
+  // rules passed by --rule and --inline-rules, None if neither is given
+  fn cli_rules(&self, overwrite: &RuleOverwrite) -> Result<Option<Vec<RuleConfig<SgLang>>>> {
+    if self.rule.is_none() && self.inline_rules.is_none() {
+      return Ok(None);
+    }
+    let mut rules = match &self.rule {
+      Some(path) => read_rule_file(path, None)?,
+      None => vec![],
+    };
+    if let Some(text) = &self.inline_rules {
+      let inline = if text == "-" {
+        if self.input.stdin {
+          return Err(anyhow::anyhow!(EC::StdInIsUsedByCode));
+        }
+        let text = std::io::read_to_string(std::io::stdin())
+          .with_context(|| EC::ReadRule("STDIN".into()))?;
+        from_yaml_string(&text, &Default::default())
+      } else {
+        from_yaml_string(text, &Default::default())
+      };
+      let inline = inline.with_context(|| EC::ParseRule("INLINE_RULES".into()))?;
+      rules.extend(overwrite.process_configs(inline)?);
+    }
+    let rules = overwrite.override_strictness(rules, &Default::default())?;
+    Ok(Some(rules))
+  }
+
+  // whether fixes are printed as diffs separately from matches
+  fn separate_fix(&self) -> bool {
+    self.output.needs_interactive() || self.stats || self.format == Some(Format::Patch)
+  }
 }
 
 pub fn run_with_config(arg: ScanArg, project: Result<ProjectConfig>) -> Result<()> {
   let project_trace = arg.output.inspect.project_trace();
   project_trace.print_project(&project)?;
   let context = arg.context.get();
-  if let Some(_format) = &arg.format {
-    let printer = CloudPrinter::stdout();
+  let writer = arg.output.writer()?;
+  if arg.stats {
+    return run_scan(arg, StatsPrinter::new(writer), project);
+  }
+  if arg.summary {
+    let printer = SummaryPrinter::new(writer, arg.summary_by_dir);
     return run_scan(arg, printer, project);
   }
+  if let Some(format) = arg.format {
+    return match format.platform() {
+      Some(platform) => run_scan(arg, CloudPrinter::new(writer, platform), project),
+      None => run_scan(arg, PatchPrinter::new(writer), project),
+    };
+  }
   if let Some(json) = arg.output.json {
-    let printer = JSONPrinter::stdout(json);
+    let printer = JSONPrinter::new(writer, json);
     return run_scan(arg, printer, project);
   }
-  let printer = ColoredPrinter::stdout(arg.output.color)
+  let printer = ColoredPrinter::new(writer)
+    .color(arg.output.color_choice())
     .style(arg.report_style)
     .context(context);
   let interactive = arg.output.needs_interactive();
@@ -103,7 +309,7 @@ fn run_scan<P: Printer + 'static>(
   project: Result<ProjectConfig>,
 ) -> Result<()> {
   if arg.input.stdin {
-    let worker = ScanWithRule::try_new(arg)?;
+    let worker = ScanWithRule::try_new(arg, project)?;
     // TODO: report a soft error if rules have different languages
     worker.run_std_in(printer)
   } else {
@@ -116,60 +322,163 @@ struct ScanWithConfig {
   arg: ScanArg,
   configs: RuleCollection<SgLang>,
   unused_suppression_rule: RuleConfig<SgLang>,
+  baseline: Option<Baseline>,
+  changed: Option<ChangedFiles>,
+  require_index: Option<RequireIndex>,
+  file_limits: FileLimits,
   trace: ScanTrace,
 }
 impl ScanWithConfig {
   fn try_new(arg: ScanArg, project: Result<ProjectConfig>) -> Result<Self> {
     let overwrite = RuleOverwrite::new(&arg.overwrite)?;
+    let file_limits = arg.input.file_limits(project.as_ref().ok());
+    let root = project_root(&project);
     let unused_suppression_rule = unused_suppression_rule_config(&arg, &overwrite);
-    let (configs, rule_trace) = if let Some(path) = &arg.rule {
-      let rules = read_rule_file(path, None)?;
-      with_rule_stats(rules)?
-    } else if let Some(text) = &arg.inline_rules {
-      let rules = from_yaml_string(text, &Default::default())
-        .with_context(|| EC::ParseRule("INLINE_RULES".into()))?;
+    let (configs, rule_trace) = if let Some(rules) = arg.cli_rules(&overwrite)? {
       with_rule_stats(rules)?
     } else {
       // NOTE: only query project here since -r does not need project
       let project_config = project?;
       project_config.find_rules(overwrite)?
     };
+    let mut rule_trace = rule_trace;
+    rule_trace.profile_rules = arg.profile_rules;
     let trace = arg.output.inspect.scan_trace(rule_trace);
     trace.print_rules(&configs)?;
+    let baseline = arg.baseline.as_deref().map(Baseline::open).transpose()?;
+    let changed = arg
+      .changed
+      .as_deref()
+      .map(ChangedFiles::from_git)
+      .transpose()?;
+    let mut rules = vec![];
+    configs.for_each_rule(|rule| rules.push(rule));
+    let require_index = build_require_index(&root, &arg.input, rules.into_iter())?;
     Ok(Self {
       arg,
       configs,
       unused_suppression_rule,
+      baseline,
+      changed,
+      require_index,
+      file_limits,
       trace,
     })
   }
+
+  fn should_report(
+    &self,
+    path: &Path,
+    rule: &RuleConfig<SgLang>,
+    m: &NodeMatch<StrDoc<SgLang>>,
+  ) -> bool {
+    if let Some(changed) = self.changed.as_ref().filter(|_| self.arg.changed_lines) {
+      let (start, end) = (m.start_pos().line(), m.end_pos().line());
+      if !changed.touches_lines(path, start, end) {
+        return false;
+      }
+    }
+    let Some(baseline) = &self.baseline else {
+      return true;
+    };
+    baseline.is_new(path, rule, &m.text())
+  }
+}
+impl ScanWithConfig {
+  /// Scan the file and keep the findings `report` accepts.
+  /// The rules are matched again if `pre_scan` is not available.
+  fn scan_file<'a, 'r: 'a>(
+    &'r self,
+    path: &'a Path,
+    grep: &'a AstGrep,
+    pre_scan: Option<PreScan>,
+    scans: &mut CombinedScans<'r>,
+    report: impl Fn(&RuleConfig<SgLang>, &NodeMatch<StrDoc<SgLang>>) -> bool,
+  ) -> Vec<Findings<'a>> {
+    let rules = self.configs.get_rule_from_lang(path, *grep.lang());
+    let combined = scans.get_with(&rules, |combined| {
+      combined.set_dedup_fixes(self.arg.dedup_fixes);
+      combined.set_unused_suppression_rule(&self.unused_suppression_rule);
+      if let Some(index) = &self.require_index {
+        combined.set_require_index(index);
+      }
+    });
+    let separate_fix = self.arg.separate_fix();
+    // files scanned again to sort findings are only timed once
+    let timed = pre_scan.is_some() && self.trace.collects_timing();
+    let pre_scan = pre_scan.unwrap_or_else(|| combined.find(grep));
+    // exclude_fix rule because we already have diff inspection before
+    let scanned = if timed {
+      let (scanned, timings) = combined.scan_with_timing(grep, pre_scan, separate_fix);
+      self.trace.add_scan_timing(&timings);
+      scanned
+    } else {
+      combined.scan(grep, pre_scan, separate_fix)
+    };
+    let mut findings = vec![];
+    let merged = scanned.merged;
+    if separate_fix {
+      let mut diffs = scanned.diffs;
+      diffs.retain(|(rule, m)| report(rule, m));
+      findings.push(Findings::Diffs(path, diffs, merged.clone()));
+    }
+    for (rule, mut matches) in scanned.matches {
+      matches.retain(|m| report(rule, m));
+      let finding = Findings::Matches(path, grep.source(), rule, matches, merged.clone());
+      findings.push(finding);
+    }
+    findings
+  }
 }
 impl Worker for ScanWithConfig {
   type Item = (PathBuf, AstGrep, PreScan);
   fn consume_items<P: Printer>(&self, items: Items<Self::Item>, mut printer: P) -> Result<()> {
     printer.before_print()?;
     let mut error_count = 0usize;
-    for (path, grep, pre_scan) in items {
-      let file_content = grep.source().to_string();
-      let path = &path;
-      let rules = self.configs.get_rule_from_lang(path, *grep.lang());
-      let mut combined = CombinedScan::new(rules);
-      combined.set_unused_suppression_rule(&self.unused_suppression_rule);
-      let interactive = self.arg.output.needs_interactive();
-      // exclude_fix rule because we already have diff inspection before
-      let scanned = combined.scan(&grep, pre_scan, /* separate_fix*/ interactive);
-      if interactive {
-        let diffs = scanned.diffs;
-        match_rule_diff_on_file(path, diffs, &mut printer)?;
+    let mut budget = FindingBudget::new(self.arg.max_findings);
+    let mut scans = CombinedScans::default();
+    if let Some(sort) = self.arg.sort {
+      // keep files with findings and the keys of their findings until scanning finishes.
+      // findings borrow the file, so the kept files are scanned again to sort and print them
+      let mut files = vec![];
+      for (path, grep, pre_scan) in items {
+        let report = |rule: &RuleConfig<_>, m: &NodeMatch<_>| self.should_report(&path, rule, m);
+        let found = self.scan_file(&path, &grep, Some(pre_scan), &mut scans, report);
+        let keys: HashSet<_> = found.iter().flat_map(Findings::keys).collect();
+        drop(found);
+        if !keys.is_empty() {
+          files.push((path, grep, keys));
+        }
       }
-      for (rule, matches) in scanned.matches {
-        if matches!(rule.severity, Severity::Error) {
-          error_count = error_count.saturating_add(matches.len());
+      let mut findings = vec![];
+      for (path, grep, keys) in &files {
+        let report =
+          |rule: &RuleConfig<_>, m: &NodeMatch<_>| keys.contains(&(rule.id.clone(), m.range()));
+        findings.extend(self.scan_file(path, grep, None, &mut scans, report));
+      }
+      sort.sort(&mut findings);
+      for f in findings {
+        let count = f.print(&mut printer, &mut budget, self.arg.error_on)?;
+        error_count = error_count.saturating_add(count);
+      }
+    } else {
+      for (path, grep, pre_scan) in items {
+        // dropping items stops producers from parsing more files
+        if budget.is_exhausted() {
+          break;
+        }
+        let report = |rule: &RuleConfig<_>, m: &NodeMatch<_>| self.should_report(&path, rule, m);
+        for f in self.scan_file(&path, &grep, Some(pre_scan), &mut scans, report) {
+          let count = f.print(&mut printer, &mut budget, self.arg.error_on)?;
+          error_count = error_count.saturating_add(count);
         }
-        match_rule_on_file(path, matches, rule, &file_content, &mut printer)?;
       }
     }
     printer.after_print()?;
+    budget.print_note();
+    if let Some(baseline) = &self.baseline {
+      baseline.save()?;
+    }
     self.trace.print()?;
     if error_count > 0 {
       Err(anyhow::anyhow!(EC::DiagnosticError(error_count)))
@@ -179,11 +488,70 @@ impl Worker for ScanWithConfig {
   }
 }
 
+/// The root to search for `requires`, or the current directory if there is no project.
+fn project_root(project: &Result<ProjectConfig>) -> PathBuf {
+  project
+    .as_ref()
+    .map_or_else(|_| PathBuf::from("."), |p| p.project_dir.clone())
+}
+
+/// Searches the whole project for the files required by rules with `requires`,
+/// no matter which paths are scanned. `requires.file` globs are relative to the project root.
+/// It must finish before scanning because any file can meet the condition.
+fn build_require_index<'r>(
+  root: &Path,
+  input: &InputArgs,
+  rules: impl Iterator<Item = &'r RuleConfig<SgLang>>,
+) -> Result<Option<RequireIndex>> {
+  use ast_grep_core::Language;
+  let rules: Vec<_> = rules.filter(|rule| rule.requires.is_some()).collect();
+  if rules.is_empty() {
+    return Ok(None);
+  }
+  let langs: HashSet<_> = rules.iter().map(|rule| rule.language).collect();
+  let paths = Mutex::new(vec![]);
+  let roots = [root.to_path_buf()];
+  input.walk_langs_in(&roots, langs.into_iter())?.run(|| {
+    let paths = &paths;
+    Box::new(move |entry: Result<DirEntry, ignore::Error>| {
+      let Ok(entry) = entry else {
+        return WalkState::Continue;
+      };
+      if entry.file_type().is_some_and(|t| t.is_file()) {
+        let mut paths = paths.lock().expect("lock should not be poisoned");
+        paths.push(entry.into_path());
+      }
+      WalkState::Continue
+    })
+  });
+  let paths = paths.into_inner().expect("lock should not be poisoned");
+  let mut index = RequireIndex::default();
+  for path in paths {
+    let Some(lang) = SgLang::from_file(&path) else {
+      continue;
+    };
+    let Ok(source) = std::fs::read_to_string(&path) else {
+      continue;
+    };
+    let relative = path.strip_prefix(root).unwrap_or(&path);
+    let lang_rules = rules
+      .iter()
+      .copied()
+      .filter(|rule| rule.language == lang)
+      .collect();
+    let grep = lang.ast_grep(source);
+    CombinedScan::new(lang_rules).index_requires(relative, &grep, &mut index);
+  }
+  Ok(Some(index))
+}
+
 // we should only suggest unused suppression if scan includes all rules
 // otherwise, keep silent about unused suppressions because they may used by other rules
 // this is a "smart" heuristic but user always can override it
 fn default_unused_suppression_rule_severity(arg: &ScanArg) -> Severity {
-  if arg.include_all_rules() {
+  if arg.report_unused_suppressions {
+    Severity::Warning
+  } else if arg.include_all_rules() {
     Severity::Hint
   } else {
     Severity::Off
@@ -199,6 +567,7 @@ fn unused_suppression_rule_config(arg: &ScanArg, overwrite: &RuleOverwrite) -> R
 }
 
 impl PathWorker for ScanWithConfig {
+  type State<'w> = CombinedScans<'w>;
   fn get_trace(&self) -> &FileTrace {
     &self.trace.inner.file_trace
   }
@@ -209,46 +578,267 @@ impl PathWorker for ScanWithConfig {
     });
     self.arg.input.walk_langs(langs.into_iter())
   }
-  fn produce_item(&self, path: &Path) -> Option<Vec<Self::Item>> {
-    filter_file_interactive(path, &self.configs, &self.trace)
+  fn get_threads(&self) -> usize {
+    self.arg.input.get_threads()
+  }
+  fn produce_item<'w>(
+    &'w self,
+    path: &Path,
+    scans: &mut CombinedScans<'w>,
+  ) -> Option<Vec<Self::Item>> {
+    if let Some(changed) = &self.changed {
+      if !changed.contains(path) {
+        return None;
+      }
+    }
+    let (configs, limits) = (&self.configs, &self.file_limits);
+    filter_file_interactive(path, configs, limits, &self.trace, scans)
   }
 }
 
 struct ScanWithRule {
   rules: Vec<RuleConfig<SgLang>>,
+  /// path reported for code from StdIn
+  path: PathBuf,
+  separate_fix: bool,
+  dedup_fixes: bool,
+  error_on: ErrorLevel,
+  max_findings: Option<usize>,
+  sort: Option<SortBy>,
+  require_index: Option<RequireIndex>,
 }
 impl ScanWithRule {
-  fn try_new(arg: ScanArg) -> Result<Self> {
-    let rules = if let Some(path) = &arg.rule {
-      read_rule_file(path, None)?
-    } else if let Some(text) = &arg.inline_rules {
-      from_yaml_string(text, &Default::default())
-        .with_context(|| EC::ParseRule("INLINE_RULES".into()))?
-    } else {
+  fn try_new(arg: ScanArg, project: Result<ProjectConfig>) -> Result<Self> {
+    let overwrite = RuleOverwrite::new(&arg.overwrite)?;
+    let Some(rules) = arg.cli_rules(&overwrite)? else {
       return Err(anyhow::anyhow!(EC::RuleNotSpecified));
     };
-    Ok(Self { rules })
+    let rules = match &arg.input.stdin_filepath {
+      Some(path) => rules_for_stdin_path(rules, path)?,
+      None => rules,
+    };
+    let path = arg.input.stdin_path();
+    let separate_fix = arg.stats || arg.format == Some(Format::Patch);
+    let require_index = build_require_index(&project_root(&project), &arg.input, rules.iter())?;
+    Ok(Self {
+      rules,
+      path,
+      separate_fix,
+      dedup_fixes: arg.dedup_fixes,
+      error_on: arg.error_on,
+      max_findings: arg.max_findings,
+      sort: arg.sort,
+      require_index,
+    })
   }
 }
 
+/// ids of rules merged into a finding by `--dedup-fixes`, keyed by the rule id and the matched range
+type MergedIds<'a> = HashMap<(&'a str, Range<usize>), Vec<&'a str>>;
+
+/// Findings of a file printed together.
+enum Findings<'a> {
+  /// fixes of all rules in the file, printed as diffs
+  Diffs(
+    &'a Path,
+    Vec<(&'a RuleConfig<SgLang>, NodeMatch<'a, StrDoc<SgLang>>)>,
+    MergedIds<'a>,
+  ),
+  /// matches of one rule in the file
+  Matches(
+    &'a Path,
+    &'a str,
+    &'a RuleConfig<SgLang>,
+    Vec<NodeMatch<'a, StrDoc<SgLang>>>,
+    MergedIds<'a>,
+  ),
+}
+
+impl Findings<'_> {
+  fn path(&self) -> &Path {
+    match self {
+      Findings::Diffs(path, ..) | Findings::Matches(path, ..) => path,
+    }
+  }
+
+  /// the rules of the findings, diffs of a file may come from several rules
+  fn rules(&self) -> Vec<&RuleConfig<SgLang>> {
+    match self {
+      Findings::Diffs(_, diffs, _) => diffs.iter().map(|(rule, _)| *rule).collect(),
+      Findings::Matches(_, _, rule, ..) => vec![*rule],
+    }
+  }
+
+  /// rule ids and ranges of the findings, used to find them again when the file is scanned twice
+  fn keys(&self) -> Vec<(String, Range<usize>)> {
+    match self {
+      Findings::Diffs(_, diffs, _) => diffs
+        .iter()
+        .map(|(rule, m)| (rule.id.clone(), m.range()))
+        .collect(),
+      Findings::Matches(_, _, rule, matches, _) => matches
+        .iter()
+        .map(|m| (rule.id.clone(), m.range()))
+        .collect(),
+    }
+  }
+
+  /// print findings within the budget and return the number of findings failing the scan
+  fn print<P: Printer>(
+    self,
+    printer: &mut P,
+    budget: &mut FindingBudget,
+    error_on: ErrorLevel,
+  ) -> Result<usize> {
+    match self {
+      Findings::Diffs(path, mut diffs, merged) => {
+        budget.take(&mut diffs);
+        match_rule_diff_on_file(path, diffs, &merged, printer)?;
+        Ok(0)
+      }
+      Findings::Matches(path, content, rule, mut matches, merged) => {
+        budget.take(&mut matches);
+        let count = if error_on.includes(&rule.severity) {
+          matches.len()
+        } else {
+          0
+        };
+        match_rule_on_file(path, matches, rule, content, &merged, printer)?;
+        Ok(count)
+      }
+    }
+  }
+}
+
+/// Order of findings in the output.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SortBy {
+  /// Sort by file path, then by rule id.
+  Path,
+  /// Sort from the most severe findings, then by rule id and file path.
+  Severity,
+  /// Sort by rule id, then by file path.
+  Rule,
+}
+
+impl SortBy {
+  fn sort(self, findings: &mut [Findings]) {
+    // diffs of a file are grouped together, so they take the first rule id and the highest severity
+    let rule_id = |f: &Findings| f.rules().into_iter().map(|r| r.id.clone()).min();
+    let severity = |f: &Findings| {
+      let ranks = f.rules().into_iter().map(|r| severity_rank(&r.severity));
+      Reverse(ranks.max())
+    };
+    match self {
+      SortBy::Path => findings.sort_by_cached_key(|f| (f.path().to_path_buf(), rule_id(f))),
+      SortBy::Severity => findings.sort_by_cached_key(|f| {
+        let path = f.path().to_path_buf();
+        (severity(f), rule_id(f), path)
+      }),
+      SortBy::Rule => findings.sort_by_cached_key(|f| (rule_id(f), f.path().to_path_buf())),
+    }
+  }
+}
+
+fn severity_rank(severity: &Severity) -> u8 {
+  match severity {
+    Severity::Off => 0,
+    Severity::Hint => 1,
+    Severity::Info => 2,
+    Severity::Warning => 3,
+    Severity::Error => 4,
+  }
+}
+
+/// Remaining number of findings allowed by `--max-findings`.
+struct FindingBudget {
+  max: Option<usize>,
+  left: usize,
+}
+
+impl FindingBudget {
+  fn new(max: Option<usize>) -> Self {
+    Self {
+      max,
+      left: max.unwrap_or(usize::MAX),
+    }
+  }
+
+  /// truncate findings to the remaining budget
+  fn take<T>(&mut self, findings: &mut Vec<T>) {
+    findings.truncate(self.left);
+    self.left -= findings.len();
+  }
+
+  fn is_exhausted(&self) -> bool {
+    self.left == 0
+  }
+
+  fn print_note(&self) {
+    if let Some(max) = self.max.filter(|_| self.is_exhausted()) {
+      eprintln!("Scan stopped after {max} finding(s). Results may be truncated.");
+    }
+  }
+}
+
+/// Keep rules that would apply to the file if the code from StdIn were read from `path`.
+fn rules_for_stdin_path(
+  rules: Vec<RuleConfig<SgLang>>,
+  path: &Path,
+) -> Result<Vec<RuleConfig<SgLang>>> {
+  use ast_grep_core::Language;
+  let lang = SgLang::from_path(path);
+  // rule globs are written against paths walked from `.`, like `./src/a.ts`
+  let glob_path = if path.is_relative() && !path.starts_with(".") {
+    Path::new(".").join(path)
+  } else {
+    path.to_path_buf()
+  };
+  let mut ret = vec![];
+  for rule in rules {
+    let applies = rule
+      .matches_path(&glob_path)
+      .with_context(|| EC::ParseRule(rule.id.clone().into()))?;
+    if applies && Some(rule.language) == lang {
+      ret.push(rule);
+    }
+  }
+  Ok(ret)
+}
+
 impl Worker for ScanWithRule {
   type Item = (PathBuf, AstGrep, PreScan);
   fn consume_items<P: Printer>(&self, items: Items<Self::Item>, mut printer: P) -> Result<()> {
     printer.before_print()?;
     let mut error_count = 0usize;
-    let combined = CombinedScan::new(self.rules.iter().collect());
+    let mut combined = CombinedScan::new(self.rules.iter().collect());
+    combined.set_dedup_fixes(self.dedup_fixes);
+    if let Some(index) = &self.require_index {
+      combined.set_require_index(index);
+    }
+    let mut budget = FindingBudget::new(self.max_findings);
     for (path, grep, pre_scan) in items {
-      let file_content = grep.source().to_string();
-      // do not exclude_fix rule in run_with_rule
-      let scanned = combined.scan(&grep, pre_scan, false);
+      let mut findings = vec![];
+      // do not exclude_fix rule in run_with_rule, unless fixes are printed as patch
+      let scanned = combined.scan(&grep, pre_scan, self.separate_fix);
+      let merged = scanned.merged;
+      if self.separate_fix {
+        findings.push(Findings::Diffs(&path, scanned.diffs, merged.clone()));
+      }
       for (rule, matches) in scanned.matches {
-        if matches!(rule.severity, Severity::Error) {
-          error_count = error_count.saturating_add(matches.len());
-        }
-        match_rule_on_file(&path, matches, rule, &file_content, &mut printer)?;
+        let finding = Findings::Matches(&path, grep.source(), rule, matches, merged.clone());
+        findings.push(finding);
+      }
+      if let Some(sort) = self.sort {
+        sort.sort(&mut findings);
+      }
+      for f in findings {
+        let count = f.print(&mut printer, &mut budget, self.error_on)?;
+        error_count = error_count.saturating_add(count);
       }
     }
     printer.after_print()?;
+    budget.print_note();
     if error_count > 0 {
       Err(anyhow::anyhow!(EC::DiagnosticError(error_count)))
     } else {
@@ -260,28 +850,43 @@ impl Worker for ScanWithRule {
 impl StdInWorker for ScanWithRule {
   fn parse_stdin(&self, src: String) -> Option<Self::Item> {
     use ast_grep_core::Language;
-    let lang = self.rules[0].language;
+    let lang = self.rules.first()?.language;
     let combined = CombinedScan::new(self.rules.iter().collect());
     let grep = lang.ast_grep(src);
     let pre_scan = combined.find(&grep);
     if !pre_scan.is_empty() {
-      Some((PathBuf::from("STDIN"), grep, pre_scan))
+      Some((self.path.clone(), grep, pre_scan))
     } else {
       None
     }
   }
 }
+/// generate the diff of the rule fix, listing rules whose identical fixes were merged into it
+fn generate_diff<'a>(
+  m: NodeMatch<'a, StrDoc<SgLang>>,
+  rule: &RuleConfig<SgLang>,
+  fixer: &Fixer<SgLang>,
+  merged: &MergedIds,
+) -> Diff<'a> {
+  let key = (rule.id.as_str(), m.range());
+  let mut diff = Diff::generate(m, &rule.matcher, fixer);
+  if let Some(ids) = merged.get(&key) {
+    diff.merged_rule_ids = ids.iter().map(|id| id.to_string()).collect();
+  }
+  diff
+}
+
 fn match_rule_diff_on_file(
   path: &Path,
   matches: Vec<(&RuleConfig<SgLang>, NodeMatch<StrDoc<SgLang>>)>,
+  merged: &MergedIds,
   reporter: &mut impl Printer,
 ) -> Result<()> {
   let diffs = matches
     .into_iter()
     .filter_map(|(rule, m)| {
-      let fix = rule.matcher.fixer.as_ref()?;
-      let diff = Diff::generate(m, &rule.matcher, fix);
-      Some((diff, rule))
+      let fix = rule.matcher.get_fixer(&m)?;
+      Some((generate_diff(m, rule, fix, merged), rule))
     })
     .collect();
   reporter.print_rule_diffs(diffs, path)?;
@@ -292,18 +897,28 @@ fn match_rule_on_file(
   path: &Path,
   matches: Vec<NodeMatch<StrDoc<SgLang>>>,
   rule: &RuleConfig<SgLang>,
-  file_content: &String,
+  file_content: &str,
+  merged: &MergedIds,
   reporter: &mut impl Printer,
 ) -> Result<()> {
-  let matches = matches.into_iter();
   let file = SimpleFile::new(path.to_string_lossy(), file_content);
-  if let Some(fixer) = &rule.matcher.fixer {
-    let diffs = matches
-      .map(|m| (Diff::generate(m, &rule.matcher, fixer), rule))
-      .collect();
-    reporter.print_rule_diffs(diffs, path)?;
-  } else {
-    reporter.print_rule(matches, file, rule)?;
+  if rule.matcher.fixer.is_empty() {
+    reporter.print_rule(matches.into_iter(), file, rule)?;
+    return Ok(());
+  }
+  // matches without applicable fix are reported as plain findings
+  let mut diffs = vec![];
+  let mut unfixable = vec![];
+  for m in matches {
+    if let Some(fixer) = rule.matcher.get_fixer(&m) {
+      diffs.push((generate_diff(m, rule, fixer, merged), rule));
+    } else {
+      unfixable.push(m);
+    }
+  }
+  reporter.print_rule_diffs(diffs, path)?;
+  if !unfixable.is_empty() {
+    reporter.print_rule(unfixable.into_iter(), file, rule)?;
   }
   Ok(())
 }
@@ -312,6 +927,7 @@ fn match_rule_on_file(
 mod test {
   use super::*;
   use crate::print::ColorArg;
+  use regex::Regex;
   use std::fs::File;
   use std::io::Write;
   use tempfile::TempDir;
@@ -348,9 +964,12 @@ rule:
         no_ignore: vec![],
         paths: vec![PathBuf::from(".")],
         stdin: false,
+        stdin_filepath: None,
         follow: false,
         globs: vec![],
         threads: 0,
+        max_filesize: None,
+        no_skip_minified: false,
       },
       overwrite: OverwriteArgs {
         filter: None,
@@ -359,6 +978,8 @@ rule:
         info: None,
         hint: None,
         off: None,
+        severity: vec![],
+        strictness: None,
       },
       output: OutputArgs {
         interactive: false,
@@ -366,6 +987,7 @@ rule:
         update_all: false,
         color: ColorArg::Never,
         inspect: Default::default(),
+        output_file: None,
       },
       context: ContextArgs {
         before: 0,
@@ -373,6 +995,18 @@ rule:
         context: 0,
       },
       format: None,
+      stats: false,
+      dedup_fixes: false,
+      error_on: ErrorLevel::Error,
+      max_findings: None,
+      sort: None,
+      summary: false,
+      summary_by_dir: false,
+      baseline: None,
+      changed: None,
+      changed_lines: false,
+      report_unused_suppressions: false,
+      profile_rules: false,
     }
   }
 
@@ -413,6 +1047,28 @@ rule:
     assert!(run_with_config(arg, Err(anyhow::anyhow!("not found"))).is_ok());
   }
 
+  #[test]
+  fn test_scan_with_inline_rules_filter() {
+    let inline_rules = "{id: test, language: ts, rule: {pattern: readFileSync}}".to_string();
+    let mut overwrite = default_scan_arg().overwrite;
+    overwrite.filter = Some(Regex::new("^te").unwrap());
+    let arg = ScanArg {
+      inline_rules: Some(inline_rules.clone()),
+      overwrite,
+      ..default_scan_arg()
+    };
+    assert!(run_with_config(arg, Err(anyhow::anyhow!("not found"))).is_ok());
+    let mut overwrite = default_scan_arg().overwrite;
+    overwrite.filter = Some(Regex::new("no-match").unwrap());
+    let arg = ScanArg {
+      inline_rules: Some(inline_rules),
+      overwrite,
+      ..default_scan_arg()
+    };
+    let err = run_with_config(arg, Err(anyhow::anyhow!("not found"))).expect_err("should error");
+    assert!(err.is::<EC>());
+  }
+
   // baseline test for coverage
   #[test]
   fn test_scan_with_inline_rules_error() {
@@ -425,4 +1081,109 @@ rule:
     assert!(err.is::<EC>());
     assert_eq!(err.to_string(), "Cannot parse rule INLINE_RULES");
   }
+
+  #[test]
+  fn test_sort_findings() {
+    let rules = "{id: b, language: ts, severity: error, rule: {pattern: a}}
+---
+{id: a, language: ts, severity: warning, rule: {pattern: a}}";
+    let rules: Vec<RuleConfig<SgLang>> =
+      from_yaml_string(rules, &Default::default()).expect("should parse");
+    let order = |sort: SortBy| {
+      let mut findings = vec![];
+      for path in ["z.ts", "y.ts"] {
+        for rule in &rules {
+          let merged = HashMap::new();
+          findings.push(Findings::Matches(Path::new(path), "", rule, vec![], merged));
+        }
+      }
+      sort.sort(&mut findings);
+      findings
+        .iter()
+        .map(|f| format!("{}:{}", f.path().display(), f.rules()[0].id))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(
+      order(SortBy::Path),
+      ["y.ts:a", "y.ts:b", "z.ts:a", "z.ts:b"]
+    );
+    assert_eq!(
+      order(SortBy::Rule),
+      ["y.ts:a", "z.ts:a", "y.ts:b", "z.ts:b"]
+    );
+    assert_eq!(
+      order(SortBy::Severity),
+      ["y.ts:b", "z.ts:b", "y.ts:a", "z.ts:a"]
+    );
+  }
+
+  #[test]
+  fn test_finding_budget() {
+    let mut budget = FindingBudget::new(Some(3));
+    let mut findings = vec![1, 2];
+    budget.take(&mut findings);
+    assert_eq!(findings, [1, 2]);
+    assert!(!budget.is_exhausted());
+    let mut findings = vec![3, 4];
+    budget.take(&mut findings);
+    assert_eq!(findings, [3]);
+    assert!(budget.is_exhausted());
+    let mut budget = FindingBudget::new(None);
+    let mut findings = vec![1, 2];
+    budget.take(&mut findings);
+    assert_eq!(findings.len(), 2);
+    assert!(!budget.is_exhausted());
+  }
+
+  #[test]
+  fn test_error_level() {
+    assert!(ErrorLevel::Error.includes(&Severity::Error));
+    assert!(!ErrorLevel::Error.includes(&Severity::Warning));
+    assert!(ErrorLevel::Warning.includes(&Severity::Warning));
+    assert!(!ErrorLevel::Warning.includes(&Severity::Info));
+    assert!(ErrorLevel::Hint.includes(&Severity::Hint));
+    assert!(!ErrorLevel::Hint.includes(&Severity::Off));
+  }
+
+  #[test]
+  fn test_scan_with_rule_and_inline_rules() {
+    let dir = TempDir::new().unwrap();
+    let rule_path = dir.path().join("rule.yml");
+    std::fs::write(
+      &rule_path,
+      "id: rule-file\nlanguage: ts\nrule: {pattern: readFileSync}",
+    )
+    .unwrap();
+    let arg = ScanArg {
+      rule: Some(rule_path),
+      inline_rules: Some("{id: inline, language: ts, rule: {pattern: console.log}}".into()),
+      ..default_scan_arg()
+    };
+    let overwrite = RuleOverwrite::default();
+    let rules = arg
+      .cli_rules(&overwrite)
+      .unwrap()
+      .expect("should have rules");
+    let ids: Vec<_> = rules.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, ["rule-file", "inline"]);
+    assert!(default_scan_arg().cli_rules(&overwrite).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_inline_rules_from_stdin_conflict() {
+    let mut input = default_scan_arg().input;
+    input.stdin = true;
+    let arg = ScanArg {
+      inline_rules: Some("-".into()),
+      input,
+      ..default_scan_arg()
+    };
+    let Err(err) = arg.cli_rules(&RuleOverwrite::default()) else {
+      panic!("should error");
+    };
+    assert!(matches!(
+      err.downcast_ref::<EC>(),
+      Some(EC::StdInIsUsedByCode)
+    ));
+  }
 }
@@ -0,0 +1,269 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ast_grep_config::{PreScan, RuleCollection};
+use ast_grep_core::{Language, StrDoc};
+use clap::Args;
+use ignore::WalkParallel;
+
+use crate::config::ProjectConfig;
+use crate::lang::SgLang;
+use crate::print::{ColorArg, ColoredPrinter, Printer};
+use crate::utils::ErrorContext as EC;
+use crate::utils::{filter_file_interactive, CombinedScans, InputArgs, RuleOverwrite};
+use crate::utils::{FileLimits, FileTrace, Granularity, ScanTrace};
+use crate::utils::{Items, PathWorker, Worker};
+
+type AstGrep = ast_grep_core::AstGrep<StrDoc<SgLang>>;
+
+const IGNORE_TEXT: &str = "ast-grep-ignore";
+
+#[derive(Args)]
+pub struct SuppressArg {
+  /// Only suppress findings reported by the rule with RULE_ID.
+  ///
+  /// You can suppress findings of multiple rules by using the flag multiple times,
+  /// e.g., `--rule-id=RULE_1 --rule-id=RULE_2`. If no RULE_ID is provided,
+  /// findings of all rules in the project will be suppressed.
+  #[clap(long, action = clap::ArgAction::Append, value_name = "RULE_ID")]
+  rule_id: Vec<String>,
+
+  /// input related options
+  #[clap(flatten)]
+  input: InputArgs,
+}
+
+/// Insert `ast-grep-ignore` comments above every current finding in the project.
+///
+/// Existing violations are grandfathered so that new rules can be adopted gradually.
+pub fn run_suppress(arg: SuppressArg, project: Result<ProjectConfig>) -> Result<()> {
+  if arg.input.stdin {
+    return Err(anyhow::anyhow!(EC::StdInIsNotWritable));
+  }
+  let worker = SuppressWorker::try_new(arg, project?)?;
+  // suppress does not print findings, the printer is a mere placeholder
+  let printer = ColoredPrinter::stdout(ColorArg::Never);
+  worker.run_path(printer)
+}
+
+struct SuppressWorker {
+  arg: SuppressArg,
+  configs: RuleCollection<SgLang>,
+  file_limits: FileLimits,
+  trace: ScanTrace,
+}
+
+impl SuppressWorker {
+  fn try_new(arg: SuppressArg, project: ProjectConfig) -> Result<Self> {
+    let file_limits = arg.input.file_limits(Some(&project));
+    let (configs, rule_trace) = project.find_rules(RuleOverwrite::default())?;
+    for id in &arg.rule_id {
+      if configs.get_rule(id).is_none() {
+        return Err(anyhow::anyhow!(EC::RuleNotFound(id.clone())));
+      }
+    }
+    let trace = Granularity::default().scan_trace(rule_trace);
+    Ok(Self {
+      arg,
+      configs,
+      file_limits,
+      trace,
+    })
+  }
+
+  fn should_suppress(&self, rule_id: &str) -> bool {
+    self.arg.rule_id.is_empty() || self.arg.rule_id.iter().any(|id| id == rule_id)
+  }
+}
+
+impl Worker for SuppressWorker {
+  type Item = (PathBuf, AstGrep, PreScan);
+  fn consume_items<P: Printer>(&self, items: Items<Self::Item>, _: P) -> Result<()> {
+    let mut files: HashMap<PathBuf, BTreeMap<usize, Suppression>> = HashMap::new();
+    let mut scans = CombinedScans::default();
+    for (path, grep, pre_scan) in items {
+      let Some(delimiters) = grep.lang().comment_delimiters() else {
+        eprintln!(
+          "Warning: cannot suppress findings in {} because {} has no known comment syntax.",
+          path.display(),
+          grep.lang()
+        );
+        continue;
+      };
+      let rules = self.configs.get_rule_from_lang(&path, *grep.lang());
+      let scanned = scans.get(&rules).scan(&grep, pre_scan, false);
+      let lines = files.entry(path).or_default();
+      for (rule, matches) in scanned.matches {
+        if !self.should_suppress(&rule.id) {
+          continue;
+        }
+        for m in matches {
+          let line = m.start_pos().line();
+          let suppression = lines.entry(line).or_insert_with(|| Suppression {
+            delimiters,
+            rule_ids: BTreeSet::new(),
+          });
+          suppression.rule_ids.insert(rule.id.clone());
+        }
+      }
+    }
+    let mut finding_count = 0;
+    let mut file_count = 0;
+    for (path, lines) in files {
+      if lines.is_empty() {
+        continue;
+      }
+      let source = std::fs::read_to_string(&path).with_context(|| EC::WriteFile(path.clone()))?;
+      let new_source = insert_suppressions(&source, &lines);
+      std::fs::write(&path, new_source).with_context(|| EC::WriteFile(path.clone()))?;
+      finding_count += lines.len();
+      file_count += 1;
+    }
+    println!("Suppressed findings on {finding_count} line(s) in {file_count} file(s).");
+    Ok(())
+  }
+}
+
+impl PathWorker for SuppressWorker {
+  type State<'w> = CombinedScans<'w>;
+  fn get_trace(&self) -> &FileTrace {
+    &self.trace.inner.file_trace
+  }
+  fn build_walk(&self) -> Result<WalkParallel> {
+    let mut langs = HashSet::new();
+    self.configs.for_each_rule(|rule| {
+      langs.insert(rule.language);
+    });
+    self.arg.input.walk_langs(langs.into_iter())
+  }
+  fn get_threads(&self) -> usize {
+    self.arg.input.get_threads()
+  }
+  fn produce_item<'w>(
+    &'w self,
+    path: &Path,
+    scans: &mut CombinedScans<'w>,
+  ) -> Option<Vec<Self::Item>> {
+    let (configs, limits) = (&self.configs, &self.file_limits);
+    filter_file_interactive(path, configs, limits, &self.trace, scans)
+  }
+}
+
+/// The rules to suppress on one line, and how to write the comment.
+struct Suppression {
+  delimiters: (&'static str, &'static str),
+  rule_ids: BTreeSet<String>,
+}
+
+impl Suppression {
+  fn comment(&self, indent: &str, newline: &str) -> String {
+    let (open, close) = self.delimiters;
+    let ids: Vec<_> = self.rule_ids.iter().map(String::as_str).collect();
+    let ids = ids.join(", ");
+    if close.is_empty() {
+      format!("{indent}{open} {IGNORE_TEXT}: {ids}{newline}")
+    } else {
+      format!("{indent}{open} {IGNORE_TEXT}: {ids} {close}{newline}")
+    }
+  }
+
+  /// Merge rule ids from an existing suppression comment on its own line.
+  /// Returns false if the line is not such a comment.
+  fn merge_existing(&mut self, line: &str) -> bool {
+    let (open, close) = self.delimiters;
+    let Some(text) = line.trim().strip_prefix(open) else {
+      return false;
+    };
+    let Some(ids) = text.trim().strip_prefix(IGNORE_TEXT) else {
+      return false;
+    };
+    let Some(ids) = ids.trim().strip_prefix(':') else {
+      return false;
+    };
+    let ids = ids.trim().strip_suffix(close).unwrap_or(ids);
+    let ids = ids.split(',').map(str::trim).filter(|id| !id.is_empty());
+    self.rule_ids.extend(ids.map(String::from));
+    true
+  }
+}
+
+fn insert_suppressions(source: &str, suppressions: &BTreeMap<usize, Suppression>) -> String {
+  let mut lines: Vec<String> = source.split_inclusive('\n').map(String::from).collect();
+  // insert from the bottom so line numbers of the rest findings stay valid
+  for (&line_num, suppression) in suppressions.iter().rev() {
+    let Some(line) = lines.get(line_num) else {
+      continue;
+    };
+    let indent_len = line.len() - line.trim_start().len();
+    let indent = line[..indent_len].to_string();
+    let newline = if line.ends_with("\r\n") { "\r\n" } else { "\n" };
+    if line_num > 0 {
+      let mut merged = Suppression {
+        delimiters: suppression.delimiters,
+        rule_ids: suppression.rule_ids.clone(),
+      };
+      let prev = &lines[line_num - 1];
+      if merged.merge_existing(prev) {
+        let prev_indent_len = prev.len() - prev.trim_start().len();
+        let prev_indent = prev[..prev_indent_len].to_string();
+        let prev_newline = if prev.ends_with("\r\n") { "\r\n" } else { "\n" };
+        lines[line_num - 1] = merged.comment(&prev_indent, prev_newline);
+        continue;
+      }
+    }
+    lines.insert(line_num, suppression.comment(&indent, newline));
+  }
+  lines.concat()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn suppress(
+    delimiters: (&'static str, &'static str),
+    ids: &[(usize, &str)],
+  ) -> BTreeMap<usize, Suppression> {
+    let mut ret = BTreeMap::new();
+    for &(line, id) in ids {
+      let suppression: &mut Suppression = ret.entry(line).or_insert_with(|| Suppression {
+        delimiters,
+        rule_ids: BTreeSet::new(),
+      });
+      suppression.rule_ids.insert(id.to_string());
+    }
+    ret
+  }
+
+  #[test]
+  fn test_insert_suppressions() {
+    let source = "fn test() {\n  Some(123)\n}\n";
+    let lines = suppress(("//", ""), &[(1, "b"), (1, "a")]);
+    let ret = insert_suppressions(source, &lines);
+    assert_eq!(
+      ret,
+      "fn test() {\n  // ast-grep-ignore: a, b\n  Some(123)\n}\n"
+    );
+  }
+
+  #[test]
+  fn test_insert_block_comment() {
+    let source = "<div>\n  <img>\n</div>";
+    let lines = suppress(("<!--", "-->"), &[(1, "alt")]);
+    let ret = insert_suppressions(source, &lines);
+    assert_eq!(
+      ret,
+      "<div>\n  <!-- ast-grep-ignore: alt -->\n  <img>\n</div>"
+    );
+  }
+
+  #[test]
+  fn test_merge_existing_suppression() {
+    let source = "a = 1\n# ast-grep-ignore: old\nb = 2\r\nc = 3";
+    let lines = suppress(("#", ""), &[(0, "new"), (2, "new"), (3, "new")]);
+    let ret = insert_suppressions(source, &lines);
+    let expected = "# ast-grep-ignore: new\na = 1\n# ast-grep-ignore: new, old\nb = 2\r\n# ast-grep-ignore: new\nc = 3";
+    assert_eq!(ret, expected);
+  }
+}
@@ -1,18 +1,24 @@
+use crate::config::ProjectConfig;
 use crate::lang::SgLang;
-use crate::print::{ColorArg, JsonStyle};
+use crate::print::{ColorArg, ColorChoice, JsonStyle, OutputWriter};
 use crate::utils::ErrorContext as EC;
-use crate::utils::Granularity;
+use crate::utils::{parse_file_size, FileLimits, Granularity};
 
 use anyhow::{Context, Result};
-use clap::{Args, ValueEnum};
+use ast_grep_config::Severity;
+use ast_grep_core::MatchStrictness;
+use clap::{builder::PossibleValue, Args, ValueEnum};
 use ignore::{
+  gitignore::{Gitignore, GitignoreBuilder},
   overrides::{Override, OverrideBuilder},
-  WalkBuilder, WalkParallel,
+  types::Types,
+  DirEntry, Match, WalkBuilder, WalkParallel,
 };
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 /// input related options
 #[derive(Args)]
@@ -42,6 +48,14 @@ pub struct InputArgs {
   #[clap(long)]
   pub stdin: bool,
 
+  /// Treat code from StdIn as the content of FILE.
+  ///
+  /// The language is inferred from FILE if `--lang` is not given, rules apply according to
+  /// their `files` and `ignores` globs, and FILE is reported as the path of matches.
+  /// It is useful for editor integrations like format on save. The file does not need to exist.
+  #[clap(long, requires = "stdin", value_name = "FILE")]
+  pub stdin_filepath: Option<PathBuf>,
+
   /// Include or exclude file paths.
   ///
   /// Include or exclude files and directories for searching that match the
@@ -59,10 +73,41 @@ pub struct InputArgs {
   /// heuristics.
   #[clap(short = 'j', long, default_value = "0", value_name = "NUM")]
   pub threads: usize,
+
+  /// Skip files larger than SIZE, e.g. generated or minified code.
+  ///
+  /// SIZE is in bytes and accepts K, M and G suffixes, like `10M`.
+  /// It overrides `maxFileSize` in sgconfig.yml. By default, ast-grep skips files
+  /// that are larger than 3MB and have more than 200,000 lines at the same time.
+  #[clap(long, value_name = "SIZE", value_parser = parse_file_size)]
+  pub max_filesize: Option<usize>,
+
+  /// Search minified files, which are skipped by default.
+  ///
+  /// A file is regarded as minified if it has very long lines and little whitespace.
+  /// Binary files containing NUL bytes are always skipped.
+  #[clap(long)]
+  pub no_skip_minified: bool,
 }
 
 impl InputArgs {
-  fn get_threads(&self) -> usize {
+  /// Limits of file size from the project config, with command line flags taking precedence.
+  pub fn file_limits(&self, project: Option<&ProjectConfig>) -> FileLimits {
+    let mut limits = project.map(|p| p.file_limits).unwrap_or_default();
+    if let Some(size) = self.max_filesize {
+      limits.max_size = Some(size);
+    }
+    limits.skip_minified = !self.no_skip_minified;
+    limits
+  }
+  /// The path reported for code from StdIn.
+  pub fn stdin_path(&self) -> PathBuf {
+    self
+      .stdin_filepath
+      .clone()
+      .unwrap_or_else(|| PathBuf::from("STDIN"))
+  }
+  pub fn get_threads(&self) -> usize {
     if self.threads == 0 {
       std::thread::available_parallelism()
         .map_or(1, |n| n.get())
@@ -85,25 +130,34 @@ impl InputArgs {
   }
 
   pub fn walk_langs(&self, langs: impl Iterator<Item = SgLang>) -> Result<WalkParallel> {
-    let types = SgLang::file_types_for_langs(langs);
+    self.walk_langs_in(&self.paths, langs)
+  }
+
+  /// Like `walk_langs`, but walks `paths` instead of the input paths.
+  pub fn walk_langs_in(
+    &self,
+    paths: &[PathBuf],
+    langs: impl Iterator<Item = SgLang>,
+  ) -> Result<WalkParallel> {
+    let langs: Vec<_> = langs.collect();
+    let types = SgLang::file_types_for_langs(langs.iter().copied());
     let threads = self.get_threads();
     Ok(
       NoIgnore::disregard(&self.no_ignore)
-        .walk(&self.paths)
+        .walk_with_filter(paths, type_filter(types, langs))
         .threads(threads)
         .follow_links(self.follow)
-        .types(types)
         .build_parallel(),
     )
   }
 
   pub fn walk_lang(&self, lang: SgLang) -> WalkParallel {
     let threads = self.get_threads();
+    let filter = type_filter(lang.augmented_file_type(), vec![lang]);
     NoIgnore::disregard(&self.no_ignore)
-      .walk(&self.paths)
+      .walk_with_filter(&self.paths, filter)
       .threads(threads)
       .follow_links(self.follow)
-      .types(lang.augmented_file_type())
       .build_parallel()
   }
 
@@ -117,6 +171,22 @@ impl InputArgs {
   }
 }
 
+/// Walk files of the types and scripts without extension whose shebang names one of `langs`.
+/// `WalkBuilder::types` would skip the scripts before their first line can be read.
+fn type_filter(types: Types, langs: Vec<SgLang>) -> impl Fn(&DirEntry) -> bool + Send + Sync {
+  move |entry| {
+    let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+    match types.matched(entry.path(), is_dir) {
+      // files matching no type
+      Match::Ignore(glob) if glob.file_type_def().is_none() => {
+        SgLang::from_script(entry.path()).is_some_and(|lang| langs.contains(&lang))
+      }
+      Match::Ignore(_) => false,
+      Match::None | Match::Whitelist(_) => true,
+    }
+  }
+}
+
 /// output related options
 #[derive(Args)]
 pub struct OutputArgs {
@@ -165,8 +235,16 @@ pub struct OutputArgs {
   /// This flag helps user to observe ast-grep's internal filtering of files and rules.
   /// Inspection will output how many and why files and rules are scanned and skipped.
   /// Inspection outputs to stderr and does not affect the result of the search.
+  /// Use `json` to get per-file and per-rule timing as JSON lines, e.g. to find slow rules.
   #[clap(long, default_value = "nothing", value_name = "GRANULARITY")]
   pub inspect: Granularity,
+
+  /// Write output to FILE instead of stdout.
+  ///
+  /// Parent directories of the FILE are created if missing, and colors are disabled.
+  /// It is useful to publish reports as CI artifacts.
+  #[clap(long = "output", value_name = "FILE", conflicts_with = "interactive")]
+  pub output_file: Option<PathBuf>,
 }
 
 impl OutputArgs {
@@ -174,6 +252,22 @@ impl OutputArgs {
   pub fn needs_interactive(&self) -> bool {
     self.interactive || self.update_all
   }
+
+  // colors are never written to the output file
+  pub fn color_choice(&self) -> ColorChoice {
+    if self.output_file.is_some() {
+      ColorChoice::Never
+    } else {
+      self.color.into()
+    }
+  }
+
+  pub fn writer(&self) -> Result<OutputWriter> {
+    match &self.output_file {
+      Some(path) => OutputWriter::file(path),
+      None => Ok(OutputWriter::stdout(self.color_choice())),
+    }
+  }
 }
 
 /// context related options
@@ -227,7 +321,7 @@ impl ContextArgs {
 pub enum IgnoreFile {
   /// Search hidden files and directories. By default, hidden files and directories are skipped.
   Hidden,
-  /// Don't respect .ignore files.
+  /// Don't respect .ignore and .astgrepignore files, and `ignores` in sgconfig.yml.
   /// This does *not* affect whether ast-grep will ignore files and directories whose names begin with a dot.
   /// For that, use --no-ignore hidden.
   Dot,
@@ -244,6 +338,34 @@ pub enum IgnoreFile {
   Vcs,
 }
 
+/// ast-grep specific ignore file, in the same format as .gitignore.
+const IGNORE_FILE: &str = ".astgrepignore";
+
+// gitignore-style patterns from `ignores` in sgconfig.yml, rooted at the project directory
+static PROJECT_IGNORES: OnceLock<Gitignore> = OnceLock::new();
+
+/// Register `ignores` in sgconfig.yml. It must be called before walking any files.
+pub fn register_project_ignores(project_dir: &Path, ignores: &[String]) -> Result<()> {
+  if ignores.is_empty() {
+    return Ok(());
+  }
+  let root = std::env::current_dir()?.join(project_dir);
+  let mut builder = GitignoreBuilder::new(root);
+  for line in ignores {
+    builder
+      .add_line(None, line)
+      .context(EC::ParseConfiguration)?;
+  }
+  let ignores = builder.build().context(EC::ParseConfiguration)?;
+  // project config is set up once per process, later registrations are ignored
+  let _ = PROJECT_IGNORES.set(ignores);
+  Ok(())
+}
+
+fn project_ignores() -> Option<&'static Gitignore> {
+  PROJECT_IGNORES.get()
+}
+
 #[derive(Default)]
 pub struct NoIgnore {
   disregard_hidden: bool,
@@ -272,6 +394,15 @@ impl NoIgnore {
   }
 
   pub fn walk(&self, path: &[PathBuf]) -> WalkBuilder {
+    self.walk_with_filter(path, |_| true)
+  }
+
+  /// Like `walk`, but also skips entries rejected by `filter`.
+  /// `WalkBuilder::filter_entry` keeps only the last filter, so it is combined with project ignores.
+  pub fn walk_with_filter<F>(&self, path: &[PathBuf], filter: F) -> WalkBuilder
+  where
+    F: Fn(&DirEntry) -> bool + Send + Sync + 'static,
+  {
     let mut paths = path.iter();
     let mut builder = WalkBuilder::new(paths.next().expect("non empty"));
     for path in paths {
@@ -284,6 +415,20 @@ impl NoIgnore {
       .git_global(!self.disregard_vcs && !self.disregard_global)
       .git_ignore(!self.disregard_vcs)
       .git_exclude(!self.disregard_vcs && !self.disregard_exclude);
+    let mut ignores = None;
+    if !self.disregard_dot {
+      builder.add_custom_ignore_filename(IGNORE_FILE);
+      ignores = project_ignores();
+    }
+    // patterns are relative to project dir while walked paths are relative to cwd
+    let cwd = std::env::current_dir().unwrap_or_default();
+    builder.filter_entry(move |entry| {
+      let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+      let ignored = ignores
+        .as_ref()
+        .is_some_and(|ignores| ignores.matched(cwd.join(entry.path()), is_dir).is_ignore());
+      !ignored && filter(entry)
+    });
     builder
   }
 }
@@ -294,8 +439,13 @@ pub struct OverwriteArgs {
   /// Scan the codebase with rules with ids matching REGEX.
   ///
   /// This flags conflicts with --rule. It is useful to scan with a subset of rules from a large
-  /// set of rule definitions within a project.
-  #[clap(long, conflicts_with = "rule", value_name = "REGEX")]
+  /// set of rule definitions within a project, or from multiple rules in --inline-rules.
+  #[clap(
+    long,
+    visible_alias = "rule-filter",
+    conflicts_with = "rule",
+    value_name = "REGEX"
+  )]
   pub filter: Option<Regex>,
   /// Set rule severity to error
   ///
@@ -332,12 +482,90 @@ pub struct OverwriteArgs {
   /// Note, this flag must use `=` to specify its value.
   #[clap(long, action = clap::ArgAction::Append, value_name = "RULE_ID", num_args(0..), require_equals = true)]
   pub off: Option<Vec<String>>,
+  /// Set severity of rules matching RULE_ID to SEVERITY.
+  ///
+  /// SEVERITY can be one of error, warning, info, hint or off. RULE_ID can contain `*` as wildcard
+  /// to match multiple rules, e.g., `--severity 'no-*=off'`. You can use the flag multiple times and
+  /// the later one takes precedence. Rules specified by exact id via `--error=RULE_ID` and alike are not affected.
+  #[clap(long, action = clap::ArgAction::Append, value_name = "RULE_ID=SEVERITY", value_parser = parse_severity_overwrite)]
+  pub severity: Vec<SeverityOverwrite>,
+  /// Override the strictness of all pattern rules.
+  ///
+  /// Patterns in rules, utility rules and rewriters are matched with the given strictness
+  /// instead of the one in rule files, e.g., `--strictness signature` to match function calls
+  /// regardless of argument text.
+  #[clap(long)]
+  pub strictness: Option<Strictness>,
+}
+
+/// Pattern strictness accepted by `--strictness`.
+#[derive(Clone, Debug)]
+pub struct Strictness(pub MatchStrictness);
+impl ValueEnum for Strictness {
+  fn value_variants<'a>() -> &'a [Self] {
+    use MatchStrictness as M;
+    &[
+      Strictness(M::Cst),
+      Strictness(M::Smart),
+      Strictness(M::Ast),
+      Strictness(M::Relaxed),
+      Strictness(M::Signature),
+    ]
+  }
+  fn to_possible_value(&self) -> Option<PossibleValue> {
+    use MatchStrictness as M;
+    Some(match &self.0 {
+      M::Cst => PossibleValue::new("cst").help("Match exact all node"),
+      M::Smart => PossibleValue::new("smart").help("Match all node except source trivial nodes"),
+      M::Ast => PossibleValue::new("ast").help("Match only ast nodes"),
+      M::Relaxed => PossibleValue::new("relaxed").help("Match ast node except comments"),
+      M::Signature => {
+        PossibleValue::new("signature").help("Match ast node except comments, without text")
+      }
+    })
+  }
+}
+
+/// A rule id pattern with the severity to overwrite, parsed from `RULE_ID=SEVERITY`.
+#[derive(Clone, Debug)]
+pub struct SeverityOverwrite {
+  /// regex converted from the wildcard rule id pattern
+  pub pattern: Regex,
+  pub severity: Severity,
+}
+
+fn parse_severity_overwrite(s: &str) -> std::result::Result<SeverityOverwrite, String> {
+  let Some((id, level)) = s.rsplit_once('=') else {
+    return Err(format!("expect RULE_ID=SEVERITY but found `{s}`"));
+  };
+  let severity = match level {
+    "error" => Severity::Error,
+    "warning" => Severity::Warning,
+    "info" => Severity::Info,
+    "hint" => Severity::Hint,
+    "off" => Severity::Off,
+    _ => {
+      return Err(format!(
+        "invalid severity `{level}`, expect one of error, warning, info, hint or off"
+      ))
+    }
+  };
+  if id.is_empty() {
+    return Err("RULE_ID should not be empty".into());
+  }
+  let escaped: Vec<_> = id.split('*').map(regex::escape).collect();
+  let pattern = Regex::new(&format!("^{}$", escaped.join(".*"))).map_err(|e| e.to_string())?;
+  Ok(SeverityOverwrite { pattern, severity })
 }
 
 impl OverwriteArgs {
   /// Returns true if none rule is turned off on CLI nor filtered out
   pub fn include_all_rules(&self) -> bool {
-    self.filter.is_none() && self.off.is_none()
+    let any_off = self
+      .severity
+      .iter()
+      .any(|s| matches!(s.severity, Severity::Off));
+    self.filter.is_none() && self.off.is_none() && !any_off
   }
 }
 
@@ -352,8 +580,11 @@ mod test {
       follow: true,
       no_ignore: vec![IgnoreFile::Dot, IgnoreFile::Exclude],
       stdin: false,
+      stdin_filepath: None,
       globs: vec!["*.rs".to_string(), "!*.toml".to_string()],
       threads: 0,
+      max_filesize: None,
+      no_skip_minified: false,
     };
     assert!(input.build_globs().is_ok());
     let input = InputArgs {
@@ -361,9 +592,33 @@ mod test {
       follow: true,
       no_ignore: vec![IgnoreFile::Dot, IgnoreFile::Exclude],
       stdin: false,
+      stdin_filepath: None,
       globs: vec!["*.{rs".to_string()],
       threads: 0,
+      max_filesize: None,
+      no_skip_minified: false,
     };
     assert!(input.build_globs().is_err());
   }
+
+  #[test]
+  fn test_parse_severity_overwrite() {
+    let ret = parse_severity_overwrite("no-console=off").expect("should parse");
+    assert!(matches!(ret.severity, Severity::Off));
+    assert!(ret.pattern.is_match("no-console"));
+    assert!(!ret.pattern.is_match("no-console-log"));
+    let ret = parse_severity_overwrite("no-*=warning").expect("should parse");
+    assert!(matches!(ret.severity, Severity::Warning));
+    assert!(ret.pattern.is_match("no-console"));
+    assert!(ret.pattern.is_match("no-"));
+    assert!(!ret.pattern.is_match("yes-no-console"));
+    let ret = parse_severity_overwrite("*=error").expect("should parse");
+    assert!(ret.pattern.is_match("anything"));
+    // regex special chars are escaped
+    let ret = parse_severity_overwrite("a.b=hint").expect("should parse");
+    assert!(!ret.pattern.is_match("axb"));
+    assert!(parse_severity_overwrite("no-console").is_err());
+    assert!(parse_severity_overwrite("no-console=fatal").is_err());
+    assert!(parse_severity_overwrite("=error").is_err());
+  }
 }
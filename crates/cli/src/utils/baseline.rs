@@ -0,0 +1,191 @@
+use crate::lang::SgLang;
+use crate::utils::ErrorContext as EC;
+
+use anyhow::{Context, Result};
+use ast_grep_config::RuleConfig;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// FNV-1a hash. Fingerprint must be stable across runs and platforms,
+/// so we cannot use std's DefaultHasher here.
+pub fn fnv_hash(parts: &[&[u8]]) -> u64 {
+  const OFFSET: u64 = 0xcbf29ce484222325;
+  const PRIME: u64 = 0x100000001b3;
+  let mut hash = OFFSET;
+  for part in parts {
+    for byte in part.iter() {
+      hash ^= *byte as u64;
+      hash = hash.wrapping_mul(PRIME);
+    }
+    // separate parts so that ("ab", "c") and ("a", "bc") differ
+    hash ^= 0xff;
+    hash = hash.wrapping_mul(PRIME);
+  }
+  hash
+}
+
+/// Fingerprint of a finding, computed from rule id, file path and matched text.
+/// Line numbers are not used and whitespace in the text is normalized,
+/// so the fingerprint survives edits elsewhere in the file and reformatting.
+pub fn fingerprint(rule_id: &str, path: &str, text: &str) -> u64 {
+  let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+  fnv_hash(&[rule_id.as_bytes(), path.as_bytes(), text.as_bytes()])
+}
+
+/// Normalize path separator so the baseline can be shared across platforms.
+pub fn normalize_path(path: &Path) -> String {
+  path.to_string_lossy().replace('\\', "/")
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BaselineEntry {
+  rule_id: String,
+  file: String,
+  fingerprint: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+  findings: Vec<BaselineEntry>,
+}
+
+/// Baseline suppresses known findings so that only new issues are reported.
+/// If the baseline file does not exist yet, all findings are recorded into it.
+pub struct Baseline {
+  path: PathBuf,
+  state: Mutex<BaselineState>,
+}
+
+enum BaselineState {
+  /// remaining occurrences of known fingerprints
+  Known(HashMap<String, usize>),
+  /// the baseline file does not exist, collect all findings
+  Recording(Vec<BaselineEntry>),
+}
+
+impl Baseline {
+  pub fn open(path: &Path) -> Result<Self> {
+    let state = if path.exists() {
+      let content =
+        std::fs::read_to_string(path).with_context(|| EC::ReadBaseline(path.to_path_buf()))?;
+      let file: BaselineFile =
+        serde_json::from_str(&content).with_context(|| EC::ParseBaseline(path.to_path_buf()))?;
+      let mut known = HashMap::new();
+      for entry in file.findings {
+        *known.entry(entry.fingerprint).or_insert(0) += 1;
+      }
+      BaselineState::Known(known)
+    } else {
+      BaselineState::Recording(vec![])
+    };
+    Ok(Self {
+      path: path.to_path_buf(),
+      state: Mutex::new(state),
+    })
+  }
+
+  /// Returns true if the finding is not in the baseline.
+  /// Findings found when recording baseline are not new.
+  pub fn is_new(&self, path: &Path, rule: &RuleConfig<SgLang>, text: &str) -> bool {
+    let file = normalize_path(path);
+    let fingerprint = format!("{:016x}", fingerprint(&rule.id, &file, text));
+    let mut state = self
+      .state
+      .lock()
+      .expect("baseline lock should not be poisoned");
+    match &mut *state {
+      BaselineState::Known(known) => match known.get_mut(&fingerprint) {
+        Some(count) if *count > 0 => {
+          *count -= 1;
+          false
+        }
+        _ => true,
+      },
+      BaselineState::Recording(entries) => {
+        entries.push(BaselineEntry {
+          rule_id: rule.id.clone(),
+          file,
+          fingerprint,
+        });
+        false
+      }
+    }
+  }
+
+  /// Write the recorded findings to the baseline file if it is newly created.
+  pub fn save(&self) -> Result<()> {
+    let mut state = self
+      .state
+      .lock()
+      .expect("baseline lock should not be poisoned");
+    let BaselineState::Recording(entries) = &mut *state else {
+      return Ok(());
+    };
+    let findings = std::mem::take(entries);
+    let count = findings.len();
+    let content = serde_json::to_string_pretty(&BaselineFile { findings })?;
+    std::fs::write(&self.path, content)
+      .with_context(|| EC::WriteBaseline(self.path.to_path_buf()))?;
+    eprintln!(
+      "Baseline with {count} finding(s) is written to {}",
+      self.path.display()
+    );
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+  use tempfile::TempDir;
+
+  fn make_rule() -> RuleConfig<SgLang> {
+    let globals = GlobalRules::default();
+    let rule = "{id: test, language: ts, rule: {pattern: console.log($A)}}";
+    from_yaml_string(rule, &globals).unwrap().pop().unwrap()
+  }
+
+  #[test]
+  fn test_fingerprint() {
+    let a = fingerprint("test", "a.ts", "foo(1,\n  2)");
+    let b = fingerprint("test", "a.ts", "foo(1, 2)");
+    assert_eq!(a, b);
+    assert_ne!(a, fingerprint("test", "b.ts", "foo(1, 2)"));
+    assert_ne!(a, fingerprint("test2", "a.ts", "foo(1, 2)"));
+  }
+
+  #[test]
+  fn test_record_and_suppress() -> Result<()> {
+    let dir = TempDir::new()?;
+    let file = dir.path().join("baseline.json");
+    let rule = make_rule();
+    let path = Path::new("src/a.ts");
+    let baseline = Baseline::open(&file)?;
+    assert!(!baseline.is_new(path, &rule, "console.log(1)"));
+    assert!(!baseline.is_new(path, &rule, "console.log(a, b)"));
+    baseline.save()?;
+    assert!(file.exists());
+    let baseline = Baseline::open(&file)?;
+    // known findings are suppressed up to the recorded occurrences
+    assert!(!baseline.is_new(path, &rule, "console.log(1)"));
+    assert!(!baseline.is_new(path, &rule, "console.log(a,\n  b)"));
+    assert!(baseline.is_new(path, &rule, "console.log(1)"));
+    assert!(baseline.is_new(path, &rule, "console.log(2)"));
+    assert!(baseline.is_new(Path::new("src/b.ts"), &rule, "console.log(1)"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_invalid_baseline() -> Result<()> {
+    let dir = TempDir::new()?;
+    let file = dir.path().join("baseline.json");
+    std::fs::write(&file, "not json")?;
+    assert!(Baseline::open(&file).is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,183 @@
+use crate::utils::ErrorContext as EC;
+
+use anyhow::{Context, Result};
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Files changed since the merge-base of a git ref and HEAD, with the changed line ranges of each file.
+/// Untracked files are included and regarded as changed entirely.
+pub struct ChangedFiles {
+  /// canonical path to zero-based line ranges, None means the whole file
+  files: HashMap<PathBuf, Option<Vec<Range<usize>>>>,
+}
+
+impl ChangedFiles {
+  pub fn from_git(reference: &str) -> Result<Self> {
+    let context = || EC::GitDiff(reference.to_string());
+    let toplevel = run_git(&["rev-parse", "--show-toplevel"]).with_context(context)?;
+    let toplevel = PathBuf::from(toplevel.trim());
+    let toplevel = toplevel.canonicalize().unwrap_or(toplevel);
+    // compare against the fork point so changes made upstream after it are not reported
+    let base = run_git(&["merge-base", reference, "HEAD"]).with_context(context)?;
+    let diff = run_git(&[
+      "diff",
+      "--unified=0",
+      "--no-color",
+      "--no-ext-diff",
+      "--no-renames",
+      "--src-prefix=a/",
+      "--dst-prefix=b/",
+      base.trim(),
+      "--",
+    ])
+    .with_context(context)?;
+    let untracked = run_git(&["ls-files", "--others", "--exclude-standard", "--full-name"])
+      .with_context(context)?;
+    let mut files: HashMap<_, _> = parse_diff(&diff)
+      .into_iter()
+      .map(|(path, ranges)| (toplevel.join(path), Some(ranges)))
+      .collect();
+    for path in untracked.lines().filter(|l| !l.is_empty()) {
+      files.insert(toplevel.join(path), None);
+    }
+    Ok(Self { files })
+  }
+
+  fn lookup(&self, path: &Path) -> Option<&Option<Vec<Range<usize>>>> {
+    let path = path.canonicalize().ok()?;
+    self.files.get(&path)
+  }
+
+  pub fn contains(&self, path: &Path) -> bool {
+    self.lookup(path).is_some()
+  }
+
+  /// Whether the zero-based, inclusive line span overlaps with changed lines of the file.
+  pub fn touches_lines(&self, path: &Path, start: usize, end: usize) -> bool {
+    match self.lookup(path) {
+      Some(Some(ranges)) => ranges.iter().any(|r| r.start <= end && start < r.end),
+      Some(None) => true,
+      None => false,
+    }
+  }
+}
+
+fn run_git(args: &[&str]) -> Result<String> {
+  let output = Command::new("git").args(args).output()?;
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    return Err(anyhow::anyhow!("{}", stderr.trim()));
+  }
+  Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Parse `git diff --unified=0` output into changed files and their added line ranges.
+/// Deleted files are skipped. Files with only deleted lines have no ranges.
+fn parse_diff(diff: &str) -> Vec<(PathBuf, Vec<Range<usize>>)> {
+  let mut ret: Vec<(PathBuf, Vec<Range<usize>>)> = vec![];
+  let mut in_file = false;
+  for line in diff.lines() {
+    if line.starts_with("diff --git ") {
+      in_file = false;
+    } else if let Some(path) = line.strip_prefix("+++ ") {
+      in_file = path != "/dev/null";
+      if let Some(path) = path.strip_prefix("b/").filter(|_| in_file) {
+        ret.push((PathBuf::from(path), vec![]));
+      }
+    } else if let Some(hunk) = line.strip_prefix("@@ ") {
+      let Some((_, ranges)) = ret.last_mut().filter(|_| in_file) else {
+        continue;
+      };
+      ranges.extend(parse_hunk_header(hunk));
+    }
+  }
+  ret
+}
+
+/// Parse the new file range of a hunk header like `-1,2 +3,4 @@`.
+fn parse_hunk_header(hunk: &str) -> Option<Range<usize>> {
+  let new_range = hunk.split(' ').find_map(|s| s.strip_prefix('+'))?;
+  let (start, count) = match new_range.split_once(',') {
+    Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+    None => (new_range.parse::<usize>().ok()?, 1),
+  };
+  if count == 0 {
+    return None;
+  }
+  // hunk line number is one-based
+  let start = start.saturating_sub(1);
+  Some(start..start + count)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  const DIFF: &str = "\
+diff --git a/src/a.ts b/src/a.ts
+index 1111111..2222222 100644
+--- a/src/a.ts
++++ b/src/a.ts
+@@ -1 +1 @@
+-let a = 1
++let a = 2
+@@ -10,0 +11,3 @@ function foo() {
++console.log(1)
++console.log(2)
++console.log(3)
+@@ -20,2 +23,0 @@
+-removed
+-removed
+diff --git a/old.ts b/old.ts
+deleted file mode 100644
+--- a/old.ts
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+diff --git a/new.ts b/new.ts
+new file mode 100644
+--- /dev/null
++++ b/new.ts
+@@ -0,0 +1,2 @@
++a
++b
+";
+
+  #[test]
+  fn test_parse_hunk_header() {
+    assert_eq!(parse_hunk_header("-1 +1 @@"), Some(0..1));
+    assert_eq!(parse_hunk_header("-10,0 +11,3 @@ fn"), Some(10..13));
+    assert_eq!(parse_hunk_header("-20,2 +23,0 @@"), None);
+    assert_eq!(parse_hunk_header("garbage"), None);
+  }
+
+  #[test]
+  fn test_parse_diff() {
+    let files = parse_diff(DIFF);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].0, PathBuf::from("src/a.ts"));
+    assert_eq!(files[0].1, vec![0..1, 10..13]);
+    assert_eq!(files[1].0, PathBuf::from("new.ts"));
+    assert_eq!(files[1].1, vec![0..2]);
+  }
+
+  #[test]
+  fn test_touches_lines() {
+    let dir = tempfile::tempdir().expect("should create dir");
+    let path = dir.path().join("a.ts");
+    std::fs::write(&path, "").expect("should write");
+    let canonical = path.canonicalize().expect("should exist");
+    let mut files = HashMap::new();
+    files.insert(canonical, Some(std::iter::once(10..13).collect()));
+    let changed = ChangedFiles { files };
+    assert!(changed.contains(&path));
+    assert!(changed.touches_lines(&path, 12, 14));
+    assert!(changed.touches_lines(&path, 5, 10));
+    assert!(!changed.touches_lines(&path, 13, 14));
+    assert!(!changed.touches_lines(&path, 0, 9));
+    assert!(!changed.contains(&dir.path().join("b.ts")));
+  }
+}
@@ -1,6 +1,9 @@
+use super::ErrorContext as EC;
 use crate::lang::SgLang;
 use ansi_term::Style;
+use anyhow::{Context, Result};
 use ast_grep_core::{language::TSLanguage, matcher::PatternNode, meta_var::MetaVariable, Pattern};
+use ast_grep_core::{AstGrep, Node, StrDoc};
 use ast_grep_language::Language;
 use clap::ValueEnum;
 use tree_sitter as ts;
@@ -15,15 +18,14 @@ pub enum DebugFormat {
   Cst,
   /// Print the query in S-expression format
   Sexp,
+  /// Print the query in Pattern format and explain why it does not match the files passed as paths
+  Match,
 }
 impl DebugFormat {
   pub fn debug_pattern(&self, pattern: &Pattern<SgLang>, lang: SgLang, colored: bool) {
     match self {
-      DebugFormat::Pattern => {
-        let lang = lang.get_ts_language();
-        let mut ret = String::new();
-        let fmt = DumpFmt::named(colored);
-        if dump_pattern(&pattern.node, &lang, &fmt, 0, &mut ret).is_ok() {
+      DebugFormat::Pattern | DebugFormat::Match => {
+        if let Some(ret) = format_pattern(pattern, lang, colored) {
           eprintln!("Debug Pattern:\n{}", ret);
         } else {
           eprintln!("unexpected error in writing pattern string");
@@ -36,26 +38,272 @@ impl DebugFormat {
   }
 
   pub fn debug_tree(&self, src: &str, lang: SgLang, colored: bool) {
-    let root = lang.ast_grep(src);
+    let dumped = self.dump_tree(src, lang, colored);
     match self {
-      DebugFormat::Pattern => {
+      DebugFormat::Pattern | DebugFormat::Match => {
         debug_assert!(false, "debug_tree cannot be called with Pattern")
       }
-      DebugFormat::Sexp => {
-        eprintln!("Debug Sexp:\n{}", root.root().to_sexp());
+      DebugFormat::Sexp => eprintln!("Debug Sexp:\n{dumped}"),
+      DebugFormat::Ast => eprintln!("Debug AST:\n{dumped}"),
+      DebugFormat::Cst => eprintln!("Debug CST:\n{dumped}"),
+    }
+  }
+
+  /// Parse the source as a pattern or a tree and print it in the format.
+  pub fn dump(&self, src: &str, lang: SgLang, colored: bool) -> Result<String> {
+    if matches!(self, DebugFormat::Match) {
+      anyhow::bail!("`match` format needs a pattern, please use `sg run --debug-query=match`");
+    }
+    if !matches!(self, DebugFormat::Pattern) {
+      return Ok(self.dump_tree(src, lang, colored));
+    }
+    let pattern = Pattern::try_new(src, lang).context(EC::ParsePattern)?;
+    format_pattern(&pattern, lang, colored).context("unexpected error in writing pattern string")
+  }
+
+  fn dump_tree(&self, src: &str, lang: SgLang, colored: bool) -> String {
+    let root = lang.ast_grep(src);
+    match self {
+      DebugFormat::Pattern | DebugFormat::Match => {
+        debug_assert!(false, "dump_tree cannot be called with Pattern");
+        String::new()
+      }
+      DebugFormat::Sexp => root.root().to_sexp().into_owned(),
+      DebugFormat::Ast => dump_node(root.root().get_ts_node()).ast(colored),
+      DebugFormat::Cst => dump_node(root.root().get_ts_node()).cst(colored),
+    }
+  }
+}
+
+/// Explain why the pattern does not match any node in the file.
+/// It finds the candidate node that matches the most part of the pattern
+/// and reports where it first diverges from the pattern.
+/// Named nodes are compared first. Unnamed tokens like operators are only compared
+/// if all named nodes match, so the result is a hint rather than the exact matching algorithm.
+pub fn explain_mismatch(pattern: &Pattern<SgLang>, grep: &AstGrep<StrDoc<SgLang>>) -> String {
+  let lang = grep.lang().get_ts_language();
+  let kind_id = match &pattern.node {
+    PatternNode::Terminal { kind_id, .. } | PatternNode::Internal { kind_id, .. } => *kind_id,
+    PatternNode::MetaVar { .. } => {
+      return "A single meta variable pattern matches any node.".into()
+    }
+  };
+  let kind = kind_name(&lang, kind_id);
+  let root = grep.root();
+  let best = root
+    .dfs()
+    .filter(|n| n.kind_id() == kind_id)
+    .filter_map(|candidate| {
+      let mut explainer = Explainer::new(&lang, false);
+      if let Err(mismatch) = explainer.compare(&pattern.node, candidate.clone()) {
+        return Some((candidate, mismatch, (false, explainer.matched)));
+      }
+      // named nodes all match, so the divergence is in unnamed tokens
+      let mut explainer = Explainer::new(&lang, true);
+      let mismatch = explainer.compare(&pattern.node, candidate.clone()).err()?;
+      Some((candidate, mismatch, (true, explainer.matched)))
+    })
+    // prefer the first candidate among the ones with the same progress
+    .fold(None, |best: Option<(_, _, _)>, curr| match best {
+      Some(b) if b.2 >= curr.2 => Some(b),
+      _ => Some(curr),
+    });
+  let Some((candidate, mismatch, _)) = best else {
+    return format!(
+      "No `{kind}` node is found. The pattern is parsed as `{kind}`, use --debug-query=ast to compare it with the code."
+    );
+  };
+  let pos = |n: &Node<StrDoc<SgLang>>| {
+    let start = n.start_pos();
+    format!("{}:{}", start.line() + 1, start.column(n) + 1)
+  };
+  let snippet = |n: &Node<StrDoc<SgLang>>| {
+    let text = n.text();
+    text.lines().next().unwrap_or_default().to_string()
+  };
+  format!(
+    "The closest `{kind}` at {} `{}` diverges at {} `{}`: {}",
+    pos(&candidate),
+    snippet(&candidate),
+    pos(&mismatch.node),
+    snippet(&mismatch.node),
+    mismatch.reason,
+  )
+}
+
+struct Mismatch<'t> {
+  node: Node<'t, StrDoc<SgLang>>,
+  reason: String,
+}
+
+struct Explainer<'a> {
+  lang: &'a TSLanguage,
+  // the number of matched pattern nodes, used to rank candidates
+  matched: usize,
+  // whether unnamed tokens are compared
+  tokens: bool,
+}
+
+impl<'a> Explainer<'a> {
+  fn new(lang: &'a TSLanguage, tokens: bool) -> Self {
+    Self {
+      lang,
+      matched: 0,
+      tokens,
+    }
+  }
+
+  fn compare<'t>(
+    &mut self,
+    goal: &PatternNode,
+    candidate: Node<'t, StrDoc<SgLang>>,
+  ) -> std::result::Result<(), Mismatch<'t>> {
+    let (kind_id, children) = match goal {
+      PatternNode::MetaVar { .. } => {
+        self.matched += 1;
+        return Ok(());
+      }
+      // unnamed tokens like operators are told apart by text
+      PatternNode::Terminal {
+        text,
+        is_named: false,
+        ..
+      } => {
+        if *text != candidate.text() {
+          let reason = format!("expect `{text}` but found `{}`", candidate.text());
+          return Err(Mismatch {
+            node: candidate,
+            reason,
+          });
+        }
+        self.matched += 1;
+        return Ok(());
+      }
+      PatternNode::Terminal { kind_id, .. } | PatternNode::Internal { kind_id, .. }
+        if *kind_id != candidate.kind_id() =>
+      {
+        let reason = format!(
+          "expect kind `{}` but found `{}`",
+          kind_name(self.lang, *kind_id),
+          candidate.kind()
+        );
+        return Err(Mismatch {
+          node: candidate,
+          reason,
+        });
+      }
+      PatternNode::Terminal { text, is_named, .. } => {
+        if *is_named && *text != candidate.text() {
+          let reason = format!("expect text `{text}` but found `{}`", candidate.text());
+          return Err(Mismatch {
+            node: candidate,
+            reason,
+          });
+        }
+        self.matched += 1;
+        return Ok(());
+      }
+      PatternNode::Internal { kind_id, children } => (*kind_id, children),
+    };
+    self.matched += 1;
+    let tokens = self.tokens;
+    let goals = children.iter().filter(|g| is_compared_goal(g, tokens));
+    let mut cands = candidate
+      .children()
+      .filter(|c| (tokens || c.is_named()) && !c.kind().contains("comment"))
+      .peekable();
+    let mut goals = goals.peekable();
+    while let Some(goal) = goals.next() {
+      if is_ellipsis(goal) {
+        let Some(next) = goals.peek() else {
+          return Ok(());
+        };
+        // ellipsis consumes candidates until the next goal matches
+        loop {
+          let Some(cand) = cands.peek() else {
+            return Err(self.missing(next, candidate.clone()));
+          };
+          let mut probe = Explainer::new(self.lang, self.tokens);
+          if probe.compare(next, cand.clone()).is_ok() {
+            break;
+          }
+          cands.next();
+        }
+        continue;
       }
-      DebugFormat::Ast => {
-        let dumped = dump_node(root.root().get_ts_node());
-        eprintln!("Debug AST:\n{}", dumped.ast(colored));
+      // unnamed tokens not in the pattern are skipped, like `smart` strictness
+      let is_token = matches!(goal, PatternNode::Terminal { is_named, .. } if !is_named);
+      if !is_token {
+        while cands.next_if(|c| !c.is_named()).is_some() {}
       }
-      DebugFormat::Cst => {
-        let dumped = dump_node(root.root().get_ts_node());
-        eprintln!("Debug CST:\n{}", dumped.cst(colored));
+      let Some(cand) = cands.next() else {
+        return Err(self.missing(goal, candidate.clone()));
+      };
+      self.compare(goal, cand)?;
+    }
+    if let Some(extra) = cands.find(|c| c.is_named()) {
+      let reason = format!(
+        "unexpected `{}` after all children of `{}` in pattern are matched",
+        extra.kind(),
+        kind_name(self.lang, kind_id)
+      );
+      return Err(Mismatch {
+        node: extra,
+        reason,
+      });
+    }
+    Ok(())
+  }
+
+  fn missing<'t>(&self, goal: &PatternNode, parent: Node<'t, StrDoc<SgLang>>) -> Mismatch<'t> {
+    let expected = match goal {
+      PatternNode::Terminal { kind_id, .. } | PatternNode::Internal { kind_id, .. } => {
+        kind_name(self.lang, *kind_id)
       }
+      PatternNode::MetaVar { .. } => "meta variable".to_string(),
+    };
+    Mismatch {
+      reason: format!("missing `{expected}` in `{}`", parent.kind()),
+      node: parent,
     }
   }
 }
 
+fn kind_name(lang: &TSLanguage, kind_id: u16) -> String {
+  lang
+    .node_kind_for_id(kind_id)
+    .map_or_else(|| "unknown".into(), Cow::into_owned)
+}
+
+fn is_ellipsis(goal: &PatternNode) -> bool {
+  matches!(
+    goal,
+    PatternNode::MetaVar {
+      meta_var: MetaVariable::Multiple | MetaVariable::MultiCapture(_)
+    }
+  )
+}
+
+// meta variables of unnamed nodes are skipped, and unnamed tokens unless `tokens` is set
+fn is_compared_goal(goal: &PatternNode, tokens: bool) -> bool {
+  match goal {
+    PatternNode::Terminal { is_named, .. } => tokens || *is_named,
+    PatternNode::MetaVar { meta_var } => match meta_var {
+      MetaVariable::Capture(_, named) | MetaVariable::Dropped(named) => *named,
+      MetaVariable::Multiple | MetaVariable::MultiCapture(_) => true,
+    },
+    PatternNode::Internal { .. } => true,
+  }
+}
+
+fn format_pattern(pattern: &Pattern<SgLang>, lang: SgLang, colored: bool) -> Option<String> {
+  let lang = lang.get_ts_language();
+  let mut ret = String::new();
+  let fmt = DumpFmt::named(colored);
+  dump_pattern(&pattern.node, &lang, &fmt, 0, &mut ret).ok()?;
+  Some(ret)
+}
+
 fn dump_pattern(
   pattern: &PatternNode,
   lang: &TSLanguage,
@@ -135,6 +383,7 @@ impl DumpFmt {
   }
 }
 
+use std::borrow::Cow;
 use std::fmt::{Result as FmtResult, Write};
 impl DumpNode {
   pub fn ast(&self, colored: bool) -> String {
@@ -270,4 +519,61 @@ translation_unit (0,0)-(0,9)
     let dumped = dump_node(root.root().get_ts_node());
     assert_eq!(MISSING.trim(), dumped.cst(false).trim());
   }
+
+  #[test]
+  fn test_dump_format() -> Result<()> {
+    let lang = SgLang::Builtin(TypeScript.into());
+    let dumped = DebugFormat::Ast.dump("var a = 123", lang, false)?;
+    assert_eq!(DUMPED.trim(), dumped.trim());
+    let sexp = DebugFormat::Sexp.dump("a", lang, false)?;
+    assert_eq!(sexp, "(program (expression_statement (identifier)))");
+    let pattern = DebugFormat::Pattern.dump("$A + 1", lang, false)?;
+    assert!(pattern.contains("MetaVar $A"));
+    assert!(DebugFormat::Match.dump("$A", lang, false).is_err());
+    Ok(())
+  }
+
+  fn explain(pattern: &str, src: &str) -> String {
+    let lang = SgLang::Builtin(TypeScript.into());
+    let pattern = Pattern::new(pattern, lang);
+    let grep = lang.ast_grep(src);
+    explain_mismatch(&pattern, &grep)
+  }
+
+  #[test]
+  fn test_explain_text_mismatch() {
+    let ret = explain("console.log($A)", "foo(1)\nconsole.warn(1)");
+    assert!(ret.contains("at 2:1 `console.warn(1)`"), "{ret}");
+    assert!(ret.contains("expect text `log` but found `warn`"), "{ret}");
+  }
+
+  #[test]
+  fn test_explain_kind_mismatch() {
+    let ret = explain("console.log($A)", "foo(1)");
+    assert!(ret.contains("expect kind `member_expression` but found `identifier`"));
+    let ret = explain("class A {}", "let a = 1");
+    assert!(ret.starts_with("No `class_declaration` node"), "{ret}");
+  }
+
+  #[test]
+  fn test_explain_children_mismatch() {
+    let ret = explain("foo($A, $B)", "foo(1)");
+    assert!(
+      ret.contains("missing `meta variable` in `arguments`"),
+      "{ret}"
+    );
+    let ret = explain("foo($A)", "foo(1, 2)");
+    assert!(ret.contains("unexpected `number`"), "{ret}");
+    let ret = explain("foo($$$, 3)", "foo(1, 2)");
+    assert!(ret.contains("missing `number` in `arguments`"), "{ret}");
+  }
+
+  #[test]
+  fn test_explain_unnamed_mismatch() {
+    let ret = explain("a + b", "a - b");
+    assert!(ret.contains("at 1:1 `a - b` diverges at 1:3 `-`"), "{ret}");
+    assert!(ret.contains("expect `+` but found `-`"), "{ret}");
+    let ret = explain("let a = 1", "const a = 1");
+    assert!(ret.contains("expect `let` but found `const`"), "{ret}");
+  }
 }
@@ -32,20 +32,32 @@ pub enum ErrorContext {
   ParseRule(PathBuf),
   ParseTest(PathBuf),
   InvalidGlobalUtils,
+  InvalidSharedRewriters,
   GlobPattern,
   BuildGlobs,
   UnrecognizableLanguage(String),
   LangInjection,
   CustomLanguage,
+  LanguageGlobsConflict(Vec<String>),
   // Run
   ParsePattern,
   LanguageNotSpecified,
   StdInIsNotInteractive,
+  StdInIsUsedByCode,
   PatternHasError,
   // Scan
   DiagnosticError(usize),
   RuleNotSpecified,
   RuleNotFound(String),
+  ReadBaseline(PathBuf),
+  ParseBaseline(PathBuf),
+  WriteBaseline(PathBuf),
+  GitDiff(String),
+  // Suppress
+  StdInIsNotWritable,
+  // Apply
+  ReadReport(PathBuf),
+  ParseReport(PathBuf),
   // LSP
   StartLanguageServer,
   // Edit
@@ -60,6 +72,12 @@ pub enum ErrorContext {
   NoTestDirConfigured,
   NoUtilDirConfigured,
   InsufficientCLIArgument(&'static str),
+  // Install
+  InstallPackage(String),
+  PackageNotFound(String),
+  InstallLanguage(String),
+  ExtendsNotFound(String),
+  CyclicExtends(PathBuf),
   // Completions
   CannotInferShell,
 }
@@ -70,13 +88,23 @@ impl ErrorContext {
     // reference: https://mariadb.com/kb/en/operating-system-error-codes/
     match self {
       DiagnosticError(_) => 1,
-      ProjectNotExist | LanguageNotSpecified | RuleNotSpecified | RuleNotFound(_) => 2,
+      ProjectNotExist | LanguageNotSpecified | RuleNotSpecified | RuleNotFound(_)
+      | PackageNotFound(_) | ExtendsNotFound(_) => 2,
       TestFail(_) => 3,
       NoTestDirConfigured | NoUtilDirConfigured => 4,
-      ReadConfiguration | ReadRule(_) | WalkRuleDir(_) | WriteFile(_) => 5,
-      StdInIsNotInteractive => 6,
-      ParseTest(_) | ParseRule(_) | ParseConfiguration | ParsePattern | InvalidGlobalUtils
-      | LangInjection => 8,
+      ReadConfiguration | ReadRule(_) | WalkRuleDir(_) | WriteFile(_) | ReadBaseline(_)
+      | WriteBaseline(_) | GitDiff(_) | InstallPackage(_) | InstallLanguage(_) | ReadReport(_) => 5,
+      StdInIsNotInteractive | StdInIsUsedByCode | StdInIsNotWritable => 6,
+      ParseTest(_)
+      | ParseRule(_)
+      | ParseConfiguration
+      | ParsePattern
+      | InvalidGlobalUtils
+      | InvalidSharedRewriters
+      | LangInjection
+      | ParseBaseline(_)
+      | CyclicExtends(_)
+      | ParseReport(_) => 8,
       GlobPattern | BuildGlobs => 9,
       CannotInferShell => 10,
       ProjectAlreadyExist | FileAlreadyExist(_) => 17,
@@ -85,7 +113,7 @@ impl ErrorContext {
       CustomLanguage => 79,
       OpenEditor | StartLanguageServer => 126,
       // soft error
-      PatternHasError => 0,
+      PatternHasError | LanguageGlobsConflict(_) => 0,
     }
   }
 
@@ -169,11 +197,24 @@ impl ErrorMessage {
         "The custom language library is not found or cannot be loaded.",
         CUSTOM_LANG_GUIDE,
       ),
+      LanguageGlobsConflict(conflicts) => Self::new(
+        "Conflicting file patterns in languageGlobs",
+        format!(
+          "{}\nA file can only be parsed as one language. languageGlobs take precedence over default file extensions, and a file matched by two languages in languageGlobs is parsed as the first language in alphabetical order. Use `sg lang list --globs` to inspect the patterns of each language.",
+          conflicts.join("\n")
+        ),
+        CONFIG_REFERENCE,
+      ),
       InvalidGlobalUtils => Self::new(
         "Error occurs when parsing global utility rules",
         "Please check the YAML rules inside the rule directory",
         UTILITY_RULE,
       ),
+      InvalidSharedRewriters => Self::new(
+        "Error occurs when parsing shared rewriters",
+        "Rewriters inside rewriter directories must have unique ids and `fix`.",
+        CONFIG_REFERENCE,
+      ),
       UnrecognizableLanguage(lang) => Self::new(
         format!("Language `{lang}` is not supported"),
         "Please choose a built-in language or register a custom language in sgconfig.yml.",
@@ -186,7 +227,7 @@ impl ErrorMessage {
       ),
       DiagnosticError(num) => Self::new(
         format!("{num} error(s) found in code."),
-        "Scan succeeded and found error level diagnostics in the codebase.",
+        "Scan succeeded and found diagnostics at or above the `--error-on` severity in the codebase.",
         None,
       ),
       ParsePattern => Self::new(
@@ -204,6 +245,11 @@ impl ErrorMessage {
         "`--interactive` needs StdIn, but it is used as source code. Please use files as input.",
         TOOL_OVERVIEW,
       ),
+      StdInIsUsedByCode => Self::new(
+        "Rules cannot be read from StdIn when it is used as source code.",
+        "`--inline-rules -` reads rules from StdIn, which conflicts with `--stdin`. Please pass the rule text or use `--rule`.",
+        CLI_USAGE,
+      ),
       PatternHasError => Self::new(
         "Pattern contains an ERROR node and may cause unexpected results.",
         "ast-grep parsed the pattern but it matched nothing in this run. Try using playground to refine the pattern.",
@@ -219,6 +265,41 @@ impl ErrorMessage {
         format!("Rule with id '{id}' not found in project configuration. Please make sure it exists."),
         TOOL_OVERVIEW,
       ),
+      ReadBaseline(file) => Self::new(
+        format!("Cannot read baseline {}", file.display()),
+        "The baseline file cannot be opened. Please check the file permission.",
+        CLI_USAGE,
+      ),
+      ParseBaseline(file) => Self::new(
+        format!("Cannot parse baseline {}", file.display()),
+        "The file is not a valid baseline. Please remove it and rerun scan to create a new one.",
+        CLI_USAGE,
+      ),
+      WriteBaseline(file) => Self::new(
+        format!("Cannot write baseline {}", file.display()),
+        "Fail to record current findings into the baseline file.",
+        CLI_USAGE,
+      ),
+      GitDiff(reference) => Self::new(
+        format!("Cannot list files changed since {reference}"),
+        "`--changed` runs git to find changed files. Please make sure git is installed, the current directory is inside a git repository and the ref exists.",
+        CLI_USAGE,
+      ),
+      StdInIsNotWritable => Self::new(
+        "Suppression comments cannot be written to code from StdIn.",
+        "`suppress` edits files in place. Please use files as input.",
+        CLI_USAGE,
+      ),
+      ReadReport(file) => Self::new(
+        format!("Cannot read report {}", file.display()),
+        "The report file cannot be opened. Please check the file path and permission.",
+        CLI_USAGE,
+      ),
+      ParseReport(file) => Self::new(
+        format!("Cannot parse report {}", file.display()),
+        "The file is not a valid JSON report. Please generate it by `run --json` or `scan --json`.",
+        CLI_USAGE,
+      ),
       StartLanguageServer => Self::new(
         "Cannot start language server.",
         "Please see language server logging file.",
@@ -269,6 +350,31 @@ impl ErrorMessage {
         format!("You need to provide `{name}` in command line to use non-interactive `new`."),
         None,
       ),
+      InstallPackage(package) => Self::new(
+        format!("Cannot install package {package}"),
+        "`install` clones the package with git. Please make sure git is installed and the package is a valid git repository, URL or GitHub `owner/repo`.",
+        PROJECT_GUIDE,
+      ),
+      PackageNotFound(name) => Self::new(
+        format!("Cannot find sgconfig.yml of package {name}"),
+        "A rule package must have sgconfig.yml at its root.",
+        CONFIG_REFERENCE,
+      ),
+      InstallLanguage(grammar) => Self::new(
+        format!("Cannot install language {grammar}"),
+        "`lang install` fetches the grammar with git or npm and compiles its `src/parser.c` with a C compiler. Please make sure these tools are installed and the grammar is a valid tree-sitter grammar.",
+        CUSTOM_LANG_GUIDE,
+      ),
+      ExtendsNotFound(entry) => Self::new(
+        format!("Cannot find {entry} in extends"),
+        "`extends` accepts paths to YAML config files relative to the config, or names of packages installed by `sg install`.",
+        CONFIG_REFERENCE,
+      ),
+      CyclicExtends(file) => Self::new(
+        format!("Config {} extends itself", file.display()),
+        "Please remove the cycle in `extends` of your configs.",
+        CONFIG_REFERENCE,
+      ),
       CannotInferShell => Self::new(
         "Can not infer which shell to generate completions.",
         "Either specify shell flavor by `sg completions [SHELL]` or set correct `SHELL` environment.",
@@ -278,6 +384,20 @@ impl ErrorMessage {
   }
 }
 
+/// Print a soft error as a warning without exiting.
+pub fn print_warning(context: ErrorContext) {
+  let error = anyhow::anyhow!(context);
+  let context = error.downcast_ref().expect("must be error context");
+  let error_fmt = ErrorFormat {
+    context,
+    inner: &error,
+  };
+  eprintln!("{error_fmt}");
+}
+
+/// exit code for errors without context, the same as grep's exit code for errors
+const UNKNOWN_ERROR_CODE: i32 = 2;
+
 pub fn exit_with_error(error: Error) -> Result<()> {
   if let Some(e) = error.downcast_ref::<clap::Error>() {
     e.exit()
@@ -290,8 +410,9 @@ pub fn exit_with_error(error: Error) -> Result<()> {
     eprintln!("{error_fmt}");
     std::process::exit(e.exit_code())
   }
-  // use anyhow's default error reporting
-  Err(error)
+  // use anyhow's default error reporting, but keep exit code 1 for diagnostics found by scan
+  eprintln!("Error: {error:?}");
+  std::process::exit(UNKNOWN_ERROR_CODE)
 }
 
 // use raw ansi escape code to render links in terminal. references:
@@ -9,19 +9,24 @@
 //!   * number of rules applied
 //!   * rules skipped (dues to ignore/files)
 //! - Detail level: show how a rule runs on a file
+//! - JSON level: machine readable parse/match timing of every file and rule, one JSON object per line
 
+use super::SkipReason;
 use crate::config::ProjectConfig;
 use crate::lang::SgLang;
-use ast_grep_config::{RuleCollection, RuleConfig};
+use ast_grep_config::{RuleCollection, RuleConfig, RuleTiming};
 
 use anyhow::Result;
 use clap::ValueEnum;
+use serde_json::{json, Value};
 
+use std::collections::HashMap;
 use std::fmt;
 use std::io::{Stderr, Write};
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Mutex;
+use std::time::Duration;
 
 #[derive(Clone, Copy, ValueEnum, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Granularity {
@@ -33,6 +38,8 @@ pub enum Granularity {
   /// Show per-file/per-rule tracing information
   Entity = 2,
   // Detail,
+  /// Output per-file and per-rule timing as JSON lines, instead of text
+  Json = 3,
 }
 
 impl fmt::Debug for Granularity {
@@ -41,6 +48,7 @@ impl fmt::Debug for Granularity {
       Granularity::Nothing => write!(f, "nothing"),
       Granularity::Summary => write!(f, "summary"),
       Granularity::Entity => write!(f, "entity"),
+      Granularity::Json => write!(f, "json"),
     }
   }
 }
@@ -82,10 +90,12 @@ impl Granularity {
 
 // total = scanned + skipped
 //       = (matched + unmatched) + skipped
+// unparsed files are skipped by rules before parsing, a subset of total
 #[derive(Default)]
 pub struct FileTrace {
   files_scanned: AtomicUsize,
   files_skipped: AtomicUsize,
+  files_unparsed: AtomicUsize,
 }
 
 impl FileTrace {
@@ -95,6 +105,10 @@ impl FileTrace {
   pub fn add_skipped(&self) {
     self.files_skipped.fetch_add(1, Ordering::AcqRel);
   }
+  /// no rule applies to the file or no rule literal is found in it
+  pub fn add_unparsed(&self) {
+    self.files_unparsed.fetch_add(1, Ordering::AcqRel);
+  }
 }
 
 pub struct TraceInfo<T, W: Write> {
@@ -109,7 +123,7 @@ impl<T, W: Write + Sync> TraceInfo<T, W> {
   where
     F: FnOnce(&mut W) -> Result<()>,
   {
-    if self.level < level {
+    if self.level < level || self.level == Granularity::Json {
       return Ok(());
     }
     let mut w = self.output.lock().expect("lock should not be poisoned");
@@ -142,11 +156,51 @@ impl<T, W: Write + Sync> TraceInfo<T, W> {
     })
   }
 
+  /// Print a file skipped because of its content, see `SkipReason`.
+  pub fn print_skipped_file(&self, path: &Path, reason: SkipReason) -> Result<()> {
+    self.print_json(json!({
+      "type": "file",
+      "path": path.display().to_string(),
+      "skipReason": reason.to_string(),
+    }))?;
+    self.print_entity("file", path.display(), |w| {
+      write!(w, "skipReason={reason}")?;
+      Ok(())
+    })
+  }
+
+  fn print_json(&self, value: Value) -> Result<()> {
+    if self.level != Granularity::Json {
+      return Ok(());
+    }
+    let mut w = self.output.lock().expect("lock should not be poisoned");
+    writeln!(w, "{value}")?;
+    Ok(())
+  }
+
+  fn print_json_summary(&self, files: &FileTrace, rules: Option<&RuleTrace>) -> Result<()> {
+    let mut summary = json!({
+      "type": "summary",
+      "scannedFileCount": files.files_scanned.load(Ordering::Acquire),
+      "skippedFileCount": files.files_skipped.load(Ordering::Acquire),
+      "unparsedFileCount": files.files_unparsed.load(Ordering::Acquire),
+    });
+    if let Some(rules) = rules {
+      summary["effectiveRuleCount"] = rules.effective_rule_count.into();
+      summary["skippedRuleCount"] = rules.skipped_rule_count.into();
+    }
+    self.print_json(summary)
+  }
+
   fn print_files(&self, trace: &FileTrace) -> Result<()> {
     self.print_summary("file", |w| {
       let scanned = trace.files_scanned.load(Ordering::Acquire);
       let skipped = trace.files_skipped.load(Ordering::Acquire);
-      write!(w, "scannedFileCount={scanned},skippedFileCount={skipped}")?;
+      let unparsed = trace.files_unparsed.load(Ordering::Acquire);
+      write!(
+        w,
+        "scannedFileCount={scanned},skippedFileCount={skipped},unparsedFileCount={unparsed}"
+      )?;
       Ok(())
     })
   }
@@ -154,6 +208,15 @@ impl<T, W: Write + Sync> TraceInfo<T, W> {
 
 impl<W: Write + Sync> TraceInfo<(), W> {
   pub fn print_project(&self, project: &Result<ProjectConfig>) -> Result<()> {
+    let dir = project
+      .as_ref()
+      .ok()
+      .map(|p| p.project_dir.display().to_string());
+    self.print_json(json!({
+      "type": "project",
+      "isProject": dir.is_some(),
+      "projectDir": dir,
+    }))?;
     self.print_summary("project", |w| {
       if let Ok(project) = project {
         let dir = project.project_dir.display();
@@ -168,10 +231,16 @@ impl<W: Write + Sync> TraceInfo<(), W> {
 
 impl<W: Write + Sync> TraceInfo<FileTrace, W> {
   pub fn print(&self) -> Result<()> {
+    self.print_json_summary(&self.inner, None)?;
     self.print_files(&self.inner)
   }
 
   pub fn print_file(&self, path: &Path, lang: SgLang) -> Result<()> {
+    self.print_json(json!({
+      "type": "file",
+      "path": path.display().to_string(),
+      "language": lang.to_string(),
+    }))?;
     self.print_entity("file", path.display(), |w| {
       write!(w, "language={lang}")?;
       Ok(())
@@ -182,6 +251,9 @@ impl<W: Write + Sync> TraceInfo<FileTrace, W> {
 impl<W: Write + Sync> TraceInfo<RuleTrace, W> {
   // TODO: support more format?
   pub fn print(&self) -> Result<()> {
+    self.print_rule_profile()?;
+    self.print_rule_timings()?;
+    self.print_json_summary(&self.inner.file_trace, Some(&self.inner))?;
     self.print_files(&self.inner.file_trace)?;
     self.print_summary("rule", |w| {
       let (effective, skipped) = (
@@ -206,6 +278,132 @@ impl<W: Write + Sync> TraceInfo<RuleTrace, W> {
     Ok(())
   }
 
+  /// Print a file skipped before parsing, see `FileTrace::add_unparsed`.
+  pub fn print_unparsed_file(
+    &self,
+    path: &Path,
+    lang: SgLang,
+    rules: &[&RuleConfig<SgLang>],
+  ) -> Result<()> {
+    self.print_json(json!({
+      "type": "file",
+      "path": path.display().to_string(),
+      "language": lang.to_string(),
+      "appliedRuleCount": rules.len(),
+      "parsed": false,
+    }))?;
+    self.print_file(path, lang, rules)
+  }
+
+  /// Whether rule timing should be measured during scanning.
+  pub fn collects_timing(&self) -> bool {
+    self.level == Granularity::Json || self.inner.profile_rules
+  }
+
+  /// Print a scanned file with its timing and accumulate the timing of each rule.
+  /// `parse_time` is None for injected documents which are parsed with their host file.
+  pub fn print_file_timing(
+    &self,
+    path: &Path,
+    lang: SgLang,
+    rules: &[&RuleConfig<SgLang>],
+    parse_time: Option<Duration>,
+    timings: &[RuleTiming],
+  ) -> Result<()> {
+    let match_time: Duration = timings.iter().map(|t| t.elapsed).sum();
+    {
+      let mut stats = self
+        .inner
+        .rule_timings
+        .lock()
+        .expect("lock should not be poisoned");
+      for timing in timings {
+        let stat = stats.entry(timing.id.clone()).or_default();
+        stat.elapsed += timing.elapsed;
+        stat.tested_nodes += timing.tested_nodes;
+        stat.file_count += 1;
+        stat.hit_file_count += usize::from(timing.matched);
+      }
+    }
+    self.print_json(json!({
+      "type": "file",
+      "path": path.display().to_string(),
+      "language": lang.to_string(),
+      "appliedRuleCount": rules.len(),
+      "parsed": true,
+      "parseMicros": parse_time.map(|t| t.as_micros() as u64),
+      "matchMicros": match_time.as_micros() as u64,
+    }))
+  }
+
+  /// Accumulate the time rules take to report matches, after the pre-scan timed in `print_file_timing`.
+  pub fn add_scan_timing(&self, timings: &[RuleTiming]) {
+    let mut stats = self
+      .inner
+      .rule_timings
+      .lock()
+      .expect("lock should not be poisoned");
+    for timing in timings {
+      let stat = stats.entry(timing.id.clone()).or_default();
+      stat.elapsed += timing.elapsed;
+      stat.tested_nodes += timing.tested_nodes;
+    }
+  }
+
+  /// Print accumulated rule timings, slowest first.
+  fn print_rule_timings(&self) -> Result<()> {
+    if self.level != Granularity::Json {
+      return Ok(());
+    }
+    for (id, stat) in self.sorted_rule_timings() {
+      self.print_json(json!({
+        "type": "rule",
+        "id": id,
+        "matchMicros": stat.elapsed.as_micros() as u64,
+        "testedNodeCount": stat.tested_nodes,
+        "fileCount": stat.file_count,
+        "hitFileCount": stat.hit_file_count,
+      }))?;
+    }
+    Ok(())
+  }
+
+  /// Print the table of `--profile-rules`, regardless of inspect level.
+  fn print_rule_profile(&self) -> Result<()> {
+    if !self.inner.profile_rules {
+      return Ok(());
+    }
+    let stats = self.sorted_rule_timings();
+    let width = stats.iter().map(|(id, _)| id.len()).max().unwrap_or(0);
+    let width = width.max("RULE".len());
+    let mut w = self.output.lock().expect("lock should not be poisoned");
+    writeln!(w, "{:width$}  FILES HIT  TOTAL MS   AVG MS", "RULE")?;
+    for (id, stat) in stats {
+      let total = stat.elapsed.as_secs_f64() * 1000.0;
+      let avg = total / stat.file_count.max(1) as f64;
+      writeln!(
+        w,
+        "{id:width$}  {:>9}  {total:>8.2}  {avg:>7.2}",
+        stat.hit_file_count
+      )?;
+    }
+    Ok(())
+  }
+
+  fn sorted_rule_timings(&self) -> Vec<(String, RuleTimingStat)> {
+    let stats = self
+      .inner
+      .rule_timings
+      .lock()
+      .expect("lock should not be poisoned");
+    let mut stats: Vec<_> = stats
+      .iter()
+      .map(|(id, s)| (id.clone(), s.clone()))
+      .collect();
+    stats.sort_by(|a, b| b.1.elapsed.cmp(&a.1.elapsed).then_with(|| a.0.cmp(&b.0)));
+    stats
+  }
+
   pub fn print_rules(&self, rules: &RuleCollection<SgLang>) -> Result<()> {
     if self.level < Granularity::Entity {
       return Ok(());
@@ -225,6 +423,20 @@ pub struct RuleTrace {
   pub file_trace: FileTrace,
   pub effective_rule_count: usize,
   pub skipped_rule_count: usize,
+  /// rule id to its accumulated timing, only collected in JSON inspection
+  pub rule_timings: Mutex<HashMap<String, RuleTimingStat>>,
+  /// collect rule timings and print them as a table, see `sg scan --profile-rules`
+  pub profile_rules: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct RuleTimingStat {
+  elapsed: Duration,
+  tested_nodes: usize,
+  /// number of files the rule is tested on
+  file_count: usize,
+  /// number of files the rule matches
+  hit_file_count: usize,
 }
 
 pub type ProjectTrace = TraceInfo<(), Stderr>;
@@ -234,6 +446,7 @@ pub type ScanTrace = TraceInfo<RuleTrace, Stderr>;
 #[cfg(test)]
 mod test {
   use super::*;
+  use ast_grep_core::Language;
 
   #[test]
   fn test_tracing() {
@@ -246,7 +459,7 @@ mod test {
     assert!(run_trace.print().is_ok());
     assert_eq!(
       ret,
-      "sg: summary|file: scannedFileCount=0,skippedFileCount=0\n"
+      "sg: summary|file: scannedFileCount=0,skippedFileCount=0,unparsedFileCount=0\n"
     );
 
     let mut ret = String::new();
@@ -254,6 +467,8 @@ mod test {
       effective_rule_count: 10,
       skipped_rule_count: 2,
       file_trace: Default::default(),
+      rule_timings: Default::default(),
+      profile_rules: false,
     };
     let scan_trace = tracing.scan_trace_impl(rule_stats, unsafe { ret.as_mut_vec() });
     assert_eq!(scan_trace.level, Granularity::Summary);
@@ -278,12 +493,101 @@ mod test {
     assert!(scan_trace.print().is_ok());
     assert_eq!(
       ret,
-      r"sg: summary|file: scannedFileCount=0,skippedFileCount=0
+      r"sg: summary|file: scannedFileCount=0,skippedFileCount=0,unparsedFileCount=0
 sg: summary|rule: effectiveRuleCount=10,skippedRuleCount=2
 "
     );
   }
 
+  #[test]
+  fn test_tracing_json() -> Result<()> {
+    let tracing = Granularity::Json;
+    let mut ret = String::new();
+    let scan_trace = tracing.scan_trace_impl(RuleTrace::default(), unsafe { ret.as_mut_vec() });
+    let rule: RuleConfig<SgLang> = ast_grep_config::from_yaml_string(
+      "id: slow\nlanguage: ts\nrule: {pattern: a}",
+      &Default::default(),
+    )?
+    .pop()
+    .expect("should have one rule");
+    let timing = |micros, tested_nodes| RuleTiming {
+      id: rule.id.clone(),
+      elapsed: Duration::from_micros(micros),
+      tested_nodes,
+      matched: true,
+    };
+    let path = Path::new("a.ts");
+    let lang = SgLang::from_path(path).expect("should be ts");
+    scan_trace.print_file_timing(
+      path,
+      lang,
+      &[&rule],
+      Some(Duration::from_micros(5)),
+      &[timing(30, 3)],
+    )?;
+    scan_trace.add_scan_timing(&[timing(20, 1)]);
+    scan_trace.print_unparsed_file(Path::new("b.ts"), lang, &[])?;
+    scan_trace.print()?;
+    let lines: Vec<Value> = ret
+      .lines()
+      .map(serde_json::from_str)
+      .collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0]["type"], "file");
+    assert_eq!(lines[0]["parseMicros"], 5);
+    assert_eq!(lines[0]["matchMicros"], 30);
+    assert_eq!(lines[1]["parsed"], false);
+    assert_eq!(lines[2]["type"], "rule");
+    assert_eq!(lines[2]["id"], "slow");
+    assert_eq!(lines[2]["matchMicros"], 50);
+    assert_eq!(lines[2]["testedNodeCount"], 4);
+    assert_eq!(lines[2]["hitFileCount"], 1);
+    assert_eq!(lines[3]["type"], "summary");
+    assert_eq!(lines[3]["effectiveRuleCount"], 0);
+    Ok(())
+  }
+
+  #[test]
+  fn test_profile_rules() -> Result<()> {
+    let mut ret = String::new();
+    let rule_stats = RuleTrace {
+      profile_rules: true,
+      ..Default::default()
+    };
+    let scan_trace = Granularity::Nothing.scan_trace_impl(rule_stats, unsafe { ret.as_mut_vec() });
+    assert!(scan_trace.collects_timing());
+    let stat = |ms, file_count, hit_file_count| RuleTimingStat {
+      elapsed: Duration::from_millis(ms),
+      tested_nodes: 0,
+      file_count,
+      hit_file_count,
+    };
+    {
+      let mut timings = scan_trace.inner.rule_timings.lock().expect("should lock");
+      timings.insert("fast".into(), stat(2, 2, 0));
+      timings.insert("slow-rule".into(), stat(30, 3, 1));
+    }
+    scan_trace.print()?;
+    assert_eq!(
+      ret,
+      "\
+RULE       FILES HIT  TOTAL MS   AVG MS
+slow-rule          1     30.00    10.00
+fast               0      2.00     1.00
+"
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_print_skipped_file() -> Result<()> {
+    let mut ret = String::new();
+    let run_trace = Granularity::Entity.run_trace_impl(unsafe { ret.as_mut_vec() });
+    run_trace.print_skipped_file(Path::new("a.min.js"), SkipReason::Minified)?;
+    assert_eq!(ret, "sg: entity|file|a.min.js: skipReason=minified\n");
+    Ok(())
+  }
+
   #[test]
   fn test_tracing_nothing() {
     let tracing = Granularity::Nothing;
@@ -1,15 +1,24 @@
 mod args;
+mod baseline;
+mod changed;
 mod debug_query;
 mod error_context;
 mod inspect;
 mod rule_overwrite;
+mod watch;
 mod worker;
 
-pub use args::{ContextArgs, InputArgs, OutputArgs, OverwriteArgs};
-pub use debug_query::DebugFormat;
-pub use error_context::{exit_with_error, ErrorContext};
+pub use args::{
+  register_project_ignores, ContextArgs, InputArgs, NoIgnore, OutputArgs, OverwriteArgs, Strictness,
+};
+pub use baseline::{fingerprint, fnv_hash, normalize_path, Baseline};
+pub use changed::ChangedFiles;
+pub use debug_query::{explain_mismatch, DebugFormat};
+pub use error_context::{exit_with_error, print_warning, ErrorContext};
+use inspect::TraceInfo;
 pub use inspect::{FileTrace, Granularity, RuleTrace, RunTrace, ScanTrace};
 pub use rule_overwrite::RuleOverwrite;
+pub use watch::FileWatcher;
 pub use worker::{Items, PathWorker, StdInWorker, Worker};
 
 use crate::lang::SgLang;
@@ -23,16 +32,19 @@ use crossterm::{
   terminal::{Clear, ClearType},
 };
 
-use ast_grep_config::{CombinedScan, PreScan, RuleCollection};
+use ast_grep_config::{CombinedScan, PreScan, RuleCollection, RuleConfig};
 use ast_grep_core::Pattern;
 use ast_grep_core::{Matcher, StrDoc};
 use ast_grep_language::Language;
 
-use std::fs::read_to_string;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::read;
 use std::io::stdout;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 type AstGrep = ast_grep_core::AstGrep<StrDoc<SgLang>>;
 
@@ -90,17 +102,58 @@ pub fn prompt(prompt_text: &str, letters: &str, default: Option<char>) -> Result
   }
 }
 
-fn read_file(path: &Path) -> Option<String> {
-  let file_content = read_to_string(path)
+fn read_file<T, W>(path: &Path, limits: &FileLimits, trace: &TraceInfo<T, W>) -> Option<String>
+where
+  W: Write + Sync,
+{
+  let bytes = read(path)
     .with_context(|| format!("Cannot read file {}", path.to_string_lossy()))
     .map_err(|err| eprintln!("{err:#}"))
     .ok()?;
-  // skip large files or empty file
-  if file_too_large(&file_content) || file_content.is_empty() {
-    // TODO add output
-    None
-  } else {
-    Some(file_content)
+  // check binary before decoding to avoid reporting binary files as invalid UTF-8
+  if is_binary(&bytes) {
+    trace.print_skipped_file(path, SkipReason::Binary).ok()?;
+    return None;
+  }
+  let file_content = String::from_utf8(bytes)
+    .with_context(|| format!("Cannot read file {}", path.to_string_lossy()))
+    .map_err(|err| eprintln!("{err:#}"))
+    .ok()?;
+  if file_content.is_empty() {
+    return None;
+  }
+  let Some(reason) = limits.skip_reason(&file_content) else {
+    return Some(file_content);
+  };
+  trace.print_skipped_file(path, reason).ok()?;
+  None
+}
+
+/// Combined scans of the rules applied to files, built once per worker and reused
+/// for files with the same rules because building the kind index and literal filter is costly.
+#[derive(Default)]
+pub struct CombinedScans<'r> {
+  /// keyed by the addresses of the rules
+  scans: HashMap<Vec<usize>, CombinedScan<'r, SgLang>>,
+}
+
+impl<'r> CombinedScans<'r> {
+  pub fn get(&mut self, rules: &[&'r RuleConfig<SgLang>]) -> &CombinedScan<'r, SgLang> {
+    self.get_with(rules, |_| ())
+  }
+
+  /// Get the scan of the rules, `configure` is applied when the scan is first built.
+  pub fn get_with(
+    &mut self,
+    rules: &[&'r RuleConfig<SgLang>],
+    configure: impl FnOnce(&mut CombinedScan<'r, SgLang>),
+  ) -> &CombinedScan<'r, SgLang> {
+    let key = rules.iter().map(|r| *r as *const _ as usize).collect();
+    self.scans.entry(key).or_insert_with(|| {
+      let mut combined = CombinedScan::new(rules.to_vec());
+      configure(&mut combined);
+      combined
+    })
   }
 }
 
@@ -108,13 +161,21 @@ fn filter(
   grep: &AstGrep,
   path: &Path,
   lang: SgLang,
-  configs: &RuleCollection<SgLang>,
+  rules: &[&RuleConfig<SgLang>],
+  combined: &CombinedScan<SgLang>,
+  parse_time: Option<Duration>,
   rule_stats: &ScanTrace,
 ) -> Option<PreScan> {
-  let rules = configs.get_rule_from_lang(path, lang);
-  rule_stats.print_file(path, lang, &rules).ok()?;
-  let combined = CombinedScan::new(rules);
-  let pre_scan = combined.find(grep);
+  let pre_scan = if rule_stats.collects_timing() {
+    let (pre_scan, timings) = combined.find_with_timing(grep);
+    rule_stats
+      .print_file_timing(path, lang, rules, parse_time, &timings)
+      .ok()?;
+    pre_scan
+  } else {
+    rule_stats.print_file(path, lang, rules).ok()?;
+    combined.find(grep)
+  };
   if pre_scan.is_empty() {
     None
   } else {
@@ -122,28 +183,55 @@ fn filter(
   }
 }
 
-pub fn filter_file_interactive(
+pub fn filter_file_interactive<'r>(
   path: &Path,
-  configs: &RuleCollection<SgLang>,
+  configs: &'r RuleCollection<SgLang>,
+  limits: &FileLimits,
   trace: &ScanTrace,
+  scans: &mut CombinedScans<'r>,
 ) -> Option<Vec<(PathBuf, AstGrep, PreScan)>> {
-  let lang = SgLang::from_path(path)?;
-  let file_content = read_file(path)?;
+  let lang = SgLang::from_file(path)?;
+  // group rules by language before reading the file, files/ignores are already applied
+  let rules = configs.get_rule_from_lang(path, lang);
+  let injected_rules: Vec<_> = lang
+    .injectable_sg_langs()
+    .into_iter()
+    .flatten()
+    .map(|l| (l, configs.get_rule_from_lang(path, l)))
+    .filter(|(_, rules)| !rules.is_empty())
+    .collect();
+  let file_trace = &trace.inner.file_trace;
+  if rules.is_empty() && injected_rules.is_empty() {
+    trace.print_unparsed_file(path, lang, &rules).ok()?;
+    file_trace.add_unparsed();
+    return None;
+  }
+  let file_content = read_file(path, limits, trace)?;
+  // injected documents are only known after parsing
+  if injected_rules.is_empty() && !scans.get(&rules).may_match(&file_content) {
+    trace.print_unparsed_file(path, lang, &rules).ok()?;
+    file_trace.add_unparsed();
+    return Some(vec![]);
+  }
+  let start = Instant::now();
   let grep = lang.ast_grep(file_content);
+  let parse_time = start.elapsed();
   let mut ret = vec![];
-  let root = filter(&grep, path, lang, configs, trace)
+  let combined = scans.get(&rules);
+  let root = filter(&grep, path, lang, &rules, combined, Some(parse_time), trace)
     .map(|pre_scan| (path.to_path_buf(), grep.clone(), pre_scan));
   ret.extend(root);
-  if let Some(injected) = lang.injectable_sg_langs() {
-    let docs = grep.inner.get_injections(|s| SgLang::from_str(s).ok());
-    let inj = injected.filter_map(|l| {
-      let doc = docs.iter().find(|d| *d.lang() == l)?;
-      let grep = AstGrep { inner: doc.clone() };
-      let pre_scan = filter(&grep, path, l, configs, trace)?;
-      Some((path.to_path_buf(), grep, pre_scan))
-    });
-    ret.extend(inj)
+  if injected_rules.is_empty() {
+    return Some(ret);
   }
+  let docs = grep.inner.get_injections(|s| SgLang::from_str(s).ok());
+  let inj = injected_rules.into_iter().filter_map(|(l, rules)| {
+    let doc = docs.iter().find(|d| *d.lang() == l)?;
+    let grep = AstGrep { inner: doc.clone() };
+    let pre_scan = filter(&grep, path, l, &rules, scans.get(&rules), None, trace)?;
+    Some((path.to_path_buf(), grep, pre_scan))
+  });
+  ret.extend(inj);
   Some(ret)
 }
 
@@ -152,8 +240,10 @@ pub fn filter_file_pattern(
   lang: SgLang,
   root_matcher: Option<Pattern<SgLang>>,
   matchers: impl Iterator<Item = (SgLang, Pattern<SgLang>)>,
+  limits: &FileLimits,
+  trace: &RunTrace,
 ) -> Option<Vec<(MatchUnit<Pattern<SgLang>>, SgLang)>> {
-  let file_content = read_file(path)?;
+  let file_content = read_file(path, limits, trace)?;
   let grep = lang.ast_grep(&file_content);
   let do_match = |ast_grep: AstGrep, matcher: Pattern<SgLang>, lang: SgLang| {
     let fixed = matcher.fixed_string();
@@ -192,10 +282,108 @@ pub fn filter_file_pattern(
 const MAX_FILE_SIZE: usize = 3_000_000;
 const MAX_LINE_COUNT: usize = 200_000;
 
-// skip files that are too large in size AND have too many lines
-fn file_too_large(file_content: &str) -> bool {
-  // the && operator is intentional here to include more files
-  file_content.len() > MAX_FILE_SIZE && file_content.lines().count() > MAX_LINE_COUNT
+/// bytes inspected for NUL to detect binary files, same as git
+const BINARY_CHECK_SIZE: usize = 8000;
+/// files smaller than this are never regarded as minified
+const MINIFIED_MIN_SIZE: usize = 1024;
+const MINIFIED_LINE_LENGTH: usize = 1000;
+const MINIFIED_WHITESPACE_RATIO: f64 = 0.1;
+
+/// Limits to skip large files, e.g. generated or minified code.
+/// Configured by `--max-filesize` or `maxFileSize`/`maxLineCount` in sgconfig.yml.
+#[derive(Clone, Copy, Default)]
+pub struct FileLimits {
+  /// max file size in bytes
+  pub max_size: Option<usize>,
+  pub max_lines: Option<usize>,
+  /// skip minified files, disabled by `--no-skip-minified`
+  pub skip_minified: bool,
+}
+
+/// Why a file is skipped before parsing, reported in `--inspect` output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+  Binary,
+  TooLarge,
+  Minified,
+}
+
+impl fmt::Display for SkipReason {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SkipReason::Binary => write!(f, "binary"),
+      SkipReason::TooLarge => write!(f, "tooLarge"),
+      SkipReason::Minified => write!(f, "minified"),
+    }
+  }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+  let head = &bytes[..bytes.len().min(BINARY_CHECK_SIZE)];
+  head.contains(&0)
+}
+
+/// Minified code has very long lines and little whitespace.
+/// A long line alone is not enough since normal code can embed long strings.
+fn is_minified(file_content: &str) -> bool {
+  if file_content.len() < MINIFIED_MIN_SIZE {
+    return false;
+  }
+  let has_long_line = file_content
+    .lines()
+    .any(|line| line.len() > MINIFIED_LINE_LENGTH);
+  if !has_long_line {
+    return false;
+  }
+  let whitespace = file_content
+    .bytes()
+    .filter(|b| b.is_ascii_whitespace())
+    .count();
+  (whitespace as f64) < file_content.len() as f64 * MINIFIED_WHITESPACE_RATIO
+}
+
+impl FileLimits {
+  /// Why the file content should be skipped, or None if it should be parsed.
+  pub fn skip_reason(&self, file_content: &str) -> Option<SkipReason> {
+    if self.is_too_large(file_content) {
+      Some(SkipReason::TooLarge)
+    } else if self.skip_minified && is_minified(file_content) {
+      Some(SkipReason::Minified)
+    } else {
+      None
+    }
+  }
+
+  /// Files exceeding any configured limit are too large.
+  /// Without any configuration, skip files that are too large in size AND have too many lines
+  fn is_too_large(&self, file_content: &str) -> bool {
+    if self.max_size.is_none() && self.max_lines.is_none() {
+      // the && operator is intentional here to include more files
+      return file_content.len() > MAX_FILE_SIZE && file_content.lines().count() > MAX_LINE_COUNT;
+    }
+    let too_big = self.max_size.is_some_and(|max| file_content.len() > max);
+    too_big
+      || self
+        .max_lines
+        .is_some_and(|max| file_content.lines().count() > max)
+  }
+}
+
+/// Parse size like `1024`, `512K`, `10M` or `1G`. Suffixes are powers of 1024.
+pub fn parse_file_size(size: &str) -> Result<usize, String> {
+  let size = size.trim();
+  let (num, unit) = match size.char_indices().last() {
+    Some((i, 'K' | 'k')) => (&size[..i], 1 << 10),
+    Some((i, 'M' | 'm')) => (&size[..i], 1 << 20),
+    Some((i, 'G' | 'g')) => (&size[..i], 1 << 30),
+    _ => (size, 1),
+  };
+  let num: usize = num.trim().parse().map_err(|_| {
+    format!("invalid file size `{size}`, expect a number with optional K/M/G suffix")
+  })?;
+  num
+    .checked_mul(unit)
+    .ok_or_else(|| format!("file size `{size}` is too large"))
 }
 
 /// A single atomic unit where matches happen.
@@ -212,6 +400,62 @@ mod test {
   use super::*;
   use ast_grep_language::SupportLang;
 
+  #[test]
+  fn test_combined_scans() {
+    let rule = "id: a\nlanguage: TypeScript\nrule: { pattern: a }";
+    let rules = ast_grep_config::from_yaml_string(rule, &Default::default()).expect("should parse");
+    let rules: Vec<_> = rules.iter().collect();
+    let mut scans = CombinedScans::default();
+    let first = scans.get(&rules) as *const _;
+    assert!(std::ptr::eq(first, scans.get(&rules)));
+    assert!(!scans.get(&rules[..0]).may_match("a"));
+    assert_eq!(scans.scans.len(), 2);
+  }
+
+  #[test]
+  fn test_binary_and_minified() {
+    assert!(is_binary(b"PK\x03\x04\x00\x00"));
+    assert!(!is_binary(b"let a = 1"));
+    let minified = "a=b+c;".repeat(300);
+    assert!(is_minified(&minified));
+    // long string in normal code
+    let code = format!("let a = 1\nlet s = '{}'\n", "x".repeat(1200));
+    let code = format!("{code}{}", "let b = a + a\n".repeat(200));
+    assert!(!is_minified(&code));
+    assert!(!is_minified("var a=1;"));
+  }
+
+  #[test]
+  fn test_parse_file_size() {
+    assert_eq!(parse_file_size("1024"), Ok(1024));
+    assert_eq!(parse_file_size("512K"), Ok(512 * 1024));
+    assert_eq!(parse_file_size("10m"), Ok(10 * 1024 * 1024));
+    assert_eq!(parse_file_size("1G"), Ok(1 << 30));
+    assert!(parse_file_size("10MB").is_err());
+    assert!(parse_file_size("M").is_err());
+  }
+
+  #[test]
+  fn test_file_limits() {
+    let minified = "a".repeat(100);
+    let default = FileLimits::default();
+    assert!(!default.is_too_large(&minified));
+    let by_size = FileLimits {
+      max_size: Some(50),
+      max_lines: None,
+      skip_minified: false,
+    };
+    assert!(by_size.is_too_large(&minified));
+    assert!(!by_size.is_too_large("a\nb\nc"));
+    let by_lines = FileLimits {
+      max_size: None,
+      max_lines: Some(2),
+      skip_minified: false,
+    };
+    assert!(!by_lines.is_too_large(&minified));
+    assert!(by_lines.is_too_large("a\nb\nc"));
+  }
+
   #[test]
   fn test_html_embedding() {
     let root =
@@ -2,9 +2,9 @@ use super::OverwriteArgs;
 use crate::lang::SgLang;
 use crate::utils::ErrorContext as EC;
 
-use anyhow::Result;
-use ast_grep_config::{RuleConfig, Severity};
-use ast_grep_core::Language;
+use anyhow::{Context, Result};
+use ast_grep_config::{GlobalRules, RuleConfig, Severity};
+use ast_grep_core::{Language, MatchStrictness};
 use regex::Regex;
 
 use std::collections::HashMap;
@@ -13,7 +13,10 @@ use std::collections::HashMap;
 pub struct RuleOverwrite {
   default_severity: Option<Severity>,
   by_rule_id: HashMap<String, Severity>,
+  /// wildcard patterns from `--severity`, the later one takes precedence
+  by_pattern: Vec<(Regex, Severity)>,
   rule_filter: Option<Regex>,
+  strictness: Option<MatchStrictness>,
 }
 
 fn read_severity(
@@ -66,10 +69,17 @@ impl RuleOverwrite {
       &mut by_rule_id,
       &mut default_severity,
     );
+    let by_pattern = cli
+      .severity
+      .iter()
+      .map(|s| (s.pattern.clone(), s.severity.clone()))
+      .collect();
     Ok(Self {
       default_severity,
       by_rule_id,
+      by_pattern,
       rule_filter: cli.filter.clone(),
+      strictness: cli.strictness.as_ref().map(|s| s.0.clone()),
     })
   }
 
@@ -89,10 +99,52 @@ impl RuleOverwrite {
     Ok(configs)
   }
 
+  /// Same as `process_configs` but it is not an error if no rule matches the filter.
+  /// Used for rules of nested configs, which can be a subset of project rules.
+  pub fn overwrite_configs(&self, mut configs: Vec<RuleConfig<SgLang>>) -> Vec<RuleConfig<SgLang>> {
+    if let Some(filter) = &self.rule_filter {
+      configs.retain(|c| filter.is_match(&c.id));
+    }
+    for config in &mut configs {
+      let overwrite = self.find(&config.id);
+      overwrite.overwrite(config);
+    }
+    configs
+  }
+
+  /// Strictness from `--strictness` to apply to all pattern rules, including utility rules.
+  pub fn strictness(&self) -> Option<&MatchStrictness> {
+    self.strictness.as_ref()
+  }
+
+  /// Recompile rules with the strictness from `--strictness`, if any.
+  pub fn override_strictness(
+    &self,
+    configs: Vec<RuleConfig<SgLang>>,
+    globals: &GlobalRules<SgLang>,
+  ) -> Result<Vec<RuleConfig<SgLang>>> {
+    let Some(strictness) = &self.strictness else {
+      return Ok(configs);
+    };
+    configs
+      .into_iter()
+      .map(|config| {
+        let id = config.id.clone();
+        config
+          .with_strictness(strictness.clone(), globals)
+          .with_context(|| EC::ParseRule(id.into()))
+      })
+      .collect()
+  }
+
   pub fn find(&self, id: &str) -> OverwriteResult {
     let severity = self
       .by_rule_id
       .get(id)
+      .or_else(|| {
+        let mut patterns = self.by_pattern.iter().rev();
+        patterns.find(|(p, _)| p.is_match(id)).map(|(_, s)| s)
+      })
       .cloned()
       .or_else(|| self.default_severity.clone());
     OverwriteResult { severity }
@@ -129,3 +181,76 @@ impl OverwriteResult {
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn overwrite(args: &str) -> RuleOverwrite {
+    use clap::Parser;
+    #[derive(Parser)]
+    struct Cli {
+      // dummy arg for `conflicts_with` in OverwriteArgs
+      #[clap(long)]
+      rule: Option<String>,
+      #[clap(flatten)]
+      overwrite: OverwriteArgs,
+    }
+    let args = std::iter::once("sg").chain(args.split(' '));
+    let cli = Cli::try_parse_from(args).expect("should parse");
+    RuleOverwrite::new(&cli.overwrite).expect("should create overwrite")
+  }
+
+  fn severity_of(overwrite: &RuleOverwrite, id: &str) -> Option<Severity> {
+    overwrite.find(id).severity
+  }
+
+  #[test]
+  fn test_severity_pattern() {
+    let ret = overwrite("--severity no-*=off --severity no-console=warning");
+    assert!(matches!(
+      severity_of(&ret, "no-console"),
+      Some(Severity::Warning)
+    ));
+    assert!(matches!(
+      severity_of(&ret, "no-debugger"),
+      Some(Severity::Off)
+    ));
+    assert!(severity_of(&ret, "prefer-const").is_none());
+  }
+
+  #[test]
+  fn test_severity_precedence() {
+    // exact id has higher priority than pattern, pattern is higher than default
+    let ret = overwrite("--error=no-console --severity no-*=hint --info");
+    assert!(matches!(
+      severity_of(&ret, "no-console"),
+      Some(Severity::Error)
+    ));
+    assert!(matches!(
+      severity_of(&ret, "no-debugger"),
+      Some(Severity::Hint)
+    ));
+    assert!(matches!(
+      severity_of(&ret, "prefer-const"),
+      Some(Severity::Info)
+    ));
+  }
+
+  #[test]
+  fn test_override_strictness() {
+    use ast_grep_config::from_yaml_string;
+    use ast_grep_core::Language;
+    let rules = "{id: test, language: ts, rule: {pattern: 'foo(a)'}}";
+    let globals = GlobalRules::default();
+    let src = SgLang::from(ast_grep_language::SupportLang::TypeScript).ast_grep("foo(b)");
+    let configs = from_yaml_string(rules, &globals).expect("should parse");
+    let configs = RuleOverwrite::default().override_strictness(configs, &globals);
+    let configs = configs.expect("should work");
+    assert!(src.root().find(&configs[0].matcher).is_none());
+    let configs = from_yaml_string(rules, &globals).expect("should parse");
+    let configs = overwrite("--strictness signature").override_strictness(configs, &globals);
+    let configs = configs.expect("should work");
+    assert!(src.root().find(&configs[0].matcher).is_some());
+  }
+}
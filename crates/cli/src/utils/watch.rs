@@ -0,0 +1,106 @@
+use ignore::WalkBuilder;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// A polling file watcher over a set of directories.
+/// It compares modification times so it works on every platform without native notification.
+pub struct FileWatcher {
+  dirs: Vec<PathBuf>,
+  filter: fn(&Path) -> bool,
+  mtimes: HashMap<PathBuf, SystemTime>,
+}
+
+impl FileWatcher {
+  /// Watch files under `dirs` that pass the `filter`.
+  pub fn new(dirs: Vec<PathBuf>, filter: fn(&Path) -> bool) -> Self {
+    let mut watcher = Self {
+      dirs,
+      filter,
+      mtimes: HashMap::new(),
+    };
+    watcher.refresh();
+    watcher
+  }
+
+  /// Forget changes since last poll, e.g. files written by ast-grep itself.
+  pub fn refresh(&mut self) {
+    self.mtimes = self.collect_mtimes();
+  }
+
+  /// Block until some files are modified, created or removed, then return their paths.
+  pub fn wait_for_changes(&mut self) -> Vec<PathBuf> {
+    loop {
+      sleep(POLL_INTERVAL);
+      let changed = self.poll();
+      if !changed.is_empty() {
+        return changed;
+      }
+    }
+  }
+
+  fn poll(&mut self) -> Vec<PathBuf> {
+    let current = self.collect_mtimes();
+    let mut changed: Vec<_> = current
+      .iter()
+      .filter(|(path, time)| self.mtimes.get(*path) != Some(time))
+      .map(|(path, _)| path.clone())
+      .collect();
+    let removed = self.mtimes.keys().filter(|p| !current.contains_key(*p));
+    changed.extend(removed.cloned());
+    changed.sort();
+    self.mtimes = current;
+    changed
+  }
+
+  fn collect_mtimes(&self) -> HashMap<PathBuf, SystemTime> {
+    let mut mtimes = HashMap::new();
+    for dir in self.dirs.iter().filter(|d| d.exists()) {
+      for entry in WalkBuilder::new(dir).build().flatten() {
+        let path = entry.path();
+        if !entry.file_type().is_some_and(|t| t.is_file()) || !(self.filter)(path) {
+          continue;
+        }
+        if let Some(time) = entry.metadata().ok().and_then(|m| m.modified().ok()) {
+          mtimes.insert(path.to_path_buf(), time);
+        }
+      }
+    }
+    mtimes
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::fs;
+  use tempfile::TempDir;
+
+  fn is_yaml(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "yml")
+  }
+
+  #[test]
+  fn test_poll_changes() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let rule = dir.path().join("rule.yml");
+    fs::write(&rule, "id: a")?;
+    fs::write(dir.path().join("note.txt"), "ignored")?;
+    let mut watcher = FileWatcher::new(vec![dir.path().to_path_buf()], is_yaml);
+    assert!(watcher.poll().is_empty());
+    let new_rule = dir.path().join("new.yml");
+    fs::write(&new_rule, "id: b")?;
+    fs::write(dir.path().join("other.txt"), "ignored")?;
+    assert_eq!(watcher.poll(), vec![new_rule.clone()]);
+    fs::remove_file(&rule)?;
+    assert_eq!(watcher.poll(), vec![rule]);
+    fs::write(&new_rule, "id: c")?;
+    watcher.refresh();
+    assert!(watcher.poll().is_empty());
+    Ok(())
+  }
+}
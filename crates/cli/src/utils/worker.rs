@@ -3,6 +3,7 @@ use crate::utils::FileTrace;
 
 use anyhow::{anyhow, Result};
 use ignore::{DirEntry, WalkParallel, WalkState};
+use rayon::iter::{ParallelBridge, ParallelIterator};
 
 use std::path::{Path, PathBuf};
 use std::sync::{mpsc, Arc};
@@ -28,15 +29,26 @@ pub trait Worker: Sync + Send {
 ///
 /// It follows multiple-producer-single-consumer pattern.
 /// ast-grep discovers files in parallel by `build_walk`.
-/// Then every file is parsed and filtered in `produce_item`.
+/// Then every file is parsed and filtered in `produce_item` on a work-stealing rayon pool
+/// of `get_threads` threads, so a few huge files do not block directory walking.
 /// Finally, `produce_item` will send `Item` to the consumer thread.
 pub trait PathWorker: Worker {
   /// WalkParallel will determine what files will be processed.
   fn build_walk(&self) -> Result<WalkParallel>;
+  /// Number of threads to parse and match files, usually from `--threads`.
+  fn get_threads(&self) -> usize;
+  /// State of a producer thread reused across files, e.g. rule indexes built once per worker.
+  type State<'w>: Default
+  where
+    Self: 'w;
   /// Record trace for the worker.
   fn get_trace(&self) -> &FileTrace;
   /// Parse and find_match can be done in `produce_item`.
-  fn produce_item(&self, path: &Path) -> Option<Vec<Self::Item>>;
+  fn produce_item<'w>(
+    &'w self,
+    path: &Path,
+    state: &mut Self::State<'w>,
+  ) -> Option<Vec<Self::Item>>;
 
   fn run_path<P: Printer>(self, printer: P) -> Result<()>
   where
@@ -101,38 +113,55 @@ fn filter_result(result: Result<DirEntry, ignore::Error>) -> Option<PathBuf> {
   }
 }
 
+/// Maximum number of pending paths or items between pipeline stages.
+/// It bounds memory usage when a later stage is slower, e.g. printing.
+const CHANNEL_BOUND: usize = 256;
+
 fn run_worker<W: PathWorker + ?Sized + 'static, P: Printer>(
   worker: Arc<W>,
   printer: P,
 ) -> Result<()> {
-  let (tx, rx) = mpsc::channel();
-  let w = worker.clone();
+  let (path_tx, path_rx) = mpsc::sync_channel(CHANNEL_BOUND);
+  let (item_tx, item_rx) = mpsc::sync_channel(CHANNEL_BOUND);
   let walker = worker.build_walk()?;
-  // walker run will block the thread
+  let pool = rayon::ThreadPoolBuilder::new()
+    .num_threads(worker.get_threads())
+    .build()?;
+  // walker threads only discover files, walker run will block the thread
   std::thread::spawn(move || {
-    let tx = tx;
     walker.run(|| {
-      let tx = tx.clone();
-      let w = w.clone();
+      let tx = path_tx.clone();
       Box::new(move |result| {
         let Some(p) = filter_result(result) else {
           return WalkState::Continue;
         };
-        let stats = w.get_trace();
-        stats.add_scanned();
-        let Some(items) = w.produce_item(&p) else {
-          stats.add_skipped();
-          return WalkState::Continue;
-        };
-        for result in items {
-          match tx.send(result) {
-            Ok(_) => continue,
-            Err(_) => return WalkState::Quit,
-          }
+        match tx.send(p) {
+          Ok(_) => WalkState::Continue,
+          Err(_) => WalkState::Quit,
         }
-        WalkState::Continue
       })
     });
   });
-  worker.consume_items(Items(rx), printer)
+  // parse and match files with work stealing, which will block the thread
+  let w = worker.clone();
+  std::thread::spawn(move || {
+    let w = &*w;
+    let init = || (item_tx.clone(), W::State::default());
+    // error means the consumer has stopped receiving
+    let _ = pool.install(|| {
+      path_rx
+        .into_iter()
+        .par_bridge()
+        .try_for_each_init(init, |(tx, state), p| {
+          let stats = w.get_trace();
+          stats.add_scanned();
+          let Some(items) = w.produce_item(&p, state) else {
+            stats.add_skipped();
+            return Ok(());
+          };
+          items.into_iter().try_for_each(|item| tx.send(item))
+        })
+    });
+  });
+  worker.consume_items(Items(item_rx), printer)
 }
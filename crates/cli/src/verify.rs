@@ -1,4 +1,5 @@
 mod case_result;
+mod coverage;
 mod find_file;
 mod reporter;
 mod snapshot;
@@ -6,25 +7,28 @@ mod test_case;
 
 use crate::config::ProjectConfig;
 use crate::lang::SgLang;
-use crate::utils::ErrorContext;
+use crate::print::{ColorChoice, OutputWriter};
+use crate::utils::{ErrorContext, FileWatcher};
 use anyhow::{anyhow, Result};
 use ast_grep_config::RuleCollection;
 use ast_grep_core::{Node as SgNode, StrDoc};
 use clap::Args;
 use regex::Regex;
-use serde_yaml::to_string;
+use serde::Deserialize;
+use serde_yaml::{to_string, Value};
 
 use std::collections::HashMap;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
 use case_result::{CaseResult, CaseStatus};
+use coverage::{CoverageFormat, CoverageReport};
 use find_file::TestHarness;
 use reporter::{DefaultReporter, InteractiveReporter, Reporter};
 use snapshot::{SnapshotAction, SnapshotCollection, TestSnapshots};
-use test_case::TestCase;
+pub use test_case::TestCase;
 
 type Node<'a, L> = SgNode<'a, StrDoc<L>>;
 
@@ -94,6 +98,10 @@ fn run_test_rule_impl<R: Reporter + Send>(
   let action = reporter.collect_snapshot_action();
   apply_snapshot_action(action, &results, snapshots, path_map)?;
   reporter.report_summaries(&results)?;
+  if let Some(format) = arg.coverage {
+    let report = CoverageReport::collect(collections, &test_cases, arg.filter.as_ref());
+    report.print(format, reporter.get_output())?;
+  }
   let (passed, message) = reporter.after_report(&results)?;
   if passed {
     writeln!(reporter.get_output(), "{message}",)?;
@@ -138,14 +146,33 @@ fn verify_test_case_simple<'a>(
   rules: &RuleCollection<SgLang>,
   snapshots: Option<&SnapshotCollection>,
 ) -> Option<CaseResult<'a>> {
-  let rule_config = rules.get_rule(&test_case.id)?;
-  let test_case = if let Some(snapshots) = snapshots {
-    let snaps = snapshots.get(&test_case.id);
-    test_case.verify_with_snapshot(rule_config, snaps)
-  } else {
-    test_case.verify_rule(rule_config)
-  };
-  Some(test_case)
+  // a rule listing several languages is cloned per language with the same id, test every clone
+  let mut rule_configs = vec![];
+  rules.for_each_rule(|rule| {
+    if rule.id == test_case.id {
+      rule_configs.push(rule);
+    }
+  });
+  let mut result: Option<CaseResult> = None;
+  for rule_config in rule_configs {
+    let ret = if let Some(snapshots) = snapshots {
+      let snaps = snapshots.get(&test_case.id);
+      test_case.verify_with_snapshot(rule_config, snaps)
+    } else {
+      test_case.verify_rule(rule_config)
+    };
+    let Some(merged) = &mut result else {
+      result = Some(ret);
+      continue;
+    };
+    // report the first failure of every case among the languages
+    for (case, status) in merged.cases.iter_mut().zip(ret.cases) {
+      if case.is_pass() && !status.is_pass() {
+        *case = status;
+      }
+    }
+  }
+  result
 }
 
 // for result in summary {
@@ -158,7 +185,7 @@ fn verify_test_case_simple<'a>(
 //   }
 // }
 
-#[derive(Args)]
+#[derive(Args, Clone)]
 pub struct TestArg {
   /// the directories to search test YAML files
   #[clap(short, long)]
@@ -181,10 +208,35 @@ pub struct TestArg {
   /// Only run rule test cases that matches REGEX.
   #[clap(short, long, value_name = "REGEX")]
   filter: Option<Regex>,
+  /// Watch rule and test files, and re-run tests of the changed rules.
+  /// Conflicts with --interactive.
+  #[clap(short, long, conflicts_with = "interactive")]
+  watch: bool,
+  /// Report rules without test cases or invalid cases,
+  /// and branches of `any` rules that never match code in tests.
+  #[clap(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "table")]
+  coverage: Option<CoverageFormat>,
+  /// Write the test report to FILE instead of stdout, without colors.
+  /// Conflicts with --interactive and --watch.
+  #[clap(
+    long = "output",
+    value_name = "FILE",
+    conflicts_with = "interactive",
+    conflicts_with = "watch"
+  )]
+  output_file: Option<PathBuf>,
 }
 
 pub fn run_test_rule(arg: TestArg, project: Result<ProjectConfig>) -> Result<()> {
   let project = project?;
+  if arg.watch {
+    run_test_watch(arg, project)
+  } else {
+    run_test_once(arg, project)
+  }
+}
+
+fn run_test_once(arg: TestArg, project: ProjectConfig) -> Result<()> {
   if arg.interactive {
     let reporter = InteractiveReporter {
       output: std::io::stdout(),
@@ -192,18 +244,106 @@ pub fn run_test_rule(arg: TestArg, project: Result<ProjectConfig>) -> Result<()>
     };
     run_test_rule_impl(arg, reporter, project)
   } else {
+    let output = match &arg.output_file {
+      Some(path) => OutputWriter::file(path)?,
+      None => OutputWriter::stdout(ColorChoice::Auto),
+    };
     let reporter = DefaultReporter {
-      output: std::io::stdout(),
+      output,
       update_all: arg.update_all,
     };
     run_test_rule_impl(arg, reporter, project)
   }
 }
 
+/// Run all tests first, then re-run the affected tests whenever rule or test files change.
+fn run_test_watch(arg: TestArg, project: ProjectConfig) -> Result<()> {
+  // rules using changed utils or rewriters are unknown, all tests are affected
+  let util_dirs: Vec<_> = project
+    .util_dirs
+    .iter()
+    .chain(&project.rewriter_dirs)
+    .flatten()
+    .map(|d| project.project_dir.join(d))
+    .collect();
+  let mut dirs: Vec<_> = project
+    .rule_dirs
+    .iter()
+    .map(|d| project.project_dir.join(d))
+    .chain(util_dirs.iter().cloned())
+    .collect();
+  if let Some(test_dir) = &arg.test_dir {
+    dirs.push(std::env::current_dir()?.join(test_dir));
+  } else {
+    let tests = project.test_configs.iter().flatten();
+    dirs.extend(tests.map(|t| project.project_dir.join(&t.test_dir)));
+  }
+  let mut watcher = FileWatcher::new(dirs, is_yaml);
+  let mut filter = arg.filter.clone();
+  loop {
+    let run_arg = TestArg {
+      filter,
+      ..arg.clone()
+    };
+    if let Err(e) = run_test_once(run_arg, project.clone()) {
+      eprintln!("{e:#}");
+    }
+    println!("Watching for changes of rules and tests...");
+    // ignore snapshots written by the test run
+    watcher.refresh();
+    filter = loop {
+      let changed = watcher.wait_for_changes();
+      let Some(ids) = affected_rule_ids(&changed, &util_dirs) else {
+        break arg.filter.clone();
+      };
+      if let Some(regex) = id_filter(&ids, arg.filter.as_ref()) {
+        break Some(regex);
+      }
+    };
+  }
+}
+
+fn is_yaml(path: &Path) -> bool {
+  matches!(
+    path.extension().and_then(|e| e.to_str()),
+    Some("yml" | "yaml")
+  )
+}
+
+/// Ids in changed rule, test or snapshot files. None means all tests are affected,
+/// e.g. a util rule is changed or a file is removed.
+fn affected_rule_ids(changed: &[PathBuf], util_dirs: &[PathBuf]) -> Option<Vec<String>> {
+  let mut ids = vec![];
+  for path in changed {
+    if util_dirs.iter().any(|d| path.starts_with(d)) {
+      return None;
+    }
+    let yaml = std::fs::read_to_string(path).ok()?;
+    for doc in serde_yaml::Deserializer::from_str(&yaml) {
+      let value = Value::deserialize(doc).ok()?;
+      ids.push(value.get("id")?.as_str()?.to_string());
+    }
+  }
+  Some(ids)
+}
+
+/// Build a regex matching exactly the ids that also pass the user's filter.
+fn id_filter(ids: &[String], filter: Option<&Regex>) -> Option<Regex> {
+  let ids: Vec<_> = ids
+    .iter()
+    .filter(|id| filter.map_or(true, |f| f.is_match(id)))
+    .map(|id| regex::escape(id))
+    .collect();
+  if ids.is_empty() {
+    return None;
+  }
+  Regex::new(&format!("^(?:{})$", ids.join("|"))).ok()
+}
+
 #[cfg(test)]
 pub mod test {
   use super::*;
-  use ast_grep_config::{from_str, GlobalRules, RuleConfig};
+  use ast_grep_config::{from_str, from_yaml_string, GlobalRules, RuleConfig};
 
   pub const TEST_RULE: &str = "test-rule";
 
@@ -289,6 +429,22 @@ rule:
     assert_eq!(ret, test_case_result(CaseStatus::Missing("123")),);
   }
 
+  #[test]
+  fn test_rule_with_languages() {
+    let rule = get_rule_text("{kind: identifier, regex: ^number$}");
+    let rule = rule.replace("TypeScript", "[TypeScript, JavaScript]");
+    let rules = from_yaml_string(&rule, &Default::default()).expect("should parse");
+    let rules = RuleCollection::try_new(rules).expect("RuleCollection must be valid");
+    let case = TestCase {
+      id: TEST_RULE.into(),
+      valid: vec![],
+      invalid: vec!["let a: number".into()],
+    };
+    // TypeScript parses the type as `predefined_type`, so only the JavaScript clone reports it
+    let ret = verify_test_case_simple(&case, &rules, None);
+    assert_eq!(ret, test_case_result(CaseStatus::Missing("let a: number")));
+  }
+
   #[test]
   fn test_no_such_rule() {
     let case = TestCase {
@@ -310,9 +466,43 @@ rule:
       test_dir: None,
       update_all: false,
       filter: None,
+      watch: false,
+      coverage: None,
+      output_file: None,
     };
     assert!(run_test_rule(arg, Err(anyhow!("error"))).is_err());
   }
+
+  #[test]
+  fn test_affected_rule_ids() -> Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let rule = dir.path().join("rules/a.yml");
+    let util = dir.path().join("utils/u.yml");
+    std::fs::create_dir_all(rule.parent().unwrap())?;
+    std::fs::create_dir_all(util.parent().unwrap())?;
+    std::fs::write(&rule, "id: a\n---\nid: b\n")?;
+    std::fs::write(&util, "id: u\n")?;
+    let util_dirs = vec![dir.path().join("utils")];
+    let ids = affected_rule_ids(std::slice::from_ref(&rule), &util_dirs);
+    assert_eq!(ids, Some(vec!["a".to_string(), "b".to_string()]));
+    assert_eq!(affected_rule_ids(&[rule.clone(), util], &util_dirs), None);
+    let removed = dir.path().join("rules/removed.yml");
+    assert_eq!(affected_rule_ids(&[removed], &util_dirs), None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_id_filter() {
+    let ids = vec!["no-console".to_string(), "no-debugger".to_string()];
+    let regex = id_filter(&ids, None).expect("should build regex");
+    assert!(regex.is_match("no-console"));
+    assert!(!regex.is_match("no-console-log"));
+    let user = Regex::new("console").unwrap();
+    let regex = id_filter(&ids, Some(&user)).expect("should build regex");
+    assert!(!regex.is_match("no-debugger"));
+    let user = Regex::new("other").unwrap();
+    assert!(id_filter(&ids, Some(&user)).is_none());
+  }
   const TRANSFORM_TEXT: &str = "
 transform:
   B:
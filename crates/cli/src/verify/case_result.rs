@@ -72,7 +72,7 @@ impl<'a> CaseStatus<'a> {
       Err(_) => return CaseStatus::Error,
     };
     match snapshot {
-      Some(e) if e == &actual => CaseStatus::Reported,
+      Some(e) if actual.is_same(e) => CaseStatus::Reported,
       nullable => CaseStatus::Wrong {
         source: case,
         actual,
@@ -85,13 +85,7 @@ impl<'a> CaseStatus<'a> {
     let CaseStatus::Wrong { source, actual, .. } = self else {
       return false;
     };
-    let updated = std::mem::replace(
-      actual,
-      TestSnapshot {
-        fixed: None,
-        labels: vec![],
-      },
-    );
+    let updated = std::mem::take(actual);
     *self = CaseStatus::Updated { source, updated };
     true
   }
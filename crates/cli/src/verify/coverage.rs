@@ -0,0 +1,191 @@
+use super::TestCase;
+use crate::lang::SgLang;
+
+use anyhow::Result;
+use ast_grep_config::{RuleCollection, RuleConfig};
+use ast_grep_core::Language;
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Serialize;
+
+use std::io::Write;
+
+/// Output format of the test coverage report.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CoverageFormat {
+  /// Print a table of rules and a summary of missing tests.
+  Table,
+  /// Print the report as a JSON object.
+  Json,
+}
+
+/// Test coverage of one rule.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleCoverage {
+  id: String,
+  valid_cases: usize,
+  invalid_cases: usize,
+  /// paths of `any` branches that never match while the rule runs, e.g. `rule.any[1]`
+  unfired_branches: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct CoverageReport {
+  rules: Vec<RuleCoverage>,
+}
+
+impl CoverageReport {
+  pub fn collect(
+    rules: &RuleCollection<SgLang>,
+    test_cases: &[TestCase],
+    filter: Option<&Regex>,
+  ) -> Self {
+    let mut coverages = vec![];
+    rules.for_each_rule(|rule| {
+      if filter.map_or(true, |f| f.is_match(&rule.id)) {
+        coverages.push(rule_coverage(rule, test_cases));
+      }
+    });
+    coverages.sort_by(|a, b| a.id.cmp(&b.id));
+    Self { rules: coverages }
+  }
+
+  pub fn print(&self, format: CoverageFormat, output: &mut impl Write) -> Result<()> {
+    match format {
+      CoverageFormat::Json => writeln!(output, "{}", serde_json::to_string_pretty(self)?)?,
+      CoverageFormat::Table => self.print_table(output)?,
+    }
+    Ok(())
+  }
+
+  fn print_table(&self, output: &mut impl Write) -> Result<()> {
+    let width = self.rules.iter().map(|r| r.id.len()).max().unwrap_or(0);
+    let width = width.max("RULE".len());
+    writeln!(
+      output,
+      "{:width$}  VALID  INVALID  UNFIRED BRANCHES",
+      "RULE"
+    )?;
+    for rule in &self.rules {
+      writeln!(
+        output,
+        "{:width$}  {:>5}  {:>7}  {}",
+        rule.id,
+        rule.valid_cases,
+        rule.invalid_cases,
+        rule.unfired_branches.join(", ")
+      )?;
+    }
+    let untested: Vec<_> = self.ids(|r| r.valid_cases + r.invalid_cases == 0);
+    let no_invalid: Vec<_> = self.ids(|r| r.valid_cases > 0 && r.invalid_cases == 0);
+    let unfired: Vec<_> = self.ids(|r| !r.unfired_branches.is_empty());
+    writeln!(output)?;
+    writeln!(output, "Rules without tests: {}", summary(&untested))?;
+    writeln!(
+      output,
+      "Rules without invalid cases: {}",
+      summary(&no_invalid)
+    )?;
+    writeln!(output, "Rules with unfired branches: {}", summary(&unfired))?;
+    Ok(())
+  }
+
+  fn ids(&self, pred: impl Fn(&RuleCoverage) -> bool) -> Vec<&str> {
+    self
+      .rules
+      .iter()
+      .filter(|r| pred(r))
+      .map(|r| r.id.as_str())
+      .collect()
+  }
+}
+
+fn summary(ids: &[&str]) -> String {
+  if ids.is_empty() {
+    "none".to_string()
+  } else {
+    format!("{} ({})", ids.len(), ids.join(", "))
+  }
+}
+
+fn rule_coverage(rule: &RuleConfig<SgLang>, test_cases: &[TestCase]) -> RuleCoverage {
+  let cases: Vec<_> = test_cases.iter().filter(|c| c.id == rule.id).collect();
+  let valid_cases = cases.iter().map(|c| c.valid.len()).sum();
+  let invalid_cases = cases.iter().map(|c| c.invalid.len()).sum();
+  let codes = cases
+    .iter()
+    .flat_map(|c| c.valid.iter().chain(c.invalid.iter()));
+  // a branch fires if it matches a node while the whole rule runs on the test code
+  let hits = rule.matcher.record_any_hits(&rule.rule, || {
+    for code in codes {
+      let grep = rule.language.ast_grep(code);
+      grep.root().find_all(&rule.matcher).for_each(drop);
+    }
+  });
+  let unfired_branches = hits
+    .into_iter()
+    .filter(|(_, hit)| !hit)
+    .map(|(path, _)| path)
+    .collect();
+  RuleCoverage {
+    id: rule.id.clone(),
+    valid_cases,
+    invalid_cases,
+    unfired_branches,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::verify::test::get_rule_config;
+
+  fn test_case(valid: &[&str], invalid: &[&str]) -> TestCase {
+    TestCase {
+      id: "test-rule".into(),
+      valid: valid.iter().map(|s| s.to_string()).collect(),
+      invalid: invalid.iter().map(|s| s.to_string()).collect(),
+    }
+  }
+
+  #[test]
+  fn test_unfired_branches() {
+    let rule = get_rule_config(
+      "any:
+    - pattern: a
+    - pattern: b
+    - all:
+      - kind: call_expression
+      - has: { any: [ {pattern: c}, {pattern: d} ] }",
+    );
+    // c matches somewhere but never as a child of a call
+    let cases = [test_case(&["a"], &["d(c)"])];
+    let coverage = rule_coverage(&rule, &cases);
+    assert_eq!(coverage.valid_cases, 1);
+    assert_eq!(coverage.invalid_cases, 1);
+    assert_eq!(
+      coverage.unfired_branches,
+      ["rule.any[1]", "rule.any[2].all[1].has.any[0]"]
+    );
+  }
+
+  #[test]
+  fn test_coverage_report() -> Result<()> {
+    let rule = get_rule_config("pattern: a");
+    let rules = RuleCollection::try_new(vec![rule]).expect("should be valid");
+    let report = CoverageReport::collect(&rules, &[test_case(&["b"], &[])], None);
+    let mut output = vec![];
+    report.print(CoverageFormat::Table, &mut output)?;
+    let table = String::from_utf8(output)?;
+    assert!(table.contains("Rules without tests: none"));
+    assert!(table.contains("Rules without invalid cases: 1 (test-rule)"));
+    let report = CoverageReport::collect(&rules, &[], None);
+    let mut output = vec![];
+    report.print(CoverageFormat::Json, &mut output)?;
+    let json: serde_json::Value = serde_json::from_slice(&output)?;
+    assert_eq!(json["rules"][0]["id"], "test-rule");
+    assert_eq!(json["rules"][0]["invalidCases"], 0);
+    Ok(())
+  }
+}
@@ -199,6 +199,11 @@ fn report_case_detail_impl<W: Write>(
       writeln!(output, "{}", Style::new().italic().paint("For Code:"))?;
       indented_write(output, source)?;
       writeln!(output)?;
+      if let Some(fixed) = &actual.fixed {
+        writeln!(output, "{}", Style::new().italic().paint("Fixed Code:"))?;
+        print_diff(source, fixed, &styles, output, 3)?;
+        writeln!(output)?;
+      }
     }
     CaseStatus::Missing(s) => {
       writeln!(
@@ -321,10 +326,7 @@ mod test {
       CaseStatus::Noisy(MOCK),
       CaseStatus::Wrong {
         source: MOCK,
-        actual: TestSnapshot {
-          fixed: None,
-          labels: vec![],
-        },
+        actual: TestSnapshot::default(),
         expected: None,
       },
       CaseStatus::Error,
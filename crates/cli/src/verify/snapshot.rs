@@ -5,6 +5,7 @@ use ast_grep_core::{Language, NodeMatch, StrDoc};
 
 use super::{CaseResult, Node};
 use serde::{Deserialize, Serialize, Serializer};
+use similar::TextDiff;
 
 use std::collections::{BTreeMap, HashMap};
 
@@ -67,14 +68,33 @@ pub struct TestSnapshots {
   pub snapshots: HashMap<Source, TestSnapshot>,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct TestSnapshot {
+  /// code fixed by the first applicable fixer
   #[serde(skip_serializing_if = "Option::is_none")]
   pub fixed: Option<String>,
+  /// unified diff between the test code and `fixed`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub diff: Option<String>,
+  /// output of every applicable fixer, only recorded if the rule has more than one
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub fixes: Vec<FixSnapshot>,
+  /// metavariables produced by `transform`
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub transformed: BTreeMap<String, String>,
   pub labels: Vec<Label>,
 }
 
+/// The code produced by one fixer, labeled by its title.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FixSnapshot {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  pub fixed: String,
+}
+
 impl TestSnapshot {
   /// Generate snapshot from rule and test case code
   // Ideally we should return Option<Result<T>>
@@ -82,25 +102,74 @@ impl TestSnapshot {
   // then Result<T> indicates if we have error during replace
   // But to reuse anyhow we use the Result<Option<T>>
   pub fn generate(rule_config: &RuleConfig<SgLang>, case: &str) -> Result<Option<Self>> {
-    let mut sg = rule_config.language.ast_grep(case);
+    let sg = rule_config.language.ast_grep(case);
     let rule = &rule_config.matcher;
     let Some(matched) = sg.root().find(rule) else {
       return Ok(None);
     };
+    let transformed = transformed_vars(rule_config, &matched);
+    let fixers: Vec<_> = rule
+      .fixer
+      .iter()
+      .filter(|f| f.is_applicable(&matched))
+      .collect();
     let labels = Label::from_matched(matched);
-    let Some(fix) = &rule_config.matcher.fixer else {
-      return Ok(Some(Self {
-        fixed: None,
-        labels,
-      }));
-    };
-    let changed = sg.replace(rule, fix)?;
-    debug_assert!(changed);
+    let mut fixes = vec![];
+    for fixer in fixers {
+      let mut sg = rule_config.language.ast_grep(case);
+      let changed = sg.replace(rule, fixer)?;
+      debug_assert!(changed);
+      fixes.push(FixSnapshot {
+        title: fixer.title().map(String::from),
+        fixed: sg.source().to_string(),
+      });
+    }
+    let fixed = fixes.first().map(|f| f.fixed.clone());
+    let diff = fixed.as_deref().map(|fixed| unified_diff(case, fixed));
+    if fixes.len() < 2 {
+      fixes.clear();
+    }
     Ok(Some(Self {
-      fixed: Some(sg.source().to_string()),
+      fixed,
+      diff,
+      fixes,
+      transformed,
       labels,
     }))
   }
+
+  /// Compare with the baseline snapshot.
+  /// `diff` is derived from `fixed`, so baselines recorded without it are still valid.
+  pub fn is_same(&self, baseline: &Self) -> bool {
+    self.fixed == baseline.fixed
+      && self.fixes == baseline.fixes
+      && self.transformed == baseline.transformed
+      && self.labels == baseline.labels
+  }
+}
+
+fn transformed_vars(
+  rule_config: &RuleConfig<SgLang>,
+  matched: &NodeMatch<StrDoc<SgLang>>,
+) -> BTreeMap<String, String> {
+  let Some(transform) = &rule_config.transform else {
+    return BTreeMap::new();
+  };
+  let env = matched.get_env();
+  transform
+    .keys()
+    .filter_map(|name| {
+      let bytes = env.get_transformed(name)?;
+      Some((name.clone(), String::from_utf8_lossy(bytes).into_owned()))
+    })
+    .collect()
+}
+
+fn unified_diff(old: &str, new: &str) -> String {
+  TextDiff::from_lines(old, new)
+    .unified_diff()
+    .context_radius(3)
+    .to_string()
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
@@ -175,14 +244,14 @@ mod tests {
     assert_eq!(
       result,
       Some(TestSnapshot {
-        fixed: None,
         labels: vec![Label {
           source: "let x = 42;".into(),
           message: None,
           style: LabelStyle::Primary,
           start: 0,
           end: 11,
-        }]
+        }],
+        ..Default::default()
       })
     );
     Ok(())
@@ -206,7 +275,6 @@ mod tests {
     assert_eq!(
       result,
       Some(TestSnapshot {
-        fixed: None,
         labels: vec![
           Label {
             source: "let x = 42;".into(),
@@ -223,11 +291,41 @@ mod tests {
             end: 31
           }
         ],
+        ..Default::default()
       })
     );
     Ok(())
   }
 
+  #[test]
+  fn test_fix_and_transform() -> Result<()> {
+    let rule_config = get_rule_config(
+      "pattern: let x = $A
+transform:
+  B:
+    substring: {source: $A, startChar: 1}
+fix:
+- {template: 'let y = $B', title: 'Rename'}
+- {template: 'const x = $A', title: 'Use const'}",
+    );
+    let case = "let x = 123;";
+    let result = TestSnapshot::generate(&rule_config, case)?.expect("should match");
+    assert_eq!(result.fixed.as_deref(), Some("let y = 23"));
+    assert_eq!(result.transformed["B"], "23");
+    assert_eq!(result.fixes.len(), 2);
+    assert_eq!(result.fixes[1].title.as_deref(), Some("Use const"));
+    assert_eq!(result.fixes[1].fixed, "const x = 123");
+    let diff = result.diff.as_deref().expect("should have diff");
+    assert!(diff.contains("-let x = 123;"));
+    assert!(diff.contains("+let y = 23"));
+    let baseline = TestSnapshot {
+      diff: None,
+      ..result.clone()
+    };
+    assert!(result.is_same(&baseline));
+    Ok(())
+  }
+
   #[test]
   fn test_snapshot_action() -> Result<()> {
     use crate::verify::CaseStatus;
@@ -8,11 +8,10 @@ pub fn create_test_files<'a>(
 ) -> Result<TempDir> {
   let dir = TempDir::new()?;
   for (name, contents) in names_and_contents {
-    if let Some((sub, _)) = name.split_once('/') {
-      let sub_dir = dir.path().join(sub);
+    let path = dir.path().join(name);
+    if let Some(sub_dir) = path.parent() {
       std::fs::create_dir_all(sub_dir)?;
     }
-    let path = dir.path().join(name);
     let mut file = File::create(path.clone())?;
     file.write_all(contents.as_bytes())?;
     file.sync_all()?;
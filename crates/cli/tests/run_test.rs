@@ -32,6 +32,28 @@ fn test_simple_specific_lang() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn test_stdin_filepath() -> Result<()> {
+  Command::cargo_bin("ast-grep")?
+    .args([
+      "-p",
+      "console.log($A)",
+      "--stdin",
+      "--stdin-filepath",
+      "src/a.ts",
+    ])
+    .write_stdin("console.log(123)")
+    .assert()
+    .success()
+    .stdout(contains("src/a.ts"))
+    .stdout(contains("console.log(123)"));
+  Command::cargo_bin("ast-grep")?
+    .args(["-p", "console.log($A)", "--stdin-filepath", "src/a.ts"])
+    .assert()
+    .failure();
+  Ok(())
+}
+
 #[test]
 fn test_js_in_html() -> Result<()> {
   let dir = create_test_files([
@@ -61,6 +83,34 @@ fn test_inspect() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn test_skip_minified_and_binary() -> Result<()> {
+  let minified = format!("alert(1);{}", "a=b+c;".repeat(300));
+  let dir = create_test_files([
+    ("a.min.js", minified.as_str()),
+    ("b.js", "alert(2)\0"),
+    ("c.js", "alert(3)"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["-p", "alert($A)", "-l", "js", "--inspect", "entity"])
+    .assert()
+    .success()
+    .stdout(contains("alert(3)"))
+    .stdout(contains("alert(1)").not())
+    .stdout(contains("alert(2)").not())
+    .stderr(contains("skipReason=minified"))
+    .stderr(contains("skipReason=binary"))
+    .stderr(contains("Cannot read file").not());
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["-p", "alert($A)", "-l", "js", "--no-skip-minified"])
+    .assert()
+    .success()
+    .stdout(contains("alert(1)"));
+  Ok(())
+}
+
 #[test]
 fn test_debug_query() -> Result<()> {
   // should not print pattern if invalid
@@ -82,6 +132,27 @@ fn test_debug_query() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn test_debug_query_match() -> Result<()> {
+  let dir = create_test_files([("a.ts", "a - b"), ("b.ts", "a * b")])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["-p", "a + b", "-l", "ts", "--debug-query=match", "a.ts"])
+    .assert()
+    .success()
+    .stderr(contains("Debug Match a.ts"))
+    .stderr(contains("expect `+` but found `-`"));
+  // files found in directories are not explained
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["-p", "a + b", "-l", "ts", "--debug-query=match"])
+    .assert()
+    .success()
+    .stderr(contains("Debug Match").not())
+    .stderr(contains("only explains files passed as paths"));
+  Ok(())
+}
+
 #[test]
 fn test_invalid_sg_config() -> Result<()> {
   let dir = create_test_files([("invalid.yml", "invalid")])?;
@@ -135,3 +206,99 @@ fn test_trace_project() -> Result<()> {
     .stderr(contains("isProject=true,projectDir"));
   Ok(())
 }
+
+#[test]
+fn test_rewrite_config() -> Result<()> {
+  let dir = create_test_files([
+    ("a.ts", "const someValue = 1"),
+    (
+      "rewrite.yml",
+      "
+transform:
+  NEW:
+    convert:
+      source: $A
+      toCase: snakeCase
+",
+    ),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["-p", "const $A = $B", "-r", "const $NEW = $B", "-l", "ts"])
+    .args(["--rewrite-config", "rewrite.yml", "-U"])
+    .assert()
+    .success();
+  let changed = std::fs::read_to_string(dir.path().join("a.ts"))?;
+  assert_eq!(changed, "const some_value = 1");
+  Ok(())
+}
+
+#[test]
+fn test_apply_from_report() -> Result<()> {
+  let dir = create_test_files([("a.ts", "console.log(123)\nconsole.log(456)")])?;
+  let report = Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["-p", "console.log($A)", "-r", "print($A)", "--json=stream"])
+    .assert()
+    .success()
+    .get_output()
+    .stdout
+    .clone();
+  std::fs::write(dir.path().join("report.json"), report)?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["apply", "--from", "report.json"])
+    .assert()
+    .success()
+    .stdout(contains("Applied 2 fix(es) to 1 file(s)"));
+  let content = std::fs::read_to_string(dir.path().join("a.ts"))?;
+  assert_eq!(content, "print(123)\nprint(456)");
+  // fixes are stale after applied
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["apply", "--from", "report.json"])
+    .assert()
+    .success()
+    .stdout(contains("Applied 0 fix(es)"));
+  Ok(())
+}
+
+#[test]
+fn test_format_patch() -> Result<()> {
+  let dir = create_test_files([("a.ts", "let a = 1\nconsole.log(123)\n")])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args([
+      "-p",
+      "console.log($A)",
+      "-r",
+      "print($A)",
+      "--format",
+      "patch",
+    ])
+    .assert()
+    .success()
+    .stdout(contains("diff --git a/a.ts b/a.ts"))
+    .stdout(contains("-console.log(123)\n+print(123)"));
+  let content = std::fs::read_to_string(dir.path().join("a.ts"))?;
+  assert_eq!(content, "let a = 1\nconsole.log(123)\n");
+  Ok(())
+}
+
+#[test]
+fn test_rewrite_stats() -> Result<()> {
+  let dir = create_test_files([
+    ("a.ts", "console.log(1)\nconsole.log(2)"),
+    ("b.ts", "console.log(3)"),
+    ("c.ts", "let a = 1"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["-p", "console.log($A)", "-r", "print($A)", "--stats"])
+    .assert()
+    .success()
+    .stdout(contains("2 file(s) would be modified by 3 edit(s)"));
+  let content = std::fs::read_to_string(dir.path().join("a.ts"))?;
+  assert_eq!(content, "console.log(1)\nconsole.log(2)");
+  Ok(())
+}
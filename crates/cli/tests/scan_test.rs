@@ -126,6 +126,83 @@ fn test_sg_scan_py_empty_text() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn test_sg_scan_shebang_script() -> Result<()> {
+  let inline_rules = "{id: test, language: py, rule: {pattern: print($A)}}";
+  let dir = create_test_files([
+    ("bin/tool", "#!/usr/bin/env python3\nprint(1)"),
+    ("bin/data", "print(2)"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--inline-rules", inline_rules, "--json=stream"])
+    .assert()
+    .success()
+    .stdout(contains("print(1)"))
+    .stdout(contains("print(2)").not());
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_stdin_filepath() -> Result<()> {
+  let inline_rules = "{id: test, language: ts, files: [./src/**], rule: {pattern: Some($A)}}";
+  Command::cargo_bin("ast-grep")?
+    .args(["scan", "--stdin", "--inline-rules", inline_rules])
+    .args(["--stdin-filepath", "src/a.ts"])
+    .write_stdin("Some(123)")
+    .assert()
+    .stdout(contains("src/a.ts:1:1"));
+  for path in ["lib/a.ts", "src/a.py"] {
+    Command::cargo_bin("ast-grep")?
+      .args(["scan", "--stdin", "--inline-rules", inline_rules])
+      .args(["--stdin-filepath", path])
+      .write_stdin("Some(123)")
+      .assert()
+      .success()
+      .stdout(contains("Some(123)").not());
+  }
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_format_patch() -> Result<()> {
+  let inline_rules = "{id: test, language: ts, rule: {pattern: Some($A)}, fix: $A}";
+  Command::cargo_bin("ast-grep")?
+    .args([
+      "scan",
+      "--stdin",
+      "--inline-rules",
+      inline_rules,
+      "--format",
+      "patch",
+    ])
+    .args(["--stdin-filepath", "a.ts"])
+    .write_stdin(
+      "Some(123)
+",
+    )
+    .assert()
+    .success()
+    .stdout(contains("--- a/a.ts\n+++ b/a.ts"))
+    .stdout(contains("-Some(123)\n+123"));
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_json_transform() -> Result<()> {
+  let inline_rules = "{id: test, language: ts, rule: {pattern: Some($A)}, transform: {B: {convert: {source: $A, toCase: upperCase}}}}";
+  let output = Command::cargo_bin("ast-grep")?
+    .args(["scan", "--stdin", "--json", "--inline-rules", inline_rules])
+    .write_stdin("Some(abc)")
+    .output()?;
+  let json: Value = from_slice(&output.stdout)?;
+  let vars = &json[0]["metaVariables"];
+  assert_eq!(vars["single"]["A"]["text"], "abc");
+  assert_eq!(vars["transformed"]["B"], "ABC");
+  Ok(())
+}
+
 #[test]
 fn test_sg_scan_html() -> Result<()> {
   let dir = create_test_files([
@@ -144,6 +221,125 @@ fn test_sg_scan_html() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn test_scan_skip_unparsed_files() -> Result<()> {
+  let rule = format!("{RULE1}ignores: ['**/ignored.ts']");
+  let dir = create_test_files([
+    ("rule.yml", rule.as_str()),
+    ("a.ts", "Some(1)"),
+    ("ignored.ts", "Some(2)"),
+    ("none.ts", "None"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "-r", "rule.yml", "--inspect=summary"])
+    .assert()
+    .success()
+    .stdout(contains("Some(1)"))
+    .stdout(contains("Some(2)").not())
+    .stderr(contains("scannedFileCount=3"))
+    .stderr(contains("unparsedFileCount=2"));
+  Ok(())
+}
+
+#[test]
+fn test_scan_inspect_json() -> Result<()> {
+  let dir = create_test_files([("rule.yml", RULE1), ("a.ts", "Some(1)")])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "-r", "rule.yml", "--inspect=json"])
+    .assert()
+    .success()
+    .stdout(contains("Some(1)"))
+    .stderr(contains(r#""type":"rule""#))
+    .stderr(contains(r#""id":"on-rule""#))
+    .stderr(contains(r#""parseMicros":"#))
+    .stderr(contains("scannedFileCount=").not());
+  Ok(())
+}
+
+#[test]
+fn test_scan_profile_rules() -> Result<()> {
+  let dir = create_test_files([("rule.yml", RULE1), ("a.ts", "Some(1)")])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "-r", "rule.yml", "--profile-rules"])
+    .assert()
+    .success()
+    .stdout(contains("Some(1)"))
+    .stderr(contains("RULE     FILES HIT  TOTAL MS   AVG MS"))
+    .stderr(contains("on-rule          1"));
+  Ok(())
+}
+
+#[test]
+fn test_scan_max_file_size() -> Result<()> {
+  let config = format!("{CONFIG}maxFileSize: 10\n");
+  let dir = create_test_files([
+    ("sgconfig.yml", config.as_str()),
+    ("rules/rule.yml", RULE1),
+    ("small.ts", "Some(1)"),
+    ("large.ts", "Some(123456789)"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan"])
+    .assert()
+    .success()
+    .stdout(contains("Some(1)"))
+    .stdout(contains("Some(123456789)").not());
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--max-filesize", "1K"])
+    .assert()
+    .success()
+    .stdout(contains("Some(123456789)"));
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["run", "-p", "Some($A)", "--max-filesize", "10"])
+    .assert()
+    .success()
+    .stdout(contains("Some(1)"))
+    .stdout(contains("Some(123456789)").not());
+  Ok(())
+}
+
+#[test]
+fn test_scan_custom_ignores() -> Result<()> {
+  let config = format!("{CONFIG}ignores:\n- generated/\n");
+  let dir = create_test_files([
+    ("sgconfig.yml", config.as_str()),
+    ("rules/rule.yml", RULE1),
+    (".astgrepignore", "vendor/\n"),
+    ("src/a.ts", "Some(1)"),
+    ("vendor/b.ts", "Some(2)"),
+    ("generated/c.ts", "Some(3)"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan"])
+    .assert()
+    .success()
+    .stdout(contains("Some(1)"))
+    .stdout(contains("Some(2)").not())
+    .stdout(contains("Some(3)").not());
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path().join("src"))
+    .args(["scan", ".."])
+    .assert()
+    .success()
+    .stdout(contains("Some(1)"))
+    .stdout(contains("Some(3)").not());
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--no-ignore", "dot"])
+    .assert()
+    .success()
+    .stdout(contains("Some(2)"))
+    .stdout(contains("Some(3)"));
+  Ok(())
+}
+
 #[test]
 fn test_scan_unused_suppression() -> Result<()> {
   let dir = create_test_files([
@@ -209,6 +405,38 @@ fn test_scan_unused_suppression_off() -> Result<()> {
   Ok(())
 }
 
+#[test]
+fn test_report_unused_suppressions() -> Result<()> {
+  let dir = create_test_files([
+    ("sgconfig.yml", CONFIG),
+    ("rules/rule.yml", RULE1),
+    (
+      "test.ts",
+      "None(123) // ast-grep-ignore\nSome(123) // ast-grep-ignore\n",
+    ),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args([
+      "scan",
+      "--filter",
+      "on-rule",
+      "--report-unused-suppressions",
+    ])
+    .assert()
+    .success()
+    .stdout(contains("unused-suppression"))
+    .stdout(contains("warning"));
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--report-unused-suppressions", "-U"])
+    .assert()
+    .success();
+  let content = std::fs::read_to_string(dir.path().join("test.ts"))?;
+  assert_eq!(content, "None(123) \nSome(123) // ast-grep-ignore\n");
+  Ok(())
+}
+
 #[test]
 fn test_severity_override() -> Result<()> {
   let dir = setup()?;
@@ -232,3 +460,448 @@ fn test_severity_override() -> Result<()> {
     .stdout(contains("warning"));
   Ok(())
 }
+
+#[test]
+fn test_scan_error_on() -> Result<()> {
+  let dir = setup()?;
+  // on-rule is a warning, which does not fail the scan by default
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan"])
+    .assert()
+    .success();
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--error-on", "warning"])
+    .assert()
+    .code(1)
+    .stdout(contains("on-rule"));
+  Ok(())
+}
+
+#[test]
+fn test_scan_output_file() -> Result<()> {
+  let dir = setup()?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--json", "--output", "reports/scan.json"])
+    .assert()
+    .success()
+    .stdout(predicate::str::is_empty());
+  let output = std::fs::read(dir.path().join("reports/scan.json"))?;
+  let json: Value = from_slice(&output)?;
+  assert_eq!(json[0]["ruleId"], "on-rule");
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--color", "always", "--output", "scan.txt"])
+    .assert()
+    .success();
+  let output = std::fs::read_to_string(dir.path().join("scan.txt"))?;
+  assert!(output.contains("on-rule"));
+  assert!(!output.contains('\u{1b}'));
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_baseline() -> Result<()> {
+  let dir = setup()?;
+  // create baseline from current findings
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--baseline", "baseline.json"])
+    .assert()
+    .success()
+    .stdout(contains("on-rule").not())
+    .stderr(contains("Baseline with 1 finding(s)"));
+  assert!(dir.path().join("baseline.json").exists());
+  // existing finding moved to another line is still suppressed
+  std::fs::write(dir.path().join("test.ts"), "\n\nSome(123)\nSome(456)")?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--baseline", "baseline.json", "--json=stream"])
+    .assert()
+    .success()
+    .stdout(contains("Some(456)"))
+    .stdout(contains("Some(123)").not());
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_sort_baseline() -> Result<()> {
+  let dir = setup()?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--baseline", "baseline.json"])
+    .assert()
+    .success();
+  std::fs::write(dir.path().join("a.ts"), "Some(456)")?;
+  // sorted files are scanned again but the baseline is only applied once
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--baseline", "baseline.json", "--sort", "path"])
+    .args(["--json=stream"])
+    .assert()
+    .success()
+    .stdout(contains("Some(456)"))
+    .stdout(contains("Some(123)").not());
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_suppress() -> Result<()> {
+  let dir = setup()?;
+  std::fs::write(dir.path().join("test.ts"), "if (a) {\n  Some(123)\n}\n")?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["suppress", "--rule-id", "on-rule"])
+    .assert()
+    .success()
+    .stdout(contains("Suppressed findings on 1 line(s) in 1 file(s)."));
+  let content = std::fs::read_to_string(dir.path().join("test.ts"))?;
+  assert_eq!(
+    content,
+    "if (a) {\n  // ast-grep-ignore: on-rule\n  Some(123)\n}\n"
+  );
+  // suppressed findings are not reported by scan anymore
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--json=stream"])
+    .assert()
+    .success()
+    .stdout(contains("Some(123)").not());
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["suppress", "--rule-id", "not-exist"])
+    .assert()
+    .failure()
+    .stderr(contains("Rule not found"));
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_multiple_fixes() -> Result<()> {
+  let inline_rules = "{id: test, language: ts, rule: {pattern: Some($A)}, fix: [{template: None, condition: {pattern: Some(456)}}, {template: $A}]}";
+  let dir = setup()?;
+  std::fs::write(dir.path().join("test.ts"), "Some(123)\nSome(456)")?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--inline-rules", inline_rules, "--json=stream"])
+    .assert()
+    .success()
+    .stdout(contains(r#""replacement":"123""#))
+    .stdout(contains(r#""replacement":"None""#));
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_dedup_fixes() -> Result<()> {
+  let inline_rules = "{id: a, language: ts, severity: hint, rule: {pattern: Some($A)}, fix: $A}
+---
+{id: b, language: ts, severity: error, rule: {pattern: Some($A)}, fix: $A}";
+  let dir = setup()?;
+  // the error finding is kept and the scan fails
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args([
+      "scan",
+      "--inline-rules",
+      inline_rules,
+      "--dedup-fixes",
+      "--json=stream",
+    ])
+    .assert()
+    .failure()
+    .stdout(contains(r#""ruleId":"b""#))
+    .stdout(contains(r#""mergedRuleIds":["a"]"#))
+    .stdout(contains(r#""ruleId":"a""#).not());
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--inline-rules", inline_rules, "--dedup-fixes"])
+    .assert()
+    .failure()
+    .stdout(contains("error[b]"))
+    .stdout(contains("Same fix by: a"));
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_requires() -> Result<()> {
+  let inline_rules = "{id: test, language: ts, rule: {pattern: Some($A)}, requires: {file: 'utils/**', rule: {pattern: 'function Some($$$) {}'}, absent: true}}";
+  let dir = setup()?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--inline-rules", inline_rules, "--json=stream"])
+    .assert()
+    .success()
+    .stdout(contains("Some(123)"));
+  std::fs::create_dir(dir.path().join("utils"))?;
+  std::fs::write(dir.path().join("utils/some.ts"), "function Some(a) {}")?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--inline-rules", inline_rules, "--json=stream"])
+    .assert()
+    .success()
+    .stdout(contains("Some(123)").not());
+  // required files outside the scanned paths are still searched
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args([
+      "scan",
+      "--inline-rules",
+      inline_rules,
+      "--json=stream",
+      "test.ts",
+    ])
+    .assert()
+    .success()
+    .stdout(contains("Some(123)").not());
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args([
+      "scan",
+      "--inline-rules",
+      inline_rules,
+      "--json=stream",
+      "--stdin",
+    ])
+    .write_stdin("Some(123)")
+    .assert()
+    .success()
+    .stdout(contains("Some(123)").not());
+  // globs are relative to the project root, not the current directory
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path().join("utils"))
+    .args([
+      "scan",
+      "--inline-rules",
+      inline_rules,
+      "--json=stream",
+      "../test.ts",
+    ])
+    .assert()
+    .success()
+    .stdout(contains("Some(123)").not());
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_shared_utils() -> Result<()> {
+  let rule = "
+id: use-util
+message: found
+severity: error
+language: TypeScript
+rule:
+  any:
+    - matches: some-call
+    - matches: none-call
+";
+  let utils = "
+id: some-call
+language: TypeScript
+rule: {pattern: Some($A)}
+---
+id: none-call
+language: TypeScript
+rule: {pattern: None()}
+";
+  let dir = create_test_files([
+    ("sgconfig.yml", "ruleDirs: [rules]\nutilDirs: [utils]"),
+    ("rules/rule.yml", rule),
+    ("utils/calls.yml", utils),
+    ("test.ts", "None()"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan"])
+    .assert()
+    .failure()
+    .stdout(contains("use-util"));
+  // the same util id in another file is an error
+  let duplicate = "{id: some-call, language: TypeScript, rule: {pattern: Some()}}";
+  std::fs::write(dir.path().join("utils/duplicate.yml"), duplicate)?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan"])
+    .assert()
+    .failure()
+    .stderr(contains("some-call"));
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_shared_rewriters() -> Result<()> {
+  let rule = "
+id: rewrite-args
+message: found
+severity: error
+language: TypeScript
+rule: {pattern: 'log($$$A)'}
+transform:
+  B:
+    rewrite:
+      rewriters: [to-str]
+      source: $$$A
+fix: print($B)
+";
+  let rewriter = "{id: to-str, rule: {kind: number}, fix: \"'num'\"}";
+  let dir = create_test_files([
+    (
+      "sgconfig.yml",
+      "ruleDirs: [rules]\nrewriterDirs: [rewriters]",
+    ),
+    ("rules/rule.yml", rule),
+    ("rewriters/to-str.yml", rewriter),
+    ("test.ts", "log(1, 2)"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--json=stream"])
+    .assert()
+    .failure()
+    .stdout(contains("print('num', 'num')"));
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_extends_package() -> Result<()> {
+  let package_rule = "
+id: package-rule
+message: rule from package
+severity: error
+language: TypeScript
+rule:
+  matches: some-call
+";
+  let package_util = "
+id: some-call
+language: TypeScript
+rule:
+  pattern: Some($A)
+";
+  let dir = create_test_files([
+    ("sgconfig.yml", "ruleDirs: []\nextends: [shared]"),
+    (
+      ".ast-grep/packages/shared/sgconfig.yml",
+      "ruleDirs: [rules]\nutilDirs: [utils]",
+    ),
+    (".ast-grep/packages/shared/rules/rule.yml", package_rule),
+    (
+      ".ast-grep/packages/shared/utils/some-call.yml",
+      package_util,
+    ),
+    ("test.ts", "Some(123)"),
+  ])?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan"])
+    .assert()
+    .failure()
+    .stdout(contains("package-rule"));
+  std::fs::write(
+    dir.path().join("sgconfig.yml"),
+    "ruleDirs: []\nextends: [missing]",
+  )?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan"])
+    .assert()
+    .failure()
+    .stderr(contains("missing"));
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_extends_config() -> Result<()> {
+  let shared_rule = "
+id: shared-rule
+message: rule from shared config
+severity: error
+language: TypeScript
+rule:
+  pattern: Some($A)
+";
+  let dir = create_test_files([
+    (
+      "project/sgconfig.yml",
+      "ruleDirs: []\nextends: [../shared/sgconfig.yml]",
+    ),
+    ("project/test.ts", "Some(123)"),
+    ("shared/sgconfig.yml", "ruleDirs: [rules]"),
+    ("shared/rules/rule.yml", shared_rule),
+  ])?;
+  let project = dir.path().join("project");
+  Command::cargo_bin("ast-grep")?
+    .current_dir(&project)
+    .args(["scan"])
+    .assert()
+    .failure()
+    .stdout(contains("shared-rule"));
+  // cyclic extends is an error
+  std::fs::write(
+    dir.path().join("shared/sgconfig.yml"),
+    "ruleDirs: [rules]\nextends: [../project/sgconfig.yml]",
+  )?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(&project)
+    .args(["scan"])
+    .assert()
+    .failure()
+    .stderr(contains("extends itself"));
+  drop(dir);
+  Ok(())
+}
+
+#[test]
+fn test_sg_scan_nested_config() -> Result<()> {
+  let legacy_rule = "
+id: legacy-rule
+message: rule for legacy code
+severity: error
+language: TypeScript
+rule:
+  pattern: legacy()
+";
+  let nested = "root: false\nruleDirs: [rules]\nseverities:\n  on-rule: off";
+  let dir = setup()?;
+  std::fs::create_dir_all(dir.path().join("legacy/rules"))?;
+  std::fs::write(dir.path().join("legacy/sgconfig.yml"), nested)?;
+  std::fs::write(dir.path().join("legacy/rules/rule.yml"), legacy_rule)?;
+  std::fs::write(dir.path().join("legacy/a.ts"), "Some(456)\nlegacy()")?;
+  std::fs::write(dir.path().join("b.ts"), "legacy()")?;
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--json=stream"])
+    .assert()
+    .failure()
+    .stdout(contains("Some(123)"))
+    .stdout(contains("Some(456)").not())
+    .stdout(contains("legacy-rule").count(1));
+  // project config is still found from the nested directory
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path().join("legacy"))
+    .args(["scan", "--json=stream"])
+    .assert()
+    .failure()
+    .stdout(contains("legacy-rule"));
+  // absolute paths are in scope too
+  Command::cargo_bin("ast-grep")?
+    .current_dir(dir.path())
+    .args(["scan", "--json=stream"])
+    .arg(dir.path().join("legacy/a.ts"))
+    .assert()
+    .failure()
+    .stdout(contains("Some(456)").not())
+    .stdout(contains("legacy-rule"));
+  drop(dir);
+  Ok(())
+}
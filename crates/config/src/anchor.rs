@@ -0,0 +1,82 @@
+//! YAML anchor helpers used by the `resolveAlias` constraint.
+//! An alias like `*base` is resolved to the node marked by the nearest
+//! preceding anchor `&base` in the same document of a YAML stream.
+
+use ast_grep_core::{Doc, Node};
+
+/// Returns the node that an alias refers to.
+/// The node can be an `alias` or a `flow_node` wrapping an alias, e.g. `*base` in `<<: *base`.
+/// Returns None if the node is not an alias or the anchor is not defined before it.
+pub fn resolve_alias<'r, D: Doc>(node: &Node<'r, D>) -> Option<Node<'r, D>> {
+  let alias = find_alias(node)?;
+  let text = alias.text();
+  let name = text.trim_start_matches('*').trim();
+  let start = alias.range().start;
+  // anchors do not cross documents in a multi-document stream
+  let scope = alias
+    .ancestors()
+    .find(|n| n.kind() == "document")
+    .or_else(|| alias.ancestors().last())?;
+  // later anchors override earlier ones with the same name
+  let anchor = scope
+    .dfs()
+    .take_while(|n| n.range().start < start)
+    .filter(|n| n.kind() == "anchor")
+    .filter(|n| n.text().trim_start_matches('&').trim() == name)
+    .last()?;
+  anchored_content(anchor)
+}
+
+fn find_alias<'r, D: Doc>(node: &Node<'r, D>) -> Option<Node<'r, D>> {
+  match &*node.kind() {
+    "alias" => Some(node.clone()),
+    "flow_node" => node.children().find(|n| n.kind() == "alias"),
+    _ => None,
+  }
+}
+
+/// Anchor is a property of a node, the content is the following sibling other than a tag.
+/// `&a !!map {b: 1}` anchors the flow mapping.
+fn anchored_content<'r, D: Doc>(anchor: Node<'r, D>) -> Option<Node<'r, D>> {
+  let content = anchor
+    .next_all()
+    .find(|n| n.is_named() && !matches!(&*n.kind(), "tag" | "comment"));
+  content.or_else(|| anchor.parent())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::test::Yaml;
+  use ast_grep_core::matcher::KindMatcher;
+  use ast_grep_core::Language;
+
+  fn resolve(src: &str, kind: &str) -> Option<String> {
+    let grep = Yaml.ast_grep(src);
+    let matcher = KindMatcher::new(kind, Yaml);
+    let node = grep.root().find(matcher).expect("should match");
+    resolve_alias(&node).map(|n| n.text().to_string())
+  }
+
+  #[test]
+  fn test_resolve_alias() {
+    let src = "base: &base\n  image: node\njob:\n  <<: *base\n  script: test";
+    assert_eq!(resolve(src, "alias"), Some("image: node".into()));
+    let src = "a: &v 1\nb: *v";
+    assert_eq!(resolve(src, "alias"), Some("1".into()));
+    let src = "a: &v !!str 1\nb: *v";
+    assert_eq!(resolve(src, "alias"), Some("1".into()));
+  }
+
+  #[test]
+  fn test_resolve_alias_scope() {
+    // the nearest anchor wins
+    let src = "a: &v 1\nb: &v 2\nc: *v";
+    assert_eq!(resolve(src, "alias"), Some("2".into()));
+    // anchor in a previous document is not visible
+    let src = "a: &v 1\n---\nb: *v";
+    assert_eq!(resolve(src, "alias"), None);
+    // not an alias
+    assert_eq!(resolve("a: 1", "plain_scalar"), None);
+  }
+}
@@ -25,7 +25,7 @@ pub fn check_rule_with_hint<'r, L: Language>(
   utils: &'r RuleRegistration<L>,
   constraints: &'r HashMap<String, Rule<L>>,
   transform: &'r Option<HashMap<String, Transformation>>,
-  fixer: &Option<Fixer<L>>,
+  fixer: &[Fixer<L>],
   hint: CheckHint<'r>,
 ) -> RResult<()> {
   match hint {
@@ -51,7 +51,7 @@ fn check_vars_in_rewriter<'r, L: Language>(
   utils: &'r RuleRegistration<L>,
   constraints: &'r HashMap<String, Rule<L>>,
   transform: &'r Option<HashMap<String, Transformation>>,
-  fixer: &Option<Fixer<L>>,
+  fixer: &[Fixer<L>],
   upper_var: &HashSet<&str>,
 ) -> RResult<()> {
   let vars = get_vars_from_rules(rule, utils);
@@ -80,7 +80,7 @@ fn check_vars<'r, L: Language>(
   utils: &'r RuleRegistration<L>,
   constraints: &'r HashMap<String, Rule<L>>,
   transform: &'r Option<HashMap<String, Transformation>>,
-  fixer: &Option<Fixer<L>>,
+  fixer: &[Fixer<L>],
 ) -> RResult<()> {
   let vars = get_vars_from_rules(rule, utils);
   let vars = check_var_in_constraints(vars, constraints)?;
@@ -148,11 +148,8 @@ fn check_var_in_transform<'r>(
   Ok(vars)
 }
 
-fn check_var_in_fix<L: Language>(vars: HashSet<&str>, fixer: &Option<Fixer<L>>) -> RResult<()> {
-  let Some(fixer) = fixer else {
-    return Ok(());
-  };
-  for var in fixer.used_vars() {
+fn check_var_in_fix<L: Language>(vars: HashSet<&str>, fixer: &[Fixer<L>]) -> RResult<()> {
+  for var in fixer.iter().flat_map(|f| f.used_vars()) {
     if !vars.contains(&var) {
       return Err(RuleCoreError::UndefinedMetaVar(var.to_string(), "fix"));
     }
@@ -1,14 +1,23 @@
 use crate::{RuleConfig, SerializableRule, SerializableRuleConfig, SerializableRuleCore, Severity};
 
 use ast_grep_core::language::Language;
+use ast_grep_core::source::Content;
 use ast_grep_core::{AstGrep, Doc, Matcher, Node, NodeMatch};
 
+use aho_corasick::AhoCorasick;
 use bit_set::BitSet;
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 pub struct ScanResult<'t, 'r, D: Doc, L: Language> {
   pub diffs: Vec<(&'r RuleConfig<L>, NodeMatch<'t, D>)>,
   pub matches: Vec<(&'r RuleConfig<L>, Vec<NodeMatch<'t, D>>)>,
+  /// ids of rules whose identical fixes were dropped by dedup,
+  /// keyed by the id of the rule kept and the range of its match
+  pub merged: HashMap<(&'r str, Range<usize>), Vec<&'r str>>,
 }
 
 /// store the index to the rule and the matched node
@@ -17,14 +26,66 @@ struct ScanResultInner<'t, D: Doc> {
   diffs: Vec<(usize, NodeMatch<'t, D>)>,
   matches: HashMap<usize, Vec<NodeMatch<'t, D>>>,
   unused_suppressions: Vec<NodeMatch<'t, D>>,
+  /// rules merged into the kept finding, keyed by the kept rule and the matched range
+  merged: HashMap<(usize, Range<usize>), Vec<usize>>,
 }
 
 impl<'t, D: Doc> ScanResultInner<'t, D> {
+  /// Drop fixes identical to a fix of another rule on the same range.
+  /// The finding of the most severe rule is kept, ties go to the smallest index, that is the
+  /// first rule by id. Ids of the dropped rules are recorded for the kept finding.
+  fn dedup_fixes<L: Language>(&mut self, combined: &CombinedScan<L>)
+  where
+    D: Doc<Lang = L>,
+  {
+    let key = |idx: usize, nm: &NodeMatch<D>| fix_key(combined.get_rule(idx), nm);
+    let mut groups: HashMap<_, Vec<_>> = HashMap::new();
+    let diffs = self.diffs.iter().map(|(idx, nm)| (*idx, nm));
+    let matches = self
+      .matches
+      .iter()
+      .flat_map(|(idx, nms)| nms.iter().map(move |nm| (*idx, nm)));
+    for (idx, nm) in diffs.chain(matches) {
+      if let Some(k) = key(idx, nm) {
+        groups.entry(k).or_default().push((idx, nm.range()));
+      }
+    }
+    let mut kept = HashMap::new();
+    for (k, group) in groups {
+      let rank = |idx: usize| severity_rank(&combined.get_rule(idx).severity);
+      let (idx, range) = group
+        .iter()
+        .max_by_key(|(idx, _)| (rank(*idx), Reverse(*idx)))
+        .cloned()
+        .expect("group should not be empty");
+      let mut others: Vec<_> = group.into_iter().map(|(i, _)| i).collect();
+      others.retain(|i| *i != idx);
+      others.sort_unstable();
+      others.dedup();
+      if !others.is_empty() {
+        self.merged.entry((idx, range)).or_default().extend(others);
+      }
+      kept.insert(k, idx);
+    }
+    let is_kept = |idx: usize, nm: &NodeMatch<D>| key(idx, nm).map_or(true, |k| kept[&k] == idx);
+    self.diffs.retain(|(idx, nm)| is_kept(*idx, nm));
+    for (idx, nms) in self.matches.iter_mut() {
+      nms.retain(|nm| is_kept(*idx, nm));
+    }
+    self.matches.retain(|_, nms| !nms.is_empty());
+  }
+
   pub fn into_result<'r, L: Language>(
-    self,
+    mut self,
     combined: &CombinedScan<'r, L>,
     separate_fix: bool,
-  ) -> ScanResult<'t, 'r, D, L> {
+  ) -> ScanResult<'t, 'r, D, L>
+  where
+    D: Doc<Lang = L>,
+  {
+    if combined.dedup_fixes {
+      self.dedup_fixes(combined);
+    }
     let mut diffs: Vec<_> = self
       .diffs
       .into_iter()
@@ -45,16 +106,68 @@ impl<'t, D: Doc> ScanResultInner<'t, D> {
         matches.push((rule, supprs));
       }
     }
-    ScanResult { diffs, matches }
+    let merged = self
+      .merged
+      .into_iter()
+      .map(|((idx, range), others)| {
+        let ids = others.into_iter().map(|i| combined.get_rule(i).id.as_str());
+        ((combined.get_rule(idx).id.as_str(), range), ids.collect())
+      })
+      .collect();
+    ScanResult {
+      diffs,
+      matches,
+      merged,
+    }
   }
 }
 
-struct Suppressions(HashMap<usize, Suppression>);
+fn severity_rank(severity: &Severity) -> u8 {
+  match severity {
+    Severity::Error => 3,
+    Severity::Warning => 2,
+    Severity::Info => 1,
+    Severity::Hint | Severity::Off => 0,
+  }
+}
+
+/// the replaced range and the fixed text identify a fix regardless of its rule
+fn fix_key<D: Doc>(
+  rule: &RuleConfig<D::Lang>,
+  nm: &NodeMatch<D>,
+) -> Option<(usize, usize, String)> {
+  let fixer = rule.matcher.get_fixer(nm)?;
+  let edit = nm.make_edit(&rule.matcher, fixer);
+  let text = D::Source::encode_bytes(&edit.inserted_text).into_owned();
+  Some((edit.position, edit.deleted_length, text))
+}
+
+#[derive(Default)]
+struct Suppressions {
+  /// line to suppression from `ast-grep-ignore`
+  lines: HashMap<usize, Suppression>,
+  /// byte ranges disabled by `ast-grep-disable` and `ast-grep-enable`
+  ranges: Vec<DisabledRange>,
+}
+
 impl Suppressions {
   fn collect<D: Doc>(&mut self, node: &Node<D>) {
-    if !node.kind().contains("comment") || !node.text().contains(IGNORE_TEXT) {
+    if !node.kind().contains("comment") {
       return;
     }
+    let text = node.text();
+    if text.contains(IGNORE_FILE_TEXT) {
+      // handled by FileSuppression before scanning
+    } else if text.contains(IGNORE_TEXT) {
+      self.collect_line(node, &text);
+    } else if text.contains(DISABLE_TEXT) {
+      self.disable(node, &text);
+    } else if text.contains(ENABLE_TEXT) {
+      self.enable(node, &text);
+    }
+  }
+
+  fn collect_line<D: Doc>(&mut self, node: &Node<D>, text: &str) {
     let line = node.start_pos().line();
     let suppress_next_line = if let Some(prev) = node.prev() {
       prev.start_pos().line() != line
@@ -62,27 +175,126 @@ impl Suppressions {
       true
     };
     let key = if suppress_next_line { line + 1 } else { line };
-    self.0.insert(
+    self.lines.insert(
       key,
       Suppression {
-        suppressed: parse_suppression_set(&node.text()),
+        suppressed: parse_suppression_set(text),
         node_id: node.node_id(),
       },
     );
   }
 
+  /// open a range until the matching `ast-grep-enable` or the end of file
+  fn disable<D: Doc>(&mut self, node: &Node<D>, text: &str) {
+    self.ranges.push(DisabledRange {
+      start: node.range().end,
+      end: usize::MAX,
+      suppression: Suppression {
+        suppressed: parse_rule_set(text, DISABLE_TEXT),
+        node_id: node.node_id(),
+      },
+    });
+  }
+
+  /// A bare `ast-grep-enable` closes all open ranges.
+  /// `ast-grep-enable: ids` only re-enables the listed rules,
+  /// so a range disabling all rules is not affected by it.
+  fn enable<D: Doc>(&mut self, node: &Node<D>, text: &str) {
+    let enabled = parse_rule_set(text, ENABLE_TEXT);
+    let range = node.range();
+    let mut reopened = vec![];
+    for open in self.ranges.iter_mut().filter(|r| r.end == usize::MAX) {
+      let Some(enabled) = &enabled else {
+        open.end = range.start;
+        continue;
+      };
+      let Some(disabled) = &open.suppression.suppressed else {
+        continue;
+      };
+      if disabled.is_disjoint(enabled) {
+        continue;
+      }
+      open.end = range.start;
+      let rest: HashSet<_> = disabled.difference(enabled).cloned().collect();
+      if !rest.is_empty() {
+        reopened.push(DisabledRange {
+          start: range.end,
+          end: usize::MAX,
+          suppression: Suppression {
+            suppressed: Some(rest),
+            node_id: open.suppression.node_id,
+          },
+        });
+      }
+    }
+    self.ranges.extend(reopened);
+  }
+
+  fn is_empty(&self) -> bool {
+    self.lines.is_empty() && self.ranges.is_empty()
+  }
+
   fn suppression_ids(&self) -> HashSet<usize> {
-    self.0.values().map(|s| s.node_id).collect()
+    let lines = self.lines.values().map(|s| s.node_id);
+    let ranges = self.ranges.iter().map(|r| r.suppression.node_id);
+    lines.chain(ranges).collect()
   }
 
-  fn check_suppression<D: Doc>(&mut self, node: &Node<D>) -> MaySuppressed {
+  fn check_suppression<D: Doc>(&self, node: &Node<D>) -> MaySuppressed {
     let line = node.start_pos().line();
-    if let Some(sup) = self.0.get_mut(&line) {
+    if let Some(sup) = self.lines.get(&line) {
       MaySuppressed::Yes(sup)
     } else {
       MaySuppressed::No
     }
   }
+
+  /// find the disabled range covering the node start that suppresses the rule
+  fn disabled_id<D: Doc>(&self, node: &Node<D>, rule_id: &str) -> Option<usize> {
+    let pos = node.range().start;
+    self
+      .ranges
+      .iter()
+      .filter(|r| r.start <= pos && pos < r.end)
+      .find_map(|r| MaySuppressed::Yes(&r.suppression).suppressed_id(rule_id))
+  }
+}
+
+/// Rules suppressed by `ast-grep-ignore-file` in the leading comments of a file.
+#[derive(Default)]
+struct FileSuppression {
+  all: bool,
+  rules: HashSet<String>,
+}
+
+impl FileSuppression {
+  fn collect<D: Doc>(root: &AstGrep<D>) -> Self {
+    let mut ret = Self::default();
+    for node in root.root().children() {
+      if !node.kind().contains("comment") {
+        break;
+      }
+      let text = node.text();
+      if !text.contains(IGNORE_FILE_TEXT) {
+        continue;
+      }
+      match parse_rule_set(&text, IGNORE_FILE_TEXT) {
+        Some(rules) => ret.rules.extend(rules),
+        None => ret.all = true,
+      }
+    }
+    ret
+  }
+
+  fn suppresses(&self, rule_id: &str) -> bool {
+    self.all || self.rules.contains(rule_id)
+  }
+}
+
+struct DisabledRange {
+  start: usize,
+  end: usize,
+  suppression: Suppression,
 }
 
 struct Suppression {
@@ -114,7 +326,11 @@ impl MaySuppressed<'_> {
   }
 }
 
+const SUPPRESSION_PREFIX: &str = "ast-grep-";
 const IGNORE_TEXT: &str = "ast-grep-ignore";
+const IGNORE_FILE_TEXT: &str = "ast-grep-ignore-file";
+const DISABLE_TEXT: &str = "ast-grep-disable";
+const ENABLE_TEXT: &str = "ast-grep-enable";
 
 pub struct PreScan {
   hit_set: BitSet,
@@ -123,7 +339,37 @@ pub struct PreScan {
 
 impl PreScan {
   pub fn is_empty(&self) -> bool {
-    self.hit_set.is_empty() && self.suppressions.0.is_empty()
+    self.hit_set.is_empty() && self.suppressions.is_empty()
+  }
+}
+
+/// Matching cost of one rule in a pre-scan.
+#[derive(Clone, Default)]
+pub struct RuleTiming {
+  /// id of the measured rule
+  pub id: String,
+  /// total time spent on matching the rule
+  pub elapsed: Duration,
+  /// number of nodes the rule is tested against
+  pub tested_nodes: usize,
+  /// whether the rule matches any node
+  pub matched: bool,
+}
+
+/// A project-wide index recording which rules have their `requires` condition found.
+/// It is built by scanning every candidate file before reporting any issue.
+#[derive(Default)]
+pub struct RequireIndex {
+  found: HashSet<String>,
+}
+
+impl RequireIndex {
+  /// Whether the rule can report issues according to its `requires` condition.
+  pub fn is_met<L: Language>(&self, rule: &RuleConfig<L>) -> bool {
+    let Some(require) = &rule.requires else {
+      return true;
+    };
+    self.found.contains(&rule.id) != require.absent
   }
 }
 
@@ -136,6 +382,13 @@ pub struct CombinedScan<'r, L: Language> {
   kind_rule_mapping: Vec<Vec<usize>>,
   /// a rule for unused_suppressions
   unused_suppression_rule: Option<&'r RuleConfig<L>>,
+  /// rules with unmet `requires` are skipped if the index is provided
+  require_index: Option<&'r RequireIndex>,
+  /// literals of which at least one must appear in the source for any rule to match
+  /// None if some rule has no literal
+  literal_filter: Option<AhoCorasick>,
+  /// report only one of the identical fixes made by different rules on the same range
+  dedup_fixes: bool,
 }
 
 impl<'r, L: Language> CombinedScan<'r, L> {
@@ -159,13 +412,58 @@ impl<'r, L: Language> CombinedScan<'r, L> {
         mapping[kind].push(idx);
       }
     }
+    let literal_filter = build_literal_filter(&rules);
     Self {
       rules,
       kind_rule_mapping: mapping,
       unused_suppression_rule: None,
+      require_index: None,
+      literal_filter,
+      dedup_fixes: false,
     }
   }
 
+  /// Checks the raw source text before parsing, like ripgrep's literal optimization.
+  /// Returns false only if no rule can possibly match the source,
+  /// so parsing the file can be skipped entirely.
+  pub fn may_match(&self, source: &str) -> bool {
+    // suppression comments need a full scan to report unused ones
+    if source.contains(SUPPRESSION_PREFIX) {
+      return true;
+    }
+    self
+      .literal_filter
+      .as_ref()
+      .map_or(true, |filter| filter.is_match(source))
+  }
+
+  pub fn set_require_index(&mut self, index: &'r RequireIndex) {
+    self.require_index = Some(index);
+  }
+
+  /// Records the rules whose `requires` condition is found in the file.
+  pub fn index_requires<D>(&self, path: &Path, root: &AstGrep<D>, index: &mut RequireIndex)
+  where
+    D: Doc<Lang = L>,
+  {
+    for rule in &self.rules {
+      let Some(require) = &rule.requires else {
+        continue;
+      };
+      if index.found.contains(&rule.id) || !require.is_candidate(path) {
+        continue;
+      }
+      if require.is_found(root) {
+        index.found.insert(rule.id.clone());
+      }
+    }
+  }
+
+  /// Report only the first rule by id if several rules make the same fix on the same range.
+  pub fn set_dedup_fixes(&mut self, dedup: bool) {
+    self.dedup_fixes = dedup;
+  }
+
   pub fn set_unused_suppression_rule(&mut self, rule: &'r RuleConfig<L>) {
     if matches!(rule.severity, Severity::Off) {
       return;
@@ -174,11 +472,52 @@ impl<'r, L: Language> CombinedScan<'r, L> {
   }
 
   pub fn find<D>(&self, root: &AstGrep<D>) -> PreScan
+  where
+    D: Doc<Lang = L>,
+  {
+    self.find_impl(root, None)
+  }
+
+  /// Same as `find` but also measures how long each rule takes to match.
+  /// Each timing carries the id of its rule.
+  pub fn find_with_timing<D>(&self, root: &AstGrep<D>) -> (PreScan, Vec<RuleTiming>)
+  where
+    D: Doc<Lang = L>,
+  {
+    let mut timings = self.new_timings();
+    let pre_scan = self.find_impl(root, Some(&mut timings));
+    (pre_scan, timings)
+  }
+
+  fn new_timings(&self) -> Vec<RuleTiming> {
+    let timing = |rule: &&RuleConfig<L>| RuleTiming {
+      id: rule.id.clone(),
+      ..Default::default()
+    };
+    self.rules.iter().map(timing).collect()
+  }
+
+  fn find_impl<D>(&self, root: &AstGrep<D>, mut timings: Option<&mut [RuleTiming]>) -> PreScan
   where
     D: Doc<Lang = L>,
   {
     let mut hit = BitSet::new();
-    let mut suppressions = Suppressions(HashMap::new());
+    let mut suppressions = Suppressions::default();
+    // skip matching file-level suppressed rules entirely
+    let file_suppression = FileSuppression::collect(root);
+    if file_suppression.all {
+      return PreScan {
+        hit_set: hit,
+        suppressions,
+      };
+    }
+    let skipped: BitSet = self
+      .rules
+      .iter()
+      .enumerate()
+      .filter(|(_, rule)| file_suppression.suppresses(&rule.id))
+      .map(|(idx, _)| idx)
+      .collect();
     for node in root.root().dfs() {
       suppressions.collect(&node);
       let kind = node.kind_id() as usize;
@@ -186,11 +525,18 @@ impl<'r, L: Language> CombinedScan<'r, L> {
         continue;
       };
       for &idx in rule_idx {
-        if hit.contains(idx) {
+        if hit.contains(idx) || skipped.contains(idx) {
           continue;
         }
         let rule = &self.rules[idx];
-        if rule.matcher.match_node(node.clone()).is_some() {
+        let start = timings.is_some().then(Instant::now);
+        let matched = rule.matcher.match_node(node.clone()).is_some();
+        if let (Some(timings), Some(start)) = (timings.as_deref_mut(), start) {
+          timings[idx].elapsed += start.elapsed();
+          timings[idx].tested_nodes += 1;
+          timings[idx].matched |= matched;
+        }
+        if matched {
           hit.insert(idx);
         }
       }
@@ -206,7 +552,36 @@ impl<'r, L: Language> CombinedScan<'r, L> {
     root: &'a AstGrep<D>,
     pre: PreScan,
     separate_fix: bool,
-  ) -> ScanResult<'a, '_, D, L>
+  ) -> ScanResult<'a, 'r, D, L>
+  where
+    D: Doc<Lang = L>,
+  {
+    self.scan_impl(root, pre, separate_fix, None)
+  }
+
+  /// Same as `scan` but also measures how long each rule takes to match.
+  /// Only the rules hit in the pre-scan are tested.
+  pub fn scan_with_timing<'a, D>(
+    &self,
+    root: &'a AstGrep<D>,
+    pre: PreScan,
+    separate_fix: bool,
+  ) -> (ScanResult<'a, 'r, D, L>, Vec<RuleTiming>)
+  where
+    D: Doc<Lang = L>,
+  {
+    let mut timings = self.new_timings();
+    let result = self.scan_impl(root, pre, separate_fix, Some(&mut timings));
+    (result, timings)
+  }
+
+  fn scan_impl<'a, D>(
+    &self,
+    root: &'a AstGrep<D>,
+    pre: PreScan,
+    separate_fix: bool,
+    mut timings: Option<&mut [RuleTiming]>,
+  ) -> ScanResult<'a, 'r, D, L>
   where
     D: Doc<Lang = L>,
   {
@@ -214,10 +589,11 @@ impl<'r, L: Language> CombinedScan<'r, L> {
       diffs: vec![],
       matches: HashMap::new(),
       unused_suppressions: vec![],
+      merged: HashMap::new(),
     };
     let PreScan {
       hit_set,
-      mut suppressions,
+      suppressions,
     } = pre;
     let mut suppression_ids = suppressions.suppression_ids();
     let mut suppression_nodes = HashMap::new();
@@ -235,10 +611,23 @@ impl<'r, L: Language> CombinedScan<'r, L> {
           continue;
         }
         let rule = &self.rules[idx];
-        let Some(ret) = rule.matcher.match_node(node.clone()) else {
+        if self.require_index.map_or(false, |index| !index.is_met(rule)) {
+          continue;
+        }
+        let start = timings.is_some().then(Instant::now);
+        let ret = rule.matcher.match_node(node.clone());
+        if let (Some(timings), Some(start)) = (timings.as_deref_mut(), start) {
+          timings[idx].elapsed += start.elapsed();
+          timings[idx].tested_nodes += 1;
+          timings[idx].matched |= ret.is_some();
+        }
+        let Some(ret) = ret else {
           continue;
         };
-        if let Some(id) = suppression.suppressed_id(&rule.id) {
+        let suppressed = suppression
+          .suppressed_id(&rule.id)
+          .or_else(|| suppressions.disabled_id(&node, &rule.id));
+        if let Some(id) = suppressed {
           suppression_ids.remove(&id);
           continue;
         }
@@ -290,18 +679,42 @@ impl<'r, L: Language> CombinedScan<'r, L> {
       note: None,
       rewriters: None,
       url: None,
+      requires: None,
+      labels: None,
     };
     RuleConfig::try_from(config, &Default::default()).unwrap()
   }
 }
 
+fn build_literal_filter<L: Language>(rules: &[&RuleConfig<L>]) -> Option<AhoCorasick> {
+  let mut literals = vec![];
+  // rules without potential kinds are never scanned, see `CombinedScan::new`
+  for rule in rules
+    .iter()
+    .filter(|r| r.matcher.potential_kinds().is_some())
+  {
+    literals.extend(rule.matcher.required_literals()?);
+  }
+  AhoCorasick::new(literals).ok()
+}
+
 fn parse_suppression_set(text: &str) -> Option<HashSet<String>> {
-  let (_, after) = text.trim().split_once(IGNORE_TEXT)?;
+  parse_rule_set(text, IGNORE_TEXT)
+}
+
+/// parse rule ids after the directive like `ast-grep-disable: a, b`, None means all rules
+fn parse_rule_set(text: &str, directive: &str) -> Option<HashSet<String>> {
+  let (_, after) = text.trim().split_once(directive)?;
   let after = after.trim();
   if after.is_empty() {
     return None;
   }
   let (_, rules) = after.split_once(':')?;
+  // strip the closing delimiter of block comments like `/* */` or `<!-- -->`
+  let rules = rules
+    .trim_end()
+    .trim_end_matches("*/")
+    .trim_end_matches("-->");
   let set = rules.split(',').map(|r| r.trim().to_string()).collect();
   Some(set)
 }
@@ -342,7 +755,7 @@ language: Tsx",
     let rules = vec![&rule];
     let scan = CombinedScan::new(rules);
     let pre = scan.find(&root);
-    assert_eq!(pre.suppressions.0.len(), 4);
+    assert_eq!(pre.suppressions.lines.len(), 4);
     let scanned = scan.scan(&root, pre, false);
     let matches = &scanned.matches[0];
     assert_eq!(matches.1.len(), 2);
@@ -364,7 +777,7 @@ language: Tsx",
     let rules = vec![&rule];
     let scan = CombinedScan::new(rules);
     let pre = scan.find(&root);
-    assert_eq!(pre.suppressions.0.len(), 4);
+    assert_eq!(pre.suppressions.lines.len(), 4);
     let scanned = scan.scan(&root, pre, false);
     let matches = &scanned.matches[0];
     assert_eq!(matches.1.len(), 2);
@@ -385,11 +798,255 @@ language: Tsx",
     let mut scan = CombinedScan::new(rules);
     scan.set_unused_suppression_rule(&rule);
     let pre = scan.find(&root);
-    assert_eq!(pre.suppressions.0.len(), 2);
+    assert_eq!(pre.suppressions.lines.len(), 2);
     let scanned = scan.scan(&root, pre, false);
     assert_eq!(scanned.matches.len(), 2);
     let unused = &scanned.matches[1];
     assert_eq!(unused.1.len(), 1);
     assert_eq!(unused.1[0].text(), "// ast-grep-ignore: test");
   }
+
+  #[test]
+  fn test_disable_range() {
+    let source = r#"
+    console.log('before')
+    // ast-grep-disable
+    console.log('disabled all')
+    // ast-grep-enable
+    /* ast-grep-disable: not-test, test */
+    console.log('disabled one')
+    // ast-grep-enable: test
+    console.log('enabled again')
+    // ast-grep-disable: not-test
+    console.log('disable another')
+    // ast-grep-disable: test
+    console.log('till end')
+    "#;
+    let root = TypeScript::Tsx.ast_grep(source);
+    let rule = create_rule();
+    let rules = vec![&rule];
+    let scan = CombinedScan::new(rules);
+    let pre = scan.find(&root);
+    assert_eq!(pre.suppressions.ranges.len(), 5);
+    let scanned = scan.scan(&root, pre, false);
+    let matches = &scanned.matches[0];
+    assert_eq!(matches.1.len(), 3);
+    assert_eq!(matches.1[0].text(), "console.log('before')");
+    assert_eq!(matches.1[1].text(), "console.log('enabled again')");
+    assert_eq!(matches.1[2].text(), "console.log('disable another')");
+  }
+
+  #[test]
+  fn test_non_used_disable() {
+    let source = r#"
+    // ast-grep-disable: test
+    console.log('disabled')
+    // ast-grep-enable
+    // ast-grep-disable: test
+    console.debug('not used')
+    // ast-grep-enable
+    "#;
+    let root = TypeScript::Tsx.ast_grep(source);
+    let rule = create_rule();
+    let rules = vec![&rule];
+    let mut scan = CombinedScan::new(rules);
+    scan.set_unused_suppression_rule(&rule);
+    let pre = scan.find(&root);
+    let scanned = scan.scan(&root, pre, false);
+    assert_eq!(scanned.matches.len(), 1);
+    let unused = &scanned.matches[0];
+    assert_eq!(unused.1.len(), 1);
+    assert_eq!(unused.1[0].text(), "// ast-grep-disable: test");
+    assert_eq!(unused.1[0].start_pos().line(), 4);
+  }
+
+  fn create_rule_from(rule: &str) -> RuleConfig<TypeScript> {
+    let rule: SerializableRuleConfig<TypeScript> = from_str(&format!(
+      r"
+id: test
+rule: {rule}
+language: Tsx"
+    ))
+    .expect("parse");
+    RuleConfig::try_from(rule, &Default::default()).expect("work")
+  }
+
+  #[test]
+  fn test_may_match() {
+    let may_match = |rule: &str, source: &str| {
+      let rule = create_rule_from(rule);
+      CombinedScan::new(vec![&rule]).may_match(source)
+    };
+    let rule = "{pattern: 'console.log($A)'}";
+    assert!(may_match(rule, "console.log(1)"));
+    assert!(!may_match(rule, "let a = 1"));
+    assert!(may_match(rule, "let a = 1 // ast-grep-ignore"));
+    // regex alone has no potential kinds, so rules use it with kind
+    let rule = "{kind: call_expression, any: [{pattern: foo()}, {regex: ^bar}, {pattern: baz()}]}";
+    assert!(may_match(rule, "let a = 1"));
+    let rule = "{kind: call_expression, any: [{pattern: foo()}, {regex: bar}]}";
+    assert!(may_match(rule, "bar()"));
+    assert!(!may_match(rule, "let a = 1"));
+    let rule = "{all: [{kind: call_expression}, {has: {pattern: foo}}]}";
+    assert!(may_match(rule, "foo()"));
+    assert!(!may_match(rule, "bar()"));
+    assert!(may_match("{kind: call_expression}", "bar()"));
+    // rules without literals cannot filter files
+    let rule = "{kind: call_expression, not: {pattern: foo()}}";
+    assert!(may_match(rule, "bar()"));
+    assert!(may_match(rule, "let a = 1"));
+  }
+
+  #[test]
+  fn test_find_with_timing() {
+    let rule = create_rule();
+    let grep = TypeScript::Tsx.ast_grep("foo(2); console.log(1)");
+    let combined = CombinedScan::new(vec![&rule]);
+    let (pre, timings) = combined.find_with_timing(&grep);
+    assert_eq!(pre.hit_set.len(), 1);
+    assert_eq!(timings.len(), 1);
+    assert_eq!(timings[0].id, rule.id);
+    assert_eq!(timings[0].tested_nodes, 2);
+    assert!(timings[0].matched);
+  }
+
+  #[test]
+  fn test_timing_rule_ids() {
+    let fixable: SerializableRuleConfig<TypeScript> =
+      from_str("{id: a, language: Tsx, rule: {pattern: foo($A)}, fix: bar($A)}").expect("parse");
+    let fixable = RuleConfig::try_from(fixable, &Default::default()).expect("work");
+    let rule = create_rule();
+    let grep = TypeScript::Tsx.ast_grep("console.log(1)");
+    // rules are reordered by fix and id, timings should follow their rules
+    let combined = CombinedScan::new(vec![&fixable, &rule]);
+    let (_, timings) = combined.find_with_timing(&grep);
+    let matched: Vec<_> = timings.iter().map(|t| (t.id.as_str(), t.matched)).collect();
+    assert_eq!(matched, [("test", true), ("a", false)]);
+    let pre = combined.find(&grep);
+    let (_, timings) = combined.scan_with_timing(&grep, pre, false);
+    let tested: Vec<_> = timings.iter().map(|t| t.tested_nodes).collect();
+    // only the rule hit in the pre-scan is matched again
+    assert_eq!(tested, [1, 0]);
+  }
+
+  #[test]
+  fn test_ignore_file() {
+    let scan_source = |source: &str| {
+      let root = TypeScript::Tsx.ast_grep(source);
+      let rule = create_rule();
+      let scan = CombinedScan::new(vec![&rule]);
+      let pre = scan.find(&root);
+      let scanned = scan.scan(&root, pre, false);
+      scanned.matches.iter().map(|m| m.1.len()).sum::<usize>()
+    };
+    assert_eq!(scan_source("// ast-grep-ignore-file\nconsole.log(1)"), 0);
+    let source = "/* license */\n// ast-grep-ignore-file: other, test\nconsole.log(1)";
+    assert_eq!(scan_source(source), 0);
+    assert_eq!(
+      scan_source("// ast-grep-ignore-file: other\nconsole.log(1)"),
+      1
+    );
+    // not at the top of file
+    assert_eq!(scan_source("console.log(1)\n// ast-grep-ignore-file"), 1);
+  }
+
+  fn create_require_rule(absent: bool) -> RuleConfig<TypeScript> {
+    let rule: SerializableRuleConfig<TypeScript> = from_str(&format!(
+      r"
+id: test
+rule: {{pattern: 'console.log($A)'}}
+language: Tsx
+requires:
+  file: 'utils/**'
+  rule: {{pattern: 'function log() {{}}'}}
+  absent: {absent}"
+    ))
+    .expect("parse");
+    RuleConfig::try_from(rule, &Default::default()).expect("work")
+  }
+
+  #[test]
+  fn test_require_index() {
+    let util = TypeScript::Tsx.ast_grep("function log() {}");
+    let root = TypeScript::Tsx.ast_grep("console.log(1)");
+    let present = create_require_rule(false);
+    let absent = create_require_rule(true);
+    let scan = CombinedScan::new(vec![&present, &absent]);
+    let mut index = RequireIndex::default();
+    scan.index_requires(Path::new("src/a.ts"), &util, &mut index);
+    assert!(!index.is_met(&present));
+    assert!(index.is_met(&absent));
+    scan.index_requires(Path::new("utils/log.ts"), &root, &mut index);
+    assert!(!index.is_met(&present));
+    scan.index_requires(Path::new("utils/log.ts"), &util, &mut index);
+    assert!(index.is_met(&present));
+    assert!(!index.is_met(&absent));
+    assert!(index.is_met(&create_rule()));
+  }
+
+  #[test]
+  fn test_scan_with_require_index() {
+    let root = TypeScript::Tsx.ast_grep("console.log(1)");
+    let rule = create_require_rule(false);
+    let index = RequireIndex::default();
+    let mut scan = CombinedScan::new(vec![&rule]);
+    let pre = scan.find(&root);
+    assert!(!scan.scan(&root, pre, false).matches.is_empty());
+    scan.set_require_index(&index);
+    let pre = scan.find(&root);
+    assert!(scan.scan(&root, pre, false).matches.is_empty());
+  }
+
+  #[test]
+  fn test_dedup_fixes() {
+    let create = |id: &str, fix: &str, severity: &str| {
+      let rule: SerializableRuleConfig<TypeScript> = from_str(&format!(
+        "{{id: {id}, language: Tsx, severity: {severity}, rule: {{pattern: 'console.log($A)'}}, fix: '{fix}'}}"
+      ))
+      .expect("parse");
+      RuleConfig::try_from(rule, &Default::default()).expect("work")
+    };
+    let (b, a, c, d) = (
+      create("b", "log($A)", "error"),
+      create("a", "log($A)", "hint"),
+      create("c", "$A", "hint"),
+      create("d", "log($A)", "error"),
+    );
+    let root = TypeScript::Tsx.ast_grep("console.log(1)");
+    let mut scan = CombinedScan::new(vec![&b, &a, &c, &d]);
+    let ids = |scan: &CombinedScan<TypeScript>, separate_fix| {
+      let pre = scan.find(&root);
+      let scanned = scan.scan(&root, pre, separate_fix);
+      let diffs = scanned.diffs.into_iter().map(|(r, _)| r.id.clone());
+      let matches = scanned.matches.into_iter().map(|(r, _)| r.id.clone());
+      let mut ids: Vec<_> = diffs.chain(matches).collect();
+      ids.sort();
+      let merged: HashMap<_, _> = scanned
+        .merged
+        .into_iter()
+        .map(|((id, range), ids)| ((id.to_string(), range), ids.join(",")))
+        .collect();
+      (ids, merged)
+    };
+    let (found, merged) = ids(&scan, true);
+    assert_eq!(found, ["a", "b", "c", "d"]);
+    assert!(merged.is_empty());
+    scan.set_dedup_fixes(true);
+    for separate_fix in [true, false] {
+      // the error rule b is kept over the hint rule a, and over d by id
+      let (found, merged) = ids(&scan, separate_fix);
+      assert_eq!(found, ["b", "c"]);
+      assert_eq!(merged.len(), 1);
+      assert_eq!(merged[&("b".to_string(), 0..14)], "a,d");
+    }
+  }
+
+  #[test]
+  fn test_parse_block_comment_suppression() {
+    let set = parse_suppression_set("/* ast-grep-ignore: a, b */").expect("should parse");
+    assert_eq!(set, ["a", "b"].iter().map(|s| s.to_string()).collect());
+    let set = parse_suppression_set("<!-- ast-grep-ignore: a -->").expect("should parse");
+    assert_eq!(set, ["a"].iter().map(|s| s.to_string()).collect());
+    assert!(parse_suppression_set("<!-- ast-grep-ignore -->").is_none());
+  }
 }
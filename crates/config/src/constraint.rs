@@ -0,0 +1,363 @@
+use crate::resolver::resolve_import;
+use crate::rule::SerializableRule;
+
+use ast_grep_core::meta_var::MetaVarEnv;
+use ast_grep_core::Doc;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde_yaml::{Mapping, Value};
+
+use std::borrow::Cow;
+
+const LENGTH: &str = "length";
+const COUNT: &str = "count";
+const RESOLVES_TO: &str = "resolvesTo";
+const RESOLVE_ALIAS: &str = "resolveAlias";
+const LINE: &str = "line";
+const COLUMN: &str = "column";
+const IS_LEAF: &str = "isLeaf";
+
+/// An inclusive range of sizes. Either bound can be omitted.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SizeRange {
+  /// The minimum size, inclusive.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub min: Option<usize>,
+  /// The maximum size, inclusive.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max: Option<usize>,
+}
+
+impl SizeRange {
+  fn contains(&self, size: usize) -> bool {
+    self.min.map_or(true, |min| size >= min) && self.max.map_or(true, |max| size <= max)
+  }
+}
+
+/// A constraint on a meta variable.
+/// Besides any rule fields, it accepts `length` to limit the number of characters captured,
+/// counted in Unicode chars over the text of each captured node without whitespace between them,
+/// and `count` to limit the number of named nodes captured by a multi meta variable like `$$$ARGS`.
+/// `resolvesTo` requires the captured name to be imported from the given module in the same file,
+/// and not shadowed by a parameter or declaration in an enclosing scope.
+/// `resolveAlias` checks the rule against the anchored node if the captured node is a YAML alias.
+/// `line` and `column` limit the 0-based start position of the captured node(s),
+/// and `isLeaf` requires the captured node(s) to have no children.
+#[derive(Clone, Default)]
+pub struct SerializableConstraint {
+  /// None if the constraint only has metric fields
+  pub rule: Option<SerializableRule>,
+  pub length: Option<SizeRange>,
+  pub count: Option<SizeRange>,
+  pub resolves_to: Option<String>,
+  pub resolve_alias: bool,
+  pub line: Option<SizeRange>,
+  pub column: Option<SizeRange>,
+  pub is_leaf: Option<bool>,
+}
+
+impl SerializableConstraint {
+  fn has_metric(&self) -> bool {
+    self.length.is_some()
+      || self.count.is_some()
+      || self.resolves_to.is_some()
+      || self.line.is_some()
+      || self.column.is_some()
+      || self.is_leaf.is_some()
+  }
+
+  pub(crate) fn get_metric(&self) -> Option<MetaVarMetric> {
+    if !self.has_metric() {
+      return None;
+    }
+    Some(MetaVarMetric {
+      length: self.length.clone(),
+      count: self.count.clone(),
+      resolves_to: self.resolves_to.clone(),
+      line: self.line.clone(),
+      column: self.column.clone(),
+      is_leaf: self.is_leaf,
+    })
+  }
+}
+
+impl<'de> Deserialize<'de> for SerializableConstraint {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let mut mapping = Mapping::deserialize(deserializer)?;
+    let mut take_range = |key: &str| {
+      mapping
+        .remove(key)
+        .map(serde_yaml::from_value::<SizeRange>)
+        .transpose()
+        .map_err(<D::Error as de::Error>::custom)
+    };
+    let length = take_range(LENGTH)?;
+    let count = take_range(COUNT)?;
+    let line = take_range(LINE)?;
+    let column = take_range(COLUMN)?;
+    let resolves_to = mapping
+      .remove(RESOLVES_TO)
+      .map(serde_yaml::from_value::<String>)
+      .transpose()
+      .map_err(<D::Error as de::Error>::custom)?;
+    let resolve_alias = mapping
+      .remove(RESOLVE_ALIAS)
+      .map(serde_yaml::from_value::<bool>)
+      .transpose()
+      .map_err(<D::Error as de::Error>::custom)?
+      .unwrap_or(false);
+    let is_leaf = mapping
+      .remove(IS_LEAF)
+      .map(serde_yaml::from_value::<bool>)
+      .transpose()
+      .map_err(<D::Error as de::Error>::custom)?;
+    let mut ret = Self {
+      rule: None,
+      length,
+      count,
+      resolves_to,
+      resolve_alias,
+      line,
+      column,
+      is_leaf,
+    };
+    // a constraint with only metric fields does not need a rule
+    if !mapping.is_empty() || !ret.has_metric() {
+      let value = Value::Mapping(mapping);
+      let rule = serde_yaml::with::singleton_map_recursive::deserialize(value)
+        .map_err(<D::Error as de::Error>::custom)?;
+      ret.rule = Some(rule);
+    }
+    Ok(ret)
+  }
+}
+
+impl Serialize for SerializableConstraint {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut mapping = match &self.rule {
+      Some(rule) => match serde_yaml::to_value(rule).map_err(ser::Error::custom)? {
+        Value::Mapping(m) => m,
+        _ => Mapping::new(),
+      },
+      None => Mapping::new(),
+    };
+    let mut put_range = |key: &str, range: &Option<SizeRange>| -> Result<(), S::Error> {
+      let Some(range) = range else {
+        return Ok(());
+      };
+      let value = serde_yaml::to_value(range).map_err(ser::Error::custom)?;
+      mapping.insert(key.into(), value);
+      Ok(())
+    };
+    put_range(LENGTH, &self.length)?;
+    put_range(COUNT, &self.count)?;
+    put_range(LINE, &self.line)?;
+    put_range(COLUMN, &self.column)?;
+    if let Some(module) = &self.resolves_to {
+      mapping.insert(RESOLVES_TO.into(), module.as_str().into());
+    }
+    if self.resolve_alias {
+      mapping.insert(RESOLVE_ALIAS.into(), true.into());
+    }
+    if let Some(is_leaf) = self.is_leaf {
+      mapping.insert(IS_LEAF.into(), is_leaf.into());
+    }
+    mapping.serialize(serializer)
+  }
+}
+
+impl JsonSchema for SerializableConstraint {
+  fn schema_name() -> String {
+    "SerializableConstraint".into()
+  }
+  fn schema_id() -> Cow<'static, str> {
+    Cow::Borrowed("SerializableConstraint")
+  }
+  fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+    let mut schema = SerializableRule::json_schema(gen).into_object();
+    let length = gen.subschema_for::<SizeRange>();
+    let count = gen.subschema_for::<SizeRange>();
+    let object = schema.object();
+    let resolves_to = gen.subschema_for::<String>();
+    let resolve_alias = gen.subschema_for::<bool>();
+    let line = gen.subschema_for::<SizeRange>();
+    let column = gen.subschema_for::<SizeRange>();
+    let is_leaf = gen.subschema_for::<bool>();
+    object.properties.insert(LENGTH.into(), length);
+    object.properties.insert(COUNT.into(), count);
+    object.properties.insert(RESOLVES_TO.into(), resolves_to);
+    object
+      .properties
+      .insert(RESOLVE_ALIAS.into(), resolve_alias);
+    object.properties.insert(LINE.into(), line);
+    object.properties.insert(COLUMN.into(), column);
+    object.properties.insert(IS_LEAF.into(), is_leaf);
+    Schema::Object(schema)
+  }
+}
+
+/// Checks the nodes captured by a meta variable by their size, position, shape or import source.
+pub struct MetaVarMetric {
+  length: Option<SizeRange>,
+  count: Option<SizeRange>,
+  resolves_to: Option<String>,
+  line: Option<SizeRange>,
+  column: Option<SizeRange>,
+  is_leaf: Option<bool>,
+}
+
+impl MetaVarMetric {
+  /// Returns true if the nodes captured by the meta variable satisfy the metric.
+  /// An uncaptured meta variable has zero length and zero count, but no position nor shape.
+  pub fn check<D: Doc>(&self, var: &str, env: &MetaVarEnv<D>) -> bool {
+    let nodes = if let Some(node) = env.get_match(var) {
+      vec![node.clone()]
+    } else {
+      env.get_multiple_matches(var)
+    };
+    if let Some(length) = &self.length {
+      let len: usize = nodes.iter().map(|n| n.text().chars().count()).sum();
+      if !length.contains(len) {
+        return false;
+      }
+    }
+    if let Some(count) = &self.count {
+      let cnt = nodes.iter().filter(|n| n.is_named()).count();
+      if !count.contains(cnt) {
+        return false;
+      }
+    }
+    if let Some(module) = &self.resolves_to {
+      let Some(node) = env.get_match(var) else {
+        return false;
+      };
+      if resolve_import(node).as_deref() != Some(module.as_str()) {
+        return false;
+      }
+    }
+    if self.line.is_some() || self.column.is_some() {
+      let Some(first) = nodes.first() else {
+        return false;
+      };
+      let pos = first.start_pos();
+      if !self.line.as_ref().map_or(true, |l| l.contains(pos.line())) {
+        return false;
+      }
+      // column is expensive for utf-8 source, check it only if needed
+      if !self
+        .column
+        .as_ref()
+        .map_or(true, |c| c.contains(pos.column(first)))
+      {
+        return false;
+      }
+    }
+    if let Some(is_leaf) = self.is_leaf {
+      if nodes.is_empty() || nodes.iter().any(|n| n.is_leaf() != is_leaf) {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::from_str;
+  use crate::maybe::Maybe;
+
+  #[test]
+  fn test_deserialize_metric_only() {
+    let cons: SerializableConstraint = from_str("{count: {min: 2}}").expect("should parse");
+    assert!(cons.rule.is_none());
+    assert!(cons.length.is_none());
+    assert_eq!(
+      cons.count,
+      Some(SizeRange {
+        min: Some(2),
+        max: None
+      })
+    );
+  }
+
+  #[test]
+  fn test_deserialize_with_rule() {
+    let cons: SerializableConstraint =
+      from_str("{regex: '^a', length: {max: 3}}").expect("should parse");
+    let rule = cons.rule.expect("should have rule");
+    assert_eq!(rule.regex, Maybe::Present("^a".into()));
+    assert_eq!(
+      cons.length,
+      Some(SizeRange {
+        min: None,
+        max: Some(3)
+      })
+    );
+    let cons: SerializableConstraint = from_str("{pattern: a}").expect("should parse");
+    assert!(cons.rule.is_some());
+    assert!(cons.get_metric().is_none());
+  }
+
+  #[test]
+  fn test_deserialize_error() {
+    let ret: Result<SerializableConstraint, _> = from_str("{length: {least: 1}}");
+    assert!(ret.is_err());
+    let ret: Result<SerializableConstraint, _> = from_str("{regexp: a}");
+    assert!(ret.is_err());
+  }
+
+  #[test]
+  fn test_deserialize_resolves_to() {
+    let cons: SerializableConstraint = from_str("{resolvesTo: lodash}").expect("should parse");
+    assert!(cons.rule.is_none());
+    assert_eq!(cons.resolves_to.as_deref(), Some("lodash"));
+    assert!(cons.get_metric().is_some());
+  }
+
+  #[test]
+  fn test_deserialize_resolve_alias() {
+    let cons: SerializableConstraint =
+      from_str("{kind: block_mapping, resolveAlias: true}").expect("should parse");
+    assert!(cons.rule.is_some());
+    assert!(cons.resolve_alias);
+    assert!(cons.get_metric().is_none());
+    let text = serde_yaml::to_string(&cons).expect("should serialize");
+    let round: SerializableConstraint = from_str(&text).expect("should parse");
+    assert!(round.resolve_alias);
+  }
+
+  #[test]
+  fn test_deserialize_position() {
+    let cons: SerializableConstraint =
+      from_str("{line: {min: 1}, column: {max: 0}, isLeaf: true}").expect("should parse");
+    assert!(cons.rule.is_none());
+    assert_eq!(
+      cons.line,
+      Some(SizeRange {
+        min: Some(1),
+        max: None
+      })
+    );
+    assert_eq!(cons.is_leaf, Some(true));
+    assert!(cons.get_metric().is_some());
+    let text = serde_yaml::to_string(&cons).expect("should serialize");
+    let round: SerializableConstraint = from_str(&text).expect("should parse");
+    assert_eq!(round.column, cons.column);
+    assert_eq!(round.is_leaf, Some(true));
+  }
+
+  #[test]
+  fn test_serialize() {
+    let cons: SerializableConstraint =
+      from_str("{regex: a, count: {min: 1, max: 2}}").expect("should parse");
+    let text = serde_yaml::to_string(&cons).expect("should serialize");
+    let round: SerializableConstraint = from_str(&text).expect("should parse");
+    assert!(round.rule.is_some());
+    assert_eq!(round.count, cons.count);
+  }
+}
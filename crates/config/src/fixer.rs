@@ -1,5 +1,5 @@
 use crate::maybe::Maybe;
-use crate::rule::{Relation, Rule, RuleSerializeError, StopBy};
+use crate::rule::{Relation, Rule, RuleSerializeError, SerializableRule, StopBy};
 use crate::transform::Transformation;
 use crate::DeserializeEnv;
 use ast_grep_core::replacer::{Content, Replacer, TemplateFix, TemplateFixError};
@@ -13,11 +13,14 @@ use std::ops::Range;
 
 /// A pattern string or fix object to auto fix the issue.
 /// It can reference metavariables appeared in rule.
+/// `${A:-text}` uses text if A is not captured or empty, `${A-text}` only if A is not captured, and `{{#if A}}...{{else}}...{{/if}}` picks code by A.
+/// A list of fix objects provides alternative fixes to choose from.
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(untagged)]
 pub enum SerializableFixer {
   Str(String),
   Config(SerializableFixConfig),
+  List(Vec<SerializableFixConfig>),
 }
 
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
@@ -28,6 +31,12 @@ pub struct SerializableFixConfig {
   expand_end: Maybe<Relation>,
   #[serde(default, skip_serializing_if = "Maybe::is_absent")]
   expand_start: Maybe<Relation>,
+  /// A short description of the fix, shown when choosing among multiple fixes.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  title: Option<String>,
+  /// The fix is only applicable if the matched node also matches the condition rule.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  condition: Option<SerializableRule>,
   // TODO: add these
   // prepend: String,
 }
@@ -38,6 +47,8 @@ pub enum FixerError {
   InvalidTemplate(#[from] TemplateFixError),
   #[error("Fixer expansion contains invalid rule.")]
   WrongExpansion(#[from] RuleSerializeError),
+  #[error("Fixer condition contains invalid rule.")]
+  InvalidCondition(#[source] RuleSerializeError),
 }
 
 struct Expansion<L: Language> {
@@ -64,35 +75,52 @@ pub struct Fixer<L: Language> {
   template: TemplateFix,
   expand_start: Option<Expansion<L>>,
   expand_end: Option<Expansion<L>>,
+  title: Option<String>,
+  condition: Option<Rule<L>>,
 }
 
 impl<L: Language> Fixer<L> {
   fn do_parse(
     serialized: &SerializableFixConfig,
     env: &DeserializeEnv<L>,
+    transform: &Option<HashMap<String, Transformation>>,
   ) -> Result<Self, FixerError> {
     let SerializableFixConfig {
       template,
       expand_end,
       expand_start,
+      title,
+      condition,
     } = serialized;
     let expand_start = Expansion::parse(expand_start, env)?;
     let expand_end = Expansion::parse(expand_end, env)?;
+    let condition = condition
+      .as_ref()
+      .map(|c| env.deserialize_rule(c.clone()))
+      .transpose()
+      .map_err(FixerError::InvalidCondition)?;
     Ok(Self {
-      template: TemplateFix::try_new(template, &env.lang)?,
+      template: parse_template(template, env, transform)?,
       expand_start,
       expand_end,
+      title: title.clone(),
+      condition,
     })
   }
 
+  /// Parse all fixers in the order they are declared.
   pub fn parse(
     fixer: &SerializableFixer,
     env: &DeserializeEnv<L>,
     transform: &Option<HashMap<String, Transformation>>,
-  ) -> Result<Self, FixerError> {
+  ) -> Result<Vec<Self>, FixerError> {
     match fixer {
-      SerializableFixer::Str(fix) => Self::with_transform(fix, env, transform),
-      SerializableFixer::Config(cfg) => Self::do_parse(cfg, env),
+      SerializableFixer::Str(fix) => Ok(vec![Self::with_transform(fix, env, transform)?]),
+      SerializableFixer::Config(cfg) => Ok(vec![Self::do_parse(cfg, env, transform)?]),
+      SerializableFixer::List(list) => list
+        .iter()
+        .map(|cfg| Self::do_parse(cfg, env, transform))
+        .collect(),
     }
   }
 
@@ -101,16 +129,12 @@ impl<L: Language> Fixer<L> {
     env: &DeserializeEnv<L>,
     transform: &Option<HashMap<String, Transformation>>,
   ) -> Result<Self, FixerError> {
-    let template = if let Some(trans) = transform {
-      let keys: Vec<_> = trans.keys().cloned().collect();
-      TemplateFix::with_transform(fix, &env.lang, &keys)
-    } else {
-      TemplateFix::try_new(fix, &env.lang)?
-    };
     Ok(Self {
-      template,
+      template: parse_template(fix, env, transform)?,
       expand_end: None,
       expand_start: None,
+      title: None,
+      condition: None,
     })
   }
 
@@ -120,14 +144,46 @@ impl<L: Language> Fixer<L> {
       template,
       expand_start: None,
       expand_end: None,
+      title: None,
+      condition: None,
     })
   }
 
+  /// The title to describe the fix, if provided.
+  pub fn title(&self) -> Option<&str> {
+    self.title.as_deref()
+  }
+
+  /// Returns true if the fix can be applied to the matched node.
+  /// A fix without condition is always applicable.
+  pub fn is_applicable<D: Doc<Lang = L>>(&self, nm: &NodeMatch<D>) -> bool {
+    let Some(condition) = &self.condition else {
+      return true;
+    };
+    let mut env = std::borrow::Cow::Borrowed(nm.get_env());
+    condition
+      .match_node_with_env(nm.get_node().clone(), &mut env)
+      .is_some()
+  }
+
   pub(crate) fn used_vars(&self) -> HashSet<&str> {
     self.template.used_vars()
   }
 }
 
+fn parse_template<L: Language>(
+  fix: &str,
+  env: &DeserializeEnv<L>,
+  transform: &Option<HashMap<String, Transformation>>,
+) -> Result<TemplateFix, FixerError> {
+  if let Some(trans) = transform {
+    let keys: Vec<_> = trans.keys().cloned().collect();
+    Ok(TemplateFix::with_transform(fix, &env.lang, &keys))
+  } else {
+    Ok(TemplateFix::try_new(fix, &env.lang)?)
+  }
+}
+
 impl<D, L, C> Replacer<D> for Fixer<L>
 where
   D: Doc<Source = C, Lang = L>,
@@ -220,11 +276,13 @@ mod test {
     let config = SerializableFixConfig {
       expand_end: Maybe::Present(relation),
       expand_start: Maybe::Absent,
+      title: None,
+      condition: None,
       template: "abcd".to_string(),
     };
     let config = SerializableFixer::Config(config);
     let env = DeserializeEnv::new(TypeScript::Tsx);
-    let ret = Fixer::parse(&config, &env, &Some(Default::default()))?;
+    let ret = Fixer::parse(&config, &env, &Some(Default::default()))?.remove(0);
     assert!(ret.expand_start.is_none());
     assert!(ret.expand_end.is_some());
     assert!(matches!(ret.template, TemplateFix::Textual(_)));
@@ -235,7 +293,7 @@ mod test {
   fn test_parse_str() -> Result<(), FixerError> {
     let config = SerializableFixer::Str("abcd".to_string());
     let env = DeserializeEnv::new(TypeScript::Tsx);
-    let ret = Fixer::parse(&config, &env, &Some(Default::default()))?;
+    let ret = Fixer::parse(&config, &env, &Some(Default::default()))?.remove(0);
     assert!(ret.expand_end.is_none());
     assert!(ret.expand_start.is_none());
     assert!(matches!(ret.template, TemplateFix::Textual(_)));
@@ -248,11 +306,13 @@ mod test {
     let config = SerializableFixConfig {
       expand_end: Maybe::Present(expand_end),
       expand_start: Maybe::Absent,
+      title: None,
+      condition: None,
       template: "var $A = 456".to_string(),
     };
     let config = SerializableFixer::Config(config);
     let env = DeserializeEnv::new(TypeScript::Tsx);
-    let fixer = Fixer::parse(&config, &env, &Some(Default::default()))?;
+    let fixer = Fixer::parse(&config, &env, &Some(Default::default()))?.remove(0);
     let grep = TypeScript::Tsx.ast_grep("let a = 123");
     let node = grep.root().find("let $A = 123").expect("should found");
     let edit = fixer.generate_replacement(&node);
@@ -267,11 +327,13 @@ mod test {
     let config = SerializableFixConfig {
       expand_end: Maybe::Present(expand_end),
       expand_start: Maybe::Absent,
+      title: None,
+      condition: None,
       template: "c: 456".to_string(),
     };
     let config = SerializableFixer::Config(config);
     let env = DeserializeEnv::new(TypeScript::Tsx);
-    let fixer = Fixer::parse(&config, &env, &Some(Default::default()))?;
+    let fixer = Fixer::parse(&config, &env, &Some(Default::default()))?.remove(0);
     let grep = TypeScript::Tsx.ast_grep("var a = { b: 123, }");
     let matcher = KindMatcher::new("pair", TypeScript::Tsx);
     let node = grep.root().find(&matcher).expect("should found");
@@ -282,4 +344,31 @@ mod test {
     assert_eq!(edit.deleted_length, 7);
     Ok(())
   }
+
+  #[test]
+  fn test_parse_list() -> Result<(), FixerError> {
+    let src = "[{template: 'a', title: 'first'}, {template: 'b', condition: {regex: '^1'}}]";
+    let config: SerializableFixer = from_str(src).expect("should parse");
+    assert!(matches!(config, SerializableFixer::List(_)));
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let fixers = Fixer::parse(&config, &env, &None)?;
+    assert_eq!(fixers.len(), 2);
+    assert_eq!(fixers[0].title(), Some("first"));
+    assert_eq!(fixers[1].title(), None);
+    Ok(())
+  }
+
+  #[test]
+  fn test_fix_condition() -> Result<(), FixerError> {
+    let config: SerializableFixer =
+      from_str("{template: 'b', condition: {regex: '^1'}}").expect("should parse");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let fixer = Fixer::parse(&config, &env, &None)?.remove(0);
+    let grep = TypeScript::Tsx.ast_grep("let a = 123; let b = 456");
+    let node = grep.root().find("123").expect("should found");
+    assert!(fixer.is_applicable(&node));
+    let node = grep.root().find("456").expect("should found");
+    assert!(!fixer.is_applicable(&node));
+    Ok(())
+  }
 }
@@ -1,7 +1,10 @@
+mod anchor;
 mod check_var;
 mod combined;
+mod constraint;
 mod fixer;
 mod maybe;
+mod resolver;
 mod rule;
 mod rule_collection;
 mod rule_config;
@@ -9,17 +12,23 @@ mod rule_core;
 mod transform;
 
 use serde::Deserialize;
-use serde_yaml::{with::singleton_map_recursive::deserialize, Deserializer, Error as YamlError};
+use serde_yaml::{
+  with::singleton_map_recursive::deserialize, Deserializer, Error as YamlError, Value,
+};
 
 use ast_grep_core::language::Language;
 
-pub use combined::{CombinedScan, PreScan};
+pub use combined::{CombinedScan, PreScan, RequireIndex, RuleTiming};
+pub use constraint::{SerializableConstraint, SizeRange};
 pub use fixer::Fixer;
 pub use rule::referent_rule::GlobalRules;
-pub use rule::DeserializeEnv;
+pub use rule::{DeserializeEnv, SerializableGlobalRule};
 pub use rule::{Rule, RuleSerializeError, SerializableRule};
 pub use rule_collection::RuleCollection;
-pub use rule_config::{RuleConfig, RuleConfigError, SerializableRuleConfig, Severity};
+pub use rule_config::{
+  Label, LabelConfig, LabelStyle, Require, RuleConfig, RuleConfigError, SerializableRequire,
+  SerializableRewriter, SerializableRuleConfig, Severity, SharedRewriters,
+};
 pub use rule_core::{RuleCore, RuleCoreError, SerializableRuleCore};
 pub use transform::Transformation;
 
@@ -31,14 +40,43 @@ pub fn from_str<'de, T: Deserialize<'de>>(s: &'de str) -> Result<T, YamlError> {
 pub fn from_yaml_string<'a, L: Language + Deserialize<'a>>(
   yamls: &'a str,
   registration: &GlobalRules<L>,
+) -> Result<Vec<RuleConfig<L>>, RuleConfigError> {
+  from_yaml_string_with_rewriters(yamls, registration, &SharedRewriters::default())
+}
+
+/// Same as `from_yaml_string` but rules can use rewriters from the shared rewriters.
+pub fn from_yaml_string_with_rewriters<'a, L: Language + Deserialize<'a>>(
+  yamls: &'a str,
+  registration: &GlobalRules<L>,
+  rewriters: &SharedRewriters,
 ) -> Result<Vec<RuleConfig<L>>, RuleConfigError> {
   let mut ret = vec![];
   for yaml in Deserializer::from_str(yamls) {
-    let config = RuleConfig::deserialize(yaml, registration)?;
-    ret.push(config);
+    let value = Value::deserialize(yaml)?;
+    for value in expand_languages(value) {
+      let mut config: SerializableRuleConfig<L> = deserialize(value)?;
+      config.add_shared_rewriters(rewriters);
+      ret.push(RuleConfig::try_from(config, registration)?);
+    }
   }
   Ok(ret)
 }
+
+/// A rule with `language: [ts, tsx, js]` is cloned for every language with the same id.
+fn expand_languages(value: Value) -> Vec<Value> {
+  let langs = match value.get("language") {
+    Some(Value::Sequence(langs)) => langs.clone(),
+    _ => return vec![value],
+  };
+  langs
+    .into_iter()
+    .map(|lang| {
+      let mut value = value.clone();
+      value["language"] = lang;
+      value
+    })
+    .collect()
+}
 #[cfg(test)]
 mod test {
 
@@ -59,6 +97,14 @@ mod test {
     }
   }
 
+  #[derive(Clone)]
+  pub struct Yaml;
+  impl Language for Yaml {
+    fn get_ts_language(&self) -> TSLanguage {
+      tree_sitter_yaml::LANGUAGE.into()
+    }
+  }
+
   fn test_rule_match(yaml: &str, source: &str) {
     let globals = GlobalRules::default();
     let config = &from_yaml_string::<TypeScript>(yaml, &globals).expect("rule should parse")[0];
@@ -214,4 +260,27 @@ rule:
 ";
     test_rule_match(yaml, "a();a(123);a();a(123)");
   }
+
+  #[test]
+  fn test_multi_language_rule() {
+    let yaml = r"
+id: multi
+language: [Tsx, Tsx]
+rule:
+  pattern: console.log($A)
+---
+id: single
+language: Tsx
+rule:
+  pattern: alert($A)
+";
+    let globals = GlobalRules::default();
+    let configs = from_yaml_string::<TypeScript>(yaml, &globals).expect("rule should parse");
+    let ids: Vec<_> = configs.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, ["multi", "multi", "single"]);
+    let grep = TypeScript::Tsx.ast_grep("console.log(1)");
+    assert!(grep.root().find(&configs[1].matcher).is_some());
+    let ret = from_yaml_string::<TypeScript>("{id: a, language: [Js], rule: {kind: a}}", &globals);
+    assert!(ret.is_err());
+  }
 }
@@ -0,0 +1,323 @@
+//! A lightweight import resolver used by the `resolvesTo` constraint.
+//! It only inspects import statements in the same file and supports
+//! ES modules (JavaScript/TypeScript) and Python imports.
+
+use ast_grep_core::{Doc, Node};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// A local name bound by an import statement and the module it comes from.
+struct ImportBinding {
+  local: String,
+  module: String,
+}
+
+/// Module level imports of the last root resolved on this thread, from local names to modules.
+/// The root text is kept to tell whether the next node is in the same root.
+struct ImportIndex {
+  kind: String,
+  text: String,
+  imports: HashMap<String, String>,
+}
+
+thread_local! {
+  static INDEX: RefCell<Option<ImportIndex>> = const { RefCell::new(None) };
+}
+
+/// A name declared in a scope, either imported or defined locally.
+enum Declared {
+  Import(String),
+  Local,
+}
+
+/// Returns the module that the node's leading name is imported from.
+/// For `foo.bar()` or `foo.bar`, the leading name is `foo`.
+/// Names declared in an enclosing function or block shadow module level imports.
+pub fn resolve_import<D: Doc>(node: &Node<D>) -> Option<String> {
+  let text = node.text();
+  let name = leading_name(&text)?;
+  let mut root = node.clone();
+  for scope in node.ancestors() {
+    if scope.parent().is_none() {
+      root = scope;
+      break;
+    }
+    match declared_in_scope(&scope, name) {
+      Some(Declared::Import(module)) => return Some(module),
+      Some(Declared::Local) => return None,
+      None => (),
+    }
+  }
+  module_import(&root, name)
+}
+
+/// Look up the name in the import index of the root, which is built once per root.
+fn module_import<D: Doc>(root: &Node<D>, name: &str) -> Option<String> {
+  let kind = root.kind();
+  let text = root.text();
+  INDEX.with(|index| {
+    let mut index = index.borrow_mut();
+    let is_same_root = index
+      .as_ref()
+      .map_or(false, |i| i.kind == kind && i.text == text);
+    if !is_same_root {
+      let imports = root
+        .children()
+        .flat_map(|n| collect_bindings(&n))
+        .map(|b| (b.local, b.module))
+        .collect();
+      *index = Some(ImportIndex {
+        kind: kind.to_string(),
+        text: text.to_string(),
+        imports,
+      });
+    }
+    let imports = &index.as_ref()?.imports;
+    imports.get(name).cloned()
+  })
+}
+
+/// Check the parameters of a function scope and the statements directly in the scope.
+fn declared_in_scope<D: Doc>(scope: &Node<D>, name: &str) -> Option<Declared> {
+  if let Some(params) = scope
+    .field("parameters")
+    .or_else(|| scope.field("parameter"))
+  {
+    if binds_name(&params, name) {
+      return Some(Declared::Local);
+    }
+  }
+  for stmt in scope.children() {
+    if let Some(b) = collect_bindings(&stmt)
+      .into_iter()
+      .find(|b| b.local == name)
+    {
+      return Some(Declared::Import(b.module));
+    }
+    if declares_name(&stmt, name) {
+      return Some(Declared::Local);
+    }
+  }
+  None
+}
+
+fn declares_name<D: Doc>(stmt: &Node<D>, name: &str) -> bool {
+  match &*stmt.kind() {
+    "lexical_declaration" | "variable_declaration" => stmt
+      .children()
+      .filter_map(|d| d.field("name"))
+      .any(|pattern| binds_name(&pattern, name)),
+    "function_declaration" | "class_declaration" | "function_definition" | "class_definition" => {
+      stmt.field("name").map_or(false, |n| n.text() == name)
+    }
+    // python assignment `a = 1` is wrapped in an expression statement
+    "expression_statement" => stmt
+      .children()
+      .filter(|c| c.kind() == "assignment")
+      .filter_map(|c| c.field("left"))
+      .any(|left| binds_name(&left, name)),
+    _ => false,
+  }
+}
+
+/// Whether the parameter list or binding pattern binds the name.
+/// Default values are skipped because they refer to names instead of binding them.
+fn binds_name<D: Doc>(pattern: &Node<D>, name: &str) -> bool {
+  match &*pattern.kind() {
+    "identifier" | "shorthand_property_identifier_pattern" => pattern.text() == name,
+    _ => pattern
+      .children()
+      .filter(|c| c.is_named())
+      .filter(|c| !is_default_value(c))
+      .any(|c| binds_name(&c, name)),
+  }
+}
+
+fn is_default_value<D: Doc>(node: &Node<D>) -> bool {
+  let Some(parent) = node.parent() else {
+    return false;
+  };
+  // `value` in default parameters, `right` in destructuring defaults, `type` in annotations
+  ["value", "right", "type"].into_iter().any(|field| {
+    parent
+      .field(field)
+      .map_or(false, |f| f.range() == node.range())
+  })
+}
+
+fn leading_name(text: &str) -> Option<&str> {
+  let end = text
+    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+    .unwrap_or(text.len());
+  let name = &text[..end];
+  (!name.is_empty()).then_some(name)
+}
+
+fn collect_bindings<D: Doc>(node: &Node<D>) -> Vec<ImportBinding> {
+  match &*node.kind() {
+    // ES module has `source`, Python `import a.b as c` does not
+    "import_statement" => match node.field("source") {
+      Some(source) => es_bindings(node, &unquote(&source.text())),
+      None => py_import_bindings(node),
+    },
+    "import_from_statement" => py_from_bindings(node),
+    _ => vec![],
+  }
+}
+
+fn unquote(text: &str) -> String {
+  text.trim_matches(|c| c == '"' || c == '\'' || c == '`').to_string()
+}
+
+/// import foo, { bar, baz as qux }, * as ns from 'module'
+fn es_bindings<D: Doc>(node: &Node<D>, module: &str) -> Vec<ImportBinding> {
+  let binding = |local: Node<D>| ImportBinding {
+    local: local.text().to_string(),
+    module: module.to_string(),
+  };
+  let mut ret = vec![];
+  for n in node.dfs() {
+    match &*n.kind() {
+      "import_specifier" => {
+        let local = n.field("alias").or_else(|| n.field("name"));
+        ret.extend(local.map(binding));
+      }
+      "identifier" => {
+        let parent = n.parent();
+        let parent_kind = parent.as_ref().map(|p| p.kind());
+        if matches!(parent_kind.as_deref(), Some("import_clause" | "namespace_import")) {
+          ret.push(binding(n));
+        }
+      }
+      _ => (),
+    }
+  }
+  ret
+}
+
+/// import a.b, c as d
+fn py_import_bindings<D: Doc>(node: &Node<D>) -> Vec<ImportBinding> {
+  node
+    .field_children("name")
+    .filter_map(|name| {
+      if let Some(alias) = name.field("alias") {
+        let module = name.field("name")?.text().to_string();
+        return Some(ImportBinding {
+          local: alias.text().to_string(),
+          module,
+        });
+      }
+      // `import a.b` binds the top level package `a`
+      let text = name.text();
+      let top = text.split('.').next()?.trim().to_string();
+      Some(ImportBinding {
+        local: top.clone(),
+        module: top,
+      })
+    })
+    .collect()
+}
+
+/// from a.b import c, d as e
+fn py_from_bindings<D: Doc>(node: &Node<D>) -> Vec<ImportBinding> {
+  let Some(module) = node.field("module_name") else {
+    return vec![];
+  };
+  let module = module.text().to_string();
+  node
+    .field_children("name")
+    .map(|name| {
+      let local = match name.field("alias") {
+        Some(alias) => alias,
+        None => name.field("name").unwrap_or(name),
+      };
+      ImportBinding {
+        local: local.text().to_string(),
+        module: module.clone(),
+      }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::test::TypeScript;
+  use ast_grep_core::{Language, Pattern};
+
+  use ast_grep_core::language::TSLanguage;
+
+  #[derive(Clone)]
+  struct Python;
+  impl Language for Python {
+    fn get_ts_language(&self) -> TSLanguage {
+      tree_sitter_python::LANGUAGE.into()
+    }
+  }
+
+  fn resolve_in<L: Language>(lang: L, src: &str, pattern: &str) -> Option<String> {
+    let grep = lang.ast_grep(src);
+    let pattern = Pattern::new(pattern, lang);
+    let node = grep.root().find(pattern).expect("should match");
+    resolve_import(&node)
+  }
+
+  fn resolve(src: &str, pattern: &str) -> Option<String> {
+    resolve_in(TypeScript::Tsx, src, pattern)
+  }
+
+  #[test]
+  fn test_leading_name() {
+    assert_eq!(leading_name("foo.bar()"), Some("foo"));
+    assert_eq!(leading_name("$el"), Some("$el"));
+    assert_eq!(leading_name("(a)"), None);
+  }
+
+  #[test]
+  fn test_resolve_es_import() {
+    let src = "import a, { b, c as d } from 'lodash'\nimport * as ns from \"ns\"\n";
+    assert_eq!(resolve(&format!("{src}a()"), "a()"), Some("lodash".into()));
+    assert_eq!(resolve(&format!("{src}b()"), "b()"), Some("lodash".into()));
+    assert_eq!(resolve(&format!("{src}d()"), "d()"), Some("lodash".into()));
+    assert_eq!(resolve(&format!("{src}c()"), "c()"), None);
+    assert_eq!(resolve(&format!("{src}ns.e()"), "ns.e()"), Some("ns".into()));
+    assert_eq!(resolve(&format!("{src}f()"), "f()"), None);
+  }
+
+  #[test]
+  fn test_resolve_shadowed_import() {
+    let src = "import a from 'lodash'\n";
+    let shadowed = |code: &str| resolve(&format!("{src}{code}"), "a.b()");
+    assert_eq!(shadowed("function f() { a.b() }"), Some("lodash".into()));
+    assert_eq!(shadowed("function f(a) { a.b() }"), None);
+    assert_eq!(shadowed("function f({ a }) { a.b() }"), None);
+    assert_eq!(
+      shadowed("function f(c = a) { a.b() }"),
+      Some("lodash".into())
+    );
+    assert_eq!(shadowed("const f = a => a.b()"), None);
+    assert_eq!(shadowed("{ const a = 1; a.b() }"), None);
+    assert_eq!(shadowed("{ const c = a; a.b() }"), Some("lodash".into()));
+  }
+
+  #[test]
+  fn test_resolve_py_import() {
+    let src = "import os.path\nimport numpy as np\nfrom a.b import c, d as e\n";
+    let resolve = |code: &str, pattern| resolve_in(Python, &format!("{src}{code}"), pattern);
+    assert_eq!(
+      resolve("os.path.join()", "os.path.join()"),
+      Some("os".into())
+    );
+    assert_eq!(resolve("np.array()", "np.array()"), Some("numpy".into()));
+    assert_eq!(resolve("c()", "c()"), Some("a.b".into()));
+    assert_eq!(resolve("e()", "e()"), Some("a.b".into()));
+    assert_eq!(resolve("d()", "d()"), None);
+    let local = "def f(np):\n  np.array()\n";
+    assert_eq!(resolve(local, "np.array()"), None);
+    let local = "def f():\n  np = 1\n  np.array()\n";
+    assert_eq!(resolve(local, "np.array()"), None);
+    let local = "def f():\n  import pandas as np\n  np.array()\n";
+    assert_eq!(resolve(local, "np.array()"), Some("pandas".into()));
+  }
+}
@@ -0,0 +1,181 @@
+//! Record which `any` branches match while a rule runs, e.g. to report test coverage.
+use super::{Rule, SerializableRule};
+use crate::maybe::Maybe;
+
+use ast_grep_core::language::Language;
+use ast_grep_core::meta_var::MetaVarEnv;
+use ast_grep_core::{Doc, Matcher, Node};
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+thread_local! {
+  // addresses of matched branches, None if not recording
+  static HITS: RefCell<Option<HashSet<usize>>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn is_recording() -> bool {
+  HITS.with(|hits| hits.borrow().is_some())
+}
+
+/// Match like `o::Any` and record the branch that matches the node.
+pub(crate) fn match_any<'tree, L: Language, D: Doc<Lang = L>>(
+  branches: &[Rule<L>],
+  node: Node<'tree, D>,
+  env: &mut Cow<MetaVarEnv<'tree, D>>,
+) -> Option<Node<'tree, D>> {
+  for branch in branches {
+    let mut new_env = Cow::Borrowed(env.as_ref());
+    if branch
+      .match_node_with_env(node.clone(), &mut new_env)
+      .is_some()
+    {
+      let new_env = new_env.into_owned();
+      HITS.with(|hits| {
+        if let Some(hits) = hits.borrow_mut().as_mut() {
+          hits.insert(branch as *const Rule<L> as usize);
+        }
+      });
+      *env = Cow::Owned(new_env);
+      return Some(node);
+    }
+  }
+  None
+}
+
+/// Run `f` and report whether each `any` branch of the rule matched a node meanwhile.
+pub(crate) fn record_any_hits<L: Language>(
+  rule: &Rule<L>,
+  serialized: &SerializableRule,
+  f: impl FnOnce(),
+) -> Vec<(String, bool)> {
+  let mut branches = vec![];
+  collect_branches(rule, serialized, "rule".into(), &mut branches);
+  let outer = HITS.with(|hits| hits.replace(Some(HashSet::new())));
+  f();
+  let hits = HITS.with(|hits| hits.replace(outer)).unwrap_or_default();
+  branches
+    .into_iter()
+    .map(|(path, branch)| {
+      let hit = hits.contains(&(branch as *const Rule<L> as usize));
+      (path, hit)
+    })
+    .collect()
+}
+
+enum Part<'s> {
+  Leaf,
+  All(&'s [SerializableRule]),
+  Any(&'s [SerializableRule]),
+  Single(&'s SerializableRule),
+}
+
+/// Pair `any` branches of the compiled rule with their paths in the serialized rule,
+/// e.g. `rule.any[2].all[1].has.any[0]`. Parts are listed in the order `deserialize_rule` compiles them.
+fn collect_branches<'r, L: Language>(
+  rule: &'r Rule<L>,
+  ser: &SerializableRule,
+  path: String,
+  branches: &mut Vec<(String, &'r Rule<L>)>,
+) {
+  let mut parts = vec![];
+  let atomics = [
+    ser.pattern.is_present(),
+    ser.kind.is_present(),
+    ser.regex.is_present(),
+    ser.nth_child.is_present(),
+    ser.range.is_present(),
+  ];
+  for _ in atomics.into_iter().filter(|a| *a) {
+    parts.push(("", Part::Leaf));
+  }
+  if let Maybe::Present(all) = &ser.all {
+    parts.push(("all", Part::All(all)));
+  }
+  if let Maybe::Present(any) = &ser.any {
+    parts.push(("any", Part::Any(any)));
+  }
+  if let Maybe::Present(not) = &ser.not {
+    parts.push(("not", Part::Single(not)));
+  }
+  if ser.matches.is_present() {
+    parts.push(("", Part::Leaf));
+  }
+  let relations = [
+    ("inside", &ser.inside),
+    ("has", &ser.has),
+    ("precedes", &ser.precedes),
+    ("follows", &ser.follows),
+  ];
+  for (key, relation) in relations {
+    if let Maybe::Present(relation) = relation {
+      parts.push((key, Part::Single(&relation.rule)));
+    }
+  }
+  let compiled = match (rule, parts.len()) {
+    (_, 1) => std::slice::from_ref(rule),
+    (Rule::All(all), _) => all.inner(),
+    _ => return,
+  };
+  for ((key, part), rule) in parts.into_iter().zip(compiled) {
+    let path = format!("{path}.{key}");
+    match (part, rule) {
+      (Part::All(sers), Rule::All(all)) => {
+        for (i, (ser, rule)) in sers.iter().zip(all.inner()).enumerate() {
+          collect_branches(rule, ser, format!("{path}[{i}]"), branches);
+        }
+      }
+      (Part::Any(sers), Rule::Any(any)) => {
+        for (i, (ser, rule)) in sers.iter().zip(any.inner()).enumerate() {
+          let path = format!("{path}[{i}]");
+          branches.push((path.clone(), rule));
+          collect_branches(rule, ser, path, branches);
+        }
+      }
+      (Part::Single(ser), Rule::Not(not)) => collect_branches(not.inner(), ser, path, branches),
+      (Part::Single(ser), Rule::Inside(r)) => collect_branches(&r.outer, ser, path, branches),
+      (Part::Single(ser), Rule::Has(r)) => collect_branches(&r.inner, ser, path, branches),
+      (Part::Single(ser), Rule::Precedes(r)) => collect_branches(&r.later, ser, path, branches),
+      (Part::Single(ser), Rule::Follows(r)) => collect_branches(&r.former, ser, path, branches),
+      _ => (),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::from_str;
+  use crate::test::TypeScript;
+  use crate::DeserializeEnv;
+
+  #[test]
+  fn test_record_any_hits() {
+    let src = "
+any:
+  - pattern: a
+  - pattern: b
+  - all:
+    - kind: call_expression
+    - has: { any: [ {pattern: c}, {pattern: d} ] }";
+    let ser: SerializableRule = from_str(src).expect("should parse");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let rule = env.deserialize_rule(ser.clone()).expect("should compile");
+    let grep = TypeScript::Tsx.ast_grep("a; d(c)");
+    let hits = record_any_hits(&rule, &ser, || {
+      grep.root().find_all(&rule).count();
+    });
+    let expected = [
+      ("rule.any[0]", true),
+      ("rule.any[1]", false),
+      ("rule.any[2]", true),
+      // c is in the code but never a child of the call, so the branch never matched
+      ("rule.any[2].all[1].has.any[0]", false),
+      ("rule.any[2].all[1].has.any[1]", true),
+    ];
+    let hits: Vec<_> = hits.iter().map(|(p, h)| (p.as_str(), *h)).collect();
+    assert_eq!(hits, expected);
+    assert!(!is_recording());
+  }
+}
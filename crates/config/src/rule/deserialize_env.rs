@@ -24,11 +24,15 @@ pub struct SerializableGlobalRule<L: Language> {
 
 fn into_map<L: Language>(
   rules: Vec<SerializableGlobalRule<L>>,
-) -> HashMap<String, (L, SerializableRuleCore)> {
-  rules
-    .into_iter()
-    .map(|r| (r.id, (r.language, r.core)))
-    .collect()
+) -> Result<HashMap<String, (L, SerializableRuleCore)>, ReferentRuleError> {
+  let mut map = HashMap::new();
+  for rule in rules {
+    if map.contains_key(&rule.id) {
+      return Err(ReferentRuleError::DuplicateRule(rule.id));
+    }
+    map.insert(rule.id, (rule.language, rule.core));
+  }
+  Ok(map)
 }
 
 type OrderResult<T> = Result<T, String>;
@@ -173,7 +177,7 @@ impl<L: Language> DeserializeEnv<L> {
     utils: Vec<SerializableGlobalRule<L>>,
   ) -> Result<GlobalRules<L>, RuleCoreError> {
     let registration = GlobalRules::default();
-    let utils = into_map(utils);
+    let utils = into_map(utils).map_err(RuleSerializeError::MatchesReference)?;
     let order = TopologicalSort::get_order(&utils)
       .map_err(ReferentRuleError::CyclicRule)
       .map_err(RuleSerializeError::from)?;
@@ -324,4 +328,18 @@ local-rule-b:
     ));
     Ok(())
   }
+
+  #[test]
+  fn test_duplicate_global_utils() -> Result<()> {
+    let util = "{id: global-rule, language: Tsx, rule: {kind: identifier}}";
+    let utils = vec![from_str(util)?, from_str(util)?];
+    let ret = DeserializeEnv::<TypeScript>::parse_global_utils(utils);
+    assert!(matches!(
+      ret,
+      Err(RuleCoreError::Rule(RuleSerializeError::MatchesReference(
+        ReferentRuleError::DuplicateRule(id)
+      ))) if id == "global-rule"
+    ));
+    Ok(())
+  }
 }
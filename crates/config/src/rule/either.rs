@@ -0,0 +1,153 @@
+use ast_grep_core::meta_var::MetaVarEnv;
+use ast_grep_core::{Doc, Language, MatchStrictness, Matcher, Node, Pattern, PatternError};
+
+use bit_set::BitSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+/// Matches a node if any of the alternative patterns matches, e.g. `either: [foo($A), bar($A)]`.
+/// Unlike `any` with pattern rules, only the patterns whose root kind equals the node's kind are tried.
+/// The first matched pattern in declaration order writes its meta variables into the shared env.
+pub struct EitherPattern<L: Language> {
+  patterns: Vec<Pattern<L>>,
+  /// indexes of patterns to try for a kind, including `any_kind`, in declaration order
+  by_kind: HashMap<u16, Vec<usize>>,
+  /// indexes of patterns that can match any kind, e.g. a bare meta variable `$A`
+  any_kind: Vec<usize>,
+}
+
+impl<L: Language> EitherPattern<L> {
+  pub fn try_new(patterns: &[String], lang: L) -> Result<Self, PatternError> {
+    let patterns = patterns
+      .iter()
+      .map(|p| Pattern::try_new(p, lang.clone()))
+      .collect::<Result<Vec<_>, _>>()?;
+    Ok(Self::new(patterns))
+  }
+
+  pub fn new(patterns: Vec<Pattern<L>>) -> Self {
+    let mut by_kind: HashMap<_, Vec<_>> = HashMap::new();
+    let mut any_kind = vec![];
+    for (i, pattern) in patterns.iter().enumerate() {
+      let Some(kinds) = pattern.potential_kinds() else {
+        any_kind.push(i);
+        continue;
+      };
+      for kind in kinds.iter() {
+        by_kind.entry(kind as u16).or_default().push(i);
+      }
+    }
+    for indexes in by_kind.values_mut() {
+      indexes.extend(&any_kind);
+      indexes.sort_unstable();
+    }
+    Self {
+      patterns,
+      by_kind,
+      any_kind,
+    }
+  }
+
+  pub fn with_strictness(mut self, strictness: MatchStrictness) -> Self {
+    self.patterns = self
+      .patterns
+      .into_iter()
+      .map(|p| p.with_strictness(strictness.clone()))
+      .collect();
+    self
+  }
+
+  pub fn patterns(&self) -> &[Pattern<L>] {
+    &self.patterns
+  }
+
+  pub fn defined_vars(&self) -> HashSet<&str> {
+    self
+      .patterns
+      .iter()
+      .flat_map(|p| p.defined_vars())
+      .collect()
+  }
+
+  /// indexes of patterns to try for the kind, in declaration order
+  fn candidates(&self, kind: u16) -> &[usize] {
+    self.by_kind.get(&kind).unwrap_or(&self.any_kind)
+  }
+}
+
+impl<L: Language> Matcher<L> for EitherPattern<L> {
+  fn match_node_with_env<'tree, D: Doc<Lang = L>>(
+    &self,
+    node: Node<'tree, D>,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
+  ) -> Option<Node<'tree, D>> {
+    // pattern does not pollute env if it does not match
+    self
+      .candidates(node.kind_id())
+      .iter()
+      .find_map(|&i| self.patterns[i].match_node_with_env(node.clone(), env))
+  }
+
+  fn potential_kinds(&self) -> Option<BitSet> {
+    if !self.any_kind.is_empty() {
+      return None;
+    }
+    let mut kinds = BitSet::new();
+    for kind in self.by_kind.keys() {
+      kinds.insert(*kind as usize);
+    }
+    Some(kinds)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::test::TypeScript;
+
+  fn either(patterns: &[&str]) -> EitherPattern<TypeScript> {
+    let patterns: Vec<_> = patterns.iter().map(|p| p.to_string()).collect();
+    EitherPattern::try_new(&patterns, TypeScript::Tsx).expect("should parse")
+  }
+
+  #[test]
+  fn test_either_match() {
+    let matcher = either(&["foo($A)", "bar($A)", "let $A = 1"]);
+    let grep = TypeScript::Tsx.ast_grep("bar(123)");
+    let nm = grep.root().find(&matcher).expect("should match");
+    assert_eq!(
+      nm.get_env().get_match("A").expect("should exist").text(),
+      "123"
+    );
+    let grep = TypeScript::Tsx.ast_grep("let a = 1");
+    assert!(grep.root().find(&matcher).is_some());
+    let grep = TypeScript::Tsx.ast_grep("baz(123)");
+    assert!(grep.root().find(&matcher).is_none());
+  }
+
+  #[test]
+  fn test_either_potential_kinds() {
+    let matcher = either(&["foo($A)", "bar($A)"]);
+    let kinds = matcher.potential_kinds().expect("should have kinds");
+    assert_eq!(kinds.len(), 1);
+    let matcher = either(&["foo($A)", "$A"]);
+    assert!(matcher.potential_kinds().is_none());
+    assert_eq!(matcher.defined_vars(), ["A"].into_iter().collect());
+  }
+
+  #[test]
+  fn test_either_declaration_order() {
+    let matcher = either(&["$A", "foo($B)"]);
+    let grep = TypeScript::Tsx.ast_grep("foo(1)");
+    let nm = grep.root().find(&matcher).expect("should match");
+    assert!(nm.get_env().get_match("A").is_some());
+    assert!(nm.get_env().get_match("B").is_none());
+    let matcher = either(&["foo($B)", "$A", "let $C = 1"]);
+    let kinds = matcher.patterns[0]
+      .potential_kinds()
+      .expect("should have kind");
+    let call = kinds.iter().next().expect("should have kind") as u16;
+    assert_eq!(matcher.candidates(call), [0, 1]);
+    assert_eq!(matcher.candidates(u16::MAX), [1]);
+  }
+}
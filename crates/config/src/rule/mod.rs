@@ -1,11 +1,15 @@
+mod coverage;
 mod deserialize_env;
+mod either;
 mod nth_child;
 mod range;
 pub mod referent_rule;
 mod relational_rule;
 mod stop_by;
 
-pub use deserialize_env::DeserializeEnv;
+pub(crate) use coverage::record_any_hits;
+pub use deserialize_env::{DeserializeEnv, SerializableGlobalRule};
+pub use either::EitherPattern;
 pub use relational_rule::Relation;
 pub use stop_by::StopBy;
 
@@ -25,7 +29,7 @@ use bit_set::BitSet;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use thiserror::Error;
 
 /// A rule object to find matching AST nodes. We have three categories of rules in ast-grep.
@@ -47,9 +51,11 @@ pub struct SerializableRule {
   #[serde(default, skip_serializing_if = "Maybe::is_absent")]
   pub pattern: Maybe<PatternStyle>,
   /// The kind name of the node to match. You can look up code's kind names in playground.
+  /// Multiple kinds can be separated by `|`, and `*` matches any part of a kind name, e.g. `*_statement`.
   #[serde(default, skip_serializing_if = "Maybe::is_absent")]
   pub kind: Maybe<String>,
   /// A Rust regular expression to match the node's text. https://docs.rs/regex/latest/regex/#syntax
+  /// Named groups like `(?P<NAME>...)` are captured as meta variables usable in message, fix and transform.
   #[serde(default, skip_serializing_if = "Maybe::is_absent")]
   pub regex: Maybe<String>,
   /// `nth_child` accepts number, string or object.
@@ -102,6 +108,39 @@ struct Categorized {
 }
 
 impl SerializableRule {
+  /// Overrides the strictness of all patterns in the rule and its sub-rules.
+  pub(crate) fn set_strictness(&mut self, strictness: &Strictness) {
+    if let Maybe::Present(pattern) = &mut self.pattern {
+      pattern.set_strictness(strictness);
+    }
+    if let Maybe::Present(SerializableNthChild::Complex {
+      of_rule: Some(rule),
+      ..
+    }) = &mut self.nth_child
+    {
+      rule.set_strictness(strictness);
+    }
+    let relations = [
+      &mut self.inside,
+      &mut self.has,
+      &mut self.precedes,
+      &mut self.follows,
+    ];
+    for relation in relations {
+      if let Maybe::Present(relation) = relation {
+        relation.set_strictness(strictness);
+      }
+    }
+    for sub in [&mut self.all, &mut self.any] {
+      if let Maybe::Present(rules) = sub {
+        rules.iter_mut().for_each(|r| r.set_strictness(strictness));
+      }
+    }
+    if let Maybe::Present(not) = &mut self.not {
+      not.set_strictness(strictness);
+    }
+  }
+
   fn categorized(self) -> Categorized {
     Categorized {
       atomic: AtomicRule {
@@ -178,11 +217,13 @@ impl From<Strictness> for MatchStrictness {
 }
 
 /// A String pattern will match one single AST node according to pattern syntax.
-/// Or an object with field `context`, `selector` and optionally `strictness`.
+/// Or an object with field `context`, `selector` and optionally `strictness` and `vars`.
+/// Or an object with field `either`, a list of alternative pattern strings.
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(untagged)]
 pub enum PatternStyle {
   Str(String),
+  #[serde(rename_all = "camelCase")]
   Contextual {
     /// The surrounding code that helps to resolve any ambiguity in the syntax.
     context: String,
@@ -190,9 +231,40 @@ pub enum PatternStyle {
     selector: Option<String>,
     /// Strictness of the pattern. More strict pattern matches fewer nodes.
     strictness: Option<Strictness>,
+    /// A map from meta variable name to the node kind it must match, e.g. `A: identifier`.
+    vars: Option<HashMap<String, String>>,
+    /// Skip ERROR and missing nodes in the matched code, useful when macros confuse the parser.
+    allow_error_nodes: Option<bool>,
+  },
+  Either {
+    /// Alternative patterns. A node matches if any of them matches, tried in order.
+    either: Vec<String>,
+    /// Strictness of the patterns. More strict pattern matches fewer nodes.
+    strictness: Option<Strictness>,
   },
 }
 
+impl PatternStyle {
+  fn set_strictness(&mut self, strictness: &Strictness) {
+    match self {
+      PatternStyle::Str(pattern) => {
+        let context = std::mem::take(pattern);
+        *self = PatternStyle::Contextual {
+          context,
+          selector: None,
+          strictness: Some(strictness.clone()),
+          vars: None,
+          allow_error_nodes: None,
+        };
+      }
+      PatternStyle::Contextual { strictness: s, .. }
+      | PatternStyle::Either { strictness: s, .. } => {
+        *s = Some(strictness.clone());
+      }
+    }
+  }
+}
+
 pub struct RelationalRule {
   pub inside: Option<Box<Relation>>,
   pub has: Option<Box<Relation>>,
@@ -210,6 +282,7 @@ pub struct CompositeRule {
 pub enum Rule<L: Language> {
   // atomic
   Pattern(Pattern<L>),
+  Either(EitherPattern<L>),
   Kind(KindMatcher<L>),
   Regex(RegexMatcher<L>),
   NthChild(NthChild<L>),
@@ -230,7 +303,7 @@ impl<L: Language> Rule<L> {
     use Rule::*;
     matches!(
       self,
-      Pattern(_) | Kind(_) | Regex(_) | NthChild(_) | Range(_)
+      Pattern(_) | Either(_) | Kind(_) | Regex(_) | NthChild(_) | Range(_)
     )
   }
   pub fn is_relational(&self) -> bool {
@@ -260,8 +333,9 @@ impl<L: Language> Rule<L> {
   pub fn defined_vars(&self) -> HashSet<&str> {
     match self {
       Rule::Pattern(p) => p.defined_vars(),
+      Rule::Either(e) => e.defined_vars(),
       Rule::Kind(_) => HashSet::new(),
-      Rule::Regex(_) => HashSet::new(),
+      Rule::Regex(r) => r.defined_vars(),
       Rule::NthChild(n) => n.defined_vars(),
       Rule::Range(_) => HashSet::new(),
       Rule::Has(c) => c.defined_vars(),
@@ -276,10 +350,34 @@ impl<L: Language> Rule<L> {
     }
   }
 
+  /// Strings of which at least one must appear in the source text for the rule to match.
+  /// None means no literal can be extracted so any source can match.
+  pub(crate) fn required_literals(&self) -> Option<Vec<String>> {
+    match self {
+      Rule::Pattern(p) => pattern_literal(p).map(|s| vec![s]),
+      Rule::Either(e) => e.patterns().iter().map(pattern_literal).collect(),
+      Rule::Regex(r) => regex_literal(r.as_str()).map(|s| vec![s]),
+      Rule::Has(c) => c.required_literals(),
+      Rule::Inside(p) => p.required_literals(),
+      Rule::Precedes(f) => f.required_literals(),
+      Rule::Follows(f) => f.required_literals(),
+      Rule::All(sub) => sub.inner().iter().find_map(|r| r.required_literals()),
+      Rule::Any(sub) => {
+        let mut literals = vec![];
+        for rule in sub.inner() {
+          literals.extend(rule.required_literals()?);
+        }
+        Some(literals)
+      }
+      Rule::Kind(_) | Rule::NthChild(_) | Rule::Range(_) | Rule::Not(_) | Rule::Matches(_) => None,
+    }
+  }
+
   /// check if util rules used are defined
   pub fn verify_util(&self) -> Result<(), RuleSerializeError> {
     match self {
       Rule::Pattern(_) => Ok(()),
+      Rule::Either(_) => Ok(()),
       Rule::Kind(_) => Ok(()),
       Rule::Regex(_) => Ok(()),
       Rule::NthChild(n) => n.verify_util(),
@@ -296,6 +394,28 @@ impl<L: Language> Rule<L> {
   }
 }
 
+fn pattern_literal<L: Language>(pattern: &Pattern<L>) -> Option<String> {
+  // other strictness can skip terminal text
+  if !matches!(
+    pattern.strictness,
+    MatchStrictness::Cst | MatchStrictness::Smart
+  ) {
+    return None;
+  }
+  // missing nodes can match pattern text without having it
+  if pattern.allow_error_nodes {
+    return None;
+  }
+  let fixed = pattern.fixed_string();
+  (!fixed.is_empty()).then(|| fixed.to_string())
+}
+
+/// only regex without any special character is a literal
+fn regex_literal(regex: &str) -> Option<String> {
+  let is_special = |c: char| r"\.+*?()|[]{}^$#&-~".contains(c) || c.is_whitespace();
+  (!regex.is_empty() && !regex.contains(is_special)).then(|| regex.to_string())
+}
+
 impl<L: Language> Matcher<L> for Rule<L> {
   fn match_node_with_env<'tree, D: Doc<Lang = L>>(
     &self,
@@ -306,6 +426,7 @@ impl<L: Language> Matcher<L> for Rule<L> {
     match self {
       // atomic
       Pattern(pattern) => pattern.match_node_with_env(node, env),
+      Either(either) => either.match_node_with_env(node, env),
       Kind(kind) => kind.match_node_with_env(node, env),
       Regex(regex) => regex.match_node_with_env(node, env),
       NthChild(nth_child) => nth_child.match_node_with_env(node, env),
@@ -317,6 +438,7 @@ impl<L: Language> Matcher<L> for Rule<L> {
       Follows(former) => match_and_add_label(&**former, node, env),
       // composite
       All(all) => all.match_node_with_env(node, env),
+      Any(any) if coverage::is_recording() => coverage::match_any(any.inner(), node, env),
       Any(any) => any.match_node_with_env(node, env),
       Not(not) => not.match_node_with_env(node, env),
       Matches(rule) => rule.match_node_with_env(node, env),
@@ -328,6 +450,7 @@ impl<L: Language> Matcher<L> for Rule<L> {
     match self {
       // atomic
       Pattern(pattern) => pattern.potential_kinds(),
+      Either(either) => either.potential_kinds(),
       Kind(kind) => kind.potential_kinds(),
       Regex(regex) => regex.potential_kinds(),
       NthChild(nth_child) => nth_child.potential_kinds(),
@@ -382,6 +505,8 @@ pub enum RuleSerializeError {
   InvalidRange(#[from] RangeMatcherError),
   #[error("field is only supported in has/inside.")]
   FieldNotSupported,
+  #[error("notBetween is only supported in precedes/follows.")]
+  NotBetweenNotSupported,
   #[error("Relational rule contains invalid field {0}.")]
   InvalidField(String),
 }
@@ -474,6 +599,8 @@ fn deserialze_atomic_rule<L: Language>(
         context,
         selector,
         strictness,
+        vars,
+        allow_error_nodes,
       } => {
         let pattern = if let Some(selector) = selector {
           Pattern::contextual(&context, &selector, env.lang.clone())?
@@ -485,8 +612,25 @@ fn deserialze_atomic_rule<L: Language>(
         } else {
           pattern
         };
+        let pattern = pattern.with_error_nodes_allowed(allow_error_nodes.unwrap_or(false));
+        let pattern = if let Some(vars) = vars {
+          let mut var_kinds = HashMap::new();
+          for (var, kind) in vars {
+            var_kinds.insert(var, KindMatcher::try_new(&kind, env.lang.clone())?);
+          }
+          pattern.with_var_kinds(var_kinds)?
+        } else {
+          pattern
+        };
         R::Pattern(pattern)
       }
+      PatternStyle::Either { either, strictness } => {
+        let either = EitherPattern::try_new(&either, env.lang.clone())?;
+        match strictness {
+          Some(strictness) => R::Either(either.with_strictness(strictness.into())),
+          None => R::Either(either),
+        }
+      }
     });
   }
   if let Some(kind) = atomic.kind {
@@ -527,6 +671,70 @@ pattern:
     assert!(matches!(rule.pattern, Maybe::Present(Contextual { .. }),));
   }
 
+  #[test]
+  fn test_pattern_vars() {
+    let src = r"
+pattern:
+  context: $A + 1
+  vars: { A: identifier }
+";
+    let rule: SerializableRule = from_str(src).expect("cannot parse rule");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let rule = deserialize_rule(rule, &env).expect("should deserialize");
+    let root = TypeScript::Tsx.ast_grep("a + 1");
+    assert!(root.root().find(&rule).is_some());
+    let root = TypeScript::Tsx.ast_grep("a.b + 1");
+    assert!(root.root().find(&rule).is_none());
+  }
+
+  #[test]
+  fn test_pattern_allow_error_nodes() {
+    let src = r"
+pattern:
+  context: foo($A)
+  allowErrorNodes: true
+";
+    let rule: SerializableRule = from_str(src).expect("cannot parse rule");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let rule = deserialize_rule(rule, &env).expect("should deserialize");
+    let root = TypeScript::Tsx.ast_grep("foo(a \\)");
+    assert!(root.root().find(&rule).is_some());
+  }
+
+  #[test]
+  fn test_pattern_either() {
+    let src = r"
+pattern:
+  either: [foo($A), bar($A)]
+";
+    let rule: SerializableRule = from_str(src).expect("cannot parse rule");
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let rule = deserialize_rule(rule, &env).expect("should deserialize");
+    assert!(matches!(rule, Rule::Either(_)));
+    let root = TypeScript::Tsx.ast_grep("bar(1)");
+    assert!(root.root().find(&rule).is_some());
+    assert_eq!(
+      rule.required_literals(),
+      Some(vec!["foo".to_string(), "bar".to_string()])
+    );
+    let rule: SerializableRule =
+      from_str("pattern: {either: ['', bar]}").expect("cannot parse rule");
+    assert!(deserialize_rule(rule, &env).is_err());
+  }
+
+  #[test]
+  fn test_pattern_vars_error() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let rule: SerializableRule =
+      from_str("pattern: {context: $A + 1, vars: {A: not_a_kind}}").expect("cannot parse rule");
+    let ret = deserialize_rule(rule, &env);
+    assert!(matches!(ret, Err(RuleSerializeError::InvalidKind(_))));
+    let rule: SerializableRule =
+      from_str("pattern: {context: $A + 1, vars: {B: identifier}}").expect("cannot parse rule");
+    let ret = deserialize_rule(rule, &env);
+    assert!(matches!(ret, Err(RuleSerializeError::InvalidPattern(_))));
+  }
+
   #[test]
   fn test_augmentation() {
     let src = r"
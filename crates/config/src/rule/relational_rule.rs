@@ -1,6 +1,6 @@
 use super::deserialize_env::DeserializeEnv;
 use super::stop_by::{SerializableStopBy, StopBy};
-use crate::rule::{Rule, RuleSerializeError, SerializableRule};
+use crate::rule::{Rule, RuleSerializeError, SerializableRule, Strictness};
 use ast_grep_core::language::Language;
 use ast_grep_core::meta_var::MetaVarEnv;
 use ast_grep_core::{Doc, Matcher, Node};
@@ -18,6 +18,28 @@ pub struct Relation {
   #[serde(default)]
   pub stop_by: SerializableStopBy,
   pub field: Option<String>,
+  /// Sibling nodes between the target and the matched node must not match this rule.
+  /// Only supported in precedes/follows.
+  pub not_between: Option<SerializableRule>,
+}
+
+impl Relation {
+  pub(crate) fn set_strictness(&mut self, strictness: &Strictness) {
+    self.rule.set_strictness(strictness);
+    if let SerializableStopBy::Rule(rule) = &mut self.stop_by {
+      rule.set_strictness(strictness);
+    }
+    if let Some(rule) = &mut self.not_between {
+      rule.set_strictness(strictness);
+    }
+  }
+}
+
+fn not_between_rule<L: Language>(
+  not_between: Option<SerializableRule>,
+  env: &DeserializeEnv<L>,
+) -> Result<Option<Rule<L>>, RuleSerializeError> {
+  not_between.map(|r| env.deserialize_rule(r)).transpose()
 }
 
 fn field_name_to_id<L: Language>(
@@ -35,12 +57,15 @@ fn field_name_to_id<L: Language>(
 }
 
 pub struct Inside<L: Language> {
-  outer: Rule<L>,
+  pub(crate) outer: Rule<L>,
   field: Option<u16>,
   stop_by: StopBy<L>,
 }
 impl<L: Language> Inside<L> {
   pub fn try_new(relation: Relation, env: &DeserializeEnv<L>) -> Result<Self, RuleSerializeError> {
+    if relation.not_between.is_some() {
+      return Err(RuleSerializeError::NotBetweenNotSupported);
+    }
     Ok(Self {
       stop_by: StopBy::try_from(relation.stop_by, env)?,
       field: field_name_to_id(relation.field, env)?,
@@ -48,6 +73,10 @@ impl<L: Language> Inside<L> {
     })
   }
 
+  pub(crate) fn required_literals(&self) -> Option<Vec<String>> {
+    self.outer.required_literals()
+  }
+
   pub fn defined_vars(&self) -> HashSet<&str> {
     self
       .outer
@@ -92,12 +121,15 @@ impl<L: Language> Matcher<L> for Inside<L> {
 }
 
 pub struct Has<L: Language> {
-  inner: Rule<L>,
+  pub(crate) inner: Rule<L>,
   stop_by: StopBy<L>,
   field: Option<u16>,
 }
 impl<L: Language> Has<L> {
   pub fn try_new(relation: Relation, env: &DeserializeEnv<L>) -> Result<Self, RuleSerializeError> {
+    if relation.not_between.is_some() {
+      return Err(RuleSerializeError::NotBetweenNotSupported);
+    }
     Ok(Self {
       stop_by: StopBy::try_from(relation.stop_by, env)?,
       inner: env.deserialize_rule(relation.rule)?,
@@ -105,6 +137,10 @@ impl<L: Language> Has<L> {
     })
   }
 
+  pub(crate) fn required_literals(&self) -> Option<Vec<String>> {
+    self.inner.required_literals()
+  }
+
   pub fn defined_vars(&self) -> HashSet<&str> {
     self
       .inner
@@ -171,8 +207,9 @@ impl<L: Language> Matcher<L> for Has<L> {
 }
 
 pub struct Precedes<L: Language> {
-  later: Rule<L>,
+  pub(crate) later: Rule<L>,
   stop_by: StopBy<L>,
+  not_between: Option<Rule<L>>,
 }
 impl<L: Language> Precedes<L> {
   pub fn try_new(relation: Relation, env: &DeserializeEnv<L>) -> Result<Self, RuleSerializeError> {
@@ -182,9 +219,14 @@ impl<L: Language> Precedes<L> {
     Ok(Self {
       stop_by: StopBy::try_from(relation.stop_by, env)?,
       later: env.deserialize_rule(relation.rule)?,
+      not_between: not_between_rule(relation.not_between, env)?,
     })
   }
 
+  pub(crate) fn required_literals(&self) -> Option<Vec<String>> {
+    self.later.required_literals()
+  }
+
   pub fn defined_vars(&self) -> HashSet<&str> {
     self
       .later
@@ -196,6 +238,9 @@ impl<L: Language> Precedes<L> {
 
   pub fn verify_util(&self) -> Result<(), RuleSerializeError> {
     self.later.verify_util()?;
+    if let Some(rule) = &self.not_between {
+      rule.verify_util()?;
+    }
     self.stop_by.verify_util()
   }
 }
@@ -207,14 +252,16 @@ impl<L: Language> Matcher<L> for Precedes<L> {
   ) -> Option<Node<'tree, D>> {
     let next = || node.next();
     let next_all = || node.next_all();
-    let finder = |n| self.later.match_node_with_env(n, env);
+    let mut blocked = false;
+    let finder = |n| match_sibling(n, &self.later, &self.not_between, env, &mut blocked);
     self.stop_by.find(next, next_all, finder)
   }
 }
 
 pub struct Follows<L: Language> {
-  former: Rule<L>,
+  pub(crate) former: Rule<L>,
   stop_by: StopBy<L>,
+  not_between: Option<Rule<L>>,
 }
 impl<L: Language> Follows<L> {
   pub fn try_new(relation: Relation, env: &DeserializeEnv<L>) -> Result<Self, RuleSerializeError> {
@@ -224,8 +271,13 @@ impl<L: Language> Follows<L> {
     Ok(Self {
       stop_by: StopBy::try_from(relation.stop_by, env)?,
       former: env.deserialize_rule(relation.rule)?,
+      not_between: not_between_rule(relation.not_between, env)?,
     })
   }
+  pub(crate) fn required_literals(&self) -> Option<Vec<String>> {
+    self.former.required_literals()
+  }
+
   pub fn defined_vars(&self) -> HashSet<&str> {
     self
       .former
@@ -237,6 +289,9 @@ impl<L: Language> Follows<L> {
 
   pub fn verify_util(&self) -> Result<(), RuleSerializeError> {
     self.former.verify_util()?;
+    if let Some(rule) = &self.not_between {
+      rule.verify_util()?;
+    }
     self.stop_by.verify_util()
   }
 }
@@ -248,11 +303,33 @@ impl<L: Language> Matcher<L> for Follows<L> {
   ) -> Option<Node<'tree, D>> {
     let prev = || node.prev();
     let prev_all = || node.prev_all();
-    let finder = |n| self.former.match_node_with_env(n, env);
+    let mut blocked = false;
+    let finder = |n| match_sibling(n, &self.former, &self.not_between, env, &mut blocked);
     self.stop_by.find(prev, prev_all, finder)
   }
 }
 
+/// Matches the sibling against `rule`. Once a sibling matches `not_between`,
+/// no later sibling can match since the node is no longer adjacent to it.
+fn match_sibling<'tree, L: Language, D: Doc<Lang = L>>(
+  n: Node<'tree, D>,
+  rule: &Rule<L>,
+  not_between: &Option<Rule<L>>,
+  env: &mut Cow<MetaVarEnv<'tree, D>>,
+  blocked: &mut bool,
+) -> Option<Node<'tree, D>> {
+  if *blocked {
+    return None;
+  }
+  if let Some(found) = rule.match_node_with_env(n.clone(), env) {
+    return Some(found);
+  }
+  if let Some(not_between) = not_between {
+    *blocked = n.matches(not_between);
+  }
+  None
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -287,6 +364,7 @@ mod test {
     let precedes = Precedes {
       later: Rule::Pattern(Pattern::new("var a = 1", TS::Tsx)),
       stop_by: StopBy::End,
+      not_between: None,
     };
     let rule = make_rule("var b = 2", Rule::Precedes(Box::new(precedes)));
     test_found(
@@ -315,6 +393,7 @@ mod test {
     let precedes = Precedes {
       later: Rule::Pattern(Pattern::new("var a = 1", TS::Tsx)),
       stop_by: StopBy::Neighbor,
+      not_between: None,
     };
     let rule = make_rule("var b = 2", Rule::Precedes(Box::new(precedes)));
     test_found(
@@ -344,6 +423,7 @@ mod test {
     let follows = Follows {
       former: Rule::Pattern(Pattern::new("var b = 2", TS::Tsx)),
       stop_by: StopBy::End,
+      not_between: None,
     };
     let rule = make_rule("var a = 1", Rule::Follows(Box::new(follows)));
     test_found(
@@ -375,6 +455,7 @@ mod test {
     let follows = Follows {
       former: Rule::Pattern(Pattern::new("var b = 2", TS::Tsx)),
       stop_by: StopBy::Neighbor,
+      not_between: None,
     };
     let rule = make_rule("var a = 1", Rule::Follows(Box::new(follows)));
     test_found(
@@ -401,6 +482,61 @@ mod test {
     );
   }
 
+  #[test]
+  fn test_precedes_not_between() {
+    let precedes = Precedes {
+      later: Rule::Pattern(Pattern::new("var a = 1", TS::Tsx)),
+      stop_by: StopBy::End,
+      not_between: Some(Rule::Pattern(Pattern::new("var c = $C", TS::Tsx))),
+    };
+    let rule = make_rule("var b = 2", Rule::Precedes(Box::new(precedes)));
+    test_found(
+      &[
+        "var b = 2; var a = 1;",
+        "var b = 2; log(b); var a = 1;",
+        "var b = 2; var a = 1; var c = 3;",
+      ],
+      &rule,
+    );
+    test_not_found(
+      &[
+        "var b = 2; var c = 3; var a = 1;",
+        "var b = 2; log(b); var c = 3; var a = 1;",
+      ],
+      &rule,
+    );
+  }
+
+  #[test]
+  fn test_follows_not_between() {
+    let follows = Follows {
+      former: Rule::Pattern(Pattern::new("var b = 2", TS::Tsx)),
+      stop_by: StopBy::End,
+      not_between: Some(Rule::Pattern(Pattern::new("var c = $C", TS::Tsx))),
+    };
+    let rule = make_rule("var a = 1", Rule::Follows(Box::new(follows)));
+    test_found(
+      &[
+        "var b = 2; var a = 1;",
+        "var c = 3; var b = 2; log(b); var a = 1",
+      ],
+      &rule,
+    );
+    test_not_found(&["var b = 2; var c = 3; var a = 1"], &rule);
+  }
+
+  #[test]
+  fn test_not_between_not_supported() {
+    let env = DeserializeEnv::new(TS::Tsx);
+    let relation: Relation =
+      crate::from_str("{pattern: test, notBetween: {pattern: a}}").expect("should parse");
+    let has = Has::try_new(relation, &env);
+    assert!(matches!(
+      has,
+      Err(RuleSerializeError::NotBetweenNotSupported)
+    ));
+  }
+
   #[test]
   fn test_has_rule() {
     let has = Has {
@@ -650,6 +786,7 @@ mod test {
       rule: crate::from_str("pattern: test").unwrap(),
       stop_by: SerializableStopBy::End,
       field: Some("invalid_field".to_string()),
+      not_between: None,
     };
     let inside = Inside::try_new(relation, &env);
     assert!(inside.is_err());
@@ -664,11 +801,13 @@ mod test {
     let precedes = Precedes {
       later: Rule::Pattern(Pattern::new("var a = $A", TS::Tsx)),
       stop_by: StopBy::Rule(Rule::Pattern(Pattern::new("var b = $B", TS::Tsx))),
+      not_between: None,
     };
     assert_eq!(precedes.defined_vars(), ["A", "B"].into_iter().collect());
     let follows = Follows {
       former: Rule::Pattern(Pattern::new("var a = 123", TS::Tsx)),
       stop_by: StopBy::Rule(Rule::Pattern(Pattern::new("var b = $B", TS::Tsx))),
+      not_between: None,
     };
     assert_eq!(follows.defined_vars(), ["B"].into_iter().collect());
     let inside = Inside {
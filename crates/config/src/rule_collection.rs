@@ -1,7 +1,8 @@
 use crate::{RuleConfig, Severity};
 use ast_grep_core::language::Language;
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 /// RuleBucket stores rules of the same language id.
 /// Rules for different language will stay in separate buckets.
@@ -54,15 +55,33 @@ where
 
 impl<L: Language> ContingentRule<L> {
   pub fn matches_path<P: AsRef<Path>>(&self, path: P) -> bool {
-    if let Some(ignore_globs) = &self.ignore_globs {
-      if ignore_globs.is_match(&path) {
-        return false;
-      }
-    }
-    if let Some(files_globs) = &self.files_globs {
-      return files_globs.is_match(path);
+    matches_globs(&self.files_globs, &self.ignore_globs, path)
+  }
+}
+
+fn matches_globs<P: AsRef<Path>>(
+  files_globs: &Option<GlobSet>,
+  ignore_globs: &Option<GlobSet>,
+  path: P,
+) -> bool {
+  if let Some(ignore_globs) = ignore_globs {
+    if ignore_globs.is_match(&path) {
+      return false;
     }
-    true
+  }
+  if let Some(files_globs) = files_globs {
+    return files_globs.is_match(path);
+  }
+  true
+}
+
+impl<L: Language> RuleConfig<L> {
+  /// Whether the rule applies to the path according to its `files` and `ignores` globs.
+  /// Prefer RuleCollection when checking many paths since it compiles the globs only once.
+  pub fn matches_path<P: AsRef<Path>>(&self, path: P) -> Result<bool, globset::Error> {
+    let files_globs = self.files.as_ref().map(build_glob_set).transpose()?;
+    let ignore_globs = self.ignores.as_ref().map(build_glob_set).transpose()?;
+    Ok(matches_globs(&files_globs, &ignore_globs, path))
   }
 }
 
@@ -75,6 +94,16 @@ pub struct RuleCollection<L: Language + Eq> {
   tenured: Vec<RuleBucket<L>>,
   /// contingent rules will run against a file if it matches file/ignore glob.
   contingent: Vec<ContingentRule<L>>,
+  /// rules of nested configs, sorted from the shallowest directory to the deepest.
+  scopes: Vec<ScopedRules<L>>,
+}
+
+/// Rules of a nested config that only run against files under its directory.
+struct ScopedRules<L: Language + Eq> {
+  dir: PathBuf,
+  rules: RuleCollection<L>,
+  /// ids of rules from outer scopes that are replaced or turned off in this scope
+  shadowed: HashSet<String>,
 }
 
 impl<L: Language + Eq> RuleCollection<L> {
@@ -93,10 +122,52 @@ impl<L: Language + Eq> RuleCollection<L> {
     Ok(Self {
       tenured,
       contingent,
+      scopes: vec![],
     })
   }
 
+  /// Add rules of a nested config which only apply to files under `dir`.
+  /// A rule replaces the rule of the same id from outer scopes, or removes it if turned off.
+  /// Deeper directories take precedence over shallower ones.
+  /// `dir` should be canonical, so both relative and absolute paths of its files are in scope.
+  pub fn add_scope(
+    &mut self,
+    dir: PathBuf,
+    configs: Vec<RuleConfig<L>>,
+  ) -> Result<(), globset::Error> {
+    let shadowed = configs.iter().map(|c| c.id.clone()).collect();
+    let rules = Self::try_new(configs)?;
+    let depth = dir.components().count();
+    let index = self
+      .scopes
+      .partition_point(|s| s.dir.components().count() <= depth);
+    let scope = ScopedRules {
+      dir,
+      rules,
+      shadowed,
+    };
+    self.scopes.insert(index, scope);
+    Ok(())
+  }
+
   pub fn get_rule_from_lang(&self, path: &Path, lang: L) -> Vec<&RuleConfig<L>> {
+    let mut all_rules = self.get_unscoped_rules(path, lang.clone());
+    if self.scopes.is_empty() {
+      return all_rules;
+    }
+    // fall back to the path itself if the file does not exist on disk
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    for scope in &self.scopes {
+      if !canonical.starts_with(&scope.dir) {
+        continue;
+      }
+      all_rules.retain(|r| !scope.shadowed.contains(&r.id));
+      all_rules.extend(scope.rules.get_rule_from_lang(path, lang.clone()));
+    }
+    all_rules
+  }
+
+  fn get_unscoped_rules(&self, path: &Path, lang: L) -> Vec<&RuleConfig<L>> {
     let mut all_rules = vec![];
     for rule in &self.tenured {
       if rule.lang == lang {
@@ -137,16 +208,26 @@ impl<L: Language + Eq> RuleCollection<L> {
         return Some(&rule.rule);
       }
     }
-    None
+    self.scopes.iter().find_map(|s| s.rules.get_rule(id))
   }
 
   pub fn total_rule_count(&self) -> usize {
     let mut ret = self.tenured.iter().map(|bucket| bucket.rules.len()).sum();
     ret += self.contingent.len();
+    ret += self
+      .scopes
+      .iter()
+      .map(|s| s.rules.total_rule_count())
+      .sum::<usize>();
     ret
   }
 
-  pub fn for_each_rule(&self, mut f: impl FnMut(&RuleConfig<L>)) {
+  pub fn for_each_rule<'a>(&'a self, mut f: impl FnMut(&'a RuleConfig<L>)) {
+    self.visit_rules(&mut f);
+  }
+
+  // use dyn to avoid infinite generic instantiation in recursion of scopes
+  fn visit_rules<'a>(&'a self, f: &mut dyn FnMut(&'a RuleConfig<L>)) {
     for bucket in &self.tenured {
       for rule in &bucket.rules {
         f(rule);
@@ -155,6 +236,9 @@ impl<L: Language + Eq> RuleCollection<L> {
     for rule in &self.contingent {
       f(&rule.rule);
     }
+    for scope in &self.scopes {
+      scope.rules.visit_rules(f);
+    }
   }
 
   fn add_tenured_rule(tenured: &mut Vec<RuleBucket<L>>, rule: RuleConfig<L>) {
@@ -176,6 +260,7 @@ impl<L: Language + Eq> Default for RuleCollection<L> {
     Self {
       tenured: vec![],
       contingent: vec![],
+      scopes: vec![],
     }
   }
 }
@@ -259,6 +344,20 @@ ignores:
     assert_ignore_path(&collection, "./src/excluded/app.py");
   }
 
+  #[test]
+  fn test_rule_config_matches_path() {
+    let rule = |src: &str| {
+      let yaml = format!("id: test\nlanguage: Tsx\nrule: {{kind: number}}\n{src}");
+      make_rules(&yaml).pop().expect("should have rule")
+    };
+    let plain = rule("");
+    assert!(plain.matches_path("./src/app.py").expect("ok"));
+    let scoped = rule("files: [./src/**/*.py]\nignores: [./src/excluded/*.py]");
+    assert!(scoped.matches_path("./src/test.py").expect("ok"));
+    assert!(!scoped.matches_path("./src/excluded/app.py").expect("ok"));
+    assert!(!scoped.matches_path("./lib/test.py").expect("ok"));
+  }
+
   #[test]
   fn test_rule_collection_get_contingent_rule() {
     let src = r#"
@@ -277,6 +376,69 @@ files:
     assert!(collection.get_rule("test").is_some());
   }
 
+  fn make_rules(rules: &str) -> Vec<RuleConfig<TypeScript>> {
+    from_yaml_string(rules, &GlobalRules::default()).expect("should parse")
+  }
+
+  fn rule_ids(rules: Vec<&RuleConfig<TypeScript>>) -> Vec<(&str, String)> {
+    let ret = rules
+      .into_iter()
+      .map(|r| (r.id.as_str(), format!("{:?}", r.severity)));
+    ret.collect()
+  }
+
+  #[test]
+  fn test_scoped_rules() {
+    let mut collection = RuleCollection::try_new(make_rules(
+      r"
+id: a
+language: Tsx
+severity: error
+rule: {kind: number}
+---
+id: b
+language: Tsx
+severity: error
+rule: {kind: number}",
+    ))
+    .expect("should parse");
+    let scope = make_rules(
+      r"
+id: a
+language: Tsx
+severity: warning
+rule: {kind: number}
+---
+id: b
+language: Tsx
+severity: off
+rule: {kind: number}",
+    );
+    collection
+      .add_scope(PathBuf::from("/project/legacy"), scope)
+      .expect("should add");
+    let deeper = make_rules("{id: c, language: Tsx, severity: hint, rule: {kind: number}}");
+    collection
+      .add_scope(PathBuf::from("/project/legacy/new"), deeper)
+      .expect("should add");
+    let error = || "Error".to_string();
+    let warning = || "Warning".to_string();
+    assert_eq!(
+      rule_ids(collection.for_path("/project/src/a.tsx")),
+      vec![("a", error()), ("b", error())]
+    );
+    assert_eq!(
+      rule_ids(collection.for_path("/project/legacy/a.tsx")),
+      vec![("a", warning())]
+    );
+    assert_eq!(
+      rule_ids(collection.for_path("/project/legacy/new/a.tsx")),
+      vec![("a", warning()), ("c", "Hint".to_string())]
+    );
+    assert_eq!(collection.for_path("/project/legacy.tsx").len(), 2);
+    assert_eq!(collection.total_rule_count(), 4);
+  }
+
   #[test]
   #[ignore]
   fn test_rules_for_path() {
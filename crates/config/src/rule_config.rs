@@ -2,13 +2,17 @@ use crate::GlobalRules;
 
 use crate::check_var::{check_rewriters_in_transform, CheckHint};
 use crate::fixer::Fixer;
-use crate::rule::DeserializeEnv;
+use crate::rule::{DeserializeEnv, Rule, SerializableRule, Strictness};
 use crate::rule_core::{RuleCore, RuleCoreError, SerializableRuleCore};
+use crate::transform::{parse_meta_var, Convert};
 
 use ast_grep_core::language::Language;
+use ast_grep_core::meta_var::{MetaVarEnv, MetaVariable};
 use ast_grep_core::replacer::Replacer;
-use ast_grep_core::{Matcher, NodeMatch, StrDoc};
+use ast_grep_core::source::Content;
+use ast_grep_core::{AstGrep, Doc, MatchStrictness, Matcher, Node, NodeMatch, StrDoc};
 
+use globset::{Glob, GlobMatcher};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Error as YamlError;
@@ -16,7 +20,8 @@ use serde_yaml::{with::singleton_map_recursive::deserialize, Deserializer};
 use thiserror::Error;
 
 use std::collections::{HashMap, HashSet};
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
+use std::path::Path;
 
 #[derive(Serialize, Deserialize, Clone, Default, JsonSchema, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -46,8 +51,45 @@ pub enum RuleConfigError {
   UndefinedRewriter(String),
   #[error("Rewriter rule `{0}` should have `fix`.")]
   NoFixInRewriter(String),
+  #[error("Duplicate rewriter id `{0}` is found in shared rewriters.")]
+  DuplicateRewriter(String),
   #[error("Rule must specify a set of AST kinds to match. Try adding `kind` rule.")]
   MissingPotentialKinds,
+  #[error("`requires` is not configured correctly.")]
+  Requires(#[source] RuleCoreError),
+  #[error("`requires` contains invalid file glob.")]
+  RequiresGlob(#[from] globset::Error),
+}
+
+/// A condition on other files in the project.
+/// The rule only reports issues when a file matching `file` contains a node matching `rule`.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct SerializableRequire {
+  /// Glob pattern of the files to search, e.g. `utils/**`
+  pub file: String,
+  /// A rule object to find in the files
+  pub rule: SerializableRule,
+  /// Reverse the condition: report issues only when no file contains a matching node
+  #[serde(default)]
+  pub absent: bool,
+}
+
+pub struct Require<L: Language> {
+  file: GlobMatcher,
+  rule: Rule<L>,
+  pub absent: bool,
+}
+
+impl<L: Language> Require<L> {
+  /// Whether the file should be searched for the condition.
+  pub fn is_candidate(&self, path: &Path) -> bool {
+    self.file.is_match(path)
+  }
+
+  /// Whether the file contains a node matching the condition.
+  pub fn is_found<D: Doc<Lang = L>>(&self, root: &AstGrep<D>) -> bool {
+    root.root().find(&self.rule).is_some()
+  }
 }
 
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
@@ -58,6 +100,62 @@ pub struct SerializableRewriter {
   pub id: String,
 }
 
+/// Rewriters defined once in shared files and referenced by id from any rule's `transform.rewrite`.
+/// A rule only compiles the shared rewriters it uses, directly or through other rewriters.
+///
+/// Shared rewriters can refer to each other even if they form a cycle across files,
+/// the same as rewriters in one rule. A rewriter only rewrites the nodes captured in its match,
+/// so recursive rewriters are useful to handle nested code like `[1, [2, [3]]]`.
+#[derive(Clone, Default)]
+pub struct SharedRewriters(HashMap<String, SerializableRewriter>);
+
+impl SharedRewriters {
+  /// Checks the rewriters when they are loaded, before any rule uses them.
+  /// Rewriters have no language until a rule uses them, so their patterns are compiled later.
+  pub fn try_new(rewriters: Vec<SerializableRewriter>) -> Result<Self, RuleConfigError> {
+    let mut map = HashMap::new();
+    for rewriter in rewriters {
+      if rewriter.core.fix.is_none() {
+        return Err(RuleConfigError::NoFixInRewriter(rewriter.id));
+      }
+      if map.contains_key(&rewriter.id) {
+        return Err(RuleConfigError::DuplicateRewriter(rewriter.id));
+      }
+      map.insert(rewriter.id.clone(), rewriter);
+    }
+    // shared rewriters can not see rewriters defined in the rule using them
+    let undefined = map
+      .values()
+      .flat_map(|rewriter| used_rewriters(&rewriter.core))
+      .find(|id| !map.contains_key(*id));
+    if let Some(id) = undefined {
+      return Err(RuleConfigError::UndefinedRewriter(id.clone()));
+    }
+    Ok(Self(map))
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+}
+
+fn used_rewriters(core: &SerializableRuleCore) -> impl Iterator<Item = &String> {
+  core
+    .transform
+    .iter()
+    .flat_map(|t| t.values())
+    .flat_map(|t| t.used_rewriters())
+}
+
+/// Schema of `language`, which is expanded to one language per rule before deserialization.
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum OneOrMany<L> {
+  One(L),
+  Many(Vec<L>),
+}
+
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct SerializableRuleConfig<L: Language> {
   #[serde(flatten)]
@@ -65,11 +163,14 @@ pub struct SerializableRuleConfig<L: Language> {
   /// Unique, descriptive identifier, e.g., no-unused-variable
   pub id: String,
   /// Specify the language to parse and the file extension to include in matching.
+  /// A list of languages in a rule file clones the rule for every language with the same id.
+  #[schemars(with = "OneOrMany<L>")]
   pub language: L,
   /// Rewrite rules for `rewrite` transformation
   pub rewriters: Option<Vec<SerializableRewriter>>,
   /// Main message highlighting why this rule fired. It should be single line and concise,
   /// but specific enough to be understood without additional context.
+  /// Besides meta variables, it supports `${A.kind}`, `${A.line}`, `${A.column}` and `${A|upperCase}`.
   #[serde(default)]
   pub message: String,
   /// Additional notes to elaborate the message and provide potential fix to the issue.
@@ -85,9 +186,91 @@ pub struct SerializableRuleConfig<L: Language> {
   pub url: Option<String>,
   /// Extra information for the rule
   pub metadata: Option<HashMap<String, String>>,
+  /// A condition on other files in the project that must hold for the rule to report issues
+  pub requires: Option<SerializableRequire>,
+  /// Labels to highlight meta variables in diagnostics, keyed by meta variable name without `$`.
+  /// Every key must be a meta variable defined in the rule.
+  /// If present, they replace the default secondary labels of relational rules.
+  pub labels: Option<HashMap<String, LabelConfig>>,
+}
+
+/// Style of a label in diagnostics.
+#[derive(Serialize, Deserialize, Clone, Copy, JsonSchema, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LabelStyle {
+  /// The label describes the primary cause of the diagnostic.
+  Primary,
+  /// The label provides additional context, e.g. where a variable is declared.
+  Secondary,
+}
+
+/// A label highlighting the code captured by a meta variable.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct LabelConfig {
+  /// the style of the label
+  pub style: LabelStyle,
+  /// optional message displayed with the label, e.g. "declared here"
+  pub message: Option<String>,
+}
+
+/// A label of a rule match, resolved from `labels` in rule config.
+pub struct Label<'r, 't, L: Language> {
+  pub style: LabelStyle,
+  pub message: Option<&'r str>,
+  /// first node of the labeled code
+  pub start_node: Node<'t, StrDoc<L>>,
+  /// last node of the labeled code, the same as start node unless the var is a multi capture
+  pub end_node: Node<'t, StrDoc<L>>,
+}
+
+impl<'r, 't, L: Language> Label<'r, 't, L> {
+  pub fn range(&self) -> Range<usize> {
+    self.start_node.range().start..self.end_node.range().end
+  }
 }
 
 impl<L: Language> SerializableRuleConfig<L> {
+  /// Copies the shared rewriters used by the rule into its own `rewriters`.
+  /// Rewriters defined in the rule take precedence over shared ones of the same id.
+  /// Rewriters can refer to each other across files, every one of them is added once.
+  pub fn add_shared_rewriters(&mut self, shared: &SharedRewriters) {
+    if shared.is_empty() {
+      return;
+    }
+    let local = self.rewriters.iter().flatten();
+    let mut visited: HashSet<_> = local.clone().map(|r| r.id.clone()).collect();
+    let mut pending: Vec<_> = used_rewriters(&self.core)
+      .chain(local.flat_map(|r| used_rewriters(&r.core)))
+      .cloned()
+      .collect();
+    let mut added = vec![];
+    while let Some(id) = pending.pop() {
+      if !visited.insert(id.clone()) {
+        continue;
+      }
+      // undefined rewriters are reported when the rule is compiled
+      let Some(rewriter) = shared.0.get(&id) else {
+        continue;
+      };
+      pending.extend(used_rewriters(&rewriter.core).cloned());
+      added.push(rewriter.clone());
+    }
+    if !added.is_empty() {
+      self.rewriters.get_or_insert_with(Vec::new).extend(added);
+    }
+  }
+
+  /// Overrides the strictness of all patterns in the rule, rewriters and `requires`.
+  pub fn set_strictness(&mut self, strictness: MatchStrictness) {
+    self.core.set_strictness(strictness.clone());
+    for rewriter in self.rewriters.iter_mut().flatten() {
+      rewriter.core.set_strictness(strictness.clone());
+    }
+    if let Some(requires) = &mut self.requires {
+      requires.rule.set_strictness(&Strictness::from(strictness));
+    }
+  }
+
   pub fn get_matcher(&self, globals: &GlobalRules<L>) -> Result<RuleCore<L>, RuleConfigError> {
     // every RuleConfig has one rewriters, and the rewriter is shared between sub-rules
     // all RuleConfigs has one common globals
@@ -101,6 +284,25 @@ impl<L: Language> SerializableRuleConfig<L> {
     Ok(rule)
   }
 
+  fn get_require(&self, globals: &GlobalRules<L>) -> Result<Option<Require<L>>, RuleConfigError> {
+    let Some(ser) = &self.requires else {
+      return Ok(None);
+    };
+    let env = DeserializeEnv::new(self.language.clone()).with_globals(globals);
+    let env = self
+      .get_deserialize_env(env)
+      .map_err(RuleConfigError::Requires)?;
+    let rule = env
+      .deserialize_rule(ser.rule.clone())
+      .and_then(|r| r.verify_util().map(|_| r))
+      .map_err(|e| RuleConfigError::Requires(e.into()))?;
+    Ok(Some(Require {
+      file: Glob::new(&ser.file)?.compile_matcher(),
+      rule,
+      absent: ser.absent,
+    }))
+  }
+
   fn register_rewriters(
     &self,
     rule: &RuleCore<L>,
@@ -160,6 +362,8 @@ impl<L: Language> DerefMut for SerializableRuleConfig<L> {
 pub struct RuleConfig<L: Language> {
   inner: SerializableRuleConfig<L>,
   pub matcher: RuleCore<L>,
+  /// compiled `requires` condition, checked against a project-wide index
+  pub requires: Option<Require<L>>,
 }
 
 impl<L: Language> RuleConfig<L> {
@@ -171,7 +375,31 @@ impl<L: Language> RuleConfig<L> {
     if matcher.potential_kinds().is_none() {
       return Err(RuleConfigError::MissingPotentialKinds);
     }
-    Ok(Self { inner, matcher })
+    let vars = matcher.defined_vars();
+    if let Some(var) = inner.labels.iter().flatten().find_map(|(var, _)| {
+      let undefined = !vars.contains(var.as_str());
+      undefined.then_some(var)
+    }) {
+      let err = RuleCoreError::UndefinedMetaVar(var.to_string(), "labels");
+      return Err(err.into());
+    }
+    let requires = inner.get_require(globals)?;
+    Ok(Self {
+      inner,
+      matcher,
+      requires,
+    })
+  }
+
+  /// Recompiles the rule with the strictness of all patterns overridden.
+  pub fn with_strictness(
+    self,
+    strictness: MatchStrictness,
+    globals: &GlobalRules<L>,
+  ) -> Result<Self, RuleConfigError> {
+    let mut inner = self.inner;
+    inner.set_strictness(strictness);
+    Self::try_from(inner, globals)
   }
 
   pub fn deserialize<'de>(
@@ -185,22 +413,162 @@ impl<L: Language> RuleConfig<L> {
     Self::try_from(inner, globals)
   }
 
-  pub fn get_message(&self, node: &NodeMatch<StrDoc<L>>) -> String {
+  pub fn get_message<D: Doc<Lang = L>>(&self, node: &NodeMatch<D>) -> String {
     let env = self.matcher.get_env(self.language.clone());
-    let parsed = Fixer::with_transform(&self.message, &env, &self.transform).expect("should work");
-    let bytes = parsed.generate_replacement(node);
-    String::from_utf8(bytes).expect("replacement must be valid utf-8")
+    let mut message = String::new();
+    for piece in split_message(&self.message, &self.language) {
+      match piece {
+        MessagePiece::Text(text) => {
+          let parsed = Fixer::with_transform(text, &env, &self.transform).expect("should work");
+          let bytes = parsed.generate_replacement(node);
+          message.push_str(&D::Source::encode_bytes(&bytes));
+        }
+        MessagePiece::Format(format) => message.push_str(&format.apply(node.get_env())),
+      }
+    }
+    message
+  }
+  /// Resolve configured labels against meta variables of the match, sorted by position.
+  /// Labels of unmatched meta variables are skipped.
+  pub fn get_labels<'t>(&self, nm: &NodeMatch<'t, StrDoc<L>>) -> Vec<Label<'_, 't, L>> {
+    let Some(labels) = &self.labels else {
+      return vec![];
+    };
+    let env = nm.get_env();
+    let mut ret: Vec<_> = labels
+      .iter()
+      .filter_map(|(var, config)| {
+        let (start_node, end_node) = if let Some(node) = env.get_match(var) {
+          (node.clone(), node.clone())
+        } else {
+          let nodes = env.get_multiple_matches(var);
+          (nodes.first()?.clone(), nodes.last()?.clone())
+        };
+        Some(Label {
+          style: config.style,
+          message: config.message.as_deref(),
+          start_node,
+          end_node,
+        })
+      })
+      .collect();
+    ret.sort_by_key(|l| (l.range().start, l.range().end));
+    ret
   }
-  pub fn get_fixer(&self) -> Result<Option<Fixer<L>>, RuleConfigError> {
+
+  pub fn get_fixer(&self) -> Result<Vec<Fixer<L>>, RuleConfigError> {
     if let Some(fix) = &self.fix {
       let env = self.matcher.get_env(self.language.clone());
       let parsed = Fixer::parse(fix, &env, &self.transform).map_err(RuleCoreError::Fixer)?;
-      Ok(Some(parsed))
+      Ok(parsed)
     } else {
-      Ok(None)
+      Ok(vec![])
     }
   }
 }
+enum MessagePiece<'a> {
+  /// text rendered as fix template
+  Text(&'a str),
+  Format(MessageFormat),
+}
+
+/// Formatters of meta variable in message, e.g. `${A.kind}`, `${$$$ARGS.line}` or `${A|upperCase}`.
+/// Unbraced `$A.kind` is plain text after the meta variable, as in messages before formatters.
+enum MessageFormat {
+  Kind(MetaVariable),
+  Line(MetaVariable),
+  Column(MetaVariable),
+  Case(Convert<MetaVariable>),
+}
+
+impl MessageFormat {
+  fn parse<L: Language>(src: &str, lang: &L) -> Option<(Self, usize)> {
+    let mv_char = lang.meta_var_char();
+    let inner = src.strip_prefix(mv_char)?.strip_prefix('{')?;
+    let end = inner.find('}')?;
+    let len = mv_char.len_utf8() + 1 + end + 1;
+    let body = &inner[..end];
+    if let Some((name, case)) = body.split_once('|') {
+      let var = parse_var(name.trim(), lang)?;
+      let case = serde_yaml::from_str(case.trim()).ok()?;
+      return Some((Self::Case(Convert::new(var, case)), len));
+    }
+    let (name, prop) = body.rsplit_once('.')?;
+    let var = parse_var(name, lang)?;
+    let format = match prop {
+      "kind" => Self::Kind(var),
+      "line" => Self::Line(var),
+      "column" => Self::Column(var),
+      _ => return None,
+    };
+    Some((format, len))
+  }
+
+  fn apply<D: Doc>(&self, env: &MetaVarEnv<D>) -> String {
+    let nodes = |var: &MetaVariable| match var {
+      MetaVariable::Capture(name, _) => env.get_match(name).cloned().into_iter().collect(),
+      MetaVariable::MultiCapture(name) => env.get_multiple_matches(name),
+      _ => vec![],
+    };
+    let first = |var| nodes(var).into_iter().next();
+    let formatted = match self {
+      // unnamed nodes like commas in multi captures are skipped, as in `join`
+      Self::Kind(var) => {
+        let kinds: Vec<_> = nodes(var)
+          .iter()
+          .filter(|n| n.is_named())
+          .map(|n| n.kind().to_string())
+          .collect();
+        Some(kinds.join(", "))
+      }
+      // line and column are one-based as shown in reports
+      Self::Line(var) => first(var).map(|n| (n.start_pos().line() + 1).to_string()),
+      Self::Column(var) => first(var).map(|n| (n.start_pos().column(&n) + 1).to_string()),
+      Self::Case(convert) => convert.apply(env),
+    };
+    formatted.unwrap_or_default()
+  }
+}
+
+/// Parse `A` or `$$$A` in braces like the `source` of transformations.
+fn parse_var<L: Language>(name: &str, lang: &L) -> Option<MetaVariable> {
+  let mv_char = lang.meta_var_char();
+  let src = if name.starts_with(mv_char) {
+    name.to_string()
+  } else {
+    format!("{mv_char}{name}")
+  };
+  match parse_meta_var(&src, lang).ok()? {
+    var @ (MetaVariable::Capture(..) | MetaVariable::MultiCapture(_)) => Some(var),
+    _ => None,
+  }
+}
+
+/// Split message into formatters and text between them.
+fn split_message<'m, L: Language>(message: &'m str, lang: &L) -> Vec<MessagePiece<'m>> {
+  let mv_char = lang.meta_var_char();
+  let mut pieces = vec![];
+  let mut start = 0;
+  let mut i = 0;
+  while let Some(offset) = message[i..].find(mv_char) {
+    let pos = i + offset;
+    if let Some((format, len)) = MessageFormat::parse(&message[pos..], lang) {
+      if start < pos {
+        pieces.push(MessagePiece::Text(&message[start..pos]));
+      }
+      pieces.push(MessagePiece::Format(format));
+      i = pos + len;
+      start = i;
+    } else {
+      i = pos + mv_char.len_utf8();
+    }
+  }
+  if start < message.len() || pieces.is_empty() {
+    pieces.push(MessagePiece::Text(&message[start..]));
+  }
+  pieces
+}
+
 impl<L: Language> Deref for RuleConfig<L> {
   type Target = SerializableRuleConfig<L>;
   fn deref(&self) -> &Self::Target {
@@ -241,9 +609,26 @@ mod test {
       ignores: None,
       url: None,
       metadata: None,
+      requires: None,
+      labels: None,
     }
   }
 
+  #[test]
+  fn test_with_strictness() {
+    let globals = GlobalRules::default();
+    let rule = from_str("{pattern: foo(a), inside: {pattern: bar($$$), stopBy: end}}")
+      .expect("cannot parse rule");
+    let config = RuleConfig::try_from(ts_rule_config(rule), &globals).expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("baz(foo(b))");
+    assert!(grep.root().find(&config.matcher).is_none());
+    // signature strictness does not compare text
+    let config = config
+      .with_strictness(MatchStrictness::Signature, &globals)
+      .expect("should work");
+    assert!(grep.root().find(&config.matcher).is_some());
+  }
+
   #[test]
   fn test_rule_message() {
     let globals = GlobalRules::default();
@@ -260,6 +645,34 @@ mod test {
     assert_eq!(config.get_message(&node_match), "Found TestClass");
   }
 
+  #[test]
+  fn test_regex_named_group_in_message() {
+    let globals = GlobalRules::default();
+    let config: SerializableRuleConfig<TypeScript> = from_str(
+      r"
+id: test
+language: Tsx
+message: Prefer hook $HOOK
+rule: {pattern: $F()}
+constraints:
+  F: {regex: '^use(?P<HOOK>[A-Z]\w*)$'}
+fix: use$HOOK(undefined)
+",
+    )
+    .expect("should parse");
+    let config = RuleConfig::try_from(config, &globals).expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("useState()");
+    let nm = grep.root().find(&config.matcher).expect("should match");
+    assert_eq!(config.get_message(&nm), "Prefer hook State");
+    let fixer = config
+      .get_fixer()
+      .expect("should work")
+      .pop()
+      .expect("should have fix");
+    let replacement = fixer.generate_replacement(&nm);
+    assert_eq!(String::from_utf8_lossy(&replacement), "useState(undefined)");
+  }
+
   #[test]
   fn test_augmented_rule() {
     let globals = GlobalRules::default();
@@ -392,7 +805,7 @@ test-rule:
     let mut config = get_matches_config();
     config.fix = Some(from_str("string!!").unwrap());
     let rule = RuleConfig::try_from(config, &globals).unwrap();
-    let fixer = rule.get_fixer().unwrap().unwrap();
+    let fixer = rule.get_fixer().unwrap().pop().unwrap();
     let grep = TypeScript::Tsx.ast_grep("some(123)");
     let nm = grep.root().find(&rule.matcher).unwrap();
     let replacement = fixer.generate_replacement(&nm);
@@ -425,6 +838,70 @@ rewriters:
     assert_eq!(String::from_utf8_lossy(b), "yjsnp");
   }
 
+  #[test]
+  fn test_shared_rewriters() {
+    let shared: Vec<SerializableRewriter> = from_str(
+      r"
+- id: re1
+  rule: {pattern: '[$$$C]'}
+  transform:
+    D: {rewrite: {source: $$$C, rewriters: [re2]}}
+  fix: $D
+- id: re2
+  rule: {pattern: '[$$$C]'}
+  transform:
+    D: {rewrite: {source: $$$C, rewriters: [re1]}}
+  fix: $D
+- id: unused
+  rule: {kind: number}
+  fix: '0'
+",
+    )
+    .expect("should parse");
+    let shared = SharedRewriters::try_new(shared).expect("should work");
+    let mut rule: SerializableRuleConfig<TypeScript> = from_str(
+      r"
+id: test
+rule: {pattern: 'a = $A'}
+language: Tsx
+transform:
+  B:
+    rewrite:
+      rewriters: [re1]
+      source: $A
+",
+    )
+    .expect("should parse");
+    rule.add_shared_rewriters(&shared);
+    // re1 and re2 form a cycle, each is added once and they recurse into nested arrays
+    let ids: Vec<_> = rule.rewriters.iter().flatten().map(|r| &r.id).collect();
+    assert_eq!(ids, ["re1", "re2"]);
+    let rule = RuleConfig::try_from(rule, &Default::default()).expect("work");
+    let grep = TypeScript::Tsx.ast_grep("a = [1, [2, [3]]]");
+    let nm = grep.root().find(&rule.matcher).unwrap();
+    let b = nm.get_env().get_transformed("B").expect("should have");
+    assert_eq!(String::from_utf8_lossy(b), "1, 2, 3");
+  }
+
+  #[test]
+  fn test_shared_rewriters_error() {
+    let dup = "{id: re, rule: {kind: number}, fix: '0'}";
+    let shared = vec![from_str(dup).unwrap(), from_str(dup).unwrap()];
+    let ret = SharedRewriters::try_new(shared);
+    assert!(matches!(ret, Err(RuleConfigError::DuplicateRewriter(id)) if id == "re"));
+    let shared = vec![from_str("{id: re, rule: {kind: number}}").unwrap()];
+    let ret = SharedRewriters::try_new(shared);
+    assert!(matches!(ret, Err(RuleConfigError::NoFixInRewriter(_))));
+    let undefined = "
+id: re
+rule: {pattern: '[$$$C]'}
+transform:
+  D: {rewrite: {source: $$$C, rewriters: [missing]}}
+fix: $D";
+    let ret = SharedRewriters::try_new(vec![from_str(undefined).unwrap()]);
+    assert!(matches!(ret, Err(RuleConfigError::UndefinedRewriter(id)) if id == "missing"));
+  }
+
   #[test]
   fn test_rewriters_access_utils() {
     let rule: SerializableRuleConfig<TypeScript> = from_str(
@@ -673,4 +1150,104 @@ message: $TEST
     let nm = grep.root().find(&rule.matcher).unwrap();
     assert_eq!(rule.get_message(&nm), "'123'");
   }
+
+  #[test]
+  fn test_get_message_format() {
+    let src = r"
+id: test-rule
+language: Tsx
+rule: { pattern: 'let $A = $B' }
+transform:
+  C: { substring: { source: $B, startChar: 1 } }
+message: ${A.kind} at ${A.line}:${A.column}, ${A|upperCase} ${C|pascalCase} $B.size $$.kind
+    ";
+    let rule: SerializableRuleConfig<TypeScript> = from_str(src).expect("should parse");
+    let rule = RuleConfig::try_from(rule, &Default::default()).expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("\n  let some_var = a_value");
+    let nm = grep.root().find(&rule.matcher).unwrap();
+    assert_eq!(
+      rule.get_message(&nm),
+      "identifier at 2:7, SOME_VAR Value a_value.size $$.kind"
+    );
+  }
+
+  #[test]
+  fn test_get_message_format_multi() {
+    let src = r"
+id: test-rule
+language: Tsx
+rule: { pattern: 'foo($$$ARGS)' }
+message: ${$$$ARGS.kind} at ${$$$ARGS.column}, ${$$$ARGS|upperCase}, $$$ARGS.kind
+    ";
+    let rule: SerializableRuleConfig<TypeScript> = from_str(src).expect("should parse");
+    let rule = RuleConfig::try_from(rule, &Default::default()).expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("foo(a, 1)");
+    let nm = grep.root().find(&rule.matcher).unwrap();
+    // unbraced `.kind` is kept as text for messages written before formatters
+    assert_eq!(
+      rule.get_message(&nm),
+      "identifier, number at 5, A, 1, a, 1.kind"
+    );
+  }
+
+  #[test]
+  fn test_get_labels() {
+    let src = r"
+id: test-rule
+language: Tsx
+rule:
+  any:
+    - pattern: 'foo($A, $$$B)'
+    - pattern: 'bar($C)'
+labels:
+  B: { style: secondary, message: rest args }
+  A: { style: primary }
+  C: { style: secondary }
+    ";
+    let rule: SerializableRuleConfig<TypeScript> = from_str(src).expect("should parse");
+    let rule = RuleConfig::try_from(rule, &Default::default()).expect("should work");
+    let grep = TypeScript::Tsx.ast_grep("foo(a, b, c)");
+    let nm = grep.root().find(&rule.matcher).unwrap();
+    let labels = rule.get_labels(&nm);
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels[0].style, LabelStyle::Primary);
+    assert_eq!(labels[0].message, None);
+    assert_eq!(labels[0].range(), 4..5);
+    assert_eq!(labels[1].message, Some("rest args"));
+    assert_eq!(labels[1].range(), 7..11);
+  }
+
+  #[test]
+  fn test_undefined_label() {
+    let src = r"
+id: test-rule
+language: Tsx
+rule: { pattern: 'foo($A)' }
+labels:
+  B: { style: secondary }
+    ";
+    let rule: SerializableRuleConfig<TypeScript> = from_str(src).expect("should parse");
+    let ret = RuleConfig::try_from(rule, &Default::default());
+    assert!(matches!(
+      ret,
+      Err(RuleConfigError::Core(RuleCoreError::UndefinedMetaVar(var, "labels"))) if var == "B"
+    ));
+  }
+
+  #[test]
+  fn test_split_message() {
+    let lang = TypeScript::Tsx;
+    let pieces = split_message("${A.kind}", &lang);
+    assert!(matches!(
+      pieces[..],
+      [MessagePiece::Format(MessageFormat::Kind(_))]
+    ));
+    let pieces = split_message("a $A.kind ${A.kinds} ${A:-b}", &lang);
+    assert!(matches!(
+      pieces[..],
+      [MessagePiece::Text("a $A.kind ${A.kinds} ${A:-b}")]
+    ));
+    let pieces = split_message("", &lang);
+    assert!(matches!(pieces[..], [MessagePiece::Text("")]));
+  }
 }
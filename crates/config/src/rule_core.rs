@@ -1,14 +1,16 @@
+use crate::anchor::resolve_alias;
 use crate::check_var::{check_rule_with_hint, CheckHint};
+use crate::constraint::{MetaVarMetric, SerializableConstraint};
 use crate::fixer::{Fixer, FixerError, SerializableFixer};
 use crate::rule::referent_rule::RuleRegistration;
-use crate::rule::Rule;
+use crate::rule::{record_any_hits, Rule, Strictness};
 use crate::rule::{RuleSerializeError, SerializableRule};
 use crate::transform::{Transform, TransformError, Transformation};
 use crate::DeserializeEnv;
 
 use ast_grep_core::language::Language;
 use ast_grep_core::meta_var::MetaVarEnv;
-use ast_grep_core::{Doc, Matcher, Node};
+use ast_grep_core::{Doc, MatchStrictness, Matcher, Node, NodeMatch};
 use serde::{Deserialize, Serialize};
 use serde_yaml::Error as YamlError;
 
@@ -45,8 +47,12 @@ type RResult<T> = std::result::Result<T, RuleCoreError>;
 pub struct SerializableRuleCore {
   /// A rule object to find matching AST nodes
   pub rule: SerializableRule,
-  /// Additional meta variables pattern to filter matching
-  pub constraints: Option<HashMap<String, SerializableRule>>,
+  /// Additional meta variables pattern to filter matching.
+  /// Besides rules, `length` and `count` can limit the size of captured nodes,
+  /// `resolvesTo` checks the module a captured name is imported from,
+  /// `resolveAlias` checks the anchored node of a YAML alias,
+  /// `line` and `column` limit the 0-based start position, and `isLeaf` requires nodes without children.
+  pub constraints: Option<HashMap<String, SerializableConstraint>>,
   /// Utility rules that can be used in `matches`
   pub utils: Option<HashMap<String, SerializableRule>>,
   /// A dictionary for metavariable manipulation. Dict key is the new variable name.
@@ -60,6 +66,20 @@ pub struct SerializableRuleCore {
 }
 
 impl SerializableRuleCore {
+  /// Overrides the strictness of all patterns in the rule, constraints and utils.
+  pub fn set_strictness(&mut self, strictness: MatchStrictness) {
+    let strictness = Strictness::from(strictness);
+    self.rule.set_strictness(&strictness);
+    for cons in self.constraints.iter_mut().flat_map(|c| c.values_mut()) {
+      if let Some(rule) = &mut cons.rule {
+        rule.set_strictness(&strictness);
+      }
+    }
+    for util in self.utils.iter_mut().flat_map(|u| u.values_mut()) {
+      util.set_strictness(&strictness);
+    }
+  }
+
   pub(crate) fn get_deserialize_env<L: Language>(
     &self,
     env: DeserializeEnv<L>,
@@ -83,20 +103,43 @@ impl SerializableRuleCore {
       return Ok(constraints);
     };
     for (key, ser) in serde_cons {
+      let Some(rule) = &ser.rule else {
+        continue;
+      };
       let constraint = env
-        .deserialize_rule(ser.clone())
+        .deserialize_rule(rule.clone())
         .map_err(RuleCoreError::Constraints)?;
       constraints.insert(key.to_string(), constraint);
     }
     Ok(constraints)
   }
 
-  fn get_fixer<L: Language>(&self, env: &DeserializeEnv<L>) -> RResult<Option<Fixer<L>>> {
+  fn get_metrics(&self) -> HashMap<String, MetaVarMetric> {
+    let Some(serde_cons) = &self.constraints else {
+      return HashMap::new();
+    };
+    serde_cons
+      .iter()
+      .filter_map(|(key, ser)| Some((key.to_string(), ser.get_metric()?)))
+      .collect()
+  }
+
+  fn get_alias_vars(&self) -> HashSet<String> {
+    let Some(serde_cons) = &self.constraints else {
+      return HashSet::new();
+    };
+    serde_cons
+      .iter()
+      .filter(|(_, ser)| ser.resolve_alias)
+      .map(|(key, _)| key.to_string())
+      .collect()
+  }
+
+  fn get_fixer<L: Language>(&self, env: &DeserializeEnv<L>) -> RResult<Vec<Fixer<L>>> {
     if let Some(fix) = &self.fix {
-      let parsed = Fixer::parse(fix, env, &self.transform)?;
-      Ok(Some(parsed))
+      Ok(Fixer::parse(fix, env, &self.transform)?)
     } else {
-      Ok(None)
+      Ok(vec![])
     }
   }
 
@@ -112,6 +155,8 @@ impl SerializableRuleCore {
     Ok(
       RuleCore::new(rule)
         .with_matchers(constraints)
+        .with_metrics(self.get_metrics())
+        .with_alias_vars(self.get_alias_vars())
         .with_utils(env.registration.clone())
         .with_transform(transform)
         .with_fixer(fixer),
@@ -129,6 +174,13 @@ impl SerializableRuleCore {
   ) -> RResult<RuleCore<L>> {
     let env = self.get_deserialize_env(env)?;
     let ret = self.get_matcher_from_env(&env)?;
+    let vars = ret.defined_vars();
+    if let Some(var) = ret.metrics.keys().find(|v| !vars.contains(v.as_str())) {
+      return Err(RuleCoreError::UndefinedMetaVar(
+        var.to_string(),
+        "constraints",
+      ));
+    }
     check_rule_with_hint(
       &ret.rule,
       &ret.utils,
@@ -144,9 +196,13 @@ impl SerializableRuleCore {
 pub struct RuleCore<L: Language> {
   rule: Rule<L>,
   constraints: HashMap<String, Rule<L>>,
+  metrics: HashMap<String, MetaVarMetric>,
+  /// constrained vars whose YAML alias is resolved before matching
+  alias_vars: HashSet<String>,
   kinds: Option<BitSet>,
   pub(crate) transform: Option<Transform>,
-  pub fixer: Option<Fixer<L>>,
+  /// alternative fixers in declaration order, the first applicable one is the default
+  pub fixer: Vec<Fixer<L>>,
   // this is required to hold util rule reference
   utils: RuleRegistration<L>,
 }
@@ -170,6 +226,16 @@ impl<L: Language> RuleCore<L> {
     }
   }
 
+  #[inline]
+  pub fn with_metrics(self, metrics: HashMap<String, MetaVarMetric>) -> Self {
+    Self { metrics, ..self }
+  }
+
+  #[inline]
+  pub fn with_alias_vars(self, alias_vars: HashSet<String>) -> Self {
+    Self { alias_vars, ..self }
+  }
+
   #[inline]
   pub fn with_utils(self, utils: RuleRegistration<L>) -> Self {
     Self { utils, ..self }
@@ -181,10 +247,20 @@ impl<L: Language> RuleCore<L> {
   }
 
   #[inline]
-  pub fn with_fixer(self, fixer: Option<Fixer<L>>) -> Self {
+  pub fn with_fixer(self, fixer: Vec<Fixer<L>>) -> Self {
     Self { fixer, ..self }
   }
 
+  /// Returns the first fixer applicable to the matched node.
+  pub fn get_fixer<D: Doc<Lang = L>>(&self, nm: &NodeMatch<D>) -> Option<&Fixer<L>> {
+    self.fixer.iter().find(|f| f.is_applicable(nm))
+  }
+
+  /// Strings of which at least one must appear in the source for the rule to match.
+  pub(crate) fn required_literals(&self) -> Option<Vec<String>> {
+    self.rule.required_literals()
+  }
+
   pub fn get_env(&self, lang: L) -> DeserializeEnv<L> {
     DeserializeEnv {
       lang,
@@ -192,6 +268,17 @@ impl<L: Language> RuleCore<L> {
     }
   }
 
+  /// Run `f` and report whether each `any` branch of the rule matched a node meanwhile.
+  /// Branches are named by their path in `serialized`, the rule this core is created from,
+  /// e.g. `rule.any[2].all[1].has.any[0]`. Only matches on the current thread are recorded.
+  pub fn record_any_hits(
+    &self,
+    serialized: &SerializableRule,
+    f: impl FnOnce(),
+  ) -> Vec<(String, bool)> {
+    record_any_hits(&self.rule, serialized, f)
+  }
+
   pub fn defined_vars(&self) -> HashSet<&str> {
     let mut ret = self.rule.defined_vars();
     for v in self.utils.get_local_util_vars() {
@@ -222,7 +309,10 @@ impl<L: Language> RuleCore<L> {
       }
     }
     let ret = self.rule.match_node_with_env(node, env)?;
-    if !env.to_mut().match_constraints(&self.constraints) {
+    if !self.match_constraints(env) {
+      return None;
+    }
+    if !self.metrics.iter().all(|(var, m)| m.check(var, env)) {
       return None;
     }
     if let Some(trans) = &self.transform {
@@ -238,6 +328,29 @@ impl<L: Language> RuleCore<L> {
     }
     Some(ret)
   }
+
+  fn match_constraints<'tree, D: Doc<Lang = L>>(
+    &self,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
+  ) -> bool {
+    if self.alias_vars.is_empty() {
+      return env.to_mut().match_constraints(&self.constraints);
+    }
+    for (var, rule) in &self.constraints {
+      let Some(node) = env.get_match(var).cloned() else {
+        continue;
+      };
+      let node = if self.alias_vars.contains(var) {
+        resolve_alias(&node).unwrap_or(node)
+      } else {
+        node
+      };
+      if rule.match_node_with_env(node, env).is_none() {
+        return false;
+      }
+    }
+    true
+  }
 }
 impl<L: Language> Deref for RuleCore<L> {
   type Target = Rule<L>;
@@ -252,9 +365,11 @@ impl<L: Language> Default for RuleCore<L> {
     Self {
       rule: Rule::default(),
       constraints: HashMap::default(),
+      metrics: HashMap::default(),
+      alias_vars: HashSet::default(),
       kinds: None,
       transform: None,
-      fixer: None,
+      fixer: vec![],
       utils: RuleRegistration::default(),
     }
   }
@@ -278,7 +393,7 @@ impl<L: Language> Matcher<L> for RuleCore<L> {
 mod test {
   use super::*;
   use crate::rule::referent_rule::{ReferentRule, ReferentRuleError};
-  use crate::test::TypeScript;
+  use crate::test::{TypeScript, Yaml};
   use crate::{from_str, GlobalRules};
   use ast_grep_core::matcher::{Pattern, RegexMatcher};
 
@@ -397,6 +512,135 @@ transform:
     assert_eq!(matched, "2");
   }
 
+  #[test]
+  fn test_count_constraint() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore = from_str(
+      "{rule: {pattern: 'function $F($$$ARGS) {}'}, constraints: {ARGS: {count: {min: 3}}} }",
+    )
+    .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("function f(a, b) {}");
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = TypeScript::Tsx.ast_grep("function f(a, b, c) {}");
+    assert!(grep.root().find(&matcher).is_some());
+  }
+
+  #[test]
+  fn test_length_constraint() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore = from_str(
+      "{rule: {pattern: $A = 1}, constraints: {A: {regex: '^a', length: {min: 2, max: 3}}} }",
+    )
+    .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("a = 1");
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = TypeScript::Tsx.ast_grep("abc = 1");
+    assert!(grep.root().find(&matcher).is_some());
+    let grep = TypeScript::Tsx.ast_grep("bcd = 1");
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = TypeScript::Tsx.ast_grep("abcd = 1");
+    assert!(grep.root().find(&matcher).is_none());
+  }
+
+  #[test]
+  fn test_length_and_count_of_multi_capture() {
+    let matcher = |constraint: &str| {
+      let src =
+        format!("{{rule: {{pattern: 'foo($$$ARGS)'}}, constraints: {{ARGS: {constraint}}} }}");
+      let ser_rule: SerializableRuleCore = from_str(&src).expect("should deser");
+      ser_rule
+        .get_matcher(DeserializeEnv::new(TypeScript::Tsx))
+        .expect("should parse")
+    };
+    // length counts chars of `a`, `,` and `b`, count only the named `a` and `b`
+    let length = matcher("{length: {max: 3}}");
+    let count = matcher("{count: {max: 2}}");
+    let grep = TypeScript::Tsx.ast_grep("foo(a, b)");
+    assert!(grep.root().find(&length).is_some());
+    assert!(grep.root().find(&count).is_some());
+    let grep = TypeScript::Tsx.ast_grep("foo(ä, ö)");
+    assert!(grep.root().find(&length).is_some());
+    let grep = TypeScript::Tsx.ast_grep("foo(a, bc)");
+    assert!(grep.root().find(&length).is_none());
+    assert!(grep.root().find(&count).is_some());
+    let grep = TypeScript::Tsx.ast_grep("foo(a, b, c)");
+    assert!(grep.root().find(&count).is_none());
+  }
+
+  #[test]
+  fn test_resolves_to_constraint() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore =
+      from_str("{rule: {pattern: $F()}, constraints: {F: {resolvesTo: lodash}} }")
+        .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("import { debounce } from 'lodash'\ndebounce()");
+    assert!(grep.root().find(&matcher).is_some());
+    let grep = TypeScript::Tsx.ast_grep("import { debounce } from './utils'\ndebounce()");
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = TypeScript::Tsx.ast_grep("debounce()");
+    assert!(grep.root().find(&matcher).is_none());
+  }
+
+  #[test]
+  fn test_position_constraint() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore = from_str(
+      "{rule: {pattern: $A = $B}, constraints: {A: {kind: identifier, line: {min: 1}, column: {max: 2}}, B: {isLeaf: true}} }",
+    )
+    .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = TypeScript::Tsx.ast_grep("a = 1");
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = TypeScript::Tsx.ast_grep("\na = 1");
+    assert!(grep.root().find(&matcher).is_some());
+    let grep = TypeScript::Tsx.ast_grep("\n   a = 1");
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = TypeScript::Tsx.ast_grep("\na = f()");
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = TypeScript::Tsx.ast_grep("\na.b = 1");
+    assert!(grep.root().find(&matcher).is_none());
+  }
+
+  #[test]
+  fn test_resolve_alias_constraint() {
+    let env = DeserializeEnv::new(Yaml);
+    let ser_rule: SerializableRuleCore = from_str(
+      "{rule: {pattern: 'image: $I'}, constraints: {I: {regex: latest, resolveAlias: true}} }",
+    )
+    .expect("should deser");
+    let matcher = ser_rule.get_matcher(env).expect("should parse");
+    let grep = Yaml.ast_grep(
+      "tag: &tag node:latest
+job:
+  image: *tag",
+    );
+    assert!(grep.root().find(&matcher).is_some());
+    let grep = Yaml.ast_grep(
+      "tag: &tag node:20
+job:
+  image: *tag",
+    );
+    assert!(grep.root().find(&matcher).is_none());
+    let grep = Yaml.ast_grep("image: node:latest");
+    assert!(grep.root().find(&matcher).is_some());
+  }
+
+  #[test]
+  fn test_undefined_metric_var() {
+    let env = DeserializeEnv::new(TypeScript::Tsx);
+    let ser_rule: SerializableRuleCore =
+      from_str("{rule: {pattern: $A}, constraints: {B: {length: {max: 1}}} }")
+        .expect("should deser");
+    let ret = ser_rule.get_matcher(env);
+    assert!(matches!(
+      ret,
+      Err(RuleCoreError::UndefinedMetaVar(name, "constraints")) if name == "B"
+    ));
+  }
+
   fn get_rewriters() -> GlobalRules<TypeScript> {
     // NOTE: initialize a DeserializeEnv here is not 100% correct
     // it does not inherit global rules or local rules
@@ -12,6 +12,7 @@ use std::collections::HashMap;
 use thiserror::Error;
 
 use transformation::Transformation as Trans;
+pub(crate) use transformation::{parse_meta_var, Convert};
 pub type Transformation = Trans<String>;
 
 #[derive(Debug, Error)]
@@ -22,6 +23,8 @@ pub enum TransformError {
   AlreadyDefined(String),
   #[error("source `{0}` should be $-prefixed.")]
   MalformedVar(String),
+  #[error("`replace` contains invalid regex.")]
+  InvalidRegex(#[from] regex::Error),
 }
 
 pub struct Transform {
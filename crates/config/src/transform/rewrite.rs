@@ -2,9 +2,9 @@ use super::Ctx;
 use super::{transformation::parse_meta_var, TransformError};
 use crate::rule_core::RuleCore;
 
-use ast_grep_core::meta_var::MetaVariable;
+use ast_grep_core::meta_var::{MetaVarEnv, MetaVariable};
 use ast_grep_core::source::{Content, Edit};
-use ast_grep_core::{Doc, Language, Node, NodeMatch};
+use ast_grep_core::{AstGrep, Doc, Language, Node, NodeMatch};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -16,8 +16,16 @@ pub struct Rewrite<T> {
   // do we need this?
   // sort_by: Option<String>,
   pub(super) join_by: Option<String>,
+  /// apply rewriters again to the rewritten code until nothing changes
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub(super) reapply: bool,
+  /// maximum rounds of rewriting when `reapply` is true, defaults to 10
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub(super) max_depth: Option<usize>,
 }
 
+const DEFAULT_MAX_DEPTH: usize = 10;
+
 fn get_nodes_from_env<'b, D: Doc>(var: &MetaVariable, ctx: &Ctx<'_, 'b, D>) -> Vec<Node<'b, D>> {
   match var {
     MetaVariable::MultiCapture(n) => ctx.env.get_multiple_matches(n),
@@ -38,6 +46,8 @@ impl Rewrite<String> {
       source,
       rewriters: self.rewriters.clone(),
       join_by: self.join_by.clone(),
+      reapply: self.reapply,
+      max_depth: self.max_depth,
     })
   }
 }
@@ -57,7 +67,8 @@ impl Rewrite<MetaVariable> {
       .iter()
       .filter_map(|id| rewriters.get(id)) // NOTE: rewriter must be defined
       .collect();
-    let edits = find_and_make_edits(nodes, &rules, ctx);
+    let lang = nodes[0].lang().clone();
+    let edits = find_and_make_edits(nodes, &rules, ctx.enclosing_env);
     let rewritten = if let Some(joiner) = &self.join_by {
       let mut ret = vec![];
       let mut edits = edits.into_iter();
@@ -82,40 +93,75 @@ impl Rewrite<MetaVariable> {
     } else {
       make_edit::<D>(bytes, edits, start)
     };
-    Some(D::Source::encode_bytes(&rewritten).to_string())
+    let mut rewritten = D::Source::encode_bytes(&rewritten).to_string();
+    if self.reapply {
+      let max_depth = self.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+      // the first round is already done above
+      for _ in 1..max_depth {
+        let Some(next) = reapply_rewriters::<D>(&rewritten, &lang, &rules, ctx.enclosing_env)
+        else {
+          break;
+        };
+        rewritten = next;
+      }
+    }
+    Some(rewritten)
+  }
+}
+
+/// Parse the rewritten code and apply rewriters to it again.
+/// Returns None if no rewriter changes the code, i.e. a fixpoint is reached.
+fn reapply_rewriters<D: Doc>(
+  src: &str,
+  lang: &D::Lang,
+  rules: &[&RuleCore<D::Lang>],
+  enclosing_env: &MetaVarEnv<'_, D>,
+) -> Option<String> {
+  let root = AstGrep::doc(D::from_str(src, lang.clone()));
+  let edits = replace_one(root.root(), rules, enclosing_env);
+  if edits.is_empty() {
+    return None;
   }
+  let old = D::Source::decode_str(src);
+  let new = make_edit::<D>(&old, edits, 0);
+  let new = D::Source::encode_bytes(&new).to_string();
+  (new != src).then_some(new)
 }
 
 type Bytes<D> = [<<D as Doc>::Source as Content>::Underlying];
 fn find_and_make_edits<'n, D: Doc>(
   nodes: Vec<Node<'n, D>>,
   rules: &[&RuleCore<D::Lang>],
-  ctx: &Ctx<'_, 'n, D>,
+  enclosing_env: &MetaVarEnv<'n, D>,
 ) -> Vec<Edit<D::Source>> {
   nodes
     .into_iter()
-    .flat_map(|n| replace_one(n, rules, ctx))
+    .flat_map(|n| replace_one(n, rules, enclosing_env))
     .collect()
 }
 
 fn replace_one<'n, D: Doc>(
   node: Node<'n, D>,
   rules: &[&RuleCore<D::Lang>],
-  ctx: &Ctx<'_, 'n, D>,
+  enclosing_env: &MetaVarEnv<'n, D>,
 ) -> Vec<Edit<D::Source>> {
   let mut edits = vec![];
   for child in node.dfs() {
     for rule in rules {
-      let mut env = std::borrow::Cow::Borrowed(ctx.enclosing_env);
+      let mut env = std::borrow::Cow::Borrowed(enclosing_env);
       // NOTE: we inherit meta_var_env from enclosing rule
       // but match env will NOT inherited recursively!
       // e.g. $B is matched in parent linter and it is inherited.
       // $C is matched in rewriter but is NOT inherited in recursive rewriter
       // this is to enable recursive rewriter to match sub nodes
       // in future, we can use the explict `expose` to control env inheritance
-      if let Some(n) = rule.do_match(child.clone(), &mut env, Some(ctx.enclosing_env)) {
+      if let Some(n) = rule.do_match(child.clone(), &mut env, Some(enclosing_env)) {
         let nm = NodeMatch::new(n, env.into_owned());
-        edits.push(nm.make_edit(rule, rule.fixer.as_ref().expect("rewriter must have fix")));
+        // rewriter must have fix, but none may be applicable due to conditions
+        let Some(fixer) = rule.get_fixer(&nm) else {
+          continue;
+        };
+        edits.push(nm.make_parsed_edit(rule, fixer));
         // stop at first fix, skip duplicate fix
         break;
       }
@@ -196,6 +242,8 @@ mod test {
       source: "$A".into(),
       rewriters: str_vec!["rewrite"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     let rewriters = make_rewriters(&[("rewrite", "{rule: {kind: number}, fix: '810'}")]);
     let ret = apply_transformation(rewrite, "log(t(1, 2, 3))", "log($A)", rewriters);
@@ -208,6 +256,8 @@ mod test {
       source: "$A".into(),
       rewriters: str_vec!["re1", "re2"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     let rewriters = make_rewriters(&[
       ("re1", "{rule: {regex: '^1$'}, fix: '810'}"),
@@ -223,6 +273,8 @@ mod test {
       source: "$A".into(),
       rewriters: str_vec!["re1"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     let rewriters = make_rewriters(&[
       ("ignored", "{rule: {regex: '^2$'}, fix: '1919'}"),
@@ -238,6 +290,8 @@ mod test {
       source: "$A".into(),
       rewriters: str_vec!["re2", "re1"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     // first match wins the rewrite
     let rewriters = make_rewriters(&[
@@ -254,6 +308,8 @@ mod test {
       source: "$A".into(),
       rewriters: str_vec!["re1", "re2"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     // parent node wins fix, even if rule comes later
     let rewriters = make_rewriters(&[
@@ -270,6 +326,8 @@ mod test {
       source: "$A".into(),
       rewriters: str_vec!["re1"],
       join_by: Some(" + ".into()),
+      reapply: false,
+      max_depth: None,
     };
     let rewriters = make_rewriters(&[("re1", "{rule: {kind: number}, fix: '810'}")]);
     let ret = apply_transformation(rewrite, "log(t(1, 2, 3))", "log($A)", rewriters);
@@ -282,6 +340,8 @@ mod test {
       source: "$A".into(),
       rewriters: str_vec!["re1"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     let rule = r#"
 rule: {pattern: '[$$$C]'}
@@ -303,6 +363,8 @@ fix: $D
       source: "$A".into(),
       rewriters: str_vec!["re"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     let rewriters = make_rewriters(&[("re", "{rule: {pattern: $C}, fix: '123'}")]);
     let ret = apply_transformation(rewrite.clone(), "[1, 2]", "[$A, $B]", rewriters.clone());
@@ -320,6 +382,8 @@ fix: $D
       source: "$A".into(),
       rewriters: str_vec!["re"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     let rewriters = make_rewriters(&[("re", "{rule: {pattern: $B}, fix: '123'}")]);
     let ret = compute_rewritten("[1, 2]", "[$B, $C]", rewrite, rewriters);
@@ -332,6 +396,8 @@ fix: $D
       source: "$A".into(),
       rewriters: str_vec!["re"],
       join_by: None,
+      reapply: false,
+      max_depth: None,
     };
     let mut vars = HashSet::new();
     vars.insert("C");
@@ -341,6 +407,28 @@ fix: $D
     assert_eq!(ret, "1 == 2");
   }
 
+  #[test]
+  fn test_reapply_rewriters() {
+    let mut rewrite = Rewrite {
+      source: "$A".into(),
+      rewriters: str_vec!["flat"],
+      join_by: None,
+      reapply: false,
+      max_depth: None,
+    };
+    // the inner call is only exposed after the outer one is rewritten
+    let rewriters = make_rewriters(&[("flat", "{rule: {pattern: 'f(f($B))'}, fix: 'f($B)'}")]);
+    let src = "log(f(f(f(f(1)))))";
+    let ret = apply_transformation(rewrite.clone(), src, "log($A)", rewriters.clone());
+    assert_eq!(ret, "f(f(f(1)))");
+    rewrite.reapply = true;
+    let ret = apply_transformation(rewrite.clone(), src, "log($A)", rewriters.clone());
+    assert_eq!(ret, "f(1)");
+    rewrite.max_depth = Some(2);
+    let ret = apply_transformation(rewrite, src, "log($A)", rewriters);
+    assert_eq!(ret, "f(f(1))");
+  }
+
   fn compute_rewritten(
     src: &str,
     pat: &str,
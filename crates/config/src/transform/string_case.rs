@@ -22,24 +22,65 @@ pub enum StringCase {
   SnakeCase,
   KebabCase,
   PascalCase,
+  DotCase,
+  TitleCase,
 }
 
 use StringCase::*;
 
 impl StringCase {
-  pub fn apply(&self, s: &str, seps: Option<&[Separator]>) -> String {
+  /// Convert string to the case. Words matching `acronyms` are kept in their configured
+  /// spelling when capitalized, e.g. `http_server` -> `HTTPServer` instead of `HttpServer`.
+  pub fn apply(&self, s: &str, seps: Option<&[Separator]>, acronyms: &[String]) -> String {
+    let words = split(s, seps).flat_map(|w| split_acronyms(w, acronyms));
     match &self {
       LowerCase => s.to_lowercase(),
       UpperCase => s.to_uppercase(),
       Capitalize => capitalize(s),
-      CamelCase => join_camel_case(split(s, seps)),
-      SnakeCase => join(split(s, seps), '_'),
-      KebabCase => join(split(s, seps), '-'),
-      PascalCase => split(s, seps).map(capitalize).collect(),
+      CamelCase => join_camel_case(words, acronyms),
+      SnakeCase => join(words, '_'),
+      KebabCase => join(words, '-'),
+      DotCase => join(words, '.'),
+      PascalCase => words.map(|w| capitalize_word(w, acronyms)).collect(),
+      TitleCase => {
+        let words: Vec<_> = words.map(|w| capitalize_word(w, acronyms)).collect();
+        words.join(" ")
+      }
     }
   }
 }
 
+fn find_acronym<'a>(word: &str, acronyms: &'a [String]) -> Option<&'a String> {
+  acronyms.iter().find(|a| a.eq_ignore_ascii_case(word))
+}
+
+/// Capitalize a word, or use its configured spelling if it is an acronym.
+fn capitalize_word(word: &str, acronyms: &[String]) -> String {
+  match find_acronym(word, acronyms) {
+    Some(acronym) => acronym.clone(),
+    None => capitalize(word),
+  }
+}
+
+/// Split consecutive uppercase letters by configured acronyms, e.g. `XMLHTTP` -> `XML`, `HTTP`.
+/// Case change alone cannot tell where one acronym ends and another starts.
+fn split_acronyms<'a>(word: &'a str, acronyms: &[String]) -> Vec<&'a str> {
+  if word.chars().any(char::is_lowercase) {
+    return vec![word];
+  }
+  let mut words = vec![];
+  let mut rest = word;
+  while let Some(acronym) = acronyms.iter().find(|a| {
+    let prefix = rest.get(..a.len());
+    !a.is_empty() && a.len() < rest.len() && prefix.map_or(false, |p| p.eq_ignore_ascii_case(a))
+  }) {
+    words.push(&rest[..acronym.len()]);
+    rest = &rest[acronym.len()..];
+  }
+  words.push(rest);
+  words
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Separator to split string. e.g. `user_accountName` -> `user`, `accountName`
@@ -206,7 +247,7 @@ where
   result
 }
 
-fn join_camel_case<'a, I>(words: I) -> String
+fn join_camel_case<'a, I>(words: I, acronyms: &[String]) -> String
 where
   I: Iterator<Item = &'a str>,
 {
@@ -215,7 +256,7 @@ where
     if i == 0 {
       result.push_str(&word.to_lowercase());
     } else {
-      result.push_str(&capitalize(word));
+      result.push_str(&capitalize_word(word, acronyms));
     }
   }
   result
@@ -227,9 +268,9 @@ mod test {
 
   #[test]
   fn test_case_conversions() {
-    assert_eq!(StringCase::LowerCase.apply("aBc", None), "abc");
-    assert_eq!(StringCase::UpperCase.apply("aBc", None), "ABC");
-    assert_eq!(StringCase::Capitalize.apply("aBc", None), "ABc");
+    assert_eq!(StringCase::LowerCase.apply("aBc", None, &[]), "abc");
+    assert_eq!(StringCase::UpperCase.apply("aBc", None, &[]), "ABC");
+    assert_eq!(StringCase::Capitalize.apply("aBc", None, &[]), "ABc");
   }
   const CAMEL: &str = "camelsLiveInTheDesert";
   const SNAKE: &str = "snakes_live_in_forests";
@@ -271,7 +312,7 @@ mod test {
   }
 
   fn assert_format(fmt: StringCase, src: &str, expected: &str) {
-    assert_eq!(fmt.apply(src, None), expected)
+    assert_eq!(fmt.apply(src, None, &[]), expected)
   }
 
   #[test]
@@ -280,5 +321,23 @@ mod test {
     assert_format(KebabCase, CAMEL, "camels-live-in-the-desert");
     assert_format(PascalCase, KEBAB, "KebabIsADeliciousFood");
     assert_format(PascalCase, SNAKE, "SnakesLiveInForests");
+    assert_format(DotCase, PASCAL, "pascal.is.a.cool.guy");
+    assert_format(TitleCase, SNAKE, "Snakes Live In Forests");
+    assert_format(SnakeCase, "HTTPServer", "http_server");
+  }
+
+  fn assert_acronym(fmt: StringCase, src: &str, expected: &str) {
+    let acronyms = ["HTTP".to_string(), "XML".to_string(), "ID".to_string()];
+    assert_eq!(fmt.apply(src, None, &acronyms), expected)
+  }
+
+  #[test]
+  fn test_acronyms() {
+    assert_acronym(PascalCase, "http_server", "HTTPServer");
+    assert_acronym(CamelCase, "http_server_id", "httpServerID");
+    assert_acronym(TitleCase, "xml-http-request", "XML HTTP Request");
+    assert_acronym(SnakeCase, "XMLHTTPRequest", "xml_http_request");
+    assert_acronym(KebabCase, "userID", "user-id");
+    assert_format(SnakeCase, "XMLHTTPRequest", "xmlhttp_request");
   }
 }
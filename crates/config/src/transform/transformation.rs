@@ -1,6 +1,6 @@
 use super::rewrite::Rewrite;
 use super::{string_case, Ctx, TransformError};
-use ast_grep_core::meta_var::MetaVariable;
+use ast_grep_core::meta_var::{MetaVarEnv, MetaVariable};
 use ast_grep_core::source::Content;
 use ast_grep_core::{Doc, Language};
 
@@ -10,6 +10,8 @@ use serde::{Deserialize, Serialize};
 
 use string_case::{Separator, StringCase};
 
+use std::collections::{HashMap, HashSet};
+
 fn get_text_from_env<D: Doc>(var: &MetaVariable, ctx: &mut Ctx<D>) -> Option<String> {
   // TODO: check if topological sort has resolved transform dependency
   let bytes = ctx.env.get_var_bytes(var)?;
@@ -67,9 +69,12 @@ fn resolve_char(opt: &Option<i32>, dft: i32, len: i32) -> usize {
 pub struct Replace<T> {
   /// source meta variable to be transformed
   source: T,
-  /// a regex to find substring to be replaced
+  /// a regex to find substring to be replaced, also accepted as `pattern`
+  #[serde(alias = "pattern")]
   replace: String,
-  /// the replacement string
+  /// the replacement string, also accepted as `replacement`.
+  /// It can reference capture groups by `$1` or `$name`.
+  #[serde(alias = "replacement")]
   by: String,
 }
 impl Replace<MetaVariable> {
@@ -80,6 +85,34 @@ impl Replace<MetaVariable> {
   }
 }
 
+/// Wrap numbered capture groups in braces so `$1_suffix` is group 1 followed by `_suffix`.
+/// Otherwise regex reads it as a group named `1_suffix`, which never matches.
+fn brace_group_refs(by: &str) -> String {
+  let mut ret = String::with_capacity(by.len());
+  let mut rest = by;
+  while let Some(i) = rest.find('$') {
+    ret.push_str(&rest[..i]);
+    let after = &rest[i + 1..];
+    // $$ is an escaped dollar sign
+    if let Some(after) = after.strip_prefix('$') {
+      ret.push_str("$$");
+      rest = after;
+      continue;
+    }
+    let digits = after
+      .find(|c: char| !c.is_ascii_digit())
+      .unwrap_or(after.len());
+    if digits == 0 {
+      ret.push('$');
+    } else {
+      ret.push_str(&format!("${{{}}}", &after[..digits]));
+    }
+    rest = &after[digits..];
+  }
+  ret.push_str(rest);
+  ret
+}
+
 /// Converts the source meta variable's text content to a specified case format.
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -90,16 +123,119 @@ pub struct Convert<T> {
   to_case: StringCase,
   /// optional separators to specify how to separate word
   separated_by: Option<Vec<Separator>>,
+  /// optional acronyms to keep in their spelling, e.g. `HTTP` in `HTTPServer`
+  acronyms: Option<Vec<String>>,
 }
 impl Convert<MetaVariable> {
+  /// Convert with default separators, e.g. for `${A|upperCase}` in messages.
+  pub(crate) fn new(source: MetaVariable, to_case: StringCase) -> Self {
+    Self {
+      source,
+      to_case,
+      separated_by: None,
+      acronyms: None,
+    }
+  }
+
+  /// Convert the text of a matched or transformed meta variable in the env.
+  pub(crate) fn apply<D: Doc>(&self, env: &MetaVarEnv<D>) -> Option<String> {
+    let mut env = env.clone();
+    let ctx = &mut Ctx {
+      env: &mut env,
+      rewriters: &HashMap::new(),
+      enclosing_env: &MetaVarEnv::new(),
+    };
+    self.compute(ctx)
+  }
+
+  fn compute<D: Doc>(&self, ctx: &mut Ctx<D>) -> Option<String> {
+    let text = get_text_from_env(&self.source, ctx)?;
+    let acronyms = self.acronyms.as_deref().unwrap_or_default();
+    Some(
+      self
+        .to_case
+        .apply(&text, self.separated_by.as_deref(), acronyms),
+    )
+  }
+}
+
+/// Joins the text of nodes captured by a multi meta variable, e.g. `$$$ARGS`.
+/// Unnamed nodes like commas are skipped.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Join<T> {
+  /// source meta variable to be transformed
+  source: T,
+  /// the string inserted between joined items
+  separator: String,
+  /// sort items alphabetically before joining
+  #[serde(default)]
+  sort: bool,
+  /// remove duplicate items, keeping the first occurrence
+  #[serde(default)]
+  unique: bool,
+}
+impl Join<MetaVariable> {
+  fn compute<D: Doc>(&self, ctx: &mut Ctx<D>) -> Option<String> {
+    let items = match &self.source {
+      MetaVariable::MultiCapture(name) => ctx
+        .env
+        .get_multiple_matches(name)
+        .iter()
+        .filter(|n| n.is_named())
+        .map(|n| n.text().to_string())
+        .collect(),
+      var => vec![get_text_from_env(var, ctx)?],
+    };
+    Some(reshape(items, self.sort, self.unique).join(&self.separator))
+  }
+}
+
+/// Splits the source meta variable's text content by a separator and joins the items again.
+/// Items are trimmed and empty ones are dropped.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Split<T> {
+  /// source meta variable to be transformed
+  source: T,
+  /// the string to split text content by
+  separator: String,
+  /// optional string to join items, defaults to the separator
+  join_by: Option<String>,
+  /// sort items alphabetically before joining
+  #[serde(default)]
+  sort: bool,
+  /// remove duplicate items, keeping the first occurrence
+  #[serde(default)]
+  unique: bool,
+}
+impl Split<MetaVariable> {
   fn compute<D: Doc>(&self, ctx: &mut Ctx<D>) -> Option<String> {
     let text = get_text_from_env(&self.source, ctx)?;
-    Some(self.to_case.apply(&text, self.separated_by.as_deref()))
+    let items = text
+      .split(self.separator.as_str())
+      .map(str::trim)
+      .filter(|s| !s.is_empty())
+      .map(String::from)
+      .collect();
+    let joiner = self.join_by.as_ref().unwrap_or(&self.separator);
+    Some(reshape(items, self.sort, self.unique).join(joiner))
   }
 }
 
+fn reshape(mut items: Vec<String>, sort: bool, unique: bool) -> Vec<String> {
+  if unique {
+    let mut seen = HashSet::new();
+    items.retain(|item| seen.insert(item.clone()));
+  }
+  if sort {
+    items.sort();
+  }
+  items
+}
+
 /// Represents a transformation that can be applied to a matched AST node.
-/// Available transformations are `substring`, `replace` and `convert`.
+/// Available transformations are `substring`, `replace`, `convert`, `join` and `split`.
 #[derive(Serialize, Deserialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum Transformation<T> {
@@ -107,6 +243,8 @@ pub enum Transformation<T> {
   Replace(Replace<T>),
   Convert(Convert<T>),
   Rewrite(Rewrite<T>),
+  Join(Join<T>),
+  Split(Split<T>),
 }
 
 impl<T> Transformation<T> {
@@ -117,6 +255,20 @@ impl<T> Transformation<T> {
       T::Substring(s) => &s.source,
       T::Convert(c) => &c.source,
       T::Rewrite(r) => &r.source,
+      T::Join(j) => &j.source,
+      T::Split(s) => &s.source,
+    }
+  }
+
+  pub fn used_rewriters(&self) -> &[String] {
+    use Transformation as T;
+    match self {
+      T::Replace(_) => &[],
+      T::Substring(_) => &[],
+      T::Convert(_) => &[],
+      T::Join(_) => &[],
+      T::Split(_) => &[],
+      T::Rewrite(r) => &r.rewriters,
     }
   }
 }
@@ -140,11 +292,15 @@ impl Transformation<String> {
   ) -> Result<Transformation<MetaVariable>, TransformError> {
     use Transformation as T;
     Ok(match self {
-      T::Replace(r) => T::Replace(Replace {
-        source: parse_meta_var(&r.source, lang)?,
-        replace: r.replace.clone(),
-        by: r.by.clone(),
-      }),
+      T::Replace(r) => {
+        // validate regex here so compute can safely unwrap it
+        Regex::new(&r.replace)?;
+        T::Replace(Replace {
+          source: parse_meta_var(&r.source, lang)?,
+          replace: r.replace.clone(),
+          by: brace_group_refs(&r.by),
+        })
+      }
       T::Substring(s) => T::Substring(Substring {
         source: parse_meta_var(&s.source, lang)?,
         start_char: s.start_char,
@@ -154,8 +310,22 @@ impl Transformation<String> {
         source: parse_meta_var(&c.source, lang)?,
         to_case: c.to_case,
         separated_by: c.separated_by.clone(),
+        acronyms: c.acronyms.clone(),
       }),
       T::Rewrite(r) => T::Rewrite(r.parse(lang)?),
+      T::Join(j) => T::Join(Join {
+        source: parse_meta_var(&j.source, lang)?,
+        separator: j.separator.clone(),
+        sort: j.sort,
+        unique: j.unique,
+      }),
+      T::Split(s) => T::Split(Split {
+        source: parse_meta_var(&s.source, lang)?,
+        separator: s.separator.clone(),
+        join_by: s.join_by.clone(),
+        sort: s.sort,
+        unique: s.unique,
+      }),
     })
   }
 
@@ -187,16 +357,8 @@ impl Transformation<MetaVariable> {
       T::Substring(s) => s.compute(ctx),
       T::Convert(c) => c.compute(ctx),
       T::Rewrite(r) => r.compute(ctx),
-    }
-  }
-
-  pub fn used_rewriters(&self) -> &[String] {
-    use Transformation as T;
-    match self {
-      T::Replace(_) => &[],
-      T::Substring(_) => &[],
-      T::Convert(_) => &[],
-      T::Rewrite(r) => &r.rewriters,
+      T::Join(j) => j.compute(ctx),
+      T::Split(s) => s.compute(ctx),
     }
   }
 }
@@ -295,6 +457,38 @@ mod test {
     Ok(())
   }
 
+  #[test]
+  fn test_replace_capture_group() -> R {
+    let trans = parse(
+      r#"
+      replace:
+        source: "$A"
+        pattern: (?<num>\d)(\d+)
+        replacement: "${num}_$2_suffix"
+    "#,
+    )?;
+    let actual = get_transformed("let a = 123", "let a= $A", &trans).ok_or(())?;
+    assert_eq!(actual, "1_23_suffix");
+    Ok(())
+  }
+
+  #[test]
+  fn test_brace_group_refs() {
+    assert_eq!(brace_group_refs("$1_suffix"), "${1}_suffix");
+    assert_eq!(brace_group_refs("$12a$name"), "${12}a$name");
+    assert_eq!(brace_group_refs("$$1 ${1}"), "$$1 ${1}");
+    assert_eq!(brace_group_refs("cost $"), "cost $");
+  }
+
+  #[test]
+  fn test_invalid_regex() -> R {
+    let trans = parse(r#"replace: { source: $A, replace: "(", by: b }"#)?;
+    match trans.parse(&TypeScript::Tsx) {
+      Err(TransformError::InvalidRegex(_)) => Ok(()),
+      _ => panic!("should be invalid regex"),
+    }
+  }
+
   #[test]
   fn test_wrong_rule() {
     let parsed = parse(
@@ -436,6 +630,28 @@ mod test {
     Ok(())
   }
 
+  #[test]
+  fn test_join() -> R {
+    let trans = parse("join: { source: $$$ARGS, separator: ' | ' }")?;
+    let actual = get_transformed("foo(b, a, b)", "foo($$$ARGS)", &trans).ok_or(())?;
+    assert_eq!(actual, "b | a | b");
+    let trans = parse("join: { source: $$$ARGS, separator: ', ', sort: true, unique: true }")?;
+    let actual = get_transformed("foo(b, a, b)", "foo($$$ARGS)", &trans).ok_or(())?;
+    assert_eq!(actual, "a, b");
+    Ok(())
+  }
+
+  #[test]
+  fn test_split() -> R {
+    let trans = parse("split: { source: $A, separator: '+', unique: true }")?;
+    let actual = get_transformed("let a = c + b + a + c", "let a = $A", &trans).ok_or(())?;
+    assert_eq!(actual, "c+b+a");
+    let trans = parse("split: { source: $A, separator: '+', joinBy: ' + ', sort: true }")?;
+    let actual = get_transformed("let a = c + b + a + c", "let a = $A", &trans).ok_or(())?;
+    assert_eq!(actual, "a + b + c + c");
+    Ok(())
+  }
+
   #[test]
   fn test_transform_indentation_with_insertion() -> R {
     let src = "
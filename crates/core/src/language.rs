@@ -56,6 +56,12 @@ pub trait Language: Clone {
     extract_meta_var(source, self.expando_char())
   }
 
+  /// Returns the opening and closing delimiters of a comment that fits in one line,
+  /// used to insert suppression comments. None if the comment syntax is unknown.
+  fn comment_delimiters(&self) -> Option<(&'static str, &'static str)> {
+    None
+  }
+
   fn injectable_languages(&self) -> Option<&'static [&'static str]> {
     None
   }
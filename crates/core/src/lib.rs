@@ -54,7 +54,12 @@ impl<D: Doc> AstGrep<D> {
     pattern: M,
     replacer: R,
   ) -> Result<bool, TSParseError> {
-    if let Some(edit) = self.root().replace(pattern, replacer) {
+    // the doc holds the parsed text, so the edit is not mapped to the original source
+    let root = self.root();
+    let edit = pattern
+      .find_node(root)
+      .map(|matched| matched.make_parsed_edit(&pattern, &replacer));
+    if let Some(edit) = edit {
       self.edit(edit)?;
       Ok(true)
     } else {
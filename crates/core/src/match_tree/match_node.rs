@@ -10,9 +10,19 @@ pub(super) fn match_node_impl<'tree, D: Doc>(
   candidate: &Node<'tree, D>,
   agg: &mut impl Aggregator<'tree, D>,
   strictness: &MatchStrictness,
+  allow_error: bool,
 ) -> MatchOneNode {
   use PatternNode as P;
   match &goal {
+    // missing node has no text, only kind is compared
+    P::Terminal { kind_id, .. }
+      if allow_error && *kind_id == candidate.kind_id() && candidate.get_ts_node().is_missing() =>
+    {
+      match agg.match_terminal(candidate) {
+        Some(()) => MatchOneNode::MatchedBoth,
+        None => MatchOneNode::NoMatch,
+      }
+    }
     // leaf = without named children
     P::Terminal {
       text,
@@ -35,8 +45,11 @@ pub(super) fn match_node_impl<'tree, D: Doc>(
     P::Internal {
       kind_id, children, ..
     } if *kind_id == candidate.kind_id() => {
-      let cand_children = candidate.children();
-      match match_nodes_impl_recursive(children, cand_children, agg, strictness) {
+      // ERROR nodes are skipped so that broken code does not hide matches
+      let cand_children = candidate
+        .children()
+        .filter(|n| !(allow_error && n.is_error()));
+      match match_nodes_impl_recursive(children, cand_children, agg, strictness, allow_error) {
         Some(()) => MatchOneNode::MatchedBoth,
         None => MatchOneNode::NoMatch,
       }
@@ -50,12 +63,19 @@ fn match_nodes_impl_recursive<'tree, D: Doc + 'tree>(
   candidates: impl Iterator<Item = Node<'tree, D>>,
   agg: &mut impl Aggregator<'tree, D>,
   strictness: &MatchStrictness,
+  allow_error: bool,
 ) -> Option<()> {
   let mut goal_children = goals.iter().peekable();
   let mut cand_children = candidates.peekable();
   cand_children.peek()?;
   loop {
-    match may_match_ellipsis_impl(&mut goal_children, &mut cand_children, agg, strictness)? {
+    match may_match_ellipsis_impl(
+      &mut goal_children,
+      &mut cand_children,
+      agg,
+      strictness,
+      allow_error,
+    )? {
       ControlFlow::Return => return Some(()),
       ControlFlow::Continue => continue,
       ControlFlow::Fallthrough => (),
@@ -65,6 +85,7 @@ fn match_nodes_impl_recursive<'tree, D: Doc + 'tree>(
       &mut cand_children,
       agg,
       strictness,
+      allow_error,
     )? {
       ControlFlow::Return => return Some(()),
       ControlFlow::Continue => continue,
@@ -94,6 +115,7 @@ fn may_match_ellipsis_impl<'p, 't: 'p, D: Doc + 't>(
   cand_children: &mut Peekable<impl Iterator<Item = Node<'t, D>>>,
   agg: &mut impl Aggregator<'t, D>,
   strictness: &MatchStrictness,
+  allow_error: bool,
 ) -> Option<ControlFlow> {
   let Some(curr_node) = goal_children.peek() else {
     // in rare case, an internal node's children is empty
@@ -146,6 +168,7 @@ fn may_match_ellipsis_impl<'p, 't: 'p, D: Doc + 't>(
         cand_children.peek().unwrap(),
         agg,
         strictness,
+        allow_error,
       ),
       MatchOneNode::MatchedBoth
     ) {
@@ -169,6 +192,7 @@ fn match_single_node_while_skip_trivial<'p, 't: 'p, D: Doc + 't>(
   cand_children: &mut Peekable<impl Iterator<Item = Node<'t, D>>>,
   agg: &mut impl Aggregator<'t, D>,
   strictness: &MatchStrictness,
+  allow_error: bool,
 ) -> Option<ControlFlow> {
   loop {
     let Some(cand) = cand_children.peek() else {
@@ -178,8 +202,15 @@ fn match_single_node_while_skip_trivial<'p, 't: 'p, D: Doc + 't>(
         .should_skip_goal(goal_children)
         .then_some(ControlFlow::Fallthrough);
     };
+    let skip_missing = allow_error && cand.get_ts_node().is_missing();
     // try match goal node with candidate node
-    match match_node_impl(goal_children.peek().unwrap(), cand, agg, strictness) {
+    match match_node_impl(
+      goal_children.peek().unwrap(),
+      cand,
+      agg,
+      strictness,
+      allow_error,
+    ) {
       MatchOneNode::MatchedBoth => return Some(ControlFlow::Fallthrough),
       MatchOneNode::SkipGoal => {
         goal_children.next();
@@ -198,6 +229,10 @@ fn match_single_node_while_skip_trivial<'p, 't: 'p, D: Doc + 't>(
       MatchOneNode::SkipCandidate => {
         cand_children.next();
       }
+      // missing nodes are inserted by error recovery, e.g. after a trailing comma
+      MatchOneNode::NoMatch if skip_missing => {
+        cand_children.next();
+      }
       // unmatched significant node
       MatchOneNode::NoMatch => return None,
     }
@@ -237,13 +272,21 @@ mod test {
   use crate::{meta_var::MetaVarEnv, Matcher, Pattern, Root};
   use std::borrow::Cow;
   fn match_tree(p: &str, n: &str, strictness: MatchStrictness) -> MatchOneNode {
+    match_tree_impl(p, n, strictness, false)
+  }
+  fn match_tree_impl(
+    p: &str,
+    n: &str,
+    strictness: MatchStrictness,
+    allow_error: bool,
+  ) -> MatchOneNode {
     let pattern = Pattern::str(p, Tsx);
     let kind = pattern.potential_kinds().expect("should have kind");
     let kind = KindMatcher::from_id(kind.into_iter().next().expect("should have kind") as u16);
     let n = Root::str(n, Tsx);
     let n = n.root().find(kind).expect("should find");
     let mut env = Cow::Owned(MetaVarEnv::new());
-    match_node_impl(&pattern.node, &n, &mut env, &strictness)
+    match_node_impl(&pattern.node, &n, &mut env, &strictness, allow_error)
   }
   fn matched(p: &str, n: &str, strictness: MatchStrictness) {
     let ret = match_tree(p, n, strictness);
@@ -49,7 +49,13 @@ pub fn match_end_non_recursive<D: Doc>(
   candidate: Node<D>,
 ) -> Option<usize> {
   let mut end = ComputeEnd(0);
-  match match_node_impl(&goal.node, &candidate, &mut end, &goal.strictness) {
+  match match_node_impl(
+    &goal.node,
+    &candidate,
+    &mut end,
+    &goal.strictness,
+    goal.allow_error_nodes,
+  ) {
     MatchOneNode::MatchedBoth => Some(end.0),
     _ => None,
   }
@@ -117,7 +123,13 @@ pub fn match_node_non_recursive<'tree, D: Doc>(
   candidate: Node<'tree, D>,
   env: &mut Cow<MetaVarEnv<'tree, D>>,
 ) -> Option<Node<'tree, D>> {
-  match match_node_impl(&goal.node, &candidate, env, &goal.strictness) {
+  match match_node_impl(
+    &goal.node,
+    &candidate,
+    env,
+    &goal.strictness,
+    goal.allow_error_nodes,
+  ) {
     MatchOneNode::MatchedBoth => Some(candidate),
     _ => None,
   }
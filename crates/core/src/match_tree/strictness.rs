@@ -4,7 +4,7 @@ use crate::{Doc, Node};
 use std::iter::Peekable;
 use std::str::FromStr;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum MatchStrictness {
   Cst,       // all nodes are matched
   Smart,     // all nodes except source trivial nodes are matched.
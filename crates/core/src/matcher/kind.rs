@@ -24,39 +24,65 @@ pub enum KindMatcherError {
 
 #[derive(Clone)]
 pub struct KindMatcher<L: Language> {
-  kind: KindId,
+  kinds: BitSet,
   lang: PhantomData<L>,
 }
 
 impl<L: Language> KindMatcher<L> {
   pub fn new(node_kind: &str, lang: L) -> Self {
-    Self {
-      kind: lang
-        .get_ts_language()
-        .id_for_node_kind(node_kind, /*named*/ true),
-      lang: PhantomData,
-    }
+    let kind = lang
+      .get_ts_language()
+      .id_for_node_kind(node_kind, /*named*/ true);
+    Self::from_id(kind)
   }
 
+  /// Creates a matcher from a kind expression. Besides a plain kind name,
+  /// it accepts alternation like `call_expression | new_expression`
+  /// and glob like `*_statement`, which is expanded to all named kinds matching it.
   pub fn try_new(node_kind: &str, lang: L) -> Result<Self, KindMatcherError> {
-    let s = Self::new(node_kind, lang);
-    if s.is_invalid() {
-      Err(KindMatcherError::InvalidKindName(node_kind.into()))
-    } else {
-      Ok(s)
+    let ts_lang = lang.get_ts_language();
+    let mut kinds = BitSet::new();
+    for name in node_kind.split('|').map(str::trim) {
+      let invalid = || KindMatcherError::InvalidKindName(name.into());
+      if !name.contains('*') {
+        let kind = ts_lang.id_for_node_kind(name, /*named*/ true);
+        if kind == TS_BUILTIN_SYM_END {
+          return Err(invalid());
+        }
+        kinds.insert(kind.into());
+        continue;
+      }
+      let count = kinds.len();
+      for id in 0..ts_lang.node_kind_count() as KindId {
+        if !ts_lang.node_kind_is_named(id) {
+          continue;
+        }
+        if matches!(ts_lang.node_kind_for_id(id), Some(k) if glob_match(name, &k)) {
+          kinds.insert(id.into());
+        }
+      }
+      if kinds.len() == count {
+        return Err(invalid());
+      }
     }
+    Ok(Self {
+      kinds,
+      lang: PhantomData,
+    })
   }
 
   pub fn from_id(kind: KindId) -> Self {
+    let mut kinds = BitSet::new();
+    kinds.insert(kind.into());
     Self {
-      kind,
+      kinds,
       lang: PhantomData,
     }
   }
 
   /// Whether the kind matcher contains undefined tree-sitter kind.
   pub fn is_invalid(&self) -> bool {
-    self.kind == TS_BUILTIN_SYM_END
+    self.kinds.contains(TS_BUILTIN_SYM_END.into())
   }
 
   /// Whether the kind will match parsing error occurred in the source code.
@@ -64,7 +90,7 @@ impl<L: Language> KindMatcher<L> {
   /// the name `is_error` implies the matcher itself is error.
   /// But here the matcher itself is valid and it is what it matches is error.
   pub fn is_error_matcher(&self) -> bool {
-    self.kind == TS_BUILTIN_SYM_ERROR
+    self.kinds.len() == 1 && self.kinds.contains(TS_BUILTIN_SYM_ERROR.into())
   }
 
   /// Construct a matcher that only matches ERROR
@@ -79,7 +105,7 @@ impl<L: Language> Matcher<L> for KindMatcher<L> {
     node: Node<'tree, D>,
     _env: &mut Cow<MetaVarEnv<'tree, D>>,
   ) -> Option<Node<'tree, D>> {
-    if node.kind_id() == self.kind {
+    if self.kinds.contains(node.kind_id().into()) {
       Some(node)
     } else {
       None
@@ -87,12 +113,31 @@ impl<L: Language> Matcher<L> for KindMatcher<L> {
   }
 
   fn potential_kinds(&self) -> Option<BitSet> {
-    let mut set = BitSet::new();
-    set.insert(self.kind.into());
-    Some(set)
+    Some(self.kinds.clone())
   }
 }
 
+/// Matches a kind name against a glob where `*` stands for any sequence of characters.
+fn glob_match(glob: &str, name: &str) -> bool {
+  let mut parts = glob.split('*');
+  let first = parts.next().unwrap_or_default();
+  let Some(mut rest) = name.strip_prefix(first) else {
+    return false;
+  };
+  let mut parts: Vec<_> = parts.collect();
+  let Some(last) = parts.pop() else {
+    // no star in glob
+    return rest.is_empty();
+  };
+  for part in parts {
+    let Some(i) = rest.find(part) else {
+      return false;
+    };
+    rest = &rest[i + part.len()..];
+  }
+  rest.ends_with(last)
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -140,4 +185,43 @@ mod test {
     // should has exactly one potential kind
     assert_eq!(potential_kinds.len(), 1);
   }
+
+  #[test]
+  fn test_kind_alternation() {
+    let matcher =
+      KindMatcher::try_new("call_expression | new_expression", Tsx).expect("should be valid");
+    let potential_kinds = matcher
+      .potential_kinds()
+      .expect("should have potential kinds");
+    assert_eq!(potential_kinds.len(), 2);
+    let cand = pattern_node("new A()");
+    assert!(matcher.find_node(cand.root()).is_some());
+    let cand = pattern_node("a()");
+    assert!(matcher.find_node(cand.root()).is_some());
+    let cand = pattern_node("a.b");
+    assert!(matcher.find_node(cand.root()).is_none());
+    assert!(KindMatcher::try_new("call_expression | not_kind", Tsx).is_err());
+  }
+
+  #[test]
+  fn test_kind_glob() {
+    let matcher = KindMatcher::try_new("*_statement", Tsx).expect("should be valid");
+    let cand = pattern_node("if (a) {}");
+    let found = matcher.find_node(cand.root()).expect("should match");
+    assert_eq!(found.kind(), "if_statement");
+    let cand = pattern_node("const a = 1");
+    assert!(matcher.find_node(cand.root()).is_none());
+    assert!(KindMatcher::try_new("*_not_exist", Tsx).is_err());
+  }
+
+  #[test]
+  fn test_glob_match() {
+    assert!(glob_match("*_statement", "if_statement"));
+    assert!(glob_match("*", "identifier"));
+    assert!(glob_match("a*b*c", "aXbYc"));
+    assert!(glob_match("identifier", "identifier"));
+    assert!(!glob_match("*_statement", "statement_block"));
+    assert!(!glob_match("a*b*c", "aXcYb"));
+    assert!(!glob_match("ab*ba", "aba"));
+  }
 }
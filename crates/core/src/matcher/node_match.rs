@@ -37,20 +37,33 @@ impl<'tree, D: Doc> NodeMatch<'tree, D> {
 }
 
 impl<D: Doc> NodeMatch<'_, D> {
+  /// Replace the matched node. The edit position is in the original source if the doc has a source map.
   pub fn replace_by<R: Replacer<D>>(&self, replacer: R) -> Edit<D::Source> {
     let range = self.range();
     let position = range.start;
     let deleted_length = range.len();
     let inserted_text = replacer.generate_replacement(self);
-    Edit {
+    self.original_edit(Edit {
       position,
       deleted_length,
       inserted_text,
-    }
+    })
   }
 
+  /// The edit position is in the original source if the doc has a source map.
   #[doc(hidden)]
   pub fn make_edit<M, R>(&self, matcher: &M, replacer: &R) -> Edit<D::Source>
+  where
+    M: Matcher<D::Lang>,
+    R: Replacer<D>,
+  {
+    self.original_edit(self.make_parsed_edit(matcher, replacer))
+  }
+
+  /// Like `make_edit`, but the edit position is always in the parsed text.
+  /// Use it to edit the doc itself rather than the original source.
+  #[doc(hidden)]
+  pub fn make_parsed_edit<M, R>(&self, matcher: &M, replacer: &R) -> Edit<D::Source>
   where
     M: Matcher<D::Lang>,
     R: Replacer<D>,
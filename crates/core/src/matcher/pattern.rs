@@ -2,15 +2,16 @@ use crate::language::Language;
 use crate::match_tree::{match_end_non_recursive, match_node_non_recursive, MatchStrictness};
 use crate::matcher::{KindMatcher, KindMatcherError, Matcher};
 use crate::meta_var::{MetaVarEnv, MetaVariable};
-use crate::source::TSParseError;
+use crate::source::{Edit, TSParseError};
 use crate::{Doc, Node, Root, StrDoc};
 
 use bit_set::BitSet;
 use thiserror::Error;
 
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
+use std::ops::Range;
 
 #[derive(Clone)]
 pub struct Pattern<L: Language> {
@@ -18,6 +19,10 @@ pub struct Pattern<L: Language> {
   root_kind: Option<u16>,
   lang: PhantomData<L>,
   pub strictness: MatchStrictness,
+  /// skip ERROR nodes and ignore the text of missing nodes in the candidate
+  pub allow_error_nodes: bool,
+  /// node kinds that meta variables must match, keyed by meta variable name
+  var_kinds: HashMap<String, KindMatcher<L>>,
 }
 
 #[derive(Clone)]
@@ -74,17 +79,37 @@ impl<'r, D: Doc> From<Node<'r, D>> for PatternNode {
 
 impl<'r, D: Doc> From<Node<'r, D>> for Pattern<D::Lang> {
   fn from(node: Node<'r, D>) -> Self {
+    Self::from_pattern_node(convert_node_to_pattern(node))
+  }
+}
+
+impl<L: Language> Pattern<L> {
+  fn from_pattern_node(node: PatternNode) -> Self {
     Self {
-      node: convert_node_to_pattern(node),
+      node,
       root_kind: None,
       lang: PhantomData,
       strictness: MatchStrictness::Smart,
+      allow_error_nodes: false,
+      var_kinds: HashMap::new(),
     }
   }
 }
 
 fn convert_node_to_pattern<D: Doc>(node: Node<D>) -> PatternNode {
-  if let Some(meta_var) = extract_var_from_node(&node) {
+  convert_node_with_holes(node, &[])
+}
+
+/// holes are operator tokens substituted for unnamed meta variables, see `fill_operator_vars`
+fn convert_node_with_holes<D: Doc>(node: Node<D>, holes: &[Hole]) -> PatternNode {
+  let hole = holes
+    .iter()
+    .find(|h| node.is_leaf() && h.range == node.range());
+  if let Some(hole) = hole {
+    PatternNode::MetaVar {
+      meta_var: hole.meta_var.clone(),
+    }
+  } else if let Some(meta_var) = extract_var_from_node(&node) {
     PatternNode::MetaVar { meta_var }
   } else if node.is_leaf() {
     PatternNode::Terminal {
@@ -97,7 +122,7 @@ fn convert_node_to_pattern<D: Doc>(node: Node<D>) -> PatternNode {
       if n.get_ts_node().is_missing() {
         None
       } else {
-        Some(PatternNode::from(n))
+        Some(convert_node_with_holes(n, holes))
       }
     });
     PatternNode::Internal {
@@ -112,6 +137,174 @@ fn extract_var_from_node<D: Doc>(goal: &Node<D>) -> Option<MetaVariable> {
   goal.lang().extract_meta_var(&key)
 }
 
+/// An unnamed meta variable replaced by an operator token in the pattern source.
+#[derive(Clone)]
+struct Hole {
+  range: Range<usize>,
+  meta_var: MetaVariable,
+}
+
+/// ERROR or MISSING nodes in the tree.
+fn has_parse_error<D: Doc>(root: &Root<D>) -> bool {
+  root.root().get_ts_node().has_error()
+}
+
+/// Finds unnamed meta variables like `$$OP` in the processed pattern source.
+fn find_unnamed_vars<L: Language>(src: &str, lang: &L) -> Vec<Hole> {
+  let expando = lang.expando_char();
+  let is_var_char =
+    |c: char| c == expando || c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_';
+  let mut holes = vec![];
+  let mut start = None;
+  for (i, c) in src.char_indices().chain(std::iter::once((src.len(), ' '))) {
+    if is_var_char(c) {
+      start.get_or_insert(i);
+      continue;
+    }
+    let Some(s) = start.take() else {
+      continue;
+    };
+    let meta_var = lang.extract_meta_var(&src[s..i]);
+    if let Some(meta_var @ (MetaVariable::Capture(_, false) | MetaVariable::Dropped(false))) =
+      meta_var
+    {
+      holes.push(Hole {
+        range: s..i,
+        meta_var,
+      });
+    }
+  }
+  holes
+}
+
+/// Common binary operators, tried first so most patterns are parsed only once.
+const COMMON_OPERATORS: [&str; 3] = ["+", "==", "&&"];
+
+/// Operator-like tokens of the language, e.g. `+`, `==` or `<`, common operators first.
+fn operator_tokens<L: Language>(lang: &L) -> Vec<String> {
+  let ts_lang = lang.get_ts_language();
+  let mut tokens: Vec<String> = vec![];
+  for id in 0..ts_lang.node_kind_count() {
+    if ts_lang.node_kind_is_named(id) {
+      continue;
+    }
+    let Some(kind) = ts_lang.node_kind_for_id(id) else {
+      continue;
+    };
+    let is_operator = !kind.is_empty() && kind.chars().all(|c| c.is_ascii_punctuation());
+    if is_operator && !tokens.iter().any(|t| t == kind.as_ref()) {
+      tokens.push(kind.to_string());
+    }
+  }
+  // stable sort keeps the grammar order of other tokens
+  tokens.sort_by_key(|t| {
+    let common = COMMON_OPERATORS.iter().position(|c| c == t);
+    common.unwrap_or(COMMON_OPERATORS.len())
+  });
+  tokens
+}
+
+/// Unnamed meta variables in operator position, e.g. `$A $$OP $B`, make the pattern invalid.
+/// This tries operator tokens of the language in place of the meta variables until the pattern parses
+/// and every token is the `operator` field of its parent. Common operators are tried first
+/// so `binary_expression` is preferred over e.g. `augmented_assignment_expression`.
+/// The tokens are turned back into meta variables when converting the pattern.
+/// Returns None if the pattern has no unnamed meta variable or fails to parse with any token,
+/// and an error if tokens only parse elsewhere than the `operator` field.
+fn fill_operator_vars<L: Language>(src: &str, lang: &L) -> Result<Option<Filled<L>>, PatternError> {
+  let vars = find_unnamed_vars(src, lang);
+  let tokens = operator_tokens(lang);
+  let Some(first) = tokens.first().filter(|_| !vars.is_empty()) else {
+    return Ok(None);
+  };
+  // parse the pattern once and swap the other tokens in by incremental edits
+  let (filled, mut holes) = fill_holes(src, vars, first);
+  let mut root = Root::<StrDoc<L>>::try_new(&filled, lang.clone())?;
+  let mut parsed_as_non_operator = false;
+  for token in &tokens {
+    replace_holes(&mut root, &mut holes, token)?;
+    if has_parse_error(&root) {
+      continue;
+    }
+    if holes_are_operators(&root, &holes) {
+      return Ok(Some((root, holes)));
+    }
+    parsed_as_non_operator = true;
+  }
+  if parsed_as_non_operator {
+    Err(PatternError::InvalidOperatorVar(src.into()))
+  } else {
+    // the pattern is invalid regardless of the meta variables, keep it as is
+    Ok(None)
+  }
+}
+
+/// Pattern root with operator tokens filled in and the holes to turn back into meta variables.
+type Filled<L> = (Root<StrDoc<L>>, Vec<Hole>);
+
+/// Replace unnamed meta variables in the source with the token.
+fn fill_holes(src: &str, vars: Vec<Hole>, token: &str) -> (String, Vec<Hole>) {
+  let mut filled = String::new();
+  let mut holes = vec![];
+  let mut last = 0;
+  for var in vars {
+    filled.push_str(&src[last..var.range.start]);
+    let start = filled.len();
+    filled.push_str(token);
+    last = var.range.end;
+    holes.push(Hole {
+      range: start..filled.len(),
+      meta_var: var.meta_var,
+    });
+  }
+  filled.push_str(&src[last..]);
+  (filled, holes)
+}
+
+/// Replace the text of every hole with the token and update the hole ranges.
+fn replace_holes<L: Language>(
+  root: &mut Root<StrDoc<L>>,
+  holes: &mut [Hole],
+  token: &str,
+) -> Result<(), TSParseError> {
+  let Some(first) = holes.first() else {
+    return Ok(());
+  };
+  let old_len = first.range.len();
+  if root.get_text()[first.range.clone()] == *token {
+    return Ok(());
+  }
+  // edit from the last hole so the positions of previous holes stay valid
+  for hole in holes.iter().rev() {
+    root.do_edit(Edit {
+      position: hole.range.start,
+      deleted_length: old_len,
+      inserted_text: token.as_bytes().to_vec(),
+    })?;
+  }
+  for (i, hole) in holes.iter_mut().enumerate() {
+    let start = hole.range.start - i * old_len + i * token.len();
+    hole.range = start..start + token.len();
+  }
+  Ok(())
+}
+
+/// Returns true if every hole is the `operator` field of its parent.
+fn holes_are_operators<L: Language>(root: &Root<StrDoc<L>>, holes: &[Hole]) -> bool {
+  holes.iter().all(|hole| {
+    let node = root
+      .root()
+      .dfs()
+      .find(|n| n.is_leaf() && n.range() == hole.range);
+    let operator = node.and_then(|n| n.parent()).and_then(|parent| {
+      parent
+        .field("operator")
+        .or_else(|| parent.field("operators"))
+    });
+    operator.map_or(false, |o| o.range() == hole.range)
+  })
+}
+
 #[derive(Debug, Error)]
 pub enum PatternError {
   #[error("Tree-Sitter fails to parse the pattern.")]
@@ -124,6 +317,10 @@ pub enum PatternError {
   InvalidKind(#[from] KindMatcherError),
   #[error("Fails to create Contextual pattern: selector `{selector}` matches no node in the context `{context}`.")]
   NoSelectorInContext { context: String, selector: String },
+  #[error("Meta variable `{0}` is not defined in the pattern.")]
+  UndefinedMetaVar(String),
+  #[error("Unnamed meta variables in operator position must parse as the operator. Please check the pattern source `{0}`.")]
+  InvalidOperatorVar(String),
 }
 
 #[inline]
@@ -200,7 +397,14 @@ fn collect_vars<'p>(p: &'p PatternNode, vars: &mut HashSet<&'p str>) {
 impl<L: Language> Pattern<L> {
   pub fn try_new(src: &str, lang: L) -> Result<Self, PatternError> {
     let processed = lang.pre_process_pattern(src);
-    let root = Root::<StrDoc<L>>::try_new(&processed, lang)?;
+    let mut root = Root::<StrDoc<L>>::try_new(&processed, lang.clone())?;
+    let mut holes = vec![];
+    if has_parse_error(&root) {
+      if let Some((filled, filled_holes)) = fill_operator_vars(&processed, &lang)? {
+        root = filled;
+        holes = filled_holes;
+      }
+    }
     let goal = root.root();
     if goal.inner.child_count() == 0 {
       return Err(PatternError::NoContent(src.into()));
@@ -209,7 +413,9 @@ impl<L: Language> Pattern<L> {
       return Err(PatternError::MultipleNode(src.into()));
     }
     let node = Self::single_matcher(&root);
-    Ok(Self::from(node))
+    Ok(Self::from_pattern_node(convert_node_with_holes(
+      node, &holes,
+    )))
   }
 
   pub fn new(src: &str, lang: L) -> Self {
@@ -221,6 +427,40 @@ impl<L: Language> Pattern<L> {
     self
   }
 
+  /// Tolerates ERROR and missing nodes in the matched code.
+  /// It helps matching code that tree-sitter fails to parse, e.g. code with macros.
+  pub fn with_error_nodes_allowed(mut self, allow: bool) -> Self {
+    self.allow_error_nodes = allow;
+    self
+  }
+
+  /// Restricts meta variables to nodes of the given kinds, keyed by meta variable name.
+  /// Multi meta variables like `$$$ARGS` require every named node they capture to match.
+  pub fn with_var_kinds(
+    mut self,
+    var_kinds: HashMap<String, KindMatcher<L>>,
+  ) -> Result<Self, PatternError> {
+    let vars = self.defined_vars();
+    if let Some(var) = var_kinds.keys().find(|v| !vars.contains(v.as_str())) {
+      return Err(PatternError::UndefinedMetaVar(var.clone()));
+    }
+    self.var_kinds = var_kinds;
+    Ok(self)
+  }
+
+  fn match_var_kinds<D: Doc<Lang = L>>(&self, env: &MetaVarEnv<D>) -> bool {
+    self.var_kinds.iter().all(|(var, kind)| {
+      if let Some(node) = env.get_match(var) {
+        return node.matches(kind);
+      }
+      env
+        .get_multiple_matches(var)
+        .iter()
+        .filter(|n| n.is_named())
+        .all(|n| n.matches(kind))
+    })
+  }
+
   pub fn contextual(context: &str, selector: &str, lang: L) -> Result<Self, PatternError> {
     let processed = lang.pre_process_pattern(context);
     let root = Root::<StrDoc<L>>::try_new(&processed, lang.clone())?;
@@ -237,6 +477,8 @@ impl<L: Language> Pattern<L> {
       node: convert_node_to_pattern(node.get_node().clone()),
       lang: PhantomData,
       strictness: MatchStrictness::Smart,
+      allow_error_nodes: false,
+      var_kinds: HashMap::new(),
     })
   }
   pub fn doc(doc: StrDoc<L>) -> Self {
@@ -268,6 +510,9 @@ impl<L: Language> Matcher<L> for Pattern<L> {
     // do not pollute the env if pattern does not match
     let mut may_write = Cow::Borrowed(env.as_ref());
     let node = match_node_non_recursive(self, node, &mut may_write)?;
+    if !self.var_kinds.is_empty() && !self.match_var_kinds(&may_write) {
+      return None;
+    }
     if let Cow::Owned(map) = may_write {
       // only change env when pattern matches
       *env = Cow::Owned(map);
@@ -424,6 +669,36 @@ mod test {
     assert_eq!(env["I"], "123");
   }
 
+  #[test]
+  fn test_var_kinds() {
+    let mut var_kinds = HashMap::new();
+    var_kinds.insert("A".to_string(), KindMatcher::new("identifier", Tsx));
+    let pattern = Pattern::str("$A + 1", Tsx)
+      .with_var_kinds(var_kinds)
+      .expect("should be valid");
+    let cand = pattern_node("a + 1");
+    assert!(pattern.find_node(cand.root()).is_some());
+    let cand = pattern_node("a.b + 1");
+    assert!(pattern.find_node(cand.root()).is_none());
+    let mut var_kinds = HashMap::new();
+    var_kinds.insert("B".to_string(), KindMatcher::new("identifier", Tsx));
+    let ret = Pattern::str("$A + 1", Tsx).with_var_kinds(var_kinds);
+    assert!(matches!(ret, Err(PatternError::UndefinedMetaVar(v)) if v == "B"));
+  }
+
+  #[test]
+  fn test_multi_var_kinds() {
+    let mut var_kinds = HashMap::new();
+    var_kinds.insert("ARGS".to_string(), KindMatcher::new("identifier", Tsx));
+    let pattern = Pattern::str("f($$$ARGS)", Tsx)
+      .with_var_kinds(var_kinds)
+      .expect("should be valid");
+    let cand = pattern_node("f(a, b)");
+    assert!(pattern.find_node(cand.root()).is_some());
+    let cand = pattern_node("f(a, 1)");
+    assert!(pattern.find_node(cand.root()).is_none());
+  }
+
   #[test]
   fn test_contextual_unmatch_with_env() {
     let pattern =
@@ -596,4 +871,55 @@ mod test {
   fn test_gh_1087() {
     test_match("($P) => $F($P)", "(x) => bar(x)");
   }
+
+  #[test]
+  fn test_allow_error_nodes() {
+    // the backslash cannot be parsed and tree-sitter wraps it in an ERROR node
+    test_non_match("foo($A)", "foo(a \\)");
+    let pattern = Pattern::str("foo($A)", Tsx).with_error_nodes_allowed(true);
+    let cand = pattern_node("foo(a \\)");
+    let nm = pattern.find_node(cand.root()).expect("should match");
+    assert_eq!(
+      nm.get_env().get_match("A").expect("should capture").text(),
+      "a"
+    );
+  }
+
+  #[test]
+  fn test_operator_wildcard() {
+    test_match("$A $$OP $B", "a == b");
+    test_match("a $$OP b", "a != b");
+    test_match("a $$OP b", "a < b");
+    test_match("a $$_ b", "a + b");
+    test_non_match("a $$OP b", "a += b");
+    test_non_match("a $$OP b", "a + c");
+    let env = match_env("$A $$OP $B", "x <= 1");
+    assert_eq!(env["OP"], "<=");
+    assert_eq!(env["B"], "1");
+    let env = match_env("f($A $$OP1 $B, $C $$OP2 $D)", "f(a * b, c - d)");
+    assert_eq!(env["OP1"], "*");
+    assert_eq!(env["OP2"], "-");
+    // tokens that only parse as non-operators, e.g. array commas, are an error
+    assert!(matches!(
+      Pattern::try_new("[$$A $$B]", Tsx),
+      Err(PatternError::InvalidOperatorVar(_))
+    ));
+  }
+
+  #[test]
+  fn test_operator_wildcard_vars() {
+    let vars = defined_vars("$A $$OP $B");
+    assert_eq!(vars, ["A", "B", "OP"]);
+    // unnamed meta variable in valid pattern is not replaced
+    test_match("foo($$A)", "foo(a)");
+  }
+
+  #[test]
+  fn test_pattern_with_missing_node() {
+    // the zero-width MISSING `;` ends the tree
+    let pattern = Pattern::str("if (a)", Tsx);
+    assert!(matches!(pattern.node, PatternNode::Internal { .. }));
+    test_match("if (a)", "if (a)");
+    test_non_match("if (a)", "if (b)");
+  }
 }
@@ -7,6 +7,7 @@ use regex::{Error as RegexError, Regex};
 use thiserror::Error;
 
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::marker::PhantomData;
 
 #[derive(Debug, Error)]
@@ -28,18 +29,59 @@ impl<L: Language> RegexMatcher<L> {
       lang: PhantomData,
     })
   }
+
+  pub fn as_str(&self) -> &str {
+    self.regex.as_str()
+  }
+
+  /// Named groups like `(?P<NAME>...)`, exported as meta variables when matched.
+  pub fn defined_vars(&self) -> HashSet<&str> {
+    self.regex.capture_names().flatten().collect()
+  }
 }
 
 impl<L: Language> Matcher<L> for RegexMatcher<L> {
   fn match_node_with_env<'tree, D: Doc<Lang = L>>(
     &self,
     node: Node<'tree, D>,
-    _env: &mut Cow<MetaVarEnv<'tree, D>>,
+    env: &mut Cow<MetaVarEnv<'tree, D>>,
   ) -> Option<Node<'tree, D>> {
-    self.regex.is_match(&node.text()).then_some(node)
+    let text = node.text();
+    if self.regex.capture_names().flatten().next().is_none() {
+      return self.regex.is_match(&text).then_some(node);
+    }
+    let caps = self.regex.captures(&text)?;
+    for name in self.regex.capture_names().flatten() {
+      if let Some(m) = caps.name(name) {
+        env.to_mut().insert_text(name, m.as_str());
+      }
+    }
+    Some(node)
   }
 
   fn potential_kinds(&self) -> Option<BitSet> {
     None
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::language::Tsx;
+  use crate::source::Content;
+
+  #[test]
+  fn test_regex_named_group() {
+    let matcher = RegexMatcher::<Tsx>::try_new(r"^use(?P<NAME>[A-Z]\w*)$").expect("should parse");
+    assert_eq!(matcher.defined_vars(), ["NAME"].into_iter().collect());
+    let grep = Tsx.ast_grep("useState()");
+    let nm = grep.root().find(&matcher).expect("should match");
+    let bytes = nm
+      .get_env()
+      .get_transformed("NAME")
+      .expect("should capture");
+    assert_eq!(<String as Content>::encode_bytes(bytes), "State");
+    let grep = Tsx.ast_grep("used()");
+    assert!(grep.root().find(&matcher).is_none());
+  }
+}
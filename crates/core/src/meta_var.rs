@@ -60,6 +60,13 @@ impl<'tree, D: Doc> MetaVarEnv<'tree, D> {
     self.transformed_var.insert(name.to_string(), deindented);
   }
 
+  /// Inserts text not backed by a node, e.g. a named group captured by regex.
+  /// Node captures of the same name take precedence in replacement.
+  pub fn insert_text(&mut self, name: &str, text: &str) {
+    let bytes = D::Source::decode_str(text).into_owned();
+    self.transformed_var.insert(name.to_string(), bytes);
+  }
+
   pub fn get_match(&self, var: &str) -> Option<&'_ Node<'tree, D>> {
     self.single_matched.get(var)
   }
@@ -110,12 +110,39 @@ impl<D: Doc> Root<D> {
 
   fn check_lineage(&self, inner: &tree_sitter::Node<'_>) -> bool {
     let mut node = inner.clone();
-    while let Some(n) = node.parent() {
+    while let Some(n) = self.parent_of(&node) {
       node = n;
     }
     node == self.inner.root_node()
   }
 
+  /// tree-sitter returns the node itself as the parent of zero-width nodes at the end of the tree,
+  /// e.g. the MISSING `;` in `if (a)`. Find the parent by walking down from the root in that case.
+  fn parent_of<'t>(&'t self, node: &tree_sitter::Node<'t>) -> Option<tree_sitter::Node<'t>> {
+    let parent = node.parent()?;
+    if parent != *node {
+      return Some(parent);
+    }
+    let range = node.byte_range();
+    let mut cursor = self.inner.walk();
+    loop {
+      let current = cursor.node();
+      if current == *node {
+        return cursor.goto_parent().then(|| cursor.node());
+      }
+      let r = current.byte_range();
+      // only enter the nodes covering the target
+      if r.start <= range.start && range.end <= r.end && cursor.goto_first_child() {
+        continue;
+      }
+      while !cursor.goto_next_sibling() {
+        if !cursor.goto_parent() {
+          return None;
+        }
+      }
+    }
+  }
+
   /// P.S. I am your father.
   #[doc(hidden)]
   pub unsafe fn readopt<'a: 'b, 'b>(&'a self, node: &mut Node<'b, D>) {
@@ -219,6 +246,24 @@ impl<'r, D: Doc> Node<'r, D> {
     (self.inner.start_byte() as usize)..(self.inner.end_byte() as usize)
   }
 
+  /// byte offsets in the original source if the doc is transformed before parsing.
+  /// It is the same as `range` if the doc has no source map.
+  pub fn original_range(&self) -> std::ops::Range<usize> {
+    let range = self.range();
+    match self.root.doc.get_source_map() {
+      Some(map) => map.original_range(range),
+      None => range,
+    }
+  }
+
+  /// map an edit of the parsed text to the original source if the doc is transformed.
+  pub(crate) fn original_edit(&self, edit: Edit<D>) -> Edit<D> {
+    match self.root.doc.get_source_map() {
+      Some(map) => map.original_edit(&edit),
+      None => edit,
+    }
+  }
+
   /// Nodes' start position in terms of zero-based rows and columns.
   pub fn start_pos(&self) -> Position {
     let pos = self.inner.start_position();
@@ -233,6 +278,14 @@ impl<'r, D: Doc> Node<'r, D> {
     Position::new(pos.row(), pos.column(), byte)
   }
 
+  /// Convert to tree-sitter's Range, e.g. to parse a region of injected language.
+  pub fn ts_range(&self) -> tree_sitter::Range {
+    let r = self.range();
+    let sp = self.start_pos().ts_point();
+    let ep = self.end_pos().ts_point();
+    tree_sitter::Range::new(r.start as u32, r.end as u32, &sp, &ep)
+  }
+
   pub fn text(&self) -> Cow<'r, str> {
     let source = self.root.doc.get_source();
     source.get_text(&self.inner)
@@ -339,7 +392,7 @@ pub struct DisplayContext<'r> {
 impl<'r, D: Doc> Node<'r, D> {
   #[must_use]
   pub fn parent(&self) -> Option<Self> {
-    let inner = self.inner.parent()?;
+    let inner = self.root.parent_of(&self.inner)?;
     Some(Node {
       inner,
       root: self.root,
@@ -414,14 +467,14 @@ impl<'r, D: Doc> Node<'r, D> {
   /// Note: each invocation of the returned iterator is O(n)
   /// Using cursor is overkill here because adjust cursor is too expensive.
   pub fn ancestors(&self) -> impl Iterator<Item = Node<'r, D>> + '_ {
-    let mut parent = self.inner.parent();
+    let mut parent = self.root.parent_of(&self.inner);
     std::iter::from_fn(move || {
       let inner = parent.clone()?;
       let ret = Some(Node {
         inner: inner.clone(),
         root: self.root,
       });
-      parent = inner.parent();
+      parent = self.root.parent_of(&inner);
       ret
     })
   }
@@ -558,21 +611,21 @@ impl<D: Doc> Node<'_, D> {
     let mut children = self.children().peekable();
     let start = children.peek()?.range().start;
     let end = children.last()?.range().end;
-    Some(Edit::<D> {
+    Some(self.original_edit(Edit::<D> {
       position: start,
       deleted_length: end - start,
       inserted_text: Vec::new(),
-    })
+    }))
   }
 
   /// Remove the node itself
   pub fn remove(&self) -> Edit<D> {
     let range = self.range();
-    Edit::<D> {
+    self.original_edit(Edit::<D> {
       position: range.start,
       deleted_length: range.end - range.start,
       inserted_text: Vec::new(),
-    }
+    })
   }
 }
 
@@ -608,6 +661,26 @@ mod test {
     assert_eq!(edit.position, 0);
   }
 
+  #[test]
+  fn test_original_range() {
+    use crate::source::SourceMap;
+    use crate::{Root, StrDoc};
+    // `<?js let a = 123` with the opening tag stripped
+    let mut map = SourceMap::new();
+    map.copy(5..16);
+    let root = Root::doc(StrDoc::with_source_map("let a = 123", Tsx, map));
+    let node = root.root().find("123").expect("should find");
+    assert_eq!(node.range(), 8..11);
+    assert_eq!(node.original_range(), 13..16);
+    let edit = root.root().replace("123", "456").expect("should replace");
+    assert_eq!(edit.position, 13);
+    assert_eq!(edit.deleted_length, 3);
+    assert_eq!(node.remove().position, 13);
+    let root = Tsx.ast_grep("let a = 123");
+    let node = root.root().find("123").expect("should find");
+    assert_eq!(node.original_range(), 8..11);
+  }
+
   #[test]
   fn test_field_children() {
     let root = Tsx.ast_grep("let a = 123");
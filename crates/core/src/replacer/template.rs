@@ -2,13 +2,14 @@ use super::indent::{extract_with_deindent, get_indent_at_offset, indent_lines, D
 use super::{split_first_meta_var, MetaVarExtract, Replacer, Underlying};
 use crate::language::Language;
 use crate::matcher::NodeMatch;
-use crate::meta_var::MetaVarEnv;
+use crate::meta_var::{is_valid_meta_var_char, MetaVarEnv};
 use crate::source::{Content, Doc};
 
 use thiserror::Error;
 
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::ops::Range;
 
 pub enum TemplateFix {
   // no meta_var, pure text
@@ -29,11 +30,35 @@ impl TemplateFix {
   }
 
   pub fn used_vars(&self) -> HashSet<&str> {
-    let template = match self {
-      TemplateFix::WithMetaVar(t) => t,
-      TemplateFix::Textual(_) => return HashSet::new(),
+    let mut vars = HashSet::new();
+    self.collect_vars(&mut vars);
+    vars
+  }
+
+  fn collect_vars<'a>(&'a self, vars: &mut HashSet<&'a str>) {
+    let TemplateFix::WithMetaVar(template) = self else {
+      return;
     };
-    template.vars.iter().map(|v| v.0.used_var()).collect()
+    for (slot, _) in &template.vars {
+      match slot {
+        Slot::Var(var) => {
+          vars.insert(var.used_var());
+        }
+        Slot::Default { var, fallback, .. } => {
+          vars.insert(var.used_var());
+          fallback.collect_vars(vars);
+        }
+        Slot::If {
+          cond,
+          then,
+          otherwise,
+        } => {
+          vars.insert(cond.used_var());
+          then.collect_vars(vars);
+          otherwise.collect_vars(vars);
+        }
+      }
+    }
   }
 }
 
@@ -49,42 +74,203 @@ impl<D: Doc> Replacer<D> for TemplateFix {
 
 type Indent = usize;
 
+/// A placeholder in template to be replaced by captured code.
+enum Slot {
+  /// $A, $$$A or transformed var
+  Var(MetaVarExtract),
+  /// ${A:-fallback} uses fallback if A is not captured or empty,
+  /// ${A-fallback} uses fallback only if A is not captured, like Bash.
+  Default {
+    var: MetaVarExtract,
+    fallback: TemplateFix,
+    if_empty: bool,
+  },
+  /// {{#if A}}then{{else}}otherwise{{/if}}, else branch is optional
+  If {
+    cond: MetaVarExtract,
+    then: TemplateFix,
+    otherwise: TemplateFix,
+  },
+}
+
 pub struct Template {
   fragments: Vec<String>,
-  vars: Vec<(MetaVarExtract, Indent)>,
+  vars: Vec<(Slot, Indent)>,
 }
 
+const IF_START: &str = "{{#if ";
+const ELSE: &str = "{{else}}";
+const IF_END: &str = "{{/if}}";
+
 fn create_template(tmpl: &str, mv_char: char, transforms: &[String]) -> TemplateFix {
+  parse_template(tmpl, 0..tmpl.len(), mv_char, transforms)
+}
+
+/// Parse the template in `range` of `tmpl`.
+/// Nested templates in defaults and conditions are parsed with the full string
+/// so indentation of meta vars is counted the same as top level ones.
+fn parse_template(
+  tmpl: &str,
+  range: Range<usize>,
+  mv_char: char,
+  transforms: &[String],
+) -> TemplateFix {
   let mut fragments = vec![];
   let mut vars = vec![];
-  let mut offset = 0;
-  let mut len = 0;
-  while let Some(i) = tmpl[len + offset..].find(mv_char) {
-    if let Some((meta_var, skipped)) =
-      split_first_meta_var(&tmpl[len + offset + i..], mv_char, transforms)
-    {
-      fragments.push(tmpl[len..len + offset + i].to_string());
+  let mut start = range.start;
+  let mut i = range.start;
+  while let Some(offset) = tmpl[i..range.end].find([mv_char, '{']) {
+    let pos = i + offset;
+    if let Some((slot, end)) = split_first_slot(tmpl, pos..range.end, mv_char, transforms) {
+      fragments.push(tmpl[start..pos].to_string());
       // NB we have to count ident of the full string
-      let indent = get_indent_at_offset::<String>(tmpl[..len + offset + i].as_bytes());
-      vars.push((meta_var, indent));
-      len += skipped + offset + i;
-      offset = 0;
+      let indent = get_indent_at_offset::<String>(&tmpl.as_bytes()[..pos]);
+      vars.push((slot, indent));
+      i = end;
+      start = end;
       continue;
     }
-    debug_assert!(len + offset + i < tmpl.len());
-    // offset = 0, i = 0,
-    // 0 1 2
-    // $ a $
-    offset = offset + i + 1;
+    i = pos + tmpl[pos..].chars().next().map_or(1, char::len_utf8);
   }
   if fragments.is_empty() {
-    TemplateFix::Textual(tmpl[len..].to_string())
+    TemplateFix::Textual(tmpl[start..range.end].to_string())
   } else {
-    fragments.push(tmpl[len..].to_string());
+    fragments.push(tmpl[start..range.end].to_string());
     TemplateFix::WithMetaVar(Template { fragments, vars })
   }
 }
 
+/// Returns the slot at the start of `range` and the end offset of the slot.
+/// Malformed defaults or conditions are kept as plain text.
+fn split_first_slot(
+  tmpl: &str,
+  range: Range<usize>,
+  mv_char: char,
+  transforms: &[String],
+) -> Option<(Slot, usize)> {
+  let src = &tmpl[range.clone()];
+  if src.starts_with(IF_START) {
+    return split_condition(tmpl, range, mv_char, transforms);
+  }
+  if !src.starts_with(mv_char) {
+    return None;
+  }
+  if src[mv_char.len_utf8()..].starts_with('{') {
+    return split_default(tmpl, range, mv_char, transforms);
+  }
+  let (var, skipped) = split_first_meta_var(src, mv_char, transforms)?;
+  Some((Slot::Var(var), range.start + skipped))
+}
+
+/// Parse `${A:-fallback}` or `${A-fallback}`. The fallback can contain balanced braces and other slots.
+fn split_default(
+  tmpl: &str,
+  range: Range<usize>,
+  mv_char: char,
+  transforms: &[String],
+) -> Option<(Slot, usize)> {
+  let open = range.start + mv_char.len_utf8();
+  let mut depth = 0;
+  let close = tmpl[open..range.end].char_indices().find_map(|(i, c)| {
+    match c {
+      '{' => depth += 1,
+      '}' => depth -= 1,
+      _ => (),
+    }
+    (depth == 0).then_some(open + i)
+  })?;
+  let inner = &tmpl[open + 1..close];
+  let sep = inner.find(|c| c != mv_char && !is_valid_meta_var_char(c))?;
+  let var = parse_var(&inner[..sep], mv_char, transforms)?;
+  let rest = &inner[sep..];
+  let (if_empty, sep_len) = if rest.starts_with(":-") {
+    (true, 2)
+  } else if rest.starts_with('-') {
+    (false, 1)
+  } else {
+    return None;
+  };
+  let fallback = parse_template(tmpl, open + 1 + sep + sep_len..close, mv_char, transforms);
+  let slot = Slot::Default {
+    var,
+    fallback,
+    if_empty,
+  };
+  Some((slot, close + 1))
+}
+
+/// Parse `{{#if A}}then{{else}}otherwise{{/if}}`. Conditions can be nested.
+fn split_condition(
+  tmpl: &str,
+  range: Range<usize>,
+  mv_char: char,
+  transforms: &[String],
+) -> Option<(Slot, usize)> {
+  let head = &tmpl[range.start + IF_START.len()..range.end];
+  let head_end = head.find("}}")?;
+  let cond = parse_var(head[..head_end].trim(), mv_char, transforms)?;
+  let body_start = range.start + IF_START.len() + head_end + 2;
+  let (else_pos, end_pos) = find_if_end(&tmpl[body_start..range.end])?;
+  let body_end = body_start + end_pos;
+  let (then, otherwise) = match else_pos {
+    Some(pos) => (
+      body_start..body_start + pos,
+      body_start + pos + ELSE.len()..body_end,
+    ),
+    None => (body_start..body_end, body_end..body_end),
+  };
+  let slot = Slot::If {
+    cond,
+    then: parse_template(tmpl, then, mv_char, transforms),
+    otherwise: parse_template(tmpl, otherwise, mv_char, transforms),
+  };
+  Some((slot, body_end + IF_END.len()))
+}
+
+/// Returns the offsets of the top level `{{else}}`, if any, and the matching `{{/if}}`.
+fn find_if_end(body: &str) -> Option<(Option<usize>, usize)> {
+  let mut depth = 0;
+  let mut else_pos = None;
+  let mut i = 0;
+  while i < body.len() {
+    let rest = &body[i..];
+    if rest.starts_with(IF_START) {
+      depth += 1;
+      i += IF_START.len();
+    } else if rest.starts_with(IF_END) {
+      if depth == 0 {
+        return Some((else_pos, i));
+      }
+      depth -= 1;
+      i += IF_END.len();
+    } else if rest.starts_with(ELSE) {
+      if depth == 0 && else_pos.is_none() {
+        else_pos = Some(i);
+      }
+      i += ELSE.len();
+    } else {
+      i += rest.chars().next()?.len_utf8();
+    }
+  }
+  None
+}
+
+/// Parse var name in defaults and conditions, e.g. `A`, `$A` or `$$$A`.
+fn parse_var(name: &str, mv_char: char, transforms: &[String]) -> Option<MetaVarExtract> {
+  let stripped = name.trim_start_matches(mv_char);
+  let prefix = (name.len() - stripped.len()) / mv_char.len_utf8();
+  if stripped.is_empty() || !stripped.chars().all(is_valid_meta_var_char) {
+    return None;
+  }
+  let name = stripped.to_string();
+  match prefix {
+    3 => Some(MetaVarExtract::Multiple(name)),
+    0 | 1 if transforms.contains(&name) => Some(MetaVarExtract::Transformed(name)),
+    0 | 1 => Some(MetaVarExtract::Single(name)),
+    _ => None,
+  }
+}
+
 fn replace_fixer<D: Doc>(
   fixer: &TemplateFix,
   env: &MetaVarEnv<D>,
@@ -99,9 +285,30 @@ fn replace_fixer<D: Doc>(
   if let Some(frag) = frags.next() {
     ret.extend_from_slice(&D::Source::decode_str(frag));
   }
-  for ((var, indent), frag) in vars.zip(frags) {
-    if let Some(bytes) = maybe_get_var(env, var, indent) {
-      ret.extend_from_slice(&bytes);
+  for ((slot, indent), frag) in vars.zip(frags) {
+    match slot {
+      Slot::Var(var) => {
+        if let Some(bytes) = maybe_get_var(env, var, indent) {
+          ret.extend_from_slice(&bytes);
+        }
+      }
+      Slot::Default {
+        var,
+        fallback,
+        if_empty,
+      } => match maybe_get_var(env, var, indent) {
+        Some(bytes) if !(*if_empty && bytes.is_empty()) => ret.extend_from_slice(&bytes),
+        _ => ret.extend(replace_fixer(fallback, env)),
+      },
+      Slot::If {
+        cond,
+        then,
+        otherwise,
+      } => {
+        let is_present = maybe_get_var(env, cond, indent).map_or(false, |b| !b.is_empty());
+        let branch = if is_present { then } else { otherwise };
+        ret.extend(replace_fixer(branch, env));
+      }
     }
     ret.extend_from_slice(&D::Source::decode_str(frag));
   }
@@ -123,15 +330,20 @@ where
       return Some(Cow::Borrowed(source));
     }
     MetaVarExtract::Single(name) => {
-      let replaced = env.get_match(name)?;
+      let Some(replaced) = env.get_match(name) else {
+        // text captured without a node, e.g. a regex named group in constraints
+        let source = env.get_transformed(name)?;
+        return Some(Cow::Borrowed(source));
+      };
       let source = replaced.root.doc.get_source();
       let range = replaced.range();
       (source, range)
     }
     MetaVarExtract::Multiple(name) => {
-      let nodes = env.get_multiple_matches(name);
+      // an empty capture is set, only a missing one is None
+      let nodes = env.get_labels(name)?;
       if nodes.is_empty() {
-        return None;
+        return Some(Cow::Borrowed(&[]));
       }
       // NOTE: start_byte is not always index range of source's slice.
       // e.g. start_byte is still byte_offset in utf_16 (napi). start_byte
@@ -333,6 +545,52 @@ if (true) {
     assert_eq!(tf.used_vars(), ["B", "C"].into_iter().collect());
   }
 
+  #[test]
+  fn test_default_value() {
+    test_template_replace("foo(${A:-1})", &[("A", "a")], "foo(a)");
+    test_template_replace("foo(${A:-1})", &[], "foo(1)");
+    test_template_replace("foo(${A:-$B})", &[("B", "b")], "foo(b)");
+    test_template_replace("foo(${A:-{}})", &[], "foo({})");
+    // not a default, kept as is
+    test_template_replace("`${$A}`", &[("A", "a")], "`${a}`");
+    test_template_replace("${A:-1", &[], "${A:-1");
+    test_template_replace("foo(${A-1})", &[("A", "a")], "foo(a)");
+    test_template_replace("foo(${A-1})", &[], "foo(1)");
+  }
+
+  #[test]
+  fn test_default_of_empty_var() {
+    let replace = |template: &str| {
+      let mut src = Tsx.ast_grep("foo()");
+      let pattern = Pattern::str("foo($$$ARGS)", Tsx);
+      src.replace(pattern, template).expect("should replace");
+      src.root().text().to_string()
+    };
+    // ARGS is captured but empty
+    assert_eq!(replace("bar(${$$$ARGS:-1})"), "bar(1)");
+    assert_eq!(replace("bar(${$$$ARGS-1})"), "bar()");
+    assert_eq!(replace("bar(${B-1})"), "bar(1)");
+  }
+
+  #[test]
+  fn test_condition() {
+    let tmpl = "foo($A{{#if B}}, $B{{/if}})";
+    test_template_replace(tmpl, &[("A", "a"), ("B", "b")], "foo(a, b)");
+    test_template_replace(tmpl, &[("A", "a")], "foo(a)");
+    let tmpl = "{{#if $A}}a{{else}}{{#if B}}b{{else}}c{{/if}}{{/if}}";
+    test_template_replace(tmpl, &[("A", "1")], "a");
+    test_template_replace(tmpl, &[("B", "1")], "b");
+    test_template_replace(tmpl, &[], "c");
+    // unclosed condition is kept as is
+    test_template_replace("{{#if A}}a", &[("A", "1")], "{{#if A}}a");
+  }
+
+  #[test]
+  fn test_conditional_vars() {
+    let tf = TemplateFix::try_new("{{#if A}}$B{{else}}${C:-$D}{{/if}}", &Tsx).expect("ok");
+    assert_eq!(tf.used_vars(), ["A", "B", "C", "D"].into_iter().collect());
+  }
+
   // GH #641
   #[test]
   fn test_multi_row_replace() {
@@ -10,6 +10,9 @@
 //! `Doc` is a trait that defines a document that can be parsed by Tree-sitter.
 //! It has a `Source` associated type bounded by `Content` that represents the source code of the document,
 //! and a `Lang` associated type that represents the language of the document.
+//!
+//! A `Doc` can also parse a text transformed from the original source, e.g. with macros expanded
+//! or front matter stripped. `SourceMap` maps the ranges of the parsed text back to the original.
 
 use crate::language::Language;
 use std::borrow::Cow;
@@ -55,6 +58,111 @@ fn position_for_offset(input: &[u8], offset: usize) -> Point {
   Point::new(row, col)
 }
 
+/// Maps offsets in the text parsed by tree-sitter back to the original source.
+///
+/// The parsed text is described as consecutive chunks. A chunk is either copied from the original
+/// source verbatim, or generated to replace a range of the original, e.g. an expanded macro.
+/// Original text not covered by any chunk is deleted, e.g. stripped front matter.
+/// Offsets are in the same unit as tree-sitter byte offsets of the `Doc`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+  chunks: Vec<Chunk>,
+  generated_len: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Chunk {
+  generated: Range<usize>,
+  original: Range<usize>,
+  copied: bool,
+}
+
+impl SourceMap {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Appends text copied verbatim from the `original` range.
+  pub fn copy(&mut self, original: Range<usize>) -> &mut Self {
+    let len = original.len();
+    self.push(original, len, true)
+  }
+
+  /// Appends `len` units of generated text that replaces the `original` range.
+  pub fn replace(&mut self, original: Range<usize>, len: usize) -> &mut Self {
+    self.push(original, len, false)
+  }
+
+  fn push(&mut self, original: Range<usize>, len: usize, copied: bool) -> &mut Self {
+    debug_assert!(
+      self
+        .chunks
+        .last()
+        .map_or(true, |c| c.original.end <= original.start),
+      "chunks must be appended in the order of original source"
+    );
+    // empty chunk does not occupy any generated offset, the original range is deleted
+    if len == 0 {
+      return self;
+    }
+    let start = self.generated_len;
+    self.generated_len += len;
+    self.chunks.push(Chunk {
+      generated: start..self.generated_len,
+      original,
+      copied,
+    });
+    self
+  }
+
+  /// Maps a range of the parsed text to the original source.
+  /// A range touching generated text covers the whole original range it replaces.
+  pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+    let start = self.original_start(range.start);
+    let end = self.original_end(range.end);
+    start..end.max(start)
+  }
+
+  /// Maps an edit of the parsed text to an edit of the original source.
+  pub fn original_edit<S: Content>(&self, edit: &Edit<S>) -> Edit<S> {
+    let range = self.original_range(edit.position..edit.position + edit.deleted_length);
+    Edit {
+      position: range.start,
+      deleted_length: range.len(),
+      inserted_text: edit.inserted_text.clone(),
+    }
+  }
+
+  fn original_start(&self, offset: usize) -> usize {
+    let i = self.chunks.partition_point(|c| c.generated.end <= offset);
+    let Some(chunk) = self.chunks.get(i) else {
+      return self.original_len(offset);
+    };
+    if chunk.copied {
+      chunk.original.start + offset.saturating_sub(chunk.generated.start)
+    } else {
+      chunk.original.start
+    }
+  }
+
+  fn original_end(&self, offset: usize) -> usize {
+    let i = self.chunks.partition_point(|c| c.generated.end < offset);
+    let Some(chunk) = self.chunks.get(i) else {
+      return self.original_len(offset);
+    };
+    if chunk.copied {
+      chunk.original.start + offset.saturating_sub(chunk.generated.start)
+    } else {
+      chunk.original.end
+    }
+  }
+
+  // offset beyond all chunks maps to the end of the last chunk
+  fn original_len(&self, offset: usize) -> usize {
+    self.chunks.last().map_or(offset, |c| c.original.end)
+  }
+}
+
 pub fn perform_edit<S: Content>(tree: &mut Tree, input: &mut S, edit: &Edit<S>) -> InputEdit {
   let edit = input.accept_edit(edit);
   tree.edit(&edit);
@@ -89,6 +197,10 @@ pub trait Doc: Clone {
     parse_lang(|p| source.parse_tree_sitter(p, old_tree), lang)
   }
   fn clone_with_lang(&self, lang: Self::Lang) -> Self;
+  /// The map from the parsed source to the original source, if the source is transformed.
+  fn get_source_map(&self) -> Option<&SourceMap> {
+    None
+  }
   /// TODO: are we paying too much to support str as Pattern/Replacer??
   /// this method converts string to Doc, so that we can support using
   /// string as replacer/searcher. Natively.
@@ -99,6 +211,7 @@ pub trait Doc: Clone {
 pub struct StrDoc<L: Language> {
   pub src: String,
   pub lang: L,
+  source_map: Option<SourceMap>,
 }
 
 impl<L: Language> StrDoc<L> {
@@ -106,6 +219,17 @@ impl<L: Language> StrDoc<L> {
     Self {
       src: src.into(),
       lang,
+      source_map: None,
+    }
+  }
+
+  /// Create a doc from the transformed `src`. Ranges are mapped to the original by `source_map`.
+  /// Note the map is not updated when the doc is edited.
+  pub fn with_source_map(src: &str, lang: L, source_map: SourceMap) -> Self {
+    Self {
+      src: src.into(),
+      lang,
+      source_map: Some(source_map),
     }
   }
 }
@@ -126,7 +250,14 @@ impl<L: Language> Doc for StrDoc<L> {
     Self::new(src, lang)
   }
   fn clone_with_lang(&self, lang: Self::Lang) -> Self {
-    Self::new(&self.src, lang)
+    Self {
+      src: self.src.clone(),
+      lang,
+      source_map: self.source_map.clone(),
+    }
+  }
+  fn get_source_map(&self) -> Option<&SourceMap> {
+    self.source_map.as_ref()
   }
 }
 
@@ -265,6 +396,26 @@ mod test {
     Ok(())
   }
 
+  #[test]
+  fn test_source_map() {
+    // original `---\nk: v\n---\nFOO(a)`, front matter is stripped and FOO is expanded
+    let mut map = SourceMap::new();
+    map.replace(13..16, 7).copy(16..19);
+    // parsed `foo.bar(a)`
+    assert_eq!(map.original_range(0..3), 13..16);
+    assert_eq!(map.original_range(7..10), 16..19);
+    assert_eq!(map.original_range(8..9), 17..18);
+    assert_eq!(map.original_range(0..10), 13..19);
+    assert_eq!(map.original_range(7..7), 16..16);
+    let edit = Edit::<String> {
+      position: 8,
+      deleted_length: 1,
+      inserted_text: "b".into(),
+    };
+    let edit = map.original_edit(&edit);
+    assert_eq!((edit.position, edit.deleted_length), (17, 1));
+  }
+
   #[test]
   fn test_edit() -> Result<(), TSParseError> {
     let mut src = "a + b".to_string();
@@ -1,4 +1,4 @@
-use crate::{DynamicLang, DynamicLangError, Registration};
+use crate::{DynamicLang, DynamicLangError, LanguageInjection, Registration};
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
@@ -13,6 +13,9 @@ pub struct CustomLang {
   pub meta_var_char: Option<char>,
   pub expando_char: Option<char>,
   pub extensions: Vec<String>,
+  /// regions of other languages embedded in this language
+  #[serde(default)]
+  pub injections: Vec<LanguageInjection>,
 }
 
 impl CustomLang {
@@ -37,6 +40,7 @@ fn to_registration(name: String, custom_lang: CustomLang, base: &Path) -> Regist
     meta_var_char: custom_lang.meta_var_char,
     expando_char: custom_lang.expando_char,
     extensions: custom_lang.extensions,
+    injections: custom_lang.injections,
   }
 }
 
@@ -53,5 +57,24 @@ extensions: [d, e, f]";
     let cus: CustomLang = from_str(yaml).unwrap();
     assert_eq!(cus.language_symbol, None);
     assert_eq!(cus.extensions, vec!["d", "e", "f"]);
+    assert!(cus.injections.is_empty());
+  }
+
+  #[test]
+  fn test_custom_lang_injections() {
+    let yaml = r"
+libraryPath: a/b/c.so
+extensions: [svelte]
+injections:
+- kind: script_element
+  field: content
+  language: js
+- kind: style_element
+  language: css";
+    let cus: CustomLang = from_str(yaml).unwrap();
+    assert_eq!(cus.injections.len(), 2);
+    assert_eq!(cus.injections[0].field.as_deref(), Some("content"));
+    assert_eq!(cus.injections[1].field, None);
+    assert_eq!(cus.injections[1].language, "css");
   }
 }
@@ -1,5 +1,6 @@
-use ast_grep_core::language::TSLanguage;
-use ast_grep_core::Language;
+use ast_grep_core::language::{TSLanguage, TSRange};
+use ast_grep_core::matcher::{KindMatcher, KindMatcherError};
+use ast_grep_core::{Doc, Language, Node};
 
 use ignore::types::{Types, TypesBuilder};
 use libloading::{Error as LibError, Library, Symbol};
@@ -8,10 +9,11 @@ use thiserror::Error;
 use tree_sitter_native::{Language as NativeTS, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::canonicalize;
 use std::path::{Path, PathBuf};
-use std::ptr::{addr_of, addr_of_mut};
 use std::str::FromStr;
+use std::sync::{RwLock, RwLockReadGuard};
 
 mod custom_lang;
 
@@ -29,20 +31,22 @@ pub struct DynamicLang {
 
 impl DynamicLang {
   pub fn all_langs() -> Vec<Self> {
-    Self::langs()
+    let registry = registry();
+    // skip registrations replaced by a later one with the same name
+    registry
+      .langs
       .iter()
       .enumerate()
-      .map(|(index, inner)| DynamicLang {
-        index: index as LangIndex,
-        expando: inner.expando_char,
+      .filter_map(|(index, inner)| {
+        let lang = registry.find_lang(&inner.name)?;
+        (lang.index == index as LangIndex).then_some(lang)
       })
       .collect()
   }
   pub fn file_types(&self) -> Types {
     let mut builder = TypesBuilder::new();
     let inner = self.inner();
-    let mapping = unsafe { &*addr_of!(LANG_INDEX) };
-    for (ext, i) in mapping.iter() {
+    for (ext, i) in registry().index.iter() {
       if *i == self.index {
         builder
           .add(&inner.name, &format!("*.{ext}"))
@@ -77,24 +81,35 @@ impl<'de> Deserialize<'de> for DynamicLang {
 impl FromStr for DynamicLang {
   type Err = String;
   fn from_str(name: &str) -> Result<Self, Self::Err> {
-    let langs = Self::langs();
-    for (i, lang) in langs.iter().enumerate() {
-      if lang.name == name {
-        return Ok(DynamicLang {
-          index: i as LangIndex,
-          expando: lang.expando_char,
-        });
-      }
-    }
-    Err(format!("unknow language `{name}`."))
+    registry()
+      .find_lang(name)
+      .ok_or_else(|| format!("unknow language `{name}`."))
   }
 }
 
+/// Describes a region of another language embedded in a custom language,
+/// e.g. JavaScript in the `<script>` of a template language.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageInjection {
+  /// The kind of node containing the embedded code, e.g. script_element
+  pub kind: String,
+  /// The field of the node whose text is the embedded code.
+  /// The node itself is the embedded code if not specified.
+  pub field: Option<String>,
+  /// The embedded language, e.g. js
+  pub language: String,
+}
+
 struct Inner {
   lang: TSLanguage,
   name: String,
   meta_var_char: char,
   expando_char: char,
+  // leaked like Inner itself so injectable can borrow language names from it
+  injections: &'static [LanguageInjection],
+  // names of injected languages, borrowed from injections
+  injectable: Vec<&'static str>,
   // NOTE: need to hold a reference of lib to avoid cleanup
   _lib: Library,
 }
@@ -109,6 +124,8 @@ pub enum DynamicLangError {
   IncompatibleVersion(usize),
   #[error("cannot get the absolute path of dynamic lib")]
   GetLibPath(#[from] std::io::Error),
+  #[error("invalid node kind `{0}` of injection")]
+  InvalidInjectionKind(String, #[source] KindMatcherError),
 }
 
 /// # Safety: we must keep lib in memory after load it.
@@ -136,9 +153,37 @@ unsafe fn load_ts_language(
   }
 }
 
-// both use vec since lang will be small
-static mut DYNAMIC_LANG: Vec<Inner> = vec![];
-static mut LANG_INDEX: Vec<(String, u32)> = vec![];
+/// All registered languages. A language can be registered again with the same name,
+/// e.g. after the config is reloaded, so registrations are only appended and never removed.
+/// `DynamicLang` created before the update still points to the old and valid `Inner`.
+struct Registry {
+  // both use vec since lang will be small
+  // Inner is leaked because nodes and parsers can refer to the language anytime
+  langs: Vec<&'static Inner>,
+  // extension to the latest registration of the language
+  index: Vec<(String, LangIndex)>,
+}
+
+impl Registry {
+  /// find the latest registration of the language name
+  fn find_lang(&self, name: &str) -> Option<DynamicLang> {
+    let index = self.langs.iter().rposition(|l| l.name == name)?;
+    Some(DynamicLang {
+      index: index as LangIndex,
+      expando: self.langs[index].expando_char,
+    })
+  }
+}
+
+static REGISTRY: RwLock<Registry> = RwLock::new(Registry {
+  langs: Vec::new(),
+  index: Vec::new(),
+});
+
+fn registry() -> RwLockReadGuard<'static, Registry> {
+  // registry is always consistent because it is only updated after all libs are loaded
+  REGISTRY.read().unwrap_or_else(|e| e.into_inner())
+}
 
 #[derive(Default)]
 pub struct Registration {
@@ -148,21 +193,33 @@ pub struct Registration {
   pub meta_var_char: Option<char>,
   pub expando_char: Option<char>,
   pub extensions: Vec<String>,
+  pub injections: Vec<LanguageInjection>,
 }
 
 impl DynamicLang {
+  /// Register languages, or update languages registered with the same name.
+  /// It can be called multiple times from any thread. If any lib fails to load,
+  /// none of the registrations takes effect.
   /// # Safety
-  /// the register function should be called exactly once before use.
-  /// It relies on a global mut static variable to be initialized.
+  /// the dynamic lib must be a valid tree-sitter parser.
+  /// Loading a lib will run its initialization code.
   pub unsafe fn register(regs: Vec<Registration>) -> Result<(), DynamicLangError> {
-    debug_assert!(Self::langs().is_empty());
-    let mut langs = vec![];
-    let mut mapping = vec![];
+    let mut loaded = vec![];
     for reg in regs {
-      Self::register_one(reg, &mut langs, &mut mapping)?;
+      loaded.push(Self::load_one(reg)?);
+    }
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    for (inner, extensions) in loaded {
+      let name = &inner.name;
+      // extensions of the old registration should not resolve to the language anymore
+      let Registry { langs, index } = &mut *registry;
+      index.retain(|(_, i)| langs[*i as usize].name != *name);
+      langs.push(Box::leak(Box::new(inner)));
+      let idx = langs.len() as LangIndex - 1;
+      for ext in extensions {
+        index.push((ext, idx));
+      }
     }
-    _ = std::mem::replace(&mut *addr_of_mut!(DYNAMIC_LANG), langs);
-    _ = std::mem::replace(&mut *addr_of_mut!(LANG_INDEX), mapping);
     Ok(())
   }
 
@@ -170,36 +227,39 @@ impl DynamicLang {
     &self.inner().name
   }
 
-  fn register_one(
-    reg: Registration,
-    langs: &mut Vec<Inner>,
-    mapping: &mut Vec<(String, LangIndex)>,
-  ) -> Result<(), DynamicLangError> {
+  fn load_one(reg: Registration) -> Result<(Inner, Vec<String>), DynamicLangError> {
     // lib must be retained!!
     let (_lib, lang) = unsafe { load_ts_language(reg.lib_path, reg.symbol)? };
     let meta_var_char = reg.meta_var_char.unwrap_or('$');
     let expando_char = reg.expando_char.unwrap_or(meta_var_char);
+    for injection in &reg.injections {
+      if let Err(e) = KindMatcher::try_new(&injection.kind, lang.clone()) {
+        return Err(DynamicLangError::InvalidInjectionKind(
+          injection.kind.clone(),
+          e,
+        ));
+      }
+    }
+    let injections: &'static [LanguageInjection] = Box::leak(reg.injections.into_boxed_slice());
+    let mut injectable: Vec<&'static str> = vec![];
+    for injection in injections {
+      if !injectable.contains(&injection.language.as_str()) {
+        injectable.push(&injection.language);
+      }
+    }
     let inner = Inner {
       name: reg.lang_name,
       lang,
       meta_var_char,
       expando_char,
+      injections,
+      injectable,
       _lib,
     };
-    langs.push(inner);
-    let idx = langs.len() as LangIndex - 1;
-    for ext in reg.extensions {
-      mapping.push((ext, idx));
-    }
-    Ok(())
+    Ok((inner, reg.extensions))
   }
-  fn inner(&self) -> &Inner {
-    let langs = Self::langs();
-    &langs[self.index as usize]
-  }
-
-  fn langs() -> &'static Vec<Inner> {
-    unsafe { &*addr_of!(DYNAMIC_LANG) }
+  fn inner(&self) -> &'static Inner {
+    registry().langs[self.index as usize]
   }
 }
 
@@ -211,14 +271,13 @@ impl Language for DynamicLang {
 
   fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
     let ext = path.as_ref().extension()?.to_str()?;
-    let mapping = unsafe { &*addr_of!(LANG_INDEX) };
-    let langs = Self::langs();
-    mapping.iter().find_map(|(p, idx)| {
+    let registry = registry();
+    registry.index.iter().find_map(|(p, idx)| {
       if p == ext {
         let index = *idx;
         Some(Self {
           index,
-          expando: langs[*idx as usize].expando_char,
+          expando: registry.langs[*idx as usize].expando_char,
         })
       } else {
         None
@@ -254,6 +313,39 @@ impl Language for DynamicLang {
   fn expando_char(&self) -> char {
     self.expando
   }
+
+  fn injectable_languages(&self) -> Option<&'static [&'static str]> {
+    let injectable = &self.inner().injectable;
+    if injectable.is_empty() {
+      None
+    } else {
+      Some(injectable)
+    }
+  }
+
+  fn extract_injections<D: Doc>(&self, root: Node<D>) -> HashMap<String, Vec<TSRange>> {
+    let mut map: HashMap<String, Vec<TSRange>> = HashMap::new();
+    for injection in self.inner().injections {
+      // kinds are validated at registration
+      let Ok(matcher) = KindMatcher::try_new(&injection.kind, root.lang().clone()) else {
+        continue;
+      };
+      for node in root.find_all(matcher) {
+        let region = match &injection.field {
+          Some(field) => node.field(field),
+          None => Some(node.get_node().clone()),
+        };
+        if let Some(region) = region {
+          let range = region.ts_range();
+          map
+            .entry(injection.language.clone())
+            .or_default()
+            .push(range);
+        }
+      }
+    }
+    map
+  }
 }
 
 #[cfg(test)]
@@ -301,6 +393,11 @@ mod test {
       lib_path: PathBuf::from(path),
       meta_var_char: None,
       symbol: "tree_sitter_json".into(),
+      injections: vec![LanguageInjection {
+        kind: "pair".into(),
+        field: Some("value".into()),
+        language: "js".into(),
+      }],
     };
     unsafe {
       DynamicLang::register(vec![registration]).expect("should succeed");
@@ -315,5 +412,48 @@ mod test {
     assert!(sg.root().find("123").is_some());
     let parsed = DynamicLang::from_str("json").expect("ok");
     assert_eq!(parsed.index, lang.index);
+    assert_eq!(lang.injectable_languages(), Some(&["js"][..]));
+    let sg = lang.ast_grep("{\"a\": 1, \"b\": [2]}");
+    let injections = lang.extract_injections(sg.root());
+    assert_eq!(injections["js"].len(), 2);
+    // register again, e.g. after config reload
+    let registration = Registration {
+      lang_name: "json".to_string(),
+      extensions: vec!["jsonc".into()],
+      lib_path: PathBuf::from(path),
+      symbol: "tree_sitter_json".into(),
+      ..Default::default()
+    };
+    unsafe {
+      DynamicLang::register(vec![registration]).expect("should succeed");
+    }
+    let langs = DynamicLang::all_langs();
+    assert_eq!(langs.len(), 1);
+    assert_ne!(langs[0].index, lang.index);
+    assert_eq!(langs[0].expando_char(), '$');
+    assert!(DynamicLang::from_path("a.json").is_none());
+    assert!(DynamicLang::from_path("a.jsonc") == Some(langs[0]));
+    assert!(DynamicLang::from_str("json").expect("ok") == langs[0]);
+    // language created before the update is still usable
+    assert!(lang.ast_grep("[1]").root().find("1").is_some());
+    // invalid injection kind fails the whole registration
+    let registration = Registration {
+      lang_name: "json5".to_string(),
+      extensions: vec!["json5".into()],
+      lib_path: PathBuf::from(path),
+      symbol: "tree_sitter_json".into(),
+      injections: vec![LanguageInjection {
+        kind: "not_a_kind".into(),
+        field: None,
+        language: "js".into(),
+      }],
+      ..Default::default()
+    };
+    let ret = unsafe { DynamicLang::register(vec![registration]) };
+    assert!(matches!(
+      ret,
+      Err(DynamicLangError::InvalidInjectionKind(..))
+    ));
+    assert_eq!(DynamicLang::all_langs().len(), 1);
   }
 }
@@ -32,7 +32,7 @@ impl Language for Html {
         map
           .entry(injected)
           .or_insert_with(Vec::new)
-          .push(node_to_range(&content));
+          .push(content.ts_range());
       };
     }
     let matcher = KindMatcher::new("style_element", lang.clone());
@@ -43,14 +43,14 @@ impl Language for Html {
         map
           .entry(injected)
           .or_insert_with(Vec::new)
-          .push(node_to_range(&content));
+          .push(content.ts_range());
       };
     }
     map
   }
 }
 
-fn find_lang<D: Doc>(node: &Node<D>) -> Option<String> {
+pub(crate) fn find_lang<D: Doc>(node: &Node<D>) -> Option<String> {
   let html = node.lang();
   let attr_matcher = KindMatcher::new("attribute", html.clone());
   let name_matcher = KindMatcher::new("attribute_name", html.clone());
@@ -65,14 +65,6 @@ fn find_lang<D: Doc>(node: &Node<D>) -> Option<String> {
   })
 }
 
-fn node_to_range<D: Doc>(node: &Node<D>) -> TSRange {
-  let r = node.range();
-  let start = node.start_pos();
-  let sp = start.ts_point();
-  let end = node.end_pos();
-  let ep = end.ts_point();
-  TSRange::new(r.start as u32, r.end as u32, &sp, &ep)
-}
 
 #[cfg(test)]
 mod test {
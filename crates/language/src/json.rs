@@ -39,3 +39,53 @@ fn test_json_replace() -> Result<(), TSParseError> {
   assert_eq!(ret, r#"{ "a": 456 }"#);
   Ok(())
 }
+
+fn test_match_jsonc(query: &str, source: &str) {
+  use crate::test::test_match_lang;
+  test_match_lang(query, source, Jsonc);
+}
+
+#[test]
+fn test_jsonc() {
+  let src = r#"{
+  // type check options
+  "compilerOptions": {
+    "strict": true, /* all checks */
+    "lib": [1, 2,],
+  },
+}"#;
+  test_match_jsonc("true", src);
+  test_match_jsonc("{ $$$ }", src);
+  let root = Jsonc.ast_grep(src);
+  let comments = root.root().dfs().filter(|n| n.kind() == "comment").count();
+  assert_eq!(comments, 2);
+}
+
+#[test]
+fn test_jsonc_trailing_comma() {
+  use ast_grep_core::{Matcher, Pattern};
+  let matches = |query: &str, source: &str| {
+    let root = Jsonc.ast_grep(source);
+    let pattern = Pattern::str(query, Jsonc).with_error_nodes_allowed(true);
+    pattern.find_node(root.root()).is_some()
+  };
+  // trailing commas are recovered as ERROR or missing nodes
+  assert!(matches(r#"{"a": 1}"#, r#"{"a": 1,}"#));
+  assert!(matches("[1, 2]", "[1, 2,]"));
+  assert!(!matches("[1, 3]", "[1, 2,]"));
+  crate::test::test_non_match_lang("[1, 2]", "[1, 2,]", Jsonc);
+}
+
+#[test]
+fn test_jsonc_path() {
+  let lang = |p| SupportLang::from_path(p);
+  assert_eq!(lang("tsconfig.json"), Some(SupportLang::Jsonc));
+  assert_eq!(lang(".vscode/settings.jsonc"), Some(SupportLang::Jsonc));
+  assert_eq!(lang("config.json5"), Some(SupportLang::Jsonc));
+  assert_eq!(lang("package.json"), Some(SupportLang::Json));
+  assert_eq!(
+    "json5".parse::<SupportLang>().ok(),
+    Some(SupportLang::Jsonc)
+  );
+  assert_eq!(SupportLang::Jsonc.comment_delimiters(), Some(("//", "")));
+}
@@ -18,16 +18,25 @@ mod html;
 mod json;
 mod kotlin;
 mod lua;
+#[cfg(feature = "lang-markdown")]
+mod markdown;
 mod parsers;
 mod php;
+mod php_html;
 mod python;
 mod ruby;
 mod rust;
 mod scala;
+mod svelte;
 mod swift;
 mod yaml;
+mod zig;
 
 pub use html::Html;
+#[cfg(feature = "lang-markdown")]
+pub use markdown::Markdown;
+pub use php_html::PhpHtml;
+pub use svelte::Svelte;
 
 use ast_grep_core::language::{TSLanguage, TSRange};
 use ast_grep_core::meta_var::MetaVariable;
@@ -136,11 +145,11 @@ macro_rules! impl_alias {
 /// Generates as convenience conversions between the lang types
 /// and `SupportedType`.
 macro_rules! impl_aliases {
-  ($($lang:ident => $as:expr),* $(,)?) => {
-    $(impl_alias!($lang => $as);)*
+  ($($(#[$attr:meta])* $lang:ident => $as:expr),* $(,)?) => {
+    $($(#[$attr])* impl_alias!($lang => $as);)*
     const fn alias(lang: SupportLang) -> &'static [&'static str] {
       match lang {
-        $(SupportLang::$lang => $lang::ALIAS),*
+        $($(#[$attr])* SupportLang::$lang => $lang::ALIAS),*
       }
     }
   };
@@ -182,6 +191,9 @@ impl_lang_expando!(Ruby, language_ruby, 'µ');
 impl_lang_expando!(Rust, language_rust, 'µ');
 //https://docs.swift.org/swift-book/documentation/the-swift-programming-language/lexicalstructure/#Identifiers
 impl_lang_expando!(Swift, language_swift, 'µ');
+// Zig identifiers are ASCII only, non-ASCII names need @"" syntax
+// https://ziglang.org/documentation/master/#Identifiers
+impl_lang_expando!(Zig, language_zig, '_');
 
 // Stub Language without preprocessing
 // Language Name, tree-sitter-name, alias, extension
@@ -189,6 +201,9 @@ impl_lang!(Bash, language_bash);
 impl_lang!(Java, language_java);
 impl_lang!(JavaScript, language_javascript);
 impl_lang!(Json, language_json);
+// JSONC and JSON5 share the JSON grammar, which accepts comments.
+// Trailing commas are recovered as ERROR or missing nodes, skipped by rules with `allowErrorNodes`.
+impl_lang!(Jsonc, language_json);
 impl_lang!(Lua, language_lua);
 impl_lang!(Scala, language_scala);
 impl_lang!(Tsx, language_tsx);
@@ -212,31 +227,82 @@ pub enum SupportLang {
   Java,
   JavaScript,
   Json,
+  Jsonc,
   Kotlin,
   Lua,
+  #[cfg(feature = "lang-markdown")]
+  Markdown,
   Php,
+  PhpHtml,
   Python,
   Ruby,
   Rust,
   Scala,
+  Svelte,
   Swift,
   Tsx,
   TypeScript,
   Yaml,
+  Zig,
 }
 
+/// built-in languages plus those enabled by `lang-*` features
+const ALL_LANGS: &[SupportLang] = {
+  use SupportLang::*;
+  &[
+    Bash,
+    C,
+    Cpp,
+    CSharp,
+    Css,
+    Elixir,
+    Go,
+    Haskell,
+    Html,
+    Java,
+    JavaScript,
+    Json,
+    Jsonc,
+    Kotlin,
+    Lua,
+    #[cfg(feature = "lang-markdown")]
+    Markdown,
+    Php,
+    PhpHtml,
+    Python,
+    Ruby,
+    Rust,
+    Scala,
+    Svelte,
+    Swift,
+    Tsx,
+    TypeScript,
+    Yaml,
+    Zig,
+  ]
+};
+
 impl SupportLang {
   pub const fn all_langs() -> &'static [SupportLang] {
-    use SupportLang::*;
-    &[
-      Bash, C, Cpp, CSharp, Css, Elixir, Go, Haskell, Html, Java, JavaScript, Json, Kotlin, Lua,
-      Php, Python, Ruby, Rust, Scala, Swift, Tsx, TypeScript, Yaml,
-    ]
+    ALL_LANGS
   }
 
   pub fn file_types(&self) -> Types {
     file_types(*self)
   }
+
+  /// Returns the opening and closing delimiters of a comment that fits in one line.
+  /// The closing delimiter is empty if the language has line comments.
+  /// Returns None if the language does not support comments, e.g. JSON.
+  pub const fn comment_delimiters(&self) -> Option<(&'static str, &'static str)> {
+    comment_delimiters(*self)
+  }
+
+  /// Infer the language from the interpreter in a shebang line, e.g. `#!/usr/bin/env python3`.
+  /// Reading the line is left to the caller, `from_path` never opens the file.
+  pub fn from_shebang(line: &str) -> Option<Self> {
+    from_shebang(line)
+  }
 }
 
 impl fmt::Display for SupportLang {
@@ -323,17 +389,23 @@ impl_aliases! {
   Java => &["java"],
   JavaScript => &["javascript", "js", "jsx"],
   Json => &["json"],
+  Jsonc => &["jsonc", "json5"],
   Kotlin => &["kotlin", "kt"],
   Lua => &["lua"],
+  #[cfg(feature = "lang-markdown")]
+  Markdown => &["md", "markdown"],
   Php => &["php"],
+  PhpHtml => &["phphtml", "phtml"],
   Python => &["py", "python"],
   Ruby => &["rb", "ruby"],
   Rust => &["rs", "rust"],
   Scala => &["scala"],
+  Svelte => &["svelte"],
   Swift => &["swift"],
   TypeScript => &["ts", "typescript"],
   Tsx => &["tsx"],
   Yaml => &["yaml", "yml"],
+  Zig => &["zig"],
 }
 
 /// Implements the language names and aliases.
@@ -367,17 +439,23 @@ macro_rules! execute_lang_method {
       S::Java => Java.$method($($pname,)*),
       S::JavaScript => JavaScript.$method($($pname,)*),
       S::Json => Json.$method($($pname,)*),
+      S::Jsonc => Jsonc.$method($($pname,)*),
       S::Kotlin => Kotlin.$method($($pname,)*),
       S::Lua => Lua.$method($($pname,)*),
+      #[cfg(feature = "lang-markdown")]
+      S::Markdown => Markdown.$method($($pname,)*),
       S::Php => Php.$method($($pname,)*),
+      S::PhpHtml => PhpHtml.$method($($pname,)*),
       S::Python => Python.$method($($pname,)*),
       S::Ruby => Ruby.$method($($pname,)*),
       S::Rust => Rust.$method($($pname,)*),
       S::Scala => Scala.$method($($pname,)*),
+      S::Svelte => Svelte.$method($($pname,)*),
       S::Swift => Swift.$method($($pname,)*),
       S::Tsx => Tsx.$method($($pname,)*),
       S::TypeScript => TypeScript.$method($($pname,)*),
       S::Yaml => Yaml.$method($($pname,)*),
+      S::Zig => Zig.$method($($pname,)*),
     }
   }
 }
@@ -393,7 +471,8 @@ macro_rules! impl_lang_method {
 
 impl Language for SupportLang {
   fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
-    from_extension(path.as_ref())
+    let path = path.as_ref();
+    from_file_name(path).or_else(|| from_extension(path))
   }
 
   impl_lang_method!(get_ts_language, () => TSLanguage);
@@ -402,9 +481,17 @@ impl Language for SupportLang {
   impl_lang_method!(extract_meta_var, (source: &str) => Option<MetaVariable>);
   impl_lang_method!(injectable_languages, () => Option<&'static [&'static str]>);
 
+  fn comment_delimiters(&self) -> Option<(&'static str, &'static str)> {
+    comment_delimiters(*self)
+  }
+
   fn extract_injections<D: Doc>(&self, root: Node<D>) -> HashMap<String, Vec<TSRange>> {
     match self {
       SupportLang::Html => Html.extract_injections(root),
+      SupportLang::PhpHtml => PhpHtml.extract_injections(root),
+      SupportLang::Svelte => Svelte.extract_injections(root),
+      #[cfg(feature = "lang-markdown")]
+      SupportLang::Markdown => Markdown.extract_injections(root),
       _ => HashMap::new(),
     }
   }
@@ -431,17 +518,38 @@ fn extensions(lang: SupportLang) -> &'static [&'static str] {
     Java => &["java"],
     JavaScript => &["cjs", "js", "mjs", "jsx"],
     Json => &["json"],
+    Jsonc => &["jsonc", "json5"],
     Kotlin => &["kt", "ktm", "kts"],
     Lua => &["lua"],
+    #[cfg(feature = "lang-markdown")]
+    Markdown => &["md", "markdown"],
     Php => &["php"],
+    PhpHtml => &["phtml"],
     Python => &["py", "py3", "pyi", "bzl"],
     Ruby => &["rb", "rbw", "gemspec"],
     Rust => &["rs"],
     Scala => &["scala", "sc", "sbt"],
+    Svelte => &["svelte"],
     Swift => &["swift"],
     TypeScript => &["ts", "cts", "mts"],
     Tsx => &["tsx"],
     Yaml => &["yaml", "yml"],
+    Zig => &["zig"],
+  }
+}
+
+const fn comment_delimiters(lang: SupportLang) -> Option<(&'static str, &'static str)> {
+  use SupportLang::*;
+  match lang {
+    Bash | Elixir | Python | Ruby | Yaml => Some(("#", "")),
+    C | Cpp | CSharp | Go | Java | JavaScript | Jsonc | Kotlin | Php | Rust | Scala | Swift
+    | Tsx | TypeScript | Zig => Some(("//", "")),
+    Css => Some(("/*", "*/")),
+    Haskell | Lua => Some(("--", "")),
+    Html | PhpHtml | Svelte => Some(("<!--", "-->")),
+    #[cfg(feature = "lang-markdown")]
+    Markdown => Some(("<!--", "-->")),
+    Json => None,
   }
 }
 
@@ -456,6 +564,74 @@ fn from_extension(path: &Path) -> Option<SupportLang> {
     .find(|&l| extensions(l).contains(&ext))
 }
 
+/// Well-known file names, checked before extensions.
+/// Makefile, Dockerfile and Jenkinsfile are not listed because there are no builtin parsers for them.
+fn file_names(lang: SupportLang) -> &'static [&'static str] {
+  use SupportLang::*;
+  match lang {
+    Bash => &[
+      ".bashrc",
+      ".bash_profile",
+      ".profile",
+      ".zshrc",
+      ".zprofile",
+      "PKGBUILD",
+    ],
+    // config files of TypeScript and VSCode allow comments and trailing commas
+    Jsonc => &[
+      "tsconfig.json",
+      "jsconfig.json",
+      ".eslintrc.json",
+      "devcontainer.json",
+    ],
+    Python => &["BUILD", "WORKSPACE", "SConstruct", "SConscript"],
+    Ruby => &[
+      "Gemfile",
+      "Rakefile",
+      "Podfile",
+      "Vagrantfile",
+      "Brewfile",
+      "Fastfile",
+    ],
+    _ => &[],
+  }
+}
+
+fn from_file_name(path: &Path) -> Option<SupportLang> {
+  let name = path.file_name()?.to_str()?;
+  SupportLang::all_langs()
+    .iter()
+    .copied()
+    .find(|&l| file_names(l).contains(&name))
+}
+
+fn from_shebang(line: &str) -> Option<SupportLang> {
+  use SupportLang::*;
+  let mut words = line.strip_prefix("#!")?.split_whitespace();
+  let mut program = words.next()?.rsplit('/').next()?;
+  if program == "env" {
+    // skip env flags like `env -S`
+    program = words.find(|w| !w.starts_with('-'))?;
+  }
+  // python3.11 -> python
+  let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+  let lang = match program {
+    "bash" | "sh" | "zsh" | "dash" | "ksh" => Bash,
+    "elixir" => Elixir,
+    "runghc" | "runhaskell" => Haskell,
+    "node" | "nodejs" => JavaScript,
+    "lua" | "luajit" => Lua,
+    "php" => Php,
+    "python" | "pypy" => Python,
+    "ruby" => Ruby,
+    "scala" => Scala,
+    "swift" => Swift,
+    "deno" | "ts-node" | "tsx" => TypeScript,
+    _ => return None,
+  };
+  Some(lang)
+}
+
 fn add_custom_file_type<'b>(
   builder: &'b mut TypesBuilder,
   file_type: &str,
@@ -474,6 +650,11 @@ fn file_types(lang: SupportLang) -> Types {
   let mut builder = TypesBuilder::new();
   let exts = extensions(lang);
   let lang_name = lang.to_string();
+  for name in file_names(lang) {
+    builder
+      .add(&lang_name, name)
+      .expect("file pattern must compile");
+  }
   add_custom_file_type(&mut builder, &lang_name, exts);
   builder.build().expect("file type must be valid")
 }
@@ -536,5 +717,41 @@ mod test {
     assert_eq!(from_extension(path), Some(SupportLang::Rust));
   }
 
+  #[test]
+  fn test_guess_by_file_name() {
+    let lang = SupportLang::from_path("project/Gemfile");
+    assert_eq!(lang, Some(SupportLang::Ruby));
+    let lang = SupportLang::from_path("/home/user/.bashrc");
+    assert_eq!(lang, Some(SupportLang::Bash));
+    assert_eq!(SupportLang::from_path("Makefile"), None);
+    let types = SupportLang::Ruby.file_types();
+    assert!(types.matched("Rakefile", false).is_whitelist());
+    assert!(types.matched("a.rb", false).is_whitelist());
+  }
+
+  #[test]
+  fn test_guess_by_shebang() {
+    use SupportLang::*;
+    let lang = SupportLang::from_shebang;
+    assert_eq!(lang("#!/usr/bin/env python3\n"), Some(Python));
+    assert_eq!(lang("#!/usr/bin/python3.11"), Some(Python));
+    assert_eq!(lang("#!/bin/sh -e"), Some(Bash));
+    assert_eq!(lang("#!/usr/bin/env -S deno run"), Some(TypeScript));
+    assert_eq!(lang("#!/usr/bin/env node"), Some(JavaScript));
+    assert_eq!(lang("#!/usr/bin/env perl"), None);
+    assert_eq!(lang("print(123)"), None);
+  }
+
+  #[test]
+  fn test_comment_delimiters() {
+    assert_eq!(SupportLang::Rust.comment_delimiters(), Some(("//", "")));
+    assert_eq!(SupportLang::Python.comment_delimiters(), Some(("#", "")));
+    assert_eq!(
+      SupportLang::Html.comment_delimiters(),
+      Some(("<!--", "-->"))
+    );
+    assert_eq!(SupportLang::Json.comment_delimiters(), None);
+  }
+
   // TODO: add test for file_types
 }
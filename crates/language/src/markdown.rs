@@ -0,0 +1,127 @@
+use ast_grep_core::language::TSRange;
+use ast_grep_core::Language;
+use ast_grep_core::{matcher::KindMatcher, Doc, Node};
+use std::collections::HashMap;
+
+/// Markdown document. Fenced code blocks are injected as
+/// the language named in the info string, e.g. ```rust
+#[derive(Clone, Copy, Debug)]
+pub struct Markdown;
+impl Language for Markdown {
+  fn get_ts_language(&self) -> ast_grep_core::language::TSLanguage {
+    crate::parsers::language_markdown()
+  }
+  // fence info strings recognized by SupportLang
+  fn injectable_languages(&self) -> Option<&'static [&'static str]> {
+    Some(&[
+      "bash",
+      "c",
+      "cpp",
+      "cs",
+      "csharp",
+      "css",
+      "elixir",
+      "go",
+      "haskell",
+      "html",
+      "java",
+      "javascript",
+      "js",
+      "jsx",
+      "json",
+      "json5",
+      "jsonc",
+      "kotlin",
+      "lua",
+      "php",
+      "py",
+      "python",
+      "rb",
+      "ruby",
+      "rs",
+      "rust",
+      "scala",
+      "svelte",
+      "swift",
+      "ts",
+      "tsx",
+      "typescript",
+      "yaml",
+      "yml",
+    ])
+  }
+  fn extract_injections<D: Doc>(&self, root: Node<D>) -> HashMap<String, Vec<TSRange>> {
+    let lang = root.lang();
+    let mut map = HashMap::new();
+    let matcher = KindMatcher::new("fenced_code_block", lang.clone());
+    let info_matcher = KindMatcher::new("info_string", lang.clone());
+    for block in root.find_all(matcher) {
+      let Some(info) = block.find(&info_matcher) else {
+        continue;
+      };
+      let Some(injected) = fence_lang(&info.text()) else {
+        continue;
+      };
+      let content = block.children().find(|c| c.kind() == "code_fence_content");
+      if let Some(content) = content {
+        map
+          .entry(injected)
+          .or_insert_with(Vec::new)
+          .push(content.ts_range());
+      }
+    }
+    map
+  }
+}
+
+/// the first word of the info string, e.g. `rust` in ```rust,ignore
+fn fence_lang(info: &str) -> Option<String> {
+  let word = info.split(|c: char| c.is_whitespace() || c == ',' || c == '{');
+  let lang = word.map(str::trim).find(|w| !w.is_empty())?;
+  Some(lang.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn extract(src: &str) -> HashMap<String, Vec<TSRange>> {
+    let root = Markdown.ast_grep(src);
+    Markdown.extract_injections(root.root())
+  }
+
+  #[test]
+  fn test_fence_lang() {
+    assert_eq!(fence_lang("rust").as_deref(), Some("rust"));
+    assert_eq!(fence_lang("rust,ignore").as_deref(), Some("rust"));
+    assert_eq!(fence_lang(" TS {1,3}").as_deref(), Some("ts"));
+    assert_eq!(fence_lang(""), None);
+  }
+
+  #[test]
+  fn test_markdown_extraction() {
+    let src = "
+# Title
+
+```js
+console.log(1)
+```
+
+```rust,ignore
+fn main() {}
+```
+
+```
+no language
+```
+
+```js
+console.log(2)
+```
+";
+    let map = extract(src);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["js"].len(), 2);
+    assert_eq!(map["rust"].len(), 1);
+  }
+}
@@ -88,6 +88,9 @@ pub fn language_lua() -> TSLanguage {
 pub fn language_php() -> TSLanguage {
   into_lang!(tree_sitter_php, LANGUAGE_PHP_ONLY)
 }
+pub fn language_php_html() -> TSLanguage {
+  into_lang!(tree_sitter_php, LANGUAGE_PHP)
+}
 pub fn language_python() -> TSLanguage {
   into_lang!(tree_sitter_python)
 }
@@ -100,6 +103,9 @@ pub fn language_rust() -> TSLanguage {
 pub fn language_scala() -> TSLanguage {
   into_lang!(tree_sitter_scala)
 }
+pub fn language_svelte() -> TSLanguage {
+  into_lang!(tree_sitter_svelte)
+}
 pub fn language_swift() -> TSLanguage {
   into_lang!(tree_sitter_swift)
 }
@@ -112,3 +118,10 @@ pub fn language_typescript() -> TSLanguage {
 pub fn language_yaml() -> TSLanguage {
   into_lang!(tree_sitter_yaml)
 }
+pub fn language_zig() -> TSLanguage {
+  into_lang!(tree_sitter_zig)
+}
+#[cfg(feature = "lang-markdown")]
+pub fn language_markdown() -> TSLanguage {
+  tree_sitter_md::LANGUAGE.into()
+}
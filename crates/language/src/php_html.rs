@@ -0,0 +1,95 @@
+use super::pre_process_pattern;
+use ast_grep_core::language::TSRange;
+use ast_grep_core::Language;
+use ast_grep_core::{Doc, Node};
+use std::collections::HashMap;
+
+/// PHP file mixing HTML and PHP code, e.g. templates.
+/// It is parsed by the full PHP grammar and injects code between `<?php` and `?>` as `php`,
+/// and text outside of PHP tags as `html`. Rules of both languages can match the file.
+/// `Php` itself parses code without HTML text, use languageGlobs to parse `*.php` as `PhpHtml`.
+#[derive(Clone, Copy, Debug)]
+pub struct PhpHtml;
+impl Language for PhpHtml {
+  fn get_ts_language(&self) -> ast_grep_core::language::TSLanguage {
+    crate::parsers::language_php_html()
+  }
+  // same as Php, PHP accepts unicode to be used as some name not var name though
+  fn expando_char(&self) -> char {
+    'µ'
+  }
+  fn pre_process_pattern<'q>(&self, query: &'q str) -> std::borrow::Cow<'q, str> {
+    pre_process_pattern(self.expando_char(), query)
+  }
+  fn injectable_languages(&self) -> Option<&'static [&'static str]> {
+    Some(&["php", "html"])
+  }
+  fn extract_injections<D: Doc>(&self, root: Node<D>) -> HashMap<String, Vec<TSRange>> {
+    let mut html = vec![];
+    let mut php = vec![];
+    let mut push_php = |open: &Node<D>, end: usize, end_point| {
+      let start = open.range().end;
+      if start < end {
+        let start_point = open.end_pos().ts_point();
+        php.push(TSRange::new(
+          start as u32,
+          end as u32,
+          &start_point,
+          &end_point,
+        ));
+      }
+    };
+    // php code starts after an opening tag and ends before a closing tag or EOF
+    let mut open_tag = None;
+    for node in root.dfs() {
+      match &*node.kind() {
+        "text" => html.push(node.ts_range()),
+        "php_tag" => open_tag = Some(node.clone()),
+        "?>" => {
+          if let Some(open) = open_tag.take() {
+            push_php(&open, node.range().start, node.start_pos().ts_point());
+          }
+        }
+        _ => (),
+      }
+    }
+    if let Some(open) = open_tag {
+      push_php(&open, root.range().end, root.end_pos().ts_point());
+    }
+    let mut map = HashMap::new();
+    if !html.is_empty() {
+      map.insert("html".to_string(), html);
+    }
+    if !php.is_empty() {
+      map.insert("php".to_string(), php);
+    }
+    map
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn extract(src: &str) -> HashMap<String, Vec<TSRange>> {
+    let root = PhpHtml.ast_grep(src);
+    PhpHtml.extract_injections(root.root())
+  }
+
+  #[test]
+  fn test_php_html_extraction() {
+    let map = extract("<div><?php echo $a; ?></div>\n<?= $b ?>");
+    assert_eq!(map["html"].len(), 2);
+    assert_eq!(map["php"].len(), 2);
+  }
+
+  #[test]
+  fn test_pure_php() {
+    let map = extract("<?php\necho $a;");
+    assert_eq!(map["php"].len(), 1);
+    assert!(!map.contains_key("html"));
+    let map = extract("<p>no php</p>");
+    assert_eq!(map["html"].len(), 1);
+    assert!(!map.contains_key("php"));
+  }
+}
@@ -0,0 +1,115 @@
+use super::html::find_lang;
+use super::pre_process_pattern;
+use ast_grep_core::language::TSRange;
+use ast_grep_core::Language;
+use ast_grep_core::{matcher::KindMatcher, Doc, Node};
+use std::collections::HashMap;
+
+/// Svelte component. Code in `<script>`, `<style>`
+/// and template expressions like `{expr}` or `{#if expr}` are injected.
+// tree-sitter-svelte scans tags like tree-sitter-html, so use the same expando
+#[derive(Clone, Copy, Debug)]
+pub struct Svelte;
+impl Language for Svelte {
+  fn get_ts_language(&self) -> ast_grep_core::language::TSLanguage {
+    crate::parsers::language_svelte()
+  }
+  fn expando_char(&self) -> char {
+    'z'
+  }
+  fn pre_process_pattern<'q>(&self, query: &'q str) -> std::borrow::Cow<'q, str> {
+    pre_process_pattern(self.expando_char(), query)
+  }
+  fn injectable_languages(&self) -> Option<&'static [&'static str]> {
+    Some(&["css", "js", "ts", "scss", "less", "stylus"])
+  }
+  fn extract_injections<D: Doc>(&self, root: Node<D>) -> HashMap<String, Vec<TSRange>> {
+    let lang = root.lang();
+    let mut map = HashMap::new();
+    let mut push = |injected: String, content: &Node<D>| {
+      map
+        .entry(injected)
+        .or_insert_with(Vec::new)
+        .push(content.ts_range());
+    };
+    // template expressions are written in the same language as the script
+    let mut script_lang = None;
+    let matcher = KindMatcher::new("script_element", lang.clone());
+    for script in root.find_all(matcher) {
+      let injected = find_lang(&script).unwrap_or_else(|| "js".into());
+      script_lang.get_or_insert_with(|| injected.clone());
+      if let Some(content) = script.children().find(|c| c.kind() == "raw_text") {
+        push(injected, &content);
+      }
+    }
+    let matcher = KindMatcher::new("style_element", lang.clone());
+    for style in root.find_all(matcher) {
+      let injected = find_lang(&style).unwrap_or_else(|| "css".into());
+      if let Some(content) = style.children().find(|c| c.kind() == "raw_text") {
+        push(injected, &content);
+      }
+    }
+    // svelte_raw_text is the expression in `{expr}`, `{#if expr}` and `{@html expr}`.
+    // `{#each}` and `{#await}` are not valid expressions so they are skipped
+    let expr_lang = script_lang.unwrap_or_else(|| "js".into());
+    let matcher = KindMatcher::new("svelte_raw_text", lang.clone());
+    for expr in root.find_all(matcher) {
+      let is_expr = expr.parent().map_or(false, |p| {
+        matches!(
+          &*p.kind(),
+          "expression" | "if_start" | "else_if_start" | "html_tag"
+        )
+      });
+      if is_expr {
+        push(expr_lang.clone(), &expr);
+      }
+    }
+    map
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn test_match(query: &str, source: &str) {
+    use crate::test::test_match_lang;
+    test_match_lang(query, source, Svelte);
+  }
+
+  #[test]
+  fn test_svelte_match() {
+    test_match("<$TAG>$$$</$TAG>", "<div>123</div>");
+    test_match(
+      "<button $$$A>$$$</button>",
+      "<button on:click={inc}>+</button>",
+    );
+  }
+
+  fn extract(src: &str) -> HashMap<String, Vec<TSRange>> {
+    let root = Svelte.ast_grep(src);
+    Svelte.extract_injections(root.root())
+  }
+
+  #[test]
+  fn test_svelte_extraction() {
+    let map = extract("<script>let a = 1</script><style>.a{}</style><p>{a}</p>");
+    assert_eq!(map["js"].len(), 2);
+    assert_eq!(map["css"].len(), 1);
+  }
+
+  #[test]
+  fn test_ts_script() {
+    let src = r#"
+<script lang="ts">
+  let count: number = 0
+</script>
+{#if count > 1}
+  <button on:click={() => count++}>{count}</button>
+{/if}"#;
+    let map = extract(src);
+    assert!(!map.contains_key("js"));
+    // script, if condition, event handler and text expression
+    assert_eq!(map["ts"].len(), 4);
+  }
+}
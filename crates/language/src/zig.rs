@@ -0,0 +1,51 @@
+#![cfg(test)]
+use ast_grep_core::source::TSParseError;
+
+use super::*;
+
+fn test_match(query: &str, source: &str) {
+  use crate::test::test_match_lang;
+  test_match_lang(query, source, Zig);
+}
+
+fn test_non_match(query: &str, source: &str) {
+  use crate::test::test_non_match_lang;
+  test_non_match_lang(query, source, Zig);
+}
+
+#[test]
+fn test_zig_str() {
+  test_match("print($A)", "print(123)");
+  test_match("\"123\"", "\"123\"");
+  test_non_match("\"123\"", "\"456\"");
+}
+
+#[test]
+fn test_zig_pattern() {
+  test_match("const $A = $B;", "const a = 1;");
+  test_match("std.debug.print($$$ARGS)", "std.debug.print(\"{}\", .{a});");
+  test_match(
+    "fn $F($$$) $R { $$$ }",
+    r#"
+fn add(a: i32, b: i32) i32 {
+    return a + b;
+}"#,
+  );
+  test_non_match("var $A = $B;", "const a = 1;");
+}
+
+fn test_replace(src: &str, pattern: &str, replacer: &str) -> Result<String, TSParseError> {
+  use crate::test::test_replace_lang;
+  test_replace_lang(src, pattern, replacer, Zig)
+}
+
+#[test]
+fn test_zig_replace() -> Result<(), TSParseError> {
+  let ret = test_replace(
+    "const a = try alloc(10);",
+    "try alloc($N)",
+    "alloc($N) catch unreachable",
+  )?;
+  assert_eq!(ret, "const a = alloc(10) catch unreachable;");
+  Ok(())
+}
@@ -1,42 +1,114 @@
 mod utils;
 
 use dashmap::DashMap;
+use ignore::WalkBuilder;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
-use tower_lsp::jsonrpc::Result;
+use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
-use ast_grep_config::{CombinedScan, RuleCollection, RuleConfig, Severity};
-use ast_grep_core::{language::Language, AstGrep, Doc, StrDoc};
+use ast_grep_config::{CombinedScan, RequireIndex, RuleCollection, Severity};
+use ast_grep_core::{language::Language, AstGrep, Doc, Pattern, StrDoc};
 
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
-use utils::{convert_match_to_diagnostic, diagnostic_to_code_action, RewriteData};
+use utils::{
+  apply_change, convert_match_to_diagnostic, convert_node_to_range, diagnostic_to_code_actions,
+  diagnostics_to_code_lenses, glob_edits, rename_glob, rename_of_diagnostic, selection_range_at,
+  suppression_code_actions, LspSettings, RewriteData,
+};
 
 pub use tower_lsp::{LspService, Server};
 
 pub trait LSPLang: Language + Eq + Send + Sync + 'static {}
 impl<T> LSPLang for T where T: Language + Eq + Send + Sync + 'static {}
 
+/// How workspace files are listed and read from disk.
+/// The CLI implements it to share its ignore files and file size limits with the server.
+pub trait Walker: Send + Sync + 'static {
+  fn walk(&self, base: &Path) -> WalkBuilder {
+    WalkBuilder::new(base)
+  }
+  /// Read the file content, or return None if the file should be skipped.
+  fn read(&self, path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+  }
+}
+
+struct DefaultWalker;
+impl Walker for DefaultWalker {}
+
 struct VersionedAst<D: Doc> {
   version: i32,
   root: AstGrep<D>,
+  /// cached code lenses of the current version, computed lazily on request
+  code_lens: Option<Vec<CodeLens>>,
 }
 
 pub struct Backend<L: LSPLang> {
   client: Client,
   map: DashMap<String, VersionedAst<StrDoc<L>>>,
+  /// scans of changed documents, cancelled when a newer change arrives
+  pending_scans: DashMap<String, CancellationToken>,
   base: PathBuf,
-  rules: std::result::Result<RuleCollection<L>, String>,
+  /// shared with blocking tasks scanning workspace files
+  rules: Arc<std::result::Result<RuleCollection<L>, String>>,
+  settings: RwLock<LspSettings>,
+  /// files found for rules with `requires`, None if no rule has it or the workspace is not indexed yet
+  require_index: RwLock<Option<Arc<RequireIndex>>>,
+  walker: Arc<dyn Walker>,
+  /// directories of rule files, whose globs are updated when files are renamed
+  rule_dirs: Vec<PathBuf>,
+}
+
+/// Watch all files on disk for renaming and creation.
+fn file_operation_options() -> Option<FileOperationRegistrationOptions> {
+  Some(FileOperationRegistrationOptions {
+    filters: vec![FileOperationFilter {
+      scheme: Some("file".into()),
+      pattern: FileOperationPattern {
+        glob: "**/*".into(),
+        matches: None,
+        options: None,
+      },
+    }],
+  })
 }
 
 const FALLBACK_CODE_ACTION_PROVIDER: Option<CodeActionProviderCapability> =
   Some(CodeActionProviderCapability::Simple(true));
 
 const APPLY_ALL_FIXES: &str = "ast-grep.applyAllFixes";
+const APPLY_FIX: &str = "ast-grep.applyFix";
+const OPEN_DOCUMENTATION: &str = "ast-grep.openDocumentation";
 const QUICKFIX_AST_GREP: &str = "quickfix.ast-grep";
 const FIX_ALL_AST_GREP: &str = "source.fixAll.ast-grep";
+/// Custom request to search a pattern, registered by `LspService::build(..).custom_method`.
+pub const SEARCH: &str = "ast-grep/search";
+
+/// Parameters of the `ast-grep/search` request.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchParams<L> {
+  pub pattern: String,
+  pub language: L,
+  /// search all files in the workspace, not only opened documents
+  #[serde(default)]
+  pub include_workspace: bool,
+}
+
+/// A match of the `ast-grep/search` request.
+#[derive(Serialize, Debug)]
+pub struct SearchMatch {
+  pub uri: Url,
+  pub range: Range,
+  pub text: String,
+}
 
 fn code_action_provider(
   client_capability: &ClientCapabilities,
@@ -64,18 +136,51 @@ fn code_action_provider(
 #[tower_lsp::async_trait]
 impl<L: LSPLang> LanguageServer for Backend<L> {
   async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+    if let Some(options) = params.initialization_options.clone() {
+      self.update_settings(options);
+    }
     Ok(InitializeResult {
       server_info: Some(ServerInfo {
         name: "ast-grep language server".to_string(),
         version: None,
       }),
       capabilities: ServerCapabilities {
-        // TODO: change this to incremental
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+          TextDocumentSyncKind::INCREMENTAL,
+        )),
         code_action_provider: code_action_provider(&params.capabilities)
           .or(FALLBACK_CODE_ACTION_PROVIDER),
+        diagnostic_provider: Some(DiagnosticServerCapabilities::Options(DiagnosticOptions {
+          identifier: Some("ast-grep".into()),
+          inter_file_dependencies: false,
+          workspace_diagnostics: true,
+          work_done_progress_options: Default::default(),
+        })),
+        code_lens_provider: Some(CodeLensOptions {
+          resolve_provider: Some(false),
+        }),
+        selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+          prepare_provider: Some(true),
+          work_done_progress_options: Default::default(),
+        })),
+        workspace: Some(WorkspaceServerCapabilities {
+          workspace_folders: None,
+          file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+            did_create: file_operation_options(),
+            did_rename: file_operation_options(),
+            will_rename: file_operation_options(),
+            ..Default::default()
+          }),
+        }),
         execute_command_provider: Some(ExecuteCommandOptions {
-          commands: vec![APPLY_ALL_FIXES.to_string()],
+          commands: vec![
+            APPLY_ALL_FIXES.to_string(),
+            APPLY_FIX.to_string(),
+            OPEN_DOCUMENTATION.to_string(),
+          ],
           work_done_progress_options: Default::default(),
         }),
         ..ServerCapabilities::default()
@@ -90,7 +195,7 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
       .await;
 
     // Report errors loading config once, upon initialization
-    if let Err(error) = &self.rules {
+    if let Err(error) = &*self.rules {
       // popup message
       self
         .client
@@ -108,6 +213,9 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
         )
         .await;
     }
+    if self.update_require_index().await {
+      self.republish_diagnostics().await;
+    }
   }
 
   async fn shutdown(&self) -> Result<()> {
@@ -121,11 +229,14 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
       .await;
   }
 
-  async fn did_change_configuration(&self, _: DidChangeConfigurationParams) {
+  async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
     self
       .client
       .log_message(MessageType::INFO, "configuration changed!")
       .await;
+    if self.update_settings(params.settings) {
+      self.republish_diagnostics().await;
+    }
   }
 
   async fn did_change_watched_files(&self, _: DidChangeWatchedFilesParams) {
@@ -134,6 +245,22 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
       .log_message(MessageType::INFO, "watched files have changed!")
       .await;
   }
+  async fn will_rename_files(&self, params: RenameFilesParams) -> Result<Option<WorkspaceEdit>> {
+    Ok(self.on_will_rename_files(params))
+  }
+
+  async fn did_rename_files(&self, params: RenameFilesParams) {
+    for file in params.files {
+      self.on_rename_file(file).await;
+    }
+  }
+
+  async fn did_create_files(&self, params: CreateFilesParams) {
+    for file in params.files {
+      self.on_create_file(file).await;
+    }
+  }
+
   async fn did_open(&self, params: DidOpenTextDocumentParams) {
     self
       .client
@@ -151,6 +278,10 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
       .client
       .log_message(MessageType::INFO, "file saved!")
       .await;
+    // the saved file may add or remove what `requires` looks for
+    if self.update_require_index().await {
+      self.republish_diagnostics().await;
+    }
   }
 
   async fn did_close(&self, params: DidCloseTextDocumentParams) {
@@ -165,9 +296,178 @@ impl<L: LSPLang> LanguageServer for Backend<L> {
     Ok(self.on_code_action(params).await)
   }
 
+  async fn diagnostic(
+    &self,
+    params: DocumentDiagnosticParams,
+  ) -> Result<DocumentDiagnosticReportResult> {
+    let uri = params.text_document.uri;
+    let items = self.pull_diagnostics(&uri).map_or(vec![], |(_, d)| d);
+    let report = FullDocumentDiagnosticReport {
+      result_id: None,
+      items,
+    };
+    Ok(DocumentDiagnosticReportResult::Report(
+      DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+        related_documents: None,
+        full_document_diagnostic_report: report,
+      }),
+    ))
+  }
+
+  async fn workspace_diagnostic(
+    &self,
+    _: WorkspaceDiagnosticParams,
+  ) -> Result<WorkspaceDiagnosticReportResult> {
+    let items = self.on_workspace_diagnostic().await;
+    Ok(WorkspaceDiagnosticReportResult::Report(
+      WorkspaceDiagnosticReport { items },
+    ))
+  }
+
+  async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+    Ok(self.on_code_lens(params))
+  }
+
   async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
     Ok(self.on_execute_command(params).await)
   }
+
+  async fn selection_range(
+    &self,
+    params: SelectionRangeParams,
+  ) -> Result<Option<Vec<SelectionRange>>> {
+    Ok(self.on_selection_range(params))
+  }
+
+  async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+    Ok(self.on_formatting(params.text_document, None))
+  }
+
+  async fn range_formatting(
+    &self,
+    params: DocumentRangeFormattingParams,
+  ) -> Result<Option<Vec<TextEdit>>> {
+    Ok(self.on_formatting(params.text_document, Some(params.range)))
+  }
+
+  async fn prepare_rename(
+    &self,
+    params: TextDocumentPositionParams,
+  ) -> Result<Option<PrepareRenameResponse>> {
+    Ok(self.on_prepare_rename(params))
+  }
+
+  async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+    Ok(self.on_rename(params).await)
+  }
+}
+
+impl<L: LSPLang + DeserializeOwned> Backend<L> {
+  /// Handle the `ast-grep/search` request. Documents in other languages are skipped.
+  pub async fn search(&self, params: SearchParams<L>) -> Result<Vec<SearchMatch>> {
+    let SearchParams {
+      pattern,
+      language,
+      include_workspace,
+    } = params;
+    let pattern = Pattern::try_new(&pattern, language.clone())
+      .map_err(|e| Error::invalid_params(e.to_string()))?;
+    let mut matches = vec![];
+    let mut opened = HashSet::new();
+    for entry in self.map.iter() {
+      let Ok(uri) = Url::parse(entry.key()) else {
+        continue;
+      };
+      if entry.root.lang() == &language {
+        matches.extend(search_root(&uri, &entry.root, &pattern));
+      }
+      opened.insert(uri);
+    }
+    if !include_workspace {
+      return Ok(matches);
+    }
+    let base = self.canonical_base();
+    let walker = self.walker.clone();
+    // walk, read and parse files off the async runtime, skipping other languages before parsing
+    let search = move || {
+      let files = walker.walk(&base).build().flatten();
+      let found = files.filter_map(|entry| {
+        let path = entry.path();
+        if !entry.file_type()?.is_file() || L::from_path(path).as_ref() != Some(&language) {
+          return None;
+        }
+        let uri = Url::from_file_path(path).ok()?;
+        if opened.contains(&uri) {
+          return None;
+        }
+        let root = AstGrep::new(walker.read(path)?, language.clone());
+        Some(search_root(&uri, &root, &pattern))
+      });
+      found.flatten().collect::<Vec<_>>()
+    };
+    matches.extend(
+      tokio::task::spawn_blocking(search)
+        .await
+        .unwrap_or_default(),
+    );
+    // stable sort keeps matches of a file in order
+    matches.sort_by(|a, b| a.uri.cmp(&b.uri));
+    Ok(matches)
+  }
+}
+
+/// Diagnostics of the rules applying to the file, except rules disabled by the settings.
+fn scan_diagnostics<L: LSPLang>(
+  rules: &RuleCollection<L>,
+  base: &Path,
+  settings: &LspSettings,
+  require_index: Option<&RequireIndex>,
+  uri: &Url,
+  root: &AstGrep<StrDoc<L>>,
+) -> Option<Vec<Diagnostic>> {
+  let absolute_path = uri.to_file_path().ok()?;
+  let path = absolute_path.strip_prefix(base).unwrap_or(&absolute_path);
+  let mut rules = rules.for_path(path);
+  rules.retain(|rule| settings.allows(rule));
+  if rules.is_empty() {
+    return None;
+  }
+  let unused_suppression_rule =
+    CombinedScan::unused_config(Severity::Hint, rules[0].language.clone());
+  let mut scan = CombinedScan::new(rules);
+  scan.set_unused_suppression_rule(&unused_suppression_rule);
+  if let Some(index) = require_index {
+    scan.set_require_index(index);
+  }
+  let pre_scan = scan.find(root);
+  let matches = scan.scan(root, pre_scan, false).matches;
+  let mut diagnostics = vec![];
+  for (rule, ms) in matches {
+    let to_diagnostic = |m| convert_match_to_diagnostic(uri, m, rule);
+    diagnostics.extend(ms.into_iter().map(to_diagnostic));
+  }
+  Some(diagnostics)
+}
+
+fn search_root<L: LSPLang>(
+  uri: &Url,
+  root: &AstGrep<StrDoc<L>>,
+  pattern: &Pattern<L>,
+) -> Vec<SearchMatch> {
+  let found = root.root().find_all(pattern).map(|nm| SearchMatch {
+    uri: uri.clone(),
+    range: convert_node_to_range(&nm),
+    text: nm.text().to_string(),
+  });
+  found.collect()
+}
+
+/// An identifier that a rule's fix renames, found under the cursor.
+struct RenameTarget {
+  rule_id: NumberOrString,
+  range: Range,
+  name: String,
+  fixed: String,
 }
 
 impl<L: LSPLang> Backend<L> {
@@ -178,48 +478,145 @@ impl<L: LSPLang> Backend<L> {
   ) -> Self {
     Self {
       client,
-      rules,
+      rules: Arc::new(rules),
       base,
       map: DashMap::new(),
+      pending_scans: DashMap::new(),
+      settings: RwLock::new(LspSettings::default()),
+      require_index: RwLock::new(None),
+      walker: Arc::new(DefaultWalker),
+      rule_dirs: vec![],
     }
   }
 
-  fn get_rules(&self, uri: &Url) -> Option<Vec<&RuleConfig<L>>> {
-    let absolute_path = uri.to_file_path().ok()?;
-    let path = if let Ok(p) = absolute_path.strip_prefix(&self.base) {
-      p
-    } else {
-      &absolute_path
+  /// Use the walker to list and read workspace files instead of the default one.
+  pub fn with_walker(mut self, walker: impl Walker) -> Self {
+    self.walker = Arc::new(walker);
+    self
+  }
+
+  /// Offer to update `files` and `ignores` globs in the rule files under the directories
+  /// when the paths they name are renamed.
+  pub fn with_rule_dirs(mut self, rule_dirs: Vec<PathBuf>) -> Self {
+    self.rule_dirs = rule_dirs;
+    self
+  }
+
+  /// Returns false if the settings are malformed and ignored.
+  fn update_settings(&self, value: Value) -> bool {
+    let Some(settings) = LspSettings::from_value(value) else {
+      return false;
     };
-    let rules = self.rules.as_ref().ok()?.for_path(path);
-    Some(rules)
+    *self.settings.write().expect("lock should not be poisoned") = settings;
+    true
   }
 
-  fn get_diagnostics(
-    &self,
-    uri: &Url,
-    versioned: &VersionedAst<StrDoc<L>>,
-  ) -> Option<Vec<Diagnostic>> {
-    let rules = self.get_rules(uri)?;
-    if rules.is_empty() {
-      return None;
+  async fn republish_diagnostics(&self) {
+    let keys: Vec<_> = self.map.iter().map(|v| v.key().clone()).collect();
+    for key in keys {
+      let Ok(uri) = Url::parse(&key) else {
+        continue;
+      };
+      // compute diagnostics in a scope so the map guard is not held across await
+      let (version, diagnostics) = {
+        let Some(mut versioned) = self.map.get_mut(&key) else {
+          continue;
+        };
+        versioned.code_lens = None;
+        let diagnostics = self.get_diagnostics(&uri, &versioned.root);
+        (versioned.version, diagnostics.unwrap_or_default())
+      };
+      self
+        .client
+        .publish_diagnostics(uri, diagnostics, Some(version))
+        .await;
     }
-    let unused_suppression_rule =
-      CombinedScan::unused_config(Severity::Hint, rules[0].language.clone());
-    let mut scan = CombinedScan::new(rules);
-    scan.set_unused_suppression_rule(&unused_suppression_rule);
-    let pre_scan = scan.find(&versioned.root);
-    let matches = scan.scan(&versioned.root, pre_scan, false).matches;
-    let mut diagnostics = vec![];
-    for (rule, ms) in matches {
-      let to_diagnostic = |m| convert_match_to_diagnostic(m, rule);
-      diagnostics.extend(ms.into_iter().map(to_diagnostic));
+  }
+
+  fn get_diagnostics(&self, uri: &Url, root: &AstGrep<StrDoc<L>>) -> Option<Vec<Diagnostic>> {
+    let rules = self.rules.as_ref().as_ref().ok()?;
+    let settings = self.settings.read().expect("lock should not be poisoned");
+    let index = self.get_require_index();
+    scan_diagnostics(rules, &self.base, &settings, index.as_deref(), uri, root)
+  }
+
+  fn get_require_index(&self) -> Option<Arc<RequireIndex>> {
+    let index = self
+      .require_index
+      .read()
+      .expect("lock should not be poisoned");
+    index.clone()
+  }
+
+  /// Search the whole workspace for the files required by rules with `requires`,
+  /// like `sg scan` does. `requires.file` globs are relative to the workspace root.
+  /// Returns false if no rule has `requires`.
+  async fn update_require_index(&self) -> bool {
+    let Ok(rules) = &*self.rules else {
+      return false;
+    };
+    let mut has_requires = false;
+    rules.for_each_rule(|rule| has_requires |= rule.requires.is_some());
+    if !has_requires {
+      return false;
     }
-    Some(diagnostics)
+    let rules = self.rules.clone();
+    let walk_base = self.canonical_base();
+    let walker = self.walker.clone();
+    let build = move || {
+      let mut index = RequireIndex::default();
+      let Ok(rules) = &*rules else {
+        return index;
+      };
+      let mut require_rules = vec![];
+      rules.for_each_rule(|rule| {
+        if rule.requires.is_some() {
+          require_rules.push(rule);
+        }
+      });
+      for entry in walker.walk(&walk_base).build().flatten() {
+        if !entry.file_type().map_or(false, |t| t.is_file()) {
+          continue;
+        }
+        let path = entry.path();
+        let Some(lang) = L::from_path(path) else {
+          continue;
+        };
+        let lang_rules: Vec<_> = require_rules
+          .iter()
+          .copied()
+          .filter(|rule| rule.language == lang)
+          .collect();
+        if lang_rules.is_empty() {
+          continue;
+        }
+        let Some(text) = walker.read(path) else {
+          continue;
+        };
+        let root = AstGrep::new(text, lang);
+        let relative = path.strip_prefix(&walk_base).unwrap_or(path);
+        CombinedScan::new(lang_rules).index_requires(relative, &root, &mut index);
+      }
+      index
+    };
+    let Ok(index) = tokio::task::spawn_blocking(build).await else {
+      return false;
+    };
+    *self
+      .require_index
+      .write()
+      .expect("lock should not be poisoned") = Some(Arc::new(index));
+    self
+      .client
+      .log_message(MessageType::LOG, "Indexed files required by rules.")
+      .await;
+    true
   }
 
   async fn publish_diagnostics(&self, uri: Url, versioned: &VersionedAst<StrDoc<L>>) -> Option<()> {
-    let diagnostics = self.get_diagnostics(&uri, versioned).unwrap_or_default();
+    let diagnostics = self
+      .get_diagnostics(&uri, &versioned.root)
+      .unwrap_or_default();
     self
       .client
       .publish_diagnostics(uri, diagnostics, Some(versioned.version))
@@ -227,6 +624,135 @@ impl<L: LSPLang> Backend<L> {
     Some(())
   }
 
+  /// Run `f` on the parsed document and its version, if the document is opened.
+  /// Opened documents use the editor's content, other files are read from disk.
+  fn with_root<T>(
+    &self,
+    uri: &Url,
+    f: impl FnOnce(Option<i32>, &AstGrep<StrDoc<L>>) -> Option<T>,
+  ) -> Option<T> {
+    if let Some(versioned) = self.map.get(uri.as_str()) {
+      return f(Some(versioned.version), &versioned.root);
+    }
+    let lang = Self::infer_lang_from_uri(uri)?;
+    let path = uri.to_file_path().ok()?;
+    let text = self.walker.read(&path)?;
+    let root = AstGrep::new(text, lang);
+    f(None, &root)
+  }
+
+  /// Get diagnostics and document version of the uri.
+  fn pull_diagnostics(&self, uri: &Url) -> Option<(Option<i64>, Vec<Diagnostic>)> {
+    self.with_root(uri, |version, root| {
+      let diagnostics = self.get_diagnostics(uri, root)?;
+      Some((version.map(i64::from), diagnostics))
+    })
+  }
+
+  fn canonical_base(&self) -> PathBuf {
+    self
+      .base
+      .canonicalize()
+      .unwrap_or_else(|_| self.base.clone())
+  }
+
+  /// Scan workspace files, respecting ignore files like the CLI does, and convert
+  /// the diagnostics of every file with `f`. Files are walked, read, parsed and scanned
+  /// on a blocking thread since it can take long in large projects.
+  /// Opened documents are scanned with the editor's content instead.
+  async fn scan_workspace<T, F>(&self, f: F) -> Vec<(Url, Option<i32>, T)>
+  where
+    T: Send + 'static,
+    F: Fn(&Url, &AstGrep<StrDoc<L>>, Vec<Diagnostic>) -> Option<T> + Send + Sync + 'static,
+  {
+    if self.rules.is_err() {
+      return vec![];
+    }
+    let f = Arc::new(f);
+    let scan_file = f.clone();
+    let rules = self.rules.clone();
+    let base = self.base.clone();
+    let walk_base = self.canonical_base();
+    let walker = self.walker.clone();
+    let settings = self
+      .settings
+      .read()
+      .expect("lock should not be poisoned")
+      .clone();
+    let require_index = self.get_require_index();
+    let opened: HashSet<_> = self.map.iter().map(|entry| entry.key().clone()).collect();
+    let scan = move || {
+      let mut scanned = vec![];
+      let mut opened_files = vec![];
+      let Ok(rules) = &*rules else {
+        return (scanned, opened_files);
+      };
+      for entry in walker.walk(&walk_base).build().flatten() {
+        if !entry.file_type().map_or(false, |t| t.is_file()) {
+          continue;
+        }
+        let path = entry.path();
+        let Ok(uri) = Url::from_file_path(path) else {
+          continue;
+        };
+        if opened.contains(uri.as_str()) {
+          opened_files.push(uri);
+          continue;
+        }
+        let Some(lang) = L::from_path(path) else {
+          continue;
+        };
+        let Some(text) = walker.read(path) else {
+          continue;
+        };
+        let root = AstGrep::new(text, lang);
+        let index = require_index.as_deref();
+        let Some(diagnostics) = scan_diagnostics(rules, &base, &settings, index, &uri, &root)
+        else {
+          continue;
+        };
+        if let Some(ret) = scan_file(&uri, &root, diagnostics) {
+          scanned.push((uri, None, ret));
+        }
+      }
+      (scanned, opened_files)
+    };
+    let (mut scanned, opened_files) = tokio::task::spawn_blocking(scan).await.unwrap_or_default();
+    for uri in opened_files {
+      let Some(versioned) = self.map.get(uri.as_str()) else {
+        continue;
+      };
+      let Some(diagnostics) = self.get_diagnostics(&uri, &versioned.root) else {
+        continue;
+      };
+      if let Some(ret) = f(&uri, &versioned.root, diagnostics) {
+        let version = versioned.version;
+        drop(versioned);
+        scanned.push((uri, Some(version), ret));
+      }
+    }
+    scanned
+  }
+
+  async fn on_workspace_diagnostic(&self) -> Vec<WorkspaceDocumentDiagnosticReport> {
+    let scanned = self
+      .scan_workspace(|_, _, diagnostics| Some(diagnostics))
+      .await;
+    let mut items = vec![];
+    for (uri, version, diagnostics) in scanned {
+      let report = WorkspaceFullDocumentDiagnosticReport {
+        uri,
+        version: version.map(i64::from),
+        full_document_diagnostic_report: FullDocumentDiagnosticReport {
+          result_id: None,
+          items: diagnostics,
+        },
+      };
+      items.push(WorkspaceDocumentDiagnosticReport::Full(report));
+    }
+    items
+  }
+
   async fn get_path_of_first_workspace(&self) -> Option<std::path::PathBuf> {
     let folders = self.client.workspace_folders().await.ok()??;
     let folder = folders.first()?;
@@ -264,6 +790,7 @@ impl<L: LSPLang> Backend<L> {
     let versioned = VersionedAst {
       version: text_doc.version,
       root,
+      code_lens: None,
     };
     self
       .client
@@ -277,31 +804,273 @@ impl<L: LSPLang> Backend<L> {
   async fn on_change(&self, params: DidChangeTextDocumentParams) -> Option<()> {
     let text_doc = params.text_document;
     let uri = text_doc.uri.as_str();
-    let text = &params.content_changes[0].text;
     self
       .client
       .log_message(MessageType::LOG, "Parsing changed doc.")
       .await;
     let lang = Self::infer_lang_from_uri(&text_doc.uri)?;
-    let root = AstGrep::new(text, lang);
-    let mut versioned = self.map.get_mut(uri)?;
-    // skip old version update
-    if versioned.version > text_doc.version {
+    {
+      let mut versioned = self.map.get_mut(uri)?;
+      // skip old version update
+      if versioned.version > text_doc.version {
+        return None;
+      }
+      for change in params.content_changes {
+        apply_change(&mut versioned.root, change, &lang);
+      }
+      versioned.version = text_doc.version;
+      versioned.code_lens = None;
+    } // release the document so newer changes can be applied while waiting
+    let token = CancellationToken::new();
+    if let Some(previous) = self.pending_scans.insert(uri.to_owned(), token.clone()) {
+      previous.cancel();
+    }
+    let settings = self
+      .settings
+      .read()
+      .expect("lock should not be poisoned")
+      .clone();
+    if settings.debounce_ms > 0 {
+      tokio::time::sleep(Duration::from_millis(settings.debounce_ms)).await;
+    }
+    // a newer version arrived during debounce or scan, it will publish its own diagnostics
+    if token.is_cancelled() {
       return None;
     }
-    *versioned = VersionedAst {
-      version: text_doc.version,
-      root,
+    // clone the document so the map is not locked while scanning
+    let root = self.map.get(uri)?.root.clone();
+    let rules = self.rules.clone();
+    let base = self.base.clone();
+    let require_index = self.get_require_index();
+    let scan_uri = text_doc.uri.clone();
+    let scan_token = token.clone();
+    let scan = move || {
+      if scan_token.is_cancelled() {
+        return None;
+      }
+      let rules = rules.as_ref().as_ref().ok()?;
+      let index = require_index.as_deref();
+      scan_diagnostics(rules, &base, &settings, index, &scan_uri, &root)
     };
+    let diagnostics = tokio::task::spawn_blocking(scan).await.ok()?;
+    if token.is_cancelled() || !self.is_latest_version(uri, text_doc.version) {
+      return None;
+    }
+    let diagnostics = diagnostics.unwrap_or_default();
     self
       .client
       .log_message(MessageType::LOG, "Publishing diagnostics.")
       .await;
-    self.publish_diagnostics(text_doc.uri, &versioned).await;
+    self
+      .client
+      .publish_diagnostics(text_doc.uri, diagnostics, Some(text_doc.version))
+      .await;
+    Some(())
+  }
+
+  fn is_latest_version(&self, uri: &str, version: i32) -> bool {
+    self.map.get(uri).map_or(false, |v| v.version == version)
+  }
+
+  /// Move the opened documents to the new uri, including those under a renamed directory.
+  /// Their language and rules are inferred again from the new path,
+  /// and diagnostics under the old uri are cleared.
+  async fn on_rename_file(&self, file: FileRename) -> Option<()> {
+    let old_uri = Url::parse(&file.old_uri).ok()?;
+    let new_uri = Url::parse(&file.new_uri).ok()?;
+    self.warn_rules_with_path(&old_uri, &new_uri).await;
+    let old_prefix = old_uri.as_str().trim_end_matches('/');
+    let new_prefix = new_uri.as_str().trim_end_matches('/');
+    let moved: Vec<_> = self
+      .map
+      .iter()
+      .map(|entry| entry.key().clone())
+      .filter(|key| match key.strip_prefix(old_prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+      })
+      .collect();
+    for old_key in moved {
+      let new_key = format!("{new_prefix}{}", &old_key[old_prefix.len()..]);
+      self.move_document(&old_key, &new_key).await;
+    }
+    Some(())
+  }
+
+  async fn move_document(&self, old_key: &str, new_key: &str) -> Option<()> {
+    let old_uri = Url::parse(old_key).ok()?;
+    let new_uri = Url::parse(new_key).ok()?;
+    let (_, old) = self.map.remove(old_key)?;
+    self.client.publish_diagnostics(old_uri, vec![], None).await;
+    let lang = Self::infer_lang_from_uri(&new_uri)?;
+    let versioned = VersionedAst {
+      version: old.version,
+      root: AstGrep::new(old.root.source(), lang),
+      code_lens: None,
+    };
+    self.publish_diagnostics(new_uri.clone(), &versioned).await;
+    self.map.insert(new_uri.to_string(), versioned);
+    Some(())
+  }
+
+  /// A document can be opened before its file is created, e.g. a new untitled file is saved.
+  /// Parse it again since the language may be unknown before.
+  async fn on_create_file(&self, file: FileCreate) -> Option<()> {
+    let uri = Url::parse(&file.uri).ok()?;
+    let lang = Self::infer_lang_from_uri(&uri)?;
+    let versioned = {
+      let old = self.map.get(uri.as_str())?;
+      VersionedAst {
+        version: old.version,
+        root: AstGrep::new(old.root.source(), lang),
+        code_lens: None,
+      }
+    };
+    self.publish_diagnostics(uri.clone(), &versioned).await;
+    self.map.insert(uri.to_string(), versioned);
     Some(())
   }
+
+  /// Update the globs in rule files that start with a renamed path, e.g. `src/old/**`
+  /// when `src/old` is renamed. The client applies the edit before renaming the files.
+  fn on_will_rename_files(&self, params: RenameFilesParams) -> Option<WorkspaceEdit> {
+    let rules = self.rules.as_ref().as_ref().ok()?;
+    let mut renames = vec![];
+    for file in &params.files {
+      let Some((old_path, new_path)) = self.relative_paths(file) else {
+        continue;
+      };
+      rules.for_each_rule(|rule| {
+        for glob in rule.files.iter().chain(rule.ignores.iter()).flatten() {
+          if let Some(renamed) = rename_glob(glob, &old_path, &new_path) {
+            renames.push((glob.clone(), renamed));
+          }
+        }
+      });
+    }
+    if renames.is_empty() {
+      return None;
+    }
+    renames.sort();
+    renames.dedup();
+    let mut changes = HashMap::new();
+    for dir in &self.rule_dirs {
+      for entry in WalkBuilder::new(dir).build().flatten() {
+        let path = entry.path();
+        let is_yaml = path
+          .extension()
+          .map_or(false, |ext| ext == "yml" || ext == "yaml");
+        if !is_yaml {
+          continue;
+        }
+        let Ok(yaml) = std::fs::read_to_string(path) else {
+          continue;
+        };
+        let edits = glob_edits(&yaml, &renames);
+        if edits.is_empty() {
+          continue;
+        }
+        if let Ok(uri) = Url::from_file_path(path) {
+          changes.insert(uri, edits);
+        }
+      }
+    }
+    if changes.is_empty() {
+      return None;
+    }
+    Some(WorkspaceEdit::new(changes))
+  }
+
+  /// Warn about rules whose `files` or `ignores` globs match the renamed files differently,
+  /// unless the globs were updated on `workspace/willRenameFiles`, e.g. `**/legacy/*.ts`.
+  async fn warn_rules_with_path(&self, old_uri: &Url, new_uri: &Url) -> Option<()> {
+    let rules = self.rules.as_ref().as_ref().ok()?;
+    let old_path = self.relative_path(old_uri)?;
+    let new_path = self.relative_path(new_uri)?;
+    let mut ids = HashSet::new();
+    for (old, new) in self.renamed_paths(new_uri, &old_path, &new_path) {
+      let Some(lang) = L::from_path(Path::new(&new)) else {
+        continue;
+      };
+      let before = rules.get_rule_from_lang(Path::new(&old), lang.clone());
+      let after = rules.get_rule_from_lang(Path::new(&new), lang);
+      let changed = before
+        .iter()
+        .filter(|r| !after.iter().any(|a| a.id == r.id))
+        .chain(
+          after
+            .iter()
+            .filter(|a| !before.iter().any(|r| r.id == a.id)),
+        );
+      for rule in changed {
+        let mut globs = rule.files.iter().chain(rule.ignores.iter()).flatten();
+        let updated = !self.rule_dirs.is_empty()
+          && globs.any(|g| rename_glob(g, &old_path, &new_path).is_some());
+        if (rule.files.is_some() || rule.ignores.is_some()) && !updated {
+          ids.insert(rule.id.clone());
+        }
+      }
+    }
+    if ids.is_empty() {
+      return None;
+    }
+    let mut ids: Vec<_> = ids.into_iter().collect();
+    ids.sort();
+    let message = format!(
+      "Rule {} should update `files` or `ignores` since `{old_path}` is renamed to `{new_path}`.",
+      ids.join(", ")
+    );
+    self
+      .client
+      .show_message(MessageType::WARNING, message)
+      .await;
+    Some(())
+  }
+
+  fn relative_paths(&self, file: &FileRename) -> Option<(String, String)> {
+    let old_path = self.relative_path(&Url::parse(&file.old_uri).ok()?)?;
+    let new_path = self.relative_path(&Url::parse(&file.new_uri).ok()?)?;
+    Some((old_path, new_path))
+  }
+
+  /// Old and new paths of the renamed file, or of the files under the renamed directory.
+  fn renamed_paths(&self, new_uri: &Url, old_path: &str, new_path: &str) -> Vec<(String, String)> {
+    let mut paths = vec![(old_path.to_string(), new_path.to_string())];
+    let Ok(new_dir) = new_uri.to_file_path() else {
+      return paths;
+    };
+    if !new_dir.is_dir() {
+      return paths;
+    }
+    for entry in self.walker.walk(&new_dir).build().flatten() {
+      if !entry.file_type().map_or(false, |t| t.is_file()) {
+        continue;
+      }
+      let Ok(rest) = entry.path().strip_prefix(&new_dir) else {
+        continue;
+      };
+      let rest = rest.to_string_lossy().replace('\\', "/");
+      paths.push((format!("{old_path}/{rest}"), format!("{new_path}/{rest}")));
+    }
+    paths
+  }
+
+  fn relative_path(&self, uri: &Url) -> Option<String> {
+    let absolute_path = uri.to_file_path().ok()?;
+    let base = self
+      .base
+      .canonicalize()
+      .unwrap_or_else(|_| self.base.clone());
+    let path = absolute_path.strip_prefix(base).ok()?;
+    Some(path.to_string_lossy().replace('\\', "/"))
+  }
+
   async fn on_close(&self, params: DidCloseTextDocumentParams) {
-    self.map.remove(params.text_document.uri.as_str());
+    let uri = params.text_document.uri.as_str();
+    self.map.remove(uri);
+    if let Some((_, token)) = self.pending_scans.remove(uri) {
+      token.cancel();
+    }
   }
 
   fn compute_all_fixes(
@@ -317,7 +1086,7 @@ impl<L: LSPLang> Backend<L> {
       .get(uri.as_str())
       .ok_or(LspError::UnsupportedFileType)?;
     let mut diagnostics = self
-      .get_diagnostics(&uri, &versioned)
+      .get_diagnostics(&uri, &versioned.root)
       .ok_or(LspError::NoActionableFix)?;
     diagnostics.sort_by_key(|d| (d.range.start, d.range.end));
     let mut last = Position {
@@ -344,6 +1113,99 @@ impl<L: LSPLang> Backend<L> {
     Ok(changes)
   }
 
+  /// Expand selections along syntax nodes of the opened document.
+  fn on_selection_range(&self, params: SelectionRangeParams) -> Option<Vec<SelectionRange>> {
+    let versioned = self.map.get(params.text_document.uri.as_str())?;
+    let root = &versioned.root;
+    let ranges = params
+      .positions
+      .iter()
+      .map(|pos| selection_range_at(root.source(), root.root(), pos))
+      .collect();
+    Some(ranges)
+  }
+
+  /// Formatting applies all fixes in the document, the same as fix-all code action.
+  /// Range formatting only applies fixes within the range.
+  fn on_formatting(
+    &self,
+    text_document: TextDocumentIdentifier,
+    range: Option<Range>,
+  ) -> Option<Vec<TextEdit>> {
+    let uri = text_document.uri.clone();
+    let mut changes = self.compute_all_fixes(text_document).ok()?;
+    let mut edits = changes.remove(&uri)?;
+    if let Some(range) = range {
+      edits.retain(|e| range.start <= e.range.start && e.range.end <= range.end);
+    }
+    Some(edits)
+  }
+
+  fn on_code_lens(&self, params: CodeLensParams) -> Option<Vec<CodeLens>> {
+    let uri = params.text_document.uri;
+    let mut versioned = self.map.get_mut(uri.as_str())?;
+    if let Some(lenses) = &versioned.code_lens {
+      return Some(lenses.clone());
+    }
+    let diagnostics = self.get_diagnostics(&uri, &versioned.root)?;
+    let lenses = diagnostics_to_code_lenses(&uri, &diagnostics);
+    versioned.code_lens = Some(lenses.clone());
+    Some(lenses)
+  }
+
+  fn rename_at(&self, uri: &Url, position: Position) -> Option<RenameTarget> {
+    self.with_root(uri, |_, root| {
+      let diagnostics = self.get_diagnostics(uri, root)?;
+      diagnostics.into_iter().find_map(|d| {
+        if position < d.range.start || position > d.range.end {
+          return None;
+        }
+        let (name, fixed) = rename_of_diagnostic(root.source(), &d)?;
+        Some(RenameTarget {
+          rule_id: d.code?,
+          range: d.range,
+          name: name.to_string(),
+          fixed,
+        })
+      })
+    })
+  }
+
+  fn on_prepare_rename(&self, params: TextDocumentPositionParams) -> Option<PrepareRenameResponse> {
+    let target = self.rename_at(&params.text_document.uri, params.position)?;
+    Some(PrepareRenameResponse::RangeWithPlaceholder {
+      range: target.range,
+      placeholder: target.fixed,
+    })
+  }
+
+  /// Rename all matches of the same rule and the same identifier in the project.
+  async fn on_rename(&self, params: RenameParams) -> Option<WorkspaceEdit> {
+    let TextDocumentPositionParams {
+      text_document,
+      position,
+    } = params.text_document_position;
+    let target = self.rename_at(&text_document.uri, position)?;
+    let new_name = params.new_name;
+    let rename_edits = move |_: &Url, root: &AstGrep<StrDoc<L>>, diagnostics: Vec<Diagnostic>| {
+      let edits: Vec<_> = diagnostics
+        .into_iter()
+        .filter(|d| d.code.as_ref() == Some(&target.rule_id))
+        .filter(|d| {
+          rename_of_diagnostic(root.source(), d).map_or(false, |(name, _)| name == target.name)
+        })
+        .map(|d| TextEdit::new(d.range, new_name.clone()))
+        .collect();
+      (!edits.is_empty()).then_some(edits)
+    };
+    let scanned = self.scan_workspace(rename_edits).await;
+    let changes = scanned
+      .into_iter()
+      .map(|(uri, _, edits)| (uri, edits))
+      .collect();
+    Some(WorkspaceEdit::new(changes))
+  }
+
   async fn on_code_action(&self, params: CodeActionParams) -> Option<CodeActionResponse> {
     if let Some(kinds) = params.context.only.as_ref() {
       if kinds.contains(&CodeActionKind::SOURCE_FIX_ALL) {
@@ -377,6 +1239,10 @@ impl<L: LSPLang> Backend<L> {
       return None;
     }
     let text_doc = params.text_document;
+    let suppress_context = self.map.get(text_doc.uri.as_str()).and_then(|versioned| {
+      let delimiters = versioned.root.lang().comment_delimiters()?;
+      Some((versioned.root.source().to_string(), delimiters))
+    });
     let response = params
       .context
       .diagnostics
@@ -387,7 +1253,15 @@ impl<L: LSPLang> Backend<L> {
           .map(|s| s.contains("ast-grep"))
           .unwrap_or(false)
       })
-      .filter_map(|d| diagnostic_to_code_action(&text_doc, d))
+      .flat_map(|d| {
+        let suppressions = match &suppress_context {
+          Some((src, delimiters)) => suppression_code_actions(&text_doc, &d, src, *delimiters),
+          None => vec![],
+        };
+        let mut actions = diagnostic_to_code_actions(&text_doc, d);
+        actions.extend(suppressions);
+        actions
+      })
       .map(CodeActionOrCommand::from)
       .collect();
     Some(response)
@@ -411,6 +1285,14 @@ impl<L: LSPLang> Backend<L> {
         self.on_apply_all_fix(command, arguments).await?;
         None
       }
+      APPLY_FIX => {
+        self.on_apply_fix(arguments).await?;
+        None
+      }
+      OPEN_DOCUMENTATION => {
+        self.on_open_documentation(arguments).await?;
+        None
+      }
       _ => {
         self
           .client
@@ -461,6 +1343,44 @@ impl<L: LSPLang> Backend<L> {
     None
   }
 
+  async fn on_apply_fix(&self, arguments: Vec<Value>) -> Option<()> {
+    // arguments are [uri, text_edit]
+    let (uri, edit): (Url, TextEdit) = match serde_json::from_value(Value::Array(arguments)) {
+      Ok(parsed) => parsed,
+      Err(error) => {
+        self.report_error(LspError::JSONDecodeError(error)).await;
+        return None;
+      }
+    };
+    let mut changes = HashMap::new();
+    changes.insert(uri, vec![edit]);
+    self
+      .client
+      .apply_edit(WorkspaceEdit::new(changes))
+      .await
+      .ok()?;
+    None
+  }
+
+  async fn on_open_documentation(&self, arguments: Vec<Value>) -> Option<()> {
+    let first = arguments.into_iter().next()?;
+    let uri: Url = match serde_json::from_value(first) {
+      Ok(uri) => uri,
+      Err(error) => {
+        self.report_error(LspError::JSONDecodeError(error)).await;
+        return None;
+      }
+    };
+    let params = ShowDocumentParams {
+      uri,
+      external: Some(true),
+      take_focus: None,
+      selection: None,
+    };
+    self.client.show_document(params).await.ok()?;
+    None
+  }
+
   async fn report_error(&self, error: LspError) {
     match error {
       LspError::JSONDecodeError(e) => {
@@ -1,7 +1,7 @@
 //! Provides utility to convert ast-grep data types to lsp data types
 use ast_grep_config::RuleConfig;
 use ast_grep_config::Severity;
-use ast_grep_core::{language::Language, Doc, Node, NodeMatch, StrDoc};
+use ast_grep_core::{language::Language, source::Edit, AstGrep, Doc, Node, NodeMatch, StrDoc};
 
 use serde::{Deserialize, Serialize};
 use tower_lsp::lsp_types::*;
@@ -11,9 +11,21 @@ use std::collections::HashMap;
 #[derive(Serialize, Deserialize)]
 pub struct RewriteData {
   pub fixed: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  /// other applicable fixes of the rule, offered as separate quickfixes
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub alternatives: Vec<AlternativeFix>,
   // maybe we should have fixed range
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct AlternativeFix {
+  pub fixed: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+}
+
 impl RewriteData {
   pub fn from_value(data: serde_json::Value) -> Option<Self> {
     serde_json::from_value(data).ok()
@@ -23,40 +35,238 @@ impl RewriteData {
     node_match: &NodeMatch<StrDoc<L>>,
     rule: &RuleConfig<L>,
   ) -> Option<Self> {
-    let fixer = rule.matcher.fixer.as_ref()?;
-    let edit = node_match.replace_by(fixer);
-    let rewrite = String::from_utf8(edit.inserted_text).ok()?;
-    Some(Self { fixed: rewrite })
+    let mut fixes = rule
+      .matcher
+      .fixer
+      .iter()
+      .filter(|fixer| fixer.is_applicable(node_match))
+      .filter_map(|fixer| {
+        let edit = node_match.replace_by(fixer);
+        let fixed = String::from_utf8(edit.inserted_text).ok()?;
+        let title = fixer.title().map(String::from);
+        Some(AlternativeFix { fixed, title })
+      });
+    let AlternativeFix { fixed, title } = fixes.next()?;
+    Some(Self {
+      fixed,
+      title,
+      alternatives: fixes.collect(),
+    })
+  }
+}
+
+/// Editor settings sent in initialization options or `workspace/didChangeConfiguration`.
+#[derive(Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LspSettings {
+  /// diagnostics less severe than this are not published
+  #[serde(default)]
+  pub min_severity: Option<Severity>,
+  /// rule ids whose diagnostics are not published
+  #[serde(default)]
+  pub disabled_rules: Vec<String>,
+  /// milliseconds to wait after a change before scanning the document, 0 scans immediately
+  #[serde(default)]
+  pub debounce_ms: u64,
+}
+
+impl LspSettings {
+  pub fn from_value(value: serde_json::Value) -> Option<Self> {
+    serde_json::from_value(value).ok()
+  }
+
+  pub fn allows<L: Language>(&self, rule: &RuleConfig<L>) -> bool {
+    if self.disabled_rules.contains(&rule.id) {
+      return false;
+    }
+    match &self.min_severity {
+      Some(min) => severity_level(&rule.severity) >= severity_level(min),
+      None => true,
+    }
+  }
+}
+
+fn severity_level(severity: &Severity) -> u8 {
+  match severity {
+    Severity::Hint => 0,
+    Severity::Info => 1,
+    Severity::Warning => 2,
+    Severity::Error => 3,
+    Severity::Off => 4,
+  }
+}
+
+fn fix_action_title(id: &str, title: Option<&str>) -> String {
+  match title {
+    Some(title) => format!("Fix `{id}` with ast-grep: {title}"),
+    None => format!("Fix `{id}` with ast-grep"),
   }
 }
 
-pub fn diagnostic_to_code_action(
+/// Create one quickfix per applicable fix of the diagnostic. The first one is preferred.
+pub fn diagnostic_to_code_actions(
   text_doc: &TextDocumentIdentifier,
   diagnostic: Diagnostic,
-) -> Option<CodeAction> {
-  let rewrite_data = RewriteData::from_value(diagnostic.data?)?;
-  let mut changes = HashMap::new();
-  let text_edit = TextEdit::new(diagnostic.range, rewrite_data.fixed);
-  changes.insert(text_doc.uri.clone(), vec![text_edit]);
-
-  let edit = WorkspaceEdit::new(changes);
-  let NumberOrString::String(id) = diagnostic.code? else {
-    return None;
+) -> Vec<CodeAction> {
+  let Some(rewrite_data) = diagnostic.data.and_then(RewriteData::from_value) else {
+    return vec![];
+  };
+  let Some(NumberOrString::String(id)) = diagnostic.code else {
+    return vec![];
   };
-  let action = CodeAction {
-    title: format!("Fix `{id}` with ast-grep"),
-    command: None,
-    diagnostics: None,
-    edit: Some(edit),
-    disabled: None,
-    kind: Some(CodeActionKind::QUICKFIX),
-    is_preferred: Some(true),
-    data: None,
+  let RewriteData {
+    fixed,
+    title,
+    alternatives,
+  } = rewrite_data;
+  let default_fix = AlternativeFix { fixed, title };
+  std::iter::once(default_fix)
+    .chain(alternatives)
+    .enumerate()
+    .map(|(i, fix)| {
+      let mut changes = HashMap::new();
+      let text_edit = TextEdit::new(diagnostic.range, fix.fixed);
+      changes.insert(text_doc.uri.clone(), vec![text_edit]);
+      CodeAction {
+        title: fix_action_title(&id, fix.title.as_deref()),
+        command: None,
+        diagnostics: None,
+        edit: Some(WorkspaceEdit::new(changes)),
+        disabled: None,
+        kind: Some(CodeActionKind::QUICKFIX),
+        is_preferred: Some(i == 0),
+        data: None,
+      }
+    })
+    .collect()
+}
+
+const IGNORE_TEXT: &str = "ast-grep-ignore";
+const IGNORE_FILE_TEXT: &str = "ast-grep-ignore-file";
+
+/// Create quickfixes to suppress the diagnostic by `ast-grep-ignore` above its line,
+/// or by `ast-grep-ignore-file` at the top of the file.
+pub fn suppression_code_actions(
+  text_doc: &TextDocumentIdentifier,
+  diagnostic: &Diagnostic,
+  src: &str,
+  (open, close): (&str, &str),
+) -> Vec<CodeAction> {
+  let Some(NumberOrString::String(id)) = &diagnostic.code else {
+    return vec![];
+  };
+  let close = if close.is_empty() {
+    String::new()
+  } else {
+    format!(" {close}")
   };
-  Some(action)
+  let newline = if src.contains("\r\n") { "\r\n" } else { "\n" };
+  let line_edit = suppress_line_edit(src, diagnostic.range.start.line, id, open, &close);
+  // shebang must stay on the first line
+  let file_line = u32::from(src.starts_with("#!"));
+  let file_edit = TextEdit::new(
+    Range::new(Position::new(file_line, 0), Position::new(file_line, 0)),
+    format!("{open} {IGNORE_FILE_TEXT}: {id}{close}{newline}"),
+  );
+  let actions = [
+    (
+      format!("Suppress `{id}` on this line with ast-grep"),
+      line_edit,
+    ),
+    (
+      format!("Suppress `{id}` in this file with ast-grep"),
+      file_edit,
+    ),
+  ];
+  actions
+    .into_iter()
+    .map(|(title, text_edit)| {
+      let mut changes = HashMap::new();
+      changes.insert(text_doc.uri.clone(), vec![text_edit]);
+      CodeAction {
+        title,
+        command: None,
+        diagnostics: None,
+        edit: Some(WorkspaceEdit::new(changes)),
+        disabled: None,
+        kind: Some(CodeActionKind::QUICKFIX),
+        is_preferred: Some(false),
+        data: None,
+      }
+    })
+    .collect()
 }
 
-fn convert_node_to_range<D: Doc>(node_match: &Node<D>) -> Range {
+/// Insert a suppression comment above the line, or add the rule id to an existing one.
+fn suppress_line_edit(src: &str, line: u32, id: &str, open: &str, close: &str) -> TextEdit {
+  let lines: Vec<_> = src.lines().collect();
+  let prefix = format!("{open} {IGNORE_TEXT}:");
+  let prev = line.checked_sub(1).and_then(|l| lines.get(l as usize));
+  if let Some(prev) = prev.filter(|p| p.trim_start().starts_with(&prefix)) {
+    let trimmed = prev.trim_end();
+    let ids = trimmed.strip_suffix(close.trim()).unwrap_or(trimmed);
+    let character = ids.trim_end().encode_utf16().count() as u32;
+    let pos = Position::new(line - 1, character);
+    return TextEdit::new(Range::new(pos, pos), format!(", {id}"));
+  }
+  let current = lines.get(line as usize).copied().unwrap_or_default();
+  let indent = &current[..current.len() - current.trim_start().len()];
+  let newline = if src.contains("\r\n") { "\r\n" } else { "\n" };
+  let pos = Position::new(line, 0);
+  TextEdit::new(
+    Range::new(pos, pos),
+    format!("{indent}{open} {IGNORE_TEXT}: {id}{close}{newline}"),
+  )
+}
+
+/// Create code lenses above each diagnostic. Each lens shows the rule id with its hit count in the file,
+/// and runs a command to either apply the fix or open the rule's documentation.
+pub fn diagnostics_to_code_lenses(uri: &Url, diagnostics: &[Diagnostic]) -> Vec<CodeLens> {
+  let mut hits = HashMap::new();
+  for d in diagnostics {
+    if let Some(NumberOrString::String(id)) = &d.code {
+      *hits.entry(id.as_str()).or_insert(0) += 1;
+    }
+  }
+  let mut lenses = vec![];
+  for d in diagnostics {
+    let Some(NumberOrString::String(id)) = &d.code else {
+      continue;
+    };
+    let count = hits[id.as_str()];
+    let hit_text = if count == 1 { "hit" } else { "hits" };
+    let title = format!("{id} ({count} {hit_text})");
+    let range = Range::new(d.range.start, d.range.start);
+    if let Some(rewrite) = d.data.clone().and_then(RewriteData::from_value) {
+      let edit = TextEdit::new(d.range, rewrite.fixed);
+      let command = Command {
+        title: format!("{title}: apply fix"),
+        command: crate::APPLY_FIX.into(),
+        arguments: Some(vec![serde_json::json!(uri), serde_json::json!(edit)]),
+      };
+      lenses.push(CodeLens {
+        range,
+        command: Some(command),
+        data: None,
+      });
+    }
+    if let Some(desc) = &d.code_description {
+      let command = Command {
+        title: format!("{title}: open documentation"),
+        command: crate::OPEN_DOCUMENTATION.into(),
+        arguments: Some(vec![serde_json::json!(desc.href)]),
+      };
+      lenses.push(CodeLens {
+        range,
+        command: Some(command),
+        data: None,
+      });
+    }
+  }
+  lenses
+}
+
+pub fn convert_node_to_range<D: Doc>(node_match: &Node<D>) -> Range {
   let start = node_match.start_pos();
   let end = node_match.end_pos();
   Range {
@@ -72,6 +282,7 @@ fn convert_node_to_range<D: Doc>(node_match: &Node<D>) -> Range {
 }
 
 pub fn convert_match_to_diagnostic<L: Language>(
+  uri: &Url,
   node_match: NodeMatch<StrDoc<L>>,
   rule: &RuleConfig<L>,
 ) -> Diagnostic {
@@ -92,11 +303,46 @@ pub fn convert_match_to_diagnostic<L: Language>(
     message: get_non_empty_message(rule, &node_match),
     source: Some(String::from("ast-grep")),
     tags: None,
-    related_information: None,
+    related_information: get_related_information(uri, &node_match, rule),
     data: rewrite_data,
   }
 }
 
+/// Convert rule labels with messages to related information, e.g. "declared here".
+/// Without configured labels, the secondary nodes of relational rules are used,
+/// e.g. the enclosing function that makes an `inside` rule match.
+fn get_related_information<L: Language>(
+  uri: &Url,
+  node_match: &NodeMatch<StrDoc<L>>,
+  rule: &RuleConfig<L>,
+) -> Option<Vec<DiagnosticRelatedInformation>> {
+  if rule.labels.is_none() {
+    let secondary = node_match.get_env().get_labels("secondary")?;
+    let infos = secondary
+      .iter()
+      .map(|node| DiagnosticRelatedInformation {
+        location: Location::new(uri.clone(), convert_node_to_range(node)),
+        message: format!("related `{}`", node.kind()),
+      })
+      .collect();
+    return Some(infos);
+  }
+  let infos: Vec<_> = rule
+    .get_labels(node_match)
+    .into_iter()
+    .filter_map(|label| {
+      let message = label.message?.to_string();
+      let range = Range {
+        start: convert_node_to_range(&label.start_node).start,
+        end: convert_node_to_range(&label.end_node).end,
+      };
+      let location = Location::new(uri.clone(), range);
+      Some(DiagnosticRelatedInformation { location, message })
+    })
+    .collect();
+  (!infos.is_empty()).then_some(infos)
+}
+
 fn get_non_empty_message<L: Language>(rule: &RuleConfig<L>, nm: &NodeMatch<StrDoc<L>>) -> String {
   // Note: The LSP client in vscode won't show any diagnostics at all if it receives one with an empty message
   let msg = if rule.message.is_empty() {
@@ -116,3 +362,450 @@ fn url_to_code_description(url: &Option<String>) -> Option<CodeDescription> {
   let href = Url::parse(url.as_ref()?).ok()?;
   Some(CodeDescription { href })
 }
+
+/// Returns the matched identifier and its replacement if the diagnostic's fix only renames it.
+pub fn rename_of_diagnostic<'a>(
+  src: &'a str,
+  diagnostic: &Diagnostic,
+) -> Option<(&'a str, String)> {
+  let rewrite_data = RewriteData::from_value(diagnostic.data.clone()?)?;
+  let start = position_to_offset(src, &diagnostic.range.start);
+  let end = position_to_offset(src, &diagnostic.range.end);
+  let name = src.get(start..end)?;
+  if is_identifier(name) && is_identifier(&rewrite_data.fixed) {
+    Some((name, rewrite_data.fixed))
+  } else {
+    None
+  }
+}
+
+fn is_identifier(text: &str) -> bool {
+  let mut chars = text.chars();
+  let Some(first) = chars.next() else {
+    return false;
+  };
+  let is_ident_char = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+  !first.is_numeric() && is_ident_char(first) && chars.all(is_ident_char)
+}
+
+/// Apply one content change to the document. Ranged changes are applied in place
+/// as incremental tree-sitter edits. Full text changes reparse the document, and so do
+/// failed edits, whose change is already applied to the source.
+pub fn apply_change<L: Language>(
+  root: &mut AstGrep<StrDoc<L>>,
+  change: TextDocumentContentChangeEvent,
+  lang: &L,
+) {
+  apply_change_with(root, change, lang, |root, edit| root.edit(edit).is_ok());
+}
+
+fn apply_change_with<L: Language>(
+  root: &mut AstGrep<StrDoc<L>>,
+  change: TextDocumentContentChangeEvent,
+  lang: &L,
+  edit: impl FnOnce(&mut AstGrep<StrDoc<L>>, Edit<String>) -> bool,
+) {
+  let Some(range) = change.range else {
+    *root = AstGrep::new(change.text, lang.clone());
+    return;
+  };
+  let change_edit = lsp_change_to_edit(root.source(), &range, &change.text);
+  if !edit(root, change_edit) {
+    let text = root.source().to_string();
+    *root = AstGrep::new(text, lang.clone());
+  }
+}
+
+/// Convert an LSP text change to ast-grep's edit on the source.
+fn lsp_change_to_edit(src: &str, range: &Range, text: &str) -> Edit<String> {
+  let start = position_to_offset(src, &range.start);
+  let end = position_to_offset(src, &range.end).max(start);
+  Edit {
+    position: start,
+    deleted_length: end - start,
+    inserted_text: text.as_bytes().to_vec(),
+  }
+}
+
+/// Selection ranges from the innermost named node at the position up to the root.
+/// Ancestors with the same range as their child are skipped.
+pub fn selection_range_at<D: Doc>(src: &str, root: Node<D>, pos: &Position) -> SelectionRange {
+  let offset = position_to_offset(src, pos);
+  let mut node = root;
+  loop {
+    let child = node.children().find(|c| {
+      let range = c.range();
+      c.is_named() && range.start <= offset && offset <= range.end
+    });
+    match child {
+      Some(child) => node = child,
+      None => break,
+    }
+  }
+  let mut ranges = vec![convert_node_to_range(&node)];
+  for ancestor in node.ancestors() {
+    let range = convert_node_to_range(&ancestor);
+    if ranges.last() != Some(&range) {
+      ranges.push(range);
+    }
+  }
+  let mut ranges = ranges.into_iter().rev();
+  let root_range = ranges.next().expect("should have root range");
+  let root_selection = SelectionRange {
+    range: root_range,
+    parent: None,
+  };
+  ranges.fold(root_selection, |parent, range| SelectionRange {
+    range,
+    parent: Some(Box::new(parent)),
+  })
+}
+
+/// LSP position counts characters in UTF-16 code units by default.
+/// Position beyond the line end or the document end is clamped.
+fn position_to_offset(src: &str, pos: &Position) -> usize {
+  let mut line_start = 0;
+  for _ in 0..pos.line {
+    match src[line_start..].find('\n') {
+      Some(i) => line_start += i + 1,
+      None => return src.len(),
+    }
+  }
+  let mut utf16_col = 0;
+  for (i, c) in src[line_start..].char_indices() {
+    if utf16_col >= pos.character as usize || c == '\n' {
+      return line_start + i;
+    }
+    utf16_col += c.len_utf16();
+  }
+  src.len()
+}
+
+/// The glob after renaming the path `old` to `new`, if the glob starts with the path,
+/// e.g. `src/old/**` becomes `src/new/**` when the directory `src/old` is renamed.
+pub fn rename_glob(glob: &str, old: &str, new: &str) -> Option<String> {
+  let rest = glob.strip_prefix(old)?;
+  if rest.is_empty() || rest.starts_with('/') {
+    Some(format!("{new}{rest}"))
+  } else {
+    None
+  }
+}
+
+/// Edits replacing the globs listed in a rule file, e.g. `- src/old/**` or `[src/old/**]`.
+/// Only whole list items are replaced so that messages mentioning the path are kept intact.
+pub fn glob_edits(yaml: &str, renames: &[(String, String)]) -> Vec<TextEdit> {
+  let mut edits = vec![];
+  for (line_num, line) in yaml.lines().enumerate() {
+    for (old, new) in renames {
+      for (start, _) in line.match_indices(old.as_str()) {
+        let end = start + old.len();
+        if !is_list_item(&line[..start], &line[end..]) {
+          continue;
+        }
+        let position = |offset: usize| Position {
+          line: line_num as u32,
+          character: line[..offset].encode_utf16().count() as u32,
+        };
+        let range = Range::new(position(start), position(end));
+        edits.push(TextEdit::new(range, new.clone()));
+      }
+    }
+  }
+  edits
+}
+
+fn is_list_item(before: &str, after: &str) -> bool {
+  let (before, after) = match (before.chars().last(), after.chars().next()) {
+    (Some(q @ ('"' | '\'')), Some(c)) if q == c => (&before[..before.len() - 1], &after[1..]),
+    _ => (before, after),
+  };
+  let before_ok = matches!(before.trim_end().chars().last(), Some('-' | '[' | ','));
+  let after = after.trim_start();
+  let after_ok = after.is_empty() || after.starts_with([',', ']', '#']);
+  before_ok && after_ok
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use ast_grep_config::{from_yaml_string, GlobalRules};
+  use ast_grep_language::SupportLang;
+
+  #[test]
+  fn test_multiple_fix_code_actions() {
+    let rule: RuleConfig<SupportLang> = from_yaml_string(
+      r"
+id: no-var
+language: TypeScript
+rule: {pattern: var $A = $B}
+fix:
+  - template: let $A = $B
+    title: use let
+  - template: const $A = $B
+    title: use const
+  - template: never
+    condition: {regex: never}
+",
+      &GlobalRules::default(),
+    )
+    .expect("should parse")
+    .pop()
+    .expect("should have rule");
+    let grep = SupportLang::TypeScript.ast_grep("var a = 1");
+    let nm = grep.root().find(&rule.matcher).expect("should match");
+    let diagnostic =
+      convert_match_to_diagnostic(&Url::parse("file:///test.ts").unwrap(), nm, &rule);
+    let text_doc = TextDocumentIdentifier::new(Url::parse("file:///test.ts").unwrap());
+    let actions = diagnostic_to_code_actions(&text_doc, diagnostic);
+    assert_eq!(actions.len(), 2);
+    assert_eq!(actions[0].title, "Fix `no-var` with ast-grep: use let");
+    assert_eq!(actions[0].is_preferred, Some(true));
+    assert_eq!(actions[1].title, "Fix `no-var` with ast-grep: use const");
+    assert_eq!(actions[1].is_preferred, Some(false));
+  }
+
+  #[test]
+  fn test_rename_of_diagnostic() {
+    let rules: Vec<RuleConfig<SupportLang>> = from_yaml_string(
+      r"
+id: rename
+language: TypeScript
+rule: {pattern: oldName}
+fix: newName
+---
+id: not-rename
+language: TypeScript
+rule: {pattern: oldName()}
+fix: newName()
+",
+      &GlobalRules::default(),
+    )
+    .expect("should parse");
+    let src = "oldName()";
+    let grep = SupportLang::TypeScript.ast_grep(src);
+    let to_diagnostic = |rule: &RuleConfig<SupportLang>| {
+      let node_match = grep.root().find(&rule.matcher).expect("should match");
+      convert_match_to_diagnostic(&Url::parse("file:///test.ts").unwrap(), node_match, rule)
+    };
+    let diagnostic = to_diagnostic(&rules[0]);
+    let (name, fixed) = rename_of_diagnostic(src, &diagnostic).expect("should rename");
+    assert_eq!(name, "oldName");
+    assert_eq!(fixed, "newName");
+    let diagnostic = to_diagnostic(&rules[1]);
+    assert!(rename_of_diagnostic(src, &diagnostic).is_none());
+    assert!(!is_identifier("1a"));
+    assert!(is_identifier("$a_1"));
+  }
+
+  #[test]
+  fn test_related_information() {
+    let rule: RuleConfig<SupportLang> = from_yaml_string(
+      r"
+id: unused-param
+language: TypeScript
+message: parameter is not used
+rule: {pattern: 'function $F($A) {}'}
+labels:
+  F:
+    style: secondary
+    message: in this function
+  A:
+    style: primary
+",
+      &GlobalRules::default(),
+    )
+    .expect("should parse")
+    .pop()
+    .expect("should have rule");
+    let uri = Url::parse("file:///test.ts").unwrap();
+    let grep = SupportLang::TypeScript.ast_grep("function foo(a) {}");
+    let nm = grep.root().find(&rule.matcher).expect("should match");
+    let diagnostic = convert_match_to_diagnostic(&uri, nm, &rule);
+    let infos = diagnostic.related_information.expect("should have info");
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].message, "in this function");
+    assert_eq!(infos[0].location.uri, uri);
+    assert_eq!(infos[0].location.range.start.character, 9);
+    assert_eq!(infos[0].location.range.end.character, 12);
+  }
+
+  #[test]
+  fn test_secondary_related_information() {
+    let rule: RuleConfig<SupportLang> = from_yaml_string(
+      r"
+id: no-await-in-loop
+language: TypeScript
+rule:
+  pattern: await $A
+  inside: {kind: for_statement, stopBy: end}
+",
+      &GlobalRules::default(),
+    )
+    .expect("should parse")
+    .pop()
+    .expect("should have rule");
+    let uri = Url::parse("file:///test.ts").unwrap();
+    let grep = SupportLang::TypeScript.ast_grep("for (;;) {\n  await a\n}");
+    let nm = grep.root().find(&rule.matcher).expect("should match");
+    let diagnostic = convert_match_to_diagnostic(&uri, nm, &rule);
+    let infos = diagnostic.related_information.expect("should have info");
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].message, "related `for_statement`");
+    assert_eq!(infos[0].location.range.start.line, 0);
+    assert_eq!(infos[0].location.range.end.line, 2);
+  }
+
+  #[test]
+  fn test_rename_glob() {
+    assert_eq!(rename_glob("src/old", "src/old", "lib"), Some("lib".into()));
+    let renamed = rename_glob("src/old/**/*.ts", "src/old", "src/new");
+    assert_eq!(renamed, Some("src/new/**/*.ts".into()));
+    assert_eq!(rename_glob("src/older/*.ts", "src/old", "src/new"), None);
+    assert_eq!(rename_glob("**/old/*.ts", "src/old", "src/new"), None);
+  }
+
+  #[test]
+  fn test_glob_edits() {
+    let yaml = "id: test
+message: do not use src/old/**
+files:
+  - src/old/**
+  - 'src/old/**' # quoted
+ignores: [src/old/**, test]
+";
+    let renames = [("src/old/**".to_string(), "src/new/**".to_string())];
+    let edits = glob_edits(yaml, &renames);
+    let ranges: Vec<_> = edits
+      .iter()
+      .map(|e| {
+        (
+          e.range.start.line,
+          e.range.start.character,
+          e.range.end.character,
+        )
+      })
+      .collect();
+    assert_eq!(ranges, [(3, 4, 14), (4, 5, 15), (5, 10, 20)]);
+    assert!(edits.iter().all(|e| e.new_text == "src/new/**"));
+  }
+
+  #[test]
+  fn test_apply_change() {
+    let lang = SupportLang::TypeScript;
+    let mut grep = lang.ast_grep("let a = 1\nfoo(a)\n");
+    let change = |range: Option<Range>, text: &str| TextDocumentContentChangeEvent {
+      range,
+      range_length: None,
+      text: text.to_string(),
+    };
+    let range = Range::new(Position::new(1, 0), Position::new(1, 3));
+    apply_change(&mut grep, change(Some(range), "bar"), &lang);
+    assert_eq!(grep.source(), "let a = 1\nbar(a)\n");
+    // the source is edited before parsing, so a failed parse reparses the edited source
+    let range = Range::new(Position::new(0, 8), Position::new(0, 9));
+    let fail_parse = |root: &mut AstGrep<_>, edit| {
+      let _ = root.edit(edit);
+      false
+    };
+    apply_change_with(&mut grep, change(Some(range), "2"), &lang, fail_parse);
+    assert_eq!(grep.source(), "let a = 2\nbar(a)\n");
+    assert!(grep.root().find("bar($A)").is_some());
+    apply_change(&mut grep, change(None, "baz()"), &lang);
+    assert_eq!(grep.source(), "baz()");
+  }
+
+  #[test]
+  fn test_selection_range() {
+    let src = "function foo() {\n  return a + b;\n}";
+    let grep = SupportLang::TypeScript.ast_grep(src);
+    let pos = Position::new(1, 13);
+    let selection = selection_range_at(src, grep.root(), &pos);
+    let mut ranges = vec![];
+    let mut current = Some(&selection);
+    while let Some(s) = current {
+      ranges.push(s.range);
+      current = s.parent.as_deref();
+    }
+    let range = |l1, c1, l2, c2| Range::new(Position::new(l1, c1), Position::new(l2, c2));
+    assert_eq!(ranges[0], range(1, 13, 1, 14));
+    assert_eq!(ranges[1], range(1, 9, 1, 14));
+    assert_eq!(ranges[2], range(1, 2, 1, 15));
+    assert_eq!(ranges.last(), Some(&range(0, 0, 2, 1)));
+    assert!(ranges.windows(2).all(|w| w[0] != w[1]));
+  }
+
+  fn suppress(src: &str, line: u32, delimiters: (&str, &str)) -> Vec<TextEdit> {
+    let uri = Url::parse("file:///test.ts").unwrap();
+    let text_doc = TextDocumentIdentifier::new(uri.clone());
+    let pos = Position::new(line, 2);
+    let mut diagnostic = Diagnostic::new_simple(Range::new(pos, pos), "test".into());
+    diagnostic.code = Some(NumberOrString::String("no-a".into()));
+    let actions = suppression_code_actions(&text_doc, &diagnostic, src, delimiters);
+    assert_eq!(actions.len(), 2);
+    assert_eq!(
+      actions[0].title,
+      "Suppress `no-a` on this line with ast-grep"
+    );
+    actions
+      .into_iter()
+      .map(|a| {
+        a.edit
+          .unwrap()
+          .changes
+          .unwrap()
+          .remove(&uri)
+          .unwrap()
+          .remove(0)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_suppression_code_actions() {
+    let edits = suppress("let b = 1\n  let a = 1\n", 1, ("//", ""));
+    assert_eq!(edits[0].new_text, "  // ast-grep-ignore: no-a\n");
+    assert_eq!(edits[0].range.start, Position::new(1, 0));
+    assert_eq!(edits[1].new_text, "// ast-grep-ignore-file: no-a\n");
+    assert_eq!(edits[1].range.start, Position::new(0, 0));
+    let edits = suppress(
+      "#!/bin/sh\n<!-- ast-grep-ignore: b -->\n<a>",
+      2,
+      ("<!--", "-->"),
+    );
+    assert_eq!(edits[0].new_text, ", no-a");
+    assert_eq!(edits[0].range.start, Position::new(1, 23));
+    assert_eq!(edits[1].new_text, "<!-- ast-grep-ignore-file: no-a -->\n");
+    assert_eq!(edits[1].range.start, Position::new(1, 0));
+  }
+
+  #[test]
+  fn test_settings_allows_rule() {
+    let rules: Vec<RuleConfig<SupportLang>> = from_yaml_string(
+      r"
+id: warn
+language: TypeScript
+severity: warning
+rule: {pattern: a}
+---
+id: hint
+language: TypeScript
+severity: hint
+rule: {pattern: a}
+",
+      &GlobalRules::default(),
+    )
+    .expect("should parse");
+    let settings = LspSettings::default();
+    assert!(rules.iter().all(|r| settings.allows(r)));
+    let value = serde_json::json!({"minSeverity": "info", "disabledRules": []});
+    let settings = LspSettings::from_value(value).expect("should parse");
+    assert!(settings.allows(&rules[0]));
+    assert!(!settings.allows(&rules[1]));
+    let value = serde_json::json!({"disabledRules": ["warn"], "debounceMs": 200});
+    let settings = LspSettings::from_value(value).expect("should parse");
+    assert!(!settings.allows(&rules[0]));
+    assert!(settings.allows(&rules[1]));
+    assert_eq!(settings.debounce_ms, 200);
+  }
+}
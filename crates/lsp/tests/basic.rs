@@ -56,9 +56,17 @@ fn req_resp_should_work() {
 }
 
 pub fn create_lsp() -> (DuplexStream, DuplexStream) {
-  let globals = GlobalRules::default();
-  let config: RuleConfig<SupportLang> = from_yaml_string(
-    r"
+  create_lsp_with_base(Path::new("./"))
+}
+
+struct AllFiles;
+impl Walker for AllFiles {}
+
+pub fn create_lsp_with_base(base: &Path) -> (DuplexStream, DuplexStream) {
+  create_lsp_with_walker(base, AllFiles)
+}
+
+const NO_CONSOLE_RULE: &str = r"
 id: no-console-rule
 message: No console.log
 severity: warning
@@ -68,17 +76,26 @@ rule:
 note: no console.log
 fix: |
   alert($$$A)
-",
-    &globals,
-  )
-  .unwrap()
-  .pop()
-  .unwrap();
-  let base = Path::new("./").to_path_buf();
+";
+
+pub fn create_lsp_with_walker(base: &Path, walker: impl Walker) -> (DuplexStream, DuplexStream) {
+  create_lsp_with_rule(base, walker, NO_CONSOLE_RULE)
+}
+
+pub fn create_lsp_with_rule(
+  base: &Path,
+  walker: impl Walker,
+  rule: &str,
+) -> (DuplexStream, DuplexStream) {
+  let globals = GlobalRules::default();
+  let config: RuleConfig<SupportLang> = from_yaml_string(rule, &globals).unwrap().pop().unwrap();
+  let base = base.to_path_buf();
   let rc: RuleCollection<SupportLang> = RuleCollection::try_new(vec![config]).unwrap();
   let rc_result: std::result::Result<_, String> = Ok(rc);
   let (service, socket) =
-    LspService::build(|client| Backend::new(client, base, rc_result)).finish();
+    LspService::build(|client| Backend::new(client, base, rc_result).with_walker(walker))
+      .custom_method(SEARCH, Backend::search)
+      .finish();
   let (req_client, req_server) = duplex(1024);
   let (resp_server, resp_client) = duplex(1024);
 
@@ -258,3 +275,365 @@ fn test_execute_apply_all_fixes() {
     );
   });
 }
+
+// read server messages until one satisfies the predicate
+async fn wait_for(
+  resp_client: &mut DuplexStream,
+  pending: &mut Vec<u8>,
+  pred: impl Fn(&Value) -> bool,
+) -> Value {
+  let mut buf = vec![0; 1024];
+  loop {
+    let mut input = std::str::from_utf8(pending).unwrap();
+    let mut consumed = 0;
+    let before = input.len();
+    while let Some(val) = parse_jsonrpc(&mut input) {
+      consumed = before - input.len();
+      if pred(&val) {
+        pending.drain(..consumed);
+        return val;
+      }
+    }
+    pending.drain(..consumed);
+    let n = resp_client.read(&mut buf).await.unwrap();
+    pending.extend_from_slice(&buf[..n]);
+  }
+}
+
+async fn wait_for_diagnostics(
+  req_client: &mut DuplexStream,
+  resp_client: &mut DuplexStream,
+  pending: &mut Vec<u8>,
+) -> Value {
+  let msg = wait_for(resp_client, pending, |v| {
+    v["method"] == "workspace/workspaceFolders" || v["method"] == "textDocument/publishDiagnostics"
+  })
+  .await;
+  if msg["method"] == "textDocument/publishDiagnostics" {
+    return msg;
+  }
+  // answer workspace folder request so that the server can proceed
+  let reply = format!(r#"{{"jsonrpc":"2.0","id":{},"result":null}}"#, msg["id"]);
+  req_client.write_all(req(&reply).as_bytes()).await.unwrap();
+  wait_for(resp_client, pending, |v| {
+    v["method"] == "textDocument/publishDiagnostics"
+  })
+  .await
+}
+
+#[test]
+fn test_incremental_change() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let uri = "file:///Users/codes/ast-grep-vscode/test.ts";
+    let open = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{uri}","languageId":"typescript","version":1,"text":"let a = '😄'\nfoo(a)\n"}}}}}}"#
+    );
+    req_client.write_all(req(&open).as_bytes()).await.unwrap();
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    assert_eq!(diagnostics["params"]["diagnostics"], serde_json::json!([]));
+    // replace `foo` with `console.log`, the emoji takes two UTF-16 code units
+    let change = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/didChange","params":{{"textDocument":{{"uri":"{uri}","version":2}},"contentChanges":[{{"range":{{"start":{{"line":1,"character":0}},"end":{{"line":1,"character":3}}}},"text":"console.log"}}]}}}}"#
+    );
+    req_client.write_all(req(&change).as_bytes()).await.unwrap();
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    assert_eq!(diagnostics["params"]["version"], 2);
+    let diagnostics = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["code"], "no-console-rule");
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+    // edit after the emoji on the same line
+    let change = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/didChange","params":{{"textDocument":{{"uri":"{uri}","version":3}},"contentChanges":[{{"range":{{"start":{{"line":0,"character":12}},"end":{{"line":0,"character":12}}}},"text":";"}},{{"range":{{"start":{{"line":1,"character":0}},"end":{{"line":1,"character":11}}}},"text":"bar"}}]}}}}"#
+    );
+    req_client.write_all(req(&change).as_bytes()).await.unwrap();
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    assert_eq!(diagnostics["params"]["version"], 3);
+    assert_eq!(diagnostics["params"]["diagnostics"], serde_json::json!([]));
+  });
+}
+
+#[test]
+fn test_debounce_change() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    let mut pending = vec![];
+    let initialize = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{},"initializationOptions":{"debounceMs":100}}}"#;
+    req_client.write_all(req(initialize).as_bytes()).await.unwrap();
+    wait_for(&mut resp_client, &mut pending, |v| v["id"] == 1).await;
+    let uri = "file:///Users/codes/ast-grep-vscode/test.ts";
+    let open = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{uri}","languageId":"typescript","version":1,"text":"foo(a)\n"}}}}}}"#
+    );
+    req_client.write_all(req(&open).as_bytes()).await.unwrap();
+    wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    let change = |version: i32, text: &str| {
+      format!(
+        r#"{{"jsonrpc":"2.0","method":"textDocument/didChange","params":{{"textDocument":{{"uri":"{uri}","version":{version}}},"contentChanges":[{{"text":"{text}"}}]}}}}"#
+      )
+    };
+    req_client
+      .write_all(req(&change(2, "console.log(a)")).as_bytes())
+      .await
+      .unwrap();
+    req_client
+      .write_all(req(&change(3, "console.log(b)")).as_bytes())
+      .await
+      .unwrap();
+    // the scan of version 2 is cancelled by the newer version
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    assert_eq!(diagnostics["params"]["version"], 3);
+    let diagnostics = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+  });
+}
+
+#[test]
+fn test_rename_file() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let old_uri = "file:///Users/codes/ast-grep-vscode/test.js";
+    let new_uri = "file:///Users/codes/ast-grep-vscode/test.ts";
+    let open = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{old_uri}","languageId":"javascript","version":1,"text":"console.log(1)\n"}}}}}}"#
+    );
+    req_client.write_all(req(&open).as_bytes()).await.unwrap();
+    let diagnostics = wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    // the rule only applies to TypeScript
+    assert_eq!(diagnostics["params"]["diagnostics"], serde_json::json!([]));
+    let rename = format!(
+      r#"{{"jsonrpc":"2.0","method":"workspace/didRenameFiles","params":{{"files":[{{"oldUri":"{old_uri}","newUri":"{new_uri}"}}]}}}}"#
+    );
+    req_client.write_all(req(&rename).as_bytes()).await.unwrap();
+    let diagnostics = wait_for(&mut resp_client, &mut pending, |v| {
+      v["method"] == "textDocument/publishDiagnostics" && v["params"]["uri"] == new_uri
+    })
+    .await;
+    assert_eq!(diagnostics["params"]["version"], 1);
+    let diagnostics = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["code"], "no-console-rule");
+  });
+}
+
+#[test]
+fn test_rename_directory() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let old_uri = "file:///Users/codes/old/test.ts";
+    let new_uri = "file:///Users/codes/new/test.ts";
+    let open = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{old_uri}","languageId":"typescript","version":1,"text":"console.log(1)\n"}}}}}}"#
+    );
+    req_client.write_all(req(&open).as_bytes()).await.unwrap();
+    wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    let rename = r#"{"jsonrpc":"2.0","method":"workspace/didRenameFiles","params":{"files":[{"oldUri":"file:///Users/codes/old","newUri":"file:///Users/codes/new"}]}}"#;
+    req_client.write_all(req(rename).as_bytes()).await.unwrap();
+    let cleared = wait_for(&mut resp_client, &mut pending, |v| {
+      v["method"] == "textDocument/publishDiagnostics" && v["params"]["uri"] == old_uri
+    })
+    .await;
+    assert_eq!(cleared["params"]["diagnostics"], serde_json::json!([]));
+    let diagnostics = wait_for(&mut resp_client, &mut pending, |v| {
+      v["method"] == "textDocument/publishDiagnostics" && v["params"]["uri"] == new_uri
+    })
+    .await;
+    let diagnostics = diagnostics["params"]["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+  });
+}
+
+#[test]
+fn test_code_lens() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let uri = "file:///Users/codes/ast-grep-vscode/test.ts";
+    let open = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{uri}","languageId":"typescript","version":1,"text":"console.log(1)\nconsole.log(2)\n"}}}}}}"#
+    );
+    req_client.write_all(req(&open).as_bytes()).await.unwrap();
+    wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    let code_lens = format!(
+      r#"{{"jsonrpc":"2.0","id":2,"method":"textDocument/codeLens","params":{{"textDocument":{{"uri":"{uri}"}}}}}}"#
+    );
+    req_client.write_all(req(&code_lens).as_bytes()).await.unwrap();
+    let lenses = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 2).await;
+    let lenses = lenses["result"].as_array().unwrap();
+    assert_eq!(lenses.len(), 2);
+    let command = &lenses[1]["command"];
+    assert_eq!(command["title"], "no-console-rule (2 hits): apply fix");
+    assert_eq!(command["command"], "ast-grep.applyFix");
+    assert_eq!(command["arguments"][0], uri);
+    assert_eq!(command["arguments"][1]["newText"], "alert(2)\n");
+    assert_eq!(lenses[1]["range"]["start"]["line"], 1);
+  });
+}
+
+#[test]
+fn test_formatting() {
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp();
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let uri = "file:///Users/codes/ast-grep-vscode/test.ts";
+    let open = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{uri}","languageId":"typescript","version":1,"text":"console.log(1)\nconsole.log(2)\n"}}}}}}"#
+    );
+    req_client.write_all(req(&open).as_bytes()).await.unwrap();
+    wait_for_diagnostics(&mut req_client, &mut resp_client, &mut pending).await;
+    let format = format!(
+      r#"{{"jsonrpc":"2.0","id":2,"method":"textDocument/formatting","params":{{"textDocument":{{"uri":"{uri}"}},"options":{{"tabSize":2,"insertSpaces":true}}}}}}"#
+    );
+    req_client.write_all(req(&format).as_bytes()).await.unwrap();
+    let edits = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 2).await;
+    let edits = edits["result"].as_array().unwrap();
+    assert_eq!(edits.len(), 2);
+    assert_eq!(edits[0]["newText"], "alert(1)\n");
+    let range = r#"{"start":{"line":1,"character":0},"end":{"line":2,"character":0}}"#;
+    let format = format!(
+      r#"{{"jsonrpc":"2.0","id":3,"method":"textDocument/rangeFormatting","params":{{"textDocument":{{"uri":"{uri}"}},"range":{range},"options":{{"tabSize":2,"insertSpaces":true}}}}}}"#
+    );
+    req_client.write_all(req(&format).as_bytes()).await.unwrap();
+    let edits = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 3).await;
+    let edits = edits["result"].as_array().unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0]["newText"], "alert(2)\n");
+  });
+}
+
+#[test]
+fn test_search() {
+  let dir = tempfile::tempdir().unwrap();
+  std::fs::write(dir.path().join("a.ts"), "foo(1)\nbar(2)").unwrap();
+  std::fs::write(dir.path().join("b.rs"), "fn main() { foo(3) }").unwrap();
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp_with_base(dir.path());
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let search = r#"{"jsonrpc":"2.0","id":2,"method":"ast-grep/search","params":{"pattern":"foo($A)","language":"TypeScript"}}"#;
+    req_client.write_all(req(search).as_bytes()).await.unwrap();
+    let result = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 2).await;
+    // no document is opened
+    assert_eq!(result["result"], serde_json::json!([]));
+    let search = r#"{"jsonrpc":"2.0","id":3,"method":"ast-grep/search","params":{"pattern":"foo($A)","language":"TypeScript","includeWorkspace":true}}"#;
+    req_client.write_all(req(search).as_bytes()).await.unwrap();
+    let result = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 3).await;
+    let matches = result["result"].as_array().unwrap();
+    assert_eq!(matches.len(), 1);
+    assert!(matches[0]["uri"].as_str().unwrap().ends_with("a.ts"));
+    assert_eq!(matches[0]["text"], "foo(1)");
+    assert_eq!(matches[0]["range"]["end"]["character"], 6);
+    let search = r#"{"jsonrpc":"2.0","id":4,"method":"ast-grep/search","params":{"pattern":"","language":"TypeScript"}}"#;
+    req_client.write_all(req(search).as_bytes()).await.unwrap();
+    let result = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 4).await;
+    assert_eq!(result["error"]["code"], -32602);
+  });
+}
+
+#[test]
+fn test_workspace_diagnostic() {
+  let dir = tempfile::tempdir().unwrap();
+  std::fs::write(dir.path().join("a.ts"), "console.log(1)").unwrap();
+  std::fs::write(dir.path().join("b.ts"), "let a = 1").unwrap();
+  std::fs::write(dir.path().join("c.rs"), "fn main() {}").unwrap();
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp_with_base(dir.path());
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let request = r#"{"jsonrpc":"2.0","id":2,"method":"workspace/diagnostic","params":{"previousResultIds":[]}}"#;
+    req_client.write_all(req(request).as_bytes()).await.unwrap();
+    let result = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 2).await;
+    let mut items = result["result"]["items"].as_array().unwrap().clone();
+    items.sort_by_key(|i| i["uri"].as_str().unwrap().to_string());
+    // rust file does not have applicable rules
+    assert_eq!(items.len(), 2);
+    assert!(items[0]["uri"].as_str().unwrap().ends_with("a.ts"));
+    assert_eq!(items[0]["kind"], "full");
+    let diagnostics = items[0]["items"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["code"], "no-console-rule");
+    assert_eq!(items[1]["items"], serde_json::json!([]));
+  });
+}
+
+#[test]
+fn test_workspace_diagnostic_with_requires() {
+  let rule = r"
+id: log-requires-util
+message: use the log util
+severity: warning
+language: TypeScript
+rule: {pattern: console.log($$$A)}
+requires:
+  file: 'utils/**'
+  rule: {pattern: 'function log() {}'}
+";
+  let dir = tempfile::tempdir().unwrap();
+  std::fs::create_dir(dir.path().join("utils")).unwrap();
+  std::fs::write(dir.path().join("a.ts"), "console.log(1)").unwrap();
+  std::fs::write(dir.path().join("b.ts"), "function log() {}").unwrap();
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp_with_rule(dir.path(), AllFiles, rule);
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let request = r#"{"jsonrpc":"2.0","id":2,"method":"workspace/diagnostic","params":{"previousResultIds":[]}}"#;
+    let initialized = r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#;
+    let indexed = |v: &Value| v["params"]["message"] == "Indexed files required by rules.";
+    // `log` is not defined under utils, the rule does not apply
+    req_client.write_all(req(initialized).as_bytes()).await.unwrap();
+    wait_for(&mut resp_client, &mut pending, indexed).await;
+    req_client.write_all(req(request).as_bytes()).await.unwrap();
+    let result = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 2).await;
+    let items = result["result"]["items"].as_array().unwrap();
+    assert!(items.iter().all(|i| i["items"] == serde_json::json!([])));
+    std::fs::write(dir.path().join("utils/log.ts"), "function log() {}").unwrap();
+    let save = r#"{"jsonrpc":"2.0","method":"textDocument/didSave","params":{"textDocument":{"uri":"file:///utils/log.ts"}}}"#;
+    req_client.write_all(req(save).as_bytes()).await.unwrap();
+    wait_for(&mut resp_client, &mut pending, indexed).await;
+    let request = request.replace(r#""id":2"#, r#""id":3"#);
+    req_client.write_all(req(&request).as_bytes()).await.unwrap();
+    let result = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 3).await;
+    let items = result["result"]["items"].as_array().unwrap();
+    let a = items
+      .iter()
+      .find(|i| i["uri"].as_str().unwrap().ends_with("a.ts"))
+      .unwrap();
+    assert_eq!(a["items"][0]["code"], "log-requires-util");
+  });
+}
+
+// skip generated files like the CLI skips minified ones
+struct SkipGenerated;
+impl Walker for SkipGenerated {
+  fn read(&self, path: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    (!text.starts_with("// generated")).then_some(text)
+  }
+}
+
+#[test]
+fn test_workspace_diagnostic_with_walker() {
+  let dir = tempfile::tempdir().unwrap();
+  std::fs::write(dir.path().join("a.ts"), "console.log(1)").unwrap();
+  std::fs::write(dir.path().join("b.ts"), "// generated\nconsole.log(2)").unwrap();
+  tokio::runtime::Runtime::new().unwrap().block_on(async {
+    let (mut req_client, mut resp_client) = create_lsp_with_walker(dir.path(), SkipGenerated);
+    initialize_lsp(&mut req_client, &mut resp_client).await;
+    let mut pending = vec![];
+    let request = r#"{"jsonrpc":"2.0","id":2,"method":"workspace/diagnostic","params":{"previousResultIds":[]}}"#;
+    req_client.write_all(req(request).as_bytes()).await.unwrap();
+    let result = wait_for(&mut resp_client, &mut pending, |v| v["id"] == 2).await;
+    let items = result["result"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert!(items[0]["uri"].as_str().unwrap().ends_with("a.ts"));
+  });
+}
@@ -1,4 +1,7 @@
 import test from 'ava'
+import * as fs from 'node:fs'
+import * as os from 'node:os'
+import * as path from 'node:path'
 
 import {
   js,
@@ -6,9 +9,16 @@ import {
   tsx,
   html,
   parseFiles,
+  findInFiles,
+  Lang,
+  scanFiles,
+  scanFix,
+  applyFixesToFiles,
 } from '../index'
+import type { ScanMatch } from '../index'
 const { parse, kind } = js
 const parseMulti = countedPromise(parseFiles)
+const scanMulti = countedPromise(scanFiles)
 
 test('find from native code', t => {
   const sg = parse('console.log(123)')
@@ -464,4 +474,114 @@ test('check if a node has another using config', t => {
   const match = sg.root().find('if (true) { $$$ }')
   t.assert(match!.has({ rule: { pattern: 'const x = 1' }}))
   t.assert(match!.has({ rule: { pattern: 'const y = 2' }}) === false)
-})
\ No newline at end of file
+})
+test('scanFix applies fixes of rules', t => {
+  const rules = [{
+    id: 'no-console',
+    language: 'JavaScript',
+    rule: { pattern: 'console.log($A)' },
+    fix: 'alert($A)',
+  }]
+  const fixed = scanFix('JavaScript', 'console.log(1)\nconsole.log(2)', rules)
+  t.is(fixed, 'alert(1)\nalert(2)')
+  t.is(scanFix('TypeScript', 'console.log(1)', rules), 'console.log(1)')
+})
+
+test('scanFiles reports findings of rules', async t => {
+  const findings: string[] = []
+  const fileCount = await scanMulti(
+    {
+      paths: ['./__test__/'],
+      rules: [{
+        id: 'test-with-parse',
+        language: 'TypeScript',
+        severity: 'warning',
+        message: 'found $P',
+        rule: { pattern: 'parse($P)' },
+      }],
+    },
+    (err, matches) => {
+      t.is(err, null)
+      for (const m of matches) {
+        t.is(m.ruleId, 'test-with-parse')
+        t.is(m.severity, 'warning')
+        t.assert(m.file.endsWith('.spec.ts'))
+        findings.push(m.message)
+      }
+    },
+  )
+  t.assert(fileCount > 0)
+  t.assert(findings.includes("found 'console.log(123)'"))
+})
+
+test('find in files with async iterator', async t => {
+  const config = {
+    paths: ['./__test__/'],
+    matcher: {
+      rule: { kind: 'await_expression' },
+    },
+  }
+  let fileCount = 0
+  for await (const nodes of findInFiles(Lang.TypeScript, config)) {
+    t.assert(nodes.length > 0)
+    t.assert(nodes[0].getRoot().filename().includes('.spec.ts'))
+    fileCount++
+  }
+  t.assert(fileCount > 0)
+  // break early should stop the iteration
+  for await (const nodes of findInFiles(Lang.TypeScript, config)) {
+    t.is(nodes[0].kind(), 'await_expression')
+    break
+  }
+})
+
+test('commitEdits of root can write back to file', async t => {
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'sg-'))
+  const file = path.join(dir, 'a.js')
+  fs.writeFileSync(file, 'console.log(123)')
+  await parseMulti([dir], (err, sg) => {
+    t.is(err, null)
+    const edit = sg.root().find('console.log')!.replace('alert')
+    t.is(sg.commitEdits([edit], true), 'alert(123)')
+  })
+  t.is(fs.readFileSync(file, 'utf8'), 'alert(123)')
+  const anonymous = parse('console.log(123)')
+  const edit = anonymous.root().find('console.log')!.replace('alert')
+  t.is(anonymous.commitEdits([edit]), 'alert(123)')
+  t.throws(() => anonymous.commitEdits([edit], true))
+})
+
+test('applyFixesToFiles writes fixes of scanFiles', async t => {
+  const dir = fs.mkdtempSync(path.join(os.tmpdir(), 'sg-'))
+  const file = path.join(dir, 'a.js')
+  fs.writeFileSync(file, 'console.log(1)\nconsole.log(2)')
+  const findings: ScanMatch[] = []
+  await scanMulti(
+    {
+      paths: [dir],
+      rules: [{
+        id: 'no-console',
+        language: 'JavaScript',
+        rule: { pattern: 'console.log($A)' },
+        fix: 'alert($A)',
+      }],
+    },
+    (err, matches) => {
+      t.is(err, null)
+      findings.push(...matches)
+    },
+  )
+  t.is(findings.length, 2)
+  t.is(await applyFixesToFiles(findings), 1)
+  t.is(fs.readFileSync(file, 'utf8'), 'alert(1)\nalert(2)')
+  // fixes are stale after the file changed
+  await t.throwsAsync(() => applyFixesToFiles(findings))
+  t.is(fs.readFileSync(file, 'utf8'), 'alert(1)\nalert(2)')
+})
+
+test('commitEdits rejects edits out of the source', t => {
+  const sg = parse('console.log(123)')
+  const edit = { startPos: 10, endPos: 100, insertedText: '' }
+  t.throws(() => sg.root().commitEdits([edit]))
+  t.throws(() => sg.commitEdits([edit]))
+})
@@ -1,6 +1,13 @@
 //-----Type Only Export!-----//
 export type { Pos, Edit, Range } from './types/sgnode'
-export type { NapiConfig, FindConfig, FileOption } from './types/config'
+export type {
+  NapiConfig,
+  FindConfig,
+  FileOption,
+  ScanConfig,
+  ScanMatch,
+  FileFix,
+} from './types/config'
 export type { DynamicLangRegistrations } from './types/registerDynamicLang'
 // Only Rule here. User can use Rule['pattern'], e.g., to get the type of subfield.
 export type { Rule } from './types/rule'
@@ -15,6 +22,9 @@ export {
   kind,
   pattern,
   findInFiles,
+  scanFiles,
+  scanFix,
+  applyFixesToFiles,
 } from './types/api'
 export { registerDynamicLanguage } from './types/registerDynamicLang'
 // deprecated
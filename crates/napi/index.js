@@ -310,9 +310,12 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { parseFiles, Lang, SgNode, SgRoot, parse, parseAsync, kind, pattern, findInFiles, registerDynamicLanguage, html, js, jsx, ts, tsx, css } = nativeBinding
+const { parseFiles, scanFiles, scanFix, applyFixesToFiles, Lang, SgNode, SgRoot, parse, parseAsync, kind, pattern, findInFiles, registerDynamicLanguage, html, js, jsx, ts, tsx, css } = nativeBinding
 
 module.exports.parseFiles = parseFiles
+module.exports.scanFiles = scanFiles
+module.exports.scanFix = scanFix
+module.exports.applyFixesToFiles = applyFixesToFiles
 module.exports.Lang = Lang
 module.exports.SgNode = SgNode
 module.exports.SgRoot = SgRoot
@@ -21,7 +21,7 @@ pub struct NapiConfig {
   pub constraints: Option<serde_json::Value>,
   /// Available languages: html, css, js, jsx, ts, tsx
   pub language: Option<String>,
-  /// transform is NOT useful in JavaScript. You can use JS code to directly transform the result.
+  /// Transformed meta variables can be read by `getTransformed` of the matched node.
   /// https://ast-grep.github.io/reference/yaml.html#transform
   pub transform: Option<serde_json::Value>,
   /// https://ast-grep.github.io/guide/rule-config/utility-rule.html
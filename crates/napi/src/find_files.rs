@@ -5,10 +5,12 @@ use ignore::{WalkBuilder, WalkParallel, WalkState};
 use napi::anyhow::{anyhow, Context, Result as Ret};
 use napi::bindgen_prelude::*;
 use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
-use napi::{JsNumber, Task};
+use napi::{JsNumber, JsObject, JsSymbol, Task};
 use napi_derive::napi;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
 
 use crate::doc::{JsDoc, NapiConfig};
 use crate::napi_lang::{build_files, LangOption, NapiLang};
@@ -33,13 +35,13 @@ impl Task for ParseAsync {
   }
 }
 
-type Entry = std::result::Result<ignore::DirEntry, ignore::Error>;
+pub(crate) type Entry = std::result::Result<ignore::DirEntry, ignore::Error>;
 
 pub struct IterateFiles<D> {
-  walk: WalkParallel,
-  lang_option: LangOption,
-  tsfn: D,
-  producer: fn(&D, Entry, &LangOption) -> Ret<bool>,
+  pub(crate) walk: WalkParallel,
+  pub(crate) lang_option: LangOption,
+  pub(crate) tsfn: D,
+  pub(crate) producer: fn(&D, Entry, &LangOption) -> Ret<bool>,
 }
 
 impl<T: 'static + Send + Sync> Task for IterateFiles<T> {
@@ -75,7 +77,7 @@ impl<T: 'static + Send + Sync> Task for IterateFiles<T> {
 // See https://github.com/ast-grep/ast-grep/issues/206
 // NodeJS has a 1000 file limitation on sync iteration count.
 // https://github.com/nodejs/node/blob/8ba54e50496a6a5c21d93133df60a9f7cb6c46ce/src/node_api.cc#L336
-const THREAD_FUNC_QUEUE_SIZE: usize = 1000;
+pub(crate) const THREAD_FUNC_QUEUE_SIZE: usize = 1000;
 
 type ParseFiles = IterateFiles<ThreadsafeFunction<SgRoot, ErrorStrategy::CalleeHandled>>;
 
@@ -128,7 +130,10 @@ fn call_sg_root(
   Ok(true)
 }
 
-fn get_root(entry: ignore::DirEntry, lang_option: &LangOption) -> Ret<(AstGrep<JsDoc>, String)> {
+pub(crate) fn get_root(
+  entry: ignore::DirEntry,
+  lang_option: &LangOption,
+) -> Ret<(AstGrep<JsDoc>, String)> {
   let path = entry.into_path();
   let file_content = std::fs::read_to_string(&path)?;
   let lang = lang_option
@@ -166,9 +171,9 @@ pub fn find_in_files_impl(
   lang: NapiLang,
   config: FindConfig,
   callback: JsFunction,
-) -> Result<AsyncTask<FindInFiles>> {
+) -> Result<FindInFiles> {
   let tsfn = callback.create_threadsafe_function(THREAD_FUNC_QUEUE_SIZE, |ctx| {
-    from_pinned_data(ctx.value, ctx.env)
+    from_pinned_data(ctx.value, ctx.env).map(|nodes| vec![nodes])
   })?;
   let FindConfig {
     paths,
@@ -177,16 +182,16 @@ pub fn find_in_files_impl(
   } = config;
   let rule = matcher.parse_with(lang)?;
   let walk = lang.find_files(paths, language_globs)?;
-  Ok(AsyncTask::new(FindInFiles {
+  Ok(FindInFiles {
     walk,
     tsfn: (tsfn, rule),
     lang_option: LangOption::Specified(lang),
     producer: call_sg_node,
-  }))
+  })
 }
 
 // TODO: optimize
-fn from_pinned_data(pinned: PinnedNodes, env: napi::Env) -> Result<Vec<Vec<SgNode>>> {
+fn from_pinned_data(pinned: PinnedNodes, env: napi::Env) -> Result<Vec<SgNode>> {
   let (root, nodes) = pinned.0.into_raw();
   let sg_root = SgRoot(AstGrep { inner: root }, pinned.1);
   let reference = SgRoot::into_reference(sg_root, env)?;
@@ -202,7 +207,7 @@ fn from_pinned_data(pinned: PinnedNodes, env: napi::Env) -> Result<Vec<Vec<SgNod
     };
     v.push(sg_node);
   }
-  Ok(vec![v])
+  Ok(v)
 }
 
 fn call_sg_node(
@@ -221,13 +226,143 @@ fn call_sg_node(
   {
     return Ok(false);
   }
+  let Some(pinned) = find_pinned(rule, entry, lang_option)? else {
+    return Ok(false);
+  };
+  tsfn.call(Ok(pinned), ThreadsafeFunctionCallMode::Blocking);
+  Ok(true)
+}
+
+fn find_pinned(
+  rule: &RuleCore<NapiLang>,
+  entry: ignore::DirEntry,
+  lang_option: &LangOption,
+) -> Ret<Option<PinnedNodes>> {
   let (root, path) = get_root(entry, lang_option)?;
   let mut pinned = PinnedNodeData::new(root.inner, |r| r.root().find_all(rule).collect());
   let hits: &Vec<_> = pinned.get_data();
   if hits.is_empty() {
-    return Ok(false);
+    return Ok(None);
+  }
+  Ok(Some(PinnedNodes(pinned, path)))
+}
+
+// the number of files buffered before JS consumes them in the async iterator.
+// walker threads will block when the buffer is full, which provides backpressure.
+const ITERATOR_BUFFER_SIZE: usize = 64;
+
+type NodesReceiver = Arc<Mutex<Receiver<PinnedNodes>>>;
+
+/// Return an async iterator yielding matching nodes found in each file.
+/// Walker threads pause when too many files are waiting to be consumed.
+pub fn find_in_files_iter(env: Env, lang: NapiLang, config: FindConfig) -> Result<JsObject> {
+  let FindConfig {
+    paths,
+    matcher,
+    language_globs,
+  } = config;
+  let rule = matcher.parse_with(lang)?;
+  let walk = lang.find_files(paths, language_globs)?;
+  let (tx, rx) = sync_channel(ITERATOR_BUFFER_SIZE);
+  let closed = Arc::new(AtomicBool::new(false));
+  let lang_option = LangOption::Specified(lang);
+  spawn_walker(walk, rule, lang_option, tx, closed.clone());
+  create_async_iterator(env, Arc::new(Mutex::new(rx)), closed)
+}
+
+fn spawn_walker(
+  walk: WalkParallel,
+  rule: RuleCore<NapiLang>,
+  lang_option: LangOption,
+  tx: SyncSender<PinnedNodes>,
+  closed: Arc<AtomicBool>,
+) {
+  std::thread::spawn(move || {
+    walk.run(|| {
+      let (tx, rule, lang_option, closed) = (tx.clone(), &rule, &lang_option, &closed);
+      Box::new(move |entry| {
+        if closed.load(Ordering::Acquire) {
+          return WalkState::Quit;
+        }
+        let Ok(entry) = entry else {
+          return WalkState::Skip;
+        };
+        if !entry.file_type().map_or(false, |t| t.is_file()) {
+          return WalkState::Continue;
+        }
+        match find_pinned(rule, entry, lang_option) {
+          Ok(Some(pinned)) => match tx.send(pinned) {
+            Ok(_) => WalkState::Continue,
+            // receiver is dropped, no one will consume the nodes
+            Err(_) => WalkState::Quit,
+          },
+          Ok(None) => WalkState::Continue,
+          Err(_) => WalkState::Skip,
+        }
+      })
+    });
+  });
+}
+
+fn create_async_iterator(env: Env, rx: NodesReceiver, closed: Arc<AtomicBool>) -> Result<JsObject> {
+  let next_rx = rx.clone();
+  let next = env.create_function_from_closure("next", move |ctx| {
+    let task = NextNodes {
+      rx: next_rx.clone(),
+    };
+    Ok(ctx.env.spawn(task)?.promise_object())
+  })?;
+  // called when the loop exits early, e.g. break or throw in `for await`
+  let ret = env.create_function_from_closure("return", move |ctx| {
+    closed.store(true, Ordering::Release);
+    let rx = rx.clone();
+    // drain the buffer so blocked walker threads can observe the closed flag
+    std::thread::spawn(move || {
+      if let Ok(rx) = rx.lock() {
+        while rx.recv().is_ok() {}
+      }
+    });
+    iter_result(*ctx.env, None)
+  })?;
+  let iterator = env.create_function_from_closure("asyncIterator", |ctx| ctx.this::<JsObject>())?;
+  let symbol: JsObject = env.get_global()?.get_named_property("Symbol")?;
+  let async_iterator: JsSymbol = symbol.get_named_property("asyncIterator")?;
+  let mut obj = env.create_object()?;
+  obj.set_named_property("next", next)?;
+  obj.set_named_property("return", ret)?;
+  obj.set_property(async_iterator, iterator)?;
+  Ok(obj)
+}
+
+fn iter_result(env: Env, nodes: Option<Vec<SgNode>>) -> Result<JsObject> {
+  let mut result = env.create_object()?;
+  result.set_named_property("done", nodes.is_none())?;
+  if let Some(nodes) = nodes {
+    result.set_named_property("value", nodes)?;
+  }
+  Ok(result)
+}
+
+struct NextNodes {
+  rx: NodesReceiver,
+}
+
+impl Task for NextNodes {
+  type Output = Option<PinnedNodes>;
+  type JsValue = JsObject;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let rx = self
+      .rx
+      .lock()
+      .map_err(|_| Error::from_reason("iterator is poisoned"))?;
+    // all walker threads are finished if recv fails
+    Ok(rx.recv().ok())
+  }
+  fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    let nodes = output
+      .map(|pinned| from_pinned_data(pinned, env))
+      .transpose()?;
+    iter_result(env, nodes)
   }
-  let pinned = PinnedNodes(pinned, path);
-  tsfn.call(Ok(pinned), ThreadsafeFunctionCallMode::Blocking);
-  Ok(true)
 }
@@ -3,20 +3,23 @@
 mod doc;
 mod find_files;
 mod napi_lang;
+mod scan;
 mod sg_node;
 
 use ast_grep_core::{AstGrep, Language};
 use ast_grep_language::SupportLang;
 use napi::bindgen_prelude::*;
+use napi::JsObject;
 use napi_derive::napi;
 use napi_lang::register_dynamic_language as register_dynamic_language_impl;
 
 use doc::{JsDoc, NapiConfig};
-use find_files::{find_in_files_impl, FindConfig, FindInFiles, ParseAsync};
+use find_files::{find_in_files_impl, find_in_files_iter, FindConfig, FindInFiles, ParseAsync};
 use napi_lang::NapiLang;
 use sg_node::SgRoot;
 
 pub use find_files::parse_files;
+pub use scan::{apply_fixes_to_files, scan_files, scan_fix};
 
 macro_rules! impl_lang_mod {
   ($name: ident, $lang: ident) => {
@@ -46,7 +49,7 @@ macro_rules! impl_lang_mod {
         config: FindConfig,
         callback: JsFunction,
       ) -> Result<AsyncTask<FindInFiles>> {
-        find_in_files_impl(SupportLang::$lang.into(), config, callback)
+        find_in_files_impl(SupportLang::$lang.into(), config, callback).map(AsyncTask::new)
       }
     }
   };
@@ -110,14 +113,22 @@ pub fn pattern(lang: String, pattern: String) -> NapiConfig {
 /// `lang` specifies the language.
 /// `config` specifies the file path and matcher.
 /// `callback` will receive matching nodes found in a file.
+/// If `callback` is omitted, an async iterator yielding matching nodes in each file is returned.
 #[napi]
 pub fn find_in_files(
+  env: Env,
   lang: String,
   config: FindConfig,
-  callback: JsFunction,
-) -> Result<AsyncTask<FindInFiles>> {
+  callback: Option<JsFunction>,
+) -> Result<JsObject> {
   let lang: NapiLang = lang.parse()?;
-  find_in_files_impl(lang, config, callback)
+  match callback {
+    Some(callback) => {
+      let task = find_in_files_impl(lang, config, callback)?;
+      Ok(env.spawn(task)?.promise_object())
+    }
+    None => find_in_files_iter(env, lang, config),
+  }
 }
 
 /// Register a dynamic language to ast-grep.
@@ -8,6 +8,7 @@ use napi::anyhow::anyhow;
 use napi::anyhow::Error;
 use napi::bindgen_prelude::Result;
 use napi_derive::napi;
+use serde::{Deserialize, Deserializer};
 
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -105,6 +106,14 @@ impl FromStr for NapiLang {
   }
 }
 
+/// Deserialize `language` in rule configs. Only languages supported in napi are accepted.
+impl<'de> Deserialize<'de> for NapiLang {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    NapiLang::from_str(&name).map_err(serde::de::Error::custom)
+  }
+}
+
 impl From<SupportLang> for NapiLang {
   fn from(val: SupportLang) -> Self {
     NapiLang::Builtin(val)
@@ -0,0 +1,246 @@
+use ast_grep_config::{CombinedScan, GlobalRules, RuleCollection, RuleConfig, Severity};
+use ast_grep_core::source::Content;
+use ast_grep_core::{AstGrep, NodeMatch, Position};
+use napi::anyhow::{Context, Result as Ret};
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::Task;
+use napi_derive::napi;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::doc::{JsDoc, Wrapper};
+use crate::find_files::{get_root, Entry, IterateFiles, THREAD_FUNC_QUEUE_SIZE};
+use crate::napi_lang::{build_files, LangOption, NapiLang};
+use crate::sg_node::{apply_edits, Edit, Pos, Range};
+
+#[napi(object)]
+pub struct ScanConfig {
+  /// specify the file paths to recursively find files
+  pub paths: Vec<String>,
+  /// rule configs similar to YAML, see https://ast-grep.github.io/reference/yaml.html
+  pub rules: Vec<serde_json::Value>,
+  /// A map of language name to pattern globs of files in the language, e.g. { html: ['*.vue'] }
+  pub language_globs: Option<HashMap<String, Vec<String>>>,
+}
+
+/// A finding reported by a rule, similar to the JSON output of `sg scan`.
+#[napi(object)]
+pub struct ScanMatch {
+  pub file: String,
+  pub rule_id: String,
+  /// One of: hint, info, warning, or error
+  pub severity: String,
+  pub message: String,
+  pub note: Option<String>,
+  pub text: String,
+  pub range: Range,
+  /// The edit to apply the first fix of the rule, if the rule has fix
+  pub fix: Option<Edit>,
+}
+
+pub type ScanFiles = IterateFiles<(
+  ThreadsafeFunction<Vec<ScanMatch>, ErrorStrategy::CalleeHandled>,
+  RuleCollection<NapiLang>,
+)>;
+
+fn parse_rules(rules: Vec<serde_json::Value>) -> Result<Vec<RuleConfig<NapiLang>>> {
+  let globals = GlobalRules::default();
+  rules
+    .into_iter()
+    .map(|rule| {
+      let config = serde_json::from_value(rule)?;
+      RuleConfig::try_from(config, &globals)
+        .map_err(|e| napi::Error::new(napi::Status::InvalidArg, e.to_string()))
+    })
+    .collect()
+}
+
+/// Scan files with rule configs like `sg scan`.
+/// `callback` will receive the matches found in a file.
+#[napi]
+pub fn scan_files(config: ScanConfig, callback: JsFunction) -> Result<AsyncTask<ScanFiles>> {
+  let tsfn: ThreadsafeFunction<Vec<ScanMatch>, ErrorStrategy::CalleeHandled> =
+    callback.create_threadsafe_function(THREAD_FUNC_QUEUE_SIZE, |ctx| Ok(vec![ctx.value]))?;
+  let rules = RuleCollection::try_new(parse_rules(config.rules)?)
+    .map_err(|e| napi::Error::new(napi::Status::InvalidArg, e.to_string()))?;
+  let globs = NapiLang::lang_globs(config.language_globs.unwrap_or_default());
+  let walk = build_files(config.paths, &globs)?;
+  Ok(AsyncTask::new(ScanFiles {
+    walk,
+    tsfn: (tsfn, rules),
+    lang_option: LangOption::infer(&globs),
+    producer: call_scan,
+  }))
+}
+
+fn call_scan(
+  (tsfn, rules): &(
+    ThreadsafeFunction<Vec<ScanMatch>, ErrorStrategy::CalleeHandled>,
+    RuleCollection<NapiLang>,
+  ),
+  entry: Entry,
+  lang_option: &LangOption,
+) -> Ret<bool> {
+  let entry = entry?;
+  if !entry
+    .file_type()
+    .context("could not use stdin as file")?
+    .is_file()
+  {
+    return Ok(false);
+  }
+  let (root, path) = get_root(entry, lang_option)?;
+  let configs = rules.get_rule_from_lang(Path::new(&path), *root.lang());
+  let matches = scan_root(&root, &path, configs);
+  if matches.is_empty() {
+    return Ok(false);
+  }
+  tsfn.call(Ok(matches), ThreadsafeFunctionCallMode::Blocking);
+  Ok(true)
+}
+
+fn scan_root(
+  root: &AstGrep<JsDoc>,
+  path: &str,
+  rules: Vec<&RuleConfig<NapiLang>>,
+) -> Vec<ScanMatch> {
+  let combined = CombinedScan::new(rules);
+  let pre_scan = combined.find(root);
+  let scanned = combined.scan(root, pre_scan, false);
+  let mut matches: Vec<_> = scanned
+    .matches
+    .into_iter()
+    .flat_map(|(rule, nms)| nms.into_iter().map(move |nm| to_scan_match(path, rule, nm)))
+    .collect();
+  matches.sort_by_key(|m| (m.range.start.index, m.range.end.index));
+  matches
+}
+
+fn to_pos(nm: &NodeMatch<JsDoc>, pos: Position, offset: usize) -> Pos {
+  Pos {
+    line: pos.line() as u32,
+    column: pos.column(nm) as u32,
+    index: offset as u32 / 2,
+  }
+}
+
+fn to_scan_match(path: &str, rule: &RuleConfig<NapiLang>, nm: NodeMatch<JsDoc>) -> ScanMatch {
+  let byte_range = nm.range();
+  let range = Range {
+    start: to_pos(&nm, nm.start_pos(), byte_range.start),
+    end: to_pos(&nm, nm.end_pos(), byte_range.end),
+  };
+  let fix = rule.matcher.fixer.first().map(|fixer| {
+    let edit = nm.make_edit(&rule.matcher, fixer);
+    Edit {
+      start_pos: (edit.position / 2) as u32,
+      end_pos: ((edit.position + edit.deleted_length) / 2) as u32,
+      inserted_text: String::from_utf16_lossy(&edit.inserted_text),
+    }
+  });
+  let severity = match rule.severity {
+    Severity::Hint => "hint",
+    Severity::Info => "info",
+    Severity::Warning => "warning",
+    Severity::Error => "error",
+    Severity::Off => "off",
+  };
+  ScanMatch {
+    file: path.to_string(),
+    rule_id: rule.id.clone(),
+    severity: severity.to_string(),
+    message: rule.get_message(&nm),
+    note: rule.note.clone(),
+    text: nm.text().to_string(),
+    range,
+    fix,
+  }
+}
+
+/// Apply fixes of all rules in the language to the source and return the fixed code.
+/// Overlapping fixes are skipped. `files` and `ignores` of rules are not checked.
+#[napi]
+pub fn scan_fix(lang: String, src: String, rules: Vec<serde_json::Value>) -> Result<String> {
+  let lang: NapiLang = lang.parse()?;
+  let configs = parse_rules(rules)?;
+  let configs = configs.iter().filter(|r| r.language == lang).collect();
+  let root = AstGrep::doc(JsDoc::new(src, lang));
+  let matches = scan_root(&root, "anonymous", configs);
+  let edits = matches.into_iter().filter_map(|m| m.fix).collect();
+  apply_edits(&root.root().text(), edits, 0)
+}
+
+/// A fix to apply in a file. `ScanMatch` returned by `scanFiles` can be used directly.
+#[napi(object)]
+pub struct FileFix {
+  pub file: String,
+  pub fix: Option<Edit>,
+  /// The matched text when the file was scanned. If given with `range`,
+  /// the file is not written if it no longer has the text in the range.
+  pub text: Option<String>,
+  pub range: Option<Range>,
+}
+
+/// Whether the file still has the text in the range, positions are in utf-16 units.
+fn is_unchanged(content: &[u16], text: &str, range: &Range) -> bool {
+  let (start, end) = (range.start.index as usize, range.end.index as usize);
+  let expected = Wrapper::decode_str(text);
+  start <= end && content.get(start..end) == Some(&*expected)
+}
+
+pub struct ApplyFixes {
+  fixes: Vec<FileFix>,
+}
+
+impl Task for ApplyFixes {
+  type Output = u32;
+  type JsValue = u32;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let mut fixes_by_file: HashMap<String, Vec<FileFix>> = HashMap::new();
+    for fix in std::mem::take(&mut self.fixes) {
+      if fix.fix.is_some() {
+        fixes_by_file.entry(fix.file.clone()).or_default().push(fix);
+      }
+    }
+    let mut changed = 0;
+    for (file, fixes) in fixes_by_file {
+      let to_err = |e: std::io::Error| napi::Error::from_reason(format!("{file}: {e}"));
+      let text = std::fs::read_to_string(&file).map_err(to_err)?;
+      let content = Wrapper::decode_str(&text);
+      let mut edits = vec![];
+      for FileFix {
+        fix, text, range, ..
+      } in fixes
+      {
+        if let (Some(text), Some(range)) = (text, range) {
+          if !is_unchanged(&content, &text, &range) {
+            let reason = format!("{file}: the file has changed since it was scanned");
+            return Err(napi::Error::from_reason(reason));
+          }
+        }
+        edits.extend(fix);
+      }
+      let new_content = apply_edits(&text, edits, 0)?;
+      if new_content != text {
+        std::fs::write(&file, new_content).map_err(to_err)?;
+        changed += 1;
+      }
+    }
+    Ok(changed)
+  }
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+/// Apply fixes to files and write them back to disk.
+/// Overlapping fixes in the same file are skipped.
+/// Throws if a file has changed since it was scanned, or a fix is out of the file.
+/// Returns the number of changed files.
+#[napi]
+pub fn apply_fixes_to_files(fixes: Vec<FileFix>) -> AsyncTask<ApplyFixes> {
+  AsyncTask::new(ApplyFixes { fixes })
+}
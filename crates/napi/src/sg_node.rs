@@ -386,30 +386,43 @@ impl SgNode {
   }
 
   #[napi]
-  pub fn commit_edits(&self, mut edits: Vec<Edit>) -> String {
-    edits.sort_by_key(|edit| edit.start_pos);
-    let mut new_content = Vec::new();
+  pub fn commit_edits(&self, edits: Vec<Edit>) -> Result<String> {
     let text = self.text();
-    let old_content = Wrapper::decode_str(&text);
     let offset = self.inner.range().start / 2;
-    let mut start = 0;
-    for diff in edits {
-      let pos = diff.start_pos as usize - offset;
-      // skip overlapping edits
-      if start > pos {
-        continue;
-      }
-      new_content.extend(&old_content[start..pos]);
-      let bytes = Wrapper::decode_str(&diff.inserted_text);
-      new_content.extend(&*bytes);
-      start = diff.end_pos as usize - offset;
-    }
-    // add trailing statements
-    new_content.extend(&old_content[start..]);
-    Wrapper::encode_bytes(&new_content).to_string()
+    apply_edits(&text, edits, offset)
   }
 }
 
+/// apply edits to the text starting at `offset`, edits' positions are in utf-16 units
+/// Returns an error if an edit is out of the text, e.g. it was made before the source changed.
+pub(crate) fn apply_edits(text: &str, mut edits: Vec<Edit>, offset: usize) -> Result<String> {
+  edits.sort_by_key(|edit| edit.start_pos);
+  let mut new_content = Vec::new();
+  let old_content = Wrapper::decode_str(text);
+  let end = offset + old_content.len();
+  let mut start = 0;
+  for diff in edits {
+    let (edit_start, edit_end) = (diff.start_pos as usize, diff.end_pos as usize);
+    if edit_start < offset || edit_start > edit_end || edit_end > end {
+      return Err(Error::from_reason(format!(
+        "edit {edit_start}..{edit_end} is out of the source range {offset}..{end}"
+      )));
+    }
+    let pos = edit_start - offset;
+    // skip overlapping edits
+    if start > pos {
+      continue;
+    }
+    new_content.extend(&old_content[start..pos]);
+    let bytes = Wrapper::decode_str(&diff.inserted_text);
+    new_content.extend(&*bytes);
+    start = edit_end - offset;
+  }
+  // add trailing statements
+  new_content.extend(&old_content[start..]);
+  Ok(Wrapper::encode_bytes(&new_content).to_string())
+}
+
 /// Represents the parsed tree of code.
 #[napi]
 pub struct SgRoot(pub(super) AstGrep<JsDoc>, pub(super) String);
@@ -428,4 +441,29 @@ impl SgRoot {
   pub fn filename(&self) -> Result<String> {
     Ok(self.1.clone())
   }
+  /// Apply edits to the whole source and return the new source.
+  /// If `write` is true, the new source is also written back to the file of `filename()`,
+  /// unless the file has changed since it was parsed.
+  #[napi]
+  pub fn commit_edits(&self, edits: Vec<Edit>, write: Option<bool>) -> Result<String> {
+    let text = self.0.root().text().to_string();
+    let new_content = apply_edits(&text, edits, 0)?;
+    if write.unwrap_or(false) {
+      if self.1 == "anonymous" {
+        return Err(Error::from_reason(
+          "cannot write edits of source not discovered from files",
+        ));
+      }
+      let current =
+        std::fs::read_to_string(&self.1).map_err(|e| Error::from_reason(e.to_string()))?;
+      if current != text {
+        return Err(Error::from_reason(format!(
+          "{} has changed since it was parsed",
+          self.1
+        )));
+      }
+      std::fs::write(&self.1, &new_content).map_err(|e| Error::from_reason(e.to_string()))?;
+    }
+    Ok(new_content)
+  }
 }
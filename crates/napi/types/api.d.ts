@@ -1,5 +1,12 @@
 import type { SgNode, SgRoot } from './sgnode'
-import type { NapiConfig, FindConfig, FileOption } from './config'
+import type {
+  NapiConfig,
+  FindConfig,
+  FileOption,
+  ScanConfig,
+  ScanMatch,
+  FileFix,
+} from './config'
 import type { NapiLang } from './lang'
 import type { NamedKinds, TypesMap } from './staticTypes'
 
@@ -43,4 +50,41 @@ export declare function findInFiles<M extends TypesMap>(
   lang: NapiLang,
   config: FindConfig<M>,
   callback: (err: null | Error, result: SgNode<M>[]) => void,
-): Promise<number>
\ No newline at end of file
+): Promise<number>
+/**
+ * Discover and parse multiple files in Rust, consumed by `for await`.
+ * Each iteration yields matching nodes found in a file.
+ * Files are discovered in background threads, which pause
+ * when too many files are waiting to be consumed.
+ */
+export declare function findInFiles<M extends TypesMap>(
+  lang: NapiLang,
+  config: FindConfig<M>,
+): AsyncIterableIterator<SgNode<M>[]>
+/**
+ * Discover and scan multiple files with rule configs, like `sg scan`.
+ * `config` specifies the file paths and rules.
+ * `callback` will receive the rule findings in a file.
+ */
+export declare function scanFiles(
+  config: ScanConfig,
+  callback: (err: null | Error, result: ScanMatch[]) => void,
+): Promise<number>
+/**
+ * Apply fixes of all rules in the language to the source and return the fixed code.
+ * Overlapping fixes are skipped. `files` and `ignores` of rules are not checked.
+ */
+export declare function scanFix(
+  lang: NapiLang,
+  src: string,
+  rules: Array<unknown>,
+): string
+/**
+ * Apply fixes to files and write them back to disk.
+ * Overlapping fixes in the same file are skipped.
+ * Throws if a file has changed since it was scanned, or a fix is out of the file.
+ * Returns the number of changed files.
+ */
+export declare function applyFixesToFiles(
+  fixes: Array<FileFix>,
+): Promise<number>
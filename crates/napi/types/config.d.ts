@@ -1,5 +1,6 @@
 import type { Rule } from './rule'
 import type { NapiLang } from './lang'
+import type { Edit, Range } from './sgnode'
 import type { TypesMap } from './staticTypes'
 
 /**
@@ -14,7 +15,7 @@ export interface NapiConfig<M extends TypesMap = TypesMap> {
   /** Builtin Language or custom language */
   language?: NapiLang
   /**
-   * transform is NOT useful in JavaScript. You can use JS code to directly transform the result.
+   * Transformed meta variables can be read by `getTransformed` of the matched node.
    * https://ast-grep.github.io/reference/yaml.html#transform
    */
   transform?: unknown
@@ -36,4 +37,36 @@ export interface FindConfig<M extends TypesMap = TypesMap> {
    * It is slightly different from https://ast-grep.github.io/reference/sgconfig.html#languageglobs
    */
   languageGlobs?: Array<string>
-}
\ No newline at end of file
+}
+export interface ScanConfig {
+  /** specify the file paths to recursively find files */
+  paths: Array<string>
+  /** rule configs similar to YAML, see https://ast-grep.github.io/reference/yaml.html */
+  rules: Array<unknown>
+  /** A map of language name to pattern globs of files in the language, e.g. { html: ['*.vue'] } */
+  languageGlobs?: Record<string, Array<string>>
+}
+/** A finding reported by a rule, similar to the JSON output of `sg scan`. */
+export interface ScanMatch {
+  file: string
+  ruleId: string
+  /** One of: hint, info, warning, or error */
+  severity: string
+  message: string
+  note?: string
+  text: string
+  range: Range
+  /** The edit to apply the first fix of the rule, if the rule has fix */
+  fix?: Edit
+}
+/** A fix to apply in a file. `ScanMatch` returned by `scanFiles` can be used directly. */
+export interface FileFix {
+  file: string
+  fix?: Edit
+  /**
+   * The matched text when the file was scanned. If given with `range`,
+   * the file is not written if it no longer has the text in the range.
+   */
+  text?: string
+  range?: Range
+}
@@ -13,6 +13,18 @@ interface LangRegistration {
    * See https://ast-grep.github.io/advanced/custom-language.html#register-language-in-sgconfig-yml
    */
   expandoChar?: string
+  /** regions of other languages embedded in this language */
+  injections?: LanguageInjection[]
+}
+
+/** A region of another language embedded in a custom language */
+interface LanguageInjection {
+  /** the kind of node containing the embedded code, e.g. script_element */
+  kind: string
+  /** the field of the node whose text is the embedded code, the node itself if omitted */
+  field?: string
+  /** the embedded language, e.g. js */
+  language: string
 }
 
 /** A map of language names to their registration information */
@@ -6,6 +6,8 @@ export interface PatternObject<M extends TypesMap = TypesMap> {
   context: string
   selector?: NamedKinds<M> // only named node types
   strictness?: Strictness
+  /** A map from meta variable name to the node kind it must match. */
+  vars?: Record<string, NamedKinds<M>>
 }
 
 export type PatternStyle<M extends TypesMap = TypesMap> =
@@ -19,6 +21,8 @@ export interface Relation<M extends TypesMap = TypesMap> extends Rule<M> {
   stopBy?: 'neighbor' | 'end' | Rule<M>
   /** Specify the tree-sitter field in parent node. Only available in has/inside rule. */
   field?: string
+  /** Sibling nodes between the target and the matched node must not match this rule. Only available in precedes/follows rule. */
+  notBetween?: Rule<M>
 }
 
 export interface NthChildObject<M extends TypesMap = TypesMap> {
@@ -54,7 +58,7 @@ export interface Range {
 export interface Rule<M extends TypesMap = TypesMap> {
   /** A pattern string or a pattern object. */
   pattern?: PatternStyle<M>
-  /** The kind name of the node to match. You can look up code's kind names in playground. */
+  /** The kind name of the node to match. You can look up code's kind names in playground. Multiple kinds can be separated by `|`, and `*` matches any part of a kind name, e.g. `*_statement`. */
   kind?: NamedKinds<M>
   /** The exact range of the node in the source code. */
   range?: Range
@@ -95,6 +95,11 @@ export declare class SgRoot<M extends TypesMap = TypesMap> {
    * Returns `"anonymous"` if the instance is created by `lang.parse(source)`.
    */
   filename(): string
+  /**
+   * Apply edits to the whole source and return the new source.
+   * If `write` is true, the new source is also written back to the file of `filename()`.
+   */
+  commitEdits(edits: Array<Edit>, write?: boolean): string
 }
 
 interface NodeMethod<M extends TypesMap, Args extends unknown[] = []> {
@@ -1,7 +1,7 @@
 from __future__ import annotations
 
 from typing import List, TypedDict,  Literal, Dict, Union, Mapping, Optional
-from .ast_grep_py import SgNode, SgRoot, Pos, Range, Edit, register_dynamic_language
+from .ast_grep_py import SgNode, SgRoot, Pos, Range, Edit, RuleConfig, ScanIterator, register_dynamic_language, scan_path
 
 Strictness = Union[Literal["cst"], Literal["smart"], Literal["ast"], Literal["relaxed"], Literal["signature"]]
 
@@ -9,6 +9,7 @@ class Pattern(TypedDict):
     selector: Optional[str]
     strictness: Optional[Strictness]
     context: str
+    vars: Optional[Dict[str, str]]
 
 class NthChild(TypedDict):
     position: int | str
@@ -57,18 +58,26 @@ StopBy = Union[Literal["neighbor"], Literal["end"], Rule]
 class Relation(RuleWithoutNot, TypedDict("Not", {"not": "Rule"}, total=False), total=False):
     stopBy: StopBy
     field: str
+    notBetween: Rule
 
 class Config(TypedDict, total=False):
     rule: Rule
     constraints: Dict[str, Mapping]
     utils: Dict[str, Rule]
     transform: Dict[str, Mapping]
+    fix: str | Mapping
+
+class LanguageInjection(TypedDict, total=False):
+  kind: str
+  field: Optional[str]
+  language: str
 
 class CustomLang(TypedDict, total=False):
   library_path: str
   language_symbol: Optional[str]
   meta_var_char: Optional[str]
   expando_char: Optional[str]
+  injections: List[LanguageInjection]
 
 __all__ = [
     "Rule",
@@ -81,5 +90,8 @@ __all__ = [
     "Pos",
     "Range",
     "Edit",
+    "RuleConfig",
+    "ScanIterator",
     "register_dynamic_language",
+    "scan_path",
 ]
\ No newline at end of file
@@ -1,4 +1,4 @@
-from typing import List, Optional, overload, Unpack, Dict
+from typing import Iterator, List, Optional, overload, Unpack, Dict, Union
 
 from . import Rule, Config, CustomLang
 
@@ -16,10 +16,17 @@ class Edit:
     end_pos: int
     inserted_text: str
 
+class RuleConfig:
+    id: str
+    language: str
+    @staticmethod
+    def from_yaml(yaml: str) -> RuleConfig: ...
+
 class SgRoot:
     def __init__(self, src: str, language: str) -> None: ...
     def root(self) -> SgNode: ...
     def filename(self) -> str: ...
+    def apply_rules(self, rules: List[Config | RuleConfig]) -> str: ...
 
 class SgNode:
     # Node Inspection
@@ -43,11 +50,11 @@ class SgNode:
 
     # Search
     @overload
-    def find(self, config: Config) -> Optional[SgNode]: ...
+    def find(self, config: Config | RuleConfig) -> Optional[SgNode]: ...
     @overload
     def find(self, **kwargs: Unpack[Rule]) -> Optional[SgNode]: ...
     @overload
-    def find_all(self, config: Config) -> List[SgNode]: ...
+    def find_all(self, config: Config | RuleConfig) -> List[SgNode]: ...
     @overload
     def find_all(self, **kwargs: Unpack[Rule]) -> List[SgNode]: ...
 
@@ -67,5 +74,17 @@ class SgNode:
     # Edit
     def replace(self, new_text: str) -> Edit: ...
     def commit_edits(self, edits: List[Edit]) -> str: ...
+    def get_fix(self, rule: Config | RuleConfig) -> Optional[Edit]: ...
+
+def register_dynamic_language(langs: Dict[str, CustomLang]): ...
+
+class ScanIterator(Iterator[SgNode]):
+    def __iter__(self) -> ScanIterator: ...
+    def __next__(self) -> SgNode: ...
 
-def register_dynamic_language(langs: Dict[str, CustomLang]): ...
\ No newline at end of file
+def scan_path(
+    path: str,
+    rule_or_pattern: Union[str, Config, RuleConfig],
+    language: str,
+    globs: Optional[List[str]] = None,
+) -> ScanIterator: ...
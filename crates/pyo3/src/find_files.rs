@@ -0,0 +1,174 @@
+use crate::py_config::RuleConfig;
+use crate::py_lang::PyLang;
+use crate::py_node::{config_from_dict, config_from_rule, SgNode};
+use crate::unicode_position::UnicodePosition;
+use crate::SgRoot;
+
+use ast_grep_config::{DeserializeEnv, RuleCore};
+use ast_grep_core::pinned::{NodeData, PinnedNodeData};
+use ast_grep_core::{AstGrep, Language, NodeMatch, StrDoc};
+use ignore::{WalkParallel, WalkState};
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Mutex;
+
+use anyhow::Context;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+// the number of files buffered before Python consumes them.
+// walker threads will block when the buffer is full.
+const BUFFER_SIZE: usize = 64;
+
+type PinnedMatches = PinnedNodeData<StrDoc<PyLang>, Vec<NodeMatch<'static, StrDoc<PyLang>>>>;
+
+/// matches found in a file by walker threads, not yet owned by Python
+struct FileMatches {
+  pinned: PinnedMatches,
+  path: String,
+  position: UnicodePosition,
+}
+
+// Root and its nodes are sent together so the nodes are valid in another thread.
+unsafe impl Send for FileMatches {}
+
+impl FileMatches {
+  fn into_nodes(self, py: Python) -> PyResult<Vec<SgNode>> {
+    let (inner, mut nodes) = self.pinned.into_raw();
+    let root = SgRoot {
+      inner: AstGrep { inner },
+      filename: self.path,
+      position: self.position,
+    };
+    let root = Py::new(py, root)?;
+    // Root is moved to Python heap, nodes need to point to the new address
+    // keep the borrow guard alive while nodes are readopted
+    let root_ref = root.borrow(py);
+    let tree = &root_ref.inner as *const AstGrep<StrDoc<PyLang>>;
+    nodes.visit_nodes(|n| unsafe { (*tree).inner.readopt(n) });
+    drop(root_ref);
+    let nodes = nodes
+      .into_iter()
+      .map(|inner| SgNode {
+        inner,
+        root: root.clone_ref(py),
+      })
+      .collect();
+    Ok(nodes)
+  }
+}
+
+/// Iterator of matched nodes found in files.
+/// Files are parsed and matched in Rust threads without holding the GIL.
+#[pyclass]
+pub struct ScanIterator {
+  rx: Mutex<Receiver<FileMatches>>,
+  pending: VecDeque<SgNode>,
+}
+
+#[pymethods]
+impl ScanIterator {
+  fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+    slf
+  }
+
+  fn __next__(mut slf: PyRefMut<Self>) -> PyResult<Option<SgNode>> {
+    loop {
+      if let Some(node) = slf.pending.pop_front() {
+        return Ok(Some(node));
+      }
+      let py = slf.py();
+      let rx = &slf.rx;
+      // all walker threads are finished if recv fails
+      let Some(matches) = py.allow_threads(|| rx.lock().ok()?.recv().ok()) else {
+        return Ok(None);
+      };
+      let nodes = matches.into_nodes(py)?;
+      slf.pending.extend(nodes);
+    }
+  }
+}
+
+/// Find nodes matching the rule in files under `path`.
+/// `rule_or_pattern` is a pattern string, a rule config dict or a `RuleConfig`.
+/// `globs` are extra file patterns to treat as the language, e.g. ["*.vue"] for html.
+#[pyfunction]
+#[pyo3(signature = (path, rule_or_pattern, language, globs=None))]
+pub fn scan_path(
+  path: &str,
+  rule_or_pattern: Bound<PyAny>,
+  language: &str,
+  globs: Option<Vec<String>>,
+) -> PyResult<ScanIterator> {
+  let lang: PyLang = language
+    .parse()
+    .map_err(|e| PyErr::new::<PyValueError, _>(format!("{e}")))?;
+  let matcher = if let Ok(rule) = rule_or_pattern.downcast::<RuleConfig>() {
+    rule.borrow().get_matcher(lang)?
+  } else {
+    let config = if let Ok(pattern) = rule_or_pattern.extract::<String>() {
+      let rule = PyDict::new(rule_or_pattern.py());
+      rule.set_item("pattern", pattern)?;
+      config_from_rule(rule)?
+    } else {
+      config_from_dict(rule_or_pattern.downcast_into()?)?
+    };
+    let env = DeserializeEnv::new(lang);
+    config.get_matcher(env).context("cannot get matcher")?
+  };
+  let walk = lang.find_files(path, globs)?;
+  let (tx, rx) = sync_channel(BUFFER_SIZE);
+  spawn_walker(walk, lang, matcher, tx);
+  Ok(ScanIterator {
+    rx: Mutex::new(rx),
+    pending: VecDeque::new(),
+  })
+}
+
+fn spawn_walker(
+  walk: WalkParallel,
+  lang: PyLang,
+  matcher: RuleCore<PyLang>,
+  tx: SyncSender<FileMatches>,
+) {
+  std::thread::spawn(move || {
+    walk.run(|| {
+      let (tx, matcher) = (tx.clone(), &matcher);
+      Box::new(move |entry| {
+        let Ok(entry) = entry else {
+          return WalkState::Skip;
+        };
+        if !entry.file_type().map_or(false, |t| t.is_file()) {
+          return WalkState::Continue;
+        }
+        let Some(matches) = find_matches(entry.path(), lang, matcher) else {
+          return WalkState::Continue;
+        };
+        match tx.send(matches) {
+          Ok(_) => WalkState::Continue,
+          // iterator is dropped, no one will consume the matches
+          Err(_) => WalkState::Quit,
+        }
+      })
+    });
+  });
+}
+
+fn find_matches(path: &Path, lang: PyLang, matcher: &RuleCore<PyLang>) -> Option<FileMatches> {
+  let src = std::fs::read_to_string(path).ok()?;
+  let position = UnicodePosition::new(&src);
+  let grep = lang.ast_grep(src);
+  let mut pinned = PinnedNodeData::new(grep.inner, |r| r.root().find_all(matcher).collect());
+  let hits: &Vec<_> = pinned.get_data();
+  if hits.is_empty() {
+    return None;
+  }
+  Some(FileMatches {
+    pinned,
+    path: path.to_string_lossy().into(),
+    position,
+  })
+}
@@ -1,9 +1,13 @@
 #![cfg(not(test))]
 #![cfg(feature = "python")]
+mod find_files;
+mod py_config;
 mod py_lang;
 mod py_node;
 mod range;
 mod unicode_position;
+use find_files::{scan_path, ScanIterator};
+use py_config::RuleConfig;
 use py_lang::register_dynamic_language;
 use py_node::{Edit, SgNode};
 use range::{Pos, Range};
@@ -22,7 +26,10 @@ fn ast_grep_py(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
   m.add_class::<Range>()?;
   m.add_class::<Pos>()?;
   m.add_class::<Edit>()?;
+  m.add_class::<ScanIterator>()?;
+  m.add_class::<RuleConfig>()?;
   m.add_function(wrap_pyfunction!(register_dynamic_language, m)?)?;
+  m.add_function(wrap_pyfunction!(scan_path, m)?)?;
   Ok(())
 }
 
@@ -59,4 +66,9 @@ impl SgRoot {
   fn filename(&self) -> &str {
     &self.filename
   }
+
+  /// Apply fixes of all rules to the source and return the new source.
+  fn apply_rules(slf: PyRef<Self>, rules: Vec<Bound<PyAny>>) -> PyResult<String> {
+    Self::root(slf).apply_rules(rules)
+  }
 }
@@ -0,0 +1,56 @@
+use crate::py_lang::PyLang;
+
+use ast_grep_config::{from_yaml_string, GlobalRules, RuleCore, SerializableRuleConfig};
+
+use anyhow::Context;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// A full rule config parsed from YAML, including utils, constraints and transform.
+#[pyclass]
+pub struct RuleConfig {
+  inner: SerializableRuleConfig<PyLang>,
+}
+
+#[pymethods]
+impl RuleConfig {
+  /// Parse a YAML string containing exactly one rule.
+  #[staticmethod]
+  fn from_yaml(yaml: &str) -> PyResult<Self> {
+    let mut configs =
+      from_yaml_string::<PyLang>(yaml, &GlobalRules::default()).context("cannot parse rule")?;
+    if configs.len() != 1 {
+      return Err(PyErr::new::<PyValueError, _>(
+        "YAML must contain exactly one rule",
+      ));
+    }
+    let config = configs.pop().expect("must have one rule");
+    Ok(Self {
+      inner: (*config).clone(),
+    })
+  }
+
+  #[getter]
+  fn id(&self) -> &str {
+    &self.inner.id
+  }
+
+  #[getter]
+  fn language(&self) -> String {
+    self.inner.language.to_string()
+  }
+}
+
+impl RuleConfig {
+  pub fn get_matcher(&self, lang: PyLang) -> PyResult<RuleCore<PyLang>> {
+    if self.inner.language != lang {
+      let msg = format!("rule `{}` is not written in {lang}", self.inner.id);
+      return Err(PyErr::new::<PyValueError, _>(msg));
+    }
+    let matcher = self
+      .inner
+      .get_matcher(&GlobalRules::default())
+      .context("cannot get matcher")?;
+    Ok(matcher)
+  }
+}
@@ -1,8 +1,10 @@
 use anyhow::Context;
 use ast_grep_core::language::TSLanguage;
-use ast_grep_dynamic::{CustomLang, DynamicLang};
+use ast_grep_dynamic::{CustomLang, DynamicLang, LanguageInjection};
 use ast_grep_language::{Language, SupportLang};
-use serde::{Deserialize, Serialize};
+use ignore::types::{Types, TypesBuilder};
+use ignore::{WalkBuilder, WalkParallel};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
@@ -25,6 +27,8 @@ pub struct CustomPyLang {
   meta_var_char: Option<char>,
   expando_char: Option<char>,
   extensions: Vec<String>,
+  #[serde(default)]
+  injections: Vec<LanguageInjection>,
 }
 
 impl From<CustomPyLang> for CustomLang {
@@ -35,6 +39,7 @@ impl From<CustomPyLang> for CustomLang {
       meta_var_char: c.meta_var_char,
       expando_char: c.expando_char,
       extensions: c.extensions,
+      injections: c.injections,
     }
   }
 }
@@ -57,6 +62,48 @@ pub enum PyLang {
   Builtin(SupportLang),
   Custom(DynamicLang),
 }
+impl PyLang {
+  fn file_types(&self) -> Types {
+    match self {
+      Builtin(b) => b.file_types(),
+      Custom(c) => c.file_types(),
+    }
+  }
+
+  /// walk files of the language under path, `globs` are extra patterns to treat files in the language
+  pub fn find_files(&self, path: &str, globs: Option<Vec<String>>) -> anyhow::Result<WalkParallel> {
+    let mut builder = TypesBuilder::new();
+    let type_name = self.to_string();
+    for def in self.file_types().definitions() {
+      for glob in def.globs() {
+        builder.add(def.name(), glob)?;
+      }
+    }
+    for glob in globs.unwrap_or_default() {
+      builder.add(&type_name, &glob)?;
+    }
+    let types = builder.select(&type_name).build()?;
+    Ok(WalkBuilder::new(path).types(types).build_parallel())
+  }
+}
+
+impl Display for PyLang {
+  fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+    match self {
+      Builtin(b) => write!(f, "{}", b),
+      Custom(c) => write!(f, "{}", c.name()),
+    }
+  }
+}
+
+/// Deserialize `language` in YAML rule configs.
+impl<'de> Deserialize<'de> for PyLang {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    PyLang::from_str(&name).map_err(serde::de::Error::custom)
+  }
+}
+
 #[derive(Debug)]
 pub enum PyLangErr {
   LanguageNotSupported(String),
@@ -1,9 +1,10 @@
+use crate::py_config::RuleConfig;
 use crate::py_lang::PyLang;
 use crate::range::Range;
 use crate::SgRoot;
 
 use ast_grep_config::{DeserializeEnv, RuleCore, SerializableRuleCore};
-use ast_grep_core::{NodeMatch, StrDoc};
+use ast_grep_core::{Matcher, NodeMatch, StrDoc};
 
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -128,7 +129,7 @@ impl SgNode {
   #[pyo3(signature = (config=None, **rule))]
   fn find(
     &self,
-    config: Option<Bound<PyDict>>,
+    config: Option<Bound<PyAny>>,
     rule: Option<Bound<PyDict>>,
   ) -> PyResult<Option<Self>> {
     let matcher = self.get_matcher(config, rule)?;
@@ -145,7 +146,7 @@ impl SgNode {
   #[pyo3(signature = (config=None, **rule))]
   fn find_all(
     &self,
-    config: Option<Bound<PyDict>>,
+    config: Option<Bound<PyAny>>,
     rule: Option<Bound<PyDict>>,
   ) -> PyResult<Vec<Self>> {
     let matcher = self.get_matcher(config, rule)?;
@@ -301,6 +302,17 @@ impl SgNode {
     new_content
   }
 
+  /// Get the edit to fix this node with the `fix` of the rule.
+  /// Returns None if the rule does not match this node or has no fix.
+  fn get_fix(&self, rule: Bound<PyAny>) -> PyResult<Option<Edit>> {
+    let matcher = self.get_matcher(Some(rule), None)?;
+    let node = (*self.inner).clone();
+    let Some(nm) = matcher.match_node(node) else {
+      return Ok(None);
+    };
+    Ok(self.make_fix(&nm, &matcher))
+  }
+
   /*---------- Magic Method  ----------*/
   fn __hash__(&self) -> u64 {
     let mut s = DefaultHasher::new();
@@ -337,12 +349,15 @@ impl SgNode {
 impl SgNode {
   fn get_matcher(
     &self,
-    config: Option<Bound<PyDict>>,
+    config: Option<Bound<PyAny>>,
     kwargs: Option<Bound<PyDict>>,
   ) -> PyResult<RuleCore<PyLang>> {
     let lang = self.inner.lang();
     let config = if let Some(config) = config {
-      config_from_dict(config)?
+      if let Ok(rule) = config.downcast::<RuleConfig>() {
+        return rule.borrow().get_matcher(*lang);
+      }
+      config_from_dict(config.downcast_into()?)?
     } else if let Some(rule) = kwargs {
       config_from_rule(rule)?
     } else {
@@ -352,13 +367,40 @@ impl SgNode {
     let matcher = config.get_matcher(env).context("cannot get matcher")?;
     Ok(matcher)
   }
+
+  fn make_fix(&self, nm: &NodeMatch<StrDoc<PyLang>>, matcher: &RuleCore<PyLang>) -> Option<Edit> {
+    let fixer = matcher.fixer.first()?;
+    let edit = nm.make_edit(matcher, fixer);
+    Python::with_gil(|py| {
+      let root = self.root.bind(py);
+      let root = root.borrow();
+      let conv = &root.position;
+      Some(Edit {
+        start_pos: conv.byte_to_char(edit.position),
+        end_pos: conv.byte_to_char(edit.position + edit.deleted_length),
+        inserted_text: String::from_utf8_lossy(&edit.inserted_text).into_owned(),
+      })
+    })
+  }
+
+  /// apply fixes of all rules to the text of this node, overlapping fixes are skipped
+  pub(crate) fn apply_rules(&self, rules: Vec<Bound<PyAny>>) -> PyResult<String> {
+    let mut edits = vec![];
+    for rule in rules {
+      let matcher = self.get_matcher(Some(rule), None)?;
+      for nm in self.inner.find_all(&matcher) {
+        edits.extend(self.make_fix(&nm, &matcher));
+      }
+    }
+    Ok(self.commit_edits(edits))
+  }
 }
 
-fn config_from_dict(dict: Bound<PyDict>) -> PyResult<SerializableRuleCore> {
+pub(crate) fn config_from_dict(dict: Bound<PyDict>) -> PyResult<SerializableRuleCore> {
   Ok(depythonize(dict.as_any())?)
 }
 
-fn config_from_rule(dict: Bound<PyDict>) -> PyResult<SerializableRuleCore> {
+pub(crate) fn config_from_rule(dict: Bound<PyDict>) -> PyResult<SerializableRuleCore> {
   let rule = depythonize(dict.as_any())?;
   Ok(SerializableRuleCore {
     rule,
@@ -1,4 +1,4 @@
-from ast_grep_py import SgRoot
+from ast_grep_py import SgRoot, RuleConfig
 
 source = """
 function test() {
@@ -49,4 +49,28 @@ def test_modified_fix():
     edit.start_pos -= 1
     edit.end_pos += 1
     s = root.commit_edits([edit])
-    assert s == "いいよ = logこいよ"
\ No newline at end of file
+    assert s == "いいよ = logこいよ"
+
+def test_get_fix():
+    edit = node1.get_fix({
+        "rule": {"pattern": "let $A = $B"},
+        "fix": "const $A = $B",
+    })
+    assert edit is not None
+    assert edit.inserted_text == "const a = 123"
+    assert node1.commit_edits([edit]) == "const a = 123"
+    # rule does not match the node
+    assert node1.get_fix({"rule": {"pattern": "var $A = $B"}, "fix": "a"}) is None
+    # rule has no fix
+    assert node1.get_fix({"rule": {"pattern": "let $A = $B"}}) is None
+
+def test_apply_rules():
+    sg = SgRoot("let a = 1\nconsole.log(a)", "javascript")
+    rule = RuleConfig.from_yaml("""
+id: no-console
+language: javascript
+rule: { pattern: console.log($A) }
+fix: alert($A)
+""")
+    config = {"rule": {"pattern": "let $A = $B"}, "fix": "const $A = $B"}
+    assert sg.apply_rules([rule, config]) == "const a = 1\nalert(a)"
@@ -1,4 +1,4 @@
-from ast_grep_py import SgRoot, Rule, Config, Relation, Pattern
+from ast_grep_py import SgRoot, Rule, Config, Relation, Pattern, RuleConfig
 
 source = """
 function test() {
@@ -168,4 +168,40 @@ def test_strictness():
     node = root.find(pattern={
         "context": "let b = 456",
         "strictness": "signature",
-    })
\ No newline at end of file
+    })
+
+def test_rule_config_from_yaml():
+    rule = RuleConfig.from_yaml("""
+id: test
+language: javascript
+rule:
+  matches: let-number
+constraints:
+  B: { regex: '^1' }
+utils:
+  let-number:
+    pattern: let $A = $B
+transform:
+  C:
+    substring:
+      source: $B
+      startChar: 1
+""")
+    assert rule.id == "test"
+    node = root.find(rule)
+    assert node is not None
+    assert node.get_transformed("C") == "23"
+    assert len(root.find_all(rule)) == 1
+
+def test_rule_config_wrong_usage():
+    rule = RuleConfig.from_yaml("id: test\nlanguage: python\nrule: { pattern: a }")
+    try:
+        root.find(rule)
+        assert False, "should raise for language mismatch"
+    except ValueError:
+        pass
+    try:
+        RuleConfig.from_yaml("id: a\nlanguage: js\nrule: { pattern: a }\n---\nid: b\nlanguage: js\nrule: { pattern: b }")
+        assert False, "should raise for multiple rules"
+    except ValueError:
+        pass
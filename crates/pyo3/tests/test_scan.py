@@ -0,0 +1,33 @@
+from ast_grep_py import scan_path
+
+def write_files(tmp_path):
+    (tmp_path / "a.js").write_text("console.log(1)\nconsole.log(2)")
+    (tmp_path / "b.js").write_text("let a = 123")
+    (tmp_path / "c.vue").write_text("console.log(3)")
+
+def test_scan_path_with_pattern(tmp_path):
+    write_files(tmp_path)
+    nodes = list(scan_path(str(tmp_path), "console.log($A)", "javascript"))
+    assert len(nodes) == 2
+    assert all(n.get_root().filename().endswith("a.js") for n in nodes)
+    assert sorted(n["A"].text() for n in nodes) == ["1", "2"]
+
+def test_scan_path_with_config(tmp_path):
+    write_files(tmp_path)
+    config = {
+        "rule": {"pattern": "let $A = $B"},
+        "constraints": {"B": {"kind": "number"}},
+    }
+    nodes = list(scan_path(str(tmp_path), config, "javascript"))
+    assert len(nodes) == 1
+    assert nodes[0].get_root().filename().endswith("b.js")
+    assert nodes[0].range().start.line == 0
+
+def test_scan_path_with_globs(tmp_path):
+    write_files(tmp_path)
+    nodes = list(scan_path(str(tmp_path), "console.log($A)", "javascript", globs=["*.vue"]))
+    assert len(nodes) == 3
+
+def test_scan_path_no_match(tmp_path):
+    write_files(tmp_path)
+    assert list(scan_path(str(tmp_path), "alert($A)", "javascript")) == []